@@ -0,0 +1,260 @@
+//! `diagnostics <PATH>`: the diagnostics of a directory without an editor,
+//! for CI, printed for people, as JSON, or as SARIF for code scanning tools.
+use std::{fmt::Write, path::PathBuf};
+
+use ide::Severity;
+use lsp_types::Url;
+use serde_json::json;
+use virtual_fs::AbsPathBuf;
+
+use super::{load_workspace, Workspace};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    Human,
+    Json,
+    Sarif,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct DiagnosticsCmd {
+    pub(crate) path: PathBuf,
+    pub(crate) format: OutputFormat,
+    /// The least severe diagnostic making the command fail, if any does.
+    pub(crate) fail_on: Option<Severity>,
+}
+
+impl DiagnosticsCmd {
+    pub(crate) fn run(self) -> anyhow::Result<i32> {
+        let workspace = load_workspace(&self.path)?;
+        let found = collect(&workspace);
+        print!("{}", render(&found, self.format));
+        let failed = self.fail_on.is_some_and(|threshold| {
+            found
+                .iter()
+                .any(|it| severity_rank(it.severity) <= severity_rank(threshold))
+        });
+        Ok(i32::from(failed))
+    }
+}
+
+/// A diagnostic, with its position as printed: lines and columns counted
+/// from 1, columns in UTF-16 code units as SARIF counts them by default.
+struct Found {
+    path: AbsPathBuf,
+    start: (u32, u32),
+    end: (u32, u32),
+    code: &'static str,
+    severity: Severity,
+    message: String,
+}
+
+fn collect(workspace: &Workspace) -> Vec<Found> {
+    let analysis = workspace.host.analysis();
+    let config = workspace.config.diagnostics();
+    let mut res = Vec::new();
+    for (file_id, path) in &workspace.files {
+        let Some(line_index) = analysis.file_line_index(*file_id) else {
+            continue;
+        };
+        let position = |offset| {
+            let utf16 = line_index.to_utf16(line_index.line_col(offset));
+            (utf16.line + 1, utf16.col + 1)
+        };
+        let mut diagnostics = analysis.diagnostics(&config, *file_id);
+        diagnostics.sort_by_key(|it| (it.range.start(), it.range.end()));
+        res.extend(diagnostics.into_iter().map(|it| Found {
+            path: path.clone(),
+            start: position(it.range.start()),
+            end: position(it.range.end()),
+            code: it.code.0,
+            severity: it.severity,
+            message: it.message,
+        }));
+    }
+    res
+}
+
+fn render(found: &[Found], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Human => {
+            let mut res = String::new();
+            for it in found {
+                let (line, col) = it.start;
+                let severity = severity_name(it.severity);
+                let _ = writeln!(
+                    res,
+                    "{}:{line}:{col}: {severity}[{}]: {}",
+                    it.path, it.code, it.message
+                );
+            }
+            let noun = if found.len() == 1 {
+                "diagnostic"
+            } else {
+                "diagnostics"
+            };
+            let _ = writeln!(res, "{} {noun}", found.len());
+            res
+        }
+        OutputFormat::Json => {
+            let diagnostics: Vec<_> = found
+                .iter()
+                .map(|it| {
+                    json!({
+                        "file": it.path.to_string(),
+                        "code": it.code,
+                        "severity": severity_name(it.severity),
+                        "message": it.message,
+                        "range": {
+                            "start": { "line": it.start.0, "column": it.start.1 },
+                            "end": { "line": it.end.0, "column": it.end.1 },
+                        },
+                    })
+                })
+                .collect();
+            format!("{:#}\n", serde_json::Value::from(diagnostics))
+        }
+        OutputFormat::Sarif => format!("{:#}\n", sarif(found)),
+    }
+}
+
+/// The diagnostics as a SARIF 2.1.0 log.
+fn sarif(found: &[Found]) -> serde_json::Value {
+    let mut rules: Vec<&str> = found.iter().map(|it| it.code).collect();
+    rules.sort_unstable();
+    rules.dedup();
+    let results: Vec<_> = found
+        .iter()
+        .map(|it| {
+            let uri =
+                Url::from_file_path(&it.path).map_or_else(|_| it.path.to_string(), String::from);
+            let level = match it.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Information | Severity::Hint => "note",
+            };
+            json!({
+                "ruleId": it.code,
+                "level": level,
+                "message": { "text": it.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri },
+                        "region": {
+                            "startLine": it.start.0,
+                            "startColumn": it.start.1,
+                            "endLine": it.end.0,
+                            "endColumn": it.end.1,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "coldfusion-language-server",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Information => "info",
+        Severity::Hint => "hint",
+    }
+}
+
+/// Orders the severities from the most severe.
+fn severity_rank(severity: Severity) -> u8 {
+    match severity {
+        Severity::Error => 0,
+        Severity::Warning => 1,
+        Severity::Information => 2,
+        Severity::Hint => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn workspace(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("app")).unwrap();
+        fs::write(
+            root.join("app/A.cfc"),
+            "component {\n    function f() {\n        return y;\n    }\n",
+        )
+        .unwrap();
+        fs::write(root.join("index.cfm"), "<cfset x = 1>\r\n").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_render() {
+        let root = workspace("cfml-cli-diagnostics");
+        let found = collect(&load_workspace(&root).unwrap());
+        let path = root.join("app/A.cfc");
+        assert_eq!(
+            render(&found, OutputFormat::Human),
+            format!(
+                "{0}:3:16: warning[undefined-variable]: `y` is not defined\n\
+                 {0}:5:1: error[syntax-error]: expected `}}`\n\
+                 2 diagnostics\n",
+                path.display()
+            )
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&found, OutputFormat::Json)).unwrap();
+        assert_eq!(
+            json[0]["range"],
+            json!({ "start": { "line": 3, "column": 16 }, "end": { "line": 3, "column": 17 } })
+        );
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render(&found, OutputFormat::Sarif)).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{ "id": "syntax-error" }, { "id": "undefined-variable" }])
+        );
+        assert_eq!(run["results"][1]["level"], "error");
+    }
+
+    #[test]
+    fn test_fail_on() {
+        let root = workspace("cfml-cli-diagnostics-fail-on");
+        fs::remove_file(root.join("app/A.cfc")).unwrap();
+        fs::write(
+            root.join("app/B.cfc"),
+            "component {\n    function f() {\n        return y;\n    }\n}\n",
+        )
+        .unwrap();
+        let run = |fail_on| {
+            let cmd = DiagnosticsCmd {
+                path: root.clone(),
+                format: OutputFormat::Json,
+                fail_on,
+            };
+            cmd.run().unwrap()
+        };
+        assert_eq!(run(Some(Severity::Error)), 0);
+        assert_eq!(run(Some(Severity::Warning)), 1);
+        assert_eq!(run(None), 0);
+    }
+}
@@ -0,0 +1,95 @@
+//! `format <PATH>...`: formats files in place, or with `--check` lists the
+//! files which aren't formatted, for CI. The edits are those of
+//! `textDocument/formatting`, so that the editor and CI agree.
+use std::path::PathBuf;
+
+use anyhow::Context;
+use virtual_fs::AbsPathBuf;
+
+use super::{load_workspace, Workspace};
+use crate::global_state::LineEndings;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct FormatCmd {
+    pub(crate) paths: Vec<PathBuf>,
+    /// Whether files are only checked, not written.
+    pub(crate) check: bool,
+}
+
+impl FormatCmd {
+    pub(crate) fn run(self) -> anyhow::Result<i32> {
+        let mut unformatted = 0;
+        for path in &self.paths {
+            let workspace = load_workspace(path)?;
+            for (path, text) in formatted(&workspace) {
+                unformatted += 1;
+                if self.check {
+                    println!("{path}");
+                } else {
+                    std::fs::write(&path, text)
+                        .with_context(|| format!("failed to write `{path}`"))?;
+                }
+            }
+        }
+        Ok(i32::from(self.check && unformatted > 0))
+    }
+}
+
+/// The files which formatting changes, with their formatted text in their
+/// own line endings.
+fn formatted(workspace: &Workspace) -> Vec<(AbsPathBuf, String)> {
+    let analysis = workspace.host.analysis();
+    let config = workspace.config.format();
+    let mut res = Vec::new();
+    for (file_id, path) in &workspace.files {
+        let Some(edit) = analysis.format(&config, *file_id) else {
+            continue;
+        };
+        if edit.is_empty() {
+            continue;
+        }
+        let mut text = analysis.file_text(*file_id).unwrap().to_string();
+        edit.apply(&mut text);
+        if workspace.line_endings.get(file_id) == Some(&LineEndings::Dos) {
+            text = text.replace('\n', "\r\n");
+        }
+        res.push((path.clone(), text));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let root = std::env::temp_dir().join("cfml-cli-format");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("A.cfc"),
+            "component {\r\n\tfunction f() {}  \r\n}\r\n",
+        )
+        .unwrap();
+        fs::write(root.join("index.cfm"), "<cfset x = 1>\n").unwrap();
+        let cmd = |check| FormatCmd {
+            paths: vec![root.clone()],
+            check,
+        };
+
+        assert_eq!(cmd(true).run().unwrap(), 1);
+        assert_eq!(
+            fs::read_to_string(root.join("A.cfc")).unwrap(),
+            "component {\r\n\tfunction f() {}  \r\n}\r\n"
+        );
+        assert_eq!(cmd(false).run().unwrap(), 0);
+        assert_eq!(
+            fs::read_to_string(root.join("A.cfc")).unwrap(),
+            "component {\r\n    function f() {}\r\n}\r\n"
+        );
+        assert_eq!(cmd(true).run().unwrap(), 0);
+    }
+}
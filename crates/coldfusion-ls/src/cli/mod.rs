@@ -0,0 +1,480 @@
+//! The command line of the server: the options of the language server, and
+//! the commands running an analysis without a client.
+mod diagnostics;
+mod format;
+mod parse;
+mod symbols;
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use ide::{AnalysisHost, Severity};
+use rustc_hash::FxHashMap;
+use virtual_fs::{AbsPathBuf, FileId, VirtualFsPath};
+
+use crate::{
+    config::Config, global_state::LineEndings, indexing, task_pool::Typing, transport::Transport,
+};
+
+pub(crate) use self::{
+    diagnostics::{DiagnosticsCmd, OutputFormat},
+    format::FormatCmd,
+    parse::ParseCmd,
+    symbols::SymbolsCmd,
+};
+
+pub(crate) const USAGE: &str = "\
+Usage: coldfusion-language-server [OPTIONS]
+       coldfusion-language-server diagnostics [OPTIONS] <PATH>
+       coldfusion-language-server symbols [--json] <PATH>
+       coldfusion-language-server format [--check] <PATH>...
+       coldfusion-language-server parse [--tokens] <FILE>
+
+Options:
+    --stdio          talk to the client over standard input and output (default)
+    --port <PORT>    connect to the client on the TCP port PORT of localhost
+    --listen         with --port, wait for the client on PORT instead
+    --pipe <PATH>    connect to the client on the Unix domain socket PATH
+    --log-file <PATH>
+                     append the logs to PATH instead of writing them to stderr
+    --parent-pid <PID>
+                     exit once the process PID, the client, is gone
+    -h, --help       print this help
+
+Commands:
+    diagnostics      print the diagnostics of the CFML files under PATH
+        --format <human|json|sarif>           how to print them (default: human)
+        --fail-on <error|warning|info|hint|none>
+                                              exit with 1 when a diagnostic is at
+                                              least this severe (default: error)
+    symbols          print the components and functions declared under PATH
+        --json                                print them as JSON
+    format           format the CFML files under each PATH in place
+        --check                               only print the files which would
+                                              change, exiting with 1 if any would
+    parse            print the syntax tree and the parse errors of FILE, or of
+                     the standard input for `-`
+        --tokens                              print the tokens instead of the tree
+";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    Server(ServerCmd),
+    Diagnostics(DiagnosticsCmd),
+    Symbols(SymbolsCmd),
+    Format(FormatCmd),
+    Parse(ParseCmd),
+    Help,
+}
+
+impl Command {
+    /// Runs a command other than the server, returning the exit code.
+    pub(crate) fn run(self) -> anyhow::Result<i32> {
+        match self {
+            Command::Server(_) => bail!("the server runs from `main`"),
+            Command::Diagnostics(cmd) => cmd.run(),
+            Command::Symbols(cmd) => cmd.run(),
+            Command::Format(cmd) => cmd.run(),
+            Command::Parse(cmd) => cmd.run(),
+            Command::Help => {
+                print!("{USAGE}");
+                Ok(0)
+            }
+        }
+    }
+}
+
+/// The options of the language server.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ServerCmd {
+    pub(crate) transport: Transport,
+    /// Where the logs are written, instead of stderr.
+    pub(crate) log_file: Option<PathBuf>,
+    /// The process of the client, which the server exits with.
+    pub(crate) parent_pid: Option<u32>,
+}
+
+/// The arguments, split into flags and their values, which clients pass
+/// both as `--port 8080` and as `--port=8080`.
+struct Args<I> {
+    args: I,
+    inline_value: Option<String>,
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    fn next(&mut self) -> Option<String> {
+        let arg = self.args.next()?;
+        self.inline_value = None;
+        match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                self.inline_value = Some(value.to_string());
+                Some(flag.to_string())
+            }
+            _ => Some(arg),
+        }
+    }
+
+    /// The value of `flag`, the last one returned.
+    fn value(&mut self, flag: &str) -> anyhow::Result<String> {
+        self.inline_value
+            .take()
+            .or_else(|| self.args.next())
+            .with_context(|| format!("`{flag}` needs a value"))
+    }
+}
+
+/// Parses the arguments, without the name of the program.
+pub(crate) fn parse_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Command> {
+    let mut args = Args {
+        args: args.into_iter().peekable(),
+        inline_value: None,
+    };
+    match args.args.peek().map(String::as_str) {
+        Some("diagnostics") => {
+            args.args.next();
+            return parse_diagnostics_args(args);
+        }
+        Some("symbols") => {
+            args.args.next();
+            return parse_symbols_args(args);
+        }
+        Some("format") => {
+            args.args.next();
+            return parse_format_args(args);
+        }
+        Some("parse") => {
+            args.args.next();
+            return parse_parse_args(args);
+        }
+        _ => (),
+    }
+    let mut stdio = false;
+    let mut port = None;
+    let mut listen = false;
+    let mut pipe = None;
+    let mut log_file = None;
+    let mut parent_pid = None;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--stdio" => stdio = true,
+            // `--socket` is the name VS Code gives the port.
+            "--port" | "--socket" => {
+                let value = args.value(&flag)?;
+                let parsed = value
+                    .parse::<u16>()
+                    .with_context(|| format!("invalid port `{value}`"))?;
+                port = Some(parsed);
+            }
+            "--listen" => listen = true,
+            "--pipe" => pipe = Some(PathBuf::from(args.value(&flag)?)),
+            "--log-file" => log_file = Some(PathBuf::from(args.value(&flag)?)),
+            "--parent-pid" => {
+                let value = args.value(&flag)?;
+                let parsed = value
+                    .parse::<u32>()
+                    .with_context(|| format!("invalid process id `{value}`"))?;
+                parent_pid = Some(parsed);
+            }
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => bail!("unknown argument `{flag}`\n\n{USAGE}"),
+        }
+    }
+    let transport = match (stdio, port, pipe) {
+        (_, None, None) if !listen => Transport::Stdio,
+        (false, Some(port), None) => Transport::Tcp { port, listen },
+        (false, None, Some(path)) if !listen => Transport::Pipe(path),
+        (_, None, None) => bail!("`--listen` needs `--port`"),
+        _ => bail!("`--stdio`, `--port` and `--pipe` can't be combined"),
+    };
+    Ok(Command::Server(ServerCmd {
+        transport,
+        log_file,
+        parent_pid,
+    }))
+}
+
+fn parse_diagnostics_args(mut args: Args<impl Iterator<Item = String>>) -> anyhow::Result<Command> {
+    let mut path = None;
+    let mut format = OutputFormat::Human;
+    let mut fail_on = Some(Severity::Error);
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--format" => {
+                format = match args.value(&flag)?.as_str() {
+                    "human" => OutputFormat::Human,
+                    "json" => OutputFormat::Json,
+                    "sarif" => OutputFormat::Sarif,
+                    other => bail!("unknown format `{other}`"),
+                }
+            }
+            "--fail-on" => {
+                fail_on = match args.value(&flag)?.as_str() {
+                    "error" => Some(Severity::Error),
+                    "warning" => Some(Severity::Warning),
+                    "info" | "information" => Some(Severity::Information),
+                    "hint" => Some(Severity::Hint),
+                    "none" => None,
+                    other => bail!("unknown severity `{other}`"),
+                }
+            }
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if flag.starts_with('-') => bail!("unknown argument `{flag}`\n\n{USAGE}"),
+            _ if path.is_none() => path = Some(PathBuf::from(flag)),
+            _ => bail!("`diagnostics` takes a single path"),
+        }
+    }
+    let path = path.context("`diagnostics` needs a path")?;
+    Ok(Command::Diagnostics(DiagnosticsCmd {
+        path,
+        format,
+        fail_on,
+    }))
+}
+
+fn parse_symbols_args(mut args: Args<impl Iterator<Item = String>>) -> anyhow::Result<Command> {
+    let mut path = None;
+    let mut json = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if flag.starts_with('-') => bail!("unknown argument `{flag}`\n\n{USAGE}"),
+            _ if path.is_none() => path = Some(PathBuf::from(flag)),
+            _ => bail!("`symbols` takes a single path"),
+        }
+    }
+    let path = path.context("`symbols` needs a path")?;
+    Ok(Command::Symbols(SymbolsCmd { path, json }))
+}
+
+fn parse_format_args(mut args: Args<impl Iterator<Item = String>>) -> anyhow::Result<Command> {
+    let mut paths = Vec::new();
+    let mut check = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--check" => check = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if flag.starts_with('-') => bail!("unknown argument `{flag}`\n\n{USAGE}"),
+            _ => paths.push(PathBuf::from(flag)),
+        }
+    }
+    if paths.is_empty() {
+        bail!("`format` needs a path");
+    }
+    Ok(Command::Format(FormatCmd { paths, check }))
+}
+
+fn parse_parse_args(mut args: Args<impl Iterator<Item = String>>) -> anyhow::Result<Command> {
+    let mut path = None;
+    let mut tokens = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--tokens" => tokens = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            "-" if path.is_none() => path = Some(PathBuf::from(flag)),
+            _ if flag.starts_with('-') => bail!("unknown argument `{flag}`\n\n{USAGE}"),
+            _ if path.is_none() => path = Some(PathBuf::from(flag)),
+            _ => bail!("`parse` takes a single file"),
+        }
+    }
+    let path = path.context("`parse` needs a file")?;
+    Ok(Command::Parse(ParseCmd { path, tokens }))
+}
+
+/// The CFML files under a path, loaded into an analysis.
+struct Workspace {
+    /// The directory, or the directory of the file, loaded.
+    root: AbsPathBuf,
+    config: Config,
+    host: AnalysisHost,
+    /// The files, by path.
+    files: Vec<(FileId, AbsPathBuf)>,
+    /// The line endings of the files, which the analysis sees as `\n`.
+    line_endings: FxHashMap<FileId, LineEndings>,
+}
+
+/// Loads the CFML files under `path`, or the file `path` itself, with the
+/// CFLint and server settings of the directory.
+fn load_workspace(path: &std::path::Path) -> anyhow::Result<Workspace> {
+    let path = AbsPathBuf::assert(std::env::current_dir()?).absolutize(path);
+    let metadata = std::fs::metadata(&path).with_context(|| format!("failed to read `{path}`"))?;
+    let root = if metadata.is_dir() {
+        path.clone()
+    } else {
+        path.parent()
+            .map_or_else(|| path.clone(), |it| it.to_path_buf())
+    };
+    let mut config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+    config.load_cflint();
+    config.load_cfconfig();
+    config.load_cfformat();
+
+    let mut files = if metadata.is_dir() {
+        let files = std::cell::RefCell::new(Vec::new());
+        // The files the server indexes, for the diagnostics to match.
+        indexing::index(
+            std::slice::from_ref(&path),
+            &config.files(),
+            &Default::default(),
+            &ide::CancellationToken::default(),
+            &Typing::default(),
+            &|progress| {
+                if let indexing::IndexingProgress::Report { files: batch, .. } = progress {
+                    files.borrow_mut().extend(batch);
+                }
+            },
+        );
+        files.into_inner()
+    } else {
+        let contents = std::fs::read(&path).with_context(|| format!("failed to read `{path}`"))?;
+        vec![(path, contents)]
+    };
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut host = AnalysisHost::default();
+    let mut res = Vec::new();
+    let mut line_endings = FxHashMap::default();
+    for (idx, (path, contents)) in files.into_iter().enumerate() {
+        let file_id = FileId(idx as u32);
+        let Ok(text) = String::from_utf8(contents) else {
+            continue;
+        };
+        let (text, endings) = LineEndings::normalize(text);
+        host.set_file_text(file_id, Some(text.into()));
+        line_endings.insert(file_id, endings);
+        host.set_file_path(file_id, Some(VirtualFsPath::from(path.clone())));
+        res.push((file_id, path));
+    }
+    Ok(Workspace {
+        root,
+        config,
+        host,
+        files: res,
+        line_endings,
+    })
+}
+
+impl Workspace {
+    fn path(&self, file_id: FileId) -> &AbsPathBuf {
+        let (_, path) = self.files.iter().find(|(it, _)| *it == file_id).unwrap();
+        path
+    }
+
+    /// The path of a file relative to the root, with `/` separators.
+    fn relative_path(&self, file_id: FileId) -> String {
+        let path = self.path(file_id);
+        match path.strip_prefix(&self.root) {
+            Some(relative) => AsRef::<std::path::Path>::as_ref(relative)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            None => path.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Command> {
+        parse_args(args.iter().map(|it| it.to_string()))
+    }
+
+    fn server(transport: Transport) -> Command {
+        Command::Server(ServerCmd {
+            transport,
+            log_file: None,
+            parent_pid: None,
+        })
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]).unwrap(), server(Transport::Stdio));
+        assert_eq!(parse(&["--stdio"]).unwrap(), server(Transport::Stdio));
+        assert_eq!(
+            parse(&["--port", "9257", "--listen"]).unwrap(),
+            server(Transport::Tcp {
+                port: 9257,
+                listen: true,
+            })
+        );
+        assert_eq!(
+            parse(&["--socket=9257"]).unwrap(),
+            server(Transport::Tcp {
+                port: 9257,
+                listen: false,
+            })
+        );
+        assert_eq!(
+            parse(&["--pipe", "/tmp/cfml.sock"]).unwrap(),
+            server(Transport::Pipe(PathBuf::from("/tmp/cfml.sock")))
+        );
+        assert_eq!(
+            parse(&["--log-file=/tmp/cfls.log"]).unwrap(),
+            Command::Server(ServerCmd {
+                transport: Transport::Stdio,
+                log_file: Some(PathBuf::from("/tmp/cfls.log")),
+                parent_pid: None,
+            })
+        );
+        assert_eq!(
+            parse(&["--stdio", "--parent-pid", "4242"]).unwrap(),
+            Command::Server(ServerCmd {
+                transport: Transport::Stdio,
+                log_file: None,
+                parent_pid: Some(4242),
+            })
+        );
+        assert_eq!(parse(&["--help"]).unwrap(), Command::Help);
+        assert_eq!(
+            parse(&["symbols", "app", "--json"]).unwrap(),
+            Command::Symbols(SymbolsCmd {
+                path: PathBuf::from("app"),
+                json: true,
+            })
+        );
+        assert_eq!(
+            parse(&["format", "--check", "app", "index.cfm"]).unwrap(),
+            Command::Format(FormatCmd {
+                paths: vec![PathBuf::from("app"), PathBuf::from("index.cfm")],
+                check: true,
+            })
+        );
+        assert!(parse(&["format"]).is_err());
+        assert_eq!(
+            parse(&["parse", "--tokens", "-"]).unwrap(),
+            Command::Parse(ParseCmd {
+                path: PathBuf::from("-"),
+                tokens: true,
+            })
+        );
+        assert_eq!(
+            parse(&["diagnostics", "--format=sarif", "app", "--fail-on", "none"]).unwrap(),
+            Command::Diagnostics(DiagnosticsCmd {
+                path: PathBuf::from("app"),
+                format: OutputFormat::Sarif,
+                fail_on: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        let error = |args: &[&str]| parse(args).unwrap_err().to_string();
+        assert_eq!(error(&["--port"]), "`--port` needs a value");
+        assert_eq!(error(&["--port", "http"]), "invalid port `http`");
+        assert_eq!(error(&["--parent-pid", "-1"]), "invalid process id `-1`");
+        assert_eq!(error(&["--listen"]), "`--listen` needs `--port`");
+        assert_eq!(
+            error(&["--stdio", "--pipe", "cfml.sock"]),
+            "`--stdio`, `--port` and `--pipe` can't be combined"
+        );
+        assert!(error(&["--verbose"]).starts_with("unknown argument `--verbose`"));
+        assert_eq!(error(&["diagnostics"]), "`diagnostics` needs a path");
+        assert_eq!(
+            error(&["diagnostics", "app", "--format", "xml"]),
+            "unknown format `xml`"
+        );
+    }
+}
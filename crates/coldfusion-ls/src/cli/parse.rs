@@ -0,0 +1,94 @@
+//! `parse <FILE>`: the syntax tree or the tokens of a file, with its parse
+//! errors, to triage grammar bugs without reproducing them in an editor.
+use std::{
+    fmt::Write,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use ide::{AnalysisHost, LineIndex};
+use virtual_fs::FileId;
+
+use crate::global_state::LineEndings;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ParseCmd {
+    /// The file to parse, `-` for the standard input.
+    pub(crate) path: PathBuf,
+    /// Whether to print the tokens instead of the tree.
+    pub(crate) tokens: bool,
+}
+
+impl ParseCmd {
+    pub(crate) fn run(self) -> anyhow::Result<i32> {
+        let text = if self.path == Path::new("-") {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("failed to read the standard input")?;
+            text
+        } else {
+            std::fs::read_to_string(&self.path)
+                .with_context(|| format!("failed to read `{}`", self.path.display()))?
+        };
+        let (output, has_errors) = parse_output(text, self.tokens);
+        print!("{output}");
+        Ok(i32::from(has_errors))
+    }
+}
+
+/// The tree or the tokens of `text`, then its errors, and whether it has
+/// any. The text is parsed as the server parses it, with `\n` line endings.
+fn parse_output(text: String, tokens: bool) -> (String, bool) {
+    let (text, _) = LineEndings::normalize(text);
+    let mut host = AnalysisHost::default();
+    host.set_file_text(FileId(0), Some(text.as_str().into()));
+    let parse = host.analysis().parse(FileId(0)).unwrap();
+    let mut res = if tokens {
+        let mut res = String::new();
+        for token in parse.syntax_node().tokens() {
+            let _ = writeln!(res, "{token:?}");
+        }
+        res
+    } else {
+        parse.debug_tree()
+    };
+    let line_index = LineIndex::new(&text);
+    for error in parse.errors() {
+        let start = line_index.line_col(error.range.start());
+        let _ = writeln!(
+            res,
+            "error {}:{} ({:?}): {}",
+            start.line + 1,
+            start.col + 1,
+            error.range,
+            error.message
+        );
+    }
+    (res, !parse.errors().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        let (output, has_errors) = parse_output("<cfset x = 1>\r\n".to_string(), false);
+        assert!(!has_errors);
+        assert!(output.starts_with("SOURCE_FILE@0..14\n"));
+
+        let (output, has_errors) = parse_output("component {\n".to_string(), true);
+        assert!(has_errors);
+        assert_eq!(
+            output,
+            "KEYWORD@0..9 \"component\"
+WHITESPACE@9..10 \" \"
+L_BRACE@10..11 \"{\"
+WHITESPACE@11..12 \"\\n\"
+error 2:1 (12..12): expected `}`
+"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! `symbols <PATH>`: the components and functions the server indexes, with
+//! the components they extend and implement resolved to files, to debug
+//! resolution and for tools wanting the server's view of the code.
+use std::{fmt::Write, path::PathBuf};
+
+use ide::{Analysis, ComponentName, FunctionSymbol};
+use serde_json::json;
+use virtual_fs::FileId;
+
+use super::{load_workspace, Workspace};
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct SymbolsCmd {
+    pub(crate) path: PathBuf,
+    pub(crate) json: bool,
+}
+
+impl SymbolsCmd {
+    pub(crate) fn run(self) -> anyhow::Result<i32> {
+        let workspace = load_workspace(&self.path)?;
+        let output = if self.json {
+            format!("{:#}\n", symbols_json(&workspace))
+        } else {
+            symbols_text(&workspace)
+        };
+        print!("{output}");
+        Ok(0)
+    }
+}
+
+fn symbols_text(workspace: &Workspace) -> String {
+    let analysis = workspace.host.analysis();
+    let mut res = String::new();
+    for (file_id, _) in &workspace.files {
+        let Some(symbols) = analysis.file_symbols(*file_id) else {
+            continue;
+        };
+        let _ = writeln!(res, "{}", workspace.relative_path(*file_id));
+        let function = |res: &mut String, indent: &str, it: &FunctionSymbol| {
+            let line = line_of(&analysis, *file_id, it);
+            let mut signature = String::new();
+            for part in [&it.access, &it.return_type].into_iter().flatten() {
+                let _ = write!(signature, "{part} ");
+            }
+            let params = it.params.join(", ");
+            let _ = writeln!(
+                res,
+                "{indent}function {signature}{}({params}) :{line}",
+                it.name
+            );
+        };
+        if let Some(component) = &symbols.component {
+            let kind = if component.is_interface {
+                "interface"
+            } else {
+                "component"
+            };
+            let _ = write!(res, "  {kind} {}", component.name);
+            if let Some(extends) = &component.extends {
+                let _ = write!(res, " extends {}", component_name_text(workspace, extends));
+            }
+            if !component.implements.is_empty() {
+                let names: Vec<String> = component
+                    .implements
+                    .iter()
+                    .map(|it| component_name_text(workspace, it))
+                    .collect();
+                let _ = write!(res, " implements {}", names.join(", "));
+            }
+            res.push('\n');
+            for property in &component.properties {
+                let _ = writeln!(res, "    property {property}");
+            }
+            for it in &component.functions {
+                function(&mut res, "    ", it);
+            }
+        }
+        for it in &symbols.functions {
+            function(&mut res, "  ", it);
+        }
+    }
+    res
+}
+
+fn symbols_json(workspace: &Workspace) -> serde_json::Value {
+    let analysis = workspace.host.analysis();
+    let component_name = |it: &ComponentName| {
+        let file = it
+            .resolved
+            .map(|file_id| workspace.path(file_id).to_string());
+        json!({ "name": it.name, "file": file })
+    };
+    let function = |file_id, it: &FunctionSymbol| {
+        json!({
+            "name": it.name,
+            "access": it.access,
+            "returnType": it.return_type,
+            "params": it.params,
+            "line": line_of(&analysis, file_id, it),
+        })
+    };
+    let files: Vec<_> = workspace
+        .files
+        .iter()
+        .filter_map(|(file_id, path)| {
+            let symbols = analysis.file_symbols(*file_id)?;
+            let component = symbols.component.map(|it| {
+                json!({
+                    "name": it.name,
+                    "interface": it.is_interface,
+                    "extends": it.extends.as_ref().map(component_name),
+                    "implements": it.implements.iter().map(component_name).collect::<Vec<_>>(),
+                    "properties": it.properties,
+                    "functions": it.functions.iter().map(|it| function(*file_id, it)).collect::<Vec<_>>(),
+                })
+            });
+            Some(json!({
+                "file": path.to_string(),
+                "component": component,
+                "functions": symbols.functions.iter().map(|it| function(*file_id, it)).collect::<Vec<_>>(),
+            }))
+        })
+        .collect();
+    files.into()
+}
+
+/// The name, with the file it resolves to, relative to the workspace.
+fn component_name_text(workspace: &Workspace, name: &ComponentName) -> String {
+    match name.resolved {
+        Some(file_id) => format!("{} ({})", name.name, workspace.relative_path(file_id)),
+        None => format!("{} (unresolved)", name.name),
+    }
+}
+
+/// The line of the name of `func`, counted from 1.
+fn line_of(analysis: &Analysis, file_id: FileId, func: &FunctionSymbol) -> u32 {
+    analysis
+        .file_line_index(file_id)
+        .map_or(0, |it| it.line_col(func.range.start()).line + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_symbols() {
+        let root = std::env::temp_dir().join("cfml-cli-symbols");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("models")).unwrap();
+        fs::write(
+            root.join("models/User.cfc"),
+            "component extends=\"Base\" implements=\"IUser\" {\n    property email;\n    \
+             public string function getName(id) {}\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("models/Base.cfc"), "component {}\n").unwrap();
+        fs::write(
+            root.join("index.cfm"),
+            "<cfscript>\nfunction helper() {}\n</cfscript>\n",
+        )
+        .unwrap();
+        let workspace = load_workspace(&root).unwrap();
+
+        assert_eq!(
+            symbols_text(&workspace),
+            "index.cfm
+  function helper() :2
+models/Base.cfc
+  component Base
+models/User.cfc
+  component User extends Base (models/Base.cfc) implements IUser (unresolved)
+    property email
+    function public string getName(id) :3
+"
+        );
+        let json = symbols_json(&workspace);
+        assert_eq!(
+            json[2]["component"]["extends"],
+            json!({ "name": "Base", "file": root.join("models/Base.cfc").to_str().unwrap() })
+        );
+        assert_eq!(
+            json[2]["component"]["functions"][0]["params"],
+            json!(["id"])
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! Reads the `.cfconfig.json` at the root of the workspace, where CommandBox
+//! keeps the settings of the server, for the names of its datasources.
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use virtual_fs::AbsPathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct CfConfig {
+    /// The datasources by name. Their settings don't matter here.
+    #[serde(default)]
+    datasources: FxHashMap<String, serde_json::Value>,
+}
+
+impl CfConfig {
+    /// The configuration at the root of the workspace, if there is one.
+    pub(crate) fn load(root: &AbsPathBuf) -> Option<CfConfig> {
+        let text = std::fs::read_to_string(root.join(".cfconfig.json")).ok()?;
+        match serde_json::from_str(&text) {
+            Ok(it) => Some(it),
+            Err(e) => {
+                tracing::warn!("Failed to read .cfconfig.json: {e}");
+                None
+            }
+        }
+    }
+
+    /// Adds the datasources of the server to the known ones.
+    pub(crate) fn apply(&self, config: &mut ide::DiagnosticsConfig) {
+        let mut names: Vec<&String> = self.datasources.keys().collect();
+        names.sort();
+        for name in names {
+            if !config
+                .datasources
+                .iter()
+                .any(|it| it.eq_ignore_ascii_case(name))
+            {
+                config.datasources.push(name.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cfconfig() {
+        let cfconfig: CfConfig = serde_json::from_str(
+            r#"{
+                "adminPassword": "secret",
+                "datasources": {
+                    "main": { "class": "org.postgresql.Driver", "host": "localhost" },
+                    "Reporting": { "dbdriver": "MSSQL" }
+                }
+            }"#,
+        )
+        .unwrap();
+        let mut config = ide::DiagnosticsConfig::default();
+        config.datasources.push("MAIN".to_string());
+        cfconfig.apply(&mut config);
+        assert_eq!(config.datasources, ["MAIN", "Reporting"]);
+    }
+}
@@ -0,0 +1,136 @@
+//! Reads the `.cfformat.json` at the root of the workspace, the settings of
+//! CommandBox's cfformat, so that teams formatting with it get the same
+//! layout from the editor, as far as the formatter has equivalent options.
+use serde::Deserialize;
+use virtual_fs::AbsPathBuf;
+
+/// The settings of cfformat, whose names are paths like `tags.lowercase`.
+/// Those without an equivalent are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct CfformatConfig {
+    #[serde(default)]
+    indent_size: Option<u32>,
+    /// Whether lines are indented with tabs.
+    #[serde(default)]
+    tab_indent: Option<bool>,
+    #[serde(default)]
+    max_columns: Option<u32>,
+    /// Whether tag names are lowercased, otherwise they are kept.
+    #[serde(default, rename = "tags.lowercase")]
+    tags_lowercase: Option<bool>,
+    /// The quotes of attribute values, `double`, `single`, or empty to keep
+    /// them.
+    #[serde(default, rename = "strings.attributes.quote")]
+    attributes_quote: Option<String>,
+    /// Whether binary operators have spaces around them.
+    #[serde(default, rename = "binary_operators.padding")]
+    binary_operators_padding: Option<bool>,
+    /// Whether keywords like `if` have a space before their parenthesis.
+    #[serde(default, rename = "keywords.spacing_to_group")]
+    keywords_spacing_to_group: Option<bool>,
+    /// The number of calls from which a chain has one per line.
+    #[serde(default, rename = "method_call.chain.multiline")]
+    method_call_chain_multiline: Option<u32>,
+}
+
+impl CfformatConfig {
+    /// The configuration at the root of the workspace, if there is one.
+    pub(crate) fn load(root: &AbsPathBuf) -> Option<CfformatConfig> {
+        let text = std::fs::read_to_string(root.join(".cfformat.json")).ok()?;
+        match serde_json::from_str(&text) {
+            Ok(it) => Some(it),
+            Err(e) => {
+                tracing::warn!("Failed to read .cfformat.json: {e}");
+                None
+            }
+        }
+    }
+
+    /// Sets the options cfformat has. cfformat never changes the case of
+    /// keywords, scopes or functions, nor does the formatter then.
+    pub(crate) fn apply(&self, config: &mut ide::FormatConfig) {
+        if let Some(indent_size) = self.indent_size {
+            config.tab_size = indent_size;
+        }
+        if let Some(tab_indent) = self.tab_indent {
+            config.insert_spaces = !tab_indent;
+        }
+        if let Some(max_columns) = self.max_columns {
+            config.max_line_width = max_columns;
+        }
+        config.tag_case = match self.tags_lowercase {
+            Some(false) => ide::Case::Preserve,
+            Some(true) | None => ide::Case::Lower,
+        };
+        match self.attributes_quote.as_deref() {
+            Some("double") => config.attribute_quote = ide::Quote::Double,
+            Some("single") => config.attribute_quote = ide::Quote::Single,
+            Some(_) => config.attribute_quote = ide::Quote::Preserve,
+            None => (),
+        }
+        let spacing = |padding| match padding {
+            true => ide::Spacing::Always,
+            false => ide::Spacing::Never,
+        };
+        if let Some(padding) = self.binary_operators_padding {
+            config.operator_spacing = spacing(padding);
+        }
+        if let Some(padding) = self.keywords_spacing_to_group {
+            config.keyword_spacing = spacing(padding);
+        }
+        if let Some(calls) = self.method_call_chain_multiline {
+            config.max_chained_calls_per_line = Some(calls.saturating_sub(1));
+        }
+        config.keyword_case = ide::Case::Preserve;
+        config.scope_case = ide::Case::Preserve;
+        config.function_case = ide::Case::Preserve;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cfformat() {
+        let cfformat: CfformatConfig = serde_json::from_str(
+            r#"{
+                "array.empty_padding": false,
+                "indent_size": 2,
+                "max_columns": 115,
+                "tab_indent": true,
+                "tags.lowercase": false,
+                "strings.attributes.quote": "single",
+                "binary_operators.padding": true,
+                "keywords.spacing_to_group": false,
+                "method_call.chain.multiline": 3,
+                "function_call.casing.builtin": "cfdocs"
+            }"#,
+        )
+        .unwrap();
+        let mut config = ide::FormatConfig::default();
+        cfformat.apply(&mut config);
+        assert_eq!(
+            config,
+            ide::FormatConfig {
+                tab_size: 2,
+                insert_spaces: false,
+                max_line_width: 115,
+                tag_case: ide::Case::Preserve,
+                keyword_case: ide::Case::Preserve,
+                scope_case: ide::Case::Preserve,
+                function_case: ide::Case::Preserve,
+                attribute_quote: ide::Quote::Single,
+                operator_spacing: ide::Spacing::Always,
+                keyword_spacing: ide::Spacing::Never,
+                max_chained_calls_per_line: Some(2),
+                ..ide::FormatConfig::default()
+            }
+        );
+
+        let mut config = ide::FormatConfig::default();
+        CfformatConfig::default().apply(&mut config);
+        assert_eq!(config.tab_size, 4);
+        assert_eq!(config.tag_case, ide::Case::Lower);
+    }
+}
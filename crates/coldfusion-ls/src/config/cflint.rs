@@ -0,0 +1,252 @@
+//! Reads the configuration of CFLint, a `.cflintrc` or `cflint.xml` at the
+//! root of the workspace, so that teams moving from CFLint keep the rules
+//! they enabled and their severities, for the diagnostics with an
+//! equivalent rule.
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use virtual_fs::AbsPathBuf;
+
+/// CFLint rules and the codes of the equivalent diagnostics.
+const RULES: &[(&str, &str)] = &[
+    ("CFQUERYPARAM_REQ", "sql-injection"),
+    ("EXCESSIVE_FUNCTION_LENGTH", "function-length"),
+    ("FUNCTION_TOO_COMPLEX", "complexity"),
+    ("MISSING_VAR", "missing-var-scope"),
+    ("QUERYPARAM_REQ", "sql-injection"),
+    ("UNUSED_LOCAL_VARIABLE", "unused-variable"),
+    ("UNUSED_METHOD_ARGUMENT", "unused-variable"),
+];
+
+/// Parameters of CFLint checkers and the thresholds they set.
+const PARAMETERS: &[(&str, Threshold)] = &[
+    ("CyclomaticComplexityChecker.maximum", Threshold::Complexity),
+    ("FunctionLengthChecker.length", Threshold::FunctionLength),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Threshold {
+    Complexity,
+    FunctionLength,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct CflintConfig {
+    /// The only rules reported, unless empty.
+    #[serde(default)]
+    includes: Vec<RuleDef>,
+    #[serde(default)]
+    excludes: Vec<RuleDef>,
+    /// Parameters of the checkers, e.g. `FunctionLengthChecker.length`.
+    #[serde(default, deserialize_with = "parameters")]
+    parameters: FxHashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct RuleDef {
+    code: String,
+    #[serde(default)]
+    severity: Option<String>,
+}
+
+/// Parameters are written as strings or as numbers.
+fn parameters<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<FxHashMap<String, String>, D::Error> {
+    let values = FxHashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .map(|(name, value)| match value {
+            serde_json::Value::String(it) => (name, it),
+            it => (name, it.to_string()),
+        })
+        .collect())
+}
+
+impl CflintConfig {
+    /// The configuration at the root of the workspace, if there is one.
+    pub(crate) fn load(root: &AbsPathBuf) -> Option<CflintConfig> {
+        if let Ok(text) = std::fs::read_to_string(root.join(".cflintrc")) {
+            match serde_json::from_str(&text) {
+                Ok(it) => return Some(it),
+                Err(e) => tracing::warn!("Failed to read .cflintrc: {e}"),
+            }
+        }
+        let text = std::fs::read_to_string(root.join("cflint.xml")).ok()?;
+        Some(CflintConfig::from_xml(&text))
+    }
+
+    /// Reads `<includes code="..." severity="..."/>`, `<excludes code="..."/>`
+    /// and the `<parameter>`s of `<rule>`s.
+    fn from_xml(text: &str) -> CflintConfig {
+        let mut res = CflintConfig::default();
+        let mut rule = String::new();
+        for (name, attributes) in xml_elements(text) {
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(it, _)| it == name)
+                    .map(|(_, value)| value.clone())
+            };
+            match name.as_str() {
+                "includes" | "excludes" => {
+                    let Some(code) = attribute("code") else {
+                        continue;
+                    };
+                    let def = RuleDef {
+                        code,
+                        severity: attribute("severity"),
+                    };
+                    match name.as_str() {
+                        "includes" => res.includes.push(def),
+                        _ => res.excludes.push(def),
+                    }
+                }
+                "rule" => rule = attribute("name").unwrap_or_default(),
+                "parameter" => {
+                    if let (Some(name), Some(value)) = (attribute("name"), attribute("value")) {
+                        res.parameters.insert(format!("{rule}.{name}"), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// Disables the diagnostics whose rules are excluded, or not included,
+    /// and sets their severities and thresholds.
+    pub(crate) fn apply(&self, config: &mut ide::DiagnosticsConfig) {
+        let has = |defs: &[RuleDef], rule: &str| defs.iter().any(|it| it.code == rule);
+        for (_, code) in RULES {
+            let rules = || {
+                RULES
+                    .iter()
+                    .filter(move |(_, it)| it == code)
+                    .map(|(rule, _)| *rule)
+            };
+            let included = self.includes.is_empty() || rules().any(|it| has(&self.includes, it));
+            if !included || rules().any(|it| has(&self.excludes, it)) {
+                config.disabled.insert(code.to_string());
+            }
+        }
+        for def in &self.includes {
+            let code = RULES.iter().find(|(rule, _)| *rule == def.code);
+            let severity = def.severity.as_deref().and_then(severity);
+            if let (Some((_, code)), Some(severity)) = (code, severity) {
+                config.severities.insert(code.to_string(), severity);
+            }
+        }
+        for (parameter, threshold) in PARAMETERS {
+            let Some(value) = self.parameters.get(*parameter) else {
+                continue;
+            };
+            let Ok(value) = value.trim().parse::<u32>() else {
+                continue;
+            };
+            match threshold {
+                Threshold::Complexity => config.complexity.warning = Some(value),
+                Threshold::FunctionLength => config.function_length.warning = Some(value),
+            }
+        }
+    }
+}
+
+/// The severity of a CFLint severity level.
+fn severity(level: &str) -> Option<ide::Severity> {
+    let res = match level.to_ascii_uppercase().as_str() {
+        "FATAL" | "CRITICAL" | "ERROR" => ide::Severity::Error,
+        "WARNING" | "CAUTION" => ide::Severity::Warning,
+        "INFO" => ide::Severity::Information,
+        "COSMETIC" => ide::Severity::Hint,
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// The opening tags of an XML document, with their attributes.
+fn xml_elements(text: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut res = Vec::new();
+    for chunk in text.split('<').skip(1) {
+        let Some(end) = chunk.find('>') else {
+            continue;
+        };
+        let element = chunk[..end].trim_end_matches('/');
+        if element.starts_with(['/', '?', '!']) {
+            continue;
+        }
+        let (name, mut rest) = element
+            .split_once(char::is_whitespace)
+            .unwrap_or((element, ""));
+        let mut attributes = Vec::new();
+        while let Some((key, after)) = rest.split_once('=') {
+            let after = after.trim_start();
+            let Some(quote) = after.chars().next().filter(|it| matches!(it, '"' | '\'')) else {
+                break;
+            };
+            let Some((value, after)) = after[1..].split_once(quote) else {
+                break;
+            };
+            attributes.push((key.trim().to_string(), value.to_string()));
+            rest = after;
+        }
+        res.push((name.to_string(), attributes));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cflintrc() {
+        let cflint: CflintConfig = serde_json::from_str(
+            r#"{
+                "rule": [],
+                "includes": [
+                    { "code": "MISSING_VAR", "severity": "ERROR" },
+                    { "code": "FUNCTION_TOO_COMPLEX" },
+                    { "code": "UNUSED_METHOD_ARGUMENT", "severity": "INFO" }
+                ],
+                "excludes": [{ "code": "UNUSED_LOCAL_VARIABLE" }],
+                "inheritParent": false,
+                "parameters": { "CyclomaticComplexityChecker.maximum": 15 }
+            }"#,
+        )
+        .unwrap();
+        let mut config = ide::DiagnosticsConfig::default();
+        cflint.apply(&mut config);
+
+        let mut disabled: Vec<&str> = config.disabled.iter().map(String::as_str).collect();
+        disabled.sort();
+        assert_eq!(
+            disabled,
+            ["function-length", "sql-injection", "unused-variable"]
+        );
+        assert_eq!(
+            config.severities.get("missing-var-scope"),
+            Some(&ide::Severity::Error)
+        );
+        assert_eq!(config.complexity.warning, Some(15));
+    }
+
+    #[test]
+    fn test_cflint_xml() {
+        let cflint = CflintConfig::from_xml(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<config>
+    <excludes code="QUERYPARAM_REQ" />
+    <excludes code='CFQUERYPARAM_REQ'/>
+    <rule name="FunctionLengthChecker" className="FunctionLengthChecker">
+        <parameter name="length" value="200"/>
+    </rule>
+</config>"#,
+        );
+        let mut config = ide::DiagnosticsConfig::default();
+        cflint.apply(&mut config);
+
+        let disabled: Vec<&str> = config.disabled.iter().map(String::as_str).collect();
+        assert_eq!(disabled, ["sql-injection"]);
+        assert_eq!(config.function_length.warning, Some(200));
+    }
+}
@@ -1,21 +1,76 @@
-use std::{iter, path::PathBuf};
-use virtual_fs::AbsPathBuf;
+mod cfconfig;
+mod cfformat;
+mod cflint;
 
-use serde::de::DeserializeOwned;
+use std::{fmt, iter, path::PathBuf, time::Duration};
+use virtual_fs::{AbsPathBuf, VirtualFsPath};
+
+use crate::{glob::Glob, global_state::PositionEncoding, indexing::FileFilter};
+use cfconfig::CfConfig;
+use cfformat::CfformatConfig;
+use cflint::CflintConfig;
+
+use rustc_hash::FxHashMap;
+use serde::{de::DeserializeOwned, Deserialize};
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct ManifestPath {
     file: AbsPathBuf,
 }
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum ProjectManifest {
     BoxJson(ManifestPath),
 }
 
+/// The problems of the settings, each named as users write it, e.g.
+/// `diagnostics.rules`.
 #[derive(Debug)]
 pub struct ConfigError {
+    /// The settings whose value has the wrong type, which keep their default.
     errors: Vec<(String, serde_json::Error)>,
+    /// The settings the server doesn't know, e.g. misspelled, and ignores.
+    unknown: Vec<String>,
+}
+
+impl ConfigError {
+    /// Adds the problems of `other`, but for those already there.
+    fn extend(&mut self, other: ConfigError) {
+        for (name, e) in other.errors {
+            if !self.errors.iter().any(|(it, _)| *it == name) {
+                self.errors.push((name, e));
+            }
+        }
+        for name in other.unknown {
+            if !self.unknown.contains(&name) {
+                self.unknown.push(name);
+            }
+        }
+    }
 }
 
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems = self
+            .errors
+            .iter()
+            .map(|(name, e)| format!("invalid value for `{name}`: {e}"))
+            .chain(
+                self.unknown
+                    .iter()
+                    .map(|name| format!("unknown setting `{name}`")),
+            );
+        for (i, problem) in problems.enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(&problem)?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct Config {
     root_path: AbsPathBuf,
@@ -23,7 +78,259 @@ pub struct Config {
     workspace_roots: Vec<AbsPathBuf>,
     detached_files: Vec<AbsPathBuf>,
     discovered_projects: Vec<ProjectManifest>,
+    diagnostics: ide::DiagnosticsConfig,
+    /// The CFLint configuration of the workspace, applied over the settings.
+    cflint: Option<CflintConfig>,
+    /// The settings of the server of each workspace root having them, for
+    /// its datasources: each root is an application of its own.
+    cfconfigs: Vec<(AbsPathBuf, CfConfig)>,
+    /// The cfformat settings of each workspace root having them, applied
+    /// over the format settings of its files.
+    cfformats: Vec<(AbsPathBuf, CfformatConfig)>,
+    /// Whether only syntax errors are kept up to date while typing, the
+    /// other diagnostics waiting for the file to be saved.
+    diagnostics_on_save_only: bool,
+    /// Whether the diagnostics of the files which aren't open are published
+    /// too, as the background analysis finds them.
+    workspace_diagnostics: bool,
+    /// Whether the client is asked to watch files for changes made outside
+    /// the editor.
+    files_watcher: bool,
+    /// Whether the index of the workspace is kept on disk between runs.
+    cache_enable: bool,
+    /// Where the index is kept, instead of the cache directory of the user.
+    cache_directory: Option<AbsPathBuf>,
+    format: ide::FormatConfig,
+    /// The directories left out of the workspace, relative to each root.
+    files_exclude_dirs: Vec<PathBuf>,
+    /// The globs of the files left out, relative to each root.
+    files_exclude_globs: Vec<String>,
+    files_max_file_size: Option<u64>,
+    files_respect_ignore_files: bool,
+    /// The size, in bytes, and the number of lines above which a document
+    /// only gets syntax errors, `None` for no limit.
+    large_file_max_size: Option<u64>,
+    large_file_max_lines: Option<usize>,
+    /// How long a request runs before the watchdog reports it as stuck.
+    watchdog_deadline: Option<Duration>,
+    /// How often the timings of the requests are logged, if they are.
+    profiling_log_interval: Option<Duration>,
+    /// The settings given in `initialize`, which those of the client go
+    /// over.
+    initialization_options: serde_json::Value,
+    /// The settings of the workspace folders having their own, over those
+    /// of the workspace, e.g. for applications running on other engines.
+    folders: Vec<(AbsPathBuf, FolderConfig)>,
+}
+
+/// The settings a workspace folder may change: those of its diagnostics and
+/// its formatting. The others are for the whole server.
+#[derive(Debug, Clone)]
+struct FolderConfig {
+    diagnostics: ide::DiagnosticsConfig,
+    format: ide::FormatConfig,
+}
+
+/// A severity as written in the settings.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SeverityDef {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl From<SeverityDef> for ide::Severity {
+    fn from(severity: SeverityDef) -> ide::Severity {
+        match severity {
+            SeverityDef::Error => ide::Severity::Error,
+            SeverityDef::Warning => ide::Severity::Warning,
+            SeverityDef::Information => ide::Severity::Information,
+            SeverityDef::Hint => ide::Severity::Hint,
+        }
+    }
+}
+
+/// The level of a rule in `diagnostics.rules`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RuleLevelDef {
+    Off,
+    Hint,
+    #[serde(alias = "information")]
+    Info,
+    Warning,
+    Error,
+}
+
+impl RuleLevelDef {
+    /// The severity of the rule, `None` when it is off.
+    fn severity(self) -> Option<ide::Severity> {
+        match self {
+            RuleLevelDef::Off => None,
+            RuleLevelDef::Hint => Some(ide::Severity::Hint),
+            RuleLevelDef::Info => Some(ide::Severity::Information),
+            RuleLevelDef::Warning => Some(ide::Severity::Warning),
+            RuleLevelDef::Error => Some(ide::Severity::Error),
+        }
+    }
+}
+
+/// A category of diagnostics as written in `diagnostics.categories`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CategoryDef {
+    Correctness,
+    Security,
+    Style,
+}
+
+impl From<CategoryDef> for ide::DiagnosticCategory {
+    fn from(category: CategoryDef) -> ide::DiagnosticCategory {
+        match category {
+            CategoryDef::Correctness => ide::DiagnosticCategory::Correctness,
+            CategoryDef::Security => ide::DiagnosticCategory::Security,
+            CategoryDef::Style => ide::DiagnosticCategory::Style,
+        }
+    }
+}
+
+/// A case of names as written in `diagnostics.naming`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+enum NamingCaseDef {
+    #[serde(rename = "camelCase")]
+    Camel,
+    #[serde(rename = "PascalCase")]
+    Pascal,
+    #[serde(rename = "UPPER_CASE")]
+    UpperSnake,
+    #[serde(rename = "snake_case")]
+    Snake,
+}
+
+impl From<NamingCaseDef> for ide::NamingCase {
+    fn from(case: NamingCaseDef) -> ide::NamingCase {
+        match case {
+            NamingCaseDef::Camel => ide::NamingCase::Camel,
+            NamingCaseDef::Pascal => ide::NamingCase::Pascal,
+            NamingCaseDef::UpperSnake => ide::NamingCase::UpperSnake,
+            NamingCaseDef::Snake => ide::NamingCase::Snake,
+        }
+    }
+}
+
+/// A case of names as written in the `format` settings.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CaseDef {
+    Preserve,
+    Lower,
+    Upper,
+}
+
+impl From<CaseDef> for ide::Case {
+    fn from(case: CaseDef) -> ide::Case {
+        match case {
+            CaseDef::Preserve => ide::Case::Preserve,
+            CaseDef::Lower => ide::Case::Lower,
+            CaseDef::Upper => ide::Case::Upper,
+        }
+    }
+}
+
+/// Quotes as written in `format.attributeQuote`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum QuoteDef {
+    Preserve,
+    Double,
+    Single,
+}
+
+impl From<QuoteDef> for ide::Quote {
+    fn from(quote: QuoteDef) -> ide::Quote {
+        match quote {
+            QuoteDef::Preserve => ide::Quote::Preserve,
+            QuoteDef::Double => ide::Quote::Double,
+            QuoteDef::Single => ide::Quote::Single,
+        }
+    }
+}
+
+/// A brace style as written in `format.braceStyle`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum BraceStyleDef {
+    SameLine,
+    NextLine,
+}
+
+impl From<BraceStyleDef> for ide::BraceStyle {
+    fn from(style: BraceStyleDef) -> ide::BraceStyle {
+        match style {
+            BraceStyleDef::SameLine => ide::BraceStyle::SameLine,
+            BraceStyleDef::NextLine => ide::BraceStyle::NextLine,
+        }
+    }
+}
+
+/// Spacing as written in `format.operatorSpacing` and
+/// `format.keywordSpacing`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SpacingDef {
+    Preserve,
+    Always,
+    Never,
+}
+
+impl From<SpacingDef> for ide::Spacing {
+    fn from(spacing: SpacingDef) -> ide::Spacing {
+        match spacing {
+            SpacingDef::Preserve => ide::Spacing::Preserve,
+            SpacingDef::Always => ide::Spacing::Always,
+            SpacingDef::Never => ide::Spacing::Never,
+        }
+    }
+}
+
+/// Trailing commas as written in `format.trailingComma`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TrailingCommaDef {
+    Preserve,
+    Multiline,
+    Never,
+}
+
+impl From<TrailingCommaDef> for ide::TrailingComma {
+    fn from(comma: TrailingCommaDef) -> ide::TrailingComma {
+        match comma {
+            TrailingCommaDef::Preserve => ide::TrailingComma::Preserve,
+            TrailingCommaDef::Multiline => ide::TrailingComma::Multiline,
+            TrailingCommaDef::Never => ide::TrailingComma::Never,
+        }
+    }
+}
+
+/// An engine as written in the settings.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum EngineDef {
+    Adobe,
+    Lucee,
+}
+
+impl From<EngineDef> for ide::EngineKind {
+    fn from(engine: EngineDef) -> ide::EngineKind {
+        match engine {
+            EngineDef::Adobe => ide::EngineKind::Adobe,
+            EngineDef::Lucee => ide::EngineKind::Lucee,
+        }
+    }
 }
+
 impl Config {
     pub fn new(
         root_path: AbsPathBuf,
@@ -36,11 +343,439 @@ impl Config {
             workspace_roots,
             detached_files: Vec::new(),
             discovered_projects: Vec::new(),
+            diagnostics: ide::DiagnosticsConfig::default(),
+            cflint: None,
+            cfconfigs: Vec::new(),
+            cfformats: Vec::new(),
+            diagnostics_on_save_only: false,
+            workspace_diagnostics: false,
+            files_watcher: true,
+            cache_enable: true,
+            cache_directory: None,
+            format: ide::FormatConfig::default(),
+            files_exclude_dirs: Vec::new(),
+            files_exclude_globs: Vec::new(),
+            files_max_file_size: None,
+            files_respect_ignore_files: true,
+            large_file_max_size: Some(1_000_000),
+            large_file_max_lines: Some(20_000),
+            watchdog_deadline: Some(Duration::from_secs(10)),
+            profiling_log_interval: None,
+            initialization_options: serde_json::Value::Null,
+            folders: Vec::new(),
+        }
+    }
+
+    /// Reads the CFLint configuration of the first workspace root having
+    /// one.
+    pub fn load_cflint(&mut self) {
+        self.cflint = self
+            .workspace_roots
+            .iter()
+            .chain(iter::once(&self.root_path))
+            .find_map(CflintConfig::load);
+    }
+
+    /// Reads the server settings of every workspace root having them.
+    pub fn load_cfconfig(&mut self) {
+        self.cfconfigs = self
+            .integration_roots()
+            .into_iter()
+            .filter_map(|root| Some((root.clone(), CfConfig::load(root)?)))
+            .collect();
+    }
+
+    /// Reads the cfformat settings of every workspace root having them.
+    pub fn load_cfformat(&mut self) {
+        self.cfformats = self
+            .integration_roots()
+            .into_iter()
+            .filter_map(|root| Some((root.clone(), CfformatConfig::load(root)?)))
+            .collect();
+    }
+
+    /// The workspace roots, and the root path if it isn't one of them.
+    fn integration_roots(&self) -> Vec<&AbsPathBuf> {
+        let mut roots: Vec<&AbsPathBuf> = self.workspace_roots.iter().collect();
+        if !roots.contains(&&self.root_path) {
+            roots.push(&self.root_path);
+        }
+        roots
+    }
+
+    pub fn workspace_roots(&self) -> &[AbsPathBuf] {
+        &self.workspace_roots
+    }
+
+    /// Changes the roots, as folders are added to or removed from the
+    /// workspace, reading the integrations of the new ones.
+    pub fn set_workspace_roots(&mut self, roots: Vec<AbsPathBuf>) {
+        self.workspace_roots = roots;
+        self.reload_integrations();
+    }
+
+    /// Whether the client shows the progress of work the server reports
+    /// with `$/progress`.
+    pub fn work_done_progress(&self) -> bool {
+        let window = self.capabilities.window.as_ref();
+        window.and_then(|it| it.work_done_progress).unwrap_or(false)
+    }
+
+    /// Whether the client watches the files matching the globs the server
+    /// registers, and notifies it of their changes.
+    pub fn did_change_watched_files_dynamic_registration(&self) -> bool {
+        let workspace = self.capabilities.workspace.as_ref();
+        workspace
+            .and_then(|it| it.did_change_watched_files.as_ref())
+            .and_then(|it| it.dynamic_registration)
+            .unwrap_or(false)
+    }
+
+    /// Whether files changed outside the editor are watched, as the client
+    /// supports it and the settings allow.
+    pub fn files_watcher(&self) -> bool {
+        self.files_watcher && self.did_change_watched_files_dynamic_registration()
+    }
+
+    /// The directory the index of the workspace is kept in between runs,
+    /// `None` when it isn't kept.
+    pub fn cache_directory(&self) -> Option<PathBuf> {
+        if !self.cache_enable {
+            return None;
+        }
+        if let Some(dir) = &self.cache_directory {
+            return Some(dir.clone().into());
+        }
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|it| PathBuf::from(it).join(".cache")))?;
+        Some(base.join("coldfusion-ls"))
+    }
+
+    /// Reads the CFLint configuration, the server settings and the cfformat
+    /// settings again, after they changed on disk.
+    pub fn reload_integrations(&mut self) {
+        self.load_cflint();
+        self.load_cfconfig();
+        self.load_cfformat();
+    }
+
+    /// Whether the client answers `workspace/configuration`, which gives the
+    /// settings when they change instead of the notification.
+    pub fn pull_configuration(&self) -> bool {
+        let workspace = self.capabilities.workspace.as_ref();
+        workspace.and_then(|it| it.configuration).unwrap_or(false)
+    }
+
+    /// The encoding of positions: UTF-8 when the client offers it, saving
+    /// conversions, and the UTF-16 of the LSP otherwise.
+    pub fn position_encoding(&self) -> PositionEncoding {
+        let offers_utf8 = self
+            .capabilities
+            .general
+            .as_ref()
+            .and_then(|it| it.position_encodings.as_ref())
+            .is_some_and(|it| it.contains(&lsp_types::PositionEncodingKind::UTF8));
+        if offers_utf8 {
+            PositionEncoding::Utf8
+        } else {
+            PositionEncoding::Utf16
+        }
+    }
+
+    /// Whether the client applies code action edits containing snippets, an
+    /// extension advertised as `experimental.snippetTextEdit`.
+    pub fn snippet_text_edit(&self) -> bool {
+        let experimental = self.capabilities.experimental.as_ref();
+        experimental
+            .and_then(|it| it.get("snippetTextEdit"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// Whether the client shows the status of the server, an extension
+    /// advertised as `experimental.serverStatusNotification`.
+    pub fn server_status_notification(&self) -> bool {
+        let experimental = self.capabilities.experimental.as_ref();
+        experimental
+            .and_then(|it| it.get("serverStatusNotification"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    }
+
+    /// Whether the client takes workspace edits as versioned document
+    /// changes, so that it can discard those computed on an older text.
+    pub fn document_changes(&self) -> bool {
+        let workspace = self.capabilities.workspace.as_ref();
+        workspace
+            .and_then(|it| it.workspace_edit.as_ref())
+            .and_then(|it| it.document_changes)
+            .unwrap_or(false)
+    }
+
+    /// Whether the client renames files as part of workspace edits, which
+    /// moving components needs.
+    pub fn rename_files(&self) -> bool {
+        let workspace_edit = self
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|it| it.workspace_edit.as_ref());
+        self.document_changes()
+            && workspace_edit
+                .and_then(|it| it.resource_operations.as_ref())
+                .is_some_and(|it| it.contains(&lsp_types::ResourceOperationKind::Rename))
+    }
+
+    /// Whether the client renders diagnostics tagged with `tag`, e.g. faded
+    /// out when unnecessary.
+    pub fn diagnostic_tag(&self, tag: &lsp_types::DiagnosticTag) -> bool {
+        let publish = self
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|it| it.publish_diagnostics.as_ref());
+        let tag_support = publish.and_then(|it| it.tag_support.as_ref());
+        tag_support.is_some_and(|it| it.value_set.contains(tag))
+    }
+
+    /// Whether the client shows the related information of diagnostics.
+    /// Otherwise it is written into their message.
+    pub fn related_information(&self) -> bool {
+        let publish = self
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|it| it.publish_diagnostics.as_ref());
+        publish
+            .and_then(|it| it.related_information)
+            .unwrap_or(false)
+    }
+
+    /// Whether the client links the codes of diagnostics to their
+    /// documentation.
+    pub fn code_description(&self) -> bool {
+        let publish = self
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|it| it.publish_diagnostics.as_ref());
+        publish
+            .and_then(|it| it.code_description_support)
+            .unwrap_or(false)
+    }
+
+    pub fn assist(&self) -> ide::AssistConfig {
+        ide::AssistConfig {
+            snippet_cap: self.snippet_text_edit(),
         }
     }
 
+    /// The files left out of the workspace, the excluded directories being
+    /// resolved against each root.
+    pub fn files(&self) -> FileFilter {
+        let mut exclude_dirs: Vec<_> = self
+            .workspace_roots
+            .iter()
+            .flat_map(|root| {
+                self.files_exclude_dirs
+                    .iter()
+                    .map(|dir| root.absolutize(dir))
+            })
+            .collect();
+        exclude_dirs.sort();
+        exclude_dirs.dedup();
+        FileFilter {
+            roots: self.workspace_roots.clone(),
+            exclude_dirs,
+            exclude_globs: self
+                .files_exclude_globs
+                .iter()
+                .map(|it| Glob::new(it))
+                .collect(),
+            max_file_size: self.files_max_file_size,
+            respect_ignore_files: self.files_respect_ignore_files,
+        }
+    }
+
+    /// The formatting of the files outside of the workspace roots, with the
+    /// cfformat settings of the first root having them.
+    pub fn format(&self) -> ide::FormatConfig {
+        let mut res = self.format.clone();
+        if let Some((_, cfformat)) = self.cfformats.first() {
+            cfformat.apply(&mut res);
+        }
+        res
+    }
+
+    /// The formatting of the file at `path`, with the settings and the
+    /// cfformat settings of its workspace folder.
+    pub fn format_for(&self, path: &VirtualFsPath) -> ide::FormatConfig {
+        let Some(root) = self.root_of(path) else {
+            return self.format();
+        };
+        let mut res = match self.folder_of(path) {
+            Some(folder) => folder.format.clone(),
+            None => self.format.clone(),
+        };
+        if let Some((_, cfformat)) = self.cfformats.iter().find(|(it, _)| it == root) {
+            cfformat.apply(&mut res);
+        }
+        res
+    }
+
+    /// The diagnostics configuration of the files outside of the workspace
+    /// roots, with the server settings of the first root having them.
+    pub fn diagnostics(&self) -> ide::DiagnosticsConfig {
+        self.diagnostics_with(&self.diagnostics, self.cfconfigs.first().map(|(_, it)| it))
+    }
+
+    /// The diagnostics configuration of the file at `path`, with the
+    /// settings and the server settings of its root only. The files under no
+    /// root, like untitled documents, go with the first one.
+    pub fn diagnostics_for(&self, path: &VirtualFsPath) -> ide::DiagnosticsConfig {
+        let Some(root) = self.root_of(path) else {
+            return self.diagnostics();
+        };
+        let cfconfig = self.cfconfigs.iter().find(|(it, _)| it == root);
+        let diagnostics = match self.folder_of(path) {
+            Some(folder) => &folder.diagnostics,
+            None => &self.diagnostics,
+        };
+        self.diagnostics_with(diagnostics, cfconfig.map(|(_, it)| it))
+    }
+
+    /// The workspace root of `path`, the innermost if roots are nested, or
+    /// the first one for the files under none.
+    fn root_of(&self, path: &VirtualFsPath) -> Option<&AbsPathBuf> {
+        let root = path.as_path().and_then(|path| {
+            self.workspace_roots
+                .iter()
+                .filter(|root| path.starts_with(root))
+                .max_by_key(|root| root.as_os_str().len())
+        });
+        root.or(self.workspace_roots.first())
+    }
+
+    fn folder_of(&self, path: &VirtualFsPath) -> Option<&FolderConfig> {
+        let root = self.root_of(path)?;
+        self.folders
+            .iter()
+            .find(|(it, _)| it == root)
+            .map(|(_, it)| it)
+    }
+
+    fn diagnostics_with(
+        &self,
+        diagnostics: &ide::DiagnosticsConfig,
+        cfconfig: Option<&CfConfig>,
+    ) -> ide::DiagnosticsConfig {
+        let mut res = diagnostics.clone();
+        if let Some(cflint) = &self.cflint {
+            cflint.apply(&mut res);
+        }
+        if let Some(cfconfig) = cfconfig {
+            cfconfig.apply(&mut res);
+        }
+        res
+    }
+
+    /// The engine the code runs on, which the built-in functions and tags,
+    /// and their deprecations, depend on.
+    pub fn engine(&self) -> Option<ide::Engine> {
+        self.diagnostics.engine
+    }
+
+    /// The engine of the workspace, then those of the folders with settings
+    /// of their own.
+    pub fn engines(&self) -> Vec<Option<ide::Engine>> {
+        iter::once(self.engine())
+            .chain(self.folders.iter().map(|(_, it)| it.diagnostics.engine))
+            .collect()
+    }
+
+    /// Whether diagnostics other than syntax errors are only computed when
+    /// documents are opened or saved, to spare the CPU on big workspaces.
+    pub fn diagnostics_on_save_only(&self) -> bool {
+        self.diagnostics_on_save_only
+    }
+
+    /// Whether `text` is too big to analyze beyond its syntax as it is
+    /// typed: it only gets syntax errors, its other features staying.
+    pub fn is_large_file(&self, text: &str) -> bool {
+        let too_big = self
+            .large_file_max_size
+            .is_some_and(|max| text.len() as u64 > max);
+        too_big
+            || self
+                .large_file_max_lines
+                .is_some_and(|max| memchr::memchr_iter(b'\n', text.as_bytes()).count() > max)
+    }
+
+    pub fn workspace_diagnostics(&self) -> bool {
+        self.workspace_diagnostics
+    }
+
+    pub fn watchdog_deadline(&self) -> Option<Duration> {
+        self.watchdog_deadline
+    }
+
+    pub fn profiling_log_interval(&self) -> Option<Duration> {
+        self.profiling_log_interval
+    }
+
+    /// Reads the settings given in `initialize`, which the settings of the
+    /// client later go over.
+    pub fn update_initialization_options(
+        &mut self,
+        json: serde_json::Value,
+    ) -> Result<(), ConfigError> {
+        self.initialization_options = json.clone();
+        self.update(json)
+    }
+
+    /// Reads the settings of the client over the initialization options,
+    /// then those of each workspace folder in `folders` over them. A folder
+    /// without settings of its own, or outside of the workspace, is left out.
+    pub fn update_settings(
+        &mut self,
+        settings: serde_json::Value,
+        folders: Vec<(AbsPathBuf, serde_json::Value)>,
+    ) -> Result<(), ConfigError> {
+        let mut base = self.initialization_options.clone();
+        merge_settings(&mut base, settings);
+        let mut res = self.update(base.clone()).err();
+        self.folders.clear();
+        for (root, settings) in folders {
+            if !self.workspace_roots.contains(&root)
+                || settings.as_object().is_none_or(|it| it.is_empty())
+            {
+                continue;
+            }
+            let mut json = base.clone();
+            merge_settings(&mut json, settings);
+            let mut folder = self.clone();
+            if let Err(e) = folder.update(json) {
+                match &mut res {
+                    Some(res) => res.extend(e),
+                    None => res = Some(e),
+                }
+            }
+            let config = FolderConfig {
+                diagnostics: folder.diagnostics,
+                format: folder.format,
+            };
+            self.folders.push((root, config));
+        }
+        res.map_or(Ok(()), Err)
+    }
+
+    /// Reads the settings, each with the type it is deserialized to. A
+    /// setting with the wrong type keeps its default, and the ones left
+    /// unread are unknown: both are reported, each with its name.
     pub fn update(&mut self, mut json: serde_json::Value) -> Result<(), ConfigError> {
-        if json.is_null() || json.as_object().map_or(false, |it| it.is_empty()) {
+        if json.is_null() || json.as_object().is_some_and(|it| it.is_empty()) {
             return Ok(());
         }
 
@@ -50,11 +785,322 @@ impl Config {
                 .into_iter()
                 .map(AbsPathBuf::assert)
                 .collect();
+        self.diagnostics.undefined_variable = get_field::<SeverityDef>(
+            &mut json,
+            &mut errors,
+            "diagnostics_undefinedVariable_severity",
+            None,
+            "\"warning\"",
+        )
+        .into();
+        self.diagnostics.complexity = ide::Thresholds {
+            hint: get_field(
+                &mut json,
+                &mut errors,
+                "diagnostics_complexity_hint",
+                None,
+                "10",
+            ),
+            warning: get_field(
+                &mut json,
+                &mut errors,
+                "diagnostics_complexity_warning",
+                None,
+                "20",
+            ),
+        };
+        self.diagnostics.function_length = ide::Thresholds {
+            hint: get_field(
+                &mut json,
+                &mut errors,
+                "diagnostics_functionLength_hint",
+                None,
+                "100",
+            ),
+            warning: get_field(
+                &mut json,
+                &mut errors,
+                "diagnostics_functionLength_warning",
+                None,
+                "300",
+            ),
+        };
+        self.diagnostics.switch_fallthrough = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_switchFallthrough_enable",
+            None,
+            "false",
+        );
+        self.diagnostics.strict_scoping = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_strictScoping_enable",
+            None,
+            "false",
+        );
+        self.diagnostics.output_hygiene = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_outputHygiene_enable",
+            None,
+            "false",
+        );
+        self.diagnostics.shared_scope_locking = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_sharedScopeLocking_enable",
+            None,
+            "false",
+        );
+        let naming_enable = get_field::<bool>(
+            &mut json,
+            &mut errors,
+            "diagnostics_naming_enable",
+            None,
+            "false",
+        );
+        let mut naming_case = |field, default| {
+            get_field::<Option<NamingCaseDef>>(&mut json, &mut errors, field, None, default)
+                .map(Into::into)
+        };
+        let naming = ide::NamingConventions {
+            functions: naming_case("diagnostics_naming_functions", "\"camelCase\""),
+            variables: naming_case("diagnostics_naming_variables", "\"camelCase\""),
+            components: naming_case("diagnostics_naming_components", "\"PascalCase\""),
+            constants: naming_case("diagnostics_naming_constants", "\"UPPER_CASE\""),
+        };
+        self.diagnostics.naming = naming_enable.then_some(naming);
+        self.diagnostics.datasources = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_datasources",
+            None,
+            "[]",
+        );
+        self.diagnostics.hardcoded_secrets_exclude = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_hardcodedSecrets_exclude",
+            None,
+            "[\"test\", \"tests\"]",
+        );
+        self.files_watcher =
+            get_field(&mut json, &mut errors, "files_watcher_enable", None, "true");
+        self.files_exclude_dirs =
+            get_field(&mut json, &mut errors, "files_excludeDirs", None, "[]");
+        self.files_exclude_globs =
+            get_field(&mut json, &mut errors, "files_excludeGlobs", None, "[]");
+        self.files_max_file_size =
+            get_field(&mut json, &mut errors, "files_maxFileSize", None, "null");
+        self.files_respect_ignore_files = get_field(
+            &mut json,
+            &mut errors,
+            "files_respectIgnoreFiles",
+            None,
+            "true",
+        );
+        // `0` lifts the limit.
+        self.large_file_max_size =
+            get_field::<Option<u64>>(&mut json, &mut errors, "largeFile_maxSize", None, "1000000")
+                .filter(|&it| it > 0);
+        self.large_file_max_lines =
+            get_field::<Option<usize>>(&mut json, &mut errors, "largeFile_maxLines", None, "20000")
+                .filter(|&it| it > 0);
+        self.cache_enable = get_field(&mut json, &mut errors, "cache_enable", None, "true");
+        self.cache_directory =
+            get_field::<Option<PathBuf>>(&mut json, &mut errors, "cache_directory", None, "null")
+                .map(|it| self.root_path.absolutize(it));
+        self.format = ide::FormatConfig {
+            tab_size: get_field(&mut json, &mut errors, "format_tabSize", None, "4"),
+            insert_spaces: get_field(&mut json, &mut errors, "format_insertSpaces", None, "true"),
+            max_line_width: get_field(&mut json, &mut errors, "format_maxLineWidth", None, "120"),
+            max_blank_lines: get_field(&mut json, &mut errors, "format_maxBlankLines", None, "1"),
+            tag_case: get_field::<CaseDef>(
+                &mut json,
+                &mut errors,
+                "format_tagCase",
+                None,
+                "\"lower\"",
+            )
+            .into(),
+            keyword_case: get_field::<CaseDef>(
+                &mut json,
+                &mut errors,
+                "format_keywordCase",
+                None,
+                "\"lower\"",
+            )
+            .into(),
+            scope_case: get_field::<CaseDef>(
+                &mut json,
+                &mut errors,
+                "format_scopeCase",
+                None,
+                "\"lower\"",
+            )
+            .into(),
+            function_case: get_field::<CaseDef>(
+                &mut json,
+                &mut errors,
+                "format_functionCase",
+                None,
+                "\"preserve\"",
+            )
+            .into(),
+            max_attributes_per_line: get_field(
+                &mut json,
+                &mut errors,
+                "format_maxAttributesPerLine",
+                None,
+                "null",
+            ),
+            align_attributes: get_field(
+                &mut json,
+                &mut errors,
+                "format_alignAttributes",
+                None,
+                "false",
+            ),
+            multiline_tags: get_field(&mut json, &mut errors, "format_multilineTags", None, "[]"),
+            attribute_quote: get_field::<QuoteDef>(
+                &mut json,
+                &mut errors,
+                "format_attributeQuote",
+                None,
+                "\"preserve\"",
+            )
+            .into(),
+            brace_style: get_field::<BraceStyleDef>(
+                &mut json,
+                &mut errors,
+                "format_braceStyle",
+                None,
+                "\"sameLine\"",
+            )
+            .into(),
+            operator_spacing: get_field::<SpacingDef>(
+                &mut json,
+                &mut errors,
+                "format_operatorSpacing",
+                None,
+                "\"preserve\"",
+            )
+            .into(),
+            keyword_spacing: get_field::<SpacingDef>(
+                &mut json,
+                &mut errors,
+                "format_keywordSpacing",
+                None,
+                "\"preserve\"",
+            )
+            .into(),
+            max_chained_calls_per_line: get_field(
+                &mut json,
+                &mut errors,
+                "format_maxChainedCallsPerLine",
+                None,
+                "null",
+            ),
+            trailing_comma: get_field::<TrailingCommaDef>(
+                &mut json,
+                &mut errors,
+                "format_trailingComma",
+                None,
+                "\"preserve\"",
+            )
+            .into(),
+            sql_layout: get_field(&mut json, &mut errors, "format_sqlLayout", None, "false"),
+            sql_keyword_case: get_field::<CaseDef>(
+                &mut json,
+                &mut errors,
+                "format_sqlKeywordCase",
+                None,
+                "\"preserve\"",
+            )
+            .into(),
+        };
+        self.workspace_diagnostics = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_workspace",
+            None,
+            "false",
+        );
+        self.profiling_log_interval = get_field::<Option<u64>>(
+            &mut json,
+            &mut errors,
+            "profiling_logInterval",
+            None,
+            "null",
+        )
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs);
+        self.watchdog_deadline =
+            get_field::<Option<u64>>(&mut json, &mut errors, "watchdog_deadline", None, "10")
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs);
+        self.diagnostics_on_save_only = get_field(
+            &mut json,
+            &mut errors,
+            "diagnostics_onSaveOnly",
+            None,
+            "false",
+        );
+        // Codes set to `off` are disabled, the others given their severity.
+        let rules = get_field::<FxHashMap<String, RuleLevelDef>>(
+            &mut json,
+            &mut errors,
+            "diagnostics_rules",
+            None,
+            "{}",
+        );
+        self.diagnostics.disabled.clear();
+        self.diagnostics.severities.clear();
+        for (code, level) in rules {
+            match level.severity() {
+                Some(severity) => {
+                    self.diagnostics.severities.insert(code, severity);
+                }
+                None => {
+                    self.diagnostics.disabled.insert(code);
+                }
+            }
+        }
+        let categories = get_field::<FxHashMap<CategoryDef, bool>>(
+            &mut json,
+            &mut errors,
+            "diagnostics_categories",
+            None,
+            "{}",
+        );
+        self.diagnostics.disabled_categories = categories
+            .into_iter()
+            .filter(|(_, enabled)| !enabled)
+            .map(|(category, _)| category.into())
+            .collect();
+        let engine_name =
+            get_field::<Option<EngineDef>>(&mut json, &mut errors, "engine_name", None, "null");
+        let engine_version =
+            get_field::<Option<u32>>(&mut json, &mut errors, "engine_version", None, "null");
+        self.diagnostics.engine =
+            engine_name
+                .zip(engine_version)
+                .map(|(kind, version)| ide::Engine {
+                    kind: kind.into(),
+                    version,
+                });
 
-        if errors.is_empty() {
+        // Read by clients for the trace of the messages they exchange.
+        get_field::<serde_json::Value>(&mut json, &mut errors, "trace_server", None, "null");
+
+        // `get_field` takes the values it reads: what is left is unknown.
+        let mut unknown = Vec::new();
+        unread_settings(&json, "", &mut unknown);
+        if errors.is_empty() && unknown.is_empty() {
             Ok(())
         } else {
-            Err(ConfigError { errors })
+            Err(ConfigError { errors, unknown })
         }
     }
 }
@@ -66,27 +1112,61 @@ fn get_field<T: DeserializeOwned>(
     alias: Option<&'static str>,
     default: &str,
 ) -> T {
-    alias
-        .into_iter()
-        .chain(iter::once(field))
-        .filter_map(move |field| {
-            let mut pointer = field.replace('_', "/");
-            pointer.insert(0, '/');
-            json.pointer_mut(&pointer)
-                .map(|it| serde_json::from_value(it.take()).map_err(|e| (e, pointer)))
-        })
-        .find(Result::is_ok)
-        .and_then(|res| match res {
-            Ok(it) => Some(it),
-            Err((e, pointer)) => {
-                tracing::warn!("Failed to deserialize config field at {}: {:?}", pointer, e);
-                error_sink.push((pointer, e));
-                None
+    let mut error = None;
+    for field in alias.into_iter().chain(iter::once(field)) {
+        let pointer = format!("/{}", field.replace('_', "/"));
+        let Some(value) = json.pointer_mut(&pointer) else {
+            continue;
+        };
+        let value = value.take();
+        let is_null = value.is_null();
+        match serde_json::from_value(value) {
+            Ok(it) => return it,
+            // Clients send `null` for the settings the user didn't set.
+            Err(_) if is_null => (),
+            Err(e) => {
+                error.get_or_insert((pointer, e));
+            }
+        }
+    }
+    if let Some((pointer, e)) = error {
+        tracing::warn!("Failed to deserialize config field at {}: {:?}", pointer, e);
+        error_sink.push((pointer[1..].replace('/', "."), e));
+    }
+    serde_json::from_str(default).unwrap_or_else(|e| panic!("{e} on: `{default}`"))
+}
+
+/// Puts the settings of `over` in `json`, replacing those with a value in
+/// both but for objects, whose settings are merged.
+fn merge_settings(json: &mut serde_json::Value, over: serde_json::Value) {
+    match (json, over) {
+        (_, serde_json::Value::Null) => (),
+        (serde_json::Value::Object(json), serde_json::Value::Object(over)) => {
+            for (key, value) in over {
+                merge_settings(json.entry(key).or_insert(serde_json::Value::Null), value);
             }
-        })
-        .unwrap_or_else(|| {
-            serde_json::from_str(default).unwrap_or_else(|e| panic!("{e} on: `{default}`"))
-        })
+        }
+        (json, over) => *json = over,
+    }
+}
+
+/// Collects the names of the settings in `json`, under `prefix`, which have
+/// a value.
+fn unread_settings(json: &serde_json::Value, prefix: &str, res: &mut Vec<String>) {
+    match json {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let name = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                unread_settings(value, &name, res);
+            }
+        }
+        serde_json::Value::Null => (),
+        _ => res.push(prefix.to_string()),
+    }
 }
 
 #[cfg(test)]
@@ -170,6 +1250,382 @@ mod tests {
         assert_eq!(config.detached_files.len(), 1);
     }
 
+    #[test]
+    fn test_config_update_problems() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        let json = serde_json::json!({
+            "diagnostics": {
+                "onSaveOnly": "yes",
+                "rules": { "xss": "off" },
+                "undefinedVariable": { "severity": "warning", "enable": true },
+            },
+            "format": {
+                "tabSize": 2,
+                "tagCase": "upper",
+                "scopeCase": "camel",
+                "multilineTags": ["cfquery"],
+                "attributeQuote": "double",
+                "braceStyle": "nextLine",
+                "trailingComma": "multiline",
+                "sqlKeywordCase": "upper",
+            },
+            "trace": { "server": "verbose" },
+            "enginee": { "name": "lucee" },
+            "cache": { "enable": null },
+        });
+        let e = config.update(json).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid value for `format.scopeCase`: unknown variant `camel`, \
+             expected one of `preserve`, `lower`, `upper`\n\
+             invalid value for `diagnostics.onSaveOnly`: invalid type: string \"yes\", \
+             expected a boolean\n\
+             unknown setting `diagnostics.undefinedVariable.enable`\n\
+             unknown setting `enginee.name`"
+        );
+        // The other settings are applied.
+        assert!(!config.diagnostics_on_save_only());
+        assert!(config.diagnostics().disabled.contains("xss"));
+        assert_eq!(config.format().tab_size, 2);
+        assert_eq!(config.format().tag_case, ide::Case::Upper);
+        assert_eq!(config.format().scope_case, ide::Case::Lower);
+        assert_eq!(config.format().multiline_tags, ["cfquery"]);
+        assert_eq!(config.format().attribute_quote, ide::Quote::Double);
+        assert_eq!(config.format().brace_style, ide::BraceStyle::NextLine);
+        assert_eq!(
+            config.format().trailing_comma,
+            ide::TrailingComma::Multiline
+        );
+        assert_eq!(config.format().sql_keyword_case, ide::Case::Upper);
+    }
+
+    #[test]
+    fn test_is_large_file() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        assert!(!config.is_large_file(&"<cfset x = 1>\n".repeat(10_000)));
+        assert!(!config.is_large_file(&"\n".repeat(20_000)));
+        assert!(config.is_large_file(&"\n".repeat(20_001)));
+
+        let json = serde_json::json!({ "largeFile": { "maxSize": 100, "maxLines": 0 } });
+        assert!(config.update(json).is_ok());
+        assert!(!config.is_large_file(&"\n".repeat(100)));
+        assert!(config.is_large_file(&"\n".repeat(101)));
+    }
+
+    #[test]
+    fn test_config_update_diagnostics() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        assert_eq!(
+            config.diagnostics().undefined_variable,
+            ide::Severity::Warning
+        );
+        let json = serde_json::json!({
+            "diagnostics": { "undefinedVariable": { "severity": "hint" } }
+        });
+        assert!(config.update(json).is_ok());
+        assert_eq!(config.diagnostics().undefined_variable, ide::Severity::Hint);
+        assert_eq!(config.diagnostics().engine, None);
+
+        let json = serde_json::json!({
+            "diagnostics": {
+                "complexity": { "hint": null },
+                "functionLength": { "warning": 800 },
+                "switchFallthrough": { "enable": true },
+                "strictScoping": { "enable": true },
+                "outputHygiene": { "enable": true },
+                "sharedScopeLocking": { "enable": true },
+                "naming": { "enable": true, "variables": "snake_case", "constants": null },
+                "datasources": ["main", "reporting"],
+                "hardcodedSecrets": { "exclude": ["fixtures"] },
+                "onSaveOnly": true,
+                "rules": { "xss": "off", "unused-variable": "info" },
+                "categories": { "style": false, "security": true }
+            }
+        });
+        assert!(config.update(json).is_ok());
+        assert_eq!(
+            config.diagnostics().complexity,
+            ide::Thresholds {
+                hint: None,
+                warning: Some(20),
+            }
+        );
+        assert_eq!(
+            config.diagnostics().function_length,
+            ide::Thresholds {
+                hint: Some(100),
+                warning: Some(800),
+            }
+        );
+        assert!(config.diagnostics().switch_fallthrough);
+        assert!(config.diagnostics().strict_scoping);
+        assert!(config.diagnostics().output_hygiene);
+        assert!(config.diagnostics().shared_scope_locking);
+        assert_eq!(
+            config.diagnostics().naming,
+            Some(ide::NamingConventions {
+                variables: Some(ide::NamingCase::Snake),
+                constants: None,
+                ..ide::NamingConventions::default()
+            })
+        );
+        assert_eq!(config.diagnostics().datasources, ["main", "reporting"]);
+        assert_eq!(config.diagnostics().hardcoded_secrets_exclude, ["fixtures"]);
+        assert!(config.diagnostics_on_save_only());
+        assert!(config.diagnostics().disabled.contains("xss"));
+        assert_eq!(
+            config.diagnostics().severities.get("unused-variable"),
+            Some(&ide::Severity::Information)
+        );
+        assert_eq!(
+            config
+                .diagnostics()
+                .disabled_categories
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [ide::DiagnosticCategory::Style]
+        );
+
+        let json = serde_json::json!({
+            "engine": { "name": "lucee", "version": 6 }
+        });
+        assert!(config.update(json).is_ok());
+        assert_eq!(
+            config.diagnostics().engine,
+            Some(ide::Engine {
+                kind: ide::EngineKind::Lucee,
+                version: 6,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cache_directory() {
+        let root = AbsPathBuf::try_from("/workspace").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        let json = serde_json::json!({ "cache": { "directory": ".cache/cfml" } });
+        assert!(config.update(json).is_ok());
+        assert_eq!(
+            config.cache_directory(),
+            Some(PathBuf::from("/workspace/.cache/cfml"))
+        );
+        let json = serde_json::json!({ "cache": { "enable": false } });
+        assert!(config.update(json).is_ok());
+        assert_eq!(config.cache_directory(), None);
+    }
+
+    #[test]
+    fn test_files() {
+        let roots = ["/a", "/b"].map(|it| AbsPathBuf::try_from(it).unwrap());
+        let mut config = Config::new(roots[0].clone(), Default::default(), roots.to_vec());
+        let json = serde_json::json!({
+            "files": {
+                "excludeDirs": ["vendor", "/data"],
+                "excludeGlobs": ["**/*.min.cfm"],
+                "maxFileSize": 1000,
+            }
+        });
+        assert!(config.update(json).is_ok());
+        let filter = config.files();
+        let excluded = |path: &str| filter.is_excluded(&AbsPathBuf::try_from(path).unwrap());
+        assert!(excluded("/a/vendor/x.cfc"));
+        assert!(excluded("/b/vendor/x.cfc"));
+        assert!(excluded("/data/x.cfm"));
+        assert!(excluded("/b/js/x.min.cfm"));
+        assert!(!excluded("/a/src/vendor.cfc"));
+        assert!(!excluded("/c/x.min.cfm"));
+        assert!(filter.is_too_big(1001));
+        assert!(!filter.is_too_big(1000));
+    }
+
+    #[test]
+    fn test_diagnostics_for() {
+        let dir = std::env::temp_dir().join("cfml-config-roots");
+        let _ = std::fs::remove_dir_all(&dir);
+        let roots = ["shop", "blog", "docs"].map(|it| {
+            let root = dir.join(it);
+            std::fs::create_dir_all(&root).unwrap();
+            AbsPathBuf::try_from(root).unwrap()
+        });
+        std::fs::write(
+            roots[0].join(".cfconfig.json"),
+            r#"{ "datasources": { "orders": {} } }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            roots[1].join(".cfconfig.json"),
+            r#"{ "datasources": { "posts": {} } }"#,
+        )
+        .unwrap();
+        let mut config = Config::new(roots[0].clone(), Default::default(), roots.to_vec());
+        config.load_cfconfig();
+        let datasources = |path: &AbsPathBuf| {
+            config
+                .diagnostics_for(&VirtualFsPath::from(path.clone()))
+                .datasources
+        };
+        assert_eq!(datasources(&roots[0].join("index.cfm")), ["orders"]);
+        assert_eq!(datasources(&roots[1].join("index.cfm")), ["posts"]);
+        assert!(datasources(&roots[2].join("index.cfm")).is_empty());
+        let outside = AbsPathBuf::try_from(dir.join("index.cfm")).unwrap();
+        assert_eq!(datasources(&outside), ["orders"]);
+        let untitled = VirtualFsPath::new_virtual_path("untitled:Untitled-1".to_string());
+        assert_eq!(config.diagnostics_for(&untitled).datasources, ["orders"]);
+    }
+
+    #[test]
+    fn test_format_for() {
+        let dir = std::env::temp_dir().join("cfml-config-cfformat");
+        let _ = std::fs::remove_dir_all(&dir);
+        let roots = ["shop", "blog"].map(|it| {
+            let root = dir.join(it);
+            std::fs::create_dir_all(&root).unwrap();
+            AbsPathBuf::try_from(root).unwrap()
+        });
+        std::fs::write(
+            roots[1].join(".cfformat.json"),
+            r#"{ "indent_size": 2, "tab_indent": true }"#,
+        )
+        .unwrap();
+        let mut config = Config::new(roots[0].clone(), Default::default(), roots.to_vec());
+        config.reload_integrations();
+        let format = |path: &AbsPathBuf| config.format_for(&VirtualFsPath::from(path.clone()));
+        assert_eq!(
+            format(&roots[0].join("index.cfm")),
+            ide::FormatConfig::default()
+        );
+        let blog = format(&roots[1].join("index.cfm"));
+        assert_eq!((blog.tab_size, blog.insert_spaces), (2, false));
+        assert_eq!(blog.keyword_case, ide::Case::Preserve);
+        assert_eq!(config.format().tab_size, 2);
+    }
+
+    #[test]
+    fn test_update_settings() {
+        let roots = ["/w/shop", "/w/blog"].map(|it| AbsPathBuf::try_from(it).unwrap());
+        let mut config = Config::new(roots[0].clone(), Default::default(), roots.to_vec());
+        config
+            .update_initialization_options(serde_json::json!({
+                "engine": { "name": "lucee", "version": 6 },
+                "format": { "tabSize": 2 },
+            }))
+            .unwrap();
+        let lucee = Some(ide::Engine {
+            kind: ide::EngineKind::Lucee,
+            version: 6,
+        });
+        let adobe = Some(ide::Engine {
+            kind: ide::EngineKind::Adobe,
+            version: 2021,
+        });
+        config
+            .update_settings(
+                serde_json::json!({ "format": { "insertSpaces": false } }),
+                vec![
+                    (
+                        roots[1].clone(),
+                        serde_json::json!({ "engine": { "name": "adobe", "version": 2021 } }),
+                    ),
+                    (roots[0].clone(), serde_json::Value::Null),
+                    (
+                        AbsPathBuf::try_from("/elsewhere").unwrap(),
+                        serde_json::json!({ "format": { "tabSize": 8 } }),
+                    ),
+                ],
+            )
+            .unwrap();
+        assert_eq!(config.engines(), [lucee, adobe]);
+        let file = |root: &AbsPathBuf| VirtualFsPath::from(root.join("index.cfm"));
+        assert_eq!(config.diagnostics_for(&file(&roots[0])).engine, lucee);
+        assert_eq!(config.diagnostics_for(&file(&roots[1])).engine, adobe);
+        // The folder goes over the settings, which go over the options.
+        let format = config.format_for(&file(&roots[1]));
+        assert_eq!((format.tab_size, format.insert_spaces), (2, false));
+
+        let e = config
+            .update_settings(
+                serde_json::json!({}),
+                vec![(
+                    roots[1].clone(),
+                    serde_json::json!({ "engine": { "nme": "x" } }),
+                )],
+            )
+            .unwrap_err();
+        assert_eq!(e.to_string(), "unknown setting `engine.nme`");
+        assert_eq!(config.engines(), [lucee, lucee]);
+    }
+
+    #[test]
+    fn test_related_information() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        assert!(!config.related_information());
+        assert!(!config.code_description());
+
+        let capabilities = serde_json::from_value(serde_json::json!({
+            "textDocument": { "publishDiagnostics": {
+                "relatedInformation": true,
+                "codeDescriptionSupport": true
+            } }
+        }))
+        .unwrap();
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        assert!(config.related_information());
+        assert!(config.code_description());
+    }
+
+    #[test]
+    fn test_workspace_edit_capabilities() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        assert!(!config.rename_files());
+        assert!(!config.diagnostic_tag(&lsp_types::DiagnosticTag::UNNECESSARY));
+
+        let capabilities = serde_json::from_value(serde_json::json!({
+            "workspace": { "workspaceEdit": {
+                "documentChanges": true,
+                "resourceOperations": ["create", "rename"]
+            } },
+            "textDocument": { "publishDiagnostics": { "tagSupport": { "valueSet": [1] } } }
+        }))
+        .unwrap();
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        assert!(config.rename_files());
+        assert!(config.diagnostic_tag(&lsp_types::DiagnosticTag::UNNECESSARY));
+        assert!(!config.diagnostic_tag(&lsp_types::DiagnosticTag::DEPRECATED));
+    }
+
+    #[test]
+    fn test_snippet_text_edit() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        assert!(!config.assist().snippet_cap);
+
+        let capabilities = lsp_types::ClientCapabilities {
+            experimental: Some(serde_json::json!({ "snippetTextEdit": true })),
+            ..Default::default()
+        };
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        assert!(config.assist().snippet_cap);
+    }
+
+    #[test]
+    fn test_position_encoding() {
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        assert_eq!(config.position_encoding(), PositionEncoding::Utf16);
+
+        let capabilities: lsp_types::ClientCapabilities = serde_json::from_value(
+            serde_json::json!({ "general": { "positionEncodings": ["utf-32", "utf-8", "utf-16"] } }),
+        )
+        .unwrap();
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        assert_eq!(config.position_encoding(), PositionEncoding::Utf8);
+    }
+
     #[test]
     fn test_get_field() {
         let mut json = serde_json::json!({
@@ -1,11 +1,12 @@
 use lsp_server::{ErrorCode, ExtractError, Notification, Request, Response};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt, panic};
+use std::{fmt, panic, time::Instant};
 
 use crate::{
     from_json,
-    global_state::GlobalState,
-    lsp::{Cancelled, LspError},
+    global_state::{GlobalState, GlobalStateSnapshot, Task},
+    lsp::LspError,
+    task_pool::ThreadIntent,
 };
 
 pub struct RequestDispatcher<'a> {
@@ -61,19 +62,152 @@ impl RequestDispatcher<'_> {
         R::Params: DeserializeOwned + panic::UnwindSafe + fmt::Debug,
         R::Result: Serialize,
     {
-        let (req, params, _panic_context) = match self.parse::<R>() {
+        let (req, params, panic_context) = match self.parse::<R>() {
             Some(it) => it,
             None => return self,
         };
         let _guard = tracing::span!(tracing::Level::INFO, "request", method = ?req.method, "request_id" = ?req.id).entered();
         tracing::debug!(?params);
-        let result = { f(self.global_state, params) };
-        if let Ok(response) = result_to_response::<R>(req.id, result) {
+        // The state may be left half updated, which beats losing the server.
+        let global_state = &mut *self.global_state;
+        let started = Instant::now();
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| f(global_state, params)));
+        self.global_state
+            .profile
+            .lock()
+            .record_handler(&req.method, None, started.elapsed());
+        let response = match result {
+            Ok(result) => result_to_response::<R>(req.id, result),
+            Err(payload) => Ok(panic_response(req.id, &panic_context, payload)),
+        };
+        if let Ok(response) = response {
             self.global_state.respond(response);
         }
 
         self
     }
+
+    /// Runs a request on the task pool against a snapshot, leaving the main
+    /// loop free.
+    pub(crate) fn on<R>(
+        &mut self,
+        f: fn(GlobalStateSnapshot, R::Params) -> anyhow::Result<R::Result>,
+    ) -> &mut Self
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + panic::UnwindSafe + Send + fmt::Debug,
+        R::Result: Serialize,
+    {
+        self.on_with_thread_intent::<R>(ThreadIntent::Worker, None, f)
+    }
+
+    /// Runs a request going through every file like [`Self::on`], showing
+    /// its progress as `title` if it takes a while.
+    pub(crate) fn on_with_progress<R>(
+        &mut self,
+        title: &'static str,
+        f: fn(GlobalStateSnapshot, R::Params) -> anyhow::Result<R::Result>,
+    ) -> &mut Self
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + panic::UnwindSafe + Send + fmt::Debug,
+        R::Result: Serialize,
+    {
+        self.on_with_thread_intent::<R>(ThreadIntent::Worker, Some(title), f)
+    }
+
+    /// Runs a request on the task pool, ahead of the other requests, for the
+    /// ones the user waits on while typing, like completions.
+    pub(crate) fn on_latency_sensitive<R>(
+        &mut self,
+        f: fn(GlobalStateSnapshot, R::Params) -> anyhow::Result<R::Result>,
+    ) -> &mut Self
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + panic::UnwindSafe + Send + fmt::Debug,
+        R::Result: Serialize,
+    {
+        self.on_with_thread_intent::<R>(ThreadIntent::LatencySensitive, None, f)
+    }
+
+    fn on_with_thread_intent<R>(
+        &mut self,
+        intent: ThreadIntent,
+        progress: Option<&'static str>,
+        f: fn(GlobalStateSnapshot, R::Params) -> anyhow::Result<R::Result>,
+    ) -> &mut Self
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + panic::UnwindSafe + Send + fmt::Debug,
+        R::Result: Serialize,
+    {
+        let (req, params, panic_context) = match self.parse::<R>() {
+            Some(it) => it,
+            None => return self,
+        };
+        let snap = self.global_state.cancellable_snapshot(&req.id, &req.method);
+        if let Some(title) = progress {
+            self.global_state.track_request_progress(&req.id, title);
+        }
+        let profile = self.global_state.profile.clone();
+        let dispatched = Instant::now();
+        self.global_state.task_pool.handle.spawn(intent, move || {
+            let _guard = tracing::span!(tracing::Level::INFO, "request", method = ?req.method, "request_id" = ?req.id).entered();
+            tracing::debug!(?params);
+            let started = Instant::now();
+            let result = panic::catch_unwind(move || f(snap, params));
+            profile.lock().record_handler(
+                &req.method,
+                Some(started - dispatched),
+                started.elapsed(),
+            );
+            let response = match result {
+                Ok(result) => result_to_response::<R>(req.id.clone(), result),
+                // The client no longer waits for the response, which the main
+                // loop drops.
+                Err(payload) if payload.is::<ide::Cancelled>() => Ok(Response::new_err(
+                    req.id.clone(),
+                    ErrorCode::RequestCanceled as i32,
+                    "canceled by client".to_owned(),
+                )),
+                Err(payload) => Ok(panic_response(req.id.clone(), &panic_context, payload)),
+            };
+            // Nothing can be cancelled before it is computed yet: tell the
+            // client the content changed instead.
+            Task::Response(response.unwrap_or_else(|cancelled| {
+                Response::new_err(
+                    req.id,
+                    ErrorCode::ContentModified as i32,
+                    cancelled.to_string(),
+                )
+            }))
+        });
+
+        self
+    }
+}
+
+/// The response to a request whose handler panicked.
+fn panic_response(
+    id: lsp_server::RequestId,
+    panic_context: &str,
+    payload: Box<dyn std::any::Any + Send>,
+) -> Response {
+    let message = panic_message(&payload);
+    tracing::error!("panic while handling {panic_context}: {message}");
+    Response::new_err(
+        id,
+        ErrorCode::InternalError as i32,
+        format!("server panicked: {message}"),
+    )
+}
+
+fn panic_message(payload: &Box<dyn std::any::Any + Send>) -> &str {
+    payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic")
 }
 
 pub struct NotificationDispatcher<'a> {
@@ -106,7 +240,8 @@ impl NotificationDispatcher<'_> {
         let params = match notification.extract::<N::Params>(N::METHOD) {
             Ok(it) => it,
             Err(ExtractError::JsonError { method, error }) => {
-                panic!("Invalid request: {} {}", method, error)
+                tracing::error!("invalid notification {method}: {error}");
+                return Ok(self);
             }
             Err(ExtractError::MethodMismatch(notification)) => {
                 self.notification = Some(notification);
@@ -114,14 +249,21 @@ impl NotificationDispatcher<'_> {
             }
         };
 
-        f(self.global_state, params)?;
+        let global_state = &mut *self.global_state;
+        match panic::catch_unwind(panic::AssertUnwindSafe(|| f(global_state, params))) {
+            Ok(result) => result?,
+            Err(payload) => {
+                let message = panic_message(&payload);
+                tracing::error!("panic while handling {}: {message}", N::METHOD);
+            }
+        }
         Ok(self)
     }
 }
 fn result_to_response<R>(
     id: lsp_server::RequestId,
     result: anyhow::Result<R::Result>,
-) -> Result<lsp_server::Response, Cancelled>
+) -> Result<lsp_server::Response, ide::Cancelled>
 where
     R: lsp_types::request::Request,
     R::Params: DeserializeOwned,
@@ -131,7 +273,7 @@ where
         Ok(res) => lsp_server::Response::new_ok(id, &res),
         Err(e) => match e.downcast::<LspError>() {
             Ok(lsp_error) => lsp_server::Response::new_err(id, lsp_error.code, lsp_error.message),
-            Err(e) => match e.downcast::<Cancelled>() {
+            Err(e) => match e.downcast::<ide::Cancelled>() {
                 Ok(cancelled) => return Err(cancelled),
                 Err(e) => {
                     let code = ErrorCode::InternalError as i32;
@@ -174,6 +316,133 @@ mod tests {
         assert!(dispatcher.req.is_none());
     }
 
+    #[test]
+    fn test_request_dispatcher_on() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/test").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![],
+        );
+        let mut global_state = GlobalState::new(sender, config);
+        let mut dispatcher = RequestDispatcher {
+            req: Some(LspRequest {
+                id: lsp_server::RequestId::from(1),
+                method: Shutdown::METHOD.to_string(),
+                params: serde_json::Value::Null,
+            }),
+            global_state: &mut global_state,
+        };
+
+        dispatcher.on::<Shutdown>(|_, ()| panic!("oops"));
+        assert!(dispatcher.req.is_none());
+
+        let Task::Response(response) = global_state.task_pool.receiver.recv().unwrap() else {
+            panic!("expected a response");
+        };
+        assert_eq!(response.id, lsp_server::RequestId::from(1));
+        assert_eq!(response.error.unwrap().message, "server panicked: oops");
+    }
+
+    #[test]
+    fn test_request_dispatcher_on_sync_mut() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/test").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![],
+        );
+        let mut global_state = GlobalState::new(sender, config);
+        let req = LspRequest {
+            id: lsp_server::RequestId::from(1),
+            method: Shutdown::METHOD.to_string(),
+            params: serde_json::Value::Null,
+        };
+        global_state.register_request(&req, std::time::Instant::now());
+        let mut dispatcher = RequestDispatcher {
+            req: Some(req),
+            global_state: &mut global_state,
+        };
+
+        dispatcher.on_sync_mut::<Shutdown>(|_, ()| panic!("oops"));
+        assert!(dispatcher.req.is_none());
+
+        let lsp_server::Message::Response(response) = receiver.recv().unwrap() else {
+            panic!("expected a response");
+        };
+        let error = response.error.unwrap();
+        assert_eq!(error.code, ErrorCode::InternalError as i32);
+        assert_eq!(error.message, "server panicked: oops");
+    }
+
+    #[test]
+    fn test_notification_dispatcher_panics() {
+        use lsp_types::notification::{DidSaveTextDocument, Exit};
+
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/test").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![],
+        );
+        let mut global_state = GlobalState::new(sender, config);
+        let mut dispatcher = NotificationDispatcher {
+            notification: Some(Notification {
+                method: "textDocument/didSave".to_string(),
+                params: serde_json::json!({ "textDocument": 1 }),
+            }),
+            global_state: &mut global_state,
+        };
+        dispatcher
+            .on_sync_mut::<DidSaveTextDocument>(|_, _| unreachable!())
+            .unwrap();
+        assert!(dispatcher.notification.is_none());
+
+        dispatcher.notification = Some(Notification {
+            method: "exit".to_string(),
+            params: serde_json::Value::Null,
+        });
+        dispatcher
+            .on_sync_mut::<Exit>(|_, ()| panic!("oops"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cancel_request() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/test").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![],
+        );
+        let mut global_state = GlobalState::new(sender, config);
+        let uri = lsp_types::Url::parse("file:///test/index.cfm").unwrap();
+        global_state.add_changes_into_document(&uri, "<cfset x = 1>".to_string());
+        global_state.process_changes();
+        let req = LspRequest {
+            id: lsp_server::RequestId::from(1),
+            method: "cfml/changeSignature".to_string(),
+            params: serde_json::Value::Null,
+        };
+        global_state.register_request(&req, std::time::Instant::now());
+
+        let snap = global_state.cancellable_snapshot(&req.id, &req.method);
+        let file_id = snap.url_to_file_id(&uri).unwrap();
+        let config = ide::DiagnosticsConfig::default();
+        let diagnostics = || snap.analysis.diagnostics(&config, file_id);
+        assert!(ide::Cancelled::catch(diagnostics).is_ok());
+
+        global_state.cancel(req.id.clone());
+        assert!(ide::Cancelled::catch(diagnostics).is_err());
+        let lsp_server::Message::Response(response) = receiver.recv().unwrap() else {
+            panic!("expected a response");
+        };
+        assert_eq!(
+            response.error.unwrap().code,
+            ErrorCode::RequestCanceled as i32
+        );
+    }
+
     #[test]
     fn test_notification_dispatcher() {
         let (sender, _) = crossbeam_channel::unbounded();
@@ -0,0 +1,90 @@
+//! Glob patterns matched against `/`-separated relative paths, as written
+//! in the settings: `*` and `?` match within a name, and a `**` name matches
+//! any number of directories.
+use std::path::Path;
+
+use virtual_fs::AbsPath;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Glob {
+    segments: Vec<String>,
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Glob {
+        let segments = pattern
+            .trim_matches('/')
+            .split('/')
+            .filter(|it| !it.is_empty())
+            .map(str::to_string)
+            .collect();
+        Glob { segments }
+    }
+
+    /// Whether `path`, relative and `/`-separated, matches the pattern.
+    pub(crate) fn is_match(&self, path: &str) -> bool {
+        let names: Vec<_> = path.split('/').filter(|it| !it.is_empty()).collect();
+        match_segments(&self.segments, &names)
+    }
+}
+
+/// `path` relative to `base`, `/`-separated as globs expect, if it is under
+/// `base`.
+pub(crate) fn relative_path(base: &AbsPath, path: &AbsPath) -> Option<String> {
+    let relative = path.strip_prefix(base)?;
+    let names: Vec<_> = AsRef::<Path>::as_ref(relative)
+        .components()
+        .map(|it| it.as_os_str().to_string_lossy())
+        .collect();
+    Some(names.join("/"))
+}
+
+fn match_segments(segments: &[String], names: &[&str]) -> bool {
+    match segments.split_first() {
+        None => names.is_empty(),
+        Some((segment, rest)) if segment == "**" => {
+            (0..=names.len()).any(|skipped| match_segments(rest, &names[skipped..]))
+        }
+        Some((segment, rest)) => match names.split_first() {
+            Some((name, names)) => {
+                let pattern: Vec<char> = segment.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                match_name(&pattern, &name) && match_segments(rest, names)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Matches a name against a pattern of `*` and `?` wildcards.
+fn match_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skipped| match_name(rest, &name[skipped..])),
+        Some(('?', rest)) => !name.is_empty() && match_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_name(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() {
+        let glob = Glob::new("**/*.min.cfm");
+        assert!(glob.is_match("a.min.cfm"));
+        assert!(glob.is_match("assets/js/a.min.cfm"));
+        assert!(!glob.is_match("a.cfm"));
+
+        let glob = Glob::new("generated/**");
+        assert!(glob.is_match("generated/a/b.cfc"));
+        assert!(glob.is_match("generated"));
+        assert!(!glob.is_match("src/generated/b.cfc"));
+
+        let glob = Glob::new("data/report?.cfm");
+        assert!(glob.is_match("data/report1.cfm"));
+        assert!(!glob.is_match("data/report10.cfm"));
+        assert!(!glob.is_match("data/sub/report1.cfm"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Analyzes the files which aren't open once the editor is idle, so that
+//! opening them later is fast and, if the settings ask for it, their
+//! diagnostics show up front.
+//!
+//! The analysis runs on a single worker and stops as soon as a request or a
+//! change comes in, to start over at the next idle time.
+use std::time::{Duration, Instant};
+
+use ide::{CancellationToken, Cancelled, Diagnostic};
+use virtual_fs::FileId;
+
+use super::{to_proto, GlobalState, Task};
+use crate::task_pool::ThreadIntent;
+
+/// How long the editor stays quiet before the analysis starts.
+const IDLE_DELAY: Duration = Duration::from_millis(500);
+
+/// The diagnostics of the files analyzed, empty unless they are published.
+pub(crate) type BackgroundDiagnostics = Result<Vec<(FileId, Vec<Diagnostic>)>, Cancelled>;
+
+impl GlobalState {
+    /// When the analysis should start, if it is needed and can run: not
+    /// while indexing, nor once shutting down.
+    pub(crate) fn background_analysis_deadline(&self) -> Option<Instant> {
+        let can_run = self.background_analysis.is_none()
+            && self.indexing.is_none()
+            && !self.shutdown_requested;
+        (self.background_analysis_needed && can_run).then(|| self.last_activity + IDLE_DELAY)
+    }
+
+    /// Marks the editor active, stopping the analysis running.
+    pub(crate) fn record_activity(&mut self) {
+        self.last_activity = Instant::now();
+        if let Some(token) = self.background_analysis.take() {
+            token.cancel();
+            self.background_analysis_needed = true;
+        }
+    }
+
+    /// Stops the analysis running, whose results are out of date, for it
+    /// to run again, even over the files it was done with.
+    pub(crate) fn restart_background_analysis(&mut self) {
+        if let Some(token) = self.background_analysis.take() {
+            token.cancel();
+        }
+        self.background_analysis_needed = true;
+    }
+
+    pub(crate) fn start_background_analysis(&mut self) {
+        let token = CancellationToken::default();
+        self.background_analysis = Some(token.clone());
+        self.background_analysis_needed = false;
+        self.background_analysis_generation += 1;
+        let generation = self.background_analysis_generation;
+        let mut snap = self.snapshot();
+        snap.analysis = snap.analysis.with_cancellation(token.clone());
+        let file_ids: Vec<_> = {
+            let vfs = &self.vfs.read().0;
+            vfs.iter()
+                .filter(|(_, path)| !self.mem_docs.contains(path))
+                .map(|(file_id, _)| file_id)
+                .collect()
+        };
+        let publish = self.config.workspace_diagnostics();
+        self.task_pool
+            .handle
+            .spawn(ThreadIntent::Background, move || {
+                let _p = tracing::span!(tracing::Level::INFO, "background_analysis").entered();
+                // The configuration of each file, which depends on its root.
+                let files: Vec<_> = file_ids
+                    .into_iter()
+                    .map(|it| {
+                        (
+                            it,
+                            publish.then(|| snap.diagnostics_config(it)),
+                            snap.is_large_file(it),
+                        )
+                    })
+                    .collect();
+                let analysis = &snap.analysis;
+                let analyze = || {
+                    let mut res = Vec::new();
+                    for (file_id, config, large) in files {
+                        if token.is_cancelled() {
+                            return Err(Cancelled::Requested);
+                        }
+                        if let Some(config) = config {
+                            let diagnostics = if large {
+                                analysis.syntax_diagnostics(&config, file_id)
+                            } else {
+                                analysis.diagnostics(&config, file_id)
+                            };
+                            res.push((file_id, diagnostics));
+                        } else {
+                            // What requests on the file compute first.
+                            analysis.file_index(file_id);
+                            analysis.file_line_index(file_id);
+                        }
+                    }
+                    Ok(res)
+                };
+                let diagnostics = Cancelled::catch(analyze).and_then(|it| it);
+                Task::BackgroundAnalysis {
+                    generation,
+                    diagnostics,
+                }
+            });
+    }
+
+    /// Publishes the diagnostics of the analysis started as `generation`,
+    /// unless it was cancelled, for the files still not open.
+    pub(crate) fn handle_background_analysis(
+        &mut self,
+        generation: u32,
+        diagnostics: BackgroundDiagnostics,
+    ) {
+        if generation != self.background_analysis_generation {
+            return;
+        }
+        self.background_analysis = None;
+        let Ok(diagnostics) = diagnostics else {
+            self.background_analysis_needed = true;
+            return;
+        };
+        let snap = self.snapshot();
+        for (file_id, diagnostics) in diagnostics {
+            let path = self.vfs.read().0.file_path(file_id).clone();
+            if self.mem_docs.contains(&path) {
+                continue;
+            }
+            let Ok(line_index) = snap.file_line_index(file_id) else {
+                continue;
+            };
+            let diagnostics = diagnostics
+                .into_iter()
+                .map(|it| to_proto::diagnostic(&snap, &line_index, it))
+                .collect();
+            self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                lsp_types::PublishDiagnosticsParams {
+                    uri: to_proto::url_from_vfs_path(&path),
+                    diagnostics,
+                    version: None,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::Message;
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_background_analysis() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        config
+            .update(serde_json::json!({ "diagnostics": { "workspace": true } }))
+            .unwrap();
+        let mut state = GlobalState::new(sender, config);
+        state.load_files(vec![
+            (root.join("a.cfm"), b"<cfif>".to_vec()),
+            (root.join("b.cfm"), b"<cfset x = 1>".to_vec()),
+        ]);
+        state.process_changes();
+        assert!(state.background_analysis_deadline().is_some());
+
+        state.start_background_analysis();
+        assert_eq!(state.background_analysis_deadline(), None);
+        let task = state.task_pool.receiver.recv().unwrap();
+        state.handle_task(task);
+        let published: Vec<_> = receiver
+            .try_iter()
+            .map(|it| match it {
+                Message::Notification(it) => {
+                    let params: lsp_types::PublishDiagnosticsParams =
+                        serde_json::from_value(it.params).unwrap();
+                    (params.uri.path().to_string(), params.diagnostics.is_empty())
+                }
+                _ => panic!("expected a notification"),
+            })
+            .collect();
+        assert_eq!(
+            published,
+            [
+                ("/tmp/a.cfm".to_string(), false),
+                ("/tmp/b.cfm".to_string(), true)
+            ]
+        );
+        assert_eq!(state.background_analysis_deadline(), None);
+
+        // A request stops the analysis, which runs again later.
+        state.start_background_analysis();
+        state.record_activity();
+        let task = state.task_pool.receiver.recv().unwrap();
+        state.handle_task(task);
+        assert!(state.background_analysis_deadline().is_some());
+    }
+}
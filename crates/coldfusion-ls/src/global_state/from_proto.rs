@@ -1,5 +1,9 @@
+use ide::{FilePosition, FileRange, TextRange, TextSize};
 use lsp_types::Url;
 use virtual_fs::{AbsPathBuf, VirtualFsPath};
+
+use super::{GlobalStateSnapshot, LineIndex};
+
 pub(crate) fn abs_path(url: &Url) -> anyhow::Result<AbsPathBuf> {
     let path = url
         .to_file_path()
@@ -7,6 +11,53 @@ pub(crate) fn abs_path(url: &Url) -> anyhow::Result<AbsPathBuf> {
     Ok(AbsPathBuf::try_from(path).unwrap())
 }
 
+/// The path of a document. Those which aren't files, like `untitled:`
+/// buffers, get a virtual path made of their URL.
 pub(crate) fn vfs_path(url: &Url) -> anyhow::Result<VirtualFsPath> {
-    abs_path(url).map(|it| VirtualFsPath::from(it))
+    if url.scheme() != "file" {
+        return Ok(VirtualFsPath::new_virtual_path(url.to_string()));
+    }
+    abs_path(url).map(VirtualFsPath::from)
+}
+
+pub(crate) fn offset(
+    line_index: &LineIndex,
+    position: lsp_types::Position,
+) -> anyhow::Result<TextSize> {
+    line_index
+        .offset(position)
+        .ok_or_else(|| anyhow::format_err!("invalid offset {:?}", position))
+}
+
+pub(crate) fn text_range(
+    line_index: &LineIndex,
+    range: lsp_types::Range,
+) -> anyhow::Result<TextRange> {
+    let start = offset(line_index, range.start)?;
+    let end = offset(line_index, range.end)?;
+    match end < start {
+        true => Err(anyhow::format_err!("Invalid Range")),
+        false => Ok(TextRange::new(start, end)),
+    }
+}
+
+pub(crate) fn file_range(
+    snap: &GlobalStateSnapshot,
+    text_document: &lsp_types::TextDocumentIdentifier,
+    range: lsp_types::Range,
+) -> anyhow::Result<FileRange> {
+    let file_id = snap.url_to_file_id(&text_document.uri)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let range = text_range(&line_index, range)?;
+    Ok(FileRange { file_id, range })
+}
+
+pub(crate) fn file_position(
+    snap: &GlobalStateSnapshot,
+    params: &lsp_types::TextDocumentPositionParams,
+) -> anyhow::Result<FilePosition> {
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let line_index = snap.file_line_index(file_id)?;
+    let offset = offset(&line_index, params.position)?;
+    Ok(FilePosition { file_id, offset })
 }
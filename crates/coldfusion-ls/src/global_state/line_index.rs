@@ -1,5 +1,66 @@
+use std::sync::Arc;
+
+use ide::{LineCol, LineColUtf16, TextSize};
 use memchr::memmem;
 
+/// A file's [`ide::LineIndex`] together with the line endings the client
+/// uses for it and the encoding of its positions. The analysis only ever
+/// sees `\n` and byte offsets; positions and edits sent back and forth are
+/// converted here.
+#[derive(Debug, Clone)]
+pub(crate) struct LineIndex {
+    pub(crate) index: Arc<ide::LineIndex>,
+    pub(crate) endings: LineEndings,
+    pub(crate) encoding: PositionEncoding,
+}
+
+/// What the columns of LSP positions count, as agreed with the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PositionEncoding {
+    /// Bytes, like the analysis, so that no conversion is needed.
+    Utf8,
+    /// The LSP default.
+    Utf16,
+}
+
+impl PositionEncoding {
+    pub(crate) fn kind(self) -> lsp_types::PositionEncodingKind {
+        match self {
+            PositionEncoding::Utf8 => lsp_types::PositionEncodingKind::UTF8,
+            PositionEncoding::Utf16 => lsp_types::PositionEncodingKind::UTF16,
+        }
+    }
+}
+
+impl LineIndex {
+    /// The LSP position of `offset`.
+    pub(crate) fn position(&self, offset: TextSize) -> lsp_types::Position {
+        let line_col = self.index.line_col(offset);
+        match self.encoding {
+            PositionEncoding::Utf8 => lsp_types::Position::new(line_col.line, line_col.col),
+            PositionEncoding::Utf16 => {
+                let wide = self.index.to_utf16(line_col);
+                lsp_types::Position::new(wide.line, wide.col)
+            }
+        }
+    }
+
+    /// The offset of an LSP position, or `None` if its line does not exist.
+    pub(crate) fn offset(&self, position: lsp_types::Position) -> Option<TextSize> {
+        let line_col = match self.encoding {
+            PositionEncoding::Utf8 => LineCol {
+                line: position.line,
+                col: position.character,
+            },
+            PositionEncoding::Utf16 => self.index.to_utf8(LineColUtf16 {
+                line: position.line,
+                col: position.character,
+            }),
+        };
+        self.index.offset(line_col)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub(crate) enum LineEndings {
     Dos,
@@ -19,7 +80,12 @@ impl LineEndings {
             let idx = match finder.find(&tail[gap_len..]) {
                 None if crlf_seen => tail.len(),
                 // SAFETY: buf is unchanged and therefore still contains utf8 data
-                None => return (unsafe { String::from_utf8_unchecked(buf) }, LineEndings::Unix),
+                None => {
+                    return (
+                        unsafe { String::from_utf8_unchecked(buf) },
+                        LineEndings::Unix,
+                    )
+                }
                 Some(idx) => {
                     crlf_seen = true;
                     idx + gap_len
@@ -68,6 +134,31 @@ mod tests {
         assert_eq!(res, "a\nb\nc\n\n\n\n");
     }
 
+    #[test]
+    fn encodings() {
+        let text = "é = 1\nx = \"😀\" & y";
+        let line_index = |encoding| LineIndex {
+            index: Arc::new(ide::LineIndex::new(text)),
+            endings: LineEndings::Unix,
+            encoding,
+        };
+        let y = text.find('y').unwrap() as TextSize;
+
+        let utf8 = line_index(PositionEncoding::Utf8);
+        assert_eq!(utf8.position(y), lsp_types::Position::new(1, 13));
+        assert_eq!(utf8.offset(lsp_types::Position::new(1, 13)), Some(y));
+
+        let utf16 = line_index(PositionEncoding::Utf16);
+        assert_eq!(utf16.position(y), lsp_types::Position::new(1, 11));
+        assert_eq!(utf16.offset(lsp_types::Position::new(1, 11)), Some(y));
+        assert_eq!(utf16.offset(lsp_types::Position::new(2, 0)), None);
+        // Between the halves of a surrogate pair, and past the end of a line.
+        let emoji = text.find('😀').unwrap() as TextSize;
+        assert_eq!(utf16.offset(lsp_types::Position::new(1, 6)), Some(emoji));
+        let eol = text.find('\n').unwrap() as TextSize;
+        assert_eq!(utf16.offset(lsp_types::Position::new(0, 100)), Some(eol));
+    }
+
     #[test]
     fn none() {
         let src = "abc";
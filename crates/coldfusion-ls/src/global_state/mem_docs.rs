@@ -9,6 +9,7 @@ pub struct MemDocs {
 }
 
 impl MemDocs {
+    #[allow(dead_code)]
     pub(crate) fn contains(&self, path: &VirtualFsPath) -> bool {
         self.mem_docs.contains_key(path)
     }
@@ -41,11 +42,13 @@ impl MemDocs {
         self.mem_docs.keys()
     }
 
+    #[allow(dead_code)]
     pub(crate) fn take_changes(&mut self) -> bool {
         mem::replace(&mut self.added_or_removed, false)
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub(crate) struct DocumentData {
     pub(crate) version: i32,
@@ -70,12 +73,12 @@ mod tests {
             "test".to_string(),
         )));
         let data = DocumentData::new(0, vec![]);
-        mem_docs.insert(path.clone(), data.clone());
+        mem_docs.insert(path.clone(), data.clone()).unwrap();
         assert!(mem_docs.get(&path).is_some());
-        assert_eq!(mem_docs.take_changes(), true);
-        assert_eq!(mem_docs.take_changes(), false);
-        mem_docs.remove(&path);
+        assert!(mem_docs.take_changes());
+        assert!(!mem_docs.take_changes());
+        mem_docs.remove(&path).unwrap();
         assert!(mem_docs.get(&path).is_none());
-        assert_eq!(mem_docs.take_changes(), true);
+        assert!(mem_docs.take_changes());
     }
 }
@@ -1,36 +1,127 @@
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, Sender};
+use ide::{Analysis, AnalysisHost, CancellationToken};
 use lsp_server::{Message, Request, Response};
 use lsp_types::Url;
 use nohash_hasher::IntMap;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
-use virtual_fs::{FileId, VirtualFS};
+use virtual_fs::{AbsPath, AbsPathBuf, AnchoredPathBuf, Change, FileId, VirtualFS, VirtualFsPath};
 
-use crate::config::Config;
-mod from_proto;
+use crate::{
+    config::Config,
+    index_cache::{self, IndexCache},
+    indexing::IndexingProgress,
+    logger::ClientLog,
+    lsp,
+    task_pool::{TaskPool, ThreadIntent, Typing},
+};
+mod background_analysis;
+pub(crate) mod from_proto;
 mod line_index;
 mod mem_docs;
+mod profile;
+mod progress;
+mod registrations;
+mod request_progress;
+mod status;
+pub(crate) mod to_proto;
+mod watchdog;
 
 type ReqHandler = fn(&mut GlobalState, lsp_server::Response);
 type ReqQueue = lsp_server::ReqQueue<(String, Instant), ReqHandler>;
-pub(crate) use mem_docs::DocumentData;
 pub use mem_docs::MemDocs;
 
-use self::line_index::LineEndings;
+pub(crate) use self::background_analysis::BackgroundDiagnostics;
+pub(crate) use self::line_index::{LineEndings, LineIndex, PositionEncoding};
+pub(crate) use self::profile::Profile;
+pub(crate) use self::progress::{progress_token, Progress};
+use self::watchdog::RunningRequest;
+
+/// The results of work done off the main loop.
+#[derive(Debug)]
+pub(crate) enum Task {
+    Response(lsp_server::Response),
+    /// The progress of the indexing started as `generation`.
+    Indexing {
+        generation: u32,
+        progress: IndexingProgress,
+    },
+    /// The end of the background analysis started as `generation`.
+    BackgroundAnalysis {
+        generation: u32,
+        diagnostics: BackgroundDiagnostics,
+    },
+}
+
+/// A handle to work running elsewhere, with the channel of its results.
+pub(crate) struct Handle<H, C> {
+    pub(crate) handle: H,
+    pub(crate) receiver: C,
+}
 
 pub struct GlobalState {
     sender: Sender<Message>,
     pub config: Arc<Config>,
     req_queue: ReqQueue,
+    /// The requests running on the task pool, with the tokens cancelling
+    /// them.
+    pub(crate) running_requests: FxHashMap<lsp_server::RequestId, RunningRequest>,
     pub shutdown_requested: bool,
     mem_docs: MemDocs,
     vfs: Arc<RwLock<(VirtualFS, IntMap<FileId, LineEndings>)>>,
+    analysis_host: AnalysisHost,
+    pub(crate) task_pool: Handle<TaskPool<Task>, Receiver<Task>>,
+    /// Whether the next diagnostics should be complete, as a document was
+    /// opened or saved, when they otherwise only report syntax errors.
+    pub(crate) full_diagnostics_requested: bool,
+    /// The token stopping the indexing of the workspace, while it runs.
+    pub(crate) indexing: Option<CancellationToken>,
+    /// Counts the indexings started, so that the progress of a cancelled one
+    /// is ignored.
+    pub(crate) indexing_generation: u32,
+    /// The ids of the capabilities registered with the client at runtime.
+    registrations: FxHashSet<String>,
+    /// Why the last settings were invalid, if they were.
+    pub(crate) config_errors: Option<String>,
+    /// Whether the user stopped indexing, leaving files out.
+    pub(crate) indexing_cancelled: bool,
+    /// The open documents too big to get more than syntax errors, as of
+    /// their last diagnostics.
+    pub(crate) large_documents: Vec<VirtualFsPath>,
+    /// The status last sent to the client.
+    last_reported_status: Option<lsp::ext::ServerStatusParams>,
+    /// The indexes kept from the last run and those computed while
+    /// indexing, given to the files read whose text hashes the same.
+    pub(crate) index_cache: IndexCache,
+    /// Where the logs are forwarded to the client, as its trace asks.
+    pub(crate) client_log: Arc<ClientLog>,
+    /// When the last message came from the client, for the background
+    /// analysis to wait for the editor to be idle.
+    last_activity: Instant,
+    /// When the user last typed, for the background tasks to pause.
+    pub(crate) typing: Typing,
+    /// The token stopping the background analysis, while it runs.
+    background_analysis: Option<CancellationToken>,
+    background_analysis_generation: u32,
+    /// Whether files changed since the background analysis last completed.
+    pub(crate) background_analysis_needed: bool,
+    /// The timings of the requests, recorded by the workers too.
+    pub(crate) profile: Arc<Mutex<Profile>>,
+    /// When the timings were last logged.
+    pub(crate) profile_logged: Instant,
+    /// The id of the process of the client, with the channel receiving once
+    /// it is gone.
+    pub(crate) client_process: Option<(u32, Receiver<()>)>,
 }
 
+#[allow(dead_code)]
 pub(crate) struct GlobalStateSnapshot {
     pub(crate) config: Arc<Config>,
     pub(crate) mem_docs: MemDocs,
+    pub(crate) analysis: Analysis,
     vfs: Arc<RwLock<(VirtualFS, IntMap<FileId, LineEndings>)>>,
 }
 
@@ -38,13 +129,46 @@ impl std::panic::UnwindSafe for GlobalStateSnapshot {}
 
 impl GlobalState {
     pub fn new(sender: Sender<Message>, config: Config) -> Self {
+        let mut state = GlobalState::new_without_roots(sender, config);
+        state.update_analysis_roots();
+        state
+    }
+
+    fn new_without_roots(sender: Sender<Message>, config: Config) -> Self {
+        let task_pool = {
+            let (sender, receiver) = crossbeam_channel::unbounded();
+            let threads = std::thread::available_parallelism().map_or(4, usize::from);
+            let handle = TaskPool::new_with_threads(sender, threads);
+            Handle { handle, receiver }
+        };
         GlobalState {
             sender,
             config: Arc::new(config.clone()),
             req_queue: ReqQueue::default(),
+            running_requests: FxHashMap::default(),
             shutdown_requested: false,
             mem_docs: MemDocs::default(),
             vfs: Arc::new(RwLock::new((VirtualFS::default(), IntMap::default()))),
+            analysis_host: AnalysisHost::default(),
+            task_pool,
+            full_diagnostics_requested: false,
+            indexing: None,
+            indexing_generation: 0,
+            registrations: FxHashSet::default(),
+            config_errors: None,
+            indexing_cancelled: false,
+            large_documents: Vec::new(),
+            last_reported_status: None,
+            index_cache: IndexCache::default(),
+            client_log: Arc::default(),
+            last_activity: Instant::now(),
+            typing: Typing::default(),
+            background_analysis: None,
+            background_analysis_generation: 0,
+            background_analysis_needed: false,
+            profile: Arc::default(),
+            profile_logged: Instant::now(),
+            client_process: None,
         }
     }
 
@@ -55,15 +179,84 @@ impl GlobalState {
         );
     }
 
+    /// A snapshot whose queries stop when the request `id` is cancelled, and
+    /// which the watchdog looks at if it runs past the deadline.
+    pub(crate) fn cancellable_snapshot(
+        &mut self,
+        id: &lsp_server::RequestId,
+        method: &str,
+    ) -> GlobalStateSnapshot {
+        let deadline = self.config.watchdog_deadline();
+        let token = self
+            .running_requests
+            .entry(id.clone())
+            .or_insert_with(|| RunningRequest::new(method.to_string(), deadline))
+            .token
+            .clone();
+        let mut snap = self.snapshot();
+        snap.analysis = snap.analysis.with_cancellation(token);
+        snap
+    }
+
     pub(crate) fn snapshot(&self) -> GlobalStateSnapshot {
         GlobalStateSnapshot {
             config: Arc::clone(&self.config),
             mem_docs: self.mem_docs.clone(),
+            analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
         }
     }
 
+    /// Feeds the changes recorded in the VFS to the analysis. Returns `true`
+    /// if anything changed.
+    pub(crate) fn process_changes(&mut self) -> bool {
+        let mut guard = self.vfs.write();
+        let (vfs, line_endings) = &mut *guard;
+        let changes = vfs.take_changes();
+        if changes.is_empty() {
+            return false;
+        }
+        self.background_analysis_needed = true;
+        for file in changes {
+            let text = match file.change {
+                Change::Create(bytes) | Change::Modify(bytes) => String::from_utf8(bytes).ok(),
+                Change::Delete => None,
+            };
+            let text = text.map(|text| {
+                let (text, endings) = LineEndings::normalize(text);
+                line_endings.insert(file.file_id, endings);
+                Arc::from(text)
+            });
+            if text.is_none() {
+                line_endings.remove(&file.file_id);
+            }
+            let path = text.as_ref().map(|_| vfs.file_path(file.file_id).clone());
+            let index = match &text {
+                Some(text) if !self.index_cache.is_empty() => {
+                    self.index_cache.get(&index_cache::text_hash(text)).cloned()
+                }
+                _ => None,
+            };
+            self.analysis_host.set_file_path(file.file_id, path);
+            self.analysis_host.set_file_text(file.file_id, text);
+            if let Some(index) = index {
+                self.analysis_host.set_file_index(file.file_id, index);
+            }
+        }
+        true
+    }
+
     pub(crate) fn respond(&mut self, response: lsp_server::Response) {
+        if let Some(request) = self.running_requests.remove(&response.id) {
+            if request.stuck {
+                let duration = request.started.elapsed();
+                tracing::warn!(
+                    "stuck request {} completed after {duration:.2?}",
+                    request.method
+                );
+            }
+            self.end_request_progress(&response.id, &request);
+        }
         if let Some((method, start)) = self.req_queue.incoming.complete(response.id.clone()) {
             if let Some(e) = &response.error {
                 if e.message.starts_with("server panicked") {
@@ -72,6 +265,7 @@ impl GlobalState {
             }
             let duration = start.elapsed();
             tracing::debug!("handled request {} in {:0.2?}", method, duration);
+            self.profile.lock().record_latency(&method, duration);
             self.send(response.into())
         }
     }
@@ -80,12 +274,98 @@ impl GlobalState {
         self.sender.send(message).unwrap()
     }
 
+    /// Sends a request to the client, calling `handler` with its response.
+    pub(crate) fn send_request<R: lsp_types::request::Request>(
+        &mut self,
+        params: R::Params,
+        handler: ReqHandler,
+    ) {
+        let request = self
+            .req_queue
+            .outgoing
+            .register(R::METHOD.to_string(), params, handler);
+        self.send(request.into());
+    }
+
+    pub(crate) fn send_notification<N: lsp_types::notification::Notification>(
+        &self,
+        params: N::Params,
+    ) {
+        let notification = lsp_server::Notification::new(N::METHOD.to_owned(), params);
+        self.send(notification.into());
+    }
+
+    /// Publishes the diagnostics of every open document. A change to one file
+    /// can affect the others, e.g. the interfaces they implement.
+    ///
+    /// With `diagnostics.onSaveOnly`, only syntax errors are published until
+    /// a document is opened or saved. Large documents only ever get those.
+    pub(crate) fn update_diagnostics(&mut self) {
+        let requested = std::mem::take(&mut self.full_diagnostics_requested);
+        let full = requested || !self.config.diagnostics_on_save_only();
+        let snap = self.snapshot();
+        let mut large_documents = Vec::new();
+        for path in self.mem_docs.iter() {
+            let Some(file_id) = self.vfs.read().0.file_id(path) else {
+                continue;
+            };
+            let Ok(line_index) = snap.file_line_index(file_id) else {
+                continue;
+            };
+            let config = snap.diagnostics_config(file_id);
+            let large = snap.is_large_file(file_id);
+            if large {
+                large_documents.push(path.clone());
+            }
+            let diagnostics = if full && !large {
+                snap.analysis.diagnostics(&config, file_id)
+            } else {
+                snap.analysis.syntax_diagnostics(&config, file_id)
+            };
+            let diagnostics = diagnostics
+                .into_iter()
+                .map(|it| to_proto::diagnostic(&snap, &line_index, it))
+                .collect();
+            let version = self.mem_docs.get(path).map(|it| it.version);
+            self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                lsp_types::PublishDiagnosticsParams {
+                    uri: to_proto::url_from_vfs_path(path),
+                    diagnostics,
+                    version,
+                },
+            );
+        }
+        large_documents.sort();
+        self.large_documents = large_documents;
+    }
+
+    /// Stops the request `request_id`, answering it with `RequestCanceled`
+    /// if it isn't done.
     pub fn cancel(&mut self, request_id: lsp_server::RequestId) {
+        if let Some(request) = self.running_requests.remove(&request_id) {
+            request.token.cancel();
+            self.end_request_progress(&request_id, &request);
+        }
         if let Some(response) = self.req_queue.incoming.cancel(request_id) {
             self.send(response.into());
         }
     }
 
+    /// Cancels the indexing and the requests running on the task pool, so
+    /// that the pool, dropped with the state, joins its threads at once
+    /// rather than once their work is done.
+    pub(crate) fn cancel_tasks(&mut self) {
+        for token in [self.indexing.take(), self.background_analysis.take()]
+            .into_iter()
+            .flatten()
+        {
+            token.cancel();
+        }
+        for (_, request) in self.running_requests.drain() {
+            request.token.cancel();
+        }
+    }
+
     pub(crate) fn complete_request(&mut self, response: Response) {
         let handler = self
             .req_queue
@@ -102,15 +382,19 @@ impl GlobalState {
         text: String,
         version: i32,
     ) -> anyhow::Result<()> {
-        let path = from_proto::vfs_path(uri).unwrap();
+        let path = from_proto::vfs_path(uri)?;
         let data = mem_docs::DocumentData::new(version, text.into_bytes());
-        self.mem_docs.insert(path, data).unwrap();
-        Ok(())
+        // Opened again, the document takes the text and version sent.
+        self.mem_docs
+            .insert(path, data)
+            .map_err(|()| anyhow::format_err!("document already open: {uri}"))
     }
 
+    /// Forgets a closed document, whose contents are read from disk again.
     pub(crate) fn remove_document(&mut self, uri: &Url) -> anyhow::Result<()> {
         let path = from_proto::vfs_path(uri).unwrap();
         self.mem_docs.remove(&path).unwrap();
+        self.vfs.write().0.remove_overlay(path);
         Ok(())
     }
 
@@ -124,9 +408,237 @@ impl GlobalState {
 
     pub(crate) fn add_changes_into_document(&mut self, uri: &Url, text: String) {
         let path = from_proto::vfs_path(uri).unwrap();
-        self.vfs
-            .write()
+        self.vfs.write().0.set_overlay(path, text.into_bytes());
+    }
+
+    /// Reads a file changed outside the editor, e.g. by a `git pull`, or
+    /// forgets it once deleted. Open documents keep the contents the editor
+    /// holds.
+    pub(crate) fn load_file_from_disk(&mut self, uri: &Url) -> anyhow::Result<()> {
+        let path = from_proto::vfs_path(uri)?;
+        if path
+            .as_path()
+            .is_some_and(|it| self.config.files().skips_file(it))
+        {
+            self.vfs.write().0.set_disk_contents(path, None);
+            return Ok(());
+        }
+        self.vfs.write().0.load_from_disk(path);
+        Ok(())
+    }
+
+    /// Changes the roots of the workspace, which the analysis keeps apart.
+    pub(crate) fn set_workspace_roots(&mut self, roots: Vec<AbsPathBuf>) {
+        let mut config = (*self.config).clone();
+        config.set_workspace_roots(roots);
+        self.config = Arc::new(config);
+        self.update_analysis_roots();
+    }
+
+    fn update_analysis_roots(&mut self) {
+        let roots = self
+            .config
+            .workspace_roots()
+            .iter()
+            .cloned()
+            .map(VirtualFsPath::from);
+        self.analysis_host.set_roots(roots.collect());
+    }
+
+    /// Adds files read from disk, but for the open documents.
+    pub(crate) fn load_files(&mut self, files: Vec<(AbsPathBuf, Vec<u8>)>) {
+        let vfs = &mut self.vfs.write().0;
+        for (path, contents) in files {
+            vfs.set_disk_contents(VirtualFsPath::from(path), Some(contents));
+        }
+    }
+
+    /// The file the index of a workspace root is kept in between runs, if it
+    /// is.
+    pub(crate) fn index_cache_file(&self, root: &AbsPath) -> Option<PathBuf> {
+        let dir = self.config.cache_directory()?;
+        Some(index_cache::cache_file(&dir, root))
+    }
+
+    /// Writes the index of every file to disk, in the file of its root, for
+    /// the next run, on a worker thread as it parses the files it has no
+    /// index of yet.
+    pub(crate) fn save_index_cache(&mut self) {
+        let files: Vec<(AbsPathBuf, PathBuf)> = self
+            .config
+            .workspace_roots()
+            .iter()
+            .filter_map(|root| Some((root.clone(), self.index_cache_file(root)?)))
+            .collect();
+        if files.is_empty() {
+            return;
+        }
+        let snapshot = self.snapshot();
+        self.task_pool
+            .handle
+            .spawn_with_sender(ThreadIntent::Background, move |_| {
+                for (root, path) in files {
+                    if let Err(e) = index_cache::save(&path, snapshot.file_indexes(&root)) {
+                        tracing::warn!("failed to write the index cache {}: {e}", path.display());
+                    }
+                }
+            });
+    }
+
+    /// Forgets the files read from disk, keeping the open documents.
+    pub(crate) fn clear_files(&mut self) {
+        let vfs = &mut self.vfs.write().0;
+        let paths: Vec<VirtualFsPath> = vfs.iter().map(|(_, path)| path.clone()).collect();
+        for path in paths {
+            vfs.set_disk_contents(path, None);
+        }
+    }
+
+    /// Forgets the files read from disk under `roots` but under none of the
+    /// workspace roots, keeping the open documents.
+    pub(crate) fn remove_files_under(&mut self, roots: &[AbsPathBuf]) {
+        let vfs = &mut self.vfs.write().0;
+        let paths: Vec<VirtualFsPath> = vfs
+            .iter()
+            .map(|(_, path)| path)
+            .filter(|path| {
+                path.as_path().is_some_and(|path| {
+                    let under = |root: &AbsPathBuf| path.starts_with(root);
+                    roots.iter().any(under) && !self.config.workspace_roots().iter().any(under)
+                })
+            })
+            .cloned()
+            .collect();
+        for path in paths {
+            vfs.set_disk_contents(path, None);
+        }
+    }
+
+    pub(crate) fn update_document(&mut self, uri: &Url, text: String, version: i32) {
+        let path = from_proto::vfs_path(uri).unwrap();
+        if let Some(doc) = self.mem_docs.get_mut(&path) {
+            *doc = mem_docs::DocumentData::new(version, text.clone().into_bytes());
+        }
+        self.add_changes_into_document(uri, text);
+    }
+}
+
+impl GlobalStateSnapshot {
+    /// The index of every file under `root`, by the hash of its text.
+    pub(crate) fn file_indexes(&self, root: &AbsPath) -> Vec<(u64, ide::FileIndex)> {
+        let file_ids: Vec<FileId> = self
+            .vfs
+            .read()
             .0
-            .set_file_contents(path, Some(text.into_bytes()));
+            .iter()
+            .filter(|(_, path)| path.as_path().is_some_and(|it| it.starts_with(root)))
+            .map(|(file_id, _)| file_id)
+            .collect();
+        file_ids
+            .into_iter()
+            .filter_map(|file_id| {
+                let text = self.analysis.file_text(file_id)?;
+                let index = self.analysis.file_index(file_id)?;
+                Some((index_cache::text_hash(&text), index))
+            })
+            .collect()
+    }
+
+    /// The memory held by the VFS and the open documents.
+    pub(crate) fn memory_usage(&self) -> Vec<lsp::ext::MemoryUsageEntry> {
+        let guard = self.vfs.read();
+        let (vfs, line_endings) = &*guard;
+        let (files, path_bytes) = vfs.iter().fold((0, 0), |(count, bytes), (_, path)| {
+            (count + 1, bytes + path.to_string().len())
+        });
+        let documents = &self.mem_docs.mem_docs;
+        vec![
+            lsp::ext::MemoryUsageEntry {
+                name: "vfs paths".to_string(),
+                count: files,
+                bytes: path_bytes,
+            },
+            lsp::ext::MemoryUsageEntry {
+                name: "line endings".to_string(),
+                count: line_endings.len(),
+                bytes: line_endings.len() * std::mem::size_of::<(FileId, LineEndings)>(),
+            },
+            lsp::ext::MemoryUsageEntry {
+                name: "open documents".to_string(),
+                count: documents.len(),
+                bytes: documents.values().map(|it| it.data.len()).sum(),
+            },
+        ]
+    }
+
+    /// The version of the document at `url`, when it is open, which the
+    /// results computed from this snapshot are for.
+    pub(crate) fn url_file_version(&self, url: &Url) -> Option<i32> {
+        let path = from_proto::vfs_path(url).ok()?;
+        Some(self.mem_docs.get(&path)?.version)
+    }
+
+    pub(crate) fn url_to_file_id(&self, url: &Url) -> anyhow::Result<FileId> {
+        let path = from_proto::vfs_path(url)?;
+        let res = self
+            .vfs
+            .read()
+            .0
+            .file_id(&path)
+            .ok_or_else(|| anyhow::format_err!("file not found: {}", path))?;
+        Ok(res)
+    }
+
+    /// The diagnostics configuration of a file, with the server settings of
+    /// its root.
+    pub(crate) fn diagnostics_config(&self, file_id: FileId) -> ide::DiagnosticsConfig {
+        let vfs = &self.vfs.read().0;
+        self.config.diagnostics_for(vfs.file_path(file_id))
+    }
+
+    /// Whether the file is too big to get more than syntax errors.
+    pub(crate) fn is_large_file(&self, file_id: FileId) -> bool {
+        let text = self.analysis.file_text(file_id);
+        text.is_some_and(|it| self.config.is_large_file(&it))
+    }
+
+    pub(crate) fn format_config(&self, file_id: FileId) -> ide::FormatConfig {
+        let vfs = &self.vfs.read().0;
+        self.config.format_for(vfs.file_path(file_id))
+    }
+
+    pub(crate) fn file_id_to_url(&self, id: FileId) -> Url {
+        let vfs = &self.vfs.read().0;
+        to_proto::url_from_vfs_path(vfs.file_path(id))
+    }
+
+    /// The URL of a path relative to the directory of its anchor file.
+    pub(crate) fn anchored_path_to_url(&self, path: &AnchoredPathBuf) -> anyhow::Result<Url> {
+        let vfs = &self.vfs.read().0;
+        let dst = vfs
+            .file_path(path.anchor)
+            .parent()
+            .and_then(|it| it.join(&path.path))
+            .ok_or_else(|| anyhow::format_err!("invalid anchored path: {:?}", path))?;
+        Ok(to_proto::url_from_vfs_path(&dst))
+    }
+
+    pub(crate) fn file_line_index(&self, file_id: FileId) -> anyhow::Result<LineIndex> {
+        let index = self
+            .analysis
+            .file_line_index(file_id)
+            .ok_or_else(|| anyhow::format_err!("no text for file {:?}", file_id))?;
+        let endings = self
+            .vfs
+            .read()
+            .1
+            .get(&file_id)
+            .copied()
+            .unwrap_or(LineEndings::Unix);
+        Ok(LineIndex {
+            index,
+            endings,
+            encoding: self.config.position_encoding(),
+        })
     }
 }
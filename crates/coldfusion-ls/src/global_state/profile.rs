@@ -0,0 +1,157 @@
+//! The timings of the requests and of the main loop, so that reports of
+//! slow requests come with data.
+use std::{fmt::Write, time::Duration};
+
+use rustc_hash::FxHashMap;
+
+#[derive(Debug, Default)]
+pub(crate) struct Profile {
+    requests: FxHashMap<String, RequestTimings>,
+    loop_turns: Timings,
+    /// Whether anything was recorded since the report was last logged.
+    recorded_since_log: bool,
+}
+
+/// Where the time answering a kind of request goes.
+#[derive(Debug, Default)]
+struct RequestTimings {
+    /// From receiving a request to sending its response.
+    latency: Timings,
+    /// From dispatching a request to the task pool to a worker starting it.
+    queue: Timings,
+    /// Running the handler.
+    handler: Timings,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Timings {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timings {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn mean(&self) -> Duration {
+        self.total.checked_div(self.count).unwrap_or_default()
+    }
+}
+
+impl Profile {
+    pub(crate) fn record_latency(&mut self, method: &str, latency: Duration) {
+        self.request(method).latency.record(latency);
+        self.recorded_since_log = true;
+    }
+
+    /// Records a run of the handler of `method`, with the time it waited
+    /// for a worker if it ran on the task pool.
+    pub(crate) fn record_handler(
+        &mut self,
+        method: &str,
+        queue: Option<Duration>,
+        handler: Duration,
+    ) {
+        let timings = self.request(method);
+        if let Some(queue) = queue {
+            timings.queue.record(queue);
+        }
+        timings.handler.record(handler);
+    }
+
+    pub(crate) fn record_loop_turn(&mut self, duration: Duration) {
+        self.loop_turns.record(duration);
+    }
+
+    fn request(&mut self, method: &str) -> &mut RequestTimings {
+        if !self.requests.contains_key(method) {
+            self.requests.insert(method.to_string(), Default::default());
+        }
+        self.requests.get_mut(method).unwrap()
+    }
+
+    /// The report to log, if requests were answered since the last one.
+    pub(crate) fn report_to_log(&mut self) -> Option<String> {
+        if !std::mem::take(&mut self.recorded_since_log) {
+            return None;
+        }
+        Some(self.report())
+    }
+
+    /// A table of the timings, the requests taking the most time in total
+    /// first.
+    pub(crate) fn report(&self) -> String {
+        let mut res = String::new();
+        let turns = &self.loop_turns;
+        let _ = writeln!(
+            res,
+            "main loop: {} turns, mean {:.2?}, max {:.2?}",
+            turns.count,
+            turns.mean(),
+            turns.max
+        );
+        let mut requests: Vec<_> = self.requests.iter().collect();
+        requests.sort_by(|(a_method, a), (b_method, b)| {
+            (b.latency.total, a_method).cmp(&(a.latency.total, b_method))
+        });
+        let _ = writeln!(
+            res,
+            "{:<40} {:>6} {:>10} {:>10} {:>10} {:>10}",
+            "request", "count", "mean", "max", "queue", "handler"
+        );
+        for (method, timings) in requests {
+            // The requests handled on the main loop don't wait for a worker.
+            let queue = match timings.queue.count {
+                0 => "-".to_string(),
+                _ => format!("{:.2?}", timings.queue.mean()),
+            };
+            let _ = writeln!(
+                res,
+                "{:<40} {:>6} {:>10} {:>10} {:>10} {:>10}",
+                method,
+                timings.latency.count,
+                format!("{:.2?}", timings.latency.mean()),
+                format!("{:.2?}", timings.latency.max),
+                queue,
+                format!("{:.2?}", timings.handler.mean()),
+            );
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let ms = Duration::from_millis;
+        let mut profile = Profile::default();
+        assert_eq!(profile.report_to_log(), None);
+
+        profile.record_loop_turn(ms(1));
+        profile.record_loop_turn(ms(3));
+        profile.record_handler("cfml/reloadWorkspace", None, ms(2));
+        profile.record_latency("cfml/reloadWorkspace", ms(2));
+        for latency in [10, 30] {
+            profile.record_handler("textDocument/completion", Some(ms(4)), ms(latency - 5));
+            profile.record_latency("textDocument/completion", ms(latency));
+        }
+
+        let report = profile.report_to_log().unwrap();
+        assert_eq!(profile.report_to_log(), None);
+        assert_eq!(
+            report,
+            "main loop: 2 turns, mean 2.00ms, max 3.00ms
+request                                   count       mean        max      queue    handler
+textDocument/completion                       2    20.00ms    30.00ms     4.00ms    15.00ms
+cfml/reloadWorkspace                          1     2.00ms     2.00ms          -     2.00ms
+"
+        );
+    }
+}
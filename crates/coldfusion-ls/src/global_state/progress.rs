@@ -0,0 +1,82 @@
+//! Reporting the progress of long running work, like indexing, with
+//! `$/progress`.
+use lsp_types::{
+    notification::Progress as ProgressNotification, request::WorkDoneProgressCreate,
+    NumberOrString, ProgressParams, ProgressParamsValue, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+
+use super::GlobalState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Progress {
+    Begin,
+    Report,
+    End,
+}
+
+/// The token of the progress of the work titled `title`.
+pub(crate) fn progress_token(title: &str) -> NumberOrString {
+    NumberOrString::String(format!("cfml/{title}"))
+}
+
+impl GlobalState {
+    /// Reports the progress of the work titled `title`, if the client shows
+    /// it. `fraction` is the part done, between 0 and 1, and `cancellable`
+    /// lets the user cancel the work from the client, which then sends
+    /// `window/workDoneProgress/cancel`.
+    pub(crate) fn report_progress(
+        &mut self,
+        title: &str,
+        state: Progress,
+        message: Option<String>,
+        fraction: Option<f64>,
+        cancellable: bool,
+    ) {
+        let token = progress_token(title);
+        self.send_progress(token, title, state, message, fraction, cancellable);
+    }
+
+    /// Reports progress like [`GlobalState::report_progress`], under
+    /// `token` rather than the one of the title.
+    pub(crate) fn send_progress(
+        &mut self,
+        token: NumberOrString,
+        title: &str,
+        state: Progress,
+        message: Option<String>,
+        fraction: Option<f64>,
+        cancellable: bool,
+    ) {
+        if !self.config.work_done_progress() {
+            return;
+        }
+        let percentage = fraction.map(|it| (it.clamp(0.0, 1.0) * 100.0) as u32);
+        let value = match state {
+            Progress::Begin => {
+                self.send_request::<WorkDoneProgressCreate>(
+                    WorkDoneProgressCreateParams {
+                        token: token.clone(),
+                    },
+                    |_, _| (),
+                );
+                WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                    title: title.to_string(),
+                    cancellable: Some(cancellable),
+                    message,
+                    percentage,
+                })
+            }
+            Progress::Report => WorkDoneProgress::Report(WorkDoneProgressReport {
+                cancellable: Some(cancellable),
+                message,
+                percentage,
+            }),
+            Progress::End => WorkDoneProgress::End(WorkDoneProgressEnd { message }),
+        };
+        self.send_notification::<ProgressNotification>(ProgressParams {
+            token,
+            value: ProgressParamsValue::WorkDone(value),
+        });
+    }
+}
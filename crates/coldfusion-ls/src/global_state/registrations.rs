@@ -0,0 +1,135 @@
+//! Capabilities registered with `client/registerCapability` rather than in
+//! the answer to `initialize`, so that the settings can turn them on and off
+//! without restarting the server.
+use lsp_types::{
+    request::{RegisterCapability, UnregisterCapability},
+    DidChangeWatchedFilesRegistrationOptions, FileSystemWatcher, GlobPattern, Registration,
+    RegistrationParams, Unregistration, UnregistrationParams,
+};
+
+use super::GlobalState;
+use crate::{config::Config, handlers::notifications::INTEGRATION_FILES, indexing};
+
+/// The capabilities the settings enable, among the ones the client can
+/// register at runtime.
+fn wanted_registrations(config: &Config) -> Vec<Registration> {
+    let mut acc = Vec::new();
+    if config.files_watcher() {
+        // Changes made outside the editor, like a `git pull`, to the CFML
+        // files and the configuration of integrations.
+        let watchers = indexing::CFML_EXTENSIONS
+            .iter()
+            .map(|it| format!("**/*.{it}"))
+            .chain(INTEGRATION_FILES.iter().map(|it| format!("**/{it}")))
+            .map(|glob| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(glob),
+                kind: None,
+            })
+            .collect();
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+        acc.push(Registration {
+            id: "workspace/didChangeWatchedFiles".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: Some(serde_json::to_value(options).unwrap()),
+        });
+    }
+    acc
+}
+
+impl GlobalState {
+    /// Registers the capabilities the settings enable, and unregisters the
+    /// ones they disable, leaving the others as they are.
+    pub(crate) fn update_registrations(&mut self) {
+        let wanted = wanted_registrations(&self.config);
+        let unregisterations: Vec<Unregistration> = self
+            .registrations
+            .iter()
+            .filter(|id| !wanted.iter().any(|it| it.id == **id))
+            .map(|id| Unregistration {
+                id: id.clone(),
+                // Ids are named after their method.
+                method: id.clone(),
+            })
+            .collect();
+        let registrations: Vec<Registration> = wanted
+            .into_iter()
+            .filter(|it| !self.registrations.contains(&it.id))
+            .collect();
+        for it in &unregisterations {
+            self.registrations.remove(&it.id);
+        }
+        self.registrations
+            .extend(registrations.iter().map(|it| it.id.clone()));
+
+        if !unregisterations.is_empty() {
+            self.send_request::<UnregisterCapability>(
+                UnregistrationParams { unregisterations },
+                |_, response| {
+                    if let Some(e) = response.error {
+                        tracing::error!("failed to unregister capabilities: {e:?}");
+                    }
+                },
+            );
+        }
+        if !registrations.is_empty() {
+            self.send_request::<RegisterCapability>(
+                RegistrationParams { registrations },
+                |_, response| {
+                    if let Some(e) = response.error {
+                        tracing::error!("failed to register capabilities: {e:?}");
+                    }
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::Message;
+    use lsp_types::request::Request;
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_update_registrations() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let capabilities = lsp_types::ClientCapabilities {
+            workspace: Some(lsp_types::WorkspaceClientCapabilities {
+                did_change_watched_files: Some(
+                    lsp_types::DidChangeWatchedFilesClientCapabilities {
+                        dynamic_registration: Some(true),
+                        relative_pattern_support: None,
+                    },
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        let mut state = GlobalState::new(sender, config.clone());
+        let sent_methods = |state: &mut GlobalState| {
+            state.update_registrations();
+            receiver
+                .try_iter()
+                .map(|it| match it {
+                    Message::Request(it) => it.method,
+                    _ => panic!("expected a request"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sent_methods(&mut state), [RegisterCapability::METHOD]);
+        assert!(sent_methods(&mut state).is_empty());
+
+        let mut config = config;
+        config
+            .update(serde_json::json!({ "files": { "watcher": { "enable": false } } }))
+            .unwrap();
+        state.config = std::sync::Arc::new(config);
+        assert_eq!(sent_methods(&mut state), [UnregisterCapability::METHOD]);
+        assert!(sent_methods(&mut state).is_empty());
+    }
+}
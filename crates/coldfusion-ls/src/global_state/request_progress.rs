@@ -0,0 +1,201 @@
+//! Shows the progress of the requests going through every file, like
+//! changing a signature, so that clients don't look frozen while they run,
+//! with a button cancelling them. The progress shows once a request ran for
+//! a moment, not to flash for the quick ones.
+use std::time::{Duration, Instant};
+
+use lsp_server::RequestId;
+use lsp_types::NumberOrString;
+
+use super::{watchdog::RunningRequest, GlobalState, Progress};
+
+/// How long a request runs before its progress shows.
+const PROGRESS_DELAY: Duration = Duration::from_millis(300);
+/// How often the progress is updated.
+const REPORT_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug)]
+pub(crate) struct RequestProgress {
+    title: &'static str,
+    began: bool,
+    next_report: Instant,
+    /// The files done out of the total, as last sent.
+    reported: Option<(usize, usize)>,
+}
+
+/// The token of the progress of the request `id`.
+pub(crate) fn request_progress_token(id: &RequestId) -> NumberOrString {
+    NumberOrString::String(format!("cfml/request/{id}"))
+}
+
+impl GlobalState {
+    /// Shows the progress of the running request `id` as `title`, if the
+    /// client shows progress.
+    pub(crate) fn track_request_progress(&mut self, id: &RequestId, title: &'static str) {
+        if !self.config.work_done_progress() {
+            return;
+        }
+        if let Some(request) = self.running_requests.get_mut(id) {
+            request.progress = Some(RequestProgress {
+                title,
+                began: false,
+                next_report: request.started + PROGRESS_DELAY,
+                reported: None,
+            });
+        }
+    }
+
+    /// When the progress of a running request should be sent next, if one
+    /// shows it.
+    pub(crate) fn request_progress_deadline(&self) -> Option<Instant> {
+        self.running_requests
+            .values()
+            .filter_map(|it| Some(it.progress.as_ref()?.next_report))
+            .min()
+    }
+
+    /// Begins the progress of the requests running past the delay, and
+    /// updates it as they go through the files.
+    pub(crate) fn report_request_progress(&mut self) {
+        let now = Instant::now();
+        let mut due = Vec::new();
+        for (id, request) in &mut self.running_requests {
+            let Some(progress) = &mut request.progress else {
+                continue;
+            };
+            if progress.next_report > now {
+                continue;
+            }
+            progress.next_report = now + REPORT_INTERVAL;
+            let current = request.token.progress();
+            let state = match progress.began {
+                false => Progress::Begin,
+                true if current != progress.reported => Progress::Report,
+                true => continue,
+            };
+            progress.began = true;
+            progress.reported = current;
+            due.push((request_progress_token(id), progress.title, state, current));
+        }
+        for (token, title, state, current) in due {
+            let message = current.map(|(done, total)| format!("{done}/{total} files"));
+            let fraction = current.map(|(done, total)| done as f64 / total as f64);
+            self.send_progress(token, title, state, message, fraction, true);
+        }
+    }
+
+    /// Ends the progress of the request `id`, done or cancelled, if it
+    /// began.
+    pub(crate) fn end_request_progress(&mut self, id: &RequestId, request: &RunningRequest) {
+        let Some(progress) = &request.progress else {
+            return;
+        };
+        if progress.began {
+            let token = request_progress_token(id);
+            self.send_progress(token, progress.title, Progress::End, None, None, false);
+        }
+    }
+
+    /// The running request whose progress is sent with `token`.
+    pub(crate) fn request_of_progress(&self, token: &NumberOrString) -> Option<RequestId> {
+        self.running_requests
+            .iter()
+            .find(|(id, request)| {
+                request.progress.is_some() && request_progress_token(id) == *token
+            })
+            .map(|(id, _)| id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::Message;
+    use lsp_types::{ProgressParams, ProgressParamsValue, WorkDoneProgress};
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+    use crate::{config::Config, handlers::notifications::handle_work_done_progress_cancel};
+
+    #[test]
+    fn test_request_progress() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let capabilities = serde_json::from_value(serde_json::json!({
+            "window": { "workDoneProgress": true }
+        }))
+        .unwrap();
+        let config = Config::new(root.clone(), capabilities, vec![root.clone()]);
+        let mut state = GlobalState::new(sender, config);
+        state.load_files(vec![
+            (root.join("a.cfm"), b"<cfset a = new User()>".to_vec()),
+            (root.join("User.cfc"), b"component {}".to_vec()),
+        ]);
+        state.process_changes();
+
+        let id = RequestId::from(1);
+        let snap = state.cancellable_snapshot(&id, "cfml/moveComponent");
+        state.track_request_progress(&id, "Moving component");
+        assert!(state.request_progress_deadline().unwrap() > Instant::now());
+        state.report_request_progress();
+        assert!(receiver.try_recv().is_err());
+
+        let progress = |receiver: &crossbeam_channel::Receiver<Message>| {
+            let values: Vec<WorkDoneProgress> = receiver
+                .try_iter()
+                .filter_map(|it| match it {
+                    Message::Notification(it) => {
+                        let params: ProgressParams = serde_json::from_value(it.params).unwrap();
+                        assert_eq!(params.token, request_progress_token(&id));
+                        let ProgressParamsValue::WorkDone(value) = params.value;
+                        Some(value)
+                    }
+                    _ => None,
+                })
+                .collect();
+            values
+        };
+        let make_due = |state: &mut GlobalState| {
+            let request = state.running_requests.get_mut(&id).unwrap();
+            request.progress.as_mut().unwrap().next_report = Instant::now();
+        };
+        make_due(&mut state);
+        state.report_request_progress();
+        let values = progress(&receiver);
+        let [WorkDoneProgress::Begin(begin)] = &values[..] else {
+            panic!("expected the progress to begin: {values:?}");
+        };
+        assert_eq!(begin.title, "Moving component");
+        assert_eq!(begin.cancellable, Some(true));
+
+        // It is updated as the request goes through the files, and only then.
+        let uri = lsp_types::Url::parse("file:///tmp/User.cfc").unwrap();
+        let file_id = snap.url_to_file_id(&uri).unwrap();
+        let new_dir = virtual_fs::VirtualFsPath::from(root.join("models"));
+        snap.analysis.move_component(file_id, &new_dir);
+        make_due(&mut state);
+        state.report_request_progress();
+        let values = progress(&receiver);
+        let [WorkDoneProgress::Report(report)] = &values[..] else {
+            panic!("expected a report: {values:?}");
+        };
+        assert_eq!(report.message.as_deref(), Some("1/2 files"));
+        assert_eq!(report.percentage, Some(50));
+        make_due(&mut state);
+        state.report_request_progress();
+        assert!(progress(&receiver).is_empty());
+
+        // Cancelling the progress cancels the request.
+        let params = lsp_types::WorkDoneProgressCancelParams {
+            token: request_progress_token(&id),
+        };
+        handle_work_done_progress_cancel(&mut state, params).unwrap();
+        let config = ide::DiagnosticsConfig::default();
+        let diagnostics = || snap.analysis.diagnostics(&config, file_id);
+        assert!(ide::Cancelled::catch(diagnostics).is_err());
+        assert!(matches!(
+            &progress(&receiver)[..],
+            [WorkDoneProgress::End(_)]
+        ));
+        assert_eq!(state.request_progress_deadline(), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! The status of the server, sent to clients showing it.
+use std::fmt::Write;
+
+use super::GlobalState;
+use crate::lsp::ext::{Health, ServerState, ServerStatusNotification, ServerStatusParams};
+
+impl GlobalState {
+    pub(crate) fn current_status(&self) -> ServerStatusParams {
+        let state = if self.indexing.is_some() {
+            ServerState::Indexing
+        } else if !self.running_requests.is_empty() {
+            ServerState::Analyzing
+        } else {
+            ServerState::Idle
+        };
+        let (health, message) = if let Some(e) = &self.config_errors {
+            (Health::Warning, Some(format!("Invalid settings: {e}")))
+        } else if self.indexing_cancelled {
+            let message = "Indexing was cancelled: only some files are known";
+            (Health::Warning, Some(message.to_string()))
+        } else if let Some(request) = self.running_requests.values().find(|it| it.stuck) {
+            let deadline = self.config.watchdog_deadline().unwrap_or_default();
+            let message = format!(
+                "Request `{}` has been running for over {}s",
+                request.method,
+                deadline.as_secs()
+            );
+            (Health::Warning, Some(message))
+        } else if !self.large_documents.is_empty() {
+            let paths: Vec<_> = self
+                .large_documents
+                .iter()
+                .map(|it| it.to_string())
+                .collect();
+            let message = format!(
+                "Only syntax errors are reported in large files: {}",
+                paths.join(", ")
+            );
+            (Health::Warning, Some(message))
+        } else {
+            (Health::Ok, None)
+        };
+        ServerStatusParams {
+            health,
+            state,
+            message,
+        }
+    }
+
+    /// Sends the status of the server if it changed since last sent.
+    pub(crate) fn update_status(&mut self) {
+        if !self.config.server_status_notification() {
+            return;
+        }
+        let status = self.current_status();
+        if self.last_reported_status.as_ref() == Some(&status) {
+            return;
+        }
+        self.last_reported_status = Some(status.clone());
+        self.send_notification::<ServerStatusNotification>(status);
+    }
+
+    /// The state of the server in words, then the timings of the requests.
+    pub(crate) fn analyzer_status(&self) -> String {
+        let mut res = String::new();
+        let roots: Vec<_> = self
+            .config
+            .workspace_roots()
+            .iter()
+            .map(|it| it.to_string())
+            .collect();
+        let _ = writeln!(res, "workspace roots: {}", roots.join(", "));
+        let files = self.vfs.read().0.iter().count();
+        let open = self.mem_docs.mem_docs.len();
+        let _ = writeln!(res, "files: {files} ({open} open)");
+        let indexing = if self.indexing.is_some() {
+            "running"
+        } else if self.indexing_cancelled {
+            "cancelled"
+        } else {
+            "done"
+        };
+        let _ = writeln!(res, "indexing: {indexing}");
+        let _ = writeln!(res, "requests running: {}", self.running_requests.len());
+        if let Some(e) = &self.config_errors {
+            let _ = writeln!(res, "invalid settings: {e}");
+        }
+        res.push('\n');
+        res.push_str(&self.profile.lock().report());
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_server::Message;
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_update_status() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let capabilities = lsp_types::ClientCapabilities {
+            experimental: Some(serde_json::json!({ "serverStatusNotification": true })),
+            ..Default::default()
+        };
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        let mut state = GlobalState::new(sender, config);
+        let sent_status = |state: &mut GlobalState| {
+            state.update_status();
+            receiver
+                .try_iter()
+                .map(|it| match it {
+                    Message::Notification(it) => serde_json::from_value(it.params).unwrap(),
+                    _ => panic!("expected a notification"),
+                })
+                .collect::<Vec<ServerStatusParams>>()
+        };
+
+        state.indexing = Some(ide::CancellationToken::default());
+        assert_eq!(
+            sent_status(&mut state),
+            [ServerStatusParams {
+                health: Health::Ok,
+                state: ServerState::Indexing,
+                message: None,
+            }]
+        );
+        assert!(sent_status(&mut state).is_empty());
+
+        state.indexing = None;
+        state.config_errors = Some("invalid value for `engine.version`".to_string());
+        assert_eq!(
+            sent_status(&mut state),
+            [ServerStatusParams {
+                health: Health::Warning,
+                state: ServerState::Idle,
+                message: Some("Invalid settings: invalid value for `engine.version`".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_analyzer_status() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root]);
+        let mut state = GlobalState::new(sender, config);
+        let uri = lsp_types::Url::parse("file:///tmp/index.cfm").unwrap();
+        state
+            .add_document(&uri, "<cfset x = 1>".to_string(), 1)
+            .unwrap();
+        state.add_changes_into_document(&uri, "<cfset x = 1>".to_string());
+        state.process_changes();
+        let req = lsp_server::Request {
+            id: 1.into(),
+            method: "cfml/memoryUsage".to_string(),
+            params: serde_json::Value::Null,
+        };
+        state.register_request(&req, std::time::Instant::now());
+        state.respond(lsp_server::Response::new_ok(req.id, ()));
+
+        let status = state.analyzer_status();
+        assert!(
+            status.starts_with(
+                "workspace roots: /tmp
+files: 1 (1 open)
+indexing: done
+requests running: 0
+
+main loop: 0 turns"
+            ),
+            "{status}"
+        );
+        assert!(status.contains("\ncfml/memoryUsage "), "{status}");
+    }
+}
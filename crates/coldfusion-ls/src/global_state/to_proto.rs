@@ -0,0 +1,213 @@
+use ide::{
+    Assist, AssistKind, Diagnostic, FileRange, FileSystemEdit, Indel, Severity, SourceChange,
+    TextRange, TextSize,
+};
+use lsp_types::Url;
+use virtual_fs::VirtualFsPath;
+
+use super::{GlobalStateSnapshot, LineEndings, LineIndex};
+use crate::lsp::ext as lsp_ext;
+
+pub(crate) fn position(line_index: &LineIndex, offset: TextSize) -> lsp_types::Position {
+    line_index.position(offset)
+}
+
+pub(crate) fn range(line_index: &LineIndex, range: TextRange) -> lsp_types::Range {
+    let start = position(line_index, range.start());
+    let end = position(line_index, range.end());
+    lsp_types::Range::new(start, end)
+}
+
+pub(crate) fn text_edit(line_index: &LineIndex, indel: Indel) -> lsp_types::TextEdit {
+    let range = range(line_index, indel.delete);
+    let new_text = match line_index.endings {
+        LineEndings::Unix => indel.insert,
+        LineEndings::Dos => indel.insert.replace('\n', "\r\n"),
+    };
+    lsp_types::TextEdit { range, new_text }
+}
+
+pub(crate) fn diagnostic_severity(severity: Severity) -> lsp_types::DiagnosticSeverity {
+    match severity {
+        Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+        Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+        Severity::Information => lsp_types::DiagnosticSeverity::INFORMATION,
+        Severity::Hint => lsp_types::DiagnosticSeverity::HINT,
+    }
+}
+
+pub(crate) fn diagnostic(
+    snap: &GlobalStateSnapshot,
+    line_index: &LineIndex,
+    diagnostic: Diagnostic,
+) -> lsp_types::Diagnostic {
+    let mut tags = Vec::new();
+    if diagnostic.unused {
+        tags.push(lsp_types::DiagnosticTag::UNNECESSARY);
+    }
+    if diagnostic.deprecated {
+        tags.push(lsp_types::DiagnosticTag::DEPRECATED);
+    }
+    tags.retain(|it| snap.config.diagnostic_tag(it));
+    let related_information: Vec<lsp_types::DiagnosticRelatedInformation> = diagnostic
+        .related
+        .into_iter()
+        .filter_map(|it| {
+            Some(lsp_types::DiagnosticRelatedInformation {
+                location: location(snap, it.range).ok()?,
+                message: it.message,
+            })
+        })
+        .collect();
+    let mut message = diagnostic.message;
+    let related_information = if snap.config.related_information() {
+        (!related_information.is_empty()).then_some(related_information)
+    } else {
+        // Clients not showing related information get it in the message.
+        for it in &related_information {
+            let file = it
+                .location
+                .uri
+                .path()
+                .rsplit('/')
+                .next()
+                .unwrap_or_default();
+            let line = it.location.range.start.line + 1;
+            message.push_str(&format!("\n{} ({file}:{line})", it.message));
+        }
+        None
+    };
+    let code_description = snap
+        .config
+        .code_description()
+        .then(|| Url::parse(&diagnostic.code.url()).ok())
+        .flatten()
+        .map(|href| lsp_types::CodeDescription { href });
+    lsp_types::Diagnostic {
+        range: range(line_index, diagnostic.range),
+        severity: Some(diagnostic_severity(diagnostic.severity)),
+        code: Some(lsp_types::NumberOrString::String(
+            diagnostic.code.0.to_string(),
+        )),
+        code_description,
+        source: Some("coldfusion-ls".to_string()),
+        message,
+        tags: (!tags.is_empty()).then_some(tags),
+        related_information,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn location(
+    snap: &GlobalStateSnapshot,
+    frange: FileRange,
+) -> anyhow::Result<lsp_types::Location> {
+    let line_index = snap.file_line_index(frange.file_id)?;
+    Ok(lsp_types::Location {
+        uri: snap.file_id_to_url(frange.file_id),
+        range: range(&line_index, frange.range),
+    })
+}
+
+pub(crate) fn url_from_vfs_path(path: &VirtualFsPath) -> Url {
+    match path.as_path() {
+        Some(path) => Url::from_file_path(path).unwrap(),
+        // The URL of a document which isn't a file.
+        None => Url::parse(&path.to_string())
+            .unwrap_or_else(|_| Url::parse(&format!("untitled:{path}")).unwrap()),
+    }
+}
+
+pub(crate) fn snippet_text_edit(
+    line_index: &LineIndex,
+    is_snippet: bool,
+    indel: Indel,
+) -> lsp_ext::SnippetTextEdit {
+    let text_edit = text_edit(line_index, indel);
+    let insert_text_format = is_snippet.then_some(lsp_types::InsertTextFormat::SNIPPET);
+    lsp_ext::SnippetTextEdit {
+        range: text_edit.range,
+        new_text: text_edit.new_text,
+        insert_text_format,
+    }
+}
+
+pub(crate) fn snippet_workspace_edit(
+    snap: &GlobalStateSnapshot,
+    source_change: SourceChange,
+) -> anyhow::Result<lsp_ext::SnippetWorkspaceEdit> {
+    // Without support for snippets, their text is inserted as is.
+    let is_snippet = source_change.is_snippet && snap.config.snippet_text_edit();
+    let mut edits = Vec::new();
+    for (file_id, edit) in source_change.source_file_edits {
+        let line_index = snap.file_line_index(file_id)?;
+        let url = snap.file_id_to_url(file_id);
+        let edit = edit
+            .into_iter()
+            .map(|indel| snippet_text_edit(&line_index, is_snippet, indel))
+            .collect::<Vec<_>>();
+        edits.push((url, edit));
+    }
+    if source_change.file_system_edits.is_empty() && !snap.config.document_changes() {
+        return Ok(lsp_ext::SnippetWorkspaceEdit {
+            changes: Some(edits.into_iter().collect()),
+            document_changes: None,
+        });
+    }
+
+    let mut document_changes: Vec<lsp_ext::SnippetDocumentChangeOperation> = edits
+        .into_iter()
+        .map(|(uri, edits)| {
+            lsp_ext::SnippetDocumentChangeOperation::Edit(lsp_ext::SnippetTextDocumentEdit {
+                text_document: lsp_types::OptionalVersionedTextDocumentIdentifier {
+                    version: snap.url_file_version(&uri),
+                    uri,
+                },
+                edits,
+            })
+        })
+        .collect();
+    for edit in source_change.file_system_edits {
+        let op = match edit {
+            FileSystemEdit::MoveFile { src, dst } => {
+                let old_uri = snap.file_id_to_url(src);
+                let new_uri = snap.anchored_path_to_url(&dst)?;
+                lsp_types::ResourceOp::Rename(lsp_types::RenameFile {
+                    old_uri,
+                    new_uri,
+                    options: None,
+                    annotation_id: None,
+                })
+            }
+        };
+        document_changes.push(lsp_ext::SnippetDocumentChangeOperation::Op(op));
+    }
+    Ok(lsp_ext::SnippetWorkspaceEdit {
+        changes: None,
+        document_changes: Some(document_changes),
+    })
+}
+
+pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
+    match kind {
+        AssistKind::QuickFix => lsp_types::CodeActionKind::QUICKFIX,
+        AssistKind::Refactor => lsp_types::CodeActionKind::REFACTOR,
+        AssistKind::RefactorExtract => lsp_types::CodeActionKind::REFACTOR_EXTRACT,
+        AssistKind::RefactorInline => lsp_types::CodeActionKind::REFACTOR_INLINE,
+        AssistKind::RefactorRewrite => lsp_types::CodeActionKind::REFACTOR_REWRITE,
+        AssistKind::Source => lsp_types::CodeActionKind::SOURCE,
+        AssistKind::OrganizeImports => lsp_types::CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+    }
+}
+
+pub(crate) fn code_action(
+    snap: &GlobalStateSnapshot,
+    assist: Assist,
+) -> anyhow::Result<lsp_ext::CodeAction> {
+    Ok(lsp_ext::CodeAction {
+        title: assist.label,
+        kind: Some(code_action_kind(assist.id.1)),
+        edit: Some(snippet_workspace_edit(snap, assist.source_change)?),
+        command: None,
+    })
+}
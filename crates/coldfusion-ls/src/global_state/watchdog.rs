@@ -0,0 +1,129 @@
+//! Notices the requests running on the task pool past the deadline of the
+//! settings, so that the hangs users report come with the request
+//! responsible: its method is logged and shown in the status, then the
+//! backtrace its handler records the next time it checks for cancellation.
+use std::time::{Duration, Instant};
+
+use ide::CancellationToken;
+
+use super::{request_progress::RequestProgress, GlobalState};
+
+/// How often a stuck request is looked at until its backtrace comes.
+const BACKTRACE_POLL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub(crate) struct RunningRequest {
+    pub(crate) token: CancellationToken,
+    pub(crate) method: String,
+    pub(crate) started: Instant,
+    /// Whether it ran past the deadline.
+    pub(crate) stuck: bool,
+    /// When the watchdog looks at it next: at the deadline, then until its
+    /// backtrace is logged.
+    next_check: Option<Instant>,
+    /// The progress shown for it, for requests going through every file.
+    pub(crate) progress: Option<RequestProgress>,
+}
+
+impl RunningRequest {
+    pub(crate) fn new(method: String, deadline: Option<Duration>) -> RunningRequest {
+        let started = Instant::now();
+        RunningRequest {
+            token: CancellationToken::default(),
+            method,
+            started,
+            stuck: false,
+            next_check: deadline.map(|it| started + it),
+            progress: None,
+        }
+    }
+}
+
+impl GlobalState {
+    /// When a running request should be looked at next, if one should.
+    pub(crate) fn watchdog_deadline(&self) -> Option<Instant> {
+        self.running_requests
+            .values()
+            .filter_map(|it| it.next_check)
+            .min()
+    }
+
+    /// Reports the requests which ran past the deadline, then logs the
+    /// backtraces of their handlers as they come.
+    pub(crate) fn check_running_requests(&mut self) {
+        let now = Instant::now();
+        for (id, request) in &mut self.running_requests {
+            if request.next_check.is_none_or(|it| it > now) {
+                continue;
+            }
+            if !request.stuck {
+                request.stuck = true;
+                request.token.request_backtrace();
+                tracing::warn!(
+                    "request {id} {} has been running for {:.2?}",
+                    request.method,
+                    request.started.elapsed()
+                );
+            }
+            request.next_check = match request.token.take_backtrace() {
+                Some(backtrace) => {
+                    tracing::warn!("backtrace of request {id} {}:\n{backtrace}", request.method);
+                    None
+                }
+                None => Some(now + BACKTRACE_POLL),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+    use crate::{config::Config, lsp::ext::Health};
+
+    #[test]
+    fn test_stuck_request() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        config
+            .update(serde_json::json!({ "watchdog": { "deadline": 1 } }))
+            .unwrap();
+        let mut state = GlobalState::new(sender, config);
+        let uri = lsp_types::Url::parse("file:///tmp/index.cfm").unwrap();
+        state.add_changes_into_document(&uri, "<cfset x = 1>".to_string());
+        state.process_changes();
+        assert_eq!(state.watchdog_deadline(), None);
+
+        let id = lsp_server::RequestId::from(1);
+        let snap = state.cancellable_snapshot(&id, "textDocument/codeAction");
+        let deadline = state.watchdog_deadline().unwrap();
+        assert!(deadline > Instant::now());
+        state.check_running_requests();
+        assert_eq!(state.current_status().health, Health::Ok);
+
+        // Past the deadline, the request is reported and asked where it is.
+        state.running_requests.get_mut(&id).unwrap().next_check = Some(Instant::now());
+        state.check_running_requests();
+        let status = state.current_status();
+        assert_eq!(status.health, Health::Warning);
+        assert_eq!(
+            status.message.as_deref(),
+            Some("Request `textDocument/codeAction` has been running for over 1s")
+        );
+        assert!(state.watchdog_deadline().unwrap() > deadline);
+
+        // The handler records its backtrace as it checks for cancellation.
+        let file_id = snap.url_to_file_id(&uri).unwrap();
+        snap.analysis
+            .diagnostics(&snap.diagnostics_config(file_id), file_id);
+        state.running_requests.get_mut(&id).unwrap().next_check = Some(Instant::now());
+        state.check_running_requests();
+        assert_eq!(state.watchdog_deadline(), None);
+
+        state.respond(lsp_server::Response::new_ok(id, ()));
+        assert_eq!(state.current_status().health, Health::Ok);
+    }
+}
@@ -1,2 +1,2 @@
-pub mod request;
 pub mod notifications;
+pub mod request;
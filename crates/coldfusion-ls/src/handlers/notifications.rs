@@ -1,10 +1,17 @@
+use std::{iter, sync::Arc};
+
 use lsp_types::{
-    CancelParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams,
+    CancelParams, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, SetTraceParams,
+    WorkDoneProgressCancelParams,
 };
 
-use crate::global_state::GlobalState;
-use crate::global_state::DocumentData;
+use crate::{
+    global_state::{from_proto, progress_token, to_proto, GlobalState},
+    indexing,
+};
+use virtual_fs::{AbsPathBuf, VirtualFsPath};
 
 pub(crate) fn handle_cancel(state: &mut GlobalState, params: CancelParams) -> anyhow::Result<()> {
     let id = match params.id {
@@ -15,6 +22,20 @@ pub(crate) fn handle_cancel(state: &mut GlobalState, params: CancelParams) -> an
     Ok(())
 }
 
+pub(crate) fn handle_work_done_progress_cancel(
+    state: &mut GlobalState,
+    params: WorkDoneProgressCancelParams,
+) -> anyhow::Result<()> {
+    if params.token == progress_token(indexing::TITLE) {
+        if let Some(token) = &state.indexing {
+            token.cancel();
+        }
+    } else if let Some(id) = state.request_of_progress(&params.token) {
+        state.cancel(id);
+    }
+    Ok(())
+}
+
 pub(crate) fn handle_did_open_text_document(
     state: &mut GlobalState,
     params: DidOpenTextDocumentParams,
@@ -25,12 +46,15 @@ pub(crate) fn handle_did_open_text_document(
     let text = text_document.text;
     let version = text_document.version;
 
-    let already_exists = state.add_document(&uri.clone(), text.clone(), version).is_err();
+    let already_exists = state
+        .add_document(&uri.clone(), text.clone(), version)
+        .is_err();
     if already_exists {
         tracing::error!("duplicate didOpen notification for {:?}", uri);
     }
 
     state.add_changes_into_document(&uri, text);
+    state.full_diagnostics_requested = true;
     Ok(())
 }
 
@@ -41,7 +65,23 @@ pub(crate) fn handle_did_close_text_document(
     let _p = tracing::span!(tracing::Level::DEBUG, "handle_did_close_text_document").entered();
     let text_document = params.text_document;
     if state.remove_document(&text_document.uri).is_err() {
-        tracing::error!("didClose notification for non-existing file: {:?}", text_document.uri);
+        tracing::error!(
+            "didClose notification for non-existing file: {:?}",
+            text_document.uri
+        );
+    }
+    if state.config.workspace_diagnostics() {
+        // The background analysis keeps the diagnostics of closed files.
+        state.background_analysis_needed = true;
+    } else {
+        // Diagnostics are only kept up to date for open documents.
+        state.send_notification::<lsp_types::notification::PublishDiagnostics>(
+            lsp_types::PublishDiagnosticsParams {
+                uri: text_document.uri,
+                diagnostics: Vec::new(),
+                version: None,
+            },
+        );
     }
 
     Ok(())
@@ -53,13 +93,730 @@ pub(crate) fn handle_did_change_text_document(
 ) -> anyhow::Result<()> {
     let _p = tracing::span!(tracing::Level::DEBUG, "handle_did_change_text_document").entered();
     let text_document = params.text_document;
-    let content_changes = params.content_changes;
     let uri = text_document.uri;
-    let DocumentData { data, version } = if let Some(doc) = state.get_document(&uri) {
-        doc
-    } else {
+    let Some(doc) = state.get_document(&uri) else {
         tracing::error!("didChange notification for non-existing file: {:?}", uri);
         return Ok(());
     };
+    // Versions only increase: an older one was sent before the text held,
+    // which it would overwrite.
+    if text_document.version <= doc.version {
+        tracing::warn!(
+            "ignoring the change of {:?} to version {}, older than version {}",
+            uri,
+            text_document.version,
+            doc.version
+        );
+        return Ok(());
+    }
+    // We only advertise full document sync, so the last change holds the
+    // whole new text.
+    if let Some(change) = params.content_changes.into_iter().last() {
+        state.typing.record();
+        state.update_document(&uri, change.text, text_document.version);
+    }
+    Ok(())
+}
+
+pub(crate) fn handle_did_save_text_document(
+    state: &mut GlobalState,
+    _params: DidSaveTextDocumentParams,
+) -> anyhow::Result<()> {
+    let _p = tracing::span!(tracing::Level::DEBUG, "handle_did_save_text_document").entered();
+    // The contents are synced on change, but the diagnostics may wait for
+    // the save.
+    if state.config.diagnostics_on_save_only() {
+        state.full_diagnostics_requested = true;
+    }
+    Ok(())
+}
+
+pub(crate) fn handle_did_change_configuration(
+    state: &mut GlobalState,
+    params: DidChangeConfigurationParams,
+) -> anyhow::Result<()> {
+    let _p = tracing::span!(tracing::Level::DEBUG, "handle_did_change_configuration").entered();
+    if !state.config.pull_configuration() {
+        // Clients send either our section or every setting.
+        let mut settings = params.settings;
+        if let Some(section) = settings.get_mut("cfml") {
+            settings = section.take();
+        }
+        update_configuration(state, settings, Vec::new());
+        return Ok(());
+    }
+    // The notification may hold nothing: ask for the current settings.
+    fetch_configuration(state);
+    Ok(())
+}
+
+/// Asks the client for the settings of the workspace, then for those of each
+/// workspace folder, which may differ, e.g. to run applications on other
+/// engines.
+pub(crate) fn fetch_configuration(state: &mut GlobalState) {
+    let roots = state.config.workspace_roots();
+    let items = iter::once(None)
+        .chain(roots.iter().map(|it| {
+            let path = VirtualFsPath::from(it.clone());
+            Some(to_proto::url_from_vfs_path(&path))
+        }))
+        .map(|scope_uri| lsp_types::ConfigurationItem {
+            scope_uri,
+            section: Some("cfml".to_string()),
+        })
+        .collect();
+    state.send_request::<lsp_types::request::WorkspaceConfiguration>(
+        lsp_types::ConfigurationParams { items },
+        |state, response| {
+            let lsp_server::Response { error, result, .. } = response;
+            let result = match (error, result) {
+                (Some(e), _) => {
+                    tracing::error!("failed to fetch the configuration: {e:?}");
+                    return;
+                }
+                (None, Some(serde_json::Value::Array(result))) => result,
+                (None, _) => {
+                    tracing::error!("received empty configuration response");
+                    return;
+                }
+            };
+            let mut result = result.into_iter();
+            let Some(settings) = result.next() else {
+                tracing::error!("empty configuration response");
+                return;
+            };
+            // The items asked for follow the roots, unless these changed
+            // since.
+            let roots = state.config.workspace_roots();
+            let folders = if result.len() == roots.len() {
+                roots.iter().cloned().zip(result).collect()
+            } else {
+                Vec::new()
+            };
+            update_configuration(state, settings, folders);
+        },
+    );
+}
+
+/// The client's trace setting changed: it now wants more or fewer logs.
+pub(crate) fn handle_set_trace(
+    state: &mut GlobalState,
+    params: SetTraceParams,
+) -> anyhow::Result<()> {
+    state.client_log.set_trace(params.value);
+    Ok(())
+}
+
+pub(crate) fn handle_did_change_watched_files(
+    state: &mut GlobalState,
+    params: DidChangeWatchedFilesParams,
+) -> anyhow::Result<()> {
+    let _p = tracing::span!(tracing::Level::DEBUG, "handle_did_change_watched_files").entered();
+    let mut integrations_changed = false;
+    for change in params.changes {
+        let name = change.uri.path().rsplit('/').next().unwrap_or_default();
+        if INTEGRATION_FILES.contains(&name) {
+            integrations_changed = true;
+            continue;
+        }
+        if let Err(e) = state.load_file_from_disk(&change.uri) {
+            tracing::error!("failed to reload {}: {e}", change.uri);
+        }
+    }
+    if integrations_changed {
+        let mut config = (*state.config).clone();
+        config.reload_integrations();
+        state.config = Arc::new(config);
+        state.update_diagnostics();
+    }
+    Ok(())
+}
+
+/// Indexes the folders added to the workspace and forgets the files of the
+/// ones removed.
+pub(crate) fn handle_did_change_workspace_folders(
+    state: &mut GlobalState,
+    params: DidChangeWorkspaceFoldersParams,
+) -> anyhow::Result<()> {
+    let _p = tracing::span!(tracing::Level::DEBUG, "handle_did_change_workspace_folders").entered();
+    let roots_of = |folders: Vec<lsp_types::WorkspaceFolder>| {
+        folders
+            .into_iter()
+            .filter_map(|it| from_proto::abs_path(&it.uri).ok())
+            .collect::<Vec<_>>()
+    };
+    let removed = roots_of(params.event.removed);
+    let mut roots = state.config.workspace_roots().to_vec();
+    roots.retain(|it| !removed.contains(it));
+    let added: Vec<_> = roots_of(params.event.added)
+        .into_iter()
+        .filter(|it| !roots.contains(it))
+        .collect();
+    roots.extend(added.iter().cloned());
+    state.set_workspace_roots(roots);
+
+    state.remove_files_under(&removed);
+    if state.config.pull_configuration() {
+        // The new folders may have settings of their own.
+        fetch_configuration(state);
+    }
+    if state.indexing.is_some() {
+        // The indexing running may miss the new roots: start it again.
+        state.start_indexing();
+    } else if !added.is_empty() {
+        state.start_indexing_roots(added);
+    }
+    state.update_diagnostics();
     Ok(())
 }
+
+/// The files configuring the tools the server integrates with, read again
+/// when they change.
+pub(crate) const INTEGRATION_FILES: &[&str] = &[
+    ".cfconfig.json",
+    ".cfformat.json",
+    ".cflintrc",
+    "cflint.xml",
+];
+
+/// Applies new settings, then refreshes what depends on them: requests read
+/// the configuration from their snapshots, and diagnostics are published
+/// again.
+fn update_configuration(
+    state: &mut GlobalState,
+    settings: serde_json::Value,
+    folders: Vec<(AbsPathBuf, serde_json::Value)>,
+) {
+    let mut config = (*state.config).clone();
+    state.config_errors = config
+        .update_settings(settings, folders)
+        .err()
+        .map(|e| e.to_string());
+    if let Some(e) = &state.config_errors {
+        state.send_notification::<lsp_types::notification::ShowMessage>(
+            lsp_types::ShowMessageParams {
+                typ: lsp_types::MessageType::WARNING,
+                message: format!("Some settings are ignored:\n{e}"),
+            },
+        );
+    }
+    let files_changed = config.files() != state.config.files();
+    let engine_changed = config.engines() != state.config.engines();
+    state.config = Arc::new(config);
+    state.update_registrations();
+    if files_changed {
+        // The files excluded are others: read the workspace again.
+        state.clear_files();
+        state.start_indexing();
+    }
+    if engine_changed {
+        // The functions and tags known, and those deprecated, are others:
+        // every diagnostic may be, even with `diagnostics.onSaveOnly`.
+        tracing::info!("the engines changed to {:?}", state.config.engines());
+        state.full_diagnostics_requested = true;
+        state.restart_background_analysis();
+    }
+    state.update_diagnostics();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use lsp_server::Message;
+    use lsp_types::request::Request;
+    use virtual_fs::AbsPathBuf;
+
+    fn state_in(root: &std::path::Path) -> (GlobalState, crossbeam_channel::Receiver<Message>) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from(root.to_str().unwrap()).unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root]);
+        (GlobalState::new(sender, config), receiver)
+    }
+
+    #[test]
+    fn test_untitled_document() {
+        let (mut state, receiver) = state_in(std::path::Path::new("/tmp"));
+        let uri = lsp_types::Url::parse("untitled:Untitled-1").unwrap();
+        let params = DidOpenTextDocumentParams {
+            text_document: lsp_types::TextDocumentItem {
+                uri: uri.clone(),
+                language_id: "cfml".to_string(),
+                version: 1,
+                text: "<cfif>".to_string(),
+            },
+        };
+        handle_did_open_text_document(&mut state, params).unwrap();
+        state.process_changes();
+        state.update_diagnostics();
+        let Ok(Message::Notification(notification)) = receiver.try_recv() else {
+            panic!("expected a notification");
+        };
+        let params: lsp_types::PublishDiagnosticsParams =
+            serde_json::from_value(notification.params).unwrap();
+        assert_eq!(params.uri, uri);
+        assert!(!params.diagnostics.is_empty());
+
+        let file_id = state.snapshot().url_to_file_id(&uri).unwrap();
+        let params = DidCloseTextDocumentParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri },
+        };
+        handle_did_close_text_document(&mut state, params).unwrap();
+        state.process_changes();
+        assert!(state.snapshot().analysis.file_text(file_id).is_none());
+    }
+
+    #[test]
+    fn test_did_change_versions() {
+        let (mut state, receiver) = state_in(std::path::Path::new("/tmp"));
+        let uri = lsp_types::Url::parse("file:///tmp/index.cfm").unwrap();
+        let open = |version| DidOpenTextDocumentParams {
+            text_document: lsp_types::TextDocumentItem {
+                uri: uri.clone(),
+                language_id: "cfml".to_string(),
+                version,
+                text: "<cfset x = 1>".to_string(),
+            },
+        };
+        let change = |version, text: &str| DidChangeTextDocumentParams {
+            text_document: lsp_types::VersionedTextDocumentIdentifier {
+                uri: uri.clone(),
+                version,
+            },
+            content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_string(),
+            }],
+        };
+        handle_did_open_text_document(&mut state, open(1)).unwrap();
+        handle_did_change_text_document(&mut state, change(3, "<cfset x = 3>")).unwrap();
+        handle_did_change_text_document(&mut state, change(2, "<cfset x = 2>")).unwrap();
+        state.process_changes();
+        let file_id = state.snapshot().url_to_file_id(&uri).unwrap();
+        let text = state.snapshot().analysis.file_text(file_id);
+        assert_eq!(text.as_deref(), Some("<cfset x = 3>"));
+        assert_eq!(state.snapshot().url_file_version(&uri), Some(3));
+
+        // Opened again, e.g. after the client lost track, it starts over.
+        handle_did_open_text_document(&mut state, open(1)).unwrap();
+        assert_eq!(state.snapshot().url_file_version(&uri), Some(1));
+
+        state.update_diagnostics();
+        let Ok(Message::Notification(notification)) = receiver.try_recv() else {
+            panic!("expected a notification");
+        };
+        assert_eq!(notification.params["version"], 1);
+    }
+
+    #[test]
+    fn test_did_change_configuration() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root]);
+        let mut state = GlobalState::new(sender, config);
+        let params = DidChangeConfigurationParams {
+            settings: serde_json::json!({ "cfml": { "engine": { "name": "lucee", "version": 6 } } }),
+        };
+        handle_did_change_configuration(&mut state, params).unwrap();
+        assert!(state.config.diagnostics().engine.is_some());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_engine_changed() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        config
+            .update(serde_json::json!({ "diagnostics": { "onSaveOnly": true } }))
+            .unwrap();
+        let mut state = GlobalState::new(sender, config);
+        let uri = lsp_types::Url::parse("file:///tmp/index.cfm").unwrap();
+        let text = r#"<cfloop index="i" from="1" to="3" times="3"></cfloop>"#;
+        let params = DidOpenTextDocumentParams {
+            text_document: lsp_types::TextDocumentItem {
+                uri,
+                language_id: "cfml".to_string(),
+                version: 1,
+                text: text.to_string(),
+            },
+        };
+        handle_did_open_text_document(&mut state, params).unwrap();
+        state.process_changes();
+        state.update_diagnostics();
+        let _ = receiver.try_iter().count();
+        // As if the files were analyzed since.
+        state.background_analysis_needed = false;
+
+        let engine = |name: &str| DidChangeConfigurationParams {
+            settings: serde_json::json!({ "cfml": {
+                "diagnostics": { "onSaveOnly": true },
+                "engine": { "name": name, "version": 2021 },
+            } }),
+        };
+        handle_did_change_configuration(&mut state, engine("adobe")).unwrap();
+        assert!(state.background_analysis_needed);
+        let published: Vec<_> = receiver
+            .try_iter()
+            .filter_map(|it| match it {
+                Message::Notification(it) => {
+                    serde_json::from_value::<lsp_types::PublishDiagnosticsParams>(it.params).ok()
+                }
+                _ => None,
+            })
+            .flat_map(|it| it.diagnostics)
+            .map(|it| it.message)
+            .collect();
+        assert_eq!(
+            published,
+            ["`<cfloop>` has no attribute `times` in Adobe ColdFusion 2021"]
+        );
+    }
+
+    #[test]
+    fn test_pull_configuration() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let capabilities = lsp_types::ClientCapabilities {
+            workspace: Some(lsp_types::WorkspaceClientCapabilities {
+                configuration: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = Config::new(root.clone(), capabilities, vec![root]);
+        let mut state = GlobalState::new(sender, config);
+        let params = DidChangeConfigurationParams {
+            settings: serde_json::Value::Null,
+        };
+        handle_did_change_configuration(&mut state, params).unwrap();
+        let Ok(Message::Request(request)) = receiver.try_recv() else {
+            panic!("expected a request");
+        };
+        assert_eq!(
+            request.method,
+            lsp_types::request::WorkspaceConfiguration::METHOD
+        );
+        assert_eq!(request.params["items"][0]["section"], "cfml");
+        assert!(state.config.diagnostics().engine.is_none());
+
+        state.complete_request(lsp_server::Response::new_ok(
+            request.id,
+            serde_json::json!([{ "engine": { "name": "lucee", "version": 6 } }]),
+        ));
+        assert!(state.config.diagnostics().engine.is_some());
+    }
+
+    #[test]
+    fn test_pull_folder_configuration() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let roots = ["/w/shop", "/w/blog"].map(|it| AbsPathBuf::try_from(it).unwrap());
+        let capabilities = serde_json::from_value(serde_json::json!({
+            "workspace": { "configuration": true }
+        }))
+        .unwrap();
+        let config = Config::new(roots[0].clone(), capabilities, roots.to_vec());
+        let mut state = GlobalState::new(sender, config);
+        fetch_configuration(&mut state);
+        let Ok(Message::Request(request)) = receiver.try_recv() else {
+            panic!("expected a request");
+        };
+        assert_eq!(
+            request.params["items"],
+            serde_json::json!([
+                { "section": "cfml" },
+                { "section": "cfml", "scopeUri": "file:///w/shop" },
+                { "section": "cfml", "scopeUri": "file:///w/blog" },
+            ])
+        );
+
+        state.complete_request(lsp_server::Response::new_ok(
+            request.id,
+            serde_json::json!([
+                { "engine": { "name": "lucee", "version": 6 } },
+                { "engine": { "name": "lucee", "version": 6 } },
+                { "engine": { "name": "adobe", "version": 2021 } },
+            ]),
+        ));
+        let engine = |path: &str| {
+            let path = VirtualFsPath::from(AbsPathBuf::try_from(path).unwrap());
+            state.config.diagnostics_for(&path).engine.map(|it| it.kind)
+        };
+        assert_eq!(engine("/w/shop/index.cfm"), Some(ide::EngineKind::Lucee));
+        assert_eq!(engine("/w/blog/index.cfm"), Some(ide::EngineKind::Adobe));
+    }
+
+    #[test]
+    fn test_did_change_watched_files() {
+        let dir = std::env::temp_dir().join("cfml-watched-files");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("Service.cfc");
+        std::fs::write(&file, "component {}").unwrap();
+        let uri = lsp_types::Url::from_file_path(&file).unwrap();
+        let (mut state, _receiver) = state_in(&dir);
+        let changed = |typ| DidChangeWatchedFilesParams {
+            changes: vec![lsp_types::FileEvent {
+                uri: uri.clone(),
+                typ,
+            }],
+        };
+
+        handle_did_change_watched_files(&mut state, changed(lsp_types::FileChangeType::CREATED))
+            .unwrap();
+        assert!(state.process_changes());
+        let file_id = state.snapshot().url_to_file_id(&uri).unwrap();
+        let text = state.snapshot().analysis.file_text(file_id);
+        assert_eq!(text.as_deref(), Some("component {}"));
+
+        std::fs::remove_file(&file).unwrap();
+        handle_did_change_watched_files(&mut state, changed(lsp_types::FileChangeType::DELETED))
+            .unwrap();
+        assert!(state.process_changes());
+        assert!(state.snapshot().analysis.file_text(file_id).is_none());
+    }
+
+    #[test]
+    fn test_did_change_workspace_folders() {
+        let dir = std::env::temp_dir().join("cfml-workspace-folders");
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+            std::fs::write(dir.join(name).join("Service.cfc"), "component {}").unwrap();
+        }
+        let uri = |name: &str| lsp_types::Url::from_file_path(dir.join(name)).unwrap();
+        let folder = |name: &str| lsp_types::WorkspaceFolder {
+            uri: uri(name),
+            name: name.to_string(),
+        };
+        let (mut state, _receiver) = state_in(&dir.join("a"));
+        let mut config = (*state.config).clone();
+        config
+            .update(serde_json::json!({ "cache": { "enable": false } }))
+            .unwrap();
+        state.config = Arc::new(config);
+        state.load_files(vec![(
+            AbsPathBuf::try_from(dir.join("a/Service.cfc")).unwrap(),
+            b"component {}".to_vec(),
+        )]);
+
+        let params = DidChangeWorkspaceFoldersParams {
+            event: lsp_types::WorkspaceFoldersChangeEvent {
+                added: vec![folder("b")],
+                removed: vec![folder("a")],
+            },
+        };
+        handle_did_change_workspace_folders(&mut state, params).unwrap();
+        let b = AbsPathBuf::try_from(dir.join("b")).unwrap();
+        assert_eq!(state.config.workspace_roots(), [b]);
+        while state.indexing.is_some() {
+            let task = state.task_pool.receiver.recv().unwrap();
+            state.handle_task(task);
+        }
+        state.process_changes();
+        let snap = state.snapshot();
+        assert!(snap.url_to_file_id(&uri("b/Service.cfc")).is_ok());
+        assert!(snap.url_to_file_id(&uri("a/Service.cfc")).is_err());
+    }
+
+    #[test]
+    fn test_close_reverts_to_disk() {
+        let dir = std::env::temp_dir().join("cfml-close-reverts");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("index.cfm");
+        std::fs::write(&file, "<cfset x = 1>").unwrap();
+        let uri = lsp_types::Url::from_file_path(&file).unwrap();
+        let (mut state, _receiver) = state_in(&dir);
+        let text_document = lsp_types::TextDocumentItem {
+            uri: uri.clone(),
+            language_id: "cfml".to_string(),
+            version: 1,
+            text: "<cfset x = 2>".to_string(),
+        };
+        handle_did_open_text_document(&mut state, DidOpenTextDocumentParams { text_document })
+            .unwrap();
+        state.process_changes();
+        let file_id = state.snapshot().url_to_file_id(&uri).unwrap();
+        let text = state.snapshot().analysis.file_text(file_id);
+        assert_eq!(text.as_deref(), Some("<cfset x = 2>"));
+
+        // The unsaved edits are dropped.
+        let params = DidCloseTextDocumentParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri },
+        };
+        handle_did_close_text_document(&mut state, params).unwrap();
+        state.process_changes();
+        let text = state.snapshot().analysis.file_text(file_id);
+        assert_eq!(text.as_deref(), Some("<cfset x = 1>"));
+    }
+
+    #[test]
+    fn test_watched_files_skip_open_documents() {
+        let dir = std::env::temp_dir().join("cfml-watched-open-files");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("index.cfm");
+        std::fs::write(&file, "<cfset x = 1>").unwrap();
+        let uri = lsp_types::Url::from_file_path(&file).unwrap();
+        let (mut state, _receiver) = state_in(&dir);
+        state
+            .add_document(&uri, "<cfset x = 2>".to_string(), 1)
+            .unwrap();
+        state.add_changes_into_document(&uri, "<cfset x = 2>".to_string());
+        state.process_changes();
+
+        let params = DidChangeWatchedFilesParams {
+            changes: vec![lsp_types::FileEvent {
+                uri: uri.clone(),
+                typ: lsp_types::FileChangeType::CHANGED,
+            }],
+        };
+        handle_did_change_watched_files(&mut state, params).unwrap();
+        assert!(!state.process_changes());
+    }
+
+    #[test]
+    fn test_diagnostics_on_save_only() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        config
+            .update(serde_json::json!({ "diagnostics": { "onSaveOnly": true } }))
+            .unwrap();
+        let mut state = GlobalState::new(sender, config);
+        let uri = lsp_types::Url::parse("file:///tmp/app/Service.cfc").unwrap();
+        let published_codes = |state: &mut GlobalState| {
+            state.process_changes();
+            state.update_diagnostics();
+            let Ok(Message::Notification(notification)) = receiver.try_recv() else {
+                panic!("expected diagnostics");
+            };
+            let params: lsp_types::PublishDiagnosticsParams =
+                serde_json::from_value(notification.params).unwrap();
+            params
+                .diagnostics
+                .into_iter()
+                .filter_map(|it| match it.code? {
+                    lsp_types::NumberOrString::String(code) => Some(code),
+                    lsp_types::NumberOrString::Number(_) => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let text = "component { function f() { y = 2; } }";
+        handle_did_open_text_document(
+            &mut state,
+            DidOpenTextDocumentParams {
+                text_document: lsp_types::TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "cfml".to_string(),
+                    version: 1,
+                    text: text.to_string(),
+                },
+            },
+        )
+        .unwrap();
+        assert_eq!(published_codes(&mut state), ["missing-var-scope"]);
+
+        let text = "component { function f() { y = ; } }";
+        handle_did_change_text_document(
+            &mut state,
+            DidChangeTextDocumentParams {
+                text_document: lsp_types::VersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version: 2,
+                },
+                content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: text.to_string(),
+                }],
+            },
+        )
+        .unwrap();
+        assert_eq!(published_codes(&mut state), ["syntax-error"]);
+
+        handle_did_save_text_document(
+            &mut state,
+            DidSaveTextDocumentParams {
+                text_document: lsp_types::TextDocumentIdentifier { uri },
+                text: None,
+            },
+        )
+        .unwrap();
+        assert!(state.full_diagnostics_requested);
+        assert_eq!(
+            published_codes(&mut state),
+            ["missing-var-scope", "syntax-error"]
+        );
+    }
+
+    #[test]
+    fn test_large_file() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        config
+            .update(serde_json::json!({ "largeFile": { "maxLines": 3 } }))
+            .unwrap();
+        let mut state = GlobalState::new(sender, config);
+        let uri = lsp_types::Url::parse("file:///tmp/Service.cfc").unwrap();
+        let published_count = |state: &mut GlobalState, text: &str, version| {
+            let params = DidChangeTextDocumentParams {
+                text_document: lsp_types::VersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version,
+                },
+                content_changes: vec![lsp_types::TextDocumentContentChangeEvent {
+                    range: None,
+                    range_length: None,
+                    text: text.to_string(),
+                }],
+            };
+            handle_did_change_text_document(state, params).unwrap();
+            state.process_changes();
+            state.update_diagnostics();
+            let params: lsp_types::PublishDiagnosticsParams = receiver
+                .try_iter()
+                .find_map(|it| match it {
+                    Message::Notification(it) => serde_json::from_value(it.params).ok(),
+                    _ => None,
+                })
+                .unwrap();
+            params.diagnostics.len()
+        };
+        state.add_document(&uri, String::new(), 1).unwrap();
+
+        let text = "component {\n    function f() { y = 2; }\n}";
+        assert_eq!(published_count(&mut state, text, 2), 1);
+        assert_eq!(state.current_status().message, None);
+
+        let text = "component {\n    function f() { y = 2; }\n\n\n}";
+        assert_eq!(published_count(&mut state, text, 3), 0);
+        let status = state.current_status();
+        assert_eq!(status.health, crate::lsp::ext::Health::Warning);
+        assert_eq!(
+            status.message.as_deref(),
+            Some("Only syntax errors are reported in large files: /tmp/Service.cfc")
+        );
+
+        let text = "component {\n    function f() { y = ; }\n\n\n}";
+        assert_eq!(published_count(&mut state, text, 4), 1);
+    }
+
+    #[test]
+    fn test_cancel_indexing() {
+        let (mut state, _receiver) = state_in(&std::env::temp_dir());
+        let token = ide::CancellationToken::default();
+        state.indexing = Some(token.clone());
+        let params = WorkDoneProgressCancelParams {
+            token: lsp_types::NumberOrString::String("cfml/Other".to_string()),
+        };
+        handle_work_done_progress_cancel(&mut state, params).unwrap();
+        assert!(!token.is_cancelled());
+        let params = WorkDoneProgressCancelParams {
+            token: progress_token(indexing::TITLE),
+        };
+        handle_work_done_progress_cancel(&mut state, params).unwrap();
+        assert!(token.is_cancelled());
+    }
+}
@@ -1,8 +1,13 @@
-use crate::global_state::GlobalState;
-use lsp_types::{CompletionItemKind, CompletionParams};
+use std::sync::Arc;
+
+use crate::{
+    global_state::{from_proto, to_proto, GlobalState, GlobalStateSnapshot},
+    lsp::ext as lsp_ext,
+};
+use lsp_types::{CodeActionParams, CompletionItemKind, CompletionParams};
 
 pub fn handle_completion(
-    _snap: &mut GlobalState,
+    _snap: GlobalStateSnapshot,
     _params: CompletionParams,
 ) -> anyhow::Result<Option<lsp_types::CompletionResponse>> {
     let completion_list = lsp_types::CompletionList {
@@ -17,10 +22,217 @@ pub fn handle_completion(
     Ok(Some(completion_list.into()))
 }
 
+pub(crate) fn handle_code_action(
+    snap: GlobalStateSnapshot,
+    params: CodeActionParams,
+) -> anyhow::Result<Option<Vec<lsp_ext::CodeAction>>> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_code_action").entered();
+    let frange = from_proto::file_range(&snap, &params.text_document, params.range)?;
+    let requested_kinds = params.context.only;
+
+    let mut res = Vec::new();
+    // Fixes of the diagnostics in range come first.
+    let config = snap.diagnostics_config(frange.file_id);
+    let diagnostics = if snap.is_large_file(frange.file_id) {
+        snap.analysis.syntax_diagnostics(&config, frange.file_id)
+    } else {
+        snap.analysis.diagnostics(&config, frange.file_id)
+    };
+    let fixes: Vec<ide::Assist> = diagnostics
+        .into_iter()
+        .filter(|it| it.range.intersect(frange.range).is_some())
+        .flat_map(|it| it.fixes)
+        .collect();
+    let assist_config = snap.config.assist();
+    // Assists which a fix already offers, e.g. `add_var_scope`, are skipped.
+    let assists = snap
+        .analysis
+        .assists(&assist_config, frange)
+        .into_iter()
+        .filter(|assist| {
+            !fixes
+                .iter()
+                .any(|it| it.id == assist.id && it.target == assist.target)
+        })
+        .collect::<Vec<_>>();
+    for assist in fixes.into_iter().chain(assists) {
+        if !assist.source_change.file_system_edits.is_empty() && !snap.config.rename_files() {
+            continue;
+        }
+        let code_action = to_proto::code_action(&snap, assist)?;
+        let kind_allowed = match (&requested_kinds, &code_action.kind) {
+            (Some(only), Some(kind)) => {
+                only.iter().any(|it| kind.as_str().starts_with(it.as_str()))
+            }
+            _ => true,
+        };
+        if kind_allowed {
+            res.push(code_action);
+        }
+    }
+
+    // The client asks for the folder, then sends `cfml/moveComponent`, whose
+    // edit renames the file.
+    let refactor_allowed = snap.config.rename_files()
+        && requested_kinds.as_ref().is_none_or(|only| {
+            only.iter().any(|it| {
+                lsp_types::CodeActionKind::REFACTOR
+                    .as_str()
+                    .starts_with(it.as_str())
+            })
+        });
+    if refactor_allowed && snap.analysis.can_move_component(frange) {
+        let title = "Move to folder…".to_string();
+        res.push(lsp_ext::CodeAction {
+            title: title.clone(),
+            kind: Some(lsp_types::CodeActionKind::REFACTOR),
+            edit: None,
+            command: Some(lsp_types::Command {
+                title,
+                command: "cfml.moveComponent".to_string(),
+                arguments: Some(vec![serde_json::to_value(&params.text_document.uri)?]),
+            }),
+        });
+    }
+    Ok(Some(res))
+}
+
+/// Formats with the settings of the server rather than the options of the
+/// request, so that the `format` command formats the same way.
+pub(crate) fn handle_formatting(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::DocumentFormattingParams,
+) -> anyhow::Result<Option<Vec<lsp_types::TextEdit>>> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_formatting").entered();
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let Some(edit) = snap.analysis.format(&snap.format_config(file_id), file_id) else {
+        return Ok(None);
+    };
+    let line_index = snap.file_line_index(file_id)?;
+    let edits = edit
+        .into_iter()
+        .map(|it| to_proto::text_edit(&line_index, it))
+        .collect();
+    Ok(Some(edits))
+}
+
+pub(crate) fn handle_move_component(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::MoveComponentParams,
+) -> anyhow::Result<Option<lsp_ext::SnippetWorkspaceEdit>> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_move_component").entered();
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let new_dir = from_proto::vfs_path(&params.new_folder)?;
+    match snap.analysis.move_component(file_id, &new_dir) {
+        Some(change) => Ok(Some(to_proto::snippet_workspace_edit(&snap, change)?)),
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn handle_change_signature(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::ChangeSignatureParams,
+) -> anyhow::Result<Option<lsp_ext::ChangeSignatureResult>> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_change_signature").entered();
+    let position = from_proto::file_position(&snap, &params.text_document_position)?;
+    let params: Vec<ide::ParamChange> = params
+        .parameters
+        .into_iter()
+        .map(|it| ide::ParamChange {
+            old_index: it.old_index,
+            name: it.name,
+            ty: it.ty,
+            default: it.default_value,
+            value: it.call_value,
+        })
+        .collect();
+    let Some(change) = snap.analysis.change_signature(position, &params) else {
+        return Ok(None);
+    };
+    let unresolved_calls = change
+        .unresolved_calls
+        .into_iter()
+        .map(|it| to_proto::location(&snap, it))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Some(lsp_ext::ChangeSignatureResult {
+        edit: to_proto::snippet_workspace_edit(&snap, change.source_change)?,
+        unresolved_calls,
+    }))
+}
+
+pub(crate) fn handle_expand_includes(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::ExpandIncludesParams,
+) -> anyhow::Result<Option<lsp_ext::ExpandIncludesResult>> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_expand_includes").entered();
+    let file_id = snap.url_to_file_id(&params.text_document.uri)?;
+    let Some(expanded) = snap.analysis.expand_includes(file_id) else {
+        return Ok(None);
+    };
+    let unresolved = expanded
+        .unresolved
+        .into_iter()
+        .map(|it| {
+            Ok(lsp_ext::UnresolvedInclude {
+                template: it.template,
+                location: to_proto::location(&snap, it.range)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Some(lsp_ext::ExpandIncludesResult {
+        files: expanded
+            .files
+            .into_iter()
+            .map(|it| snap.file_id_to_url(it))
+            .collect(),
+        unresolved,
+    }))
+}
+
+pub(crate) fn handle_memory_usage(
+    snap: GlobalStateSnapshot,
+    _params: (),
+) -> anyhow::Result<Vec<lsp_ext::MemoryUsageEntry>> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_memory_usage").entered();
+    let mut res = snap.memory_usage();
+    res.extend(
+        snap.analysis
+            .memory_usage()
+            .into_iter()
+            .map(|it| lsp_ext::MemoryUsageEntry {
+                name: it.name.to_string(),
+                count: it.count,
+                bytes: it.bytes,
+            }),
+    );
+    Ok(res)
+}
+
+pub(crate) fn handle_analyzer_status(
+    state: &mut GlobalState,
+    _params: (),
+) -> anyhow::Result<String> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_analyzer_status").entered();
+    Ok(state.analyzer_status())
+}
+
+/// Indexing runs again in the background: the request returns as soon as it
+/// starts.
+pub(crate) fn handle_reload_workspace(state: &mut GlobalState, _params: ()) -> anyhow::Result<()> {
+    let _p = tracing::span!(tracing::Level::INFO, "handle_reload_workspace").entered();
+    let mut config = (*state.config).clone();
+    config.reload_integrations();
+    state.config = Arc::new(config);
+    state.clear_files();
+    state.start_indexing();
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::Config;
+    use lsp_server::Message;
     use lsp_types::CompletionParams;
     use lsp_types::Position;
     use lsp_types::TextDocumentIdentifier;
@@ -36,7 +248,7 @@ mod tests {
         let capabilities = lsp_types::ClientCapabilities::default();
         let workspace_roots = vec![AbsPathBuf::try_from("/tmp").unwrap()];
         let config = Config::new(root_path, capabilities, workspace_roots);
-        let mut snap = GlobalState::new(sender, config);
+        let state = GlobalState::new(sender, config);
         let params = CompletionParams {
             text_document_position: TextDocumentPositionParams {
                 text_document: TextDocumentIdentifier {
@@ -55,7 +267,423 @@ mod tests {
                 partial_result_token: None,
             },
         };
-        let result = handle_completion(&mut snap, params);
+        let result = handle_completion(state.snapshot(), params);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_handle_formatting() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let uri = Url::parse("file:///tmp/test.cfc").unwrap();
+        let text = "component {\r\n\tx = 1;  \r\n}";
+        state.add_document(&uri, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&uri, text.to_string());
+        state.process_changes();
+
+        let params = lsp_types::DocumentFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            options: Default::default(),
+            work_done_progress_params: Default::default(),
+        };
+        let edits = handle_formatting(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        let edit = |start: (u32, u32), end: (u32, u32), new_text: &str| lsp_types::TextEdit {
+            range: lsp_types::Range::new(
+                Position::new(start.0, start.1),
+                Position::new(end.0, end.1),
+            ),
+            new_text: new_text.to_string(),
+        };
+        assert_eq!(
+            edits,
+            [
+                edit((1, 0), (1, 1), "    "),
+                edit((1, 7), (1, 9), ""),
+                edit((2, 1), (2, 1), "\r\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_code_action() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let uri = Url::parse("file:///tmp/test.cfc").unwrap();
+        let text = "component {\r\n  function f() {\r\n    x = 1;\r\n  }\r\n}";
+        state.add_document(&uri, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&uri, text.to_string());
+        state.process_changes();
+
+        let params = lsp_types::CodeActionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            range: lsp_types::Range::new(Position::new(2, 4), Position::new(2, 4)),
+            context: lsp_types::CodeActionContext {
+                only: Some(vec![lsp_types::CodeActionKind::QUICKFIX]),
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = handle_code_action(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        let action = &actions[0];
+        assert_eq!(action.title, "Declare `x` with `var`");
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(
+            edits,
+            &vec![lsp_ext::SnippetTextEdit {
+                range: lsp_types::Range::new(Position::new(2, 4), Position::new(2, 4)),
+                new_text: "var ".to_string(),
+                insert_text_format: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_handle_code_action_with_fixes() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let interface = Url::parse("file:///tmp/INamed.cfc").unwrap();
+        state.add_changes_into_document(&interface, "interface { function getName(); }".into());
+        let uri = Url::parse("file:///tmp/User.cfc").unwrap();
+        let text = "component implements=\"INamed\" {\n}";
+        state.add_document(&uri, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&uri, text.to_string());
+        state.process_changes();
+
+        state.update_diagnostics();
+        let Message::Notification(notification) = receiver.try_recv().unwrap() else {
+            panic!("expected a notification");
+        };
+        let params: lsp_types::PublishDiagnosticsParams =
+            serde_json::from_value(notification.params).unwrap();
+        assert_eq!(params.uri, uri);
+        assert_eq!(params.version, Some(1));
+        assert_eq!(
+            params.diagnostics[0].message,
+            "Missing implementation of `getName` from interface `INamed`"
+        );
+
+        let params = lsp_types::CodeActionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            range: lsp_types::Range::new(Position::new(0, 12), Position::new(0, 12)),
+            context: lsp_types::CodeActionContext::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = handle_code_action(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(actions[0].title, "Implement missing members of `INamed`");
+        assert_eq!(actions[0].kind, Some(lsp_types::CodeActionKind::QUICKFIX));
+    }
+
+    #[test]
+    fn test_handle_code_action_moving_file() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let capabilities = serde_json::from_value(serde_json::json!({
+            "workspace": { "workspaceEdit": {
+                "documentChanges": true,
+                "resourceOperations": ["rename"]
+            } }
+        }))
+        .unwrap();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            capabilities,
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let uri = Url::parse("file:///tmp/model/User.cfc").unwrap();
+        let text = "component name=\"models.User\" {}";
+        state.add_document(&uri, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&uri, text.to_string());
+        state.process_changes();
+
+        let params = lsp_types::CodeActionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            range: lsp_types::Range::new(Position::new(0, 12), Position::new(0, 12)),
+            context: lsp_types::CodeActionContext::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = handle_code_action(state.snapshot(), params.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(actions[0].title, "Move file to `models/User.cfc`");
+        let edit = actions[0].edit.as_ref().unwrap();
+        assert_eq!(
+            edit.document_changes,
+            Some(vec![lsp_ext::SnippetDocumentChangeOperation::Op(
+                lsp_types::ResourceOp::Rename(lsp_types::RenameFile {
+                    old_uri: uri,
+                    new_uri: Url::parse("file:///tmp/models/User.cfc").unwrap(),
+                    options: None,
+                    annotation_id: None,
+                })
+            )])
+        );
+
+        // Clients which can't rename files aren't offered to.
+        let mut snap = state.snapshot();
+        snap.config = Arc::new(Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            Default::default(),
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        ));
+        let actions = handle_code_action(snap, params).unwrap().unwrap();
+        assert!(actions.iter().all(|it| !it.title.starts_with("Move")));
+    }
+
+    #[test]
+    fn test_handle_change_signature() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let uri = Url::parse("file:///tmp/Service.cfc").unwrap();
+        let text = "component {\n  function f(a, b) {}\n  function g() { f(1, 2); x.f(3); }\n}";
+        state.add_document(&uri, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&uri, text.to_string());
+        state.process_changes();
+
+        let params: lsp_ext::ChangeSignatureParams = serde_json::from_value(serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 1, "character": 12 },
+            "parameters": [{ "oldIndex": 1, "name": "b" }, { "oldIndex": 0, "name": "a" }],
+        }))
+        .unwrap();
+        let result = handle_change_signature(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        let edits = &result.edit.changes.unwrap()[&uri];
+        let new_texts: Vec<&str> = edits.iter().map(|it| it.new_text.as_str()).collect();
+        assert_eq!(new_texts, ["b, a", "2, 1"]);
+        assert_eq!(
+            result.unresolved_calls,
+            vec![lsp_types::Location {
+                uri,
+                range: lsp_types::Range::new(Position::new(2, 26), Position::new(2, 32)),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_handle_move_component() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let capabilities = serde_json::from_value(serde_json::json!({
+            "workspace": { "workspaceEdit": {
+                "documentChanges": true,
+                "resourceOperations": ["rename"]
+            } }
+        }))
+        .unwrap();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            capabilities,
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let uri = Url::parse("file:///tmp/models/User.cfc").unwrap();
+        state.add_changes_into_document(&uri, "component {}".to_string());
+        let main = Url::parse("file:///tmp/Main.cfc").unwrap();
+        let text = "component { x = new models.User(); }";
+        state.add_document(&main, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&main, text.to_string());
+        state.process_changes();
+
+        let params = lsp_types::CodeActionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            range: lsp_types::Range::new(Position::new(0, 2), Position::new(0, 2)),
+            context: lsp_types::CodeActionContext::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = handle_code_action(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        let action = actions.last().unwrap();
+        assert_eq!(action.title, "Move to folder…");
+        assert_eq!(
+            action.command.as_ref().unwrap().command,
+            "cfml.moveComponent"
+        );
+
+        let params = lsp_ext::MoveComponentParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            new_folder: Url::parse("file:///tmp/domain").unwrap(),
+        };
+        let edit = handle_move_component(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        let changes = edit.document_changes.unwrap();
+        assert_eq!(
+            changes[0],
+            lsp_ext::SnippetDocumentChangeOperation::Edit(lsp_ext::SnippetTextDocumentEdit {
+                text_document: lsp_types::OptionalVersionedTextDocumentIdentifier {
+                    uri: main,
+                    version: Some(1),
+                },
+                edits: vec![lsp_ext::SnippetTextEdit {
+                    range: lsp_types::Range::new(Position::new(0, 20), Position::new(0, 31)),
+                    new_text: "domain.User".to_string(),
+                    insert_text_format: None,
+                }],
+            })
+        );
+        assert_eq!(
+            changes[1],
+            lsp_ext::SnippetDocumentChangeOperation::Op(lsp_types::ResourceOp::Rename(
+                lsp_types::RenameFile {
+                    old_uri: uri,
+                    new_uri: Url::parse("file:///tmp/domain/User.cfc").unwrap(),
+                    options: None,
+                    annotation_id: None,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_handle_expand_includes() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp").unwrap();
+        let config = Config::new(
+            root.clone(),
+            lsp_types::ClientCapabilities::default(),
+            vec![root.clone()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        state.load_files(vec![
+            (
+                root.join("index.cfm"),
+                b"<cfinclude template=\"vars.cfm\">\n<cfinclude template=\"nav.cfm\">".to_vec(),
+            ),
+            (
+                root.join("vars.cfm"),
+                b"<cfset user = session.user>".to_vec(),
+            ),
+        ]);
+        state.process_changes();
+
+        let uri = Url::parse("file:///tmp/index.cfm").unwrap();
+        let params = lsp_ext::ExpandIncludesParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+        };
+        let result = handle_expand_includes(state.snapshot(), params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result,
+            lsp_ext::ExpandIncludesResult {
+                files: vec![uri.clone(), Url::parse("file:///tmp/vars.cfm").unwrap()],
+                unresolved: vec![lsp_ext::UnresolvedInclude {
+                    template: "nav.cfm".to_string(),
+                    location: lsp_types::Location {
+                        uri,
+                        range: lsp_types::Range::new(Position::new(1, 21), Position::new(1, 28)),
+                    },
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn test_handle_memory_usage() {
+        let (sender, _) = crossbeam_channel::unbounded();
+        let config = Config::new(
+            AbsPathBuf::try_from("/tmp").unwrap(),
+            lsp_types::ClientCapabilities::default(),
+            vec![AbsPathBuf::try_from("/tmp").unwrap()],
+        );
+        let mut state = GlobalState::new(sender, config);
+        let uri = Url::parse("file:///tmp/index.cfm").unwrap();
+        let text = "<cfset x = 1>";
+        state.add_document(&uri, text.to_string(), 1).unwrap();
+        state.add_changes_into_document(&uri, text.to_string());
+        state.process_changes();
+
+        let usage = handle_memory_usage(state.snapshot(), ()).unwrap();
+        let line_endings_size =
+            std::mem::size_of::<(virtual_fs::FileId, crate::global_state::LineEndings)>();
+        let counts: Vec<(&str, usize, usize)> = usage
+            .iter()
+            .map(|it| (it.name.as_str(), it.count, it.bytes))
+            .collect();
+        assert_eq!(
+            counts[..],
+            [
+                ("vfs paths", 1, 14),
+                ("line endings", 1, line_endings_size),
+                ("open documents", 1, 13),
+                ("file texts", 1, 13),
+                ("parse trees", 0, 0),
+                ("file paths", 1, 14),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_handle_reload_workspace() {
+        let root = std::env::temp_dir().join("cfml-reload-workspace");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("Service.cfc"), "component {}").unwrap();
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from(root).unwrap();
+        let mut config = Config::new(
+            root.clone(),
+            lsp_types::ClientCapabilities::default(),
+            vec![root.clone()],
+        );
+        config
+            .update(serde_json::json!({ "cache": { "enable": false } }))
+            .unwrap();
+        let mut state = GlobalState::new(sender, config);
+        let stale = Url::from_file_path(root.join("Deleted.cfc")).unwrap();
+        state.load_files(vec![(root.join("Deleted.cfc"), b"component {}".to_vec())]);
+        let open = Url::from_file_path(root.join("index.cfm")).unwrap();
+        state
+            .add_document(&open, "<cfset x = 1>".to_string(), 1)
+            .unwrap();
+        state.add_changes_into_document(&open, "<cfset x = 1>".to_string());
+        state.process_changes();
+
+        handle_reload_workspace(&mut state, ()).unwrap();
+        assert_eq!(state.indexing_generation, 1);
+        state.process_changes();
+        assert!(state.snapshot().url_to_file_id(&stale).is_err());
+        assert!(state.snapshot().url_to_file_id(&open).is_ok());
+
+        // The files arrive as the main loop handles the indexing tasks.
+        let service = Url::from_file_path(root.join("Service.cfc")).unwrap();
+        while state.indexing.is_some() {
+            let task = state.task_pool.receiver.recv().unwrap();
+            state.handle_task(task);
+        }
+        state.process_changes();
+        assert!(state.snapshot().url_to_file_id(&service).is_ok());
+    }
 }
@@ -0,0 +1,160 @@
+//! The ignore files met while crawling the workspace, so that dependencies
+//! and build output aren't indexed: `.gitignore` and `.ignore`, the
+//! `.git/info/exclude` of a repository and the `core.excludesFile` of git.
+//!
+//! Their patterns are matched as git does, by the `ignore` crate.
+use std::path::Path;
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use virtual_fs::AbsPath;
+
+/// The ignore files of a directory, those later in the list taking
+/// precedence.
+pub(crate) const IGNORE_FILES: &[&str] = &[".git/info/exclude", ".gitignore", ".ignore"];
+
+/// The rules of the ignore files of a directory.
+#[derive(Debug)]
+pub(crate) struct Ignore(Gitignore);
+
+impl Ignore {
+    /// Reads the ignore files of `dir`, if it has any.
+    pub(crate) fn load(dir: &AbsPath) -> Option<Ignore> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        for name in IGNORE_FILES {
+            let path = dir.join(name);
+            if !AsRef::<Path>::as_ref(&path).is_file() {
+                continue;
+            }
+            found = true;
+            if let Some(e) = builder.add(&path) {
+                tracing::warn!("Failed to read {path}: {e}");
+            }
+        }
+        found.then(|| Ignore::build(&builder))
+    }
+
+    /// The global ignore file of git, if there is one, for the files under
+    /// `root`.
+    pub(crate) fn global(root: &AbsPath) -> Option<Ignore> {
+        let (gitignore, error) = GitignoreBuilder::new(root).build_global();
+        if let Some(e) = error {
+            tracing::warn!("Failed to read the global ignore file of git: {e}");
+        }
+        (!gitignore.is_empty()).then_some(Ignore(gitignore))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn parse(dir: &AbsPath, text: &str) -> Ignore {
+        let mut builder = GitignoreBuilder::new(dir);
+        for line in text.lines() {
+            builder.add_line(None, line).unwrap();
+        }
+        Ignore::build(&builder)
+    }
+
+    fn build(builder: &GitignoreBuilder) -> Ignore {
+        match builder.build() {
+            Ok(it) => Ignore(it),
+            Err(e) => {
+                tracing::warn!("Invalid ignore file: {e}");
+                Ignore(Gitignore::empty())
+            }
+        }
+    }
+
+    /// Whether the last rule matching `path` ignores it, if one does.
+    fn matched(&self, path: &AbsPath, is_dir: bool) -> Option<bool> {
+        if !AsRef::<Path>::as_ref(path).starts_with(self.0.path()) {
+            return None;
+        }
+        match self.0.matched(path, is_dir) {
+            Match::None => None,
+            Match::Ignore(_) => Some(true),
+            Match::Whitelist(_) => Some(false),
+        }
+    }
+}
+
+/// Whether `path` is ignored by the ignore files of its directories, from
+/// the outermost one: those deeper down take precedence.
+pub(crate) fn is_ignored(ignores: &[Ignore], path: &AbsPath, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .find_map(|it| it.matched(path, is_dir))
+        .unwrap_or(false)
+}
+
+/// Whether the file `path`, under `root`, is ignored by the ignore files
+/// read from disk of the directories between them, or by the global one.
+pub(crate) fn is_ignored_on_disk(root: &AbsPath, path: &AbsPath) -> bool {
+    let mut ignores: Vec<_> = Ignore::global(root).into_iter().collect();
+    let mut dir = root.to_path_buf();
+    let Some(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    let names: Vec<_> = AsRef::<Path>::as_ref(relative).components().collect();
+    for (i, name) in names.iter().enumerate() {
+        ignores.extend(Ignore::load(&dir));
+        dir = dir.join(name);
+        let is_dir = i + 1 < names.len();
+        if is_ignored(&ignores, &dir, is_dir) {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_is_ignored() {
+        let root = AbsPathBuf::try_from("/w").unwrap();
+        let ignores = [
+            Ignore::parse(
+                &root,
+                "# dependencies\nnode_modules/\n/build\n*.log\nWEB-INF/lib/\n!keep.log\n",
+            ),
+            Ignore::parse(&root.join("app"), "keep.log\n"),
+        ];
+        let ignored = |path: &str, is_dir| is_ignored(&ignores, &root.join(path), is_dir);
+        assert!(ignored("node_modules", true));
+        assert!(ignored("app/node_modules", true));
+        assert!(!ignored("node_modules", false));
+        assert!(ignored("build", true));
+        assert!(!ignored("app/build", true));
+        assert!(ignored("app/debug.log", false));
+        assert!(!ignored("keep.log", false));
+        assert!(ignored("app/keep.log", false));
+        assert!(ignored("WEB-INF/lib", true));
+        assert!(!ignored("WEB-INF/index.cfm", false));
+    }
+
+    #[test]
+    fn test_syntax() {
+        let root = AbsPathBuf::try_from("/w").unwrap();
+        let ignores = [Ignore::parse(
+            &root,
+            "*.cf[cm]~\n\\#notes.cfm\n\\!important.cfm\nspace\\ \nlog[0-9].txt\n",
+        )];
+        let ignored = |path: &str| is_ignored(&ignores, &root.join(path), false);
+        assert!(ignored("a.cfm~"));
+        assert!(ignored("app/a.cfc~"));
+        assert!(!ignored("a.cfs~"));
+        assert!(ignored("#notes.cfm"));
+        assert!(ignored("!important.cfm"));
+        assert!(!ignored("important.cfm"));
+        assert!(ignored("space "));
+        assert!(!ignored("space"));
+        assert!(ignored("log1.txt"));
+        assert!(!ignored("logs.txt"));
+    }
+}
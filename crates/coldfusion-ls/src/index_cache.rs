@@ -0,0 +1,207 @@
+//! The index of the workspace kept on disk between runs, so that a warm start
+//! of a large workspace skips parsing the files which didn't change.
+//!
+//! Each workspace root has a JSON file in the cache directory, holding the
+//! [`FileIndex`] of its files by the hash of their text, so that the roots
+//! opened together or alone share it. It is read when the root is indexed,
+//! and an entry is only given to a file whose text hashes the same, so a
+//! stale entry is never wrong, just unused. The file is written again once
+//! indexing ends.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ide::{FileIndex, IndexedReference, Name, TextRange};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use virtual_fs::AbsPath;
+
+/// Bumped when the format or what is indexed changes, so that the files
+/// written before are ignored.
+const VERSION: u32 = 1;
+
+/// The indexes read from disk, by the hash of the text they come from.
+pub(crate) type IndexCache = FxHashMap<u64, FileIndex>;
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    files: Vec<CachedFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFile {
+    hash: u64,
+    component_references: Vec<CachedReference>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedReference {
+    start: u32,
+    end: u32,
+    name: String,
+    wirebox: bool,
+}
+
+/// A hash of `text` which stays the same across runs and platforms, unlike
+/// the ones of the standard library: 64-bit FNV-1a.
+pub(crate) fn text_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The cache file, in `dir`, of the workspace root `root`.
+pub(crate) fn cache_file(dir: &Path, root: &AbsPath) -> PathBuf {
+    let key = text_hash(&root.to_string());
+    dir.join(format!("root-{key:016x}.json"))
+}
+
+/// Reads the cache at `path`, which is empty when it is missing, invalid or
+/// of another version.
+pub(crate) fn load(path: &Path) -> IndexCache {
+    let Ok(contents) = fs::read(path) else {
+        return IndexCache::default();
+    };
+    let file = match serde_json::from_slice::<CacheFile>(&contents) {
+        Ok(file) if file.version == VERSION => file,
+        Ok(_) => return IndexCache::default(),
+        Err(e) => {
+            tracing::warn!("ignoring the invalid index cache {}: {e}", path.display());
+            return IndexCache::default();
+        }
+    };
+    file.files
+        .into_iter()
+        .map(|file| {
+            let component_references = file
+                .component_references
+                .into_iter()
+                .map(|it| IndexedReference {
+                    range: TextRange::new(it.start, it.end),
+                    name: Name::new(&it.name),
+                    wirebox: it.wirebox,
+                })
+                .collect();
+            (
+                file.hash,
+                FileIndex {
+                    component_references,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Writes `files`, the indexes by the hash of their text, to the cache at
+/// `path`. The file is replaced at once, so that a server starting meanwhile
+/// never reads half of it.
+pub(crate) fn save(path: &Path, files: Vec<(u64, FileIndex)>) -> io::Result<()> {
+    let files = files
+        .into_iter()
+        .map(|(hash, index)| CachedFile {
+            hash,
+            component_references: index
+                .component_references
+                .into_iter()
+                .map(|it| CachedReference {
+                    start: it.range.start(),
+                    end: it.range.end(),
+                    name: it.name.to_string(),
+                    wirebox: it.wirebox,
+                })
+                .collect(),
+        })
+        .collect();
+    let contents = serde_json::to_vec(&CacheFile {
+        version: VERSION,
+        files,
+    })?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::AbsPathBuf;
+
+    use super::*;
+    use crate::{config::Config, global_state::GlobalState, indexing::IndexingProgress};
+
+    fn index(name: &str) -> FileIndex {
+        FileIndex {
+            component_references: vec![IndexedReference {
+                range: TextRange::new(19, 23),
+                name: Name::new(name),
+                wirebox: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_text_hash() {
+        assert_eq!(text_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(text_hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(text_hash("component {}"), text_hash("component { }"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join("cfml-index-cache");
+        let _ = fs::remove_dir_all(&dir);
+        let root = AbsPathBuf::try_from("/workspace").unwrap();
+        let path = cache_file(&dir, &root);
+        assert_ne!(path, cache_file(&dir, &root.join("other")));
+        assert!(load(&path).is_empty());
+
+        save(&path, vec![(1, index("Base")), (2, index("IUser"))]).unwrap();
+        let cache = load(&path);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache[&1], index("Base"));
+        assert_eq!(cache[&2], index("IUser"));
+
+        fs::write(&path, r#"{"version": 0, "files": []}"#).unwrap();
+        assert!(load(&path).is_empty());
+        fs::write(&path, "{").unwrap();
+        assert!(load(&path).is_empty());
+    }
+
+    #[test]
+    fn test_warm_start() {
+        let dir = std::env::temp_dir().join("cfml-index-cache-warm");
+        let _ = fs::remove_dir_all(&dir);
+        let root = AbsPathBuf::try_from(dir.clone()).unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        config
+            .update(serde_json::json!({ "cache": { "directory": dir.join("cache") } }))
+            .unwrap();
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let mut state = GlobalState::new(sender, config);
+        let text = "component extends=\"Base\" {}";
+        let path = state.index_cache_file(&root).unwrap();
+        save(&path, vec![(text_hash(text), index("Base"))]).unwrap();
+
+        state.handle_indexing_progress(IndexingProgress::Cached(load(&path)));
+        state.load_files(vec![(root.join("User.cfc"), text.as_bytes().to_vec())]);
+        state.process_changes();
+        let analysis = state.snapshot().analysis;
+        let file_id = state
+            .snapshot()
+            .url_to_file_id(&lsp_types::Url::from_file_path(root.join("User.cfc")).unwrap());
+        let index_of_file = analysis.file_index(file_id.unwrap()).unwrap();
+        assert_eq!(index_of_file, index("Base"));
+        let parsed = analysis
+            .memory_usage()
+            .into_iter()
+            .find(|it| it.name == "parse trees");
+        assert_eq!(parsed.unwrap().count, 0);
+
+        state.handle_indexing_progress(IndexingProgress::End);
+        assert!(state.index_cache.is_empty());
+    }
+}
@@ -0,0 +1,500 @@
+//! Loads the CFML files of the workspace roots when the server starts, so
+//! that features looking at other files, like resolving components, work
+//! before these are opened.
+//!
+//! The files are read and indexed in batches, across threads, and sent to
+//! the main loop in the order of their paths, whatever the thread finishing
+//! first, for it to report the progress of.
+//!
+//! Each file is held once while it is indexed: its text is indexed where it
+//! was read unless its line endings need converting, and the threads read
+//! only a few batches ahead of those sent, rather than the whole workspace.
+//! Big files are mapped rather than read, for their converted text to be
+//! gone before their contents are copied out of the mapping.
+//!
+//! The threads pause while the user types, for the keystrokes to be answered
+//! first.
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use ide::{CancellationToken, FileIndex};
+use parking_lot::{Condvar, Mutex};
+use rustc_hash::{FxHashMap, FxHashSet};
+use virtual_fs::{AbsPath, AbsPathBuf};
+
+use crate::{
+    glob::{self, Glob},
+    global_state::LineEndings,
+    ignore_files::{self, Ignore},
+    index_cache::{self, IndexCache},
+    task_pool::Typing,
+};
+
+/// The title of the progress of indexing, shown by clients.
+pub(crate) const TITLE: &str = "Indexing CFML";
+
+/// The extensions of the files indexed and watched.
+pub(crate) const CFML_EXTENSIONS: &[&str] = &["cfc", "cfm", "cfml"];
+
+/// The number of files read between two reports.
+const BATCH_SIZE: usize = 64;
+
+/// The size in bytes over which files are mapped rather than read.
+const MAP_FILE_SIZE: u64 = 1 << 20;
+
+#[derive(Debug)]
+pub(crate) enum IndexingProgress {
+    /// The index kept from the last run, read before the files.
+    Cached(IndexCache),
+    Begin {
+        total: usize,
+    },
+    Report {
+        done: usize,
+        total: usize,
+        files: Vec<(AbsPathBuf, Vec<u8>)>,
+        /// The indexes of the files, by the hash of their text, for those
+        /// the cache has none of.
+        indexes: Vec<(u64, FileIndex)>,
+    },
+    /// Sent once done, or cancelled.
+    End,
+}
+
+/// The files left out of the workspace: neither read nor analyzed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FileFilter {
+    /// The workspace roots, which the globs are relative to.
+    pub(crate) roots: Vec<AbsPathBuf>,
+    pub(crate) exclude_dirs: Vec<AbsPathBuf>,
+    pub(crate) exclude_globs: Vec<Glob>,
+    /// The size in bytes over which files, like big data templates, are
+    /// skipped.
+    pub(crate) max_file_size: Option<u64>,
+    /// Whether the files that `.gitignore`, `.ignore` and the other ignore
+    /// files of git list are skipped.
+    pub(crate) respect_ignore_files: bool,
+}
+
+impl FileFilter {
+    /// Whether `path` is under an excluded directory, or matches a glob
+    /// relative to one of the roots.
+    pub(crate) fn is_excluded(&self, path: &AbsPath) -> bool {
+        if self.exclude_dirs.iter().any(|dir| path.starts_with(dir)) {
+            return true;
+        }
+        if self.exclude_globs.is_empty() {
+            return false;
+        }
+        self.roots.iter().any(|root| {
+            glob::relative_path(root, path)
+                .is_some_and(|it| self.exclude_globs.iter().any(|glob| glob.is_match(&it)))
+        })
+    }
+
+    pub(crate) fn is_too_big(&self, size: u64) -> bool {
+        self.max_file_size.is_some_and(|max| size > max)
+    }
+
+    /// Whether the file at `path` is left out, reading its size and the
+    /// ignore files above it from disk.
+    pub(crate) fn skips_file(&self, path: &AbsPath) -> bool {
+        self.is_excluded(path)
+            || fs::metadata(path).is_ok_and(|it| self.is_too_big(it.len()))
+            || self.respect_ignore_files
+                && (self.roots.iter()).any(|root| ignore_files::is_ignored_on_disk(root, path))
+    }
+}
+
+/// Reads and indexes the CFML files under `roots`, but for those `filter`
+/// excludes, until `token` is cancelled. The files whose text hashes to one
+/// of `cached` already have an index.
+pub(crate) fn index(
+    roots: &[AbsPathBuf],
+    filter: &FileFilter,
+    cached: &FxHashSet<u64>,
+    token: &CancellationToken,
+    typing: &Typing,
+    send: &dyn Fn(IndexingProgress),
+) {
+    let mut paths = Vec::new();
+    for root in roots {
+        let global_ignore = filter.respect_ignore_files.then(|| Ignore::global(root));
+        let mut ignores: Vec<_> = global_ignore.into_iter().flatten().collect();
+        collect_cfml_files(root.to_path_buf(), filter, &mut ignores, &mut paths);
+    }
+    paths.sort();
+    paths.dedup();
+    let total = paths.len();
+    send(IndexingProgress::Begin { total });
+    let batches: Vec<_> = paths.chunks(BATCH_SIZE).collect();
+    let threads = thread::available_parallelism().map_or(4, usize::from);
+    let next_batch = AtomicUsize::new(0);
+    // The number of batches sent, which the threads wait on to read at most
+    // `read_ahead` batches past.
+    let sent = (Mutex::new(0), Condvar::new());
+    let read_ahead = 2 * threads;
+    thread::scope(|scope| {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        for _ in 0..threads.min(batches.len()) {
+            let (sender, batches, next_batch, sent) =
+                (sender.clone(), &batches, &next_batch, &sent);
+            scope.spawn(move || {
+                while !token.is_cancelled() {
+                    let i = next_batch.fetch_add(1, Ordering::Relaxed);
+                    let Some(batch) = batches.get(i) else {
+                        break;
+                    };
+                    let mut sent_count = sent.0.lock();
+                    while i >= *sent_count + read_ahead {
+                        sent.1.wait(&mut sent_count);
+                    }
+                    drop(sent_count);
+                    typing.wait(token);
+                    let _ = sender.send((i, read_batch(batch, cached)));
+                }
+            });
+        }
+        drop(sender);
+        // The batches are sent in order, each once those before it are.
+        let mut pending = FxHashMap::default();
+        let mut done = 0;
+        let mut next_to_send = 0;
+        for (i, batch) in receiver {
+            pending.insert(i, batch);
+            while let Some((files, indexes)) = pending.remove(&next_to_send) {
+                done += batches[next_to_send].len();
+                next_to_send += 1;
+                *sent.0.lock() = next_to_send;
+                sent.1.notify_all();
+                send(IndexingProgress::Report {
+                    done,
+                    total,
+                    files,
+                    indexes,
+                });
+            }
+        }
+    });
+    send(IndexingProgress::End);
+}
+
+type Batch = (Vec<(AbsPathBuf, Vec<u8>)>, Vec<(u64, FileIndex)>);
+
+/// Reads the files of `paths`, indexing those not `cached`.
+fn read_batch(paths: &[AbsPathBuf], cached: &FxHashSet<u64>) -> Batch {
+    let mut files = Vec::with_capacity(paths.len());
+    let mut indexes = Vec::new();
+    for path in paths {
+        let Ok(file) = fs::File::open(path) else {
+            continue;
+        };
+        let contents = if file.metadata().is_ok_and(|it| it.len() > MAP_FILE_SIZE) {
+            // SAFETY: another process may change the file under the mapping,
+            // which is only read, for as long as the file is indexed.
+            let Ok(map) = (unsafe { memmap2::Mmap::map(&file) }) else {
+                continue;
+            };
+            index_contents(&map, cached, &mut indexes);
+            map.to_vec()
+        } else {
+            let Ok(contents) = fs::read(path) else {
+                continue;
+            };
+            index_contents(&contents, cached, &mut indexes);
+            contents
+        };
+        files.push((path.clone(), contents));
+    }
+    (files, indexes)
+}
+
+/// Indexes `contents` if it is text whose hash isn't one of `cached`.
+fn index_contents(contents: &[u8], cached: &FxHashSet<u64>, indexes: &mut Vec<(u64, FileIndex)>) {
+    let Ok(text) = std::str::from_utf8(contents) else {
+        return;
+    };
+    // The index is of the text the analysis gets, with `\n` endings: only
+    // the files with others are copied to convert them.
+    let normalized;
+    let text = if memchr::memmem::find(contents, b"\r\n").is_some() {
+        normalized = LineEndings::normalize(text.to_string()).0;
+        normalized.as_str()
+    } else {
+        text
+    };
+    let hash = index_cache::text_hash(text);
+    if !cached.contains(&hash) {
+        indexes.push((hash, FileIndex::new(text)));
+    }
+}
+
+/// Returns `true` for the files with a CFML extension, in any case.
+fn is_cfml_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|it| it.to_str())
+        .is_some_and(|ext| {
+            CFML_EXTENSIONS
+                .iter()
+                .any(|it| it.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Collects the CFML files under `dir`, skipping hidden directories like
+/// `.git` and what `filter` excludes. `ignores` are the ignore files of the
+/// directories above.
+fn collect_cfml_files(
+    dir: AbsPathBuf,
+    filter: &FileFilter,
+    ignores: &mut Vec<Ignore>,
+    acc: &mut Vec<AbsPathBuf>,
+) {
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+    let ignore = filter
+        .respect_ignore_files
+        .then(|| Ignore::load(&dir))
+        .flatten();
+    let has_ignore = ignore.is_some();
+    ignores.extend(ignore);
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = dir.join(entry.file_name());
+        if filter.is_excluded(&path) || ignore_files::is_ignored(ignores, &path, file_type.is_dir())
+        {
+            continue;
+        }
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                collect_cfml_files(path, filter, ignores, acc);
+            }
+        } else if file_type.is_file() && is_cfml_file(&entry.path()) {
+            let size = entry.metadata().map_or(0, |it| it.len());
+            if filter.is_too_big(size) {
+                tracing::info!("skipping {path}: {size} bytes is over the maximum file size");
+                continue;
+            }
+            acc.push(path);
+        }
+    }
+    if has_ignore {
+        ignores.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    fn workspace(name: &str) -> AbsPathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["app/models", ".git", "assets"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "Application.cfc",
+            "app/index.cfm",
+            "app/models/User.CFC",
+            ".git/HEAD.cfm",
+            "assets/site.css",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        AbsPathBuf::try_from(root).unwrap()
+    }
+
+    #[test]
+    fn test_index() {
+        let root = workspace("cfml-indexing");
+        let progress = RefCell::new(Vec::new());
+        index(
+            std::slice::from_ref(&root),
+            &FileFilter::default(),
+            &FxHashSet::default(),
+            &CancellationToken::default(),
+            &Typing::default(),
+            &|it| progress.borrow_mut().push(it),
+        );
+        let progress = progress.into_inner();
+        assert!(matches!(progress[0], IndexingProgress::Begin { total: 3 }));
+        let IndexingProgress::Report {
+            done,
+            total,
+            files,
+            indexes,
+        } = &progress[1]
+        else {
+            panic!("expected a report");
+        };
+        assert_eq!((*done, *total), (3, 3));
+        // The files are empty: they have the same index.
+        assert_eq!(indexes.len(), 3);
+        let files: Vec<_> = files
+            .iter()
+            .map(|(path, _)| AsRef::<Path>::as_ref(path.strip_prefix(&root).unwrap()).to_owned())
+            .collect();
+        assert_eq!(
+            files,
+            [
+                Path::new("Application.cfc"),
+                Path::new("app/index.cfm"),
+                Path::new("app/models/User.CFC"),
+            ]
+        );
+        assert!(matches!(progress[2], IndexingProgress::End));
+    }
+
+    #[test]
+    fn test_index_cancelled() {
+        let root = workspace("cfml-indexing-cancelled");
+        let token = CancellationToken::default();
+        token.cancel();
+        let progress = RefCell::new(Vec::new());
+        index(
+            &[root],
+            &FileFilter::default(),
+            &FxHashSet::default(),
+            &token,
+            &Typing::default(),
+            &|it| progress.borrow_mut().push(it),
+        );
+        let progress = progress.into_inner();
+        assert!(matches!(
+            progress[..],
+            [IndexingProgress::Begin { total: 3 }, IndexingProgress::End]
+        ));
+    }
+
+    #[test]
+    fn test_read_batch() {
+        let root = workspace("cfml-indexing-read-batch");
+        let paths = [root.join("unix.cfm"), root.join("dos.cfm")];
+        fs::write(&paths[0], "<cfset x = 1>\n").unwrap();
+        fs::write(&paths[1], "<cfset x = 1>\r\n").unwrap();
+        let (files, indexes) = read_batch(&paths, &FxHashSet::default());
+        // The files keep their endings, their indexes are of the text with
+        // `\n` ones.
+        assert_eq!(files[1].1, b"<cfset x = 1>\r\n");
+        let hash = index_cache::text_hash("<cfset x = 1>\n");
+        let hashes: Vec<_> = indexes.iter().map(|(it, _)| *it).collect();
+        assert_eq!(hashes, [hash, hash]);
+
+        let (files, indexes) = read_batch(&paths, &FxHashSet::from_iter([hash]));
+        assert_eq!(files.len(), 2);
+        assert!(indexes.is_empty());
+    }
+
+    #[test]
+    fn test_index_excluded() {
+        let root = workspace("cfml-indexing-excluded");
+        fs::write(root.join("app/data.cfm"), "x".repeat(100)).unwrap();
+        let filter = FileFilter {
+            roots: vec![root.clone()],
+            exclude_dirs: vec![root.join("app/models")],
+            exclude_globs: vec![Glob::new("**/Application.cfc")],
+            max_file_size: Some(10),
+            respect_ignore_files: false,
+        };
+        let progress = RefCell::new(Vec::new());
+        index(
+            std::slice::from_ref(&root),
+            &filter,
+            &FxHashSet::default(),
+            &CancellationToken::default(),
+            &Typing::default(),
+            &|it| progress.borrow_mut().push(it),
+        );
+        let progress = progress.into_inner();
+        let IndexingProgress::Report { files, .. } = &progress[1] else {
+            panic!("expected a report");
+        };
+        let files: Vec<_> = files.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(files, [root.join("app/index.cfm")]);
+        assert!(filter.skips_file(&root.join("app/data.cfm")));
+        assert!(filter.skips_file(&root.join("app/models/Order.cfc")));
+        assert!(!filter.skips_file(&root.join("app/index.cfm")));
+    }
+
+    #[test]
+    fn test_index_big_files() {
+        let root = workspace("cfml-indexing-big-files");
+        let text = "<cfset x = 1>\r\n".repeat(MAP_FILE_SIZE as usize / 10);
+        fs::write(root.join("app/report.cfm"), &text).unwrap();
+        // It is over the size of large files, which are indexed all the same.
+        let config =
+            crate::config::Config::new(root.clone(), Default::default(), vec![root.clone()]);
+        assert!(config.is_large_file(&text));
+        let progress = RefCell::new(Vec::new());
+        index(
+            std::slice::from_ref(&root),
+            &config.files(),
+            &FxHashSet::default(),
+            &CancellationToken::default(),
+            &Typing::default(),
+            &|it| progress.borrow_mut().push(it),
+        );
+        let progress = progress.into_inner();
+        let IndexingProgress::Report { files, indexes, .. } = &progress[1] else {
+            panic!("expected a report");
+        };
+        let (_, contents) = files
+            .iter()
+            .find(|(path, _)| *path == root.join("app/report.cfm"))
+            .unwrap();
+        assert_eq!(contents.as_slice(), text.as_bytes());
+        let hash = index_cache::text_hash(&text.replace("\r\n", "\n"));
+        assert!(indexes.iter().any(|(it, _)| *it == hash));
+    }
+
+    #[test]
+    fn test_index_ignore_files() {
+        let root = workspace("cfml-indexing-ignore-files");
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::write(root.join("node_modules/pkg/x.cfm"), "").unwrap();
+        fs::write(root.join(".gitignore"), "node_modules/\n").unwrap();
+        fs::write(root.join("app/.ignore"), "*.cfm\n").unwrap();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "legacy.cf[cm]\n").unwrap();
+        fs::write(root.join("legacy.cfm"), "").unwrap();
+        let filter = FileFilter {
+            roots: vec![root.clone()],
+            respect_ignore_files: true,
+            ..FileFilter::default()
+        };
+        let progress = RefCell::new(Vec::new());
+        index(
+            std::slice::from_ref(&root),
+            &filter,
+            &FxHashSet::default(),
+            &CancellationToken::default(),
+            &Typing::default(),
+            &|it| progress.borrow_mut().push(it),
+        );
+        let progress = progress.into_inner();
+        let IndexingProgress::Report { files, .. } = &progress[1] else {
+            panic!("expected a report");
+        };
+        let files: Vec<_> = files.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(
+            files,
+            [
+                root.join("Application.cfc"),
+                root.join("app/models/User.CFC")
+            ]
+        );
+        assert!(filter.skips_file(&root.join("node_modules/pkg/x.cfm")));
+        assert!(filter.skips_file(&root.join("app/index.cfm")));
+        assert!(filter.skips_file(&root.join("legacy.cfm")));
+        assert!(!filter.skips_file(&root.join("app/models/User.CFC")));
+    }
+}
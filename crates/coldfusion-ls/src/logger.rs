@@ -0,0 +1,431 @@
+//! The `tracing` subscriber of the server: writes the events and the spans
+//! they happen in to stderr or a file, filtered per module by `CFLS_LOG`,
+//! and forwards them to the client when it asks for a trace.
+use std::{
+    cell::RefCell,
+    fmt::{self, Write as _},
+    io::Write,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+use crossbeam_channel::Sender;
+use lsp_server::Message;
+use lsp_types::{notification::Notification as _, MessageType, TraceValue};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    span, Event, Level, Metadata, Subscriber,
+};
+
+/// Which events are logged: a default level, then levels for the modules
+/// whose path starts with a prefix, as in `warn,ide=debug`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LogFilter {
+    default: LevelFilter,
+    /// The levels of the modules, the longest prefix first.
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    fn max_level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= &self.max_level(metadata.target())
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter {
+            default: LevelFilter::INFO,
+            directives: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LogFilter, String> {
+        let mut res = LogFilter::default();
+        for directive in s.split(',').map(str::trim).filter(|it| !it.is_empty()) {
+            let parse_level = |level: &str| {
+                LevelFilter::from_str(level).map_err(|_| format!("invalid log level `{level}`"))
+            };
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    // Modules are named as in paths, with `_` for `-`.
+                    let target = target.trim().replace('-', "_");
+                    res.directives.push((target, parse_level(level.trim())?));
+                }
+                None => res.default = parse_level(directive)?,
+            }
+        }
+        res.directives
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        Ok(res)
+    }
+}
+
+/// The client the events are forwarded to, and how much of them it wants,
+/// as set by its `trace` setting.
+#[derive(Debug, Default)]
+pub(crate) struct ClientLog {
+    sender: Mutex<Option<Sender<Message>>>,
+    trace: AtomicU8,
+}
+
+impl ClientLog {
+    pub(crate) fn connect(&self, sender: Sender<Message>) {
+        *self.sender.lock() = Some(sender);
+    }
+
+    /// Stops forwarding, releasing the connection so that it can close.
+    pub(crate) fn disconnect(&self) {
+        *self.sender.lock() = None;
+    }
+
+    pub(crate) fn set_trace(&self, trace: TraceValue) {
+        let trace = match trace {
+            TraceValue::Off => 0,
+            TraceValue::Messages => 1,
+            TraceValue::Verbose => 2,
+        };
+        self.trace.store(trace, Ordering::Relaxed);
+    }
+
+    /// The most verbose level forwarded.
+    fn max_level(&self) -> LevelFilter {
+        match self.trace.load(Ordering::Relaxed) {
+            0 => LevelFilter::OFF,
+            1 => LevelFilter::INFO,
+            _ => LevelFilter::DEBUG,
+        }
+    }
+
+    /// Sends errors and warnings as `window/logMessage`, the others as
+    /// `$/logTrace`, with the spans and fields when the trace is verbose.
+    fn send(&self, level: Level, message: String, context: String) {
+        let Some(sender) = &*self.sender.lock() else {
+            return;
+        };
+        let notification = match level {
+            Level::ERROR | Level::WARN => {
+                let typ = if level == Level::ERROR {
+                    MessageType::ERROR
+                } else {
+                    MessageType::WARNING
+                };
+                lsp_server::Notification::new(
+                    lsp_types::notification::LogMessage::METHOD.to_string(),
+                    lsp_types::LogMessageParams { typ, message },
+                )
+            }
+            _ => {
+                let verbose = (self.trace.load(Ordering::Relaxed) == 2).then_some(context);
+                lsp_server::Notification::new(
+                    lsp_types::notification::LogTrace::METHOD.to_string(),
+                    lsp_types::LogTraceParams { message, verbose },
+                )
+            }
+        };
+        let _ = sender.send(notification.into());
+    }
+}
+
+/// Writes the events to `writer`, each on a line with the spans it happens
+/// in, as in `INFO ide::diagnostics: request{method="shutdown"}: message`.
+pub(crate) struct Logger {
+    filter: LogFilter,
+    writer: Mutex<Box<dyn Write + Send>>,
+    client: Arc<ClientLog>,
+    next_id: AtomicU64,
+    spans: Mutex<FxHashMap<u64, SpanData>>,
+}
+
+struct SpanData {
+    name: &'static str,
+    /// The fields, formatted as `name=value` separated by spaces.
+    fields: String,
+    /// The clones of the id alive, the span closing when none is left.
+    refs: usize,
+}
+
+thread_local! {
+    /// The spans entered on this thread, innermost last.
+    static CURRENT_SPANS: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Logger {
+    pub(crate) fn new(
+        filter: LogFilter,
+        writer: Box<dyn Write + Send>,
+        client: Arc<ClientLog>,
+    ) -> Logger {
+        Logger {
+            filter,
+            writer: Mutex::new(writer),
+            client,
+            next_id: AtomicU64::new(1),
+            spans: Mutex::default(),
+        }
+    }
+
+    /// The entered spans, outermost first, as `name{fields}:name:`.
+    fn context(&self) -> String {
+        let spans = self.spans.lock();
+        let mut res = String::new();
+        CURRENT_SPANS.with_borrow(|current| {
+            for data in current.iter().filter_map(|id| spans.get(id)) {
+                res.push_str(data.name);
+                if !data.fields.is_empty() {
+                    let _ = write!(res, "{{{}}}", data.fields);
+                }
+                res.push(':');
+            }
+        });
+        res
+    }
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.enabled(metadata) || metadata.level() <= &self.client.max_level()
+    }
+
+    fn register_callsite(
+        &self,
+        metadata: &'static Metadata<'static>,
+    ) -> tracing::subscriber::Interest {
+        // The client may ask for more events at any time.
+        if self.filter.enabled(metadata) {
+            tracing::subscriber::Interest::always()
+        } else {
+            tracing::subscriber::Interest::sometimes()
+        }
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut fields = FieldsVisitor::default();
+        attrs.record(&mut fields);
+        let data = SpanData {
+            name: attrs.metadata().name(),
+            fields: fields.fields,
+            refs: 1,
+        };
+        self.spans.lock().insert(id, data);
+        span::Id::from_u64(id)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+        if let Some(data) = self.spans.lock().get_mut(&span.into_u64()) {
+            let mut fields = FieldsVisitor {
+                fields: std::mem::take(&mut data.fields),
+                ..Default::default()
+            };
+            values.record(&mut fields);
+            data.fields = fields.fields;
+        }
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut visitor = FieldsVisitor::default();
+        event.record(&mut visitor);
+        let mut message = visitor.message;
+        if !visitor.fields.is_empty() {
+            if !message.is_empty() {
+                message.push(' ');
+            }
+            message.push_str(&visitor.fields);
+        }
+        let context = self.context();
+        if self.filter.enabled(metadata) {
+            let line = format!(
+                "{:>5} {}: {context}{}{message}\n",
+                metadata.level(),
+                metadata.target(),
+                if context.is_empty() { "" } else { " " },
+            );
+            let mut writer = self.writer.lock();
+            let _ = writer.write_all(line.as_bytes());
+            let _ = writer.flush();
+        }
+        if metadata.level() <= &self.client.max_level() {
+            self.client.send(*metadata.level(), message, context);
+        }
+    }
+
+    fn enter(&self, span: &span::Id) {
+        CURRENT_SPANS.with_borrow_mut(|it| it.push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &span::Id) {
+        CURRENT_SPANS.with_borrow_mut(|it| {
+            if let Some(idx) = it.iter().rposition(|&id| id == span.into_u64()) {
+                it.remove(idx);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &span::Id) -> span::Id {
+        if let Some(data) = self.spans.lock().get_mut(&span.into_u64()) {
+            data.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: span::Id) -> bool {
+        let mut spans = self.spans.lock();
+        let Some(data) = spans.get_mut(&span.into_u64()) else {
+            return false;
+        };
+        data.refs -= 1;
+        if data.refs > 0 {
+            return false;
+        }
+        spans.remove(&span.into_u64());
+        true
+    }
+}
+
+/// Formats the `message` of an event apart from its other fields.
+#[derive(Default)]
+struct FieldsVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldsVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+            return;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={value:?}", field.name());
+    }
+}
+
+/// Installs the logger of the server, writing to `log_file` or stderr,
+/// with the filter in `CFLS_LOG`. Returns the client the events are
+/// forwarded to once connected.
+pub(crate) fn init(log_file: Option<&std::path::Path>) -> anyhow::Result<Arc<ClientLog>> {
+    let filter = match std::env::var("CFLS_LOG") {
+        Ok(it) => it
+            .parse::<LogFilter>()
+            .map_err(|e| anyhow::format_err!("invalid `CFLS_LOG`: {e}"))?,
+        Err(_) => LogFilter::default(),
+    };
+    let writer: Box<dyn Write + Send> = match log_file {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| anyhow::format_err!("failed to open `{}`: {e}", path.display()))?;
+            Box::new(file)
+        }
+        None => Box::new(std::io::stderr()),
+    };
+    let client = Arc::new(ClientLog::default());
+    let logger = Logger::new(filter, writer, Arc::clone(&client));
+    tracing::subscriber::set_global_default(logger)?;
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let filter: LogFilter = "warn, ide=debug,coldfusion-language-server::handlers=trace"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            filter.max_level("coldfusion_language_server::handlers::request"),
+            LevelFilter::TRACE
+        );
+        assert_eq!(
+            filter.max_level("coldfusion_language_server::dispatcher"),
+            LevelFilter::WARN
+        );
+        assert_eq!(filter.max_level("ide::diagnostics"), LevelFilter::DEBUG);
+        assert_eq!(filter.max_level("ide_db"), LevelFilter::WARN);
+        assert!("ide=loud".parse::<LogFilter>().is_err());
+    }
+
+    #[test]
+    fn test_logger() {
+        let buffer = Buffer::default();
+        let client = Arc::new(ClientLog::default());
+        let filter = "info".parse().unwrap();
+        let logger = Logger::new(filter, Box::new(buffer.clone()), Arc::clone(&client));
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        client.connect(sender);
+        tracing::subscriber::with_default(logger, || {
+            let _p = tracing::span!(Level::INFO, "request", method = "shutdown").entered();
+            tracing::debug!("hidden");
+            tracing::warn!(id = 3, "shown");
+            client.set_trace(TraceValue::Verbose);
+            tracing::debug!("traced");
+        });
+        client.disconnect();
+
+        assert_eq!(
+            String::from_utf8(buffer.0.lock().clone()).unwrap(),
+            " WARN coldfusion_language_server::logger::tests: request{method=\"shutdown\"}: shown id=3\n"
+        );
+        let notifications: Vec<_> = receiver
+            .try_iter()
+            .map(|it| match it {
+                Message::Notification(it) => (it.method, it.params),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            notifications,
+            [(
+                "$/logTrace".to_string(),
+                serde_json::json!({
+                    "message": "traced",
+                    "verbose": "request{method=\"shutdown\"}:",
+                })
+            )]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Extensions to the LSP protocol, negotiated through the `experimental`
+//! client capabilities.
+use std::collections::HashMap;
+
+use lsp_types::{
+    notification::Notification, request::Request, CodeActionKind, CodeActionParams,
+    InsertTextFormat, Location, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+};
+use serde::{Deserialize, Serialize};
+
+/// `textDocument/codeAction`, answered with [`CodeAction`]s whose edits may
+/// contain snippets when the client advertises `snippetTextEdit`.
+pub(crate) enum CodeActionRequest {}
+
+impl Request for CodeActionRequest {
+    type Params = CodeActionParams;
+    type Result = Option<Vec<CodeAction>>;
+    const METHOD: &'static str = "textDocument/codeAction";
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CodeAction {
+    pub(crate) title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) kind: Option<CodeActionKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) edit: Option<SnippetWorkspaceEdit>,
+    /// Run by the client instead of applying an edit, for actions which need
+    /// more input from the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) command: Option<lsp_types::Command>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnippetWorkspaceEdit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) changes: Option<HashMap<Url, Vec<SnippetTextEdit>>>,
+    /// Used instead of `changes` when files are renamed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) document_changes: Option<Vec<SnippetDocumentChangeOperation>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum SnippetDocumentChangeOperation {
+    Op(lsp_types::ResourceOp),
+    Edit(SnippetTextDocumentEdit),
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnippetTextDocumentEdit {
+    pub(crate) text_document: lsp_types::OptionalVersionedTextDocumentIdentifier,
+    pub(crate) edits: Vec<SnippetTextEdit>,
+}
+
+/// A [`lsp_types::TextEdit`] whose new text may be a snippet.
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnippetTextEdit {
+    pub(crate) range: lsp_types::Range,
+    pub(crate) new_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) insert_text_format: Option<InsertTextFormat>,
+}
+
+/// `cfml/changeSignature`: changes the parameters of the function at a
+/// position and updates its calls. Clients ask the user for the new
+/// parameters, so this is a request rather than a code action.
+pub(crate) enum ChangeSignature {}
+
+impl Request for ChangeSignature {
+    type Params = ChangeSignatureParams;
+    type Result = Option<ChangeSignatureResult>;
+    const METHOD: &'static str = "cfml/changeSignature";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChangeSignatureParams {
+    #[serde(flatten)]
+    pub(crate) text_document_position: TextDocumentPositionParams,
+    /// The parameters of the new signature, in order.
+    pub(crate) parameters: Vec<SignatureParameter>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SignatureParameter {
+    /// The position of the parameter in the old signature, absent for a new
+    /// parameter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) old_index: Option<usize>,
+    pub(crate) name: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub(crate) ty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) default_value: Option<String>,
+    /// What existing calls pass to a new parameter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) call_value: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ChangeSignatureResult {
+    pub(crate) edit: SnippetWorkspaceEdit,
+    /// Calls which may target the function but could not be updated.
+    pub(crate) unresolved_calls: Vec<Location>,
+}
+
+/// `cfml/moveComponent`: moves a component to another folder and updates
+/// the references to it. Sent by clients for the `cfml.moveComponent`
+/// command of the "Move to folder…" code action, once the user picked the
+/// folder.
+pub(crate) enum MoveComponent {}
+
+impl Request for MoveComponent {
+    type Params = MoveComponentParams;
+    type Result = Option<SnippetWorkspaceEdit>;
+    const METHOD: &'static str = "cfml/moveComponent";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MoveComponentParams {
+    pub(crate) text_document: TextDocumentIdentifier,
+    pub(crate) new_folder: Url,
+}
+
+/// `cfml/expandIncludes`: the files a template runs through its includes,
+/// in order, to find where the variables of include-heavy templates come
+/// from.
+pub(crate) enum ExpandIncludes {}
+
+impl Request for ExpandIncludes {
+    type Params = ExpandIncludesParams;
+    type Result = Option<ExpandIncludesResult>;
+    const METHOD: &'static str = "cfml/expandIncludes";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExpandIncludesParams {
+    pub(crate) text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ExpandIncludesResult {
+    /// The template, then the files it includes in execution order. A file
+    /// included several times is listed each time.
+    pub(crate) files: Vec<Url>,
+    pub(crate) unresolved: Vec<UnresolvedInclude>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnresolvedInclude {
+    pub(crate) template: String,
+    pub(crate) location: Location,
+}
+
+/// `cfml/serverStatus`: what the server is doing and whether it works well,
+/// for clients to show in their status bar. Sent when it changes, to clients
+/// advertising `experimental.serverStatusNotification`.
+pub(crate) enum ServerStatusNotification {}
+
+impl Notification for ServerStatusNotification {
+    type Params = ServerStatusParams;
+    const METHOD: &'static str = "cfml/serverStatus";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerStatusParams {
+    pub(crate) health: Health,
+    pub(crate) state: ServerState,
+    /// Why the health isn't `ok`, for the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) message: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum Health {
+    Ok,
+    /// Features work, but not fully, e.g. with invalid settings.
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ServerState {
+    Idle,
+    /// Reading the files of the workspace, while features only see part of
+    /// them.
+    Indexing,
+    /// Answering requests.
+    Analyzing,
+}
+
+/// `cfml/memoryUsage`: the memory each part of the server holds, for users
+/// to report and maintainers to debug its growth on big workspaces.
+pub(crate) enum MemoryUsage {}
+
+impl Request for MemoryUsage {
+    type Params = ();
+    type Result = Vec<MemoryUsageEntry>;
+    const METHOD: &'static str = "cfml/memoryUsage";
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MemoryUsageEntry {
+    pub(crate) name: String,
+    /// The number of items held, e.g. files.
+    pub(crate) count: usize,
+    /// An estimate of the bytes held.
+    pub(crate) bytes: usize,
+}
+
+/// `cfml/analyzerStatus`: what the server knows of the workspace and where
+/// the time answering requests goes, for reports of slow requests.
+pub(crate) enum AnalyzerStatus {}
+
+impl Request for AnalyzerStatus {
+    type Params = ();
+    type Result = String;
+    const METHOD: &'static str = "cfml/analyzerStatus";
+}
+
+/// `cfml/reloadWorkspace`: reads the settings files and every file of the
+/// workspace from disk again, for when the server got out of sync with it.
+pub(crate) enum ReloadWorkspace {}
+
+impl Request for ReloadWorkspace {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "cfml/reloadWorkspace";
+}
@@ -1,19 +1,27 @@
 use crossbeam_channel::{select, Receiver};
 use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
 use lsp_types::{
-    CompletionOptions, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    CodeActionKind, CodeActionOptions, CodeActionProviderCapability, CompletionOptions, OneOf,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
+use rustc_hash::FxHashSet;
 use serde::de::DeserializeOwned;
+use std::fmt;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use virtual_fs::AbsPathBuf;
 
+mod cli;
+use cli::Command;
+
 mod config;
 use config::Config;
 
 mod global_state;
-use global_state::GlobalState;
+use global_state::{GlobalState, Progress};
 
 mod dispatcher;
 use dispatcher::RequestDispatcher;
@@ -24,13 +32,65 @@ mod lsp;
 
 mod handlers;
 
+mod index_cache;
+
+mod logger;
+use logger::ClientLog;
+
+mod glob;
+
+mod ignore_files;
+
+mod indexing;
+use indexing::IndexingProgress;
+
+mod parent_process;
+
+mod task_pool;
+use task_pool::ThreadIntent;
+
+mod transport;
+
 enum Event {
     Lsp(Message),
+    Task(global_state::Task),
+    /// The editor was quiet long enough for the background analysis.
+    Idle,
+    /// A request may have run past the deadline of the watchdog.
+    Watchdog,
+    /// The progress of a request going through every file is due.
+    RequestProgress,
+    /// The process of the client is gone.
+    ClientExited,
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Lsp(Message::Request(it)) => write!(f, "request {} {}", it.id, it.method),
+            Event::Lsp(Message::Notification(it)) => write!(f, "notification {}", it.method),
+            Event::Lsp(Message::Response(it)) => write!(f, "response {}", it.id),
+            Event::Task(global_state::Task::Response(it)) => write!(f, "task response {}", it.id),
+            Event::Task(global_state::Task::Indexing { .. }) => f.write_str("task indexing"),
+            Event::Task(global_state::Task::BackgroundAnalysis { .. }) => {
+                f.write_str("task background analysis")
+            }
+            Event::Idle => f.write_str("idle"),
+            Event::Watchdog => f.write_str("watchdog"),
+            Event::RequestProgress => f.write_str("request progress"),
+            Event::ClientExited => f.write_str("client exited"),
+        }
+    }
 }
 fn main() -> anyhow::Result<()> {
-    eprintln!("Starting ColdFusion Language Server...");
+    let cmd = match cli::parse_args(std::env::args().skip(1))? {
+        Command::Server(cmd) => cmd,
+        command => std::process::exit(command.run()?),
+    };
+    let client_log = logger::init(cmd.log_file.as_deref())?;
+    tracing::info!("starting ColdFusion Language Server");
 
-    let (connection, io_threads) = Connection::stdio();
+    let (connection, io_threads) = transport::connect(&cmd.transport)?;
 
     let (initialize_id, initialize_params) = match connection.initialize_start() {
         Ok(it) => it,
@@ -43,10 +103,12 @@ fn main() -> anyhow::Result<()> {
     };
 
     let lsp_types::InitializeParams {
+        process_id,
         root_uri,
         initialization_options,
         capabilities,
         workspace_folders,
+        trace,
         ..
     } = from_json::<lsp_types::InitializeParams>("InitializeParams", &initialize_params)?;
 
@@ -75,9 +137,12 @@ fn main() -> anyhow::Result<()> {
         .unwrap_or_else(|| vec![root_path.clone()]);
 
     let mut config = Config::new(root_path, capabilities, workspace_roots);
+    config.load_cflint();
+    config.load_cfconfig();
+    config.load_cfformat();
 
     if let Some(json) = initialization_options {
-        if let Err(e) = config.update(json) {
+        if let Err(e) = config.update_initialization_options(json) {
             use lsp_types::{
                 notification::{Notification, ShowMessage},
                 MessageType, ShowMessageParams,
@@ -87,7 +152,7 @@ fn main() -> anyhow::Result<()> {
                 ShowMessage::METHOD.to_owned(),
                 ShowMessageParams {
                     typ: MessageType::WARNING,
-                    message: format!("Failed to update configuration: {:?}", e),
+                    message: format!("Some settings are ignored:\n{e}"),
                 },
             );
             connection
@@ -98,7 +163,15 @@ fn main() -> anyhow::Result<()> {
     }
 
     let server_capabilities = ServerCapabilities {
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        position_encoding: Some(config.position_encoding().kind()),
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
         completion_provider: Some(CompletionOptions {
             resolve_provider: Some(true),
             trigger_characters: Some(vec![".".to_string()]),
@@ -106,6 +179,27 @@ fn main() -> anyhow::Result<()> {
             all_commit_characters: None,
             completion_item: None,
         }),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::REFACTOR,
+                CodeActionKind::REFACTOR_EXTRACT,
+                CodeActionKind::REFACTOR_INLINE,
+                CodeActionKind::REFACTOR_REWRITE,
+                CodeActionKind::SOURCE,
+                CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+            ]),
+            work_done_progress_options: Default::default(),
+            resolve_provider: None,
+        })),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        workspace: Some(WorkspaceServerCapabilities {
+            workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                supported: Some(true),
+                change_notifications: Some(OneOf::Left(true)),
+            }),
+            file_operations: None,
+        }),
         ..ServerCapabilities::default()
     };
 
@@ -126,13 +220,36 @@ fn main() -> anyhow::Result<()> {
         return Err(e.into());
     }
 
-    run(config, connection)?;
+    client_log.set_trace(trace.unwrap_or(lsp_types::TraceValue::Off));
+    client_log.connect(connection.sender.clone());
+    let parent_pid = cmd.parent_pid.or(process_id);
+    let res = run(config, connection, Arc::clone(&client_log), parent_pid);
+    // The connection closes once its last sender is dropped.
+    client_log.disconnect();
+    let exit_code = match res {
+        // The threads reading and writing the messages may wait on the
+        // connection for ever.
+        Err(e) if e.is::<parent_process::ClientExited>() => {
+            tracing::warn!("{e}: stopping");
+            std::process::exit(1);
+        }
+        res => res?,
+    };
     io_threads.join()?;
-    eprintln!("ColdFusion Language Server has stopped.");
+    tracing::info!("ColdFusion Language Server has stopped");
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
     Ok(())
 }
 
-fn run(config: Config, connection: Connection) -> anyhow::Result<()> {
+/// Runs the server until the client sends `exit`, returning the exit code.
+fn run(
+    config: Config,
+    connection: Connection,
+    client_log: Arc<ClientLog>,
+    parent_pid: Option<u32>,
+) -> anyhow::Result<i32> {
     #[cfg(windows)]
     unsafe {
         use winapi::um::processthreadsapi::*;
@@ -141,18 +258,42 @@ fn run(config: Config, connection: Connection) -> anyhow::Result<()> {
         SetThreadPriority(thread, thread_priority_above_normal);
     }
 
-    GlobalState::new(connection.sender, config).run(connection.receiver)
+    let mut state = GlobalState::new(connection.sender, config);
+    state.client_log = client_log;
+    if let Some(pid) = parent_pid {
+        state.client_process = Some((
+            pid,
+            parent_process::watch(pid, parent_process::POLL_INTERVAL),
+        ));
+    }
+    state.run(connection.receiver)
 }
 
 impl GlobalState {
-    fn run(mut self, inbox: Receiver<Message>) -> anyhow::Result<()> {
+    /// Handles the messages of the client until it sends `exit`. The exit
+    /// code is 0 if it asked to shut down first, 1 otherwise, as the spec
+    /// has it. Fails with [`parent_process::ClientExited`] if the client
+    /// process is gone first.
+    fn run(mut self, inbox: Receiver<Message>) -> anyhow::Result<i32> {
+        self.update_registrations();
+        if self.config.pull_configuration() {
+            handlers::notifications::fetch_configuration(&mut self);
+        }
+        self.start_indexing();
+
         while let Some(event) = self.next_event(&inbox) {
             if matches!(
                 &event,
                 Event::Lsp(Message::Notification(Notification { method, ..}))
                 if method == "exit"
             ) {
-                return Ok(());
+                self.cancel_tasks();
+                return Ok(if self.shutdown_requested { 0 } else { 1 });
+            }
+            if let Event::ClientExited = event {
+                self.cancel_tasks();
+                let pid = self.client_process.as_ref().map_or(0, |(pid, _)| *pid);
+                return Err(parent_process::ClientExited { pid }.into());
             }
 
             self.handle_event(event)?;
@@ -161,32 +302,220 @@ impl GlobalState {
         anyhow::bail!("Connection was terminated")
     }
 
+    /// Reads the CFML files of the workspace on the task pool, reporting the
+    /// progress as they arrive.
+    /// Any indexing running is cancelled first.
+    fn start_indexing(&mut self) {
+        let roots = self.config.workspace_roots().to_vec();
+        self.start_indexing_roots(roots);
+    }
+
+    /// Reads the CFML files under `roots`, like `start_indexing`, for the
+    /// folders added to the workspace.
+    fn start_indexing_roots(&mut self, roots: Vec<AbsPathBuf>) {
+        if let Some(token) = self.indexing.take() {
+            token.cancel();
+            self.report_progress(indexing::TITLE, Progress::End, None, None, false);
+        }
+        let token = ide::CancellationToken::default();
+        self.indexing = Some(token.clone());
+        self.indexing_cancelled = false;
+        self.indexing_generation += 1;
+        let generation = self.indexing_generation;
+        let cache_files: Vec<_> = roots
+            .iter()
+            .filter_map(|it| self.index_cache_file(it))
+            .collect();
+        let filter = self.config.files();
+        let typing = self.typing.clone();
+        self.task_pool
+            .handle
+            .spawn_with_sender(ThreadIntent::Background, move |sender| {
+                let send = |progress| {
+                    let task = global_state::Task::Indexing {
+                        generation,
+                        progress,
+                    };
+                    let _ = sender.send(task);
+                };
+                let mut cached = FxHashSet::default();
+                if !cache_files.is_empty() {
+                    let mut cache = index_cache::IndexCache::default();
+                    for path in &cache_files {
+                        cache.extend(index_cache::load(path));
+                    }
+                    cached.extend(cache.keys().copied());
+                    send(IndexingProgress::Cached(cache));
+                }
+                indexing::index(&roots, &filter, &cached, &token, &typing, &send);
+            });
+    }
+
     fn next_event(&self, inbox: &Receiver<Message>) -> Option<Event> {
+        let idle = match self.background_analysis_deadline() {
+            Some(deadline) => crossbeam_channel::at(deadline),
+            None => crossbeam_channel::never(),
+        };
+        let watchdog = match self.watchdog_deadline() {
+            Some(deadline) => crossbeam_channel::at(deadline),
+            None => crossbeam_channel::never(),
+        };
+        let request_progress = match self.request_progress_deadline() {
+            Some(deadline) => crossbeam_channel::at(deadline),
+            None => crossbeam_channel::never(),
+        };
+        let client_exited = match &self.client_process {
+            Some((_, it)) => it.clone(),
+            None => crossbeam_channel::never(),
+        };
         select! {
             recv(inbox) -> msg => msg.ok().map(Event::Lsp),
+            recv(self.task_pool.receiver) -> task => Some(Event::Task(task.unwrap())),
+            recv(idle) -> _ => Some(Event::Idle),
+            recv(watchdog) -> _ => Some(Event::Watchdog),
+            recv(request_progress) -> _ => Some(Event::RequestProgress),
+            recv(client_exited) -> _ => Some(Event::ClientExited),
         }
     }
 
     fn handle_event(&mut self, event: Event) -> anyhow::Result<()> {
         let loop_start = Instant::now();
+        let event_dbg_msg = format!("{event:?}");
+        let _p =
+            tracing::span!(tracing::Level::DEBUG, "handle_event", event = %event_dbg_msg).entered();
         match event {
             Event::Lsp(msg) => match msg {
-                Message::Request(req) => self.on_new_request(loop_start, req),
-                Message::Notification(notification) => self.on_notification(notification)?,
+                Message::Request(req) => {
+                    self.record_activity();
+                    self.on_new_request(loop_start, req)
+                }
+                Message::Notification(notification) => {
+                    self.record_activity();
+                    self.on_notification(notification)?
+                }
                 Message::Response(resp) => self.complete_request(resp),
             },
+            Event::Idle => self.start_background_analysis(),
+            Event::Watchdog => self.check_running_requests(),
+            Event::RequestProgress => self.report_request_progress(),
+            // `run` stops on it.
+            Event::ClientExited => (),
+            Event::Task(task) => {
+                self.handle_task(task);
+                // Handle the other finished tasks at once.
+                while let Ok(task) = self.task_pool.receiver.try_recv() {
+                    self.handle_task(task);
+                }
+            }
+        }
+        if self.process_changes() || self.full_diagnostics_requested {
+            self.update_diagnostics();
         }
+        self.update_status();
 
-        let _event_duration = loop_start.elapsed();
+        let event_duration = loop_start.elapsed();
+        if event_duration > Duration::from_millis(100) {
+            tracing::warn!("overly long loop turn took {event_duration:?}: {event_dbg_msg}");
+        }
+        self.profile.lock().record_loop_turn(event_duration);
+        self.log_profile();
         Ok(())
     }
 
+    /// Logs the timings of the requests, if the settings ask for it and the
+    /// interval passed.
+    fn log_profile(&mut self) {
+        let Some(interval) = self.config.profiling_log_interval() else {
+            return;
+        };
+        if self.profile_logged.elapsed() < interval {
+            return;
+        }
+        self.profile_logged = Instant::now();
+        if let Some(report) = self.profile.lock().report_to_log() {
+            tracing::info!("request timings:\n{report}");
+        }
+    }
+
+    fn handle_task(&mut self, task: global_state::Task) {
+        match task {
+            global_state::Task::Response(response) => self.respond(response),
+            global_state::Task::Indexing {
+                generation,
+                progress,
+            } => {
+                if generation == self.indexing_generation {
+                    self.handle_indexing_progress(progress);
+                }
+            }
+            global_state::Task::BackgroundAnalysis {
+                generation,
+                diagnostics,
+            } => self.handle_background_analysis(generation, diagnostics),
+        }
+    }
+
+    fn handle_indexing_progress(&mut self, progress: IndexingProgress) {
+        let (state, done, total) = match progress {
+            IndexingProgress::Cached(cache) => {
+                self.index_cache = cache;
+                return;
+            }
+            IndexingProgress::Begin { total } => (Progress::Begin, 0, total),
+            IndexingProgress::Report {
+                done,
+                total,
+                files,
+                indexes,
+            } => {
+                self.index_cache.extend(indexes);
+                self.load_files(files);
+                (Progress::Report, done, total)
+            }
+            IndexingProgress::End => {
+                // The files of the last batches take their index before the
+                // indexes go.
+                if self.process_changes() {
+                    self.update_diagnostics();
+                }
+                self.indexing_cancelled = self.indexing.take().is_some_and(|it| it.is_cancelled());
+                self.index_cache = Default::default();
+                if !self.indexing_cancelled {
+                    self.save_index_cache();
+                }
+                self.report_progress(indexing::TITLE, Progress::End, None, None, false);
+                return;
+            }
+        };
+        let fraction = if total == 0 {
+            1.0
+        } else {
+            done as f64 / total as f64
+        };
+        self.report_progress(
+            indexing::TITLE,
+            state,
+            Some(format!("{done}/{total} files")),
+            Some(fraction),
+            true,
+        );
+    }
+
     fn on_new_request(&mut self, request_received: Instant, req: Request) {
         self.register_request(&req, request_received);
         self.on_request(req);
     }
 
     fn on_request(&mut self, req: Request) {
+        // Only `exit` is expected after a shutdown.
+        if self.shutdown_requested {
+            self.respond(Response::new_err(
+                req.id,
+                ErrorCode::InvalidRequest as i32,
+                "Shutdown already requested".to_owned(),
+            ));
+            return;
+        }
         let mut dispatcher = RequestDispatcher {
             req: Some(req),
             global_state: self,
@@ -197,26 +526,25 @@ impl GlobalState {
             Ok(())
         });
 
-        match &mut dispatcher {
-            RequestDispatcher {
-                req: Some(req),
-                global_state: this,
-            } if this.shutdown_requested => {
-                let invalid_request = ErrorCode::InvalidRequest as i32;
-                this.respond(Response::new_err(
-                    req.id.clone(),
-                    invalid_request,
-                    "Shutdown already requested".to_owned(),
-                ));
-            }
-            _ => (),
-        };
-
         use handlers::request as handlers;
         use lsp_types::request as lsp_request;
 
         dispatcher
-            .on_sync_mut::<lsp_request::Completion>(handlers::handle_completion)
+            .on_latency_sensitive::<lsp_request::Completion>(handlers::handle_completion)
+            .on::<lsp::ext::CodeActionRequest>(handlers::handle_code_action)
+            .on::<lsp_request::Formatting>(handlers::handle_formatting)
+            .on_with_progress::<lsp::ext::ChangeSignature>(
+                "Updating calls",
+                handlers::handle_change_signature,
+            )
+            .on_with_progress::<lsp::ext::MoveComponent>(
+                "Updating references",
+                handlers::handle_move_component,
+            )
+            .on::<lsp::ext::ExpandIncludes>(handlers::handle_expand_includes)
+            .on::<lsp::ext::MemoryUsage>(handlers::handle_memory_usage)
+            .on_sync_mut::<lsp::ext::AnalyzerStatus>(handlers::handle_analyzer_status)
+            .on_sync_mut::<lsp::ext::ReloadWorkspace>(handlers::handle_reload_workspace)
             .finish();
     }
 
@@ -224,6 +552,10 @@ impl GlobalState {
         use handlers::notifications as handlers;
         use lsp_types::notification as notifs;
 
+        if self.shutdown_requested {
+            return Ok(());
+        }
+
         let mut dispatcher = NotificationDispatcher {
             notification: Some(notification),
             global_state: self,
@@ -233,9 +565,23 @@ impl GlobalState {
             .on_sync_mut::<notifs::Cancel>(handlers::handle_cancel)?
             .on_sync_mut::<notifs::DidOpenTextDocument>(handlers::handle_did_open_text_document)?
             .on_sync_mut::<notifs::DidCloseTextDocument>(handlers::handle_did_close_text_document)?
+            .on_sync_mut::<notifs::DidSaveTextDocument>(handlers::handle_did_save_text_document)?
             .on_sync_mut::<notifs::DidChangeTextDocument>(
                 handlers::handle_did_change_text_document,
             )?
+            .on_sync_mut::<notifs::DidChangeConfiguration>(
+                handlers::handle_did_change_configuration,
+            )?
+            .on_sync_mut::<notifs::WorkDoneProgressCancel>(
+                handlers::handle_work_done_progress_cancel,
+            )?
+            .on_sync_mut::<notifs::SetTrace>(handlers::handle_set_trace)?
+            .on_sync_mut::<notifs::DidChangeWorkspaceFolders>(
+                handlers::handle_did_change_workspace_folders,
+            )?
+            .on_sync_mut::<notifs::DidChangeWatchedFiles>(
+                handlers::handle_did_change_watched_files,
+            )?
             .finish();
         Ok(())
     }
@@ -293,3 +639,78 @@ fn patch_path_prefix_works() {
         PathBuf::from(r"\\?\C:\foo\bar")
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_with(messages: Vec<Message>) -> (anyhow::Result<i32>, Vec<Message>) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp/cfml-run").unwrap();
+        let mut config = Config::new(root.clone(), Default::default(), vec![root]);
+        config
+            .update(serde_json::json!({ "cache": { "enable": false } }))
+            .unwrap();
+        let (inbox_sender, inbox) = crossbeam_channel::unbounded();
+        for message in messages {
+            inbox_sender.send(message).unwrap();
+        }
+        drop(inbox_sender);
+        let res = GlobalState::new(sender, config).run(inbox);
+        (res, receiver.try_iter().collect())
+    }
+
+    fn request(id: i32, method: &str) -> Message {
+        Request::new(id.into(), method.to_string(), serde_json::Value::Null).into()
+    }
+
+    fn exit() -> Message {
+        Notification::new("exit".to_string(), serde_json::Value::Null).into()
+    }
+
+    #[test]
+    fn test_shutdown_then_exit() {
+        let (res, sent) = run_with(vec![
+            request(1, "shutdown"),
+            request(2, "cfml/memoryUsage"),
+            exit(),
+        ]);
+        assert_eq!(res.unwrap(), 0);
+        let responses: Vec<_> = sent
+            .into_iter()
+            .filter_map(|it| match it {
+                Message::Response(it) => Some((it.id, it.error.map(|e| e.code))),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            responses,
+            [
+                (1.into(), None),
+                (2.into(), Some(ErrorCode::InvalidRequest as i32)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_client_exited() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let root = AbsPathBuf::try_from("/tmp/cfml-run").unwrap();
+        let config = Config::new(root.clone(), Default::default(), vec![root]);
+        let mut state = GlobalState::new(sender, config);
+        let (exited, client_exited) = crossbeam_channel::bounded(1);
+        state.client_process = Some((4242, client_exited));
+        exited.send(()).unwrap();
+        let (_inbox_sender, inbox) = crossbeam_channel::unbounded();
+        let e = state.run(inbox).unwrap_err();
+        assert_eq!(e.to_string(), "the client process 4242 exited");
+    }
+
+    #[test]
+    fn test_exit_without_shutdown() {
+        let (res, _) = run_with(vec![exit()]);
+        assert_eq!(res.unwrap(), 1);
+        let (res, _) = run_with(vec![request(1, "shutdown")]);
+        assert!(res.is_err());
+    }
+}
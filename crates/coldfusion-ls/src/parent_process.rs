@@ -0,0 +1,94 @@
+//! Watches the process of the client, given in `initialize` or with
+//! `--parent-pid`, so that the server stops once the editor is gone, even
+//! when it crashed without closing the connection, rather than piling up.
+use std::{fmt, thread, time::Duration};
+
+use crossbeam_channel::Receiver;
+
+/// How often the process is looked at.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Why the server stopped without `exit`.
+#[derive(Debug)]
+pub(crate) struct ClientExited {
+    pub(crate) pid: u32,
+}
+
+impl fmt::Display for ClientExited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the client process {} exited", self.pid)
+    }
+}
+
+impl std::error::Error for ClientExited {}
+
+/// A channel receiving once the process `pid` is gone, which is looked at
+/// every `interval`.
+pub(crate) fn watch(pid: u32, interval: Duration) -> Receiver<()> {
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    thread::Builder::new()
+        .name("ParentProcessWatcher".to_string())
+        .spawn(move || {
+            while is_running(pid) {
+                thread::sleep(interval);
+            }
+            let _ = sender.send(());
+        })
+        .expect("failed to spawn the thread watching the client process");
+    receiver
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    std::path::Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_running(pid: u32) -> bool {
+    use std::process::{Command, Stdio};
+
+    // Signal 0 checks that the process exists without disturbing it.
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_or(true, |it| it.success())
+}
+
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"])
+        .output();
+    // A process that can't be looked at is assumed alive.
+    output.map_or(true, |it| {
+        String::from_utf8_lossy(&it.stdout).contains(&format!("\"{pid}\""))
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn test_watch() {
+        assert!(is_running(process::id()));
+        let mut child = process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        assert!(!is_running(pid));
+
+        let exited = watch(pid, Duration::from_millis(10));
+        assert_eq!(exited.recv_timeout(Duration::from_secs(5)), Ok(()));
+        let running = watch(process::id(), Duration::from_millis(10));
+        assert!(running.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! A pool of threads running request handlers off the main loop, which keeps
+//! processing notifications meanwhile. Handlers send their results back to
+//! the loop through a channel.
+//!
+//! Tasks run by priority: those the user waits on while typing, then the
+//! other requests, then the background work. The background work never runs
+//! on the first thread, which stays free for requests however long indexing
+//! takes, and pauses while the user types.
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{Receiver, Select, Sender, TryRecvError};
+use ide::CancellationToken;
+use parking_lot::Mutex;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// How urgent a task is. Latency sensitive ones, like completions, run
+/// before any queued worker task, and these before any background task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ThreadIntent {
+    /// Work nobody waits on, like indexing and analyzing the files which
+    /// aren't open.
+    Background,
+    Worker,
+    LatencySensitive,
+}
+
+pub(crate) struct TaskPool<T> {
+    sender: Sender<T>,
+    pool: Pool,
+}
+
+impl<T: Send + 'static> TaskPool<T> {
+    pub(crate) fn new_with_threads(sender: Sender<T>, threads: usize) -> TaskPool<T> {
+        TaskPool {
+            sender,
+            pool: Pool::new(threads),
+        }
+    }
+
+    /// Runs `task` on the pool, sending its result to the main loop.
+    pub(crate) fn spawn<F>(&self, intent: ThreadIntent, task: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let sender = self.sender.clone();
+        self.pool.spawn(
+            intent,
+            Box::new(move || {
+                // The loop only stops listening when shutting down.
+                let _ = sender.send(task());
+            }),
+        );
+    }
+
+    /// Runs `task` on the pool, which sends any number of results to the
+    /// main loop, e.g. to report its progress.
+    pub(crate) fn spawn_with_sender<F>(&self, intent: ThreadIntent, task: F)
+    where
+        F: FnOnce(Sender<T>) + Send + 'static,
+    {
+        let sender = self.sender.clone();
+        self.pool.spawn(intent, Box::new(move || task(sender)));
+    }
+}
+
+/// How long after a keystroke the user still counts as typing.
+const TYPING_PAUSE: Duration = Duration::from_millis(500);
+
+/// When the user last typed, shared with the background tasks for them to
+/// pause meanwhile.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Typing(Arc<Mutex<Option<Instant>>>);
+
+impl Typing {
+    pub(crate) fn record(&self) {
+        *self.0.lock() = Some(Instant::now());
+    }
+
+    /// How long the user still counts as typing, if they do.
+    fn remaining(&self) -> Option<Duration> {
+        let last = (*self.0.lock())?;
+        TYPING_PAUSE
+            .checked_sub(last.elapsed())
+            .filter(|it| !it.is_zero())
+    }
+
+    /// Blocks while the user types, or until `token` is cancelled.
+    pub(crate) fn wait(&self, token: &CancellationToken) {
+        while let Some(remaining) = self.remaining() {
+            if token.is_cancelled() {
+                return;
+            }
+            thread::sleep(remaining.min(Duration::from_millis(50)));
+        }
+    }
+}
+
+struct Pool {
+    /// The senders of the jobs by intent, from the most urgent, `None` once
+    /// dropped, which stops the threads.
+    senders: Option<[Sender<Job>; 3]>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl Pool {
+    fn new(threads: usize) -> Pool {
+        let (urgent_sender, urgent_receiver) = crossbeam_channel::unbounded::<Job>();
+        let (worker_sender, worker_receiver) = crossbeam_channel::unbounded::<Job>();
+        let (background_sender, background_receiver) = crossbeam_channel::unbounded::<Job>();
+        let threads = threads.max(1);
+        let threads = (0..threads)
+            .map(|idx| {
+                let mut receivers = vec![urgent_receiver.clone(), worker_receiver.clone()];
+                if idx > 0 || threads == 1 {
+                    receivers.push(background_receiver.clone());
+                }
+                thread::Builder::new()
+                    .name(format!("Worker{idx}"))
+                    .spawn(move || run_jobs(&receivers))
+                    .expect("failed to spawn a worker thread")
+            })
+            .collect();
+        Pool {
+            senders: Some([urgent_sender, worker_sender, background_sender]),
+            threads,
+        }
+    }
+
+    fn spawn(&self, intent: ThreadIntent, job: Job) {
+        let Some([urgent, worker, background]) = &self.senders else {
+            return;
+        };
+        let sender = match intent {
+            ThreadIntent::Background => background,
+            ThreadIntent::Worker => worker,
+            ThreadIntent::LatencySensitive => urgent,
+        };
+        sender.send(job).expect("worker threads stopped");
+    }
+}
+
+/// Runs jobs until the channels of `receivers` are closed, taking the jobs
+/// of the first ones first.
+fn run_jobs(receivers: &[Receiver<Job>]) {
+    loop {
+        let mut closed = 0;
+        let job = receivers.iter().find_map(|it| match it.try_recv() {
+            Ok(job) => Some(job),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                closed += 1;
+                None
+            }
+        });
+        match job {
+            Some(job) => job(),
+            None if closed == receivers.len() => return,
+            // Waits for a job, to take the most urgent one then.
+            None => {
+                let mut select = Select::new();
+                for receiver in receivers {
+                    select.recv(receiver);
+                }
+                select.ready();
+            }
+        }
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.senders = None;
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_pool() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let pool = TaskPool::new_with_threads(sender, 2);
+        for i in 0..4 {
+            pool.spawn(ThreadIntent::Worker, move || i * 2);
+        }
+        pool.spawn(ThreadIntent::LatencySensitive, || 100);
+        let mut results: Vec<i32> = receiver.iter().take(5).collect();
+        results.sort();
+        assert_eq!(results, [0, 2, 4, 6, 100]);
+    }
+
+    #[test]
+    fn test_background_tasks() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let pool = TaskPool::new_with_threads(sender, 2);
+        let (release, released) = crossbeam_channel::unbounded::<()>();
+        for i in 0..2 {
+            let released = released.clone();
+            pool.spawn(ThreadIntent::Background, move || {
+                // Until `release` is dropped.
+                let _ = released.recv();
+                i
+            });
+        }
+        // The background tasks leave a thread to the requests.
+        pool.spawn(ThreadIntent::Worker, || 100);
+        assert_eq!(receiver.recv(), Ok(100));
+        drop(release);
+        let mut results: Vec<i32> = receiver.iter().take(2).collect();
+        results.sort();
+        assert_eq!(results, [0, 1]);
+    }
+
+    #[test]
+    fn test_typing() {
+        let typing = Typing::default();
+        let token = CancellationToken::default();
+        typing.wait(&token);
+        typing.record();
+        let start = Instant::now();
+        typing.wait(&token);
+        assert!(start.elapsed() >= Duration::from_millis(400));
+        assert_eq!(typing.remaining(), None);
+
+        typing.record();
+        token.cancel();
+        let start = Instant::now();
+        typing.wait(&token);
+        assert!(start.elapsed() < TYPING_PAUSE);
+    }
+}
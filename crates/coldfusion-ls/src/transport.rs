@@ -0,0 +1,133 @@
+//! The channels the server talks to its client over: standard input and
+//! output, a TCP socket, or a named pipe, for the editors which start the
+//! server apart from themselves, e.g. in a container.
+use std::{
+    io::{self, BufReader, Read, Write},
+    path::PathBuf,
+    thread,
+};
+
+use lsp_server::{Connection, Message};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Transport {
+    Stdio,
+    /// A socket on the loopback interface, on which the server waits for the
+    /// client with `listen`, or connects to it otherwise.
+    Tcp {
+        port: u16,
+        listen: bool,
+    },
+    /// A Unix domain socket the client listens on.
+    Pipe(PathBuf),
+}
+
+/// The threads reading and writing the messages, which stop once the client
+/// exits.
+pub(crate) enum IoThreads {
+    Lsp(lsp_server::IoThreads),
+    Stream {
+        reader: thread::JoinHandle<io::Result<()>>,
+        writer: thread::JoinHandle<io::Result<()>>,
+    },
+}
+
+impl IoThreads {
+    pub(crate) fn join(self) -> io::Result<()> {
+        match self {
+            IoThreads::Lsp(it) => it.join(),
+            IoThreads::Stream { reader, writer } => {
+                reader.join().expect("reader thread panicked")?;
+                writer.join().expect("writer thread panicked")
+            }
+        }
+    }
+}
+
+/// Opens the connection to the client over `transport`, waiting for the
+/// client when the server listens.
+pub(crate) fn connect(transport: &Transport) -> io::Result<(Connection, IoThreads)> {
+    let (connection, io_threads) = match transport {
+        Transport::Stdio => Connection::stdio(),
+        Transport::Tcp { port, listen: true } => Connection::listen(("127.0.0.1", *port))?,
+        Transport::Tcp {
+            port,
+            listen: false,
+        } => Connection::connect(("127.0.0.1", *port))?,
+        Transport::Pipe(path) => return connect_pipe(path),
+    };
+    Ok((connection, IoThreads::Lsp(io_threads)))
+}
+
+#[cfg(unix)]
+fn connect_pipe(path: &std::path::Path) -> io::Result<(Connection, IoThreads)> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    Ok(stream_transport(stream.try_clone()?, stream))
+}
+
+#[cfg(not(unix))]
+fn connect_pipe(_: &std::path::Path) -> io::Result<(Connection, IoThreads)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "pipes are only supported on Unix, use --port instead",
+    ))
+}
+
+/// Reads messages from `reader` and writes them to `writer`, each on its
+/// own thread, as `lsp_server` does for sockets.
+fn stream_transport(
+    reader: impl Read + Send + 'static,
+    mut writer: impl Write + Send + 'static,
+) -> (Connection, IoThreads) {
+    let (reader_sender, receiver) = crossbeam_channel::bounded::<Message>(0);
+    let reader = thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        while let Some(msg) = Message::read(&mut reader)? {
+            let is_exit = matches!(&msg, Message::Notification(it) if it.method == "exit");
+            if reader_sender.send(msg).is_err() || is_exit {
+                break;
+            }
+        }
+        Ok(())
+    });
+    let (sender, writer_receiver) = crossbeam_channel::bounded::<Message>(0);
+    let writer = thread::spawn(move || {
+        writer_receiver
+            .into_iter()
+            .try_for_each(|it| it.write(&mut writer))
+    });
+    (
+        Connection { sender, receiver },
+        IoThreads::Stream { reader, writer },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pipe() {
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join("cfml-transport.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let (connection, io_threads) = connect(&Transport::Pipe(path)).unwrap();
+        let (mut client, _) = listener.accept().unwrap();
+
+        let exit = lsp_server::Notification::new("exit".to_string(), ());
+        Message::Notification(exit).write(&mut client).unwrap();
+        let msg = connection.receiver.recv().unwrap();
+        assert!(matches!(msg, Message::Notification(it) if it.method == "exit"));
+
+        let initialized = lsp_server::Notification::new("initialized".to_string(), ());
+        connection.sender.send(initialized.into()).unwrap();
+        let msg = Message::read(&mut BufReader::new(&mut client)).unwrap();
+        assert!(matches!(msg, Some(Message::Notification(it)) if it.method == "initialized"));
+
+        drop(connection);
+        io_threads.join().unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+use syntax::TextRange;
+
+use crate::{
+    assists::{AssistContext, AssistId, AssistKind, Assists},
+    var_scope::{functions, unscoped_assignments, FunctionLike, ScopeFix, UnscopedAssignment},
+};
+
+// Assist: add_var_scope
+//
+// Declares a variable assigned inside a function as local.
+//
+// ```
+// function f() { $0x = 1; }
+// ```
+// ->
+// ```
+// function f() { var x = 1; }
+// ```
+pub(crate) fn add_var_scope(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let func = ctx
+        .covering_element()
+        .ancestors()
+        .find_map(FunctionLike::cast)?;
+    let assignment = unscoped_assignments(&func)
+        .into_iter()
+        .find(|it| it.target.contains_range(ctx.range()))?;
+    let label = match assignment.fix {
+        ScopeFix::Var => format!("Declare `{}` with `var`", assignment.name),
+        ScopeFix::Local => format!("Scope `{0}` as `local.{0}`", assignment.name),
+    };
+    acc.add(
+        AssistId("add_var_scope", AssistKind::QuickFix),
+        label,
+        assignment.name_range,
+        |builder| {
+            builder.insert(
+                assignment.fix_offset(),
+                assignment.fix.insert_text().to_string(),
+            )
+        },
+    )
+}
+
+// Assist: add_var_scope_all
+//
+// Declares every unscoped variable of the enclosing function, or of the whole
+// file, as local.
+pub(crate) fn add_var_scope_all(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let id = AssistId("add_var_scope_all", AssistKind::Source);
+
+    let enclosing = ctx
+        .covering_element()
+        .ancestors()
+        .find_map(FunctionLike::cast);
+    if let Some(func) = &enclosing {
+        let assignments = unscoped_assignments(func);
+        if !assignments.is_empty() {
+            let label = match func.name() {
+                Some(name) => format!("Add missing `var` scoping in function `{name}`"),
+                None => "Add missing `var` scoping in function".to_string(),
+            };
+            let target = func.syntax().text_range();
+            acc.add(id, label, target, |builder| {
+                for it in &assignments {
+                    builder.insert(it.fix_offset(), it.fix.insert_text().to_string());
+                }
+            });
+        }
+    }
+
+    let all: Vec<UnscopedAssignment> = functions(ctx.root())
+        .flat_map(|it| unscoped_assignments(&it))
+        .collect();
+    // Not worth a second entry if the file has just the one function.
+    let in_other_functions = enclosing.is_none_or(|func| {
+        let range = func.syntax().text_range();
+        all.iter().any(|it| !range.contains_range(it.target))
+    });
+    if all.is_empty() || !in_other_functions {
+        return None;
+    }
+    let id = AssistId("add_var_scope_file", AssistKind::Source);
+    let target = TextRange::new(0, ctx.root().text_range().end());
+    acc.add(id, "Add missing `var` scoping in file", target, |builder| {
+        for it in &all {
+            builder.insert(it.fix_offset(), it.fix.insert_text().to_string());
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn add_var_to_script_assignment() {
+        check_assist(
+            "Declare `x` with `var`",
+            "component { function f() { $0x = 1; return x; } }",
+            "component { function f() { var x = 1; return x; } }",
+        );
+    }
+
+    #[test]
+    fn add_var_to_cfset() {
+        check_assist(
+            "Declare `total` with `var`",
+            "<cffunction name=\"f\"><cfset to$0tal = 0></cffunction>",
+            "<cffunction name=\"f\"><cfset var total = 0></cffunction>",
+        );
+    }
+
+    #[test]
+    fn add_var_to_for_in() {
+        check_assist(
+            "Declare `k` with `var`",
+            "component { function f(s) { for ($0k in s) {} } }",
+            "component { function f(s) { for (var k in s) {} } }",
+        );
+    }
+
+    #[test]
+    fn scope_tag_attribute_as_local() {
+        check_assist(
+            "Scope `q` as `local.q`",
+            "<cffunction name=\"f\"><cfquery $0name=\"q\">select 1</cfquery></cffunction>",
+            "<cffunction name=\"f\"><cfquery name=\"local.q\">select 1</cfquery></cffunction>",
+        );
+    }
+
+    #[test]
+    fn scope_nested_assignment_as_local() {
+        check_assist(
+            "Scope `n` as `local.n`",
+            "component { function f() { $0n += 1; } }",
+            "component { function f() { local.n += 1; } }",
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_declared_variables() {
+        check_assist_not_applicable(
+            "add_var_scope",
+            "component { function f(a) { var b = 1; $0a = 2; } }",
+        );
+        check_assist_not_applicable(
+            "add_var_scope",
+            "component { function f() { $0local.a = 2; } }",
+        );
+        check_assist_not_applicable("add_var_scope", "<cfset $0x = 1>");
+    }
+
+    #[test]
+    fn fix_function() {
+        check_assist(
+            "Add missing `var` scoping in function `f`",
+            "component {
+    function f() {
+        $0a = 1;
+        for (i = 1; i < a; i++) { b = i; }
+        a = 2;
+    }
+}",
+            "component {
+    function f() {
+        var a = 1;
+        for (var i = 1; i < a; i++) { var b = i; }
+        a = 2;
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn fix_file() {
+        check_assist(
+            "Add missing `var` scoping in file",
+            "component {
+    function f() { $0a = 1; }
+    function g() { b = 1; }
+}",
+            "component {
+    function f() { var a = 1; }
+    function g() { var b = 1; }
+}",
+        );
+    }
+
+    #[test]
+    fn fix_file_not_offered_for_single_function() {
+        check_assist_not_applicable(
+            "add_var_scope_file",
+            "component { function f() { $0a = 1; } }",
+        );
+    }
+}
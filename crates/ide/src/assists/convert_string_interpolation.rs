@@ -0,0 +1,238 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::assists::{AssistContext, AssistId, AssistKind, Assists};
+
+// Assist: convert_to_string_interpolation
+//
+// Turns a concatenation involving strings into a single string which
+// interpolates the other operands.
+//
+// ```
+// greeting = "Hello " $0& name & "!";
+// ```
+// ->
+// ```
+// greeting = "Hello #name#!";
+// ```
+pub(crate) fn convert_to_string_interpolation(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let concat = ctx.covering_element().ancestors().find(is_concat)?;
+    // The whole chain: `a & b & c` is `(a & b) & c`.
+    let concat = concat.ancestors().take_while(is_concat).last()?;
+    if concat.parent().is_some_and(|it| it.kind() == ATTRIBUTE) {
+        return None;
+    }
+    let mut operands = Vec::new();
+    flatten(&concat, &mut operands);
+    let quote = operands
+        .iter()
+        .find_map(|it| ast::StringLit::cast(it.clone()))?
+        .quote_char();
+
+    let mut text = String::new();
+    for operand in &operands {
+        match ast::StringLit::cast(operand.clone()) {
+            Some(string) => {
+                let own_quote = string.quote_char();
+                for element in string.syntax().children_with_tokens() {
+                    match element {
+                        SyntaxElement::Token(token) if token.kind() == QUOTE => {}
+                        SyntaxElement::Token(token) if own_quote != quote => {
+                            let doubled = format!("{own_quote}{own_quote}");
+                            let unescaped = token.text().replace(&doubled, &own_quote.to_string());
+                            text.push_str(&unescaped.replace(quote, &format!("{quote}{quote}")));
+                        }
+                        SyntaxElement::Token(token) => text.push_str(token.text()),
+                        SyntaxElement::Node(node) => {
+                            if node.text().contains(quote) {
+                                return None;
+                            }
+                            text.push_str(&node.text());
+                        }
+                    }
+                }
+            }
+            None => {
+                let expr = ast::Expr::cast(operand.clone())?.unwrapped();
+                let expr = expr.syntax().text();
+                if expr.contains(quote) {
+                    return None;
+                }
+                text.push_str(&format!("#{expr}#"));
+            }
+        }
+    }
+
+    acc.add(
+        AssistId(
+            "convert_to_string_interpolation",
+            AssistKind::RefactorRewrite,
+        ),
+        "Convert to string interpolation",
+        concat.text_range(),
+        |builder| builder.replace(concat.text_range(), format!("{quote}{text}{quote}")),
+    )
+}
+
+// Assist: convert_to_string_concatenation
+//
+// Turns a string with interpolations into a concatenation of its parts.
+//
+// ```
+// greeting = "Hello $0#name#!";
+// ```
+// ->
+// ```
+// greeting = "Hello " & name & "!";
+// ```
+pub(crate) fn convert_to_string_concatenation(
+    acc: &mut Assists,
+    ctx: &AssistContext,
+) -> Option<()> {
+    let string = ctx
+        .covering_element()
+        .ancestors()
+        .find_map(ast::StringLit::cast)?;
+    let parent = string.syntax().parent()?;
+    if string.is_static() || parent.kind() == ATTRIBUTE {
+        return None;
+    }
+    let quote = string.quote_char();
+    let mut operands = Vec::new();
+    let mut has_text = false;
+    for element in string.syntax().children_with_tokens() {
+        match element {
+            SyntaxElement::Token(token) if token.kind() == STRING_TEXT => {
+                has_text = true;
+                operands.push(format!("{quote}{}{quote}", token.text()));
+            }
+            SyntaxElement::Token(_) => {}
+            SyntaxElement::Node(node) => {
+                let expr = ast::HashExpr::cast(node)?.expr()?;
+                let text = expr.syntax().text();
+                operands.push(if needs_parens(expr.syntax()) {
+                    format!("({text})")
+                } else {
+                    text
+                });
+            }
+        }
+    }
+    // `"#x#"` converts `x` to a string, which a lone `x` wouldn't.
+    if !has_text {
+        return None;
+    }
+    let mut text = operands.join(" & ");
+    // The concatenation binds looser than the operators around the string.
+    let binds_tighter = match parent.kind() {
+        BIN_EXPR => !is_concat(&parent),
+        FIELD_EXPR | INDEX_EXPR | CALL_EXPR | PREFIX_EXPR | POSTFIX_EXPR => true,
+        _ => false,
+    };
+    if binds_tighter {
+        text = format!("({text})");
+    }
+
+    let target = string.syntax().text_range();
+    acc.add(
+        AssistId(
+            "convert_to_string_concatenation",
+            AssistKind::RefactorRewrite,
+        ),
+        "Convert to string concatenation",
+        target,
+        |builder| builder.replace(target, text),
+    )
+}
+
+fn is_concat(node: &SyntaxNode) -> bool {
+    ast::BinExpr::cast(node.clone()).is_some_and(|it| it.op_text() == "&")
+}
+
+/// The operands of a chain of `&`, from left to right.
+fn flatten(node: &SyntaxNode, acc: &mut Vec<SyntaxNode>) {
+    if is_concat(node) {
+        for child in node.children() {
+            flatten(&child, acc);
+        }
+    } else {
+        acc.push(node.clone());
+    }
+}
+
+/// Returns `true` if `expr` binds looser than `&`.
+fn needs_parens(expr: &SyntaxNode) -> bool {
+    match expr.kind() {
+        BIN_EXPR => {
+            let op = ast::BinExpr::cast(expr.clone()).map(|it| it.op_text().to_ascii_lowercase());
+            !op.is_some_and(|it| {
+                matches!(
+                    it.as_str(),
+                    "+" | "-" | "*" | "/" | "\\" | "%" | "^" | "mod" | "&"
+                )
+            })
+        }
+        TERNARY_EXPR | ASSIGN_EXPR | CLOSURE_EXPR | ARROW_EXPR => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn concatenation_to_interpolation() {
+        check_assist(
+            "Convert to string interpolation",
+            r#"<cfscript>x = "Hello " $0& user.name & '!';</cfscript>"#,
+            r#"<cfscript>x = "Hello #user.name#!";</cfscript>"#,
+        );
+        check_assist(
+            "Convert to string interpolation",
+            r##"<cfset x = 'It''s ##' & f(1, "a") & ' "" ' & "#$0n#">"##,
+            r##"<cfset x = 'It''s ###f(1, "a")# "" #n#'>"##,
+        );
+    }
+
+    #[test]
+    fn interpolation_to_concatenation() {
+        check_assist(
+            "Convert to string concatenation",
+            r##"<cfscript>x = "Hello $0#user.name#, ##1 ""#a ? b : c#""";</cfscript>"##,
+            r##"<cfscript>x = "Hello " & user.name & ", ##1 """ & (a ? b : c) & """";</cfscript>"##,
+        );
+        check_assist(
+            "Convert to string concatenation",
+            r##"<cfscript>f("#$0n# items".len());</cfscript>"##,
+            r##"<cfscript>f((n & " items").len());</cfscript>"##,
+        );
+    }
+
+    #[test]
+    fn not_applicable() {
+        check_assist_not_applicable(
+            "convert_to_string_interpolation",
+            "<cfscript>x = a $0& b;</cfscript>",
+        );
+        check_assist_not_applicable(
+            "convert_to_string_interpolation",
+            r#"<cfscript>x = "a" $0& f("b");</cfscript>"#,
+        );
+        check_assist_not_applicable(
+            "convert_to_string_concatenation",
+            r##"<cfscript>x = "$0#a#";</cfscript>"##,
+        );
+        check_assist_not_applicable(
+            "convert_to_string_concatenation",
+            r##"<cfoutput query="$0#q#x"></cfoutput>"##,
+        );
+    }
+}
@@ -0,0 +1,434 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+};
+
+use crate::{
+    assists::{
+        utils::{attribute_expr, reindent},
+        AssistContext, AssistId, AssistKind, Assists,
+    },
+    component::{indent_unit, line_indent},
+};
+
+// Assist: convert_to_cfscript
+//
+// Rewrites a `<cffunction>` as a cfscript function.
+//
+// ```
+// <cffunction $0name="f" returntype="numeric">
+//     <cfargument name="x" type="numeric" required="true">
+//     <cfreturn x * 2>
+// </cffunction>
+// ```
+// ->
+// ```
+// <cfscript>
+//     numeric function f(required numeric x) {
+//         return x * 2;
+//     }
+// </cfscript>
+// ```
+pub(crate) fn convert_to_cfscript(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let tag = ctx
+        .covering_element()
+        .ancestors()
+        .filter_map(ast::Tag::cast)
+        .find(|it| it.name_lower() == "cffunction")?;
+    let open = tag.open()?;
+    if !open.syntax().text_range().contains_range(ctx.range()) || tag.close().is_none() {
+        return None;
+    }
+
+    let indent = line_indent(tag.syntax());
+    // Follow the indentation of the function body, if it has one.
+    let unit = tag
+        .child_tags()
+        .next()
+        .and_then(|it| {
+            let unit = line_indent(it.syntax()).strip_prefix(&indent)?.to_string();
+            (!unit.is_empty()).then_some(unit)
+        })
+        .unwrap_or_else(|| indent_unit(&indent).to_string());
+    let fn_indent = format!("{indent}{unit}");
+    let mut body = Converter {
+        lines: Vec::new(),
+        unit: unit.clone(),
+    };
+    body.items(&tag, 2)?;
+
+    let mut text = "<cfscript>\n".to_string();
+    if let Some(hint) = tag.attribute("hint").and_then(|it| it.value()) {
+        text.push_str(&format!("{fn_indent}/**\n"));
+        for line in hint.lines() {
+            text.push_str(&format!("{fn_indent} * {}\n", line.trim()).replace(" \n", "\n"));
+        }
+        text.push_str(&format!("{fn_indent} */\n"));
+    }
+    text.push_str(&fn_indent);
+    text.push_str(&signature(&tag)?);
+    text.push_str(" {\n");
+    for line in &body.lines {
+        if !line.is_empty() {
+            text.push_str(&indent);
+        }
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.push_str(&format!("{fn_indent}}}\n{indent}</cfscript>"));
+
+    acc.add(
+        AssistId("convert_to_cfscript", AssistKind::RefactorRewrite),
+        "Convert function to cfscript",
+        tag.syntax().text_range(),
+        |builder| builder.replace(tag.syntax().text_range(), text),
+    )
+}
+
+/// `public string function f(required string a) output="false"`.
+fn signature(tag: &ast::Tag) -> Option<String> {
+    let mut res = String::new();
+    if let Some(access) = tag.attribute("access").and_then(|it| it.value()) {
+        res.push_str(&access);
+        res.push(' ');
+    }
+    if let Some(ty) = tag.attribute("returntype").and_then(|it| it.value()) {
+        res.push_str(&ty);
+        res.push(' ');
+    }
+    let name = tag.attribute("name")?.value()?;
+    let params = tag
+        .child_tags()
+        .filter(|it| it.name_lower() == "cfargument")
+        .map(|it| param(&it))
+        .collect::<Option<Vec<_>>>()?;
+    res.push_str(&format!("function {name}({})", params.join(", ")));
+    for attr in tag.attributes() {
+        if !matches!(
+            attr.name_lower().as_str(),
+            "name" | "access" | "returntype" | "hint"
+        ) {
+            res.push(' ');
+            res.push_str(&attr.syntax().text());
+        }
+    }
+    Some(res)
+}
+
+/// `required string name = "x" hint="..."`.
+fn param(tag: &ast::Tag) -> Option<String> {
+    let mut res = String::new();
+    let required = tag.attribute("required").and_then(|it| it.value());
+    if required.is_some_and(|it| it.eq_ignore_ascii_case("true") || it.eq_ignore_ascii_case("yes"))
+    {
+        res.push_str("required ");
+    }
+    let ty = tag.attribute("type").and_then(|it| it.value());
+    if let Some(ty) = &ty {
+        res.push_str(ty);
+        res.push(' ');
+    }
+    res.push_str(&tag.attribute("name")?.value()?);
+    if let Some(default) = tag.attribute("default") {
+        let ty = ty.as_deref().unwrap_or("any");
+        res.push_str(" = ");
+        res.push_str(&attribute_expr(&default, ty)?);
+    }
+    for attr in tag.attributes() {
+        if !matches!(
+            attr.name_lower().as_str(),
+            "name" | "type" | "required" | "default"
+        ) {
+            res.push(' ');
+            res.push_str(&attr.syntax().text());
+        }
+    }
+    Some(res)
+}
+
+/// Converts tag bodies to indented script lines. Every method returns `None`
+/// when it meets something it cannot convert.
+struct Converter {
+    lines: Vec<String>,
+    unit: String,
+}
+
+impl Converter {
+    fn line(&mut self, depth: usize, text: impl AsRef<str>) {
+        self.lines
+            .push(format!("{}{}", self.unit.repeat(depth), text.as_ref()));
+    }
+
+    /// Converts the body of `tag`, stopping at `<cfelseif>` and `<cfelse>`.
+    fn items(&mut self, tag: &ast::Tag, depth: usize) -> Option<()> {
+        let body = tag
+            .syntax()
+            .children_with_tokens()
+            .filter(|it| !matches!(it.kind(), TAG_OPEN | TAG_CLOSE));
+        for element in body {
+            self.item(element, depth)?;
+        }
+        Some(())
+    }
+
+    fn item(&mut self, element: SyntaxElement, depth: usize) -> Option<()> {
+        let node = match element {
+            SyntaxElement::Token(token) => {
+                return match token.kind() {
+                    TEXT | WHITESPACE if token.text().trim().is_empty() => {
+                        // Keep blank lines separating groups of statements.
+                        if token.text().matches('\n').count() > 1
+                            && self.lines.last().is_some_and(|it| !it.is_empty())
+                        {
+                            self.lines.push(String::new());
+                        }
+                        Some(())
+                    }
+                    CF_COMMENT => {
+                        self.comment(token.text(), depth);
+                        Some(())
+                    }
+                    _ => None,
+                };
+            }
+            SyntaxElement::Node(node) => node,
+        };
+        let tag = ast::Tag::cast(node)?;
+        let name = tag.name_lower();
+        match name.as_str() {
+            "cfargument" => {}
+            "cfset" => self.line(depth, format!("{};", tag_expression(&tag))),
+            "cfreturn" => match tag_expression(&tag) {
+                expr if expr.is_empty() => self.line(depth, "return;"),
+                expr => self.line(depth, format!("return {expr};")),
+            },
+            "cfbreak" => self.line(depth, "break;"),
+            "cfcontinue" => self.line(depth, "continue;"),
+            "cfabort" => self.line(depth, "abort;"),
+            "cfrethrow" => self.line(depth, "rethrow;"),
+            "cfthrow" => {
+                let args = tag
+                    .attributes()
+                    .map(|attr| {
+                        Some(format!(
+                            "{} = {}",
+                            attr.name()?.text(),
+                            attribute_expr(&attr, "string")?
+                        ))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                self.line(depth, format!("throw({});", args.join(", ")));
+            }
+            "cfif" => self.if_tag(&tag, depth)?,
+            "cfloop" => self.loop_tag(&tag, depth)?,
+            "cfscript" => {
+                let open = tag.open()?.syntax().text_range().end();
+                let close = tag.close()?.syntax().text_range().start();
+                let text = tag.syntax().text();
+                let start = tag.syntax().text_range().start();
+                let script = &text[(open - start) as usize..(close - start) as usize];
+                if !script.trim().is_empty() {
+                    self.lines.push(reindent(script, &self.unit.repeat(depth)));
+                }
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn comment(&mut self, text: &str, depth: usize) {
+        let inner = text.trim_start_matches("<!---").trim_end_matches("--->");
+        if inner.trim().contains('\n') {
+            self.line(depth, "/*");
+            self.lines
+                .push(reindent(inner, &format!("{} ", self.unit.repeat(depth))));
+            self.line(depth, "*/");
+        } else {
+            self.line(depth, format!("// {}", inner.trim()));
+        }
+    }
+
+    fn if_tag(&mut self, tag: &ast::Tag, depth: usize) -> Option<()> {
+        self.line(depth, format!("if ({}) {{", tag_expression(tag)));
+        let body = tag
+            .syntax()
+            .children_with_tokens()
+            .filter(|it| !matches!(it.kind(), TAG_OPEN | TAG_CLOSE));
+        for element in body {
+            let branch = element
+                .as_node()
+                .cloned()
+                .and_then(ast::Tag::cast)
+                .filter(|it| matches!(it.name_lower().as_str(), "cfelseif" | "cfelse"));
+            match branch {
+                Some(branch) if branch.name_lower() == "cfelseif" => {
+                    let cond = tag_expression(&branch);
+                    self.line(depth, format!("}} else if ({cond}) {{"));
+                }
+                Some(_) => self.line(depth, "} else {"),
+                None => self.item(element, depth + 1)?,
+            }
+        }
+        self.line(depth, "}");
+        Some(())
+    }
+
+    fn loop_tag(&mut self, tag: &ast::Tag, depth: usize) -> Option<()> {
+        let attr = |name: &str| tag.attribute(name);
+        let expr = |name: &str, ty: &str| attribute_expr(&attr(name)?, ty);
+        let header = if let (Some(from), Some(to)) =
+            (expr("from", "numeric"), expr("to", "numeric"))
+        {
+            let index = attr("index")?.value()?;
+            match expr("step", "numeric") {
+                Some(step) if step.starts_with('-') => {
+                    format!("for ({index} = {from}; {index} >= {to}; {index} += {step})")
+                }
+                Some(step) => format!("for ({index} = {from}; {index} <= {to}; {index} += {step})"),
+                None => format!("for ({index} = {from}; {index} <= {to}; {index}++)"),
+            }
+        } else if let Some(condition) = attr("condition") {
+            format!("while ({})", condition.value()?)
+        } else if let Some(array) = expr("array", "any") {
+            let item = attr("index").or_else(|| attr("item"))?.value()?;
+            format!("for ({item} in {array})")
+        } else if let Some(collection) = expr("collection", "any") {
+            let item = attr("item")?.value()?;
+            format!("for ({item} in {collection})")
+        } else {
+            return None;
+        };
+        self.line(depth, format!("{header} {{"));
+        self.items(tag, depth + 1)?;
+        self.line(depth, "}");
+        Some(())
+    }
+}
+
+/// The expression of `<cfset ...>`, `<cfif ...>` and `<cfreturn ...>`.
+fn tag_expression(tag: &ast::Tag) -> String {
+    let Some(open) = tag.open() else {
+        return String::new();
+    };
+    open.syntax()
+        .children_with_tokens()
+        .filter(|it| !matches!(it.kind(), L_ANGLE | TAG_NAME | R_ANGLE | SLASH_R_ANGLE))
+        .map(|it| match it {
+            SyntaxElement::Node(it) => it.text(),
+            SyntaxElement::Token(it) => it.text().to_string(),
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn convert_function() {
+        check_assist(
+            "Convert function to cfscript",
+            r##"<cfcomponent>
+    <!--- Kept as is. --->
+    <cffunction name="$0greet" access="public" returntype="string" output="false" hint="Greets someone.">
+        <cfargument name="name" type="string" required="true">
+        <cfargument name="times" type="numeric" default="1" hint="How often.">
+        <cfargument name="since" default="#now()#">
+        <cfset var res = "">
+
+        <!--- Repeat the greeting. --->
+        <cfloop from="1" to="#arguments.times#" index="local.i">
+            <cfset res &= "Hello, #name#! ">
+        </cfloop>
+        <cfif len(res) GT 100>
+            <cfreturn left(res, 100)>
+        <cfelseif res EQ "">
+            <cfthrow message="Nothing to say" type="#t#">
+        <cfelse>
+            <cfscript>
+                res = trim(res);
+            </cfscript>
+        </cfif>
+        <cfreturn res>
+    </cffunction>
+</cfcomponent>"##,
+            r##"<cfcomponent>
+    <!--- Kept as is. --->
+    <cfscript>
+        /**
+         * Greets someone.
+         */
+        public string function greet(required string name, numeric times = 1 hint="How often.", since = now()) output="false" {
+            var res = "";
+
+            // Repeat the greeting.
+            for (local.i = 1; local.i <= arguments.times; local.i++) {
+                res &= "Hello, #name#! ";
+            }
+            if (len(res) GT 100) {
+                return left(res, 100);
+            } else if (res EQ "") {
+                throw(message = "Nothing to say", type = t);
+            } else {
+                res = trim(res);
+            }
+            return res;
+        }
+    </cfscript>
+</cfcomponent>"##,
+        );
+    }
+
+    #[test]
+    fn convert_loops_and_comments() {
+        check_assist(
+            "Convert function to cfscript",
+            r##"<cffunction $0name="f">
+	<cfloop condition="x LT 10">
+		<!---
+		  Several
+		  lines.
+		--->
+		<cfset x++>
+	</cfloop>
+	<cfloop array="#xs#" index="x"><cfbreak></cfloop>
+	<cfloop from="10" to="1" step="-1" index="i"></cfloop>
+</cffunction>"##,
+            r##"<cfscript>
+	function f() {
+		while (x LT 10) {
+			/*
+			 Several
+			 lines.
+			*/
+			x++;
+		}
+		for (x in xs) {
+			break;
+		}
+		for (i = 10; i >= 1; i += -1) {
+		}
+	}
+</cfscript>"##,
+        );
+    }
+
+    #[test]
+    fn not_applicable_to_unsupported_tags() {
+        check_assist_not_applicable(
+            "convert_to_cfscript",
+            "<cffunction $0name=\"f\"><cfquery name=\"q\">select 1</cfquery></cffunction>",
+        );
+        check_assist_not_applicable(
+            "convert_to_cfscript",
+            "<cffunction $0name=\"f\">Hello #name#</cffunction>",
+        );
+        check_assist_not_applicable(
+            "convert_to_cfscript",
+            "<cffunction name=\"f\"><cfset $0x = 1></cffunction>",
+        );
+    }
+}
@@ -0,0 +1,508 @@
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+use crate::{
+    assists::{
+        utils::{
+            assignment_target, is_statement_container, reindent, selected_expr, selected_statements,
+        },
+        AssistContext, AssistId, AssistKind, Assists,
+    },
+    component::{indent_unit, line_indent, Component},
+    ty::{infer_expr, Ty},
+    var_scope::{functions, FunctionLike},
+    Symbol,
+};
+
+// Assist: extract_function
+//
+// Moves the selected statements or expression into a new function placed
+// after the current one. Locals the selection uses are passed as arguments,
+// and a local it assigns to and which is used afterwards is returned.
+//
+// ```
+// function f(a) {
+//     $0var b = a * 2;$0
+//     return b;
+// }
+// ```
+// ->
+// ```
+// function f(a) {
+//     var b = newFunction(a);
+//     return b;
+// }
+//
+// private function newFunction(a) {
+//     var b = a * 2;
+//     return b;
+// }
+// ```
+pub(crate) fn extract_function(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let range = ctx.selection_trimmed();
+    if range.is_empty() {
+        return None;
+    }
+    let selection = Selection::find(ctx.root(), range)?;
+    let nodes = selection.nodes();
+    if !has_local_control_flow(&nodes, range) {
+        return None;
+    }
+
+    let scope: Vec<FunctionLike> = nodes[0]
+        .ancestors()
+        .filter_map(FunctionLike::cast)
+        .collect();
+    // A script function can't be declared among tags.
+    if scope.iter().any(|it| matches!(it, FunctionLike::Tag(_))) {
+        return None;
+    }
+    let mut locals = FxHashSet::default();
+    for func in &scope {
+        locals.extend(func.declared_names());
+    }
+
+    // Locals used by the selection, in order of first use.
+    let mut params: Vec<String> = Vec::new();
+    let mut declared_inside = FxHashSet::default();
+    let mut assigned_inside: Vec<String> = Vec::new();
+    let mut scoped_refs = Vec::new();
+    for node in nodes.iter().flat_map(|it| it.descendants()) {
+        if let Some(var) = ast::VarDecl::cast(node.clone()) {
+            if nested_functions(&node, range).next().is_none() {
+                let name = var.name()?.text().to_string();
+                declared_inside.insert(name.to_ascii_lowercase());
+                assigned_inside.push(name);
+            }
+            continue;
+        }
+        if let Some(target) = assignment_target(node.clone()) {
+            if let Some(var) = local_ref(target.syntax()) {
+                let key = var.name.to_ascii_lowercase();
+                if locals.contains(&Symbol::new(&key)) && !is_shadowed(&var, &key, range) {
+                    assigned_inside.push(var.name);
+                }
+            }
+            continue;
+        }
+        let Some(var) = local_ref(&node) else {
+            continue;
+        };
+        let key = var.name.to_ascii_lowercase();
+        if !locals.contains(&Symbol::new(&key))
+            || declared_inside.contains(&key)
+            || is_shadowed(&var, &key, range)
+        {
+            continue;
+        }
+        if var.scope.as_deref() == Some("local") {
+            scoped_refs.push(var.clone());
+        }
+        if !params.iter().any(|it| it.eq_ignore_ascii_case(&var.name)) {
+            params.push(var.name);
+        }
+    }
+
+    // The one local the selection computes for the code after it.
+    let after = match scope.first() {
+        Some(func) => func.own_nodes(),
+        None => ctx.root().descendants().collect(),
+    };
+    let used_after: FxHashSet<String> = after
+        .iter()
+        .filter(|it| it.text_range().start() >= range.end())
+        .filter_map(local_ref)
+        .map(|it| it.name.to_ascii_lowercase())
+        .collect();
+    let mut outputs: Vec<String> = Vec::new();
+    for name in assigned_inside {
+        let key = name.to_ascii_lowercase();
+        if used_after.contains(&key) && !outputs.iter().any(|it| it.eq_ignore_ascii_case(&key)) {
+            outputs.push(name);
+        }
+    }
+    if outputs.len() > 1 {
+        return None;
+    }
+    let output = outputs.pop();
+    let output_declared = output
+        .as_ref()
+        .is_some_and(|it| declared_inside.contains(&it.to_ascii_lowercase()));
+    if let Some(output) = &output {
+        // Assigning to an argument keeps the variable local to the new
+        // function.
+        if !output_declared && !params.iter().any(|it| it.eq_ignore_ascii_case(output)) {
+            params.push(output.clone());
+        }
+    }
+
+    let anchor = match scope
+        .iter()
+        .rev()
+        .find(|it| matches!(it, FunctionLike::Decl(_)))
+    {
+        Some(func) => func.syntax().clone(),
+        None => top_level_statement(&nodes[0])?,
+    };
+    // In broken code, the selection may end past the anchor, where the
+    // function would be inserted.
+    if anchor.text_range().end() < range.end() {
+        return None;
+    }
+    let fn_name = function_name(ctx.root());
+    let args = params.join(", ");
+    let call = format!("{fn_name}({args})");
+
+    // The selected code, with `local.x` for the new arguments rewritten.
+    let text = ctx.root().text();
+    let mut body = text[std::ops::Range::from(range)].to_string();
+    scoped_refs.sort_by_key(|it| std::cmp::Reverse(it.syntax.text_range().start()));
+    for var in scoped_refs {
+        if !params.iter().any(|it| it.eq_ignore_ascii_case(&var.name)) {
+            continue;
+        }
+        let var_range = var.syntax.text_range();
+        let start = (var_range.start() - range.start()) as usize;
+        let end = (var_range.end() - range.start()) as usize;
+        body.replace_range(start..end, &format!("arguments.{}", var.name));
+    }
+
+    let stmt_indent = line_indent(&nodes[0]);
+    let (return_type, body, replacement) = match &selection {
+        Selection::Expr(expr) => {
+            let ty = infer_expr(expr);
+            let ty = (ty != Ty::Any).then(|| ty.to_string());
+            (ty, format!("{stmt_indent}return {body};"), call)
+        }
+        Selection::Stmts(_) => match &output {
+            Some(output) => {
+                let replacement = if output_declared {
+                    format!("var {output} = {call};")
+                } else {
+                    format!("{output} = {call};")
+                };
+                let body = format!("{stmt_indent}{body}\n{stmt_indent}return {output};");
+                (None, body, replacement)
+            }
+            None => (
+                Some("void".to_string()),
+                format!("{stmt_indent}{body}"),
+                format!("{call};"),
+            ),
+        },
+    };
+
+    let fn_indent = line_indent(&anchor);
+    let unit = match stmt_indent.strip_prefix(fn_indent.as_str()) {
+        Some(it) if !it.is_empty() => it.to_string(),
+        _ => indent_unit(&fn_indent).to_string(),
+    };
+    let body = reindent(&body, &format!("{fn_indent}{unit}"));
+    let access = if Component::enclosing(&anchor).is_some() {
+        "private "
+    } else {
+        ""
+    };
+    let return_type = return_type.map(|it| format!("{it} ")).unwrap_or_default();
+    let function = format!(
+        "\n\n{fn_indent}{access}{return_type}function {fn_name}({args}) {{\n{body}\n{fn_indent}}}"
+    );
+
+    acc.add(
+        AssistId("extract_function", AssistKind::RefactorExtract),
+        "Extract into function",
+        range,
+        |builder| {
+            builder.replace(range, replacement);
+            builder.insert(anchor.text_range().end(), function);
+        },
+    )
+}
+
+/// What can be extracted: whole statements of one block, or an expression.
+enum Selection {
+    Stmts(Vec<SyntaxNode>),
+    Expr(ast::Expr),
+}
+
+impl Selection {
+    fn find(root: &SyntaxNode, range: TextRange) -> Option<Selection> {
+        let covering = match root.covering_element(range) {
+            syntax::SyntaxElement::Node(it) => it,
+            syntax::SyntaxElement::Token(it) => it.parent(),
+        };
+        if let Some(expr) = selected_expr(&covering, range) {
+            return Some(Selection::Expr(expr));
+        }
+
+        selected_statements(&covering, range).map(Selection::Stmts)
+    }
+
+    fn nodes(&self) -> Vec<SyntaxNode> {
+        match self {
+            Selection::Stmts(it) => it.clone(),
+            Selection::Expr(it) => vec![it.syntax().clone()],
+        }
+    }
+}
+
+/// Returns `false` if the selection returns from, or breaks out of, code
+/// around it: it can't be moved into another function.
+fn has_local_control_flow(nodes: &[SyntaxNode], range: TextRange) -> bool {
+    nodes.iter().flat_map(|it| it.descendants()).all(|node| {
+        let inside = |kinds: &[syntax::SyntaxKind]| {
+            node.ancestors()
+                .skip(1)
+                .take_while(|it| range.contains_range(it.text_range()))
+                .any(|it| kinds.contains(&it.kind()))
+        };
+        match node.kind() {
+            RETURN_STMT => inside(&[FUNCTION_DECL, CLOSURE_EXPR, ARROW_EXPR]),
+            BREAK_STMT => inside(&[
+                FOR_STMT,
+                FOR_IN_STMT,
+                WHILE_STMT,
+                DO_WHILE_STMT,
+                SWITCH_STMT,
+            ]),
+            CONTINUE_STMT => inside(&[FOR_STMT, FOR_IN_STMT, WHILE_STMT, DO_WHILE_STMT]),
+            _ => true,
+        }
+    })
+}
+
+/// A local variable, written as `x`, `local.x` or `arguments.x`.
+#[derive(Clone)]
+struct LocalRef {
+    name: String,
+    /// `local` or `arguments`, in lowercase, for a scoped reference.
+    scope: Option<String>,
+    syntax: SyntaxNode,
+}
+
+fn local_ref(node: &SyntaxNode) -> Option<LocalRef> {
+    if node.kind() == FIELD_EXPR {
+        let field = ast::FieldExpr::cast(node.clone())?;
+        let ast::Expr::NameRef(receiver) = field.receiver()? else {
+            return None;
+        };
+        let scope = receiver.text().to_ascii_lowercase();
+        if scope != "local" && scope != "arguments" {
+            return None;
+        }
+        return Some(LocalRef {
+            name: field.field_name()?,
+            scope: Some(scope),
+            syntax: node.clone(),
+        });
+    }
+    let name = ast::NameRef::cast(node.clone())?.text();
+    if matches!(name.to_ascii_lowercase().as_str(), "local" | "arguments") {
+        return None;
+    }
+    Some(LocalRef {
+        name,
+        scope: None,
+        syntax: node.clone(),
+    })
+}
+
+/// Functions and closures inside the selection which contain `node`.
+fn nested_functions(node: &SyntaxNode, range: TextRange) -> impl Iterator<Item = FunctionLike> {
+    node.ancestors()
+        .skip(1)
+        .take_while(move |it| range.contains_range(it.text_range()))
+        .filter_map(FunctionLike::cast)
+}
+
+/// Returns `true` if `var` refers to a parameter or local of a closure
+/// written inside the selection rather than to a local around it.
+fn is_shadowed(var: &LocalRef, key: &str, range: TextRange) -> bool {
+    let key = Symbol::new(key);
+    nested_functions(&var.syntax, range).any(|it| it.declared_names().contains(&key))
+}
+
+/// The statement directly inside a `<cfscript>` block or a script file which
+/// contains `node`.
+fn top_level_statement(node: &SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors().find(|it| {
+        it.kind().is_stmt()
+            && it
+                .parent()
+                .is_some_and(|parent| parent.kind() != BLOCK && is_statement_container(&parent))
+    })
+}
+
+/// A name not used by any function of the file.
+fn function_name(root: &SyntaxNode) -> String {
+    let taken: FxHashSet<String> = functions(root)
+        .filter_map(|it| it.name())
+        .map(|it| it.to_ascii_lowercase())
+        .collect();
+    let base = "newFunction";
+    if !taken.contains(&base.to_ascii_lowercase()) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{base}{n}"))
+        .find(|it| !taken.contains(&it.to_ascii_lowercase()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn extract_statements() {
+        check_assist(
+            "Extract into function",
+            "component {
+    function f(a) {
+        var total = 0;
+        $0var b = a * 2;
+        total = total + b;$0
+        return total;
+    }
+}",
+            "component {
+    function f(a) {
+        var total = 0;
+        total = newFunction(a, total);
+        return total;
+    }
+
+    private function newFunction(a, total) {
+        var b = a * 2;
+        total = total + b;
+        return total;
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn extract_declared_output() {
+        check_assist(
+            "Extract into function",
+            "component {
+	function f(items) {
+		$0var count = arrayLen(local.items);
+		writeLog(count);$0
+
+		return count;
+	}
+
+	function newFunction() {}
+}",
+            "component {
+	function f(items) {
+		var count = newFunction2(items);
+
+		return count;
+	}
+
+	private function newFunction2(items) {
+		var count = arrayLen(arguments.items);
+		writeLog(count);
+		return count;
+	}
+
+	function newFunction() {}
+}",
+        );
+    }
+
+    #[test]
+    fn extract_expression() {
+        check_assist(
+            "Extract into function",
+            "<cfscript>
+function f(price, qty) {
+    return $0price * qty$0 + 1;
+}
+</cfscript>",
+            "<cfscript>
+function f(price, qty) {
+    return newFunction(price, qty) + 1;
+}
+
+numeric function newFunction(price, qty) {
+    return price * qty;
+}
+</cfscript>",
+        );
+    }
+
+    #[test]
+    fn extract_template_statements() {
+        check_assist(
+            "Extract into function",
+            "<cfscript>
+    $0writeOutput(now());$0
+</cfscript>",
+            "<cfscript>
+    newFunction();
+
+    void function newFunction() {
+        writeOutput(now());
+    }
+</cfscript>",
+        );
+    }
+
+    #[test]
+    fn closure_params_are_not_arguments() {
+        check_assist(
+            "Extract into function",
+            "component {
+    function f(x) {
+        $0each([1], function(x) { writeOutput(x); });$0
+    }
+}",
+            "component {
+    function f(x) {
+        newFunction();
+    }
+
+    private void function newFunction() {
+        each([1], function(x) { writeOutput(x); });
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn not_applicable() {
+        // Returning from the outer function.
+        check_assist_not_applicable(
+            "extract_function",
+            "component { function f(a) { $0if (a) return 1;$0 return 2; } }",
+        );
+        // Breaking out of an outer loop.
+        check_assist_not_applicable(
+            "extract_function",
+            "component { function f() { while (true) { $0break;$0 } } }",
+        );
+        // Two results.
+        check_assist_not_applicable(
+            "extract_function",
+            "component { function f() { $0var a = 1; var b = 2;$0 return a + b; } }",
+        );
+        // Part of a statement.
+        check_assist_not_applicable(
+            "extract_function",
+            "component { function f() { var a = 1; $0var b = 2; var$0 c = 3; } }",
+        );
+        check_assist_not_applicable("extract_function", "<cfscript>f($0);</cfscript>");
+        // Ending past where the function would go.
+        check_assist_not_applicable(
+            "extract_function",
+            "<cfscript>\n$0x = 1;\n<cffunction name=\"g\">\ny = 2;$0\n",
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{
+    assists::{
+        utils::{assignment_target, is_statement_container, selected_expr, FIXED_TAGS},
+        AssistContext, AssistId, AssistKind, Assists,
+    },
+    component::{line_indent, starts_line},
+    var_scope::FunctionLike,
+};
+
+// Assist: extract_variable
+//
+// Declares a new variable holding the selected expression, right before the
+// statement or tag using it. When the expression occurs several times in the
+// function, a second assist replaces all of them.
+//
+// ```
+// function f(price, qty) {
+//     return $0price * qty$0 + 1;
+// }
+// ```
+// ->
+// ```
+// function f(price, qty) {
+//     var value = price * qty;
+//     return value + 1;
+// }
+// ```
+pub(crate) fn extract_variable(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let range = ctx.selection_trimmed();
+    if range.is_empty() {
+        return None;
+    }
+    let covering = match ctx.root().covering_element(range) {
+        SyntaxElement::Node(it) => it,
+        SyntaxElement::Token(it) => it.parent(),
+    };
+    let mut expr = selected_expr(&covering, range)?;
+    if let ast::Expr::HashExpr(hash) = &expr {
+        expr = hash.expr()?;
+    }
+    let func = expr.syntax().ancestors().find_map(FunctionLike::cast);
+    let scope = match &func {
+        Some(func) => func.own_nodes(),
+        None => ctx
+            .root()
+            .descendants()
+            .filter(|it| !it.ancestors().any(|it| FunctionLike::cast(it).is_some()))
+            .collect(),
+    };
+    let name = variable_name(&expr, &scope);
+    let value = expr.syntax().text();
+
+    let anchor = Anchor::find(expr.syntax(), expr.syntax().text_range())?;
+    let declaration = anchor.declaration(&name, &value, func.is_some());
+    let expr_range = expr.syntax().text_range();
+    acc.add(
+        AssistId("extract_variable", AssistKind::RefactorExtract),
+        "Extract into variable",
+        expr_range,
+        |builder| {
+            builder.insert(anchor.offset(), declaration);
+            builder.replace(expr_range, name.clone());
+        },
+    );
+
+    let key = normalized(expr.syntax());
+    let occurrences: Vec<SyntaxNode> = scope
+        .into_iter()
+        .filter(|it| it.kind() == expr.syntax().kind() && normalized(it) == key)
+        .filter(|it| {
+            let parent = it.parent();
+            parent.is_some_and(|parent| {
+                parent.kind() != EXPR_STMT
+                    && assignment_target(parent).is_none_or(|target| target.syntax() != it)
+            })
+        })
+        .collect();
+    if occurrences.len() < 2 {
+        return None;
+    }
+    let cover = occurrences
+        .iter()
+        .fold(expr_range, |acc, it| acc.cover(it.text_range()));
+    let anchor = Anchor::find(&occurrences[0], cover)?;
+    let declaration = anchor.declaration(&name, &value, func.is_some());
+    acc.add(
+        AssistId("extract_variable_all", AssistKind::RefactorExtract),
+        format!(
+            "Extract into variable, replacing {} occurrences",
+            occurrences.len()
+        ),
+        expr_range,
+        |builder| {
+            builder.insert(anchor.offset(), declaration);
+            for occurrence in &occurrences {
+                builder.replace(occurrence.text_range(), name.clone());
+            }
+        },
+    )
+}
+
+/// The statement or tag the new variable is declared before.
+struct Anchor {
+    node: SyntaxNode,
+    is_script: bool,
+}
+
+impl Anchor {
+    /// Finds the statement or tag containing `expr` whose parent also
+    /// contains `cover`. Gives up rather than move the expression out of a
+    /// function or out of a branch which might not run.
+    fn find(expr: &SyntaxNode, cover: TextRange) -> Option<Anchor> {
+        for node in expr.ancestors().skip(1) {
+            if FunctionLike::cast(node.clone()).is_some() {
+                return None;
+            }
+            let parent = node.parent()?;
+            let covers = parent.text_range().contains_range(cover);
+            if node.kind().is_stmt() {
+                if !is_statement_container(&parent) {
+                    if node.kind() == BLOCK {
+                        continue;
+                    }
+                    return None;
+                }
+                if covers {
+                    return Some(Anchor {
+                        node,
+                        is_script: true,
+                    });
+                }
+            } else if node.kind() == TAG {
+                let tag = ast::Tag::cast(node.clone())?;
+                if FIXED_TAGS.contains(&tag.name_lower().as_str()) {
+                    return None;
+                }
+                if covers {
+                    return Some(Anchor {
+                        node,
+                        is_script: false,
+                    });
+                }
+            } else if node.kind() == HASH_EXPR
+                && matches!(parent.kind(), TAG | SOURCE_FILE)
+                && covers
+            {
+                // `#expr#` in the body of a tag or in a template.
+                return Some(Anchor {
+                    node,
+                    is_script: false,
+                });
+            }
+        }
+        None
+    }
+
+    fn offset(&self) -> TextSize {
+        self.node.text_range().start()
+    }
+
+    fn declaration(&self, name: &str, value: &str, in_function: bool) -> String {
+        let var = if in_function { "var " } else { "" };
+        let declaration = if self.is_script {
+            format!("{var}{name} = {value};")
+        } else {
+            format!("<cfset {var}{name} = {value}>")
+        };
+        if starts_line(&self.node) {
+            format!("{declaration}\n{}", line_indent(&self.node))
+        } else {
+            format!("{declaration} ")
+        }
+    }
+}
+
+/// The text of `node` without trivia, to compare expressions.
+fn normalized(node: &SyntaxNode) -> String {
+    node.descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia())
+        .map(|it| it.text().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A name for the variable, after what the expression computes, which isn't
+/// used yet in `scope`.
+fn variable_name(expr: &ast::Expr, scope: &[SyntaxNode]) -> String {
+    let base = match expr {
+        ast::Expr::CallExpr(call) => call.callee_name().map(|name| {
+            match name
+                .strip_prefix("get")
+                .or_else(|| name.strip_prefix("Get"))
+            {
+                Some(rest) if rest.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                    rest.to_string()
+                }
+                _ => name,
+            }
+        }),
+        ast::Expr::FieldExpr(field) => field.field_name(),
+        ast::Expr::NewExpr(new) => new
+            .component_name()
+            .and_then(|it| it.rsplit('.').next().map(str::to_string)),
+        _ => None,
+    };
+    let base = base
+        .filter(|it| !it.is_empty())
+        .map(|it| {
+            let mut chars = it.chars();
+            let first = chars.next().unwrap().to_ascii_lowercase();
+            format!("{first}{}", chars.as_str())
+        })
+        .unwrap_or_else(|| "value".to_string());
+
+    let taken: FxHashSet<String> = scope
+        .iter()
+        .flat_map(|it| it.children_with_tokens())
+        .filter_map(|it| it.into_token())
+        .filter(|it| it.kind() == IDENT && matches!(it.parent().kind(), NAME | NAME_REF))
+        .map(|it| it.text().to_ascii_lowercase())
+        .collect();
+    if !taken.contains(&base.to_ascii_lowercase()) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{base}{n}"))
+        .find(|it| !taken.contains(&it.to_ascii_lowercase()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn extract_in_script() {
+        check_assist(
+            "Extract into variable",
+            "component {
+    function f(price, qty) {
+        return $0price * qty$0 + 1;
+    }
+}",
+            "component {
+    function f(price, qty) {
+        var value = price * qty;
+        return value + 1;
+    }
+}",
+        );
+        check_assist(
+            "Extract into variable",
+            "<cfscript>
+writeOutput($0user.getName()$0);
+</cfscript>",
+            "<cfscript>
+name = user.getName();
+writeOutput(name);
+</cfscript>",
+        );
+    }
+
+    #[test]
+    fn extract_in_tags() {
+        check_assist(
+            "Extract into variable",
+            r##"<cffunction name="f">
+	<cfargument name="user">
+	<cfoutput>
+		Hello $0#user.name#$0!
+	</cfoutput>
+</cffunction>"##,
+            r##"<cffunction name="f">
+	<cfargument name="user">
+	<cfoutput>
+		Hello <cfset var name = user.name> #name#!
+	</cfoutput>
+</cffunction>"##,
+        );
+        check_assist(
+            "Extract into variable",
+            r##"<cfif $0getCount(x)$0 gt 2>
+	<p>#x#</p>
+</cfif>"##,
+            r##"<cfset count = getCount(x)>
+<cfif count gt 2>
+	<p>#x#</p>
+</cfif>"##,
+        );
+    }
+
+    #[test]
+    fn extract_replacing_all() {
+        check_assist(
+            "Extract into variable, replacing 3 occurrences",
+            "component {
+    function f(a) {
+        if (a.items.len() > 0) {
+            log($0a.items.len()$0);
+        }
+        return a.items.len( );
+    }
+}",
+            "component {
+    function f(a) {
+        var len = a.items.len();
+        if (len > 0) {
+            log(len);
+        }
+        return len;
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn not_applicable() {
+        // Out of a branch which might not run.
+        check_assist_not_applicable(
+            "extract_variable",
+            "component { function f(a) { if (a) return $0a.b$0; } }",
+        );
+        // An assignment target.
+        check_assist_not_applicable("extract_variable", "<cfscript>$0a$0 = 1;</cfscript>");
+        check_assist_not_applicable(
+            "extract_variable",
+            r#"<cffunction name="f"><cfargument name="a" default="$0#now()#$0"></cffunction>"#,
+        );
+        check_assist_not_applicable(
+            "extract_variable_all",
+            "component { function f() { return $0a.b$0; } }",
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    assists::{AssistContext, AssistId, AssistKind, Assists},
+    component::{indent_unit, line_indent, Component, Property},
+};
+
+// Assist: generate_getter_and_setter
+//
+// Generates a getter and a setter for a property of a component without
+// implicit accessors.
+//
+// ```
+// component {
+//     property string $0name;
+// }
+// ```
+// ->
+// ```
+// component {
+//     property string name;
+//
+//     /**
+//      * Returns the name.
+//      */
+//     public string function getName() {
+//         return variables.name;
+//     }
+//
+//     /**
+//      * Sets the name.
+//      */
+//     public void function setName(required string name) {
+//         variables.name = arguments.name;
+//     }
+// }
+// ```
+pub(crate) fn generate_accessors(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let property = ctx
+        .covering_element()
+        .ancestors()
+        .find_map(Property::cast)?;
+    let component = Component::enclosing(property.syntax())?;
+    if component.is_interface()
+        || component.has_implicit_accessors()
+        || !component.properties().contains(&property)
+    {
+        return None;
+    }
+    let name = property.name()?;
+    let accessor_name = |prefix: &str| {
+        let mut chars = name.chars();
+        let first = chars.next().map(|it| it.to_uppercase().to_string());
+        format!("{prefix}{}{}", first.unwrap_or_default(), chars.as_str())
+    };
+    let getter_name = accessor_name("get");
+    let setter_name = accessor_name("set");
+    let wants_getter = property.wants_accessor("getter") && !component.has_function(&getter_name);
+    let wants_setter = property.wants_accessor("setter") && !component.has_function(&setter_name);
+
+    let offset = component.members_end()?;
+    let indent = line_indent(property.syntax());
+    let accessors = Accessors {
+        name: &name,
+        ty: property.ty().unwrap_or_else(|| "any".to_string()),
+        indent: &indent,
+        unit: indent_unit(&indent),
+        script: component.is_script(),
+    };
+    let target = property.syntax().text_range();
+
+    if wants_getter && wants_setter {
+        let id = AssistId("generate_getter_and_setter", AssistKind::Refactor);
+        let label = format!("Generate getter and setter for `{name}`");
+        acc.add(id, label, target, |builder| {
+            let text = accessors.getter(&getter_name) + &accessors.setter(&setter_name);
+            builder.insert(offset, text);
+        });
+    }
+    if wants_getter {
+        let id = AssistId("generate_getter", AssistKind::Refactor);
+        let label = format!("Generate getter for `{name}`");
+        acc.add(id, label, target, |builder| {
+            builder.insert(offset, accessors.getter(&getter_name))
+        });
+    }
+    if wants_setter {
+        let id = AssistId("generate_setter", AssistKind::Refactor);
+        let label = format!("Generate setter for `{name}`");
+        acc.add(id, label, target, |builder| {
+            builder.insert(offset, accessors.setter(&setter_name))
+        });
+    }
+    Some(())
+}
+
+struct Accessors<'a> {
+    name: &'a str,
+    ty: String,
+    indent: &'a str,
+    unit: &'a str,
+    script: bool,
+}
+
+impl Accessors<'_> {
+    fn getter(&self, function_name: &str) -> String {
+        let Accessors {
+            name,
+            ty,
+            indent,
+            unit,
+            ..
+        } = self;
+        if self.script {
+            format!(
+                "\n\n{indent}/**\n{indent} * Returns the {name}.\n{indent} */\n\
+                 {indent}public {ty} function {function_name}() {{\n\
+                 {indent}{unit}return variables.{name};\n\
+                 {indent}}}"
+            )
+        } else {
+            format!(
+                "\n\n{indent}<cffunction name=\"{function_name}\" access=\"public\" \
+                 returntype=\"{ty}\" output=\"false\" hint=\"Returns the {name}.\">\n\
+                 {indent}{unit}<cfreturn variables.{name}>\n\
+                 {indent}</cffunction>"
+            )
+        }
+    }
+
+    fn setter(&self, function_name: &str) -> String {
+        let Accessors {
+            name,
+            ty,
+            indent,
+            unit,
+            ..
+        } = self;
+        if self.script {
+            format!(
+                "\n\n{indent}/**\n{indent} * Sets the {name}.\n{indent} */\n\
+                 {indent}public void function {function_name}(required {ty} {name}) {{\n\
+                 {indent}{unit}variables.{name} = arguments.{name};\n\
+                 {indent}}}"
+            )
+        } else {
+            format!(
+                "\n\n{indent}<cffunction name=\"{function_name}\" access=\"public\" \
+                 returntype=\"void\" output=\"false\" hint=\"Sets the {name}.\">\n\
+                 {indent}{unit}<cfargument name=\"{name}\" type=\"{ty}\" required=\"true\">\n\
+                 {indent}{unit}<cfset variables.{name} = arguments.{name}>\n\
+                 {indent}</cffunction>"
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn generate_script_accessors() {
+        check_assist(
+            "Generate getter and setter for `name`",
+            "component {
+    property string $0name;
+
+    function other() {}
+}",
+            "component {
+    property string name;
+
+    function other() {}
+
+    /**
+     * Returns the name.
+     */
+    public string function getName() {
+        return variables.name;
+    }
+
+    /**
+     * Sets the name.
+     */
+    public void function setName(required string name) {
+        variables.name = arguments.name;
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn generate_tag_getter() {
+        check_assist(
+            "Generate getter for `id`",
+            r#"<cfcomponent>
+	<cfproperty name="id" $0type="numeric">
+</cfcomponent>"#,
+            r#"<cfcomponent>
+	<cfproperty name="id" type="numeric">
+
+	<cffunction name="getId" access="public" returntype="numeric" output="false" hint="Returns the id.">
+		<cfreturn variables.id>
+	</cffunction>
+</cfcomponent>"#,
+        );
+    }
+
+    #[test]
+    fn generate_tag_setter() {
+        check_assist(
+            "Generate setter for `id`",
+            r#"<cfcomponent>
+    <cfproperty $0name="id">
+    <cffunction name="getId"></cffunction>
+</cfcomponent>"#,
+            r#"<cfcomponent>
+    <cfproperty name="id">
+    <cffunction name="getId"></cffunction>
+
+    <cffunction name="setId" access="public" returntype="void" output="false" hint="Sets the id.">
+        <cfargument name="id" type="any" required="true">
+        <cfset variables.id = arguments.id>
+    </cffunction>
+</cfcomponent>"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_existing_accessors() {
+        check_assist_not_applicable(
+            "generate_getter_and_setter",
+            "component accessors=\"true\" { property $0name; }",
+        );
+        check_assist_not_applicable(
+            "generate_getter",
+            "component { property $0name; function GETNAME() {} }",
+        );
+        check_assist_not_applicable(
+            "generate_setter",
+            "component { property name=\"$0x\" setter=false; }",
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{
+    assists::{utils::script_literal, AssistContext, AssistId, AssistKind, Assists},
+    component::{indent_unit, line_indent, Component, Property},
+};
+
+// Assist: generate_init
+//
+// Generates a constructor taking the properties of the component.
+//
+// ```
+// component {
+//     property string name;$0
+// }
+// ```
+// ->
+// ```
+// component {
+//     property string name;
+//
+//     /**
+//      * Creates a new instance.
+//      */
+//     public any function init(required string name) {
+//         variables.name = arguments.name;
+//         return this;
+//     }
+// }
+// ```
+pub(crate) fn generate_init(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let component = Component::enclosing(&ctx.covering_element().ancestors().next()?)?;
+    if component.is_interface() || component.has_function("init") {
+        return None;
+    }
+    let properties: Vec<(Property, String)> = component
+        .properties()
+        .into_iter()
+        .filter_map(|it| {
+            let name = it.name()?;
+            Some((it, name))
+        })
+        .collect();
+    let (last, _) = properties.last()?;
+    let offset = last.syntax().text_range().end();
+    let indent = line_indent(properties[0].0.syntax());
+    let unit = indent_unit(&indent);
+
+    let text = if component.is_script() {
+        let params: Vec<String> = properties
+            .iter()
+            .map(|(property, name)| {
+                let ty = property.ty().unwrap_or_else(|| "any".to_string());
+                match property.attribute("default") {
+                    Some(default) => format!("{ty} {name} = {}", script_literal(&ty, &default)),
+                    None => format!("required {ty} {name}"),
+                }
+            })
+            .collect();
+        let mut text = format!(
+            "\n\n{indent}/**\n{indent} * Creates a new instance.\n{indent} */\n\
+             {indent}public any function init({}) {{\n",
+            params.join(", ")
+        );
+        for (_, name) in &properties {
+            text.push_str(&format!(
+                "{indent}{unit}variables.{name} = arguments.{name};\n"
+            ));
+        }
+        text.push_str(&format!("{indent}{unit}return this;\n{indent}}}"));
+        text
+    } else {
+        let mut text = format!(
+            "\n\n{indent}<cffunction name=\"init\" access=\"public\" returntype=\"any\" \
+             output=\"false\" hint=\"Creates a new instance.\">\n"
+        );
+        for (property, name) in &properties {
+            let ty = property.ty().unwrap_or_else(|| "any".to_string());
+            let required = match property.attribute("default") {
+                Some(default) => format!(
+                    "required=\"false\" default=\"{}\"",
+                    default.replace('"', "\"\"")
+                ),
+                None => "required=\"true\"".to_string(),
+            };
+            text.push_str(&format!(
+                "{indent}{unit}<cfargument name=\"{name}\" type=\"{ty}\" {required}>\n"
+            ));
+        }
+        for (_, name) in &properties {
+            text.push_str(&format!(
+                "{indent}{unit}<cfset variables.{name} = arguments.{name}>\n"
+            ));
+        }
+        text.push_str(&format!(
+            "{indent}{unit}<cfreturn this>\n{indent}</cffunction>"
+        ));
+        text
+    };
+
+    acc.add(
+        AssistId("generate_init", AssistKind::Source),
+        "Generate `init()` from properties",
+        component.syntax().text_range(),
+        |builder| builder.insert(offset, text),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn generate_script_init() {
+        check_assist(
+            "Generate `init()` from properties",
+            r#"component {
+    property string name;
+    property name="count" type="numeric" default="0";
+    property name="label" default="a ""b"" ##";
+
+    function $0other() {}
+}"#,
+            r#"component {
+    property string name;
+    property name="count" type="numeric" default="0";
+    property name="label" default="a ""b"" ##";
+
+    /**
+     * Creates a new instance.
+     */
+    public any function init(required string name, numeric count = 0, any label = "a ""b"" ##") {
+        variables.name = arguments.name;
+        variables.count = arguments.count;
+        variables.label = arguments.label;
+        return this;
+    }
+
+    function other() {}
+}"#,
+        );
+    }
+
+    #[test]
+    fn generate_tag_init() {
+        check_assist(
+            "Generate `init()` from properties",
+            r#"<cfcomponent>
+	<cfproperty name="id" type="numeric">
+	<cfproperty name="$0active" type="boolean" default="true">
+</cfcomponent>"#,
+            r#"<cfcomponent>
+	<cfproperty name="id" type="numeric">
+	<cfproperty name="active" type="boolean" default="true">
+
+	<cffunction name="init" access="public" returntype="any" output="false" hint="Creates a new instance.">
+		<cfargument name="id" type="numeric" required="true">
+		<cfargument name="active" type="boolean" required="false" default="true">
+		<cfset variables.id = arguments.id>
+		<cfset variables.active = arguments.active>
+		<cfreturn this>
+	</cffunction>
+</cfcomponent>"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_with_init_or_without_properties() {
+        check_assist_not_applicable(
+            "generate_init",
+            "component { property x; function INIT() {} $0}",
+        );
+        check_assist_not_applicable("generate_init", "component { function f() {} $0}");
+        check_assist_not_applicable("generate_init", "<cfset $0x = 1>");
+    }
+}
@@ -0,0 +1,371 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+use crate::{
+    assists::{
+        utils::{assignment_target, is_statement_container, removal_range},
+        AssistContext, AssistId, AssistKind, Assists,
+    },
+    var_scope::FunctionLike,
+    Symbol,
+};
+
+// Assist: inline_variable
+//
+// Replaces every use of a local variable with its initial value and removes
+// the declaration. Only offered when the variable is never written again and
+// inlining can't change what the value evaluates to.
+//
+// ```
+// function f(a) {
+//     var $0b = a * 2;
+//     return b + 1;
+// }
+// ```
+// ->
+// ```
+// function f(a) {
+//     return (a * 2) + 1;
+// }
+// ```
+pub(crate) fn inline_variable(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let node = match ctx.covering_element() {
+        syntax::SyntaxElement::Node(it) => it,
+        syntax::SyntaxElement::Token(it) => it.parent(),
+    };
+    let (decl, func) = declaration_at(&node)?;
+    let name = decl.name()?.text().to_string();
+    let key = name.to_ascii_lowercase();
+    let init = decl.initializer()?;
+    let stmt = declaring_statement(&decl)?;
+    let decl_end = stmt.text_range().end();
+
+    let mut usages = Vec::new();
+    for node in func.syntax().descendants() {
+        if is_shadowed(&node, &func, &key) {
+            continue;
+        }
+        if let Some(other) = ast::VarDecl::cast(node.clone()) {
+            let is_same = other
+                .name()
+                .is_some_and(|it| it.text().eq_ignore_ascii_case(&key));
+            if is_same && other != decl {
+                return None;
+            }
+        }
+        if let Some(target) = assignment_target(node.clone()) {
+            if variable_name(target.syntax()).as_deref() == Some(key.as_str()) {
+                return None;
+            }
+        }
+        if node.kind() == FOR_IN_STMT {
+            let loop_var = node.children().find(|it| it.kind().is_expr());
+            if loop_var.is_some_and(|it| variable_name(&it).as_deref() == Some(key.as_str())) {
+                return None;
+            }
+        }
+        if variable_name(&node).as_deref() == Some(key.as_str()) && !is_field_of_usage(&node) {
+            if node.text_range().start() < decl_end {
+                return None;
+            }
+            usages.push(node);
+        }
+    }
+    let last = usages.last()?.text_range();
+
+    if has_side_effects(init.syntax()) {
+        // Calls must still run exactly once, and not after anything else
+        // they could observe.
+        let [usage] = usages.as_slice() else {
+            return None;
+        };
+        let next = stmt.next_sibling()?;
+        let in_next = next.text_range().contains_range(usage.text_range());
+        let deferred = is_deferred(usage, &next);
+        if !in_next || deferred {
+            return None;
+        }
+    } else {
+        let between = TextRange::new(decl_end, last.end());
+        if is_modified_between(&func, &dependencies(init.syntax()), between) {
+            return None;
+        }
+    }
+
+    let value = init.syntax().text();
+    for usage in &usages {
+        if let Some(string) = usage.ancestors().find_map(ast::StringLit::cast) {
+            if value.contains(string.quote_char()) {
+                return None;
+            }
+        }
+    }
+    let parenthesized = format!("({value})");
+    let target = decl.name()?.text_range();
+    acc.add(
+        AssistId("inline_variable", AssistKind::RefactorInline),
+        format!("Inline variable `{name}`"),
+        target,
+        |builder| {
+            builder.delete(removal_range(&stmt));
+            for usage in &usages {
+                let text = if needs_parens(init.syntax(), usage) {
+                    parenthesized.clone()
+                } else {
+                    value.clone()
+                };
+                builder.replace(usage.text_range(), text);
+            }
+        },
+    )
+}
+
+/// Finds the `var` declaration at the cursor, either on the declaration
+/// itself or on a use of the variable.
+fn declaration_at(node: &SyntaxNode) -> Option<(ast::VarDecl, FunctionLike)> {
+    if let Some(decl) = node.ancestors().find_map(ast::VarDecl::cast) {
+        let in_init = decl
+            .initializer()
+            .is_some_and(|it| it.syntax().text_range().contains_range(node.text_range()));
+        if !in_init {
+            let func = decl.syntax().ancestors().find_map(FunctionLike::cast)?;
+            return Some((decl, func));
+        }
+    }
+    let usage = node
+        .ancestors()
+        .take_while(|it| it.kind().is_expr())
+        .find(|it| variable_name(it).is_some())?;
+    let key = variable_name(&usage)?;
+    usage
+        .ancestors()
+        .filter_map(FunctionLike::cast)
+        .find_map(|func| {
+            let decl = func.own_nodes().into_iter().find_map(|it| {
+                let decl = ast::VarDecl::cast(it)?;
+                decl.name()?
+                    .text()
+                    .eq_ignore_ascii_case(&key)
+                    .then_some(decl)
+            })?;
+            Some((decl, func))
+        })
+}
+
+/// The statement or `<cfset>` tag consisting of the declaration.
+fn declaring_statement(decl: &ast::VarDecl) -> Option<SyntaxNode> {
+    let parent = decl.syntax().parent()?;
+    if is_statement_container(&parent) {
+        return Some(decl.syntax().clone());
+    }
+    if parent.kind() == TAG_OPEN {
+        return parent.parent();
+    }
+    None
+}
+
+/// The lowercase name of the local variable `node` refers to, for `x` and
+/// `local.x`.
+fn variable_name(node: &SyntaxNode) -> Option<String> {
+    match ast::Expr::cast(node.clone())? {
+        ast::Expr::NameRef(it) => Some(it.text().to_ascii_lowercase()),
+        ast::Expr::FieldExpr(it) => {
+            let ast::Expr::NameRef(receiver) = it.receiver()? else {
+                return None;
+            };
+            receiver
+                .text()
+                .eq_ignore_ascii_case("local")
+                .then(|| it.field_name().map(|it| it.to_ascii_lowercase()))?
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` for `local` in `local.x`, which is part of a larger use.
+fn is_field_of_usage(node: &SyntaxNode) -> bool {
+    node.parent()
+        .is_some_and(|it| it.kind() == FIELD_EXPR && variable_name(&it).is_some())
+}
+
+/// Returns `true` if `node` is inside a closure of `func` which declares its
+/// own variable named `key`.
+fn is_shadowed(node: &SyntaxNode, func: &FunctionLike, key: &str) -> bool {
+    node.ancestors()
+        .take_while(|it| it != func.syntax())
+        .filter_map(FunctionLike::cast)
+        .any(|it| it.declared_names().contains(&Symbol::new(key)))
+}
+
+fn has_side_effects(node: &SyntaxNode) -> bool {
+    node.descendants()
+        .any(|it| matches!(it.kind(), CALL_EXPR | NEW_EXPR) || assignment_target(it).is_some())
+}
+
+/// The variables an expression reads, by the lowercase name of their root:
+/// `a` for `a.b[c]`.
+fn dependencies(node: &SyntaxNode) -> Vec<String> {
+    node.descendants()
+        .filter_map(ast::NameRef::cast)
+        .map(|it| it.text().to_ascii_lowercase())
+        .collect()
+}
+
+/// Returns `true` if code of `func` within `range` may change one of `deps`:
+/// by assigning to it, or by calling a method on it or passing it to a
+/// function, which can modify arrays and structs.
+fn is_modified_between(func: &FunctionLike, deps: &[String], range: TextRange) -> bool {
+    let root = |expr: &SyntaxNode| {
+        expr.descendants()
+            .find_map(ast::NameRef::cast)
+            .map(|it| it.text().to_ascii_lowercase())
+    };
+    let depends = |node: &SyntaxNode| root(node).is_some_and(|it| deps.contains(&it));
+    func.syntax()
+        .descendants()
+        .filter(|it| range.contains_range(it.text_range()))
+        .any(|node| {
+            if let Some(target) = assignment_target(node.clone()) {
+                return depends(target.syntax());
+            }
+            let Some(call) = ast::CallExpr::cast(node) else {
+                return false;
+            };
+            let on_dep =
+                matches!(call.callee(), Some(ast::Expr::FieldExpr(it)) if depends(it.syntax()));
+            let args = call.arg_list().into_iter().flat_map(|it| it.args());
+            on_dep
+                || args
+                    .filter_map(|it| it.expr())
+                    .any(|it| depends(it.syntax()))
+        })
+}
+
+/// Returns `true` if `usage` might not be evaluated right away, or only
+/// once, when `stmt` runs.
+fn is_deferred(usage: &SyntaxNode, stmt: &SyntaxNode) -> bool {
+    let path: Vec<SyntaxNode> = usage
+        .ancestors()
+        .take_while(|it| stmt.text_range().contains_range(it.text_range()))
+        .collect();
+    path.windows(2).any(|pair| {
+        let (child, parent) = (&pair[0], &pair[1]);
+        let is_first = parent.children().next().as_ref() == Some(child);
+        match parent.kind() {
+            FOR_STMT | FOR_IN_STMT | WHILE_STMT | DO_WHILE_STMT | SWITCH_STMT => true,
+            IF_STMT | TERNARY_EXPR => !is_first,
+            BIN_EXPR => {
+                let op =
+                    ast::BinExpr::cast(parent.clone()).map(|it| it.op_text().to_ascii_lowercase());
+                !is_first
+                    && op.is_some_and(|it| matches!(it.as_str(), "&&" | "||" | "and" | "or" | "?:"))
+            }
+            TAG => {
+                child.kind() != TAG_OPEN
+                    || ast::Tag::cast(parent.clone()).is_none_or(|it| it.name_lower() == "cfloop")
+            }
+            _ => FunctionLike::cast(parent.clone()).is_some(),
+        }
+    })
+}
+
+/// Returns `true` if `value` must be wrapped in parentheses where `usage`
+/// is written.
+fn needs_parens(value: &SyntaxNode, usage: &SyntaxNode) -> bool {
+    let compound = matches!(
+        value.kind(),
+        BIN_EXPR | TERNARY_EXPR | ASSIGN_EXPR | PREFIX_EXPR | CLOSURE_EXPR | ARROW_EXPR
+    );
+    let parent = usage.parent();
+    compound
+        && parent.is_some_and(|it| {
+            it.kind().is_expr() && !matches!(it.kind(), PAREN_EXPR | HASH_EXPR | ARRAY_LIT)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn inline_pure_value() {
+        check_assist(
+            "Inline variable `b`",
+            "component {
+    function f(a) {
+        var $0b = a * 2;
+        log(b);
+        return local.b + 1;
+    }
+}",
+            "component {
+    function f(a) {
+        log(a * 2);
+        return (a * 2) + 1;
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn inline_from_usage() {
+        check_assist(
+            "Inline variable `name`",
+            r##"component {
+    function f(user) {
+        var name = user.name;
+        return "Hello #na$0me#!";
+    }
+}"##,
+            r##"component {
+    function f(user) {
+        return "Hello #user.name#!";
+    }
+}"##,
+        );
+    }
+
+    #[test]
+    fn inline_call_into_next_statement() {
+        check_assist(
+            "Inline variable `rows`",
+            r#"<cffunction name="f">
+	<cfset var $0rows = load()>
+	<cfset total = rows.len()>
+</cffunction>"#,
+            r#"<cffunction name="f">
+	<cfset total = load().len()>
+</cffunction>"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable() {
+        // Written again.
+        check_assist_not_applicable(
+            "inline_variable",
+            "component { function f() { var $0a = 1; a = 2; return a; } }",
+        );
+        // The value would change.
+        check_assist_not_applicable(
+            "inline_variable",
+            "component { function f(x) { var $0a = x.len(); x.append(1); return a; } }",
+        );
+        check_assist_not_applicable(
+            "inline_variable",
+            "component { function f(x) { var $0a = x; x = 2; return a; } }",
+        );
+        // A call would run several times.
+        check_assist_not_applicable(
+            "inline_variable",
+            "component { function f() { var $0a = now(); log(a); return a; } }",
+        );
+        check_assist_not_applicable(
+            "inline_variable",
+            "component { function f(xs) { var $0a = now(); for (x in xs) log(a); } }",
+        );
+    }
+}
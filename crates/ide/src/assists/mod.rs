@@ -0,0 +1,258 @@
+//! Assists are the code actions offered for a range of a file: quick fixes,
+//! refactorings and source actions.
+//!
+//! Every assist lives in its own module and is a plain function which looks
+//! at an [`AssistContext`] and registers zero or more [`Assist`]s.
+mod add_var_scope;
+mod convert_string_interpolation;
+mod convert_to_cfscript;
+mod extract_function;
+mod extract_variable;
+mod generate_accessors;
+mod generate_init;
+mod inline_variable;
+mod organize_imports;
+mod surround_with_try_catch;
+mod wrap_in_queryparam;
+
+pub(crate) mod utils;
+
+use std::ops;
+
+use syntax::{SyntaxElement, SyntaxNode, TextRange, TextSize};
+use virtual_fs::FileId;
+
+use crate::{FileRange, SourceChange, TextEdit, TextEditBuilder};
+
+/// The category of an assist, mirroring LSP's code action kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssistKind {
+    QuickFix,
+    Refactor,
+    RefactorExtract,
+    RefactorInline,
+    RefactorRewrite,
+    /// An action applying to a whole function or file, e.g. a fix-all.
+    Source,
+    /// Sorting imports and the like, which clients may run on save.
+    OrganizeImports,
+}
+
+/// What the client supports, which changes how assists write their edits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssistConfig {
+    /// The client accepts snippets in edits, to place the cursor.
+    pub snippet_cap: bool,
+}
+
+/// A stable identifier of an assist, e.g. `add_var_scope`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AssistId(pub &'static str, pub AssistKind);
+
+#[derive(Debug, Clone)]
+pub struct Assist {
+    pub id: AssistId,
+    pub label: String,
+    /// The range the assist applies to, for highlighting in the client.
+    pub target: TextRange,
+    pub source_change: SourceChange,
+}
+
+type Handler = fn(&mut Assists, &AssistContext) -> Option<()>;
+
+const HANDLERS: &[Handler] = &[
+    add_var_scope::add_var_scope,
+    add_var_scope::add_var_scope_all,
+    convert_string_interpolation::convert_to_string_concatenation,
+    convert_string_interpolation::convert_to_string_interpolation,
+    convert_to_cfscript::convert_to_cfscript,
+    extract_function::extract_function,
+    extract_variable::extract_variable,
+    generate_accessors::generate_accessors,
+    generate_init::generate_init,
+    inline_variable::inline_variable,
+    organize_imports::organize_imports,
+    surround_with_try_catch::surround_with_try_catch,
+    wrap_in_queryparam::wrap_in_queryparam,
+];
+
+pub(crate) fn assists(config: &AssistConfig, frange: FileRange, text: &str) -> Vec<Assist> {
+    let parse = syntax::parse(text);
+    let ctx = AssistContext {
+        config: config.clone(),
+        root: parse.syntax_node(),
+        range: frange.range,
+    };
+    let mut acc = Assists {
+        file_id: frange.file_id,
+        buf: Vec::new(),
+    };
+    for handler in HANDLERS {
+        handler(&mut acc, &ctx);
+    }
+    acc.buf
+}
+
+/// The assist `id` at `frange`, for a diagnostic to offer it as its fix.
+pub(crate) fn assist_as_fix(id: &str, frange: FileRange, text: &str) -> Option<Assist> {
+    assists(&AssistConfig::default(), frange, text)
+        .into_iter()
+        .find(|it| it.id.0 == id)
+}
+
+pub(crate) struct AssistContext {
+    config: AssistConfig,
+    root: SyntaxNode,
+    range: TextRange,
+}
+
+impl AssistContext {
+    pub(crate) fn config(&self) -> &AssistConfig {
+        &self.config
+    }
+
+    pub(crate) fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    pub(crate) fn range(&self) -> TextRange {
+        self.range
+    }
+
+    /// The selected range without the whitespace around it.
+    pub(crate) fn selection_trimmed(&self) -> TextRange {
+        let text = self.root.text();
+        let selected = &text[ops::Range::from(self.range)];
+        let start = self.range.start() + (selected.len() - selected.trim_start().len()) as TextSize;
+        let end = self.range.end() - (selected.len() - selected.trim_end().len()) as TextSize;
+        TextRange::new(start, end.max(start))
+    }
+
+    /// The element covering the selected range.
+    pub(crate) fn covering_element(&self) -> SyntaxElement {
+        self.root.covering_element(self.range)
+    }
+}
+
+pub(crate) struct Assists {
+    file_id: FileId,
+    buf: Vec<Assist>,
+}
+
+impl Assists {
+    /// Registers an assist whose edit is built by `f`.
+    pub(crate) fn add(
+        &mut self,
+        id: AssistId,
+        label: impl Into<String>,
+        target: TextRange,
+        f: impl FnOnce(&mut TextEditBuilder),
+    ) -> Option<()> {
+        self.add_impl(id, label.into(), target, false, f)
+    }
+
+    /// Registers an assist whose edit, built by `f`, inserts snippets.
+    pub(crate) fn add_snippet(
+        &mut self,
+        id: AssistId,
+        label: impl Into<String>,
+        target: TextRange,
+        f: impl FnOnce(&mut TextEditBuilder),
+    ) -> Option<()> {
+        self.add_impl(id, label.into(), target, true, f)
+    }
+
+    fn add_impl(
+        &mut self,
+        id: AssistId,
+        label: String,
+        target: TextRange,
+        is_snippet: bool,
+        f: impl FnOnce(&mut TextEditBuilder),
+    ) -> Option<()> {
+        let mut builder = TextEdit::builder();
+        f(&mut builder);
+        if builder.is_empty() {
+            return None;
+        }
+        let mut source_change = SourceChange::from_text_edit(self.file_id, builder.finish());
+        source_change.is_snippet = is_snippet;
+        self.buf.push(Assist {
+            id,
+            label,
+            target,
+            source_change,
+        });
+        Some(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use virtual_fs::FileId;
+
+    use super::*;
+
+    /// Splits `$0` (a cursor) or a `$0...$0` selection out of `text`.
+    pub(crate) fn extract_range(text: &str) -> (String, TextRange) {
+        let start = text.find("$0").expect("no `$0` marker");
+        let text = text.replacen("$0", "", 1);
+        let end = text.find("$0").unwrap_or(start);
+        let text = text.replacen("$0", "", 1);
+        (text, TextRange::new(start as TextSize, end as TextSize))
+    }
+
+    fn assists_at(config: &AssistConfig, before: &str) -> (String, Vec<Assist>) {
+        let (text, range) = extract_range(before);
+        let frange = FileRange {
+            file_id: FileId(0),
+            range,
+        };
+        let assists = assists(config, frange, &text);
+        (text, assists)
+    }
+
+    /// Applies the assist labelled `label` at the marker in `before`.
+    pub(crate) fn check_assist(label: &str, before: &str, after: &str) {
+        check_assist_with_config(&AssistConfig::default(), label, before, after)
+    }
+
+    /// Like [`check_assist`], for a client with the given capabilities.
+    pub(crate) fn check_assist_with_config(
+        config: &AssistConfig,
+        label: &str,
+        before: &str,
+        after: &str,
+    ) {
+        let (mut text, assists) = assists_at(config, before);
+        let assist = assists
+            .iter()
+            .find(|it| it.label == label)
+            .unwrap_or_else(|| {
+                let labels: Vec<_> = assists.iter().map(|it| &it.label).collect();
+                panic!("assist `{label}` not found, available: {labels:?}")
+            });
+        assist
+            .source_change
+            .get_source_edit(FileId(0))
+            .unwrap()
+            .apply(&mut text);
+        assert_eq!(text, after);
+    }
+
+    /// Checks that no assist with the given id is offered at the marker.
+    pub(crate) fn check_assist_not_applicable(id: &str, before: &str) {
+        let (_, assists) = assists_at(&AssistConfig::default(), before);
+        let ids: Vec<_> = assists.iter().map(|it| it.id.0).collect();
+        assert!(!ids.contains(&id), "`{id}` unexpectedly offered");
+    }
+
+    #[test]
+    fn test_extract_range() {
+        let (text, range) = extract_range("a$0bc$0d");
+        assert_eq!(text, "abcd");
+        assert_eq!(range, TextRange::new(1, 3));
+        let (_, range) = extract_range("ab$0cd");
+        assert_eq!(range, TextRange::empty(2));
+    }
+}
@@ -0,0 +1,235 @@
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::{
+    assists::{utils::removal_range, AssistContext, AssistId, AssistKind, Assists},
+    component::{line_indent, Component, Property},
+    TextEditBuilder,
+};
+
+// Assist: organize_imports
+//
+// Sorts the `import` statements and `<cfimport>` tags of the file and the
+// properties of the component, which it moves to the top of the component,
+// and removes duplicates. Clients can run it on save.
+//
+// ```
+// import b;
+// import a;
+// import b;
+// component {
+//     function f() {}
+//     property string title;
+//     property numeric id;
+// }
+// ```
+// ->
+// ```
+// import a;
+// import b;
+// component {
+//     property numeric id;
+//     property string title;
+//
+//     function f() {}
+// }
+// ```
+pub(crate) fn organize_imports(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let root = ctx.root();
+    let mut groups: Vec<Vec<SyntaxNode>> = Vec::new();
+    for node in root.descendants().filter(is_import) {
+        let group = groups
+            .iter_mut()
+            .find(|group| group[0].parent() == node.parent() && group[0].kind() == node.kind());
+        match group {
+            Some(group) => group.push(node),
+            None => groups.push(vec![node]),
+        }
+    }
+    let component = root.descendants().find_map(Component::cast);
+    let target = component
+        .as_ref()
+        .map(|it| it.syntax().text_range())
+        .unwrap_or_else(|| root.text_range());
+    acc.add(
+        AssistId("organize_imports", AssistKind::OrganizeImports),
+        "Organize imports and properties",
+        target,
+        |builder| {
+            for group in &groups {
+                organize_in_place(builder, group, import_key);
+            }
+            if let Some(component) = &component {
+                organize_properties(builder, component);
+            }
+        },
+    )
+}
+
+fn is_import(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        IMPORT_DECL => true,
+        TAG => ast::Tag::cast(node.clone()).is_some_and(|it| it.name_lower() == "cfimport"),
+        _ => false,
+    }
+}
+
+/// Sorts by what is imported, ignoring case and spacing.
+fn import_key(node: &SyntaxNode) -> String {
+    let text = match ast::ImportDecl::cast(node.clone()).and_then(|it| it.path()) {
+        Some(path) => path.syntax().text(),
+        None => node.text(),
+    };
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_lowercase()
+}
+
+fn property_key(node: &SyntaxNode) -> String {
+    Property::cast(node.clone())
+        .and_then(|it| it.name())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// The texts of `nodes`, sorted by `key` without duplicates. `None` when
+/// they are sorted already.
+fn sorted(nodes: &[SyntaxNode], key: fn(&SyntaxNode) -> String) -> Option<Vec<String>> {
+    let mut seen = FxHashSet::default();
+    let mut keyed: Vec<(String, String)> = nodes
+        .iter()
+        .filter(|it| seen.insert(key(it)))
+        .map(|it| (key(it), it.text()))
+        .collect();
+    let has_duplicates = keyed.len() != nodes.len();
+    let is_sorted = keyed.windows(2).all(|pair| pair[0].0 <= pair[1].0);
+    if is_sorted && !has_duplicates {
+        return None;
+    }
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    Some(keyed.into_iter().map(|(_, text)| text).collect())
+}
+
+/// Writes the sorted nodes where the first one is and removes the others.
+fn organize_in_place(
+    builder: &mut TextEditBuilder,
+    nodes: &[SyntaxNode],
+    key: fn(&SyntaxNode) -> String,
+) {
+    let Some(texts) = sorted(nodes, key) else {
+        return;
+    };
+    let separator = format!("\n{}", line_indent(&nodes[0]));
+    builder.replace(nodes[0].text_range(), texts.join(&separator));
+    for node in &nodes[1..] {
+        builder.delete(removal_range(node));
+    }
+}
+
+/// Sorts the properties and moves them before the other members.
+fn organize_properties(builder: &mut TextEditBuilder, component: &Component) {
+    let members = component.members();
+    let properties: Vec<SyntaxNode> = component
+        .properties()
+        .iter()
+        .map(|it| it.syntax().clone())
+        .collect();
+    let Some(first) = members.first() else {
+        return;
+    };
+    let leading = members
+        .iter()
+        .take_while(|it| properties.contains(it))
+        .count();
+    if leading == properties.len() {
+        organize_in_place(builder, &properties, property_key);
+        return;
+    }
+    let mut seen = FxHashSet::default();
+    let mut keyed: Vec<(String, String)> = properties
+        .iter()
+        .filter(|it| seen.insert(property_key(it)))
+        .map(|it| (property_key(it), it.text()))
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    let indent = line_indent(first);
+    let texts: Vec<String> = keyed.into_iter().map(|(_, text)| text).collect();
+    let block = texts.join(&format!("\n{indent}"));
+    if leading > 0 {
+        // Properties already start the body: extend them with the others.
+        builder.replace(first.text_range(), block);
+        for property in &properties[1..] {
+            builder.delete(removal_range(property));
+        }
+    } else {
+        builder.insert(first.text_range().start(), format!("{block}\n\n{indent}"));
+        for property in &properties {
+            builder.delete(removal_range(property));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn organize_script_component() {
+        check_assist(
+            "Organize imports and properties",
+            "$0import models.User;
+import lib.*;
+import models.User;
+component {
+    function f() {}
+    property string title;
+    property numeric id;
+    property title;
+}",
+            "import lib.*;
+import models.User;
+component {
+    property numeric id;
+    property string title;
+
+    function f() {}
+}",
+        );
+    }
+
+    #[test]
+    fn organize_tag_component() {
+        check_assist(
+            "Organize imports and properties",
+            r#"$0<cfimport taglib="/tags" prefix="ui">
+<cfimport path="com.a.*">
+<cfcomponent>
+	<cfproperty name="title">
+	<cfproperty name="id">
+	<cffunction name="f"></cffunction>
+	<cfproperty name="author">
+</cfcomponent>"#,
+            r#"<cfimport path="com.a.*">
+<cfimport taglib="/tags" prefix="ui">
+<cfcomponent>
+	<cfproperty name="author">
+	<cfproperty name="id">
+	<cfproperty name="title">
+	<cffunction name="f"></cffunction>
+</cfcomponent>"#,
+        );
+    }
+
+    #[test]
+    fn not_applicable_when_organized() {
+        check_assist_not_applicable(
+            "organize_imports",
+            "$0import a;\nimport b;\ncomponent {\n    property a;\n    function f() {}\n}",
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+use crate::{
+    assists::{
+        utils::{escape_snippet, reindent, selected_statements, FIXED_TAGS},
+        AssistContext, AssistId, AssistKind, Assists,
+    },
+    component::{indent_unit, line_indent},
+};
+
+// Assist: surround_with_try_catch
+//
+// Wraps the selected statements in `try`/`catch`, or the selected tags in
+// `<cftry>`/`<cfcatch>`, putting the cursor in the catch block.
+//
+// ```
+// $0save(user);$0
+// ```
+// ->
+// ```
+// try {
+//     save(user);
+// } catch (any e) {
+//     $0
+// }
+// ```
+pub(crate) fn surround_with_try_catch(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let range = ctx.selection_trimmed();
+    if range.is_empty() {
+        return None;
+    }
+    let covering = match ctx.root().covering_element(range) {
+        SyntaxElement::Node(it) => it,
+        SyntaxElement::Token(it) => it.parent(),
+    };
+    let (first, is_script) = match selected_statements(&covering, range) {
+        Some(stmts) => (stmts[0].clone(), true),
+        None => (selected_tags(&covering, range)?, false),
+    };
+
+    let snippet = ctx.config().snippet_cap;
+    let text = ctx.root().text();
+    let mut selected = text[std::ops::Range::from(range)].to_string();
+    if snippet {
+        selected = escape_snippet(&selected);
+    }
+    let indent = line_indent(&first);
+    let unit = indent_unit(&indent);
+    let body = reindent(&format!("{indent}{selected}"), &format!("{indent}{unit}"));
+    let replacement = if is_script {
+        let catch_body = if snippet {
+            format!("{indent}{unit}$0\n")
+        } else {
+            String::new()
+        };
+        format!("try {{\n{body}\n{indent}}} catch (any e) {{\n{catch_body}{indent}}}")
+    } else {
+        let catch_body = if snippet {
+            format!("{indent}{unit}{unit}$0\n")
+        } else {
+            String::new()
+        };
+        format!(
+            "<cftry>\n{body}\n{indent}{unit}<cfcatch type=\"any\">\n{catch_body}{indent}{unit}</cfcatch>\n{indent}</cftry>"
+        )
+    };
+
+    let id = AssistId("surround_with_try_catch", AssistKind::Refactor);
+    let label = if is_script {
+        "Surround with try/catch"
+    } else {
+        "Surround with `<cftry>`"
+    };
+    let edit = |builder: &mut crate::TextEditBuilder| builder.replace(range, replacement);
+    if snippet {
+        acc.add_snippet(id, label, range, edit)
+    } else {
+        acc.add(id, label, range, edit)
+    }
+}
+
+/// Checks that `range` spans whole tags and text of one tag body or
+/// template, and returns the node of the first one.
+fn selected_tags(covering: &SyntaxNode, range: TextRange) -> Option<SyntaxNode> {
+    let container = covering.ancestors().find(|it| match it.kind() {
+        SOURCE_FILE => true,
+        TAG => ast::Tag::cast(it.clone()).is_some_and(|tag| {
+            let body_start = tag.open().map(|it| it.syntax().text_range().end());
+            let body_end = tag.close().map(|it| it.syntax().text_range().start());
+            tag.name_lower() != "cfscript"
+                && body_start.is_some_and(|it| it <= range.start())
+                && body_end.is_none_or(|it| range.end() <= it)
+        }),
+        _ => false,
+    })?;
+    let elements: Vec<SyntaxElement> = container
+        .children_with_tokens()
+        .filter(|it| {
+            it.text_range()
+                .intersect(range)
+                .is_some_and(|it| !it.is_empty())
+        })
+        .collect();
+    let (first, last) = (elements.first()?, elements.last()?);
+    let text_before = |element: &SyntaxElement, offset| match element {
+        SyntaxElement::Token(it) if it.kind() == TEXT => {
+            let start = it.text_range().start();
+            it.text()[..(offset - start) as usize].trim().is_empty()
+        }
+        _ => element.text_range().start() == offset,
+    };
+    let text_after = |element: &SyntaxElement, offset| match element {
+        SyntaxElement::Token(it) if it.kind() == TEXT => {
+            let start = it.text_range().start();
+            it.text()[(offset - start) as usize..].trim().is_empty()
+        }
+        _ => element.text_range().end() == offset,
+    };
+    if !text_before(first, range.start()) || !text_after(last, range.end()) {
+        return None;
+    }
+    let mut first_node = None;
+    for element in &elements {
+        match element {
+            SyntaxElement::Node(node) => {
+                if matches!(node.kind(), TAG_OPEN | TAG_CLOSE) {
+                    return None;
+                }
+                let is_fixed = ast::Tag::cast(node.clone())
+                    .is_some_and(|it| FIXED_TAGS.contains(&it.name_lower().as_str()));
+                if is_fixed || node.kind().is_stmt() {
+                    return None;
+                }
+                first_node.get_or_insert_with(|| node.clone());
+            }
+            SyntaxElement::Token(token) => {
+                if !matches!(token.kind(), TEXT | WHITESPACE | CF_COMMENT) {
+                    return None;
+                }
+            }
+        }
+    }
+    // Line up with the first tag, or with the text when there is none.
+    first_node.or_else(|| Some(container.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::{
+        tests::{check_assist, check_assist_not_applicable, check_assist_with_config},
+        AssistConfig,
+    };
+
+    #[test]
+    fn surround_statements() {
+        check_assist(
+            "Surround with try/catch",
+            "component {
+    function f(user) {
+        $0validate(user);
+        save(user);$0
+    }
+}",
+            "component {
+    function f(user) {
+        try {
+            validate(user);
+            save(user);
+        } catch (any e) {
+        }
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn surround_statements_with_snippet() {
+        check_assist_with_config(
+            &AssistConfig { snippet_cap: true },
+            "Surround with try/catch",
+            "<cfscript>
+$0x = { a: 1 };$0
+</cfscript>",
+            "<cfscript>
+try {
+    x = { a: 1 \\};
+} catch (any e) {
+    $0
+}
+</cfscript>",
+        );
+    }
+
+    #[test]
+    fn surround_tags() {
+        check_assist(
+            "Surround with `<cftry>`",
+            r#"<cffunction name="f">
+	$0<cfset load()>
+	<cfquery name="q">select 1</cfquery>$0
+</cffunction>"#,
+            r#"<cffunction name="f">
+	<cftry>
+		<cfset load()>
+		<cfquery name="q">select 1</cfquery>
+		<cfcatch type="any">
+		</cfcatch>
+	</cftry>
+</cffunction>"#,
+        );
+        check_assist_with_config(
+            &AssistConfig { snippet_cap: true },
+            "Surround with `<cftry>`",
+            "$0<cfinclude template=\"a.cfm\">$0",
+            "<cftry>\n    <cfinclude template=\"a.cfm\">\n    <cfcatch type=\"any\">\n        $0\n    </cfcatch>\n</cftry>",
+        );
+    }
+
+    #[test]
+    fn not_applicable() {
+        check_assist_not_applicable("surround_with_try_catch", "<cfscript>f($0x$0);</cfscript>");
+        check_assist_not_applicable("surround_with_try_catch", "<cfif x>$0a<cfelse>b$0</cfif>");
+        check_assist_not_applicable("surround_with_try_catch", "$0<cfif x>a$0</cfif>");
+    }
+}
@@ -0,0 +1,261 @@
+//! Helpers shared by several assists.
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::component::{line_indent, starts_line};
+
+/// Tags which must stay in place, directly inside their parent tag: no
+/// other tag can be put before them.
+pub(crate) const FIXED_TAGS: &[&str] = &[
+    "cfargument",
+    "cfcase",
+    "cfcatch",
+    "cfcomponent",
+    "cfdefaultcase",
+    "cfelse",
+    "cfelseif",
+    "cffinally",
+    "cfinterface",
+    "cfproperty",
+];
+
+/// Writes the static value of an attribute as a script expression of type
+/// `ty`: numbers and booleans stay bare, anything else becomes a string.
+pub(crate) fn script_literal(ty: &str, value: &str) -> String {
+    let bare = match ty.to_ascii_lowercase().as_str() {
+        "numeric" => value.parse::<f64>().is_ok(),
+        "boolean" => ["true", "false", "yes", "no"]
+            .iter()
+            .any(|it| it.eq_ignore_ascii_case(value)),
+        _ => false,
+    };
+    if bare {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('"', "\"\"").replace('#', "##"))
+    }
+}
+
+/// Writes the value of a tag attribute as a script expression: `"#x#"`
+/// becomes `x`, a static value becomes a literal of type `ty` and a string
+/// with interpolations is kept as it is.
+pub(crate) fn attribute_expr(attr: &ast::Attribute, ty: &str) -> Option<String> {
+    if let Some(value) = attr.value() {
+        return Some(script_literal(ty, &value));
+    }
+    match attr.value_element()? {
+        SyntaxElement::Node(node) => match ast::Expr::cast(node)? {
+            ast::Expr::HashExpr(it) => Some(it.expr()?.syntax().text()),
+            ast::Expr::StringLit(it) => {
+                let mut parts = it
+                    .syntax()
+                    .children_with_tokens()
+                    .filter(|it| it.kind() != QUOTE);
+                match (parts.next(), parts.next()) {
+                    (Some(SyntaxElement::Node(only)), None) => {
+                        Some(ast::HashExpr::cast(only)?.expr()?.syntax().text())
+                    }
+                    _ => Some(it.syntax().text()),
+                }
+            }
+            other => Some(other.syntax().text()),
+        },
+        SyntaxElement::Token(_) => None,
+    }
+}
+
+/// Writes a script expression as the value of a tag attribute: literals
+/// stay as they are, anything else is interpolated.
+pub(crate) fn attribute_value(expr: &str) -> String {
+    let is_plain_string = expr.len() >= 2
+        && (expr.starts_with('"') && expr.ends_with('"')
+            || expr.starts_with('\'') && expr.ends_with('\''))
+        && !expr.contains('#');
+    let is_literal = expr.parse::<f64>().is_ok()
+        || ["true", "false"]
+            .iter()
+            .any(|it| it.eq_ignore_ascii_case(expr));
+    if is_plain_string {
+        expr[1..expr.len() - 1].replace('"', "\"\"")
+    } else if is_literal {
+        expr.to_string()
+    } else {
+        format!("#{}#", expr.replace('"', "\"\""))
+    }
+}
+
+/// Re-indents a block of text: strips the indentation its lines have in
+/// common and prefixes each line with `indent`.
+pub(crate) fn reindent(text: &str, indent: &str) -> String {
+    let lines: Vec<&str> = text
+        .trim_end()
+        .lines()
+        .skip_while(|it| it.trim().is_empty())
+        .collect();
+    let common = lines
+        .iter()
+        .filter(|it| !it.trim().is_empty())
+        .map(|it| it.len() - it.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "{indent}{}",
+                    line.get(common..).unwrap_or(line.trim_start()).trim_end()
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The expression spanning exactly `range`, within `covering`. Whole
+/// expression statements and assignment targets don't count.
+pub(crate) fn selected_expr(covering: &SyntaxNode, range: TextRange) -> Option<ast::Expr> {
+    let node = covering
+        .ancestors()
+        .take_while(|it| it.text_range() == range)
+        .filter(|it| it.kind().is_expr())
+        .last()?;
+    let parent = node.parent()?;
+    let is_target = assignment_target(parent.clone()).is_some_and(|it| *it.syntax() == node);
+    if parent.kind() == EXPR_STMT || is_target {
+        return None;
+    }
+    ast::Expr::cast(node)
+}
+
+/// The whole script statements of one block spanning exactly `range`,
+/// within `covering`. Declarations such as functions don't count.
+pub(crate) fn selected_statements(
+    covering: &SyntaxNode,
+    range: TextRange,
+) -> Option<Vec<SyntaxNode>> {
+    let container = covering.ancestors().find(is_statement_container)?;
+    let stmts: Vec<SyntaxNode> = container
+        .children()
+        .filter(|it| it.kind().is_stmt() && range.contains_range(it.text_range()))
+        .collect();
+    let first = stmts.first()?.text_range();
+    let last = stmts.last()?.text_range();
+    if first.start() != range.start() || last.end() != range.end() {
+        return None;
+    }
+    let is_decl = |it: &SyntaxNode| {
+        matches!(
+            it.kind(),
+            COMPONENT_DECL | FUNCTION_DECL | PROPERTY_DECL | IMPORT_DECL
+        )
+    };
+    if stmts.iter().any(is_decl) {
+        return None;
+    }
+    Some(stmts)
+}
+
+/// The variable written by `x = ...`, `x += ...`, `x++` or `--x`.
+pub(crate) fn assignment_target(node: SyntaxNode) -> Option<ast::Expr> {
+    match node.kind() {
+        ASSIGN_EXPR => ast::AssignExpr::cast(node)?.lhs(),
+        PREFIX_EXPR | POSTFIX_EXPR => {
+            let is_step = node
+                .children_with_tokens()
+                .filter_map(|it| it.into_token())
+                .any(|it| matches!(it.text(), "++" | "--"));
+            if !is_step {
+                return None;
+            }
+            node.children().find_map(ast::Expr::cast)
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` for nodes whose children are script statements: blocks,
+/// `case` clauses, `<cfscript>` and script files.
+pub(crate) fn is_statement_container(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        BLOCK | CASE_CLAUSE | SOURCE_FILE => true,
+        TAG => ast::Tag::cast(node.clone()).is_some_and(|it| it.name_lower() == "cfscript"),
+        _ => false,
+    }
+}
+
+/// The range to delete to remove `node`, including its line when nothing
+/// else is written on it.
+pub(crate) fn removal_range(node: &SyntaxNode) -> TextRange {
+    let range = node.text_range();
+    if !starts_line(node) {
+        return range;
+    }
+    let rest = node.last_token().and_then(|it| it.next_token());
+    let trailing = match rest.as_ref().map(|it| it.text()) {
+        Some(text) => match text.find('\n') {
+            Some(idx) if text[..idx].trim().is_empty() => idx + 1,
+            _ => return range,
+        },
+        None => 0,
+    };
+    let indent = line_indent(node).len();
+    TextRange::new(
+        range.start() - indent as TextSize,
+        range.end() + trailing as TextSize,
+    )
+}
+
+/// Escapes text for use in an LSP snippet.
+pub(crate) fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_literal() {
+        assert_eq!(script_literal("numeric", "1.5"), "1.5");
+        assert_eq!(script_literal("numeric", "x"), "\"x\"");
+        assert_eq!(script_literal("boolean", "Yes"), "Yes");
+        assert_eq!(
+            script_literal("string", "say \"#hi#\""),
+            "\"say \"\"##hi##\"\"\""
+        );
+    }
+
+    #[test]
+    fn test_attribute_expr() {
+        let root = syntax::parse(r##"<cfloop from="1" to="#len(x)#" step=2 index="i #j#">"##)
+            .syntax_node();
+        let tag = root.descendants().find_map(ast::Tag::cast).unwrap();
+        let expr = |name| attribute_expr(&tag.attribute(name).unwrap(), "numeric");
+        assert_eq!(expr("from").as_deref(), Some("1"));
+        assert_eq!(expr("to").as_deref(), Some("len(x)"));
+        assert_eq!(expr("step").as_deref(), Some("2"));
+        assert_eq!(expr("index").as_deref(), Some("\"i #j#\""));
+    }
+
+    #[test]
+    fn test_escape_snippet() {
+        assert_eq!(escape_snippet(r"a\b ${x}"), r"a\\b \${x\}");
+    }
+
+    #[test]
+    fn test_reindent() {
+        assert_eq!(
+            reindent("\n    a\n      b\n\n    c\n", "\t"),
+            "\ta\n\t  b\n\n\tc"
+        );
+    }
+}
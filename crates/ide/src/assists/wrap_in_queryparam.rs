@@ -0,0 +1,291 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    TextRange, TextSize,
+};
+
+use crate::{
+    assists::{AssistContext, AssistId, AssistKind, Assists},
+    sql::{cf_sql_type, params_arg, sql_arg, sql_query, SqlQuery},
+    ty::{infer_expr, variable_path},
+};
+
+// Assist: wrap_in_queryparam
+//
+// Passes a value interpolated into SQL as a query parameter.
+//
+// ```
+// <cfquery name="q">select * from t where id = $0#id#</cfquery>
+// ```
+// ->
+// ```
+// <cfquery name="q">select * from t where id = <cfqueryparam value="#id#" cfsqltype="cf_sql_varchar"></cfquery>
+// ```
+pub(crate) fn wrap_in_queryparam(acc: &mut Assists, ctx: &AssistContext) -> Option<()> {
+    let (hash, query) = ctx
+        .covering_element()
+        .ancestors()
+        .filter_map(ast::HashExpr::cast)
+        .find_map(|it| {
+            let query = sql_query(&it)?;
+            Some((it, query))
+        })?;
+    let expr = hash.expr()?;
+    let sql_type = cf_sql_type(&infer_expr(&expr));
+    let hash_text = hash.syntax().text();
+    let id = AssistId("wrap_in_queryparam", AssistKind::QuickFix);
+
+    match query {
+        SqlQuery::Tag(_) => {
+            let range = quoted_range(&hash, "'");
+            let list = in_list(&hash);
+            let quote = if hash_text.contains('"') { '\'' } else { '"' };
+            let mut param =
+                format!("<cfqueryparam value={quote}{hash_text}{quote} cfsqltype=\"{sql_type}\"");
+            if list {
+                param.push_str(" list=\"true\"");
+            }
+            param.push('>');
+            let label = format!("Wrap `{hash_text}` in `<cfqueryparam>`");
+            acc.add(id, label, hash.syntax().text_range(), |builder| {
+                builder.replace(range, param)
+            })
+        }
+        SqlQuery::Call(call) => {
+            let string = ast::StringLit::cast(hash.syntax().parent()?)?;
+            let nested_quote = if string.quote_char() == '\'' {
+                "''"
+            } else {
+                "'"
+            };
+            let range = quoted_range(&hash, nested_quote);
+            let mut value = format!(
+                "{{ value: {}, cfsqltype: \"{sql_type}\"",
+                expr.syntax().text()
+            );
+            if in_list(&hash) {
+                value.push_str(", list: true");
+            }
+            value.push_str(" }");
+
+            let (placeholder, params_edit) = match params_arg(&call) {
+                None => {
+                    let key = param_key(&expr, &[]);
+                    let named = call.arg_list()?.args().any(|it| it.name().is_some());
+                    let arg = if named {
+                        format!(", params = {{ {key}: {value} }}")
+                    } else {
+                        format!(", {{ {key}: {value} }}")
+                    };
+                    let offset = sql_arg(&call)?.syntax().text_range().end();
+                    (format!(":{key}"), (offset, arg))
+                }
+                Some(arg) => match arg.expr()? {
+                    ast::Expr::StructLit(params) => {
+                        let keys: Vec<String> = params.fields().filter_map(|it| it.key()).collect();
+                        let key = param_key(&expr, &keys);
+                        let edit = match params.fields().last() {
+                            Some(last) => (
+                                last.syntax().text_range().end(),
+                                format!(", {key}: {value}"),
+                            ),
+                            None => (
+                                open_delimiter_end(params.syntax())?,
+                                format!("{key}: {value}"),
+                            ),
+                        };
+                        (format!(":{key}"), edit)
+                    }
+                    ast::Expr::ArrayLit(params) => {
+                        let elements: Vec<_> = params.elements().collect();
+                        let index = positional_index(&string, &hash);
+                        let edit = match elements.get(index) {
+                            Some(next) => {
+                                (next.syntax().text_range().start(), format!("{value}, "))
+                            }
+                            None => match elements.last() {
+                                Some(last) => {
+                                    (last.syntax().text_range().end(), format!(", {value}"))
+                                }
+                                None => (open_delimiter_end(params.syntax())?, value),
+                            },
+                        };
+                        ("?".to_string(), edit)
+                    }
+                    _ => return None,
+                },
+            };
+            let label = format!("Pass `{hash_text}` as a query parameter");
+            acc.add(id, label, hash.syntax().text_range(), |builder| {
+                builder.replace(range, placeholder);
+                builder.insert(params_edit.0, params_edit.1);
+            })
+        }
+    }
+}
+
+/// The range of `hash`, including the SQL string quotes around it, if any.
+fn quoted_range(hash: &ast::HashExpr, quote: &str) -> TextRange {
+    let range = hash.syntax().text_range();
+    let prev = hash.syntax().prev_sibling_or_token();
+    let next = hash.syntax().next_sibling_or_token();
+    let quoted = prev
+        .as_ref()
+        .is_some_and(|it| element_text(it).is_some_and(|it| it.ends_with(quote)))
+        && next
+            .as_ref()
+            .is_some_and(|it| element_text(it).is_some_and(|it| it.starts_with(quote)));
+    if !quoted {
+        return range;
+    }
+    let len = quote.len() as TextSize;
+    TextRange::new(range.start() - len, range.end() + len)
+}
+
+/// Returns `true` for `IN (#ids#)`, where the value is a list.
+fn in_list(hash: &ast::HashExpr) -> bool {
+    let Some(prev) = hash.syntax().prev_sibling_or_token() else {
+        return false;
+    };
+    let Some(text) = element_text(&prev) else {
+        return false;
+    };
+    let Some(before) = text.trim_end().strip_suffix('(') else {
+        return false;
+    };
+    let before = before.trim_end().to_ascii_lowercase();
+    before.ends_with(" in") || before.ends_with("\nin") || before == "in"
+}
+
+fn element_text(element: &SyntaxElement) -> Option<&str> {
+    match element {
+        SyntaxElement::Token(it) if matches!(it.kind(), TEXT | STRING_TEXT) => Some(it.text()),
+        _ => None,
+    }
+}
+
+/// A name for the new parameter, distinct from `existing`.
+fn param_key(expr: &ast::Expr, existing: &[String]) -> String {
+    let path = variable_path(expr);
+    let base = path
+        .as_deref()
+        .and_then(|it| it.rsplit('.').next())
+        .unwrap_or("param");
+    let taken = |key: &str| existing.iter().any(|it| it.eq_ignore_ascii_case(key));
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{base}{n}"))
+        .find(|it| !taken(it))
+        .unwrap()
+}
+
+/// How many `?` placeholders precede `hash` in the SQL string.
+fn positional_index(string: &ast::StringLit, hash: &ast::HashExpr) -> usize {
+    let start = hash.syntax().text_range().start();
+    string
+        .syntax()
+        .children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .filter(|it| it.kind() == STRING_TEXT && it.text_range().end() <= start)
+        .map(|it| it.text().matches('?').count())
+        .sum()
+}
+
+/// The offset right after the `{` or `[` opening a literal.
+fn open_delimiter_end(node: &syntax::SyntaxNode) -> Option<TextSize> {
+    let token = node.first_token()?;
+    matches!(token.kind(), L_BRACE | L_BRACK).then(|| token.text_range().end())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assists::tests::{check_assist, check_assist_not_applicable};
+
+    #[test]
+    fn wrap_tag_value() {
+        check_assist(
+            "Wrap `#id#` in `<cfqueryparam>`",
+            r##"<cffunction name="f"><cfargument name="id" type="numeric">
+<cfquery name="q">select * from t where id = $0#id#</cfquery>
+</cffunction>"##,
+            r##"<cffunction name="f"><cfargument name="id" type="numeric">
+<cfquery name="q">select * from t where id = <cfqueryparam value="#id#" cfsqltype="cf_sql_numeric"></cfquery>
+</cffunction>"##,
+        );
+    }
+
+    #[test]
+    fn wrap_quoted_tag_value() {
+        check_assist(
+            "Wrap `#form.name#` in `<cfqueryparam>`",
+            "<cfquery name=\"q\">update t set name = '#form.na$0me#'</cfquery>",
+            "<cfquery name=\"q\">update t set name = <cfqueryparam value=\"#form.name#\" cfsqltype=\"cf_sql_varchar\"></cfquery>",
+        );
+    }
+
+    #[test]
+    fn wrap_list_value() {
+        check_assist(
+            "Wrap `#ids#` in `<cfqueryparam>`",
+            "<cfquery name=\"q\">select * from t where id IN ($0#ids#)</cfquery>",
+            "<cfquery name=\"q\">select * from t where id IN (<cfqueryparam value=\"#ids#\" cfsqltype=\"cf_sql_varchar\" list=\"true\">)</cfquery>",
+        );
+    }
+
+    #[test]
+    fn wrap_value_with_quotes() {
+        check_assist(
+            "Wrap `#f(\"a\")#` in `<cfqueryparam>`",
+            "<cfquery name=\"q\">select $0#f(\"a\")#</cfquery>",
+            "<cfquery name=\"q\">select <cfqueryparam value='#f(\"a\")#' cfsqltype=\"cf_sql_varchar\"></cfquery>",
+        );
+    }
+
+    #[test]
+    fn pass_as_new_params_struct() {
+        check_assist(
+            "Pass `#arguments.id#` as a query parameter",
+            r##"component { function f(date id) { return queryExecute("select * from t where d = '#argu$0ments.id#'"); } }"##,
+            r##"component { function f(date id) { return queryExecute("select * from t where d = :id", { id: { value: arguments.id, cfsqltype: "cf_sql_timestamp" } }); } }"##,
+        );
+    }
+
+    #[test]
+    fn pass_into_existing_struct() {
+        check_assist(
+            "Pass `#id#` as a query parameter",
+            r##"<cfscript>queryExecute("select * from t where id = :id and x = #$0id#", { id: 1 }, { datasource: "ds" });</cfscript>"##,
+            r##"<cfscript>queryExecute("select * from t where id = :id and x = :id2", { id: 1, id2: { value: id, cfsqltype: "cf_sql_varchar" } }, { datasource: "ds" });</cfscript>"##,
+        );
+        check_assist(
+            "Pass `#id#` as a query parameter",
+            r##"<cfscript>queryExecute(sql = "select #$0id#", params = {});</cfscript>"##,
+            r##"<cfscript>queryExecute(sql = "select :id", params = {id: { value: id, cfsqltype: "cf_sql_varchar" }});</cfscript>"##,
+        );
+    }
+
+    #[test]
+    fn pass_into_positional_array() {
+        check_assist(
+            "Pass `#b#` as a query parameter",
+            r##"<cfscript>queryExecute("select ? + #$0b# + ?", [1, 3]);</cfscript>"##,
+            r##"<cfscript>queryExecute("select ? + ? + ?", [1, { value: b, cfsqltype: "cf_sql_varchar" }, 3]);</cfscript>"##,
+        );
+    }
+
+    #[test]
+    fn not_applicable_outside_sql() {
+        check_assist_not_applicable("wrap_in_queryparam", "<cfoutput>$0#x#</cfoutput>");
+        check_assist_not_applicable(
+            "wrap_in_queryparam",
+            "<cfquery name=\"q\" datasource=\"$0#ds#\">select 1</cfquery>",
+        );
+        check_assist_not_applicable(
+            "wrap_in_queryparam",
+            "<cfscript>queryExecute(\"select #$0x#\", params);</cfscript>",
+        );
+    }
+}
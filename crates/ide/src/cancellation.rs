@@ -0,0 +1,170 @@
+//! Cancelling queries which take long once their result isn't wanted
+//! anymore. The queries going through every file check their token: change
+//! signature, move component, component references and diagnostics.
+//!
+//! A cancelled query unwinds with a [`Cancelled`] payload from the point it
+//! checks its token, so that the code in between needn't thread a `Result`
+//! through. The caller catches it with [`Cancelled::catch`].
+//!
+//! The caller can also ask where a query taking too long is: the query
+//! records its backtrace the next time it checks its token, and how far a
+//! query going through every file got, which it records as it goes.
+use std::{
+    backtrace::Backtrace,
+    fmt, panic,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// A flag shared by the caller of a query, which sets it to cancel the
+/// query, and the query, which checks it.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<TokenState>);
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    backtrace_requested: AtomicBool,
+    backtrace: Mutex<Option<String>>,
+    /// The files done out of `total`, `total` being 0 until the query says.
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Asks the query for its backtrace, which it records the next time it
+    /// checks the token.
+    pub fn request_backtrace(&self) {
+        self.0.backtrace_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// The backtrace the query recorded, once it did.
+    pub fn take_backtrace(&self) -> Option<String> {
+        self.0.backtrace.lock().unwrap().take()
+    }
+
+    /// The files the query is done with out of the files it goes through,
+    /// once it started going through them.
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        let total = self.0.total.load(Ordering::Relaxed);
+        (total > 0).then(|| (self.0.done.load(Ordering::Relaxed).min(total), total))
+    }
+
+    pub(crate) fn report_progress(&self, done: usize, total: usize) {
+        self.0.done.store(done, Ordering::Relaxed);
+        self.0.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Unwinds with [`Cancelled`] if the query was cancelled.
+    pub(crate) fn unwind_if_cancelled(&self) {
+        if self.0.backtrace_requested.swap(false, Ordering::Relaxed) {
+            let backtrace = Backtrace::force_capture().to_string();
+            *self.0.backtrace.lock().unwrap() = Some(backtrace);
+        }
+        if self.is_cancelled() {
+            Cancelled::Requested.throw();
+        }
+    }
+}
+
+/// The payload a cancelled query unwinds with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cancelled {
+    /// The query was cancelled through its [`CancellationToken`].
+    Requested,
+
+    /// The query was operating on revision R, but there is a pending write to move to revision R+1.
+    #[non_exhaustive]
+    PendingWrite,
+
+    /// The query was blocked on another thread, and that thread panicked.
+    #[non_exhaustive]
+    PropagatedPanic,
+}
+
+impl Cancelled {
+    fn throw(self) -> ! {
+        // We use resume and not panic here to avoid running the panic
+        // hook (that is, to avoid collecting and printing backtrace).
+        std::panic::resume_unwind(Box::new(self));
+    }
+
+    /// Runs `f`, and catches any salsa cancellation.
+    pub fn catch<F, T>(f: F) -> Result<T, Cancelled>
+    where
+        F: FnOnce() -> T + panic::UnwindSafe,
+    {
+        match panic::catch_unwind(f) {
+            Ok(t) => Ok(t),
+            Err(payload) => match payload.downcast() {
+                Ok(cancelled) => Err(*cancelled),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let why = match self {
+            Cancelled::Requested => "a cancellation request",
+            Cancelled::PendingWrite => "pending write",
+            Cancelled::PropagatedPanic => "propagated panic",
+        };
+        f.write_str("cancelled because of ")?;
+        f.write_str(why)
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_query() {
+        let token = CancellationToken::default();
+        let query = |token: CancellationToken| {
+            Cancelled::catch(move || {
+                token.unwind_if_cancelled();
+                1
+            })
+        };
+        assert_eq!(query(token.clone()), Ok(1));
+        token.cancel();
+        assert_eq!(query(token), Err(Cancelled::Requested));
+    }
+
+    #[test]
+    fn backtrace_of_query() {
+        let token = CancellationToken::default();
+        token.unwind_if_cancelled();
+        assert_eq!(token.take_backtrace(), None);
+        token.request_backtrace();
+        token.unwind_if_cancelled();
+        let backtrace = token.take_backtrace().unwrap();
+        assert!(backtrace.contains("backtrace_of_query"), "{backtrace}");
+        assert_eq!(token.take_backtrace(), None);
+    }
+
+    #[test]
+    fn progress_of_query() {
+        let token = CancellationToken::default();
+        assert_eq!(token.progress(), None);
+        token.report_progress(3, 4);
+        assert_eq!(token.clone().progress(), Some((3, 4)));
+        token.report_progress(5, 4);
+        assert_eq!(token.progress(), Some((4, 4)));
+    }
+}
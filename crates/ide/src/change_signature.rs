@@ -0,0 +1,687 @@
+//! Changes the parameters of a function: reorders, adds, removes and renames
+//! them, in the declaration and in every call which can be traced to it.
+//!
+//! A call `x.f()` is updated when `x` is known to be an instance of the
+//! component declaring `f`, e.g. from `x = new Service()`. Calls which may
+//! target the function but cannot be traced are reported instead, for the
+//! user to review.
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+use virtual_fs::FileId;
+
+use crate::{
+    assists::utils::{attribute_expr, attribute_value, removal_range},
+    component::{indent_unit, line_indent},
+    resolve::{resolve_component, static_text, walk_extends},
+    ty::{infer_expr, Ty},
+    var_scope::FunctionLike,
+    Analysis, FilePosition, FileRange, SourceChange, TextEdit, TextEditBuilder,
+};
+
+/// A parameter of the new signature.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamChange {
+    /// The position of the parameter in the old signature, `None` for a new
+    /// parameter.
+    pub old_index: Option<usize>,
+    pub name: String,
+    /// The declared type of a new parameter.
+    pub ty: Option<String>,
+    /// The default value of a new parameter, as a script expression.
+    pub default: Option<String>,
+    /// The value existing calls pass to a new parameter, as a script
+    /// expression. Calls pass nothing when it is `None`.
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeSignature {
+    pub source_change: SourceChange,
+    /// Calls which may target the function but were left as they are.
+    pub unresolved_calls: Vec<FileRange>,
+}
+
+/// A parameter as declared: a script parameter or a `<cfargument>`.
+pub(crate) struct OldParam {
+    pub(crate) syntax: SyntaxNode,
+    pub(crate) name: String,
+    pub(crate) name_range: TextRange,
+    /// The default value, as a script expression.
+    pub(crate) default: Option<String>,
+}
+
+pub(crate) fn change_signature(
+    analysis: &Analysis,
+    position: FilePosition,
+    params: &[ParamChange],
+) -> Option<ChangeSignature> {
+    let root = analysis.parse(position.file_id)?.syntax_node();
+    let func = root
+        .covering_element(TextRange::empty(position.offset))
+        .ancestors()
+        .filter_map(FunctionLike::cast)
+        .find(|it| matches!(it, FunctionLike::Decl(_) | FunctionLike::Tag(_)))?;
+    let name = func.name()?;
+    let old_params = old_params(&func);
+    if params
+        .iter()
+        .any(|it| it.old_index.is_some_and(|idx| idx >= old_params.len()))
+    {
+        return None;
+    }
+
+    let mut edits: Vec<(FileId, TextEditBuilder)> = Vec::new();
+    let mut builder = TextEdit::builder();
+    edit_declaration(&mut builder, &func, &old_params, params);
+    edits.push((position.file_id, builder));
+    let body_renames = renames_in_body(&func, &old_params, params);
+    let mut call_ranges = Vec::new();
+
+    let mut unresolved_calls = Vec::new();
+    // The other roots are other applications, which can't call it.
+    let mut file_ids: Vec<FileId> = analysis
+        .files
+        .keys()
+        .copied()
+        .filter(|&it| analysis.same_root(it, position.file_id))
+        .collect();
+    file_ids.sort_by_key(|it| it.0);
+    let total = file_ids.len();
+    for (done, file_id) in file_ids.into_iter().enumerate() {
+        analysis.unwind_if_cancelled();
+        analysis.report_progress(done, total);
+        let Some(parse) = analysis.parse(file_id) else {
+            continue;
+        };
+        let calls = parse
+            .syntax_node()
+            .descendants()
+            .filter_map(ast::CallExpr::cast)
+            .filter(|it| {
+                it.callee_name()
+                    .is_some_and(|it| it.eq_ignore_ascii_case(&name))
+            });
+        for call in calls {
+            let resolved = match targets(analysis, file_id, &call, position.file_id) {
+                Some(true) => {
+                    // Recursive calls keep the renames of their arguments.
+                    let renames = match file_id == position.file_id {
+                        true => body_renames.as_slice(),
+                        false => &[],
+                    };
+                    edit_call(&call, &old_params, params, renames)
+                }
+                Some(false) => continue,
+                None => None,
+            };
+            match resolved {
+                Some(Some((range, text))) => {
+                    let idx = match edits.iter().position(|(it, _)| *it == file_id) {
+                        Some(idx) => idx,
+                        None => {
+                            edits.push((file_id, TextEdit::builder()));
+                            edits.len() - 1
+                        }
+                    };
+                    edits[idx].1.replace(range, text);
+                    if file_id == position.file_id {
+                        call_ranges.push(range);
+                    }
+                }
+                Some(None) => {}
+                None => unresolved_calls.push(FileRange {
+                    file_id,
+                    range: call.syntax().text_range(),
+                }),
+            }
+        }
+    }
+
+    for (range, text) in body_renames {
+        if !call_ranges.iter().any(|it| it.contains_range(range)) {
+            edits[0].1.replace(range, text);
+        }
+    }
+
+    let mut source_change = SourceChange::default();
+    for (file_id, builder) in edits {
+        let edit = builder.finish();
+        if !edit.is_empty() {
+            source_change.insert_source_edit(file_id, edit);
+        }
+    }
+    Some(ChangeSignature {
+        source_change,
+        unresolved_calls,
+    })
+}
+
+pub(crate) fn old_params(func: &FunctionLike) -> Vec<OldParam> {
+    match func {
+        FunctionLike::Decl(decl) => decl
+            .param_list()
+            .into_iter()
+            .flat_map(|it| it.params())
+            .filter_map(|param| {
+                let name = param.name()?;
+                Some(OldParam {
+                    name: name.text()?,
+                    name_range: name.syntax().text_range(),
+                    default: param.default().map(|it| it.syntax().text()),
+                    syntax: param.syntax().clone(),
+                })
+            })
+            .collect(),
+        FunctionLike::Tag(tag) => tag
+            .child_tags()
+            .filter(|it| it.name_lower() == "cfargument")
+            .filter_map(|tag| {
+                let token = static_text(tag.attribute("name")?.value_element()?)?;
+                let ty = tag.attribute("type").and_then(|it| it.value());
+                let default = tag
+                    .attribute("default")
+                    .and_then(|it| attribute_expr(&it, ty.as_deref().unwrap_or("any")));
+                Some(OldParam {
+                    name: token.text().to_string(),
+                    name_range: token.text_range(),
+                    default,
+                    syntax: tag.syntax().clone(),
+                })
+            })
+            .collect(),
+        FunctionLike::Closure(_) | FunctionLike::Arrow(_) => Vec::new(),
+    }
+}
+
+/// The text of `node` with the replacements falling inside it applied.
+fn rewritten(node: &SyntaxNode, replacements: &[(TextRange, String)]) -> String {
+    let node_range = node.text_range();
+    let mut replacements: Vec<&(TextRange, String)> = replacements
+        .iter()
+        .filter(|(range, _)| node_range.contains_range(*range))
+        .collect();
+    replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start()));
+    let mut res = node.text();
+    for (range, text) in replacements {
+        let start = range.start() - node_range.start();
+        let range = TextRange::at(start, range.len());
+        res.replace_range(std::ops::Range::from(range), text);
+    }
+    res
+}
+
+fn edit_declaration(
+    builder: &mut TextEditBuilder,
+    func: &FunctionLike,
+    old_params: &[OldParam],
+    params: &[ParamChange],
+) {
+    let is_script = matches!(func, FunctionLike::Decl(_));
+    let texts: Vec<String> = params
+        .iter()
+        .map(|param| match param.old_index {
+            Some(idx) => {
+                let old = &old_params[idx];
+                rewritten(&old.syntax, &[(old.name_range, param.name.clone())])
+            }
+            None if is_script => {
+                let mut res = String::new();
+                if let Some(ty) = &param.ty {
+                    res.push_str(&format!("{ty} "));
+                }
+                res.push_str(&param.name);
+                if let Some(default) = &param.default {
+                    res.push_str(&format!(" = {default}"));
+                }
+                res
+            }
+            None => {
+                let mut res = format!("<cfargument name=\"{}\"", param.name);
+                if let Some(ty) = &param.ty {
+                    res.push_str(&format!(" type=\"{ty}\""));
+                }
+                if let Some(default) = &param.default {
+                    res.push_str(&format!(" default=\"{}\"", attribute_value(default)));
+                }
+                res.push('>');
+                res
+            }
+        })
+        .collect();
+
+    match func {
+        FunctionLike::Decl(decl) => {
+            let Some(param_list) = decl.param_list() else {
+                return;
+            };
+            let range = param_list.syntax().text_range();
+            let inner = TextRange::new(range.start() + 1, range.end() - 1);
+            if param_list.syntax().text() != format!("({})", texts.join(", ")) {
+                builder.replace(inner, texts.join(", "));
+            }
+        }
+        FunctionLike::Tag(tag) => match (old_params.first(), old_params.last()) {
+            (Some(first), Some(last)) => {
+                if texts.is_empty() {
+                    for old in old_params {
+                        builder.delete(removal_range(&old.syntax));
+                    }
+                    return;
+                }
+                let indent = line_indent(&first.syntax);
+                let range = first.syntax.text_range().cover(last.syntax.text_range());
+                builder.replace(range, texts.join(&format!("\n{indent}")));
+            }
+            _ => {
+                let Some(open) = tag.open() else {
+                    return;
+                };
+                let outer = line_indent(tag.syntax());
+                let indent = format!("{outer}{}", indent_unit(&outer));
+                for text in texts {
+                    builder.insert(
+                        open.syntax().text_range().end(),
+                        format!("\n{indent}{text}"),
+                    );
+                }
+            }
+        },
+        FunctionLike::Closure(_) | FunctionLike::Arrow(_) => {}
+    }
+}
+
+/// The renames of the uses of renamed parameters in the body, as `name`
+/// and as `arguments.name`.
+fn renames_in_body(
+    func: &FunctionLike,
+    old_params: &[OldParam],
+    params: &[ParamChange],
+) -> Vec<(TextRange, String)> {
+    let locals: Vec<String> = func
+        .own_nodes()
+        .into_iter()
+        .filter_map(ast::VarDecl::cast)
+        .filter_map(|it| Some(it.name()?.text().to_ascii_lowercase()))
+        .collect();
+    let renames: Vec<(&str, &str)> = params
+        .iter()
+        .filter_map(|param| {
+            let old = &old_params[param.old_index?];
+            (old.name != param.name).then_some((old.name.as_str(), param.name.as_str()))
+        })
+        .collect();
+    let new_name = |name: &str| {
+        renames
+            .iter()
+            .find(|(old, _)| old.eq_ignore_ascii_case(name))
+            .map(|(_, new)| new.to_string())
+    };
+    let mut res = Vec::new();
+    for node in func.own_nodes() {
+        if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+            let text = name_ref.text();
+            if locals.contains(&text.to_ascii_lowercase()) {
+                continue;
+            }
+            if let Some(new) = new_name(&text) {
+                res.push((name_ref.syntax().text_range(), new));
+            }
+        } else if let Some(field) = ast::FieldExpr::cast(node) {
+            // `x.name` is not the parameter, unless `x` is `arguments`.
+            let is_arguments = matches!(
+                field.receiver(),
+                Some(ast::Expr::NameRef(it)) if it.text().eq_ignore_ascii_case("arguments")
+            );
+            let Some(token) = field.field().filter(|_| is_arguments) else {
+                continue;
+            };
+            if let Some(new) = new_name(token.text()) {
+                res.push((token.text_range(), new));
+            }
+        }
+    }
+    res
+}
+
+/// Whether `call` calls the function declared in `decl_file`: `None` when
+/// it can't be told.
+fn targets(
+    analysis: &Analysis,
+    file_id: FileId,
+    call: &ast::CallExpr,
+    decl_file: FileId,
+) -> Option<bool> {
+    let inherits = |file_id| {
+        let mut res = false;
+        walk_extends(analysis, file_id, |it, _| res |= it == decl_file);
+        res
+    };
+    match call.callee()? {
+        ast::Expr::NameRef(_) => Some(inherits(file_id)),
+        ast::Expr::FieldExpr(field) => {
+            let receiver = field.receiver()?;
+            if let ast::Expr::NameRef(it) = &receiver {
+                let scope = it.text().to_ascii_lowercase();
+                if matches!(scope.as_str(), "this" | "variables" | "super") {
+                    return Some(inherits(file_id));
+                }
+            }
+            match infer_expr(&receiver) {
+                Ty::Component(name) => {
+                    let target = resolve_component(analysis, file_id, &name)?;
+                    Some(inherits(target))
+                }
+                Ty::Any => None,
+                _ => Some(false),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The new text of the arguments of `call`, between its parentheses, or
+/// `Some(None)` when they stay as they are. `None` when the call can't be
+/// updated.
+fn edit_call(
+    call: &ast::CallExpr,
+    old_params: &[OldParam],
+    params: &[ParamChange],
+    renames: &[(TextRange, String)],
+) -> Option<Option<(TextRange, String)>> {
+    let arg_list = call.arg_list()?;
+    let args: Vec<ast::Arg> = arg_list.args().collect();
+    let texts = if args.iter().any(|it| it.name().is_some()) {
+        match args.as_slice() {
+            [only] if only.name()?.eq_ignore_ascii_case("argumentcollection") => {
+                let ast::Expr::StructLit(collection) = only.expr()? else {
+                    return None;
+                };
+                let fields: Vec<SyntaxNode> = collection
+                    .syntax()
+                    .children()
+                    .filter(|it| it.kind() == STRUCT_FIELD)
+                    .collect();
+                let separator = match fields.first() {
+                    Some(it) if it.children_with_tokens().any(|it| it.kind() == EQ) => " = ",
+                    _ => ": ",
+                };
+                let fields = named(&fields, old_params, params, renames, separator)?;
+                let text = format!("{{{}}}", fields.join(", "));
+                let expr = collection.syntax();
+                if expr.text() == text {
+                    return Some(None);
+                }
+                return Some(Some((expr.text_range(), text)));
+            }
+            _ => {
+                let args: Vec<SyntaxNode> = args.iter().map(|it| it.syntax().clone()).collect();
+                named(&args, old_params, params, renames, " = ")?
+            }
+        }
+    } else {
+        positional(&args, old_params, params, renames)?
+    };
+    let range = arg_list.syntax().text_range();
+    if arg_list.syntax().text() == format!("({})", texts.join(", ")) {
+        return Some(None);
+    }
+    let inner = TextRange::new(range.start() + 1, range.end() - 1);
+    Some(Some((inner, texts.join(", "))))
+}
+
+fn positional(
+    args: &[ast::Arg],
+    old_params: &[OldParam],
+    params: &[ParamChange],
+    renames: &[(TextRange, String)],
+) -> Option<Vec<String>> {
+    let mut texts: Vec<Option<String>> = params
+        .iter()
+        .map(|param| match param.old_index {
+            Some(idx) => args.get(idx).map(|it| rewritten(it.syntax(), renames)),
+            None => param.value.clone(),
+        })
+        .collect();
+    // Arguments past the declared parameters, e.g. for `arguments[3]`,
+    // stay last.
+    let extra: Vec<Option<String>> = args
+        .iter()
+        .skip(old_params.len())
+        .map(|it| Some(rewritten(it.syntax(), renames)))
+        .collect();
+    if extra.is_empty() {
+        while texts.last().is_some_and(Option::is_none) {
+            texts.pop();
+        }
+    }
+    // Skipped parameters in between get their default value.
+    for (text, param) in texts.iter_mut().zip(params) {
+        if text.is_none() {
+            let default = match param.old_index {
+                Some(idx) => old_params[idx].default.clone(),
+                None => param.default.clone(),
+            };
+            *text = Some(default?);
+        }
+    }
+    texts.extend(extra);
+    texts.into_iter().collect()
+}
+
+/// The new texts of named arguments, or of the fields of an
+/// `argumentCollection`.
+fn named(
+    items: &[SyntaxNode],
+    old_params: &[OldParam],
+    params: &[ParamChange],
+    renames: &[(TextRange, String)],
+    separator: &str,
+) -> Option<Vec<String>> {
+    let mut texts = Vec::new();
+    for item in items {
+        let key = static_text(item.first_child_or_token()?)?;
+        let old_index = old_params
+            .iter()
+            .position(|it| it.name.eq_ignore_ascii_case(key.text()));
+        let Some(old_index) = old_index else {
+            // Not a declared parameter: passed through as it is.
+            texts.push(rewritten(item, renames));
+            continue;
+        };
+        if let Some(param) = params.iter().find(|it| it.old_index == Some(old_index)) {
+            let mut renames = renames.to_vec();
+            renames.push((key.text_range(), param.name.clone()));
+            texts.push(rewritten(item, &renames));
+        }
+    }
+    for param in params.iter().filter(|it| it.old_index.is_none()) {
+        if let Some(value) = &param.value {
+            texts.push(format!("{}{separator}{value}", param.name));
+        }
+    }
+    Some(texts)
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::TextSize;
+    use virtual_fs::FileId;
+
+    use super::*;
+    use crate::diagnostics::tests::analysis_from_fixture;
+
+    /// Changes the signature of the function at `$0` in the first file and
+    /// checks the text of every file and the calls left as they are.
+    fn check(fixture: &str, params: &[ParamChange], after: &str, unresolved: &[&str]) {
+        let offset = analysis_from_fixture(fixture)
+            .file_text(FileId(0))
+            .unwrap()
+            .find("$0")
+            .unwrap();
+        let analysis = analysis_from_fixture(&fixture.replace("$0", ""));
+        let position = FilePosition {
+            file_id: FileId(0),
+            offset: offset as TextSize,
+        };
+        let res = change_signature(&analysis, position, params).unwrap();
+
+        let mut actual = String::new();
+        let mut file_ids: Vec<FileId> = analysis.files.keys().copied().collect();
+        file_ids.sort_by_key(|it| it.0);
+        for file_id in file_ids {
+            let mut text = analysis.file_text(file_id).unwrap().to_string();
+            if let Some(edit) = res.source_change.get_source_edit(file_id) {
+                edit.apply(&mut text);
+            }
+            actual.push_str(&format!("//- {}\n{text}\n", analysis.paths[&file_id]));
+        }
+        assert_eq!(actual.trim(), after.trim());
+
+        let unresolved_calls: Vec<String> = res
+            .unresolved_calls
+            .iter()
+            .map(|it| {
+                let text = analysis.file_text(it.file_id).unwrap();
+                text[std::ops::Range::from(it.range)].to_string()
+            })
+            .collect();
+        assert_eq!(unresolved_calls, unresolved);
+    }
+
+    fn param(old_index: usize, name: &str) -> ParamChange {
+        ParamChange {
+            old_index: Some(old_index),
+            name: name.to_string(),
+            ..ParamChange::default()
+        }
+    }
+
+    #[test]
+    fn reorder_and_rename() {
+        check(
+            r#"
+//- /app/Service.cfc
+component {
+    function $0save(required string name, numeric age = 0) {
+        return arguments.name & age;
+    }
+    function run() {
+        save("a", 1);
+        this.save(name = "a", age = 1);
+        save(argumentCollection = {name: "a", "age": 1});
+    }
+}
+//- /app/handler.cfm
+<cfscript>
+service = new Service();
+service.save("b");
+other.save("c", 2);
+cache.save(argumentCollection = args);
+</cfscript>
+"#,
+            &[param(1, "years"), param(0, "name")],
+            r#"
+//- /app/Service.cfc
+component {
+    function save(numeric years = 0, required string name) {
+        return arguments.name & years;
+    }
+    function run() {
+        save(1, "a");
+        this.save(name = "a", years = 1);
+        save(argumentCollection = {name: "a", "years": 1});
+    }
+}
+//- /app/handler.cfm
+<cfscript>
+service = new Service();
+service.save(0, "b");
+other.save("c", 2);
+cache.save(argumentCollection = args);
+</cfscript>
+"#,
+            &[
+                r#"other.save("c", 2)"#,
+                "cache.save(argumentCollection = args)",
+            ],
+        );
+    }
+
+    #[test]
+    fn add_and_remove_in_tags() {
+        check(
+            r##"
+//- /app/Mailer.cfc
+<cfcomponent>
+    <cffunction name="$0send">
+        <cfargument name="to" required="true">
+        <cfargument name="debug" type="boolean" default="false">
+        <cfreturn arguments.to>
+    </cffunction>
+</cfcomponent>
+//- /app/Newsletter.cfc
+component extends="Mailer" {
+    function run() {
+        send("a@b.c", true);
+        send(to = "d@e.f");
+    }
+}
+"##,
+            &[
+                param(0, "recipient"),
+                ParamChange {
+                    name: "subject".to_string(),
+                    ty: Some("string".to_string()),
+                    default: Some("\"\"".to_string()),
+                    value: Some("\"Hi\"".to_string()),
+                    ..ParamChange::default()
+                },
+            ],
+            r##"
+//- /app/Mailer.cfc
+<cfcomponent>
+    <cffunction name="send">
+        <cfargument name="recipient" required="true">
+        <cfargument name="subject" type="string" default="">
+        <cfreturn arguments.recipient>
+    </cffunction>
+</cfcomponent>
+//- /app/Newsletter.cfc
+component extends="Mailer" {
+    function run() {
+        send("a@b.c", "Hi");
+        send(recipient = "d@e.f", subject = "Hi");
+    }
+}
+"##,
+            &[],
+        );
+    }
+
+    #[test]
+    fn rename_in_recursive_call() {
+        check(
+            r#"
+//- /app/Job.cfc
+component {
+    function $0retry(count, delay) {
+        if (count > 0) retry(count - 1, arguments.delay * 2);
+    }
+}
+"#,
+            &[param(0, "attempts")],
+            r#"
+//- /app/Job.cfc
+component {
+    function retry(attempts) {
+        if (attempts > 0) retry(attempts - 1);
+    }
+}
+"#,
+            &[],
+        );
+    }
+}
@@ -0,0 +1,408 @@
+//! Components, written either in script (`component { ... }`) or with tags
+//! (`<cfcomponent>...</cfcomponent>`), and their members.
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextSize,
+};
+
+use crate::{assists::utils::attribute_expr, var_scope::FunctionLike};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Component {
+    Script(ast::ComponentDecl),
+    Tag(ast::Tag),
+}
+
+impl Component {
+    pub(crate) fn cast(node: SyntaxNode) -> Option<Component> {
+        match node.kind() {
+            COMPONENT_DECL => ast::ComponentDecl::cast(node).map(Component::Script),
+            TAG => {
+                let tag = ast::Tag::cast(node)?;
+                matches!(tag.name_lower().as_str(), "cfcomponent" | "cfinterface")
+                    .then_some(Component::Tag(tag))
+            }
+            _ => None,
+        }
+    }
+
+    /// The component a node belongs to.
+    pub(crate) fn enclosing(node: &SyntaxNode) -> Option<Component> {
+        node.ancestors().find_map(Component::cast)
+    }
+
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Component::Script(it) => it.syntax(),
+            Component::Tag(it) => it.syntax(),
+        }
+    }
+
+    pub(crate) fn is_script(&self) -> bool {
+        matches!(self, Component::Script(_))
+    }
+
+    pub(crate) fn is_interface(&self) -> bool {
+        match self {
+            Component::Script(it) => it.is_interface(),
+            Component::Tag(it) => it.name_lower() == "cfinterface",
+        }
+    }
+
+    pub(crate) fn find_attribute(&self, name: &str) -> Option<ast::Attribute> {
+        match self {
+            Component::Script(it) => it.attribute(name),
+            Component::Tag(it) => it.attribute(name),
+        }
+    }
+
+    /// The static value of an attribute of the component.
+    pub(crate) fn attribute(&self, name: &str) -> Option<String> {
+        self.find_attribute(name)?.value()
+    }
+
+    /// The components named by a list attribute, e.g. `implements="IA, IB"`.
+    pub(crate) fn names_in(&self, attribute: &str) -> Vec<String> {
+        self.attribute(attribute)
+            .map(|it| {
+                it.split(',')
+                    .map(str::trim)
+                    .filter(|it| !it.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if the engine generates accessors for the properties,
+    /// through `accessors="true"` or an ORM entity.
+    pub(crate) fn has_implicit_accessors(&self) -> bool {
+        let is_true = |name| self.attribute(name).is_some_and(|it| is_truthy(&it));
+        is_true("accessors") || is_true("persistent")
+    }
+
+    /// The direct members of the component: properties, functions and
+    /// anything else written in its body.
+    pub(crate) fn members(&self) -> Vec<SyntaxNode> {
+        match self {
+            Component::Script(it) => it
+                .body()
+                .map(|it| it.statements().collect())
+                .unwrap_or_default(),
+            Component::Tag(it) => it.child_tags().map(|it| it.syntax().clone()).collect(),
+        }
+    }
+
+    pub(crate) fn properties(&self) -> Vec<Property> {
+        self.members()
+            .into_iter()
+            .filter_map(Property::cast)
+            .collect()
+    }
+
+    /// Functions declared directly in the component.
+    pub(crate) fn functions(&self) -> Vec<FunctionLike> {
+        let mut res = Vec::new();
+        for member in self.members() {
+            match FunctionLike::cast(member.clone()) {
+                Some(it) => res.push(it),
+                // Script blocks inside a tag component.
+                None => res.extend(
+                    member
+                        .descendants()
+                        .filter(|it| it.kind() == FUNCTION_DECL)
+                        .filter_map(FunctionLike::cast),
+                ),
+            }
+        }
+        res
+    }
+
+    pub(crate) fn has_function(&self, name: &str) -> bool {
+        self.functions()
+            .iter()
+            .any(|it| it.name().is_some_and(|it| it.eq_ignore_ascii_case(name)))
+    }
+
+    /// Where to insert new members: after the last existing one, or at the
+    /// start of the body.
+    pub(crate) fn members_end(&self) -> Option<TextSize> {
+        if let Some(last) = self.members().last() {
+            return Some(last.text_range().end());
+        }
+        match self {
+            Component::Script(it) => Some(it.body()?.l_brace()?.text_range().end()),
+            Component::Tag(it) => Some(it.open()?.syntax().text_range().end()),
+        }
+    }
+}
+
+/// The signature of a function, whether it is declared in script or with
+/// `<cffunction>` and `<cfargument>` tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Signature {
+    pub(crate) name: String,
+    pub(crate) access: Option<String>,
+    pub(crate) return_type: Option<String>,
+    pub(crate) params: Vec<Param>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Param {
+    pub(crate) name: String,
+    pub(crate) ty: Option<String>,
+    pub(crate) required: bool,
+    /// The default value, as a script expression.
+    pub(crate) default: Option<String>,
+}
+
+impl Signature {
+    pub(crate) fn of(func: &FunctionLike) -> Option<Signature> {
+        match func {
+            FunctionLike::Decl(decl) => {
+                let params = decl
+                    .param_list()
+                    .map(|it| it.params().collect::<Vec<_>>())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|param| {
+                        Some(Param {
+                            name: param.name()?.text()?,
+                            ty: param.ty().map(|it| it.syntax().text()),
+                            required: param.is_required(),
+                            default: param.default().map(|it| it.syntax().text()),
+                        })
+                    })
+                    .collect::<Option<_>>()?;
+                Some(Signature {
+                    name: decl.name()?.text()?,
+                    access: decl.access(),
+                    return_type: decl.return_type().map(|it| it.syntax().text()),
+                    params,
+                })
+            }
+            FunctionLike::Tag(tag) => {
+                let value = |tag: &ast::Tag, name| tag.attribute(name).and_then(|it| it.value());
+                let params = tag
+                    .child_tags()
+                    .filter(|it| it.name_lower() == "cfargument")
+                    .map(|arg| {
+                        let ty = value(&arg, "type");
+                        let default = arg
+                            .attribute("default")
+                            .and_then(|it| attribute_expr(&it, ty.as_deref().unwrap_or("any")));
+                        Some(Param {
+                            name: value(&arg, "name")?,
+                            required: value(&arg, "required").is_some_and(|it| is_truthy(&it)),
+                            ty,
+                            default,
+                        })
+                    })
+                    .collect::<Option<_>>()?;
+                Some(Signature {
+                    name: value(tag, "name")?,
+                    access: value(tag, "access"),
+                    return_type: value(tag, "returntype"),
+                    params,
+                })
+            }
+            FunctionLike::Closure(_) | FunctionLike::Arrow(_) => None,
+        }
+    }
+}
+
+/// A `property` declaration or a `<cfproperty>` tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Property {
+    syntax: SyntaxNode,
+}
+
+impl Property {
+    pub(crate) fn cast(node: SyntaxNode) -> Option<Property> {
+        let is_property = match node.kind() {
+            PROPERTY_DECL => true,
+            TAG => ast::Tag::cast(node.clone())?.name_lower() == "cfproperty",
+            _ => false,
+        };
+        is_property.then_some(Property { syntax: node })
+    }
+
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<ast::Attribute> {
+        match ast::PropertyDecl::cast(self.syntax.clone()) {
+            Some(it) => it.attributes().collect(),
+            None => ast::Tag::cast(self.syntax.clone())
+                .map(|it| it.attributes().collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn find_attribute(&self, name: &str) -> Option<ast::Attribute> {
+        self.attributes()
+            .into_iter()
+            .find(|it| it.name_lower() == name)
+    }
+
+    /// The static value of an attribute of the property.
+    pub(crate) fn attribute(&self, name: &str) -> Option<String> {
+        self.find_attribute(name)?.value()
+    }
+
+    pub(crate) fn name(&self) -> Option<String> {
+        match ast::PropertyDecl::cast(self.syntax.clone()) {
+            Some(it) => it.name(),
+            None => self.attribute("name"),
+        }
+    }
+
+    /// The declared type, from `property string x;` or `type="string"`.
+    pub(crate) fn ty(&self) -> Option<String> {
+        let decl = ast::PropertyDecl::cast(self.syntax.clone());
+        decl.and_then(|it| it.ty())
+            .map(|it| it.syntax().text())
+            .or_else(|| self.attribute("type"))
+    }
+
+    /// Returns `false` if the property opts out of its getter or setter with
+    /// `getter="false"` or `setter="false"`.
+    pub(crate) fn wants_accessor(&self, accessor: &str) -> bool {
+        self.attribute(accessor).is_none_or(|it| is_truthy(&it))
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("yes")
+}
+
+/// The whitespace at the start of the line `node` starts on.
+pub(crate) fn line_indent(node: &SyntaxNode) -> String {
+    let mut line = String::new();
+    let mut element = node.first_token().and_then(|it| it.prev_token());
+    while let Some(token) = element {
+        match token.text().rfind('\n') {
+            Some(idx) => {
+                line.insert_str(0, &token.text()[idx + 1..]);
+                break;
+            }
+            None => line.insert_str(0, token.text()),
+        }
+        element = token.prev_token();
+    }
+    line.chars()
+        .take_while(|it| matches!(it, ' ' | '\t'))
+        .collect()
+}
+
+/// Returns `true` if only whitespace precedes `node` on its line.
+pub(crate) fn starts_line(node: &SyntaxNode) -> bool {
+    let mut element = node.first_token().and_then(|it| it.prev_token());
+    while let Some(token) = element {
+        let text = token.text();
+        let before = match text.rfind('\n') {
+            Some(idx) => &text[idx + 1..],
+            None => text,
+        };
+        if !before.trim().is_empty() {
+            return false;
+        }
+        if text.contains('\n') {
+            return true;
+        }
+        element = token.prev_token();
+    }
+    true
+}
+
+/// One level of indentation, in the style of `indent`.
+pub(crate) fn indent_unit(indent: &str) -> &'static str {
+    if indent.starts_with('\t') {
+        "\t"
+    } else {
+        "    "
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(text: &str) -> Component {
+        let root = syntax::parse(text).syntax_node();
+        root.descendants().find_map(Component::cast).unwrap()
+    }
+
+    #[test]
+    fn test_script_component() {
+        let comp = component(
+            "component accessors=true {
+    property name=\"id\" type=\"numeric\" setter=false;
+    property string title;
+    function getTitle() {}
+}",
+        );
+        assert!(comp.is_script());
+        assert!(comp.has_implicit_accessors());
+        let props = comp.properties();
+        assert_eq!(props[0].name().as_deref(), Some("id"));
+        assert_eq!(props[0].ty().as_deref(), Some("numeric"));
+        assert!(!props[0].wants_accessor("setter"));
+        assert_eq!(props[1].name().as_deref(), Some("title"));
+        assert_eq!(props[1].ty().as_deref(), Some("string"));
+        assert!(comp.has_function("GETTITLE"));
+        assert_eq!(comp.names_in("accessors"), ["true"]);
+        assert_eq!(line_indent(props[1].syntax()), "    ");
+    }
+
+    #[test]
+    fn test_tag_component() {
+        let comp = component(
+            r#"<cfcomponent>
+	<cfproperty name="id" type="numeric">
+	<cffunction name="getId"></cffunction>
+</cfcomponent>"#,
+        );
+        assert!(!comp.has_implicit_accessors());
+        assert_eq!(comp.properties()[0].name().as_deref(), Some("id"));
+        assert!(comp.has_function("getid"));
+        assert_eq!(line_indent(comp.properties()[0].syntax()), "\t");
+        let signature = Signature::of(&comp.functions()[0]).unwrap();
+        assert_eq!(signature.name, "getId");
+        assert!(signature.params.is_empty());
+        assert_eq!(
+            comp.members_end(),
+            Some(comp.functions()[0].syntax().text_range().end())
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        let script =
+            component("interface { public string function f(required numeric a, b = \"x\"); }");
+        let tags = component(
+            r#"<cfinterface>
+	<cffunction name="f" access="public" returntype="string">
+		<cfargument name="a" type="numeric" required="true">
+		<cfargument name="b" default="x">
+	</cffunction>
+</cfinterface>"#,
+        );
+        let script = Signature::of(&script.functions()[0]).unwrap();
+        assert_eq!(script, Signature::of(&tags.functions()[0]).unwrap());
+        assert_eq!(script.access.as_deref(), Some("public"));
+        assert_eq!(script.return_type.as_deref(), Some("string"));
+        assert_eq!(
+            script.params[0],
+            Param {
+                name: "a".to_string(),
+                ty: Some("numeric".to_string()),
+                required: true,
+                default: None,
+            }
+        );
+        assert_eq!(script.params[1].default.as_deref(), Some("\"x\""));
+    }
+}
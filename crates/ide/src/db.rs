@@ -0,0 +1,263 @@
+//! The queries derived from the files, memoized so that a change only
+//! recomputes what depends on it.
+//!
+//! The text and the path of each file are the inputs. What is derived from
+//! one file alone — its tree, its line index, the components it names — is
+//! kept in its [`FileData`], computed on first use and shared by every
+//! snapshot holding the same text. Setting the text of a file replaces its
+//! `FileData`, which drops these for this file only.
+//!
+//! What is derived from all files is kept in a [`Memo`] beside them, which
+//! the host resets when one of its inputs changes: the component references
+//! of the workspace when a text does, and the normalized paths, the files by
+//! name and their roots when a path does. These are rebuilt from the per-file queries, so an edit reparses a
+//! single file.
+//!
+//! The per-file queries needed by the ones over every file are also exposed
+//! as a [`FileIndex`], which the server keeps on disk to skip parsing the
+//! files which didn't change on its next start.
+use std::sync::{Arc, OnceLock};
+
+use rustc_hash::FxHashMap;
+use syntax::{Parse, TextRange};
+use virtual_fs::FileId;
+
+use crate::{
+    resolve::{self, ComponentReference},
+    LineIndex, Name, Symbol,
+};
+
+/// A file's text and the queries derived from it alone.
+#[derive(Debug)]
+pub(crate) struct FileData {
+    pub(crate) text: Arc<str>,
+    parse: OnceLock<Parse>,
+    line_index: OnceLock<Arc<LineIndex>>,
+    component_references: OnceLock<Arc<[ComponentReference]>>,
+}
+
+impl FileData {
+    pub(crate) fn new(text: Arc<str>) -> FileData {
+        FileData {
+            text,
+            parse: OnceLock::new(),
+            line_index: OnceLock::new(),
+            component_references: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn parse(&self) -> Parse {
+        self.parse.get_or_init(|| syntax::parse(&self.text)).clone()
+    }
+
+    /// Returns `true` once the file was parsed.
+    pub(crate) fn is_parsed(&self) -> bool {
+        self.parse.get().is_some()
+    }
+
+    pub(crate) fn line_index(&self) -> Arc<LineIndex> {
+        Arc::clone(
+            self.line_index
+                .get_or_init(|| Arc::new(LineIndex::new(&self.text))),
+        )
+    }
+
+    /// The names of components in the file, whose id is `file_id`.
+    pub(crate) fn component_references(&self, file_id: FileId) -> Arc<[ComponentReference]> {
+        Arc::clone(self.component_references.get_or_init(|| {
+            resolve::file_component_references(file_id, &self.parse().syntax_node()).into()
+        }))
+    }
+
+    pub(crate) fn index(&self, file_id: FileId) -> FileIndex {
+        let component_references = self
+            .component_references(file_id)
+            .iter()
+            .map(|it| IndexedReference {
+                range: it.range,
+                name: it.name,
+                wirebox: it.wirebox,
+            })
+            .collect();
+        FileIndex {
+            component_references,
+        }
+    }
+
+    /// Fills the queries `index` has the result of, unless they ran already.
+    pub(crate) fn set_index(&self, file_id: FileId, index: FileIndex) {
+        let component_references = index
+            .component_references
+            .into_iter()
+            .map(|it| ComponentReference {
+                file_id,
+                range: it.range,
+                name: it.name,
+                wirebox: it.wirebox,
+            })
+            .collect();
+        let _ = self.component_references.set(component_references);
+    }
+}
+
+/// What is indexed from the text of a file alone, valid as long as the text
+/// stays the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIndex {
+    pub component_references: Vec<IndexedReference>,
+}
+
+impl FileIndex {
+    /// Indexes `text`, e.g. on another thread while the workspace loads.
+    pub fn new(text: &str) -> FileIndex {
+        // The id of the file isn't part of the index.
+        FileData::new(text.into()).index(FileId(0))
+    }
+}
+
+/// A place where a component is named, e.g. `extends="Base"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedReference {
+    /// The range of the name alone, without quotes.
+    pub range: TextRange,
+    pub name: Name,
+    /// Whether the name is a WireBox id, which a binder may map elsewhere.
+    pub wirebox: bool,
+}
+
+/// A query over every file, computed once per state of its inputs.
+///
+/// Snapshots share the memo of the host they were taken from, until the
+/// host replaces it after a change.
+#[derive(Debug)]
+pub(crate) struct Memo<T>(Arc<OnceLock<T>>);
+
+impl<T: Clone> Memo<T> {
+    pub(crate) fn get_or_init(&self, f: impl FnOnce() -> T) -> T {
+        // A query cancelled in `f` leaves the memo empty for the next one.
+        self.0.get_or_init(f).clone()
+    }
+}
+
+impl<T> Clone for Memo<T> {
+    fn clone(&self) -> Memo<T> {
+        Memo(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Default for Memo<T> {
+    fn default() -> Memo<T> {
+        Memo(Arc::new(OnceLock::new()))
+    }
+}
+
+/// The queries over every file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Memos {
+    /// The component references of all files, by file id.
+    pub(crate) component_references: Memo<Arc<[ComponentReference]>>,
+    /// The paths of all files, with `/` separators and in lowercase.
+    pub(crate) normalized_paths: Memo<Arc<FxHashMap<FileId, String>>>,
+    /// The files by their name, e.g. `user.cfc`, sorted by id.
+    pub(crate) files_by_name: Memo<Arc<FxHashMap<Symbol, Vec<FileId>>>>,
+    /// The root of each file under one, by index.
+    pub(crate) file_roots: Memo<Arc<FxHashMap<FileId, usize>>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::VirtualFsPath;
+
+    use super::*;
+    use crate::AnalysisHost;
+
+    #[test]
+    fn test_edit_invalidates_one_file() {
+        let mut host = AnalysisHost::default();
+        host.set_file_text(FileId(0), Some("component extends=\"Base\" {}".into()));
+        host.set_file_text(FileId(1), Some("<cfset x = 1>".into()));
+        let analysis = host.analysis();
+        let base_refs = analysis.file_component_references(FileId(0)).unwrap();
+        let line_index = analysis.file_line_index(FileId(1)).unwrap();
+
+        host.set_file_text(FileId(1), Some("<cfset x = 2>".into()));
+        let analysis = host.analysis();
+        assert!(Arc::ptr_eq(
+            &base_refs,
+            &analysis.file_component_references(FileId(0)).unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            &line_index,
+            &analysis.file_line_index(FileId(1)).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_snapshots_share_queries() {
+        let mut host = AnalysisHost::default();
+        host.set_file_text(FileId(0), Some("component extends=\"Base\" {}".into()));
+        let first = host.analysis();
+        let second = host.analysis();
+        let refs = first.component_references();
+        assert!(Arc::ptr_eq(&refs, &second.component_references()));
+        assert!(first
+            .memory_usage()
+            .iter()
+            .any(|it| it.name == "parse trees" && it.count == 1));
+
+        // Paths don't change the references.
+        host.set_file_path(
+            FileId(0),
+            Some(VirtualFsPath::new_virtual_path("/User.cfc".to_string())),
+        );
+        assert!(Arc::ptr_eq(&refs, &host.analysis().component_references()));
+        host.set_file_text(FileId(0), Some("component {}".into()));
+        assert!(host.analysis().component_references().is_empty());
+        assert_eq!(refs.len(), 1);
+    }
+
+    #[test]
+    fn test_set_file_index() {
+        let text = "component extends=\"Base\" {}";
+        let mut host = AnalysisHost::default();
+        host.set_file_text(FileId(0), Some(text.into()));
+        let index = host.analysis().file_index(FileId(0)).unwrap();
+        assert_eq!(index.component_references.len(), 1);
+
+        let mut host = AnalysisHost::default();
+        host.set_file_text(FileId(3), Some(text.into()));
+        host.set_file_index(FileId(3), index);
+        let analysis = host.analysis();
+        let refs = analysis.component_references();
+        assert_eq!(refs[0].file_id, FileId(3));
+        assert_eq!(refs[0].name, "Base");
+        assert!(analysis
+            .memory_usage()
+            .iter()
+            .any(|it| it.name == "parse trees" && it.count == 0));
+    }
+
+    #[test]
+    fn test_files_named() {
+        let mut host = AnalysisHost::default();
+        for (idx, path) in ["/a/User.cfc", "/b/user.cfc", "/b/Role.cfc"]
+            .iter()
+            .enumerate()
+        {
+            host.set_file_path(
+                FileId(idx as u32),
+                Some(VirtualFsPath::new_virtual_path(path.to_string())),
+            );
+        }
+        let user = Symbol::new("USER.cfc");
+        assert_eq!(host.analysis().files_named(user), [FileId(0), FileId(1)]);
+
+        host.set_file_path(
+            FileId(1),
+            Some(VirtualFsPath::new_virtual_path("/b/Admin.cfc".to_string())),
+        );
+        let analysis = host.analysis();
+        assert_eq!(analysis.files_named(user), [FileId(0)]);
+        assert_eq!(analysis.files_named(Symbol::new("admin.cfc")), [FileId(1)]);
+    }
+}
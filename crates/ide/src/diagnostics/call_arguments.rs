@@ -0,0 +1,381 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    TextRange,
+};
+
+use crate::{
+    change_signature::old_params,
+    component::{Param, Signature},
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::{resolve_call, static_text},
+    ty::Ty,
+    var_scope::FunctionLike,
+    FileRange,
+};
+
+// Diagnostic: missing-argument
+//
+// Reported on calls to a function which can be resolved, e.g. of the same
+// component or of `x = new Service()`, when they leave out a required
+// argument.
+
+// Diagnostic: unknown-argument
+//
+// Reported on a named argument the called function doesn't declare, unless
+// the function reads `arguments` as a whole.
+
+// Diagnostic: argument-type-mismatch
+//
+// Reported on a literal argument which can't be converted to the declared
+// type of its parameter, e.g. `"abc"` for a `numeric`. Only literals are
+// checked: CFML converts other values at runtime.
+//
+// Each points at the declaration of the parameter, or of the function, in
+// its related information.
+pub(crate) fn call_arguments(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for call in ctx.root().descendants().filter_map(ast::CallExpr::cast) {
+        let Some(arg_list) = call.arg_list() else {
+            continue;
+        };
+        let Some((decl_file, func)) = resolve_call(ctx.analysis(), ctx.file_id(), &call) else {
+            continue;
+        };
+        let Some(signature) = Signature::of(&func) else {
+            continue;
+        };
+        let in_decl = |range| FileRange {
+            file_id: decl_file,
+            range,
+        };
+        let param_ranges: Vec<(String, TextRange)> = old_params(&func)
+            .into_iter()
+            .map(|it| (it.name.to_ascii_lowercase(), it.name_range))
+            .collect();
+        let param_range = |name: &str| {
+            param_ranges
+                .iter()
+                .find(|(it, _)| it.eq_ignore_ascii_case(name))
+                .map(|(_, range)| in_decl(*range))
+        };
+        let args: Vec<ast::Arg> = arg_list.args().collect();
+        let names: Vec<Option<String>> = args.iter().map(|it| it.name()).collect();
+        // Binds each argument to the parameter it is passed to: by position,
+        // or by name when every argument is named.
+        let bound: Vec<(ast::Arg, Option<&Param>)> = if names.iter().all(Option::is_some) {
+            if names
+                .iter()
+                .flatten()
+                .any(|it| it.eq_ignore_ascii_case("argumentcollection"))
+            {
+                continue;
+            }
+            args.into_iter()
+                .zip(&names)
+                .map(|(arg, name)| {
+                    let name = name.as_deref().unwrap_or_default();
+                    let param = signature
+                        .params
+                        .iter()
+                        .find(|it| it.name.eq_ignore_ascii_case(name));
+                    (arg, param)
+                })
+                .collect()
+        } else if names.iter().all(Option::is_none) {
+            args.into_iter()
+                .enumerate()
+                .map(|(idx, arg)| (arg, signature.params.get(idx)))
+                .collect()
+        } else {
+            // Mixing both is an error of its own.
+            continue;
+        };
+
+        let missing: Vec<&Param> = signature
+            .params
+            .iter()
+            .filter(|param| param.required && param.default.is_none())
+            .filter(|param| !bound.iter().any(|(_, it)| *it == Some(*param)))
+            .collect();
+        if let (Some(range), false) = (callee_range(&call), missing.is_empty()) {
+            let names: Vec<String> = missing.iter().map(|it| format!("`{}`", it.name)).collect();
+            let message = match names.as_slice() {
+                [one] => format!("Missing required argument {one} of `{}`", signature.name),
+                _ => format!(
+                    "Missing required arguments {} of `{}`",
+                    names.join(", "),
+                    signature.name
+                ),
+            };
+            let mut diagnostic =
+                Diagnostic::new("missing-argument", Severity::Error, message, range);
+            for param in missing {
+                if let Some(declared) = param_range(&param.name) {
+                    diagnostic = diagnostic
+                        .with_related(declared, format!("`{}` is declared here", param.name));
+                }
+            }
+            acc.push(diagnostic);
+        }
+
+        let dynamic = reads_all_arguments(&func);
+        for (arg, param) in &bound {
+            let Some(param) = param else {
+                let (Some(name), Some(name_range)) = (arg.name(), name_range(arg)) else {
+                    continue;
+                };
+                if !dynamic {
+                    let mut diagnostic = Diagnostic::new(
+                        "unknown-argument",
+                        Severity::Warning,
+                        format!("`{}` has no argument `{name}`", signature.name),
+                        name_range,
+                    );
+                    if let Some(declared) = func.name_range() {
+                        diagnostic = diagnostic.with_related(
+                            in_decl(declared),
+                            format!("`{}` is declared here", signature.name),
+                        );
+                    }
+                    acc.push(diagnostic);
+                }
+                continue;
+            };
+            let (Some(expr), Some(ty)) = (arg.expr(), param.ty.as_deref()) else {
+                continue;
+            };
+            if let Some(value) = Literal::of(&expr).filter(|it| !it.converts_to(ty)) {
+                let mut diagnostic = Diagnostic::new(
+                    "argument-type-mismatch",
+                    Severity::Error,
+                    format!(
+                        "Argument `{}` of `{}` must be `{ty}`, not {}",
+                        param.name,
+                        signature.name,
+                        value.describe(&expr)
+                    ),
+                    expr.syntax().text_range(),
+                );
+                if let Some(declared) = param_range(&param.name) {
+                    diagnostic = diagnostic.with_related(
+                        declared,
+                        format!("`{}` is declared as `{ty}` here", param.name),
+                    );
+                }
+                acc.push(diagnostic);
+            }
+        }
+    }
+    Some(())
+}
+
+/// The name of the called function: `f` in `f()` and `x.f()`.
+fn callee_range(call: &ast::CallExpr) -> Option<TextRange> {
+    match call.callee()? {
+        ast::Expr::NameRef(it) => Some(it.syntax().text_range()),
+        ast::Expr::FieldExpr(it) => Some(it.field()?.text_range()),
+        _ => None,
+    }
+}
+
+/// The name of a named argument, without quotes.
+fn name_range(arg: &ast::Arg) -> Option<TextRange> {
+    Some(static_text(arg.syntax().first_child_or_token()?)?.text_range())
+}
+
+/// Returns `true` if `func` looks up its arguments dynamically, e.g. with
+/// `structKeyExists(arguments, key)`.
+fn reads_all_arguments(func: &FunctionLike) -> bool {
+    func.syntax()
+        .descendants()
+        .filter_map(ast::NameRef::cast)
+        .filter(|it| it.text().eq_ignore_ascii_case("arguments"))
+        .any(|it| {
+            it.syntax()
+                .parent()
+                .and_then(ast::FieldExpr::cast)
+                .is_none()
+        })
+}
+
+/// A literal argument, as far as conversions go.
+enum Literal {
+    Number,
+    Boolean,
+    String(String),
+    Array,
+    Struct,
+    Function,
+}
+
+impl Literal {
+    fn of(expr: &ast::Expr) -> Option<Literal> {
+        let res = match expr.clone().unwrapped() {
+            ast::Expr::Literal(it) => {
+                let token = it.token()?;
+                if token.kind() == NUMBER {
+                    Literal::Number
+                } else if token.text().eq_ignore_ascii_case("null") {
+                    return None;
+                } else {
+                    Literal::Boolean
+                }
+            }
+            ast::Expr::StringLit(it) => Literal::String(it.value()?),
+            ast::Expr::ArrayLit(_) => Literal::Array,
+            ast::Expr::StructLit(_) => Literal::Struct,
+            ast::Expr::ClosureExpr(_) | ast::Expr::ArrowExpr(_) => Literal::Function,
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    /// Returns `false` if the engine would reject the value for an argument
+    /// of type `ty`.
+    fn converts_to(&self, ty: &str) -> bool {
+        // Typed arrays, e.g. `string[]`.
+        let ty = match ty.trim().ends_with("[]") {
+            true => Ty::Array,
+            false => Ty::from_name(ty),
+        };
+        match (ty, self) {
+            (Ty::Any | Ty::Void, _) => true,
+            (Ty::String, it) => {
+                matches!(it, Literal::Number | Literal::Boolean | Literal::String(_))
+            }
+            (Ty::Numeric, Literal::String(it)) => it.trim().parse::<f64>().is_ok(),
+            (Ty::Numeric, it) => matches!(it, Literal::Number | Literal::Boolean),
+            (Ty::Boolean, Literal::String(it)) => {
+                let it = it.trim();
+                ["true", "false", "yes", "no"]
+                    .iter()
+                    .any(|b| it.eq_ignore_ascii_case(b))
+                    || it.parse::<f64>().is_ok()
+            }
+            (Ty::Boolean, it) => matches!(it, Literal::Number | Literal::Boolean),
+            (Ty::Date | Ty::Xml, it) => matches!(it, Literal::Number | Literal::String(_)),
+            (Ty::Array, it) => matches!(it, Literal::Array),
+            (Ty::Struct, it) => matches!(it, Literal::Struct),
+            (Ty::Function, it) => matches!(it, Literal::Function),
+            (Ty::Binary | Ty::Query | Ty::Component(_), _) => false,
+        }
+    }
+
+    /// How the value reads in a message: scalars as written, the others by
+    /// their kind.
+    fn describe(&self, expr: &ast::Expr) -> String {
+        match self {
+            Literal::Number | Literal::Boolean | Literal::String(_) => {
+                format!("`{}`", expr.syntax().text())
+            }
+            Literal::Array => "an array".to_string(),
+            Literal::Struct => "a struct".to_string(),
+            Literal::Function => "a function".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_related};
+
+    #[test]
+    fn missing_and_unknown_arguments() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    function save(required string name, numeric age = 0, required id) {
+        return name & age & id;
+    }
+    function log(message) {
+        return structCount(arguments);
+    }
+    function f() {
+        save("a");
+        this.save(name = "a", id = 1, nme = "b");
+        save(argumentCollection = {name: "a"});
+        log(message = "a", level = "info");
+        var user = new User();
+        user.load();
+        user.load(1);
+    }
+}
+//- /app/User.cfc
+<cfcomponent>
+    <cffunction name="load">
+        <cfargument name="id" required="true">
+        <cfreturn id>
+    </cffunction>
+</cfcomponent>
+"#,
+            &[
+                ("save", "Missing required argument `id` of `save`"),
+                ("nme", "`save` has no argument `nme`"),
+                ("load", "Missing required argument `id` of `load`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn literal_type_mismatches() {
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<cfscript>
+    function f(numeric n, boolean b, array xs, string s, date d) {
+        return [n, b, xs, s, d];
+    }
+    f(1, "yes", [], 2, "2024-01-01");
+    f("12", 0, ["a"], true, now());
+    f("abc", "maybe", {}, [], [1]);
+    f(n = {a: 1});
+</cfscript>
+"#,
+            &[
+                (
+                    "\"abc\"",
+                    "Argument `n` of `f` must be `numeric`, not `\"abc\"`",
+                ),
+                (
+                    "\"maybe\"",
+                    "Argument `b` of `f` must be `boolean`, not `\"maybe\"`",
+                ),
+                ("{}", "Argument `xs` of `f` must be `array`, not a struct"),
+                ("[]", "Argument `s` of `f` must be `string`, not an array"),
+                ("[1]", "Argument `d` of `f` must be `date`, not an array"),
+                (
+                    "{a: 1}",
+                    "Argument `n` of `f` must be `numeric`, not a struct",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn declarations_in_related_information() {
+        check_related(
+            r#"
+//- /app/Service.cfc
+component {
+    function f() {
+        var user = new User();
+        user.load();
+        user.load(id = "a", name = "b");
+    }
+}
+//- /app/User.cfc
+component {
+    function load(required numeric id) {
+        return id;
+    }
+}
+"#,
+            &[
+                ("load", "id", "`id` is declared here"),
+                ("\"a\"", "id", "`id` is declared as `numeric` here"),
+                ("name", "load", "`load` is declared here"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::BuiltinTag,
+};
+
+// Diagnostic: empty-catch
+//
+// Reported on a `catch` or `<cfcatch>` without any statement, comments
+// aside, which silently drops the exception.
+
+// Diagnostic: swallowed-exception
+//
+// Reported on a `catch` or `<cfcatch>` which neither rethrows nor logs the
+// exception, nor reads it: the error is hidden and the code carries on as
+// if nothing failed. Calls to `writeLog()`, `<cflog>`, or a logger like
+// `log.error()` or `logError()` count as logging.
+pub(crate) fn catch_blocks(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for node in ctx.root().descendants() {
+        let Some((keyword, variable, statements)) = catch_block(&node) else {
+            continue;
+        };
+        let diagnostic = if statements.is_empty() && !has_text(&node) {
+            Diagnostic::new(
+                "empty-catch",
+                Severity::Warning,
+                "Empty catch block: the exception is silently dropped",
+                keyword.text_range(),
+            )
+        } else if !statements.iter().any(|it| handles(it, &variable)) {
+            Diagnostic::new(
+                "swallowed-exception",
+                Severity::Warning,
+                "The exception is neither rethrown nor logged, hiding the error",
+                keyword.text_range(),
+            )
+        } else {
+            continue;
+        };
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+/// The keyword, the name of the exception and the statements of a script
+/// `catch` or a `<cfcatch>`.
+fn catch_block(node: &SyntaxNode) -> Option<(SyntaxToken, String, Vec<SyntaxNode>)> {
+    match node.kind() {
+        CATCH_CLAUSE => {
+            let clause = ast::CatchClause::cast(node.clone())?;
+            let keyword = node.first_token()?;
+            let variable = clause.name()?.text()?;
+            let statements = clause.body()?.syntax().children().collect();
+            Some((keyword, variable, statements))
+        }
+        TAG => {
+            let tag = ast::Tag::cast(node.clone())?;
+            if tag.name_lower() != "cfcatch" {
+                return None;
+            }
+            let statements = node
+                .children()
+                .filter(|it| !matches!(it.kind(), TAG_OPEN | TAG_CLOSE))
+                .collect();
+            Some((tag.name()?, "cfcatch".to_string(), statements))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if a `<cfcatch>` writes markup, e.g. an error message.
+fn has_text(node: &SyntaxNode) -> bool {
+    node.kind() == TAG
+        && node
+            .children_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+            .any(|it| it.kind() == TEXT && !it.text().trim().is_empty())
+}
+
+/// Returns `true` if `statement` rethrows, logs or reads the exception
+/// `variable`.
+fn handles(statement: &SyntaxNode, variable: &str) -> bool {
+    statement.descendants().any(|node| {
+        if let Some(name_ref) = ast::NameRef::cast(node.clone()) {
+            let name = name_ref.text();
+            return name.eq_ignore_ascii_case(variable)
+                || name.eq_ignore_ascii_case("rethrow")
+                || name.eq_ignore_ascii_case("throw");
+        }
+        if let Some(call) = ast::CallExpr::cast(node.clone()) {
+            return call.callee_name().is_some_and(|it| is_logging(&it))
+                || matches!(call.callee(), Some(ast::Expr::FieldExpr(it)) if it
+                    .receiver()
+                    .is_some_and(|it| is_logger(&it.syntax().text().to_string())));
+        }
+        BuiltinTag::cast(node)
+            .is_some_and(|it| matches!(it.name.as_str(), "cflog" | "cfrethrow" | "cfthrow"))
+    })
+}
+
+/// Returns `true` for `writeLog` and names like `logError`.
+fn is_logging(name: &str) -> bool {
+    if name.eq_ignore_ascii_case("writelog") || name == "log" {
+        return true;
+    }
+    name.strip_prefix("log")
+        .and_then(|it| it.chars().next())
+        .is_some_and(|it| it.is_ascii_uppercase())
+}
+
+/// Returns `true` for receivers like `log`, `logger` or `variables.log`.
+fn is_logger(receiver: &str) -> bool {
+    let receiver = receiver.to_ascii_lowercase();
+    receiver.ends_with("log") || receiver.ends_with("logger")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn empty_catches() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    property name="log" inject="logbox:logger:{this}";
+    function f() {
+        try {
+            save();
+        } catch (any e) {
+            // Nothing to do.
+        }
+        try {
+            save();
+        } catch (any e) {
+            return false;
+        }
+        try {
+            save();
+        } catch (any e) {
+            log.error("Save failed", e);
+            return false;
+        }
+        try {
+            save();
+        } catch (any e) {
+            return e.message;
+        }
+        try {
+            save();
+        } catch (any e) {
+            cleanUp();
+            rethrow;
+        }
+    }
+    function save() {}
+    function cleanUp() {}
+}
+"#,
+            &[
+                (
+                    "catch",
+                    "Empty catch block: the exception is silently dropped",
+                ),
+                (
+                    "catch",
+                    "The exception is neither rethrown nor logged, hiding the error",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn catch_tags() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cftry>
+    <cfset x = 1>
+<cfcatch type="any"><!--- ignore ---></cfcatch>
+</cftry>
+<cftry>
+    <cfset x = 2>
+<cfcatch type="any">
+    <cfset x = 0>
+</cfcatch>
+</cftry>
+<cftry>
+    <cfset x = 3>
+<cfcatch type="any">
+    <cflog text="#cfcatch.message#">
+</cfcatch>
+</cftry>
+<cftry>
+    <cfset x = 4>
+<cfcatch type="any">
+    <p>Something went wrong</p>
+</cfcatch>
+</cftry>
+"##,
+            &[
+                (
+                    "cfcatch",
+                    "Empty catch block: the exception is silently dropped",
+                ),
+                (
+                    "cfcatch",
+                    "The exception is neither rethrown nor logged, hiding the error",
+                ),
+                (
+                    "cfcatch",
+                    "The exception is neither rethrown nor logged, hiding the error",
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+
+use rustc_hash::FxHashMap;
+use syntax::TextRange;
+use virtual_fs::FileId;
+
+use crate::{
+    component::Component,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::{component_in, list_items, resolve_component, static_text},
+    Analysis, FileRange,
+};
+
+// Diagnostic: circular-inheritance
+//
+// Reported on the `extends` of a component or interface which extends
+// itself, directly or through others. Every component of the cycle gets it,
+// with the others' `extends` in the related information.
+pub(crate) fn circular_inheritance(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let analysis = ctx.analysis();
+    let component = ctx.root().descendants().find_map(Component::cast)?;
+    for (range, name) in extends(&component) {
+        let Some(target) = resolve_component(analysis, ctx.file_id(), &name) else {
+            continue;
+        };
+        let Some(cycle) = path(analysis, target, ctx.file_id()) else {
+            continue;
+        };
+        let message = match cycle.as_slice() {
+            [_] => format!("`{}` extends itself", stem(analysis, ctx.file_id())),
+            _ => {
+                let names: Vec<String> = std::iter::once(ctx.file_id())
+                    .chain(cycle.iter().copied())
+                    .map(|it| format!("`{}`", stem(analysis, it)))
+                    .collect();
+                format!("Circular inheritance: {}", names.join(" → "))
+            }
+        };
+        let mut diagnostic =
+            Diagnostic::new("circular-inheritance", Severity::Error, message, range);
+        for pair in cycle.windows(2) {
+            let Some(range) = extends_range(analysis, pair[0]) else {
+                continue;
+            };
+            let message = format!(
+                "`{}` extends `{}` here",
+                stem(analysis, pair[0]),
+                stem(analysis, pair[1])
+            );
+            diagnostic = diagnostic.with_related(
+                FileRange {
+                    file_id: pair[0],
+                    range,
+                },
+                message,
+            );
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+/// The names `component` extends, one for components and any number for
+/// interfaces.
+fn extends(component: &Component) -> Vec<(TextRange, String)> {
+    component
+        .find_attribute("extends")
+        .and_then(|it| static_text(it.value_element()?))
+        .map(|it| list_items(&it))
+        .unwrap_or_default()
+}
+
+fn extends_range(analysis: &Analysis, file_id: FileId) -> Option<TextRange> {
+    let attribute = component_in(analysis, file_id)?.find_attribute("extends")?;
+    Some(attribute.value_element()?.text_range())
+}
+
+/// The shortest chain of `extends` from `from` to `to`, both included.
+fn path(analysis: &Analysis, from: FileId, to: FileId) -> Option<Vec<FileId>> {
+    let mut parents: FxHashMap<FileId, FileId> = FxHashMap::default();
+    let mut queue = VecDeque::from([from]);
+    while let Some(file_id) = queue.pop_front() {
+        if file_id == to {
+            let mut res = vec![to];
+            let mut current = to;
+            while let Some(&parent) = parents.get(&current) {
+                res.push(parent);
+                current = parent;
+            }
+            res.reverse();
+            return Some(res);
+        }
+        let Some(component) = component_in(analysis, file_id) else {
+            continue;
+        };
+        for (_, name) in extends(&component) {
+            let Some(next) = resolve_component(analysis, file_id, &name) else {
+                continue;
+            };
+            if next != from && !parents.contains_key(&next) {
+                parents.insert(next, file_id);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// The name of the component in `file_id`, as its file is called.
+fn stem(analysis: &Analysis, file_id: FileId) -> String {
+    let path = analysis
+        .paths
+        .get(&file_id)
+        .map(|it| it.to_string())
+        .unwrap_or_default();
+    let name = path.rsplit(['/', '\\']).next().unwrap_or_default();
+    name.strip_suffix(".cfc").unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::FileId;
+
+    use crate::{
+        diagnostics::{
+            diagnostics,
+            tests::{analysis_from_fixture, check_diagnostics},
+            DiagnosticsConfig,
+        },
+        FileRange,
+    };
+
+    #[test]
+    fn circular_components() {
+        check_diagnostics(
+            r#"
+//- /app/A.cfc
+component extends="B" {}
+//- /app/B.cfc
+component extends="C" {}
+//- /app/C.cfc
+component extends="A" {}
+"#,
+            &[("B", "Circular inheritance: `A` → `B` → `C` → `A`")],
+        );
+        check_diagnostics(
+            r#"
+//- /app/Self.cfc
+component extends="Self" {}
+"#,
+            &[("Self", "`Self` extends itself")],
+        );
+    }
+
+    #[test]
+    fn circular_interfaces() {
+        check_diagnostics(
+            r#"
+//- /app/IA.cfc
+interface extends="IBase, IB" {}
+//- /app/IBase.cfc
+interface {}
+//- /app/IB.cfc
+interface extends="IA" {}
+"#,
+            &[("IB", "Circular inheritance: `IA` → `IB` → `IA`")],
+        );
+    }
+
+    #[test]
+    fn outside_the_cycle() {
+        check_diagnostics(
+            r#"
+//- /app/Child.cfc
+component extends="A" {}
+//- /app/A.cfc
+component extends="B" {}
+//- /app/B.cfc
+component extends="A" {}
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn related_extends() {
+        let analysis = analysis_from_fixture(
+            r#"
+//- /app/A.cfc
+component extends="B" {}
+//- /app/B.cfc
+component extends="A" {}
+"#,
+        );
+        let diagnostics = diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0));
+        let related: Vec<(FileRange, &str)> = diagnostics[0]
+            .related
+            .iter()
+            .map(|it| (it.range, it.message.as_str()))
+            .collect();
+        let range = FileRange {
+            file_id: FileId(1),
+            range: syntax::TextRange::new(18, 21),
+        };
+        assert_eq!(related, [(range, "`B` extends `A` here")]);
+    }
+}
@@ -0,0 +1,191 @@
+use rustc_hash::FxHashMap;
+use syntax::ast::AstNode;
+use virtual_fs::{AnchoredPathBuf, FileId};
+
+use crate::{
+    component::Component,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::static_text,
+    Assist, AssistId, AssistKind, FileSystemEdit, SourceChange, TextEdit, TextEditBuilder,
+};
+
+// Diagnostic: component-name-mismatch
+//
+// Reported on the `name` of a component which doesn't match where its file
+// is: `component name="models.User"` in `model/User.cfc`. Other components
+// can't find it by that name. The fixes either move the file, or take the
+// name from the file and update the references using the declared one.
+pub(crate) fn component_name_mismatch(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let component = ctx.root().descendants().find_map(Component::cast)?;
+    let attribute = component.find_attribute("name")?;
+    let declared = attribute.value()?;
+    let path = ctx.analysis().paths.get(&ctx.file_id())?.to_string();
+    let segments: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|it| !it.is_empty())
+        .collect();
+    let (file_name, dirs) = segments.split_last()?;
+    let stem = file_name.strip_suffix(".cfc")?;
+
+    let declared_parts: Vec<&str> = declared.split('.').collect();
+    let (name, package) = declared_parts.split_last()?;
+    let actual_package = dirs.get(dirs.len().checked_sub(package.len())?..)?;
+    if *name == stem && package == actual_package {
+        return None;
+    }
+    let actual = actual_package
+        .iter()
+        .chain(std::iter::once(&stem))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(".");
+    let location = format!("{}.cfc", actual.replace('.', "/"));
+    let message = format!("Component is declared as `{declared}` but its file is `{location}`");
+    let range = attribute.syntax().text_range();
+
+    let mut diagnostic =
+        Diagnostic::new("component-name-mismatch", Severity::Warning, message, range);
+
+    let (label, dst) = if package == actual_package {
+        let dst = format!("{name}.cfc");
+        (format!("Rename file to `{dst}`"), dst)
+    } else {
+        let dst = format!("{}.cfc", declared.replace('.', "/"));
+        let up = "../".repeat(package.len());
+        (format!("Move file to `{dst}`"), format!("{up}{dst}"))
+    };
+    let mut source_change = SourceChange::default();
+    source_change.push_file_system_edit(FileSystemEdit::MoveFile {
+        src: ctx.file_id(),
+        dst: AnchoredPathBuf {
+            anchor: ctx.file_id(),
+            path: dst,
+        },
+    });
+    diagnostic.fixes.push(Assist {
+        id: AssistId("move_file_to_component_name", AssistKind::QuickFix),
+        label,
+        target: range,
+        source_change,
+    });
+
+    let value = static_text(attribute.value_element()?)?;
+    let mut builders: FxHashMap<FileId, TextEditBuilder> = FxHashMap::default();
+    builders
+        .entry(ctx.file_id())
+        .or_insert_with(TextEdit::builder)
+        .replace(value.text_range(), actual.clone());
+    let analysis = ctx.analysis();
+    for reference in analysis.component_references().iter() {
+        if reference.name == declared.as_str()
+            && analysis.same_root(reference.file_id, ctx.file_id())
+        {
+            builders
+                .entry(reference.file_id)
+                .or_insert_with(TextEdit::builder)
+                .replace(reference.range, actual.clone());
+        }
+    }
+    let mut source_change = SourceChange::default();
+    for (file_id, builder) in builders {
+        source_change.insert_source_edit(file_id, builder.finish());
+    }
+    diagnostic.fixes.push(Assist {
+        id: AssistId("use_file_component_name", AssistKind::QuickFix),
+        label: format!("Change name to `{actual}` and update references"),
+        target: range,
+        source_change,
+    });
+    acc.push(diagnostic);
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::{AnchoredPathBuf, FileId};
+
+    use crate::{
+        diagnostics::{
+            diagnostics,
+            tests::{analysis_from_fixture, check_diagnostics},
+            DiagnosticsConfig,
+        },
+        FileSystemEdit,
+    };
+
+    const FIXTURE: &str = r#"
+//- /app/model/User.cfc
+component name="models.User" {}
+//- /app/handlers/Main.cfc
+component extends="models.User" {
+    function f() {
+        return new Models.User();
+    }
+}
+"#;
+
+    #[test]
+    fn name_mismatch() {
+        check_diagnostics(
+            FIXTURE,
+            &[(
+                r#"name="models.User""#,
+                "Component is declared as `models.User` but its file is `model/User.cfc`",
+            )],
+        );
+        check_diagnostics(
+            "
+//- /app/models/User.cfc
+component name=\"models.User\" {}
+",
+            &[],
+        );
+    }
+
+    #[test]
+    fn move_file() {
+        let analysis = analysis_from_fixture(FIXTURE);
+        let fixes = &diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0))[0].fixes;
+        assert_eq!(fixes[0].label, "Move file to `models/User.cfc`");
+        assert_eq!(
+            fixes[0].source_change.file_system_edits,
+            [FileSystemEdit::MoveFile {
+                src: FileId(0),
+                dst: AnchoredPathBuf {
+                    anchor: FileId(0),
+                    path: "../models/User.cfc".to_string(),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn update_references() {
+        let analysis = analysis_from_fixture(FIXTURE);
+        let fix = &diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0))[0].fixes[1];
+        assert_eq!(
+            fix.label,
+            "Change name to `model.User` and update references"
+        );
+        let apply = |file_id| {
+            let mut text = analysis.file_text(file_id).unwrap().to_string();
+            fix.source_change
+                .get_source_edit(file_id)
+                .unwrap()
+                .apply(&mut text);
+            text
+        };
+        assert_eq!(apply(FileId(0)), r#"component name="model.User" {}"#);
+        assert_eq!(
+            apply(FileId(1)),
+            r#"component extends="model.User" {
+    function f() {
+        return new model.User();
+    }
+}"#
+        );
+    }
+}
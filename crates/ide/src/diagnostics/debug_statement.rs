@@ -0,0 +1,226 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+use crate::{
+    assists::utils::removal_range,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::BuiltinTag,
+    TextEdit,
+};
+
+/// Functions writing debug output.
+const DEBUG_FUNCTIONS: &[&str] = &["cfabort", "cfdump", "dump", "systemoutput", "writedump"];
+
+// Diagnostic: debug-statement
+//
+// Reported on `writeDump()`, `<cfdump>`, `<cfabort>`, `abort`,
+// `systemOutput()` and logs to the console, like `writeLog(type="console")`,
+// which are usually left over from debugging and shouldn't reach
+// production. Those guarded by an `if` or `<cfif>` whose condition mentions
+// debugging, like `if (isDebugMode())` or `<cfif application.debug>`, are
+// fine. The fix removes the statement.
+pub(crate) fn debug_statement(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for node in ctx.root().descendants() {
+        let found = if let Some(call) = ast::CallExpr::cast(node.clone()) {
+            debug_call(&call)
+        } else if let Some(tag) = BuiltinTag::cast(node.clone()) {
+            debug_tag(&tag, &node)
+        } else {
+            None
+        };
+        let Some((label, range, statement)) = found else {
+            continue;
+        };
+        if is_guarded(&node) {
+            continue;
+        }
+        let mut diagnostic = Diagnostic::new(
+            "debug-statement",
+            Severity::Warning,
+            format!("{label} looks left over from debugging"),
+            range,
+        );
+        if let Some(statement) = statement {
+            diagnostic = diagnostic.with_fix(ctx.fix(
+                "remove_debug_statement",
+                format!("Remove {label}"),
+                range,
+                TextEdit::delete(removal_range(&statement)),
+            ));
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+/// The label and range of a debug call, with the statement it makes up.
+fn debug_call(call: &ast::CallExpr) -> Option<(String, TextRange, Option<SyntaxNode>)> {
+    let name = call.callee_name()?;
+    let key = name.to_ascii_lowercase();
+    let is_debug = match key.as_str() {
+        "writelog" => call.arg_list()?.args().any(|arg| {
+            arg.name().is_some_and(|it| it.eq_ignore_ascii_case("type"))
+                && is_console(match arg.expr() {
+                    Some(ast::Expr::StringLit(it)) => it.value(),
+                    _ => None,
+                })
+        }),
+        _ => DEBUG_FUNCTIONS.contains(&key.as_str()),
+    };
+    if !is_debug {
+        return None;
+    }
+    let parent = call.syntax().parent()?;
+    let statement = match parent.kind() {
+        EXPR_STMT => Some(parent),
+        // `<cfset writeDump(x)>`.
+        TAG if ast::Tag::cast(parent.clone())?.name_lower() == "cfset" => Some(parent),
+        _ => None,
+    };
+    let range = call.callee()?.syntax().text_range();
+    Some((format!("`{name}()`"), range, statement))
+}
+
+/// The label and range of a debug tag or script statement, which is the
+/// statement to remove.
+fn debug_tag(
+    tag: &BuiltinTag,
+    node: &SyntaxNode,
+) -> Option<(String, TextRange, Option<SyntaxNode>)> {
+    let is_debug = match tag.name.as_str() {
+        "cfabort" | "cfdump" => true,
+        "cflog" => is_console(tag.attribute("type").and_then(|it| it.value())),
+        _ => false,
+    };
+    if !is_debug {
+        return None;
+    }
+    let label = if node.kind() == TAG {
+        format!("`<{}>`", tag.name)
+    } else {
+        format!("`{}`", tag.name_token.text())
+    };
+    Some((label, tag.name_token.text_range(), Some(node.clone())))
+}
+
+fn is_console(value: Option<String>) -> bool {
+    value.is_some_and(|it| it.eq_ignore_ascii_case("console"))
+}
+
+/// Returns `true` if `node` only runs when debugging: in the branch of an
+/// `if` or `<cfif>` whose condition mentions it.
+fn is_guarded(node: &SyntaxNode) -> bool {
+    let mentions_debug =
+        |it: &SyntaxNode| it.text().to_string().to_ascii_lowercase().contains("debug");
+    let mut child = node.clone();
+    for parent in node.ancestors().skip(1) {
+        match parent.kind() {
+            IF_STMT => {
+                let mut children = parent.children();
+                let condition = children.next();
+                let then_branch = children.next();
+                if then_branch.as_ref() == Some(&child)
+                    && condition.is_some_and(|it| mentions_debug(&it))
+                {
+                    return true;
+                }
+            }
+            TAG => {
+                let Some(tag) = ast::Tag::cast(parent.clone()) else {
+                    continue;
+                };
+                // The tags before any `<cfelseif>` or `<cfelse>`.
+                let in_then_branch = tag.name_lower() == "cfif"
+                    && parent
+                        .children()
+                        .take_while(|it| it != &child)
+                        .filter_map(ast::Tag::cast)
+                        .all(|it| !matches!(it.name_lower().as_str(), "cfelse" | "cfelseif"));
+                let condition = parent
+                    .children()
+                    .find(|it| it.kind() == TAG_OPEN)
+                    .and_then(|it| it.children().find(|it| ast::Expr::can_cast(it.kind())));
+                if in_then_branch && condition.is_some_and(|it| mentions_debug(&it)) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        child = parent;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn debug_statements() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfset user = {name: "a"}>
+<cfdump var="#user#">
+<cfif application.debug>
+    <cfdump var="#user#">
+<cfelse>
+    <cfabort>
+</cfif>
+<cflog text="a" type="console">
+<cflog text="a" type="information">
+<cfscript>
+    writeDump(user);
+    writeLog(text = "a", type = "console");
+    if (isDebugMode()) {
+        systemOutput(user);
+    }
+    abort;
+</cfscript>
+"##,
+            &[
+                ("cfdump", "`<cfdump>` looks left over from debugging"),
+                ("cfabort", "`<cfabort>` looks left over from debugging"),
+                ("cflog", "`<cflog>` looks left over from debugging"),
+                ("writeDump", "`writeDump()` looks left over from debugging"),
+                ("writeLog", "`writeLog()` looks left over from debugging"),
+                ("abort", "`abort` looks left over from debugging"),
+            ],
+        );
+    }
+
+    #[test]
+    fn remove_debug_statement() {
+        check_fix(
+            "Remove `writeDump()`",
+            r#"
+//- /app/Service.cfc
+component {
+    function f(x) {
+        writeDump(x);
+        return x;
+    }
+}
+"#,
+            r#"component {
+    function f(x) {
+        return x;
+    }
+}"#,
+        );
+        check_fix(
+            "Remove `<cfdump>`",
+            r##"
+//- /app/index.cfm
+<cfset x = 1>
+<cfdump var="#x#">
+<cfoutput>#x#</cfoutput>
+"##,
+            r##"<cfset x = 1>
+<cfoutput>#x#</cfoutput>"##,
+        );
+    }
+}
@@ -0,0 +1,606 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    var_scope::SCOPES,
+    Engine,
+    EngineKind::{self, Adobe, Lucee},
+    TextEdit,
+};
+
+/// Functions which have a better replacement, with the advice given.
+const DEPRECATED_FUNCTIONS: &[(&str, &str)] = &[
+    ("evaluate", "access the variable with `[]` instead"),
+    ("iif", "use a ternary `a ? b : c` instead"),
+    ("isdefined", "use `structKeyExists()` or `isNull()` instead"),
+    (
+        "parameterexists",
+        "use `structKeyExists()` or `isNull()` instead",
+    ),
+];
+
+/// A tag or function which an engine deprecated, and maybe removed later.
+struct EngineChange {
+    engine: EngineKind,
+    /// In lowercase, with the `cf` prefix for tags.
+    name: &'static str,
+    is_tag: bool,
+    deprecated_in: u32,
+    removed_in: Option<u32>,
+    advice: &'static str,
+}
+
+const fn change(
+    engine: EngineKind,
+    name: &'static str,
+    is_tag: bool,
+    deprecated_in: u32,
+    removed_in: Option<u32>,
+    advice: &'static str,
+) -> EngineChange {
+    EngineChange {
+        engine,
+        name,
+        is_tag,
+        deprecated_in,
+        removed_in,
+        advice,
+    }
+}
+
+const ENGINE_CHANGES: &[EngineChange] = &[
+    change(
+        Adobe,
+        "cfgraph",
+        true,
+        6,
+        Some(7),
+        "use `<cfchart>` instead",
+    ),
+    change(
+        Adobe,
+        "cfgraphdata",
+        true,
+        6,
+        Some(7),
+        "use `<cfchartdata>` instead",
+    ),
+    change(
+        Adobe,
+        "cfinsert",
+        true,
+        2018,
+        None,
+        "use `<cfquery>` with `<cfqueryparam>` instead",
+    ),
+    change(
+        Adobe,
+        "cfservlet",
+        true,
+        6,
+        Some(7),
+        "use `getPageContext().include()` instead",
+    ),
+    change(
+        Adobe,
+        "cfupdate",
+        true,
+        2018,
+        None,
+        "use `<cfquery>` with `<cfqueryparam>` instead",
+    ),
+    change(
+        Adobe,
+        "getk2serverdoccount",
+        false,
+        9,
+        Some(10),
+        "Verity was replaced by Solr",
+    ),
+    change(
+        Adobe,
+        "getk2serverdoccountlimit",
+        false,
+        9,
+        Some(10),
+        "Verity was replaced by Solr",
+    ),
+    change(
+        Adobe,
+        "gettemplatepath",
+        false,
+        6,
+        None,
+        "use `getBaseTemplatePath()` instead",
+    ),
+    change(
+        Lucee,
+        "cfinsert",
+        true,
+        5,
+        None,
+        "use `<cfquery>` with `<cfqueryparam>` instead",
+    ),
+    change(
+        Lucee,
+        "cfupdate",
+        true,
+        5,
+        None,
+        "use `<cfquery>` with `<cfqueryparam>` instead",
+    ),
+    change(
+        Lucee,
+        "cfvideo",
+        true,
+        5,
+        Some(5),
+        "install the video extension",
+    ),
+    change(
+        Lucee,
+        "cfvideoplayer",
+        true,
+        5,
+        Some(5),
+        "install the video extension",
+    ),
+    change(
+        Lucee,
+        "gettemplatepath",
+        false,
+        5,
+        None,
+        "use `getBaseTemplatePath()` instead",
+    ),
+];
+
+// Diagnostic: deprecated-function
+//
+// Reported on calls to functions with a modern equivalent. `evaluate()`,
+// `iif()` and `isDefined()` evaluate strings as code, which is slow and
+// risky with user input. Where the call can be rewritten, a fix does so;
+// fixes which may change the behaviour are labelled "needs review".
+pub(crate) fn deprecated_function(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    for call in ctx.root().descendants().filter_map(ast::CallExpr::cast) {
+        let Some(ast::Expr::NameRef(callee)) = call.callee() else {
+            continue;
+        };
+        let name = callee.text();
+        let lower = name.to_ascii_lowercase();
+        let Some((_, advice)) = DEPRECATED_FUNCTIONS.iter().find(|(it, _)| *it == lower) else {
+            continue;
+        };
+        let range = callee.syntax().text_range();
+        let mut diagnostic = Diagnostic::new(
+            "deprecated-function",
+            Severity::Warning,
+            format!("`{name}` is deprecated: {advice}"),
+            range,
+        )
+        .with_deprecated(true);
+        let rewrite = match lower.as_str() {
+            "evaluate" => rewrite_evaluate(&call),
+            "iif" => rewrite_iif(&call),
+            "isdefined" | "parameterexists" => rewrite_is_defined(&call),
+            _ => None,
+        };
+        if let Some(Rewrite { text, is_safe }) = rewrite {
+            let mut label = format!("Replace `{name}` with `{}`", summary(&text));
+            let text = if needs_parens(&call) && text.contains(' ') {
+                format!("({text})")
+            } else {
+                text
+            };
+            if !is_safe {
+                label.push_str(" (needs review)");
+            }
+            let call_range = call.syntax().text_range();
+            diagnostic = diagnostic.with_fix(ctx.fix(
+                "replace_deprecated_function",
+                label,
+                call_range,
+                TextEdit::replace(call_range, text),
+            ));
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+// Diagnostic: deprecated-tag
+// Diagnostic: removed-function
+// Diagnostic: removed-tag
+//
+// Reported on tags and functions which the configured engine deprecated or
+// removed, with the version which did. Removed ones fail at runtime, so
+// they are errors.
+pub(crate) fn deprecated_by_engine(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let engine = ctx.config().engine?;
+    for node in ctx.root().descendants() {
+        let (name, is_tag, range) = if let Some(tag) = ast::Tag::cast(node.clone()) {
+            let Some(name) = tag.name() else {
+                continue;
+            };
+            (format!("<{}>", name.text()), true, name.text_range())
+        } else if let Some(stmt) = ast::ScriptTagStmt::cast(node.clone()) {
+            let Some(keyword) = stmt.syntax().first_token() else {
+                continue;
+            };
+            (
+                format!("<cf{}>", keyword.text()),
+                true,
+                keyword.text_range(),
+            )
+        } else if let Some(call) = ast::CallExpr::cast(node) {
+            let Some(ast::Expr::NameRef(callee)) = call.callee() else {
+                continue;
+            };
+            (callee.text(), false, callee.syntax().text_range())
+        } else {
+            continue;
+        };
+        let key = name
+            .trim_matches(|it| it == '<' || it == '>')
+            .to_ascii_lowercase();
+        let Some(change) = ENGINE_CHANGES.iter().find(|it| {
+            it.engine == engine.kind
+                && it.is_tag == is_tag
+                && it.name == key
+                && engine.version >= it.deprecated_in
+        }) else {
+            continue;
+        };
+        let removed_in = change.removed_in.filter(|it| engine.version >= *it);
+        let (code, severity, message) = match removed_in {
+            Some(version) => (
+                if is_tag {
+                    "removed-tag"
+                } else {
+                    "removed-function"
+                },
+                Severity::Error,
+                format!(
+                    "`{name}` was removed in {}: {}",
+                    Engine { version, ..engine },
+                    change.advice
+                ),
+            ),
+            None => (
+                if is_tag {
+                    "deprecated-tag"
+                } else {
+                    "deprecated-function"
+                },
+                Severity::Warning,
+                format!(
+                    "`{name}` is deprecated since {}: {}",
+                    Engine {
+                        version: change.deprecated_in,
+                        ..engine
+                    },
+                    change.advice
+                ),
+            ),
+        };
+        acc.push(Diagnostic::new(code, severity, message, range).with_deprecated(true));
+    }
+    Some(())
+}
+
+struct Rewrite {
+    text: String,
+    /// `false` when the rewrite may behave differently, e.g. by looking in
+    /// fewer scopes.
+    is_safe: bool,
+}
+
+/// The args of a call without named arguments.
+fn positional_args(call: &ast::CallExpr) -> Option<Vec<ast::Expr>> {
+    let args: Vec<ast::Arg> = call.arg_list()?.args().collect();
+    if args.iter().any(|it| it.name().is_some()) {
+        return None;
+    }
+    args.iter().map(|it| it.expr()).collect()
+}
+
+/// `isDefined("form.a.b")` → `structKeyExists(form, "a") && structKeyExists(form.a, "b")`.
+fn rewrite_is_defined(call: &ast::CallExpr) -> Option<Rewrite> {
+    let [ast::Expr::StringLit(string)] = <[_; 1]>::try_from(positional_args(call)?).ok()? else {
+        return None;
+    };
+    let path = string.value()?;
+    let parts: Vec<&str> = path.split('.').collect();
+    if !parts.iter().all(|it| is_identifier(it)) {
+        return None;
+    }
+    let is_scoped = parts.len() > 1 && SCOPES.contains(&parts[0].to_ascii_lowercase().as_str());
+    if !is_scoped {
+        // Looked up in every scope, which `isNull()` does too, mostly.
+        return Some(Rewrite {
+            text: format!("!isNull({path})"),
+            is_safe: false,
+        });
+    }
+    let checks: Vec<String> = (1..parts.len())
+        .map(|idx| {
+            format!(
+                "structKeyExists({}, \"{}\")",
+                parts[..idx].join("."),
+                parts[idx]
+            )
+        })
+        .collect();
+    Some(Rewrite {
+        text: checks.join(" && "),
+        is_safe: true,
+    })
+}
+
+/// `evaluate("form.a_#i#")` → `form["a_#i#"]`.
+fn rewrite_evaluate(call: &ast::CallExpr) -> Option<Rewrite> {
+    let [ast::Expr::StringLit(string)] = <[_; 1]>::try_from(positional_args(call)?).ok()? else {
+        return None;
+    };
+    if let Some(path) = string.value() {
+        let is_path = path.split('.').all(is_identifier);
+        return is_path.then_some(Rewrite {
+            text: path,
+            is_safe: true,
+        });
+    }
+    // The static part before the last dot is the container, the rest the
+    // key: `form.a_#i#`.
+    let first = string
+        .syntax()
+        .children_with_tokens()
+        .find(|it| it.kind() != QUOTE)?;
+    let SyntaxElement::Token(first) = first else {
+        return None;
+    };
+    let (base, key_start) = first
+        .text()
+        .rsplit_once('.')
+        .unwrap_or(("variables", first.text()));
+    if !base.split('.').all(is_identifier) {
+        return None;
+    }
+    let quote = string.quote_char();
+    let rest: String = string
+        .syntax()
+        .children_with_tokens()
+        .skip_while(|it| it.kind() == QUOTE)
+        .skip(1)
+        .filter(|it| it.kind() != QUOTE)
+        .map(|it| match it {
+            SyntaxElement::Token(it) => it.text().to_string(),
+            SyntaxElement::Node(it) => it.text(),
+        })
+        .collect();
+    Some(Rewrite {
+        text: format!("{base}[{quote}{key_start}{rest}{quote}]"),
+        // Without a dot, the variable may be in any scope, not just in
+        // `variables`.
+        is_safe: first.text().contains('.'),
+    })
+}
+
+/// `iif(c, de("a"), de("b"))` → `c ? "a" : "b"`.
+fn rewrite_iif(call: &ast::CallExpr) -> Option<Rewrite> {
+    let [condition, then_expr, else_expr] = positional_args(call)?.try_into().ok()?;
+    let mut is_safe = true;
+    let mut branch = |expr: ast::Expr| -> Option<String> {
+        if let ast::Expr::CallExpr(de) = &expr {
+            if de.callee_name()?.eq_ignore_ascii_case("de") {
+                let [arg] = positional_args(de)?.try_into().ok()?;
+                return Some(arg.syntax().text());
+            }
+        }
+        // Other strings are evaluated as expressions.
+        let ast::Expr::StringLit(string) = expr else {
+            return None;
+        };
+        let value = string.value()?;
+        if !value.split('.').all(is_identifier) && value.parse::<f64>().is_err() {
+            is_safe = false;
+        }
+        Some(value)
+    };
+    let then_text = branch(then_expr)?;
+    let else_text = branch(else_expr)?;
+    let mut condition_text = condition.syntax().text();
+    if matches!(condition.syntax().kind(), TERNARY_EXPR | ASSIGN_EXPR) {
+        condition_text = format!("({condition_text})");
+    }
+    Some(Rewrite {
+        text: format!("{condition_text} ? {then_text} : {else_text}"),
+        is_safe,
+    })
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|it| it.is_ascii_alphabetic() || it == '_' || it == '$')
+        && chars.all(|it| it.is_ascii_alphanumeric() || it == '_' || it == '$')
+}
+
+/// Returns `true` if the parent of `call` binds tighter than `&&` or `?:`.
+fn needs_parens(call: &ast::CallExpr) -> bool {
+    call.syntax().parent().is_some_and(|it| {
+        matches!(
+            it.kind(),
+            BIN_EXPR
+                | PREFIX_EXPR
+                | POSTFIX_EXPR
+                | FIELD_EXPR
+                | INDEX_EXPR
+                | CALL_EXPR
+                | TERNARY_EXPR
+        )
+    })
+}
+
+/// A short form of `text` for a label.
+fn summary(text: &str) -> String {
+    match text.find('(') {
+        Some(idx) if !text.starts_with('(') && !text.starts_with('!') => {
+            format!("{}()", &text[..idx])
+        }
+        _ if text.chars().count() > 30 => format!("{}…", text.chars().take(30).collect::<String>()),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diagnostics::{
+            tests::{check_diagnostics, check_diagnostics_with_config, check_fix},
+            DiagnosticsConfig,
+        },
+        Engine, EngineKind,
+    };
+
+    #[test]
+    fn deprecated_functions() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfset x = true>
+<cfset name = "y">
+<cfif isDefined("form.x") AND x>
+    <cfset y = evaluate("form.#name#")>
+</cfif>
+"##,
+            &[
+                (
+                    "isDefined",
+                    "`isDefined` is deprecated: use `structKeyExists()` or `isNull()` instead",
+                ),
+                (
+                    "evaluate",
+                    "`evaluate` is deprecated: access the variable with `[]` instead",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn replace_is_defined() {
+        check_fix(
+            "Replace `isDefined` with `structKeyExists()`",
+            r##"
+//- /app/index.cfm
+<cfif isDefined("form.user.name") AND x></cfif>
+"##,
+            r##"<cfif (structKeyExists(form, "user") && structKeyExists(form.user, "name")) AND x></cfif>"##,
+        );
+        check_fix(
+            "Replace `isDefined` with `!isNull(user)` (needs review)",
+            r##"
+//- /app/index.cfm
+<cfscript>if (isDefined("user")) {}</cfscript>
+"##,
+            r##"<cfscript>if (!isNull(user)) {}</cfscript>"##,
+        );
+    }
+
+    #[test]
+    fn replace_evaluate() {
+        check_fix(
+            r##"Replace `evaluate` with `form["field_#i#"]`"##,
+            r##"
+//- /app/index.cfm
+<cfset x = evaluate("form.field_#i#")>
+"##,
+            r##"<cfset x = form["field_#i#"]>"##,
+        );
+        check_fix(
+            r##"Replace `evaluate` with `variables["a#b#"]` (needs review)"##,
+            r##"
+//- /app/index.cfm
+<cfset x = evaluate("a#b#")>
+"##,
+            r##"<cfset x = variables["a#b#"]>"##,
+        );
+    }
+
+    #[test]
+    fn replace_iif() {
+        check_fix(
+            r##"Replace `iif` with `a gt 1 ? "big" : "small"`"##,
+            r##"
+//- /app/index.cfm
+<cfoutput>#iif(a gt 1, de("big"), de("small"))#</cfoutput>
+"##,
+            r##"<cfoutput>#a gt 1 ? "big" : "small"#</cfoutput>"##,
+        );
+        check_fix(
+            r##"Replace `iif` with `ok ? x + 1 : 0` (needs review)"##,
+            r##"
+//- /app/index.cfm
+<cfset y = "n: " & iif(ok, "x + 1", "0")>
+"##,
+            r##"<cfset y = "n: " & (ok ? x + 1 : 0)>"##,
+        );
+    }
+
+    #[test]
+    fn deprecated_by_engine() {
+        let fixture = r##"
+//- /app/index.cfm
+<cfinsert datasource="app" tableName="users">
+<cfgraph type="bar"></cfgraph>
+<cfoutput>#GetTemplatePath()#</cfoutput>
+"##;
+        let config = |kind, version| DiagnosticsConfig {
+            engine: Some(Engine { kind, version }),
+            ..DiagnosticsConfig::default()
+        };
+        check_diagnostics_with_config(
+            &config(EngineKind::Adobe, 2021),
+            fixture,
+            &[
+                (
+                    "cfinsert",
+                    "`<cfinsert>` is deprecated since Adobe ColdFusion 2018: use `<cfquery>` with `<cfqueryparam>` instead",
+                ),
+                (
+                    "cfgraph",
+                    "`<cfgraph>` was removed in Adobe ColdFusion 7: use `<cfchart>` instead",
+                ),
+                (
+                    "GetTemplatePath",
+                    "`GetTemplatePath` is deprecated since Adobe ColdFusion 6: use `getBaseTemplatePath()` instead",
+                ),
+            ],
+        );
+        check_diagnostics_with_config(
+            &config(EngineKind::Adobe, 11),
+            fixture,
+            &[
+                (
+                    "cfgraph",
+                    "`<cfgraph>` was removed in Adobe ColdFusion 7: use `<cfchart>` instead",
+                ),
+                (
+                    "GetTemplatePath",
+                    "`GetTemplatePath` is deprecated since Adobe ColdFusion 6: use `getBaseTemplatePath()` instead",
+                ),
+            ],
+        );
+        check_diagnostics(fixture, &[]);
+    }
+}
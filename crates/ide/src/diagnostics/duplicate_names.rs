@@ -0,0 +1,254 @@
+use rustc_hash::FxHashMap;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    TextRange,
+};
+
+use crate::{
+    change_signature::old_params,
+    component::{Component, Property},
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    var_scope::functions,
+    TextEdit,
+};
+
+// Diagnostic: duplicate-function
+//
+// Reported on a function declared again in the same component, which the
+// engine silently lets replace the first one. With `accessors="true"`, a
+// function named like the getter or setter of a property replaces the
+// generated accessor, which is only reported as information: the fix states
+// it with `getter="false"` or `setter="false"` on the property.
+pub(crate) fn duplicate_function(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let component = ctx.root().descendants().find_map(Component::cast)?;
+    let mut first: FxHashMap<String, TextRange> = FxHashMap::default();
+    let mut declared = Vec::new();
+    for func in component.functions() {
+        let (Some(name), Some(range)) = (func.name(), func.name_range()) else {
+            continue;
+        };
+        let key = name.to_ascii_lowercase();
+        match first.get(&key) {
+            Some(first) => acc.push(
+                Diagnostic::new(
+                    "duplicate-function",
+                    Severity::Warning,
+                    format!("`{name}` is already declared in this component, and this declaration replaces it"),
+                    range,
+                )
+                .with_related(ctx.frange(*first), format!("`{name}` is first declared here")),
+            ),
+            None => {
+                first.insert(key, range);
+                declared.push((name, range));
+            }
+        }
+    }
+
+    if !component.has_implicit_accessors() {
+        return Some(());
+    }
+    for property in component.properties() {
+        let Some(property_name) = property.name() else {
+            continue;
+        };
+        for accessor in ["getter", "setter"] {
+            if !property.wants_accessor(accessor) {
+                continue;
+            }
+            let accessor_name = format!("{}{property_name}", &accessor[..3]);
+            let Some((name, range)) = declared
+                .iter()
+                .find(|(it, _)| it.eq_ignore_ascii_case(&accessor_name))
+            else {
+                continue;
+            };
+            let mut diagnostic = Diagnostic::new(
+                "duplicate-function",
+                Severity::Information,
+                format!(
+                    "`{name}` replaces the {accessor} generated for property `{property_name}`"
+                ),
+                *range,
+            )
+            .with_related(
+                ctx.frange(property.syntax().text_range()),
+                format!("property `{property_name}` is declared here"),
+            );
+            if let Some(edit) = disable_accessor(&property, accessor) {
+                diagnostic = diagnostic.with_fix(ctx.fix(
+                    "disable_accessor",
+                    format!("Add `{accessor}=\"false\"` to property `{property_name}`"),
+                    *range,
+                    edit,
+                ));
+            }
+            acc.push(diagnostic);
+        }
+    }
+    Some(())
+}
+
+// Diagnostic: duplicate-argument
+//
+// Reported on a parameter or `<cfargument>` whose name another one of the
+// same function already uses.
+pub(crate) fn duplicate_argument(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    for func in functions(ctx.root()) {
+        let mut first: FxHashMap<String, TextRange> = FxHashMap::default();
+        for param in old_params(&func) {
+            let key = param.name.to_ascii_lowercase();
+            let Some(first) = first.get(&key) else {
+                first.insert(key, param.name_range);
+                continue;
+            };
+            let message = match func.name() {
+                Some(name) => format!("Argument `{}` is declared twice in `{name}`", param.name),
+                None => format!("Argument `{}` is declared twice", param.name),
+            };
+            acc.push(
+                Diagnostic::new(
+                    "duplicate-argument",
+                    Severity::Error,
+                    message,
+                    param.name_range,
+                )
+                .with_related(
+                    ctx.frange(*first),
+                    format!("`{}` is first declared here", param.name),
+                ),
+            );
+        }
+    }
+    Some(())
+}
+
+/// Sets `accessor="false"` on `property`, replacing the value of an existing
+/// attribute.
+fn disable_accessor(property: &Property, accessor: &str) -> Option<TextEdit> {
+    if let Some(attribute) = property.find_attribute(accessor) {
+        let value = attribute.value_element()?.text_range();
+        return Some(TextEdit::replace(value, "\"false\"".to_string()));
+    }
+    // After the last attribute, or the name of `property string name;`.
+    let anchor = match property.attributes().last() {
+        Some(it) => it.syntax().text_range().end(),
+        None => property
+            .syntax()
+            .children()
+            .find(|it| it.kind() == NAME)
+            .and_then(ast::Name::cast)?
+            .syntax()
+            .text_range()
+            .end(),
+    };
+    Some(TextEdit::insert(anchor, format!(" {accessor}=\"false\"")))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn duplicate_functions() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component accessors="true" {
+    property name="name";
+    property name="age" getter="false";
+    function save() {}
+    function getName() { return variables.name; }
+    function getAge() { return 1; }
+    function SAVE() {}
+}
+"#,
+            &[
+                (
+                    "SAVE",
+                    "`SAVE` is already declared in this component, and this declaration replaces it",
+                ),
+                (
+                    "getName",
+                    "`getName` replaces the getter generated for property `name`",
+                ),
+            ],
+        );
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cfproperty name="name">
+    <cffunction name="getName"></cffunction>
+    <cffunction name="getname"></cffunction>
+</cfcomponent>
+"#,
+            &[(
+                "getname",
+                "`getname` is already declared in this component, and this declaration replaces it",
+            )],
+        );
+    }
+
+    #[test]
+    fn duplicate_arguments() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cffunction name="f">
+        <cfargument name="id">
+        <cfargument name="ID">
+        <cfreturn arguments>
+    </cffunction>
+    <cfscript>
+        function g(a, b, a) { return [a, b]; }
+    </cfscript>
+</cfcomponent>
+"#,
+            &[
+                ("ID", "Argument `ID` is declared twice in `f`"),
+                ("a", "Argument `a` is declared twice in `g`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn disable_accessor() {
+        check_fix(
+            "Add `setter=\"false\"` to property `name`",
+            r#"
+//- /app/Service.cfc
+component accessors="true" {
+    property string name;
+    function setName(value) { variables.name = trim(value); }
+}
+"#,
+            r#"component accessors="true" {
+    property string name setter="false";
+    function setName(value) { variables.name = trim(value); }
+}"#,
+        );
+        check_fix(
+            "Add `getter=\"false\"` to property `name`",
+            r#"
+//- /app/Service.cfc
+<cfcomponent accessors="true">
+    <cfproperty name="name" getter="true">
+    <cffunction name="getName"><cfreturn variables.name></cffunction>
+</cfcomponent>
+"#,
+            r#"<cfcomponent accessors="true">
+    <cfproperty name="name" getter="false">
+    <cffunction name="getName"><cfreturn variables.name></cffunction>
+</cfcomponent>"#,
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext},
+    var_scope::{functions, FunctionLike},
+};
+
+/// Tags branching the control flow.
+const BRANCHING_TAGS: &[&str] = &["cfcase", "cfcatch", "cfelseif", "cfif", "cfloop"];
+
+/// Operators evaluating their right side conditionally.
+const SHORT_CIRCUITS: &[&str] = &["&&", "||", "and", "or", "?:"];
+
+// Diagnostic: complexity
+//
+// Reported on the name of a function whose cyclomatic complexity, one plus
+// its branches, loops, `case`s, `catch`es and short-circuiting operators,
+// exceeds the configured thresholds. Closures count towards their own
+// complexity, not their function's.
+
+// Diagnostic: function-length
+//
+// Reported on the name of a function spanning more lines than the
+// configured thresholds.
+pub(crate) fn function_metrics(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let config = ctx.config();
+    for func in functions(ctx.root()) {
+        if !matches!(func, FunctionLike::Decl(_) | FunctionLike::Tag(_)) {
+            continue;
+        }
+        let (Some(name), Some(range)) = (func.name(), func.name_range()) else {
+            continue;
+        };
+        let complexity = complexity(&func);
+        if let Some((severity, limit)) = config.complexity.severity(complexity) {
+            acc.push(Diagnostic::new(
+                "complexity",
+                severity,
+                format!("`{name}` has a cyclomatic complexity of {complexity}, more than {limit}"),
+                range,
+            ));
+        }
+        let lines = func.syntax().text().lines().count() as u32;
+        if let Some((severity, limit)) = config.function_length.severity(lines) {
+            acc.push(Diagnostic::new(
+                "function-length",
+                severity,
+                format!("`{name}` is {lines} lines long, more than {limit}"),
+                range,
+            ));
+        }
+    }
+    Some(())
+}
+
+fn complexity(func: &FunctionLike) -> u32 {
+    let branches = func
+        .own_nodes()
+        .into_iter()
+        .filter(|node| match node.kind() {
+            IF_STMT | FOR_STMT | FOR_IN_STMT | WHILE_STMT | DO_WHILE_STMT | CASE_CLAUSE
+            | CATCH_CLAUSE | TERNARY_EXPR => true,
+            BIN_EXPR => ast::BinExpr::cast(node.clone()).is_some_and(|it| {
+                SHORT_CIRCUITS.contains(&it.op_text().to_ascii_lowercase().as_str())
+            }),
+            TAG => ast::Tag::cast(node.clone())
+                .is_some_and(|it| BRANCHING_TAGS.contains(&it.name_lower().as_str())),
+            _ => false,
+        });
+    1 + branches.count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{tests::check_diagnostics_with_config, DiagnosticsConfig, Thresholds};
+
+    #[test]
+    fn complex_and_long_functions() {
+        let config = DiagnosticsConfig {
+            complexity: Thresholds {
+                hint: Some(2),
+                warning: Some(4),
+            },
+            function_length: Thresholds {
+                hint: Some(6),
+                warning: None,
+            },
+            ..DiagnosticsConfig::default()
+        };
+        check_diagnostics_with_config(
+            &config,
+            r#"
+//- /app/Service.cfc
+component {
+    function simple(a) {
+        return a ? 1 : 2;
+    }
+    function branchy(a, b) {
+        if (a && b) {
+            return 1;
+        }
+        return arrayMap([], (it) => it ? 1 : 2);
+    }
+    function complex(xs) {
+        for (var x in xs) {
+            if (x or !x) {
+                return x ?: 0;
+            }
+        }
+        return 0;
+    }
+}
+"#,
+            &[
+                (
+                    "branchy",
+                    "`branchy` has a cyclomatic complexity of 3, more than 2",
+                ),
+                (
+                    "complex",
+                    "`complex` has a cyclomatic complexity of 5, more than 4",
+                ),
+                ("complex", "`complex` is 8 lines long, more than 6"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use rustc_hash::FxHashMap;
+use virtual_fs::FileId;
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::{includes, resolve_include},
+    Analysis,
+};
+
+// Diagnostic: missing-include
+//
+// Reported on the template of a `<cfinclude>` or `include` which no file of
+// the workspace resolves. Templates computed with `#...#` aren't checked.
+
+// Diagnostic: recursive-include
+//
+// Reported on an include leading back to the template it is in, directly
+// or through other templates, with the whole cycle in the message.
+pub(crate) fn include_graph(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let analysis = ctx.analysis();
+    let mut graph = IncludeGraph {
+        analysis,
+        edges: FxHashMap::default(),
+    };
+    for include in includes(ctx.root()) {
+        let Some(target) = resolve_include(analysis, ctx.file_id(), &include.template) else {
+            acc.push(Diagnostic::new(
+                "missing-include",
+                Severity::Error,
+                format!("Included template `{}` can't be found", include.template),
+                include.range,
+            ));
+            continue;
+        };
+        let Some(cycle) = graph.path(target, ctx.file_id()) else {
+            continue;
+        };
+        let cycle: Vec<String> = std::iter::once(ctx.file_id())
+            .chain(cycle)
+            .filter_map(|it| Some(format!("`{}`", analysis.paths.get(&it)?)))
+            .collect();
+        acc.push(Diagnostic::new(
+            "recursive-include",
+            Severity::Warning,
+            format!("Recursive include: {}", cycle.join(" → ")),
+            include.range,
+        ));
+    }
+    Some(())
+}
+
+/// The files each file includes, resolved as needed.
+struct IncludeGraph<'a> {
+    analysis: &'a Analysis,
+    edges: FxHashMap<FileId, Vec<FileId>>,
+}
+
+impl IncludeGraph<'_> {
+    fn edges(&mut self, file_id: FileId) -> Vec<FileId> {
+        let analysis = self.analysis;
+        self.edges
+            .entry(file_id)
+            .or_insert_with(|| {
+                let Some(parse) = analysis.parse(file_id) else {
+                    return Vec::new();
+                };
+                includes(&parse.syntax_node())
+                    .iter()
+                    .filter_map(|it| resolve_include(analysis, file_id, &it.template))
+                    .collect()
+            })
+            .clone()
+    }
+
+    /// The shortest chain of includes from `from` to `to`, both included.
+    fn path(&mut self, from: FileId, to: FileId) -> Option<Vec<FileId>> {
+        let mut parents: FxHashMap<FileId, FileId> = FxHashMap::default();
+        let mut queue = VecDeque::from([from]);
+        while let Some(file_id) = queue.pop_front() {
+            if file_id == to {
+                let mut res = vec![to];
+                let mut current = to;
+                while let Some(&parent) = parents.get(&current) {
+                    res.push(parent);
+                    current = parent;
+                }
+                res.reverse();
+                return Some(res);
+            }
+            for next in self.edges(file_id) {
+                if next != from && !parents.contains_key(&next) {
+                    parents.insert(next, file_id);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn missing_includes() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfinclude template="inc/header.cfm">
+<cfinclude template="inc/menu.cfm">
+<cfinclude template="#variables.page#.cfm">
+<cfscript>
+    include "/inc/footer.cfm";
+    include "../footer.cfm";
+</cfscript>
+//- /app/inc/header.cfm
+<h1>Header</h1>
+//- /app/inc/footer.cfm
+<p>Footer</p>
+"##,
+            &[
+                (
+                    "inc/menu.cfm",
+                    "Included template `inc/menu.cfm` can't be found",
+                ),
+                (
+                    "../footer.cfm",
+                    "Included template `../footer.cfm` can't be found",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn recursive_includes() {
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<cfinclude template="index.cfm">
+<cfinclude template="a.cfm">
+<cfinclude template="c.cfm">
+//- /app/a.cfm
+<cfinclude template="b.cfm">
+//- /app/b.cfm
+<cfinclude template="index.cfm">
+//- /app/c.cfm
+<cfinclude template="a.cfm">
+"#,
+            &[
+                (
+                    "index.cfm",
+                    "Recursive include: `/app/index.cfm` → `/app/index.cfm`",
+                ),
+                (
+                    "a.cfm",
+                    "Recursive include: `/app/index.cfm` → `/app/a.cfm` → `/app/b.cfm` → \
+                     `/app/index.cfm`",
+                ),
+                (
+                    "c.cfm",
+                    "Recursive include: `/app/index.cfm` → `/app/c.cfm` → `/app/a.cfm` → \
+                     `/app/b.cfm` → `/app/index.cfm`",
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::{AttributeType, BuiltinTag, SQL_TYPES},
+};
+
+// Diagnostic: invalid-attribute-value
+//
+// Reported on the literal value of a built-in tag's attribute which the
+// attribute doesn't take: a boolean attribute given something else than
+// `true`, `false`, `yes`, `no` or a number, a numeric one given text, or an
+// attribute like `cfsqltype` or `access` given a value not in its set.
+// Values computed with `#...#` or expressions aren't checked.
+pub(crate) fn invalid_attribute_value(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    for tag in ctx.root().descendants().filter_map(BuiltinTag::cast) {
+        for attribute in &tag.attributes {
+            let Some(schema) = tag.schema.attribute(&attribute.name_lower()) else {
+                continue;
+            };
+            let (Some(value), Some(element)) = (literal(attribute), attribute.value_element())
+            else {
+                continue;
+            };
+            let lower = value.trim().to_ascii_lowercase();
+            let expected = match schema.ty {
+                AttributeType::Any => continue,
+                AttributeType::Boolean => {
+                    if matches!(lower.as_str(), "true" | "false" | "yes" | "no")
+                        || lower.parse::<f64>().is_ok()
+                    {
+                        continue;
+                    }
+                    "a boolean".to_string()
+                }
+                AttributeType::Numeric => {
+                    if lower.parse::<f64>().is_ok() {
+                        continue;
+                    }
+                    "numeric".to_string()
+                }
+                AttributeType::OneOf(values) => {
+                    if values.contains(&lower.as_str()) {
+                        continue;
+                    }
+                    or_list(values.iter().map(|it| it.to_string()))
+                }
+                AttributeType::SqlType => {
+                    let name = lower.strip_prefix("cf_sql_").unwrap_or(&lower);
+                    if SQL_TYPES.contains(&name) {
+                        continue;
+                    }
+                    or_list(SQL_TYPES.iter().map(|it| format!("cf_sql_{it}")))
+                }
+            };
+            let name = attribute.name()?;
+            acc.push(Diagnostic::new(
+                "invalid-attribute-value",
+                Severity::Error,
+                format!(
+                    "`{}` of `<{}>` must be {expected}, not `{value}`",
+                    name.text(),
+                    tag.name
+                ),
+                element.text_range(),
+            ));
+        }
+    }
+    Some(())
+}
+
+/// The value of `attribute` if it is written literally, not computed.
+fn literal(attribute: &ast::Attribute) -> Option<String> {
+    match attribute.value_element()? {
+        SyntaxElement::Token(_) => attribute.value(),
+        SyntaxElement::Node(node) => match ast::Expr::cast(node)? {
+            ast::Expr::StringLit(it) => it.value(),
+            ast::Expr::Literal(_) => attribute.value(),
+            _ => None,
+        },
+    }
+}
+
+/// `a`, `b` or `c`.
+fn or_list(values: impl Iterator<Item = String>) -> String {
+    let values: Vec<String> = values.map(|it| format!("`{it}`")).collect();
+    match values.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn invalid_values() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfquery name="q" datasource="app" timeout="ten">
+    SELECT * FROM users
+    WHERE id = <cfqueryparam value="1" cfsqltype="cf_sql_interger">
+    AND name = <cfqueryparam value="a" cfsqltype="varchar">
+    AND age = <cfqueryparam value="1" cfsqltype="CF_SQL_INTEGER">
+</cfquery>
+<cflock name="l" timeout="#q.recordCount#" type="exclusive"></cflock>
+<cffunction name="f" access="protected" output="no"></cffunction>
+<cfscript>
+    lock name="l" timeout=1 type="shared" {}
+</cfscript>
+<cflocation url="/" addtoken="maybe">
+"##,
+            &[
+                (
+                    "\"ten\"",
+                    "`timeout` of `<cfquery>` must be numeric, not `ten`",
+                ),
+                (
+                    "\"cf_sql_interger\"",
+                    "`cfsqltype` of `<cfqueryparam>` must be `cf_sql_array`, `cf_sql_bigint`, \
+                     `cf_sql_binary`, `cf_sql_bit`, `cf_sql_blob`, `cf_sql_char`, `cf_sql_clob`, \
+                     `cf_sql_date`, `cf_sql_decimal`, `cf_sql_distinct`, `cf_sql_double`, \
+                     `cf_sql_float`, `cf_sql_idstamp`, `cf_sql_integer`, `cf_sql_longnvarchar`, \
+                     `cf_sql_longvarbinary`, `cf_sql_longvarchar`, `cf_sql_money`, \
+                     `cf_sql_money4`, `cf_sql_nchar`, `cf_sql_nclob`, `cf_sql_numeric`, \
+                     `cf_sql_nvarchar`, `cf_sql_other`, `cf_sql_real`, `cf_sql_refcursor`, \
+                     `cf_sql_smallint`, `cf_sql_sqlxml`, `cf_sql_struct`, `cf_sql_time`, \
+                     `cf_sql_timestamp`, `cf_sql_tinyint`, `cf_sql_varbinary` or `cf_sql_varchar`, \
+                     not `cf_sql_interger`",
+                ),
+                (
+                    "\"protected\"",
+                    "`access` of `<cffunction>` must be `package`, `private`, `public` or \
+                     `remote`, not `protected`",
+                ),
+                (
+                    "\"shared\"",
+                    "`type` of `<cflock>` must be `exclusive` or `readonly`, not `shared`",
+                ),
+                (
+                    "\"maybe\"",
+                    "`addtoken` of `<cflocation>` must be a boolean, not `maybe`",
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use syntax::{
+    ast::{self, AstNode},
+    tags,
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::{
+    assists::utils::removal_range,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    TextEdit,
+};
+
+// Diagnostic: unclosed-tag
+// Diagnostic: unmatched-closing-tag
+//
+// Reported on tags which must be closed but never are, e.g. a `<cfif>`
+// without `</cfif>`, and on closing tags without an opening tag. When a tag
+// is closed with the wrong name, both are reported, pointing at each other.
+// The fixes insert the missing closing tag and remove the unmatched one.
+pub(crate) fn mismatched_tags(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for node in ctx.root().descendants() {
+        match node.kind() {
+            TAG => unclosed_tag(acc, ctx, ast::Tag::cast(node)?),
+            TAG_CLOSE => unmatched_close(acc, ctx, &node),
+            _ => None,
+        };
+    }
+    Some(())
+}
+
+fn unclosed_tag(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext, tag: ast::Tag) -> Option<()> {
+    let name = tag.name()?;
+    let lower = tag.name_lower();
+    if tag.close().is_some() || tag.is_self_closing() || !tags::requires_body(&lower) {
+        return None;
+    }
+    let end = tag.syntax().text_range().end();
+    let mut diagnostic = Diagnostic::new(
+        "unclosed-tag",
+        Severity::Error,
+        format!("`<{}>` is never closed", name.text()),
+        name.text_range(),
+    );
+    // The closing tag of the parent, or a mismatched one, ended the body.
+    if let Some(close) = next_close(tag.syntax()) {
+        diagnostic = diagnostic.with_related(
+            ctx.frange(close.text_range()),
+            format!("`{}` ends the body of `<{}>`", close.text(), name.text()),
+        );
+    }
+    let closing = format!("</{}>", name.text());
+    acc.push(diagnostic.with_fix(ctx.fix(
+        "insert_closing_tag",
+        format!("Insert `{closing}`"),
+        name.text_range(),
+        TextEdit::insert(end, closing),
+    )));
+    Some(())
+}
+
+fn unmatched_close(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+    close: &SyntaxNode,
+) -> Option<()> {
+    let parent = close.parent()?;
+    if ast::Tag::can_cast(parent.kind()) {
+        return None;
+    }
+    let close_tag = ast::TagClose::cast(close.clone())?;
+    let name = close_tag.name()?;
+    let mut diagnostic = Diagnostic::new(
+        "unmatched-closing-tag",
+        Severity::Error,
+        format!("`{}` has no opening tag", close.text()),
+        close.text_range(),
+    );
+    // `<cfif><cfloop></cfif></cfloop>`: the `<cfloop>` is the one meant.
+    let opening = ctx
+        .root()
+        .descendants()
+        .filter_map(ast::Tag::cast)
+        .filter(|it| it.syntax().text_range().end() <= close.text_range().start())
+        .filter(|it| it.close().is_none() && it.name_lower() == name.text().to_ascii_lowercase())
+        .last();
+    if let Some(open_name) = opening.and_then(|it| it.name()) {
+        diagnostic = diagnostic.with_related(
+            ctx.frange(open_name.text_range()),
+            format!("`<{}>` was left open", open_name.text()),
+        );
+    }
+    acc.push(diagnostic.with_fix(ctx.fix(
+        "remove_closing_tag",
+        format!("Remove `{}`", close.text()),
+        close.text_range(),
+        TextEdit::delete(removal_range(close)),
+    )));
+    Some(())
+}
+
+/// The closing tag right after `node`, skipping text and whitespace.
+fn next_close(node: &SyntaxNode) -> Option<SyntaxNode> {
+    let mut next = node.next_sibling_or_token();
+    while let Some(element) = next {
+        match element.kind() {
+            TAG_CLOSE => return element.into_node(),
+            TEXT | WHITESPACE => next = element.next_sibling_or_token(),
+            _ => return None,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::TextRange;
+    use virtual_fs::FileId;
+
+    use crate::diagnostics::{
+        diagnostics,
+        tests::{analysis_from_fixture, check_diagnostics, check_fix},
+        DiagnosticsConfig,
+    };
+
+    const MISMATCHED: &str = r#"
+//- /app/index.cfm
+<cfif true>
+    <cfloop index="i" from="1" to="3">
+</cfif>
+</cfloop>
+"#;
+
+    #[test]
+    fn mismatched() {
+        check_diagnostics(
+            MISMATCHED,
+            &[
+                ("cfloop", "`<cfloop>` is never closed"),
+                ("</cfloop>", "`</cfloop>` has no opening tag"),
+            ],
+        );
+        let analysis = analysis_from_fixture(MISMATCHED);
+        let related: Vec<(TextRange, String)> =
+            diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0))
+                .into_iter()
+                .flat_map(|it| it.related)
+                .map(|it| {
+                    assert_eq!(it.range.file_id, FileId(0));
+                    (it.range.range, it.message)
+                })
+                .collect();
+        assert_eq!(
+            related,
+            vec![
+                (
+                    TextRange::new(51, 58),
+                    "`</cfif>` ends the body of `<cfloop>`".to_string()
+                ),
+                (
+                    TextRange::new(17, 23),
+                    "`<cfloop>` was left open".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_at_end_of_file() {
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<cfoutput>#now()#
+"#,
+            &[("cfoutput", "`<cfoutput>` is never closed")],
+        );
+    }
+
+    #[test]
+    fn insert_closing_tag() {
+        check_fix(
+            "Insert `</cfloop>`",
+            MISMATCHED,
+            r#"<cfif true>
+    <cfloop index="i" from="1" to="3">
+</cfloop></cfif>
+</cfloop>"#,
+        );
+        check_fix(
+            "Remove `</cfloop>`",
+            MISMATCHED,
+            r#"<cfif true>
+    <cfloop index="i" from="1" to="3">
+</cfif>
+"#,
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::{BuiltinTag, ATTRIBUTE_COLLECTION},
+};
+
+// Diagnostic: missing-attribute
+//
+// Reported on the name of a built-in tag lacking the attributes it needs,
+// like `<cfqueryparam>` without a `value`. Tags taking several forms, like
+// `<cfloop>`, need one of their combinations of attributes in full. Tags
+// given an `attributeCollection`, or the short form of a script statement,
+// aren't checked.
+pub(crate) fn missing_attribute(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let engine = ctx.config().engine;
+    for tag in ctx.root().descendants().filter_map(BuiltinTag::cast) {
+        if tag.shorthand || tag.attribute(ATTRIBUTE_COLLECTION).is_some() {
+            continue;
+        }
+        // Combinations the engine can't take are left out.
+        let combinations: Vec<&[&str]> = tag
+            .schema
+            .required
+            .iter()
+            .copied()
+            .filter(|combination| {
+                combination.iter().all(|name| {
+                    tag.schema
+                        .attribute(name)
+                        .is_none_or(|it| it.supported_by(engine))
+                })
+            })
+            .collect();
+        let given =
+            |combination: &&[&str]| combination.iter().all(|name| tag.attribute(name).is_some());
+        if combinations.is_empty() || combinations.iter().any(given) {
+            continue;
+        }
+        // The combinations the given attributes belong to, as they tell the
+        // intended form, e.g. `index` and `from` for a loop `to` something.
+        let given: Vec<&str> = combinations
+            .iter()
+            .flat_map(|it| it.iter().copied())
+            .filter(|name| tag.attribute(name).is_some())
+            .collect();
+        let mut candidates: Vec<&[&str]> = combinations
+            .iter()
+            .copied()
+            .filter(|combination| given.iter().all(|it| combination.contains(it)))
+            .collect();
+        if candidates.is_empty() {
+            candidates = combinations;
+        }
+        let single = candidates.len() == 1;
+        let mut missing: Vec<String> = Vec::new();
+        for combination in candidates {
+            let names: Vec<&str> = combination
+                .iter()
+                .copied()
+                .filter(|name| tag.attribute(name).is_none())
+                .collect();
+            let names = match names.as_slice() {
+                _ if single => and_list(&names),
+                [name] => format!("`{name}`"),
+                _ => format!("({})", and_list(&names)),
+            };
+            if !missing.contains(&names) {
+                missing.push(names);
+            }
+        }
+        let message = format!("`<{}>` requires {}", tag.name, missing.join(" or "));
+        acc.push(Diagnostic::new(
+            "missing-attribute",
+            Severity::Error,
+            message,
+            tag.name_token.text_range(),
+        ));
+    }
+    Some(())
+}
+
+/// `a`, `b` and `c`.
+fn and_list(names: &[&str]) -> String {
+    let names: Vec<String> = names.iter().map(|it| format!("`{it}`")).collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diagnostics::{tests::check_diagnostics_with_config, DiagnosticsConfig},
+        Engine, EngineKind,
+    };
+
+    #[test]
+    fn missing_attributes() {
+        let config = DiagnosticsConfig {
+            engine: Some(Engine {
+                kind: EngineKind::Adobe,
+                version: 2021,
+            }),
+            ..DiagnosticsConfig::default()
+        };
+        check_diagnostics_with_config(
+            &config,
+            r##"
+//- /app/index.cfm
+<cfquery name="q" datasource="app">
+    SELECT * FROM users WHERE id = <cfqueryparam cfsqltype="cf_sql_integer">
+    AND deleted = <cfqueryparam null="true">
+</cfquery>
+<cfloop index="i" from="1"></cfloop>
+<cfloop index="i" list="a,b"></cfloop>
+<cfloop item="it"></cfloop>
+<cfmail from="a@b.c" subject="Hi">Hi</cfmail>
+<cflock timeout="1"></cflock>
+<cfinclude attributeCollection="#q#">
+<cfscript>
+    include "a.cfm";
+    param x = 1;
+    lock name="l" {}
+    location url="/";
+</cfscript>
+//- /app/a.cfm
+"##,
+            &[
+                (
+                    "cfqueryparam",
+                    "`<cfqueryparam>` requires `null` or `value`",
+                ),
+                ("cfloop", "`<cfloop>` requires `to`"),
+                (
+                    "cfloop",
+                    "`<cfloop>` requires `array` or `collection` or `file` or `list`",
+                ),
+                ("cfmail", "`<cfmail>` requires `to`"),
+                ("cflock", "`<cflock>` requires `name` or `scope`"),
+                ("lock", "`<cflock>` requires `timeout`"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use rustc_hash::FxHashSet;
+use syntax::ast::AstNode;
+use virtual_fs::FileId;
+
+use crate::{
+    assists::utils::attribute_value,
+    component::{indent_unit, line_indent, Component, Signature},
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::{component_in, resolve_component, walk_extends},
+    Analysis, TextEdit,
+};
+
+// Diagnostic: missing-interface-members
+//
+// Reported on `implements` when the component, or a component it extends,
+// lacks functions declared by one of the interfaces. The engine refuses to
+// create such a component. The fix adds stubs which throw.
+pub(crate) fn missing_interface_members(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let component = ctx.root().descendants().find_map(Component::cast)?;
+    if component.is_interface() {
+        return None;
+    }
+    let attribute = component.find_attribute("implements")?;
+    let implemented = implemented_functions(ctx.analysis(), ctx.file_id());
+
+    for name in component.names_in("implements") {
+        let Some(interface) = resolve_component(ctx.analysis(), ctx.file_id(), &name) else {
+            continue;
+        };
+        let mut missing: Vec<Signature> = Vec::new();
+        let mut seen = FxHashSet::default();
+        walk_interfaces(ctx.analysis(), interface, &mut seen, &mut |signature| {
+            let key = signature.name.to_ascii_lowercase();
+            let is_new = !missing.iter().any(|it| it.name.eq_ignore_ascii_case(&key));
+            if !implemented.contains(&key) && is_new {
+                missing.push(signature);
+            }
+        });
+        if missing.is_empty() {
+            continue;
+        }
+
+        let names: Vec<String> = missing.iter().map(|it| format!("`{}`", it.name)).collect();
+        let message = format!(
+            "Missing implementation of {} from interface `{name}`",
+            names.join(", ")
+        );
+        let range = attribute.syntax().text_range();
+        let mut builder = TextEdit::builder();
+        builder.insert(component.members_end()?, stubs(&component, &missing));
+        let fix = ctx.fix(
+            "implement_missing_members",
+            format!("Implement missing members of `{name}`"),
+            range,
+            builder.finish(),
+        );
+        acc.push(
+            Diagnostic::new("missing-interface-members", Severity::Error, message, range)
+                .with_fix(fix),
+        );
+    }
+    Some(())
+}
+
+/// Lowercase names of the functions of the component in `file_id` and of
+/// the components it extends, including generated accessors.
+fn implemented_functions(analysis: &Analysis, file_id: FileId) -> FxHashSet<String> {
+    let mut res = FxHashSet::default();
+    walk_extends(analysis, file_id, |_, component| {
+        res.extend(
+            component
+                .functions()
+                .iter()
+                .filter_map(|it| it.name())
+                .map(|it| it.to_ascii_lowercase()),
+        );
+        if component.has_implicit_accessors() {
+            for property in component.properties() {
+                let Some(name) = property.name() else {
+                    continue;
+                };
+                for accessor in ["getter", "setter"] {
+                    if property.wants_accessor(accessor) {
+                        res.insert(format!("{}{}", &accessor[..3], name.to_ascii_lowercase()));
+                    }
+                }
+            }
+        }
+    });
+    res
+}
+
+/// Applies `f` to the functions of an interface and of the interfaces it
+/// extends.
+fn walk_interfaces(
+    analysis: &Analysis,
+    file_id: FileId,
+    seen: &mut FxHashSet<FileId>,
+    f: &mut dyn FnMut(Signature),
+) {
+    if !seen.insert(file_id) {
+        return;
+    }
+    let Some(interface) = component_in(analysis, file_id).filter(|it| it.is_interface()) else {
+        return;
+    };
+    for signature in interface.functions().iter().filter_map(Signature::of) {
+        f(signature);
+    }
+    for name in interface.names_in("extends") {
+        if let Some(parent) = resolve_component(analysis, file_id, &name) {
+            walk_interfaces(analysis, parent, seen, f);
+        }
+    }
+}
+
+/// Stub implementations of `signatures`, written like the members of
+/// `component`.
+fn stubs(component: &Component, signatures: &[Signature]) -> String {
+    let members = component.members();
+    let indent = match members.first() {
+        Some(first) => line_indent(first),
+        None => {
+            let outer = line_indent(component.syntax());
+            format!("{outer}{}", indent_unit(&outer))
+        }
+    };
+    let unit = indent_unit(&indent);
+    let mut res = String::new();
+    for (idx, signature) in signatures.iter().enumerate() {
+        let separator = if idx == 0 && members.is_empty() {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        res.push_str(separator);
+        let access = signature.access.as_deref().unwrap_or("public");
+        let name = &signature.name;
+        if component.is_script() {
+            let return_type = signature
+                .return_type
+                .as_ref()
+                .map(|it| format!("{it} "))
+                .unwrap_or_default();
+            let params: Vec<String> = signature
+                .params
+                .iter()
+                .map(|param| {
+                    let mut res = String::new();
+                    if param.required {
+                        res.push_str("required ");
+                    }
+                    if let Some(ty) = &param.ty {
+                        res.push_str(&format!("{ty} "));
+                    }
+                    res.push_str(&param.name);
+                    if let Some(default) = &param.default {
+                        res.push_str(&format!(" = {default}"));
+                    }
+                    res
+                })
+                .collect();
+            res.push_str(&format!(
+                "{indent}{access} {return_type}function {name}({}) {{\n\
+                 {indent}{unit}throw(\"not implemented\");\n\
+                 {indent}}}",
+                params.join(", ")
+            ));
+        } else {
+            let return_type = signature
+                .return_type
+                .as_ref()
+                .map(|it| format!(" returntype=\"{it}\""))
+                .unwrap_or_default();
+            res.push_str(&format!(
+                "{indent}<cffunction name=\"{name}\" access=\"{access}\"{return_type} output=\"false\">\n"
+            ));
+            for param in &signature.params {
+                res.push_str(&format!(
+                    "{indent}{unit}<cfargument name=\"{}\"",
+                    param.name
+                ));
+                if let Some(ty) = &param.ty {
+                    res.push_str(&format!(" type=\"{ty}\""));
+                }
+                if param.required {
+                    res.push_str(" required=\"true\"");
+                }
+                if let Some(default) = &param.default {
+                    res.push_str(&format!(" default=\"{}\"", attribute_value(default)));
+                }
+                res.push_str(">\n");
+            }
+            res.push_str(&format!(
+                "{indent}{unit}<cfthrow message=\"not implemented\">\n{indent}</cffunction>"
+            ));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn missing_members() {
+        check_diagnostics(
+            r#"
+//- /app/models/User.cfc
+component implements="IEntity,models.INamed" extends="Base" {
+    function getId() {}
+}
+//- /app/models/IEntity.cfc
+interface extends="IBase" {
+    function getId();
+    function save(boolean flush);
+}
+//- /app/models/IBase.cfc
+interface {
+    function delete();
+    function refresh();
+}
+//- /app/models/INamed.cfc
+interface {
+    function getName();
+}
+//- /app/models/Base.cfc
+component accessors="true" {
+    property name;
+    function refresh() {}
+}
+"#,
+            &[(
+                r#"implements="IEntity,models.INamed""#,
+                "Missing implementation of `save`, `delete` from interface `IEntity`",
+            )],
+        );
+    }
+
+    #[test]
+    fn implement_in_script() {
+        check_fix(
+            "Implement missing members of `IRepository`",
+            r#"
+//- /app/Repository.cfc
+component implements="IRepository" {
+    function init() {}
+}
+//- /app/IRepository.cfc
+interface {
+    public array function find(required string query, numeric limit = 10);
+    function clear();
+}
+"#,
+            r#"component implements="IRepository" {
+    function init() {}
+
+    public array function find(required string query, numeric limit = 10) {
+        throw("not implemented");
+    }
+
+    public function clear() {
+        throw("not implemented");
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn implement_in_tags() {
+        check_fix(
+            "Implement missing members of `IRepository`",
+            r##"
+//- /app/Repository.cfc
+<cfcomponent implements="IRepository">
+</cfcomponent>
+//- /app/IRepository.cfc
+interface {
+    public array function find(required string query, date since = now(), numeric limit = 10);
+}
+"##,
+            r##"<cfcomponent implements="IRepository">
+    <cffunction name="find" access="public" returntype="array" output="false">
+        <cfargument name="query" type="string" required="true">
+        <cfargument name="since" type="date" default="#now()#">
+        <cfargument name="limit" type="numeric" default="10">
+        <cfthrow message="not implemented">
+    </cffunction>
+</cfcomponent>"##,
+        );
+    }
+}
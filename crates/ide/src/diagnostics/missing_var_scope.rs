@@ -0,0 +1,116 @@
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    var_scope::{functions, unscoped_assignments, FunctionLike, ScopeFix},
+    TextEdit,
+};
+
+// Diagnostic: missing-var-scope
+//
+// Reported on assignments inside a function to variables which are neither
+// scoped nor declared with `var`, the classic varscoper check. Such a
+// variable lives in the component's `variables` scope, so calls running at
+// the same time on a shared component overwrite each other's values.
+pub(crate) fn missing_var_scope(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for func in functions(ctx.root()) {
+        let owner = match func.name() {
+            Some(name) => format!("function `{name}`"),
+            None => match enclosing_name(&func) {
+                Some(name) => format!("a closure in function `{name}`"),
+                None => "a closure".to_string(),
+            },
+        };
+        for assignment in unscoped_assignments(&func) {
+            let label = match assignment.fix {
+                ScopeFix::Var => format!("Declare `{}` with `var`", assignment.name),
+                ScopeFix::Local => format!("Scope `{0}` as `local.{0}`", assignment.name),
+            };
+            let edit = TextEdit::insert(
+                assignment.fix_offset(),
+                assignment.fix.insert_text().to_string(),
+            );
+            acc.push(
+                Diagnostic::new(
+                    "missing-var-scope",
+                    Severity::Warning,
+                    format!(
+                        "`{}` is not local to {owner} and leaks into the `variables` scope",
+                        assignment.name
+                    ),
+                    assignment.name_range,
+                )
+                .with_fix(ctx.fix(
+                    "add_var_scope",
+                    label,
+                    assignment.name_range,
+                    edit,
+                )),
+            );
+        }
+    }
+    Some(())
+}
+
+/// The name of the innermost named function around a closure.
+fn enclosing_name(func: &FunctionLike) -> Option<String> {
+    func.syntax()
+        .ancestors()
+        .skip(1)
+        .filter_map(FunctionLike::cast)
+        .find_map(|it| it.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unscoped_assignments() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    function getUser(id) {
+        var user = {};
+        local.name = id;
+        user = id;
+        result = [user, name];
+        arrayEach(result, (it) => { total = it; });
+        return result;
+    }
+}
+"#,
+            &[
+                (
+                    "result",
+                    "`result` is not local to function `getUser` and leaks into the `variables` scope",
+                ),
+                (
+                    "total",
+                    "`total` is not local to a closure in function `getUser` and leaks into the `variables` scope",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn declare_with_var() {
+        check_fix(
+            "Declare `result` with `var`",
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cffunction name="f">
+        <cfset result = 1>
+        <cfreturn result>
+    </cffunction>
+</cfcomponent>
+"#,
+            r#"<cfcomponent>
+    <cffunction name="f">
+        <cfset var result = 1>
+        <cfreturn result>
+    </cffunction>
+</cfcomponent>"#,
+        );
+    }
+}
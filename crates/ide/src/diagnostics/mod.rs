@@ -0,0 +1,749 @@
+//! Diagnostics are the errors and warnings reported on a file as it changes,
+//! each with the quick fixes resolving it.
+//!
+//! Like assists, every diagnostic lives in its own module and is a plain
+//! function which looks at a [`DiagnosticsContext`] and pushes zero or more
+//! [`Diagnostic`]s.
+mod call_arguments;
+mod catch_blocks;
+mod circular_inheritance;
+mod component_name_mismatch;
+mod debug_statement;
+mod deprecated_function;
+mod duplicate_names;
+mod function_metrics;
+mod hardcoded_secret;
+mod include_graph;
+mod invalid_attribute_value;
+mod mismatched_tags;
+mod missing_attribute;
+mod missing_interface_members;
+mod missing_var_scope;
+mod naming_convention;
+mod output_hygiene;
+mod queryparam_type;
+mod return_type;
+mod security_audit;
+mod shadowed_variable;
+mod sql_injection;
+mod suppressions;
+mod switch_cases;
+mod syntax_error;
+mod undefined_variable;
+mod unknown_attribute;
+mod unknown_datasource;
+mod unlocked_shared_scope;
+mod unreachable_code;
+mod unresolved_component;
+mod unscoped_variable;
+mod unused_variable;
+mod xss;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{SyntaxNode, TextRange};
+use virtual_fs::FileId;
+
+use crate::{Analysis, Assist, AssistId, AssistKind, Engine, FileRange, SourceChange, TextEdit};
+
+/// A stable identifier of a diagnostic, e.g. `missing-interface-members`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DiagnosticCode(pub &'static str);
+
+/// The kind of problem a diagnostic reports, to turn them off in bulk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCategory {
+    /// Code which doesn't work, or not as it seems to.
+    Correctness,
+    /// Code open to attacks, like SQL injection.
+    Security,
+    /// Code which works, but could be clearer or tidier.
+    Style,
+}
+
+/// Codes of the diagnostics in the security and style categories. The others
+/// are about correctness.
+const CATEGORIES: &[(&str, DiagnosticCategory)] = &[
+    ("case-after-default", DiagnosticCategory::Style),
+    ("code-injection", DiagnosticCategory::Security),
+    ("command-injection", DiagnosticCategory::Security),
+    ("complexity", DiagnosticCategory::Style),
+    ("debug-statement", DiagnosticCategory::Style),
+    ("deprecated-function", DiagnosticCategory::Style),
+    ("deprecated-tag", DiagnosticCategory::Style),
+    ("dynamic-include", DiagnosticCategory::Security),
+    ("empty-switch", DiagnosticCategory::Style),
+    ("function-length", DiagnosticCategory::Style),
+    ("hardcoded-secret", DiagnosticCategory::Security),
+    ("missing-output", DiagnosticCategory::Style),
+    ("naming-convention", DiagnosticCategory::Style),
+    ("shadowed-variable", DiagnosticCategory::Style),
+    ("sql-injection", DiagnosticCategory::Security),
+    ("stray-whitespace", DiagnosticCategory::Style),
+    ("switch-fallthrough", DiagnosticCategory::Style),
+    ("unrestricted-upload", DiagnosticCategory::Security),
+    ("unscoped-variable", DiagnosticCategory::Style),
+    ("unused-variable", DiagnosticCategory::Style),
+    ("xss", DiagnosticCategory::Security),
+];
+
+/// The page documenting every diagnostic, generated from their comments.
+const DOCS_URL: &str = "https://github.com/vinibispo/coldfusion-ls/blob/main/docs/diagnostics.md";
+
+impl DiagnosticCode {
+    /// The documentation of this diagnostic, for clients to link to.
+    pub fn url(self) -> String {
+        format!("{DOCS_URL}#{}", self.0)
+    }
+
+    pub fn category(self) -> DiagnosticCategory {
+        CATEGORIES
+            .iter()
+            .find(|(code, _)| *code == self.0)
+            .map_or(DiagnosticCategory::Correctness, |(_, it)| *it)
+    }
+}
+
+/// How bad a diagnostic is, mirroring LSP's severities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// Settings of the diagnostics, from the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticsConfig {
+    /// The severity of `undefined-variable`. Legacy code defining variables
+    /// in included templates triggers it a lot.
+    pub undefined_variable: Severity,
+    /// The engine the code targets, for the tags and functions it
+    /// deprecates or removed. Unknown when `None`.
+    pub engine: Option<Engine>,
+    /// The cyclomatic complexity of a function above which it is reported.
+    pub complexity: Thresholds,
+    /// The number of lines of a function above which it is reported.
+    pub function_length: Thresholds,
+    /// Whether to report script `case`s falling through to the next one,
+    /// which some teams rely on.
+    pub switch_fallthrough: bool,
+    /// Whether to report variables read without a scope, for teams
+    /// requiring fully scoped code.
+    pub strict_scoping: bool,
+    /// Whether to report tags letting whitespace into the output, for
+    /// teams managing it.
+    pub output_hygiene: bool,
+    /// Whether to report writes to the `application` and `session` scopes
+    /// outside locks, for teams requiring them.
+    pub shared_scope_locking: bool,
+    /// The conventions names must follow, for teams with a style guide.
+    /// Unchecked when `None`.
+    pub naming: Option<NamingConventions>,
+    /// The names of the datasources defined on the servers, compared
+    /// ignoring case. Unchecked when empty.
+    pub datasources: Vec<String>,
+    /// Names of directories, like `tests`, whose files aren't checked for
+    /// hardcoded secrets.
+    pub hardcoded_secrets_exclude: Vec<String>,
+    /// Codes of the diagnostics not to report.
+    pub disabled: FxHashSet<String>,
+    /// Categories of the diagnostics not to report, but for the codes given
+    /// a severity.
+    pub disabled_categories: FxHashSet<DiagnosticCategory>,
+    /// Severities replacing the ones of the diagnostics with these codes.
+    pub severities: FxHashMap<String, Severity>,
+}
+
+/// Limits of a metric: exceeding `hint` gets a hint, and exceeding `warning`
+/// a warning. `None` disables either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub hint: Option<u32>,
+    pub warning: Option<u32>,
+}
+
+impl Thresholds {
+    /// The severity of `value`, if it exceeds a limit.
+    fn severity(&self, value: u32) -> Option<(Severity, u32)> {
+        if let Some(limit) = self.warning.filter(|it| value > *it) {
+            return Some((Severity::Warning, limit));
+        }
+        let limit = self.hint.filter(|it| value > *it)?;
+        Some((Severity::Hint, limit))
+    }
+}
+
+/// A way of writing names made of several words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamingCase {
+    /// `getUser`.
+    Camel,
+    /// `UserService`.
+    Pascal,
+    /// `MAX_RETRIES`.
+    UpperSnake,
+    /// `user_count`.
+    Snake,
+}
+
+/// The case of each kind of name, or `None` to allow any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamingConventions {
+    pub functions: Option<NamingCase>,
+    /// The `var`s and `local.` variables of functions.
+    pub variables: Option<NamingCase>,
+    pub components: Option<NamingCase>,
+    /// The members of a component's `variables` scope set once to a literal
+    /// in its body.
+    pub constants: Option<NamingCase>,
+}
+
+impl Default for NamingConventions {
+    fn default() -> NamingConventions {
+        NamingConventions {
+            functions: Some(NamingCase::Camel),
+            variables: Some(NamingCase::Camel),
+            components: Some(NamingCase::Pascal),
+            constants: Some(NamingCase::UpperSnake),
+        }
+    }
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            undefined_variable: Severity::Warning,
+            engine: None,
+            complexity: Thresholds {
+                hint: Some(10),
+                warning: Some(20),
+            },
+            function_length: Thresholds {
+                hint: Some(100),
+                warning: Some(300),
+            },
+            switch_fallthrough: false,
+            strict_scoping: false,
+            output_hygiene: false,
+            shared_scope_locking: false,
+            naming: None,
+            datasources: Vec::new(),
+            hardcoded_secrets_exclude: vec!["test".to_string(), "tests".to_string()],
+            disabled: FxHashSet::default(),
+            disabled_categories: FxHashSet::default(),
+            severities: FxHashMap::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub message: String,
+    pub range: TextRange,
+    pub severity: Severity,
+    /// The code is never used, for clients to fade it out.
+    pub unused: bool,
+    /// The code uses something deprecated, for clients to strike it out.
+    pub deprecated: bool,
+    /// Other places involved, e.g. the other end of a mismatch.
+    pub related: Vec<RelatedInformation>,
+    pub fixes: Vec<Assist>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedInformation {
+    pub range: FileRange,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(
+        code: &'static str,
+        severity: Severity,
+        message: impl Into<String>,
+        range: TextRange,
+    ) -> Diagnostic {
+        Diagnostic {
+            code: DiagnosticCode(code),
+            message: message.into(),
+            range,
+            severity,
+            unused: false,
+            deprecated: false,
+            related: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+
+    fn with_unused(mut self, unused: bool) -> Diagnostic {
+        self.unused = unused;
+        self
+    }
+
+    fn with_deprecated(mut self, deprecated: bool) -> Diagnostic {
+        self.deprecated = deprecated;
+        self
+    }
+
+    fn with_related(mut self, range: FileRange, message: impl Into<String>) -> Diagnostic {
+        self.related.push(RelatedInformation {
+            range,
+            message: message.into(),
+        });
+        self
+    }
+
+    fn with_fix(mut self, fix: Assist) -> Diagnostic {
+        self.fixes.push(fix);
+        self
+    }
+}
+
+type Handler = fn(&mut Vec<Diagnostic>, &DiagnosticsContext) -> Option<()>;
+
+const HANDLERS: &[Handler] = &[
+    call_arguments::call_arguments,
+    catch_blocks::catch_blocks,
+    circular_inheritance::circular_inheritance,
+    component_name_mismatch::component_name_mismatch,
+    debug_statement::debug_statement,
+    deprecated_function::deprecated_function,
+    deprecated_function::deprecated_by_engine,
+    duplicate_names::duplicate_argument,
+    duplicate_names::duplicate_function,
+    function_metrics::function_metrics,
+    hardcoded_secret::hardcoded_secret,
+    include_graph::include_graph,
+    invalid_attribute_value::invalid_attribute_value,
+    missing_attribute::missing_attribute,
+    missing_interface_members::missing_interface_members,
+    missing_var_scope::missing_var_scope,
+    mismatched_tags::mismatched_tags,
+    naming_convention::naming_convention,
+    output_hygiene::output_hygiene,
+    queryparam_type::queryparam_type,
+    return_type::return_type,
+    security_audit::security_audit,
+    shadowed_variable::shadowed_variable,
+    sql_injection::sql_injection,
+    switch_cases::switch_cases,
+    syntax_error::syntax_error,
+    undefined_variable::undefined_variable,
+    unknown_attribute::unknown_attribute,
+    unknown_datasource::unknown_datasource,
+    unlocked_shared_scope::unlocked_shared_scope,
+    unreachable_code::unreachable_code,
+    unresolved_component::unresolved_component,
+    unscoped_variable::unscoped_variable,
+    unused_variable::unused_variable,
+    xss::xss,
+];
+
+/// The diagnostics looking at the syntax of a file alone.
+const SYNTAX_HANDLERS: &[Handler] = &[mismatched_tags::mismatched_tags, syntax_error::syntax_error];
+
+pub(crate) fn diagnostics(
+    analysis: &Analysis,
+    config: &DiagnosticsConfig,
+    file_id: FileId,
+) -> Vec<Diagnostic> {
+    run_handlers(analysis, config, file_id, HANDLERS)
+}
+
+pub(crate) fn syntax_diagnostics(
+    analysis: &Analysis,
+    config: &DiagnosticsConfig,
+    file_id: FileId,
+) -> Vec<Diagnostic> {
+    run_handlers(analysis, config, file_id, SYNTAX_HANDLERS)
+}
+
+fn run_handlers(
+    analysis: &Analysis,
+    config: &DiagnosticsConfig,
+    file_id: FileId,
+    handlers: &[Handler],
+) -> Vec<Diagnostic> {
+    let Some(parse) = analysis.parse(file_id) else {
+        return Vec::new();
+    };
+    let ctx = DiagnosticsContext {
+        analysis,
+        config,
+        file_id,
+        root: parse.syntax_node(),
+    };
+    let mut acc = Vec::new();
+    for handler in handlers {
+        analysis.unwind_if_cancelled();
+        handler(&mut acc, &ctx);
+    }
+    let suppressions = suppressions::Suppressions::of(&ctx.root);
+    acc.retain(|it| {
+        let code = it.code.0;
+        let category_disabled = config.disabled_categories.contains(&it.code.category())
+            && !config.severities.contains_key(code);
+        !config.disabled.contains(code) && !category_disabled && !suppressions.suppresses(it)
+    });
+    for diagnostic in &mut acc {
+        if let Some(severity) = config.severities.get(diagnostic.code.0) {
+            diagnostic.severity = *severity;
+        }
+    }
+    acc
+}
+
+pub(crate) struct DiagnosticsContext<'a> {
+    analysis: &'a Analysis,
+    config: &'a DiagnosticsConfig,
+    file_id: FileId,
+    root: SyntaxNode,
+}
+
+impl DiagnosticsContext<'_> {
+    /// The analysis, to look at other files.
+    pub(crate) fn analysis(&self) -> &Analysis {
+        self.analysis
+    }
+
+    pub(crate) fn config(&self) -> &DiagnosticsConfig {
+        self.config
+    }
+
+    pub(crate) fn file_id(&self) -> FileId {
+        self.file_id
+    }
+
+    pub(crate) fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// `range` in the file of the diagnostic.
+    pub(crate) fn frange(&self, range: TextRange) -> FileRange {
+        FileRange {
+            file_id: self.file_id,
+            range,
+        }
+    }
+
+    /// A quick fix editing the file of the diagnostic.
+    pub(crate) fn fix(
+        &self,
+        id: &'static str,
+        label: impl Into<String>,
+        target: TextRange,
+        edit: TextEdit,
+    ) -> Assist {
+        Assist {
+            id: AssistId(id, AssistKind::QuickFix),
+            label: label.into(),
+            target,
+            source_change: SourceChange::from_text_edit(self.file_id, edit),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use virtual_fs::VirtualFsPath;
+
+    use super::*;
+    use crate::AnalysisHost;
+
+    /// Builds an analysis from files introduced by `//- /path` lines. The
+    /// diagnostics are checked in the first file.
+    pub(crate) fn analysis_from_fixture(fixture: &str) -> Analysis {
+        let mut host = AnalysisHost::default();
+        let files = fixture.split("//- ").filter(|it| !it.trim().is_empty());
+        for (idx, file) in files.enumerate() {
+            let (path, text) = file.split_once('\n').unwrap_or((file, ""));
+            let file_id = FileId(idx as u32);
+            host.set_file_text(file_id, Some(text.trim_end().into()));
+            host.set_file_path(
+                file_id,
+                Some(VirtualFsPath::new_virtual_path(path.trim().to_string())),
+            );
+        }
+        host.analysis()
+    }
+
+    /// Checks the diagnostics of the first file, as the text they cover and
+    /// their message.
+    pub(crate) fn check_diagnostics(fixture: &str, expected: &[(&str, &str)]) {
+        check_diagnostics_with_config(&DiagnosticsConfig::default(), fixture, expected);
+    }
+
+    /// Like [`check_diagnostics`], with other settings than the defaults.
+    pub(crate) fn check_diagnostics_with_config(
+        config: &DiagnosticsConfig,
+        fixture: &str,
+        expected: &[(&str, &str)],
+    ) {
+        let analysis = analysis_from_fixture(fixture);
+        let text = analysis.file_text(FileId(0)).unwrap();
+        let actual: Vec<(&str, String)> = diagnostics(&analysis, config, FileId(0))
+            .into_iter()
+            .map(|it| (&text[std::ops::Range::from(it.range)], it.message))
+            .collect();
+        let expected: Vec<(&str, String)> = expected
+            .iter()
+            .map(|(range, message)| (*range, message.to_string()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    /// Checks the related information of the diagnostics of the first file,
+    /// as the text covered by the diagnostic, the text the related
+    /// information points at, in any file, and its message.
+    pub(crate) fn check_related(fixture: &str, expected: &[(&str, &str, &str)]) {
+        let analysis = analysis_from_fixture(fixture);
+        let text = analysis.file_text(FileId(0)).unwrap();
+        let mut actual: Vec<(String, String, String)> = Vec::new();
+        for diagnostic in diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0)) {
+            let covered = &text[std::ops::Range::from(diagnostic.range)];
+            for related in diagnostic.related {
+                let related_text = analysis.file_text(related.range.file_id).unwrap();
+                actual.push((
+                    covered.to_string(),
+                    related_text[std::ops::Range::from(related.range.range)].to_string(),
+                    related.message,
+                ));
+            }
+        }
+        let expected: Vec<(String, String, String)> = expected
+            .iter()
+            .map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    /// Applies the fix labelled `label` to the first file.
+    pub(crate) fn check_fix(label: &str, fixture: &str, after: &str) {
+        check_fix_with_config(&DiagnosticsConfig::default(), label, fixture, after);
+    }
+
+    pub(crate) fn check_fix_with_config(
+        config: &DiagnosticsConfig,
+        label: &str,
+        fixture: &str,
+        after: &str,
+    ) {
+        let analysis = analysis_from_fixture(fixture);
+        let mut text = analysis.file_text(FileId(0)).unwrap().to_string();
+        let diagnostics = diagnostics(&analysis, config, FileId(0));
+        let fix = diagnostics
+            .iter()
+            .flat_map(|it| &it.fixes)
+            .find(|it| it.label == label)
+            .unwrap_or_else(|| {
+                let labels: Vec<_> = diagnostics
+                    .iter()
+                    .flat_map(|it| &it.fixes)
+                    .map(|it| &it.label)
+                    .collect();
+                panic!("fix `{label}` not found, available: {labels:?}")
+            });
+        fix.source_change
+            .get_source_edit(FileId(0))
+            .unwrap()
+            .apply(&mut text);
+        assert_eq!(text, after);
+    }
+
+    #[test]
+    fn disabled_and_severities() {
+        let analysis = analysis_from_fixture(
+            "
+//- /app/Service.cfc
+component {
+    function f() {
+        var x = 1;
+        y = 2;
+    }
+}
+",
+        );
+        let mut config = DiagnosticsConfig::default();
+        config.disabled.insert("unused-variable".to_string());
+        config
+            .severities
+            .insert("missing-var-scope".to_string(), Severity::Error);
+        let actual: Vec<(&str, Severity)> = diagnostics(&analysis, &config, FileId(0))
+            .iter()
+            .map(|it| (it.code.0, it.severity))
+            .collect();
+        assert_eq!(actual, [("missing-var-scope", Severity::Error)]);
+    }
+
+    #[test]
+    fn disabled_categories() {
+        let analysis = analysis_from_fixture(
+            r##"
+//- /app/index.cfm
+<cffunction name="f">
+    <cfset var x = 1>
+    <cfquery name="q">SELECT * FROM t WHERE id = #url.id#</cfquery>
+    <cfoutput>#url.name#</cfoutput>
+    <cfreturn q>
+</cffunction>
+"##,
+        );
+        let mut config = DiagnosticsConfig::default();
+        config
+            .disabled_categories
+            .extend([DiagnosticCategory::Security, DiagnosticCategory::Style]);
+        config
+            .severities
+            .insert("xss".to_string(), Severity::Information);
+        let actual: Vec<(&str, Severity)> = diagnostics(&analysis, &config, FileId(0))
+            .iter()
+            .map(|it| (it.code.0, it.severity))
+            .collect();
+        assert_eq!(
+            actual,
+            [
+                ("missing-var-scope", Severity::Warning),
+                ("xss", Severity::Information),
+            ]
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let analysis = analysis_from_fixture(
+            r##"
+//- /app/index.cfm
+<cfscript>
+function f(x) {
+    var y = isDefined("x");
+    switch (x) {
+        case 1: return 1;
+        case 1: return 2;
+    }
+}
+</cfscript>
+"##,
+        );
+        let actual: Vec<(&str, bool, bool)> =
+            diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0))
+                .iter()
+                .map(|it| (it.code.0, it.unused, it.deprecated))
+                .collect();
+        assert_eq!(
+            actual,
+            [
+                ("deprecated-function", false, true),
+                ("duplicate-case", true, false),
+                ("unused-variable", true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_only() {
+        let analysis = analysis_from_fixture(
+            r#"
+//- /app/index.cfm
+<cfif true>
+<cfscript>
+    x = ;
+</cfscript>
+"#,
+        );
+        let actual: Vec<&str> =
+            syntax_diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0))
+                .iter()
+                .map(|it| it.code.0)
+                .collect();
+        assert_eq!(actual, ["unclosed-tag", "syntax-error"]);
+    }
+
+    /// Keeps `docs/diagnostics.md`, which [`DiagnosticCode::url`] links to,
+    /// in sync with the `// Diagnostic:` comments, rewriting it if needed.
+    #[test]
+    fn docs_are_fresh() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/diagnostics");
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|it| it.unwrap().path())
+            .collect();
+        files.sort();
+        let mut docs: Vec<(String, String)> = Vec::new();
+        for file in files {
+            let text = std::fs::read_to_string(file).unwrap();
+            let mut lines = text.lines().peekable();
+            while let Some(line) = lines.next() {
+                let Some(code) = line.strip_prefix("// Diagnostic: ") else {
+                    continue;
+                };
+                let mut codes = vec![code.to_string()];
+                while let Some(code) = lines
+                    .peek()
+                    .and_then(|it| it.strip_prefix("// Diagnostic: "))
+                {
+                    codes.push(code.to_string());
+                    lines.next();
+                }
+                let mut doc = String::new();
+                while let Some(line) = lines.peek().and_then(|it| it.strip_prefix("//")) {
+                    doc.push_str(line.strip_prefix(' ').unwrap_or(line));
+                    doc.push('\n');
+                    lines.next();
+                }
+                for code in codes {
+                    docs.push((code, doc.trim().to_string()));
+                }
+            }
+        }
+        docs.sort();
+
+        let mut expected = String::from(
+            "# Diagnostics\n\n<!-- Generated from the comments of crates/ide/src/diagnostics by `cargo test -p ide`. Don't edit. -->\n",
+        );
+        for (code, doc) in &docs {
+            let category = match CATEGORIES.iter().find(|(it, _)| it == code) {
+                Some((_, DiagnosticCategory::Security)) => "security",
+                Some((_, DiagnosticCategory::Style)) => "style",
+                _ => "correctness",
+            };
+            expected.push_str(&format!("\n## {code}\n\nCategory: {category}\n\n{doc}\n"));
+        }
+        for (code, _) in CATEGORIES {
+            assert!(
+                docs.iter().any(|(it, _)| it == code),
+                "`{code}` isn't documented"
+            );
+        }
+
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../docs/diagnostics.md");
+        let actual = std::fs::read_to_string(&path).unwrap_or_default();
+        if actual != expected {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, expected).unwrap();
+            panic!("docs/diagnostics.md was stale and has been updated, rerun the tests");
+        }
+    }
+
+    #[test]
+    fn test_analysis_from_fixture() {
+        let analysis = analysis_from_fixture(
+            "
+//- /app/A.cfc
+component {}
+//- /app/B.cfc
+component extends=\"A\" {}
+",
+        );
+        assert_eq!(
+            analysis.file_text(FileId(0)).as_deref(),
+            Some("component {}")
+        );
+        assert_eq!(
+            analysis.paths[&FileId(1)],
+            VirtualFsPath::new_virtual_path("/app/B.cfc".to_string())
+        );
+    }
+}
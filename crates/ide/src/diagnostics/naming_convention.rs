@@ -0,0 +1,682 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextSize,
+};
+use virtual_fs::{AnchoredPathBuf, FileId};
+
+use crate::{
+    component::Component,
+    diagnostics::{Diagnostic, DiagnosticsContext, NamingCase, Severity},
+    resolve::{resolve_call, resolve_component},
+    var_scope::{functions, FunctionLike},
+    Assist, AssistId, AssistKind, FileSystemEdit, SourceChange, Symbol, TextEdit, TextEditBuilder,
+};
+
+// Diagnostic: naming-convention
+//
+// Reported, when enabled, on names not written in the case configured for
+// their kind: by default camelCase for functions and local variables,
+// PascalCase for components and UPPER_CASE for constants, the members of
+// `variables` set once to a literal in the body of a component. The fix
+// renames the name and its uses: the calls resolving to a function, the
+// file of a component and the references to it. Arguments are left alone,
+// as callers may pass them by name.
+pub(crate) fn naming_convention(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let Some(conventions) = ctx.config().naming else {
+        return Some(());
+    };
+    if let Some(case) = conventions.functions {
+        function_names(acc, ctx, case);
+    }
+    if let Some(case) = conventions.variables {
+        variable_names(acc, ctx, case);
+    }
+    if let Some(case) = conventions.components {
+        component_name(acc, ctx, case);
+    }
+    if let Some(case) = conventions.constants {
+        constant_names(acc, ctx, case);
+    }
+    Some(())
+}
+
+impl NamingCase {
+    fn label(self) -> &'static str {
+        match self {
+            NamingCase::Camel => "camelCase",
+            NamingCase::Pascal => "PascalCase",
+            NamingCase::UpperSnake => "UPPER_CASE",
+            NamingCase::Snake => "snake_case",
+        }
+    }
+
+    /// `name` written in this case, or `None` if it already is. Leading
+    /// underscores are kept.
+    fn rename(self, name: &str) -> Option<String> {
+        let rest = name.trim_start_matches('_');
+        let prefix = &name[..name.len() - rest.len()];
+        if rest.is_empty() || self.matches(rest) {
+            return None;
+        }
+        let words = words(rest);
+        let renamed = match self {
+            NamingCase::Camel => {
+                let (first, others) = words.split_first()?;
+                let others: String = others.iter().map(|it| capitalized(it)).collect();
+                format!("{}{others}", first.to_ascii_lowercase())
+            }
+            NamingCase::Pascal => words.iter().map(|it| capitalized(it)).collect(),
+            NamingCase::UpperSnake => words.join("_").to_ascii_uppercase(),
+            NamingCase::Snake => words.join("_").to_ascii_lowercase(),
+        };
+        (renamed != rest).then(|| format!("{prefix}{renamed}"))
+    }
+
+    /// Returns `true` if `name` is written in this case. Acronyms are
+    /// allowed, as in `getURL`.
+    fn matches(self, name: &str) -> bool {
+        let first = name.chars().next();
+        match self {
+            NamingCase::Camel => {
+                first.is_some_and(|it| it.is_ascii_lowercase()) && !name.contains('_')
+            }
+            NamingCase::Pascal => {
+                first.is_some_and(|it| it.is_ascii_uppercase()) && !name.contains('_')
+            }
+            NamingCase::UpperSnake => !name.chars().any(|it| it.is_ascii_lowercase()),
+            NamingCase::Snake => !name.chars().any(|it| it.is_ascii_uppercase()),
+        }
+    }
+}
+
+/// The words of a name, split at underscores and where the case changes:
+/// `getURLParser` is `get`, `URL` and `Parser`.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut res = Vec::new();
+    let mut word = String::new();
+    for (idx, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                res.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let prev = idx.checked_sub(1).map(|it| chars[it]);
+        let next = chars.get(idx + 1);
+        let starts_word = c.is_ascii_uppercase()
+            && !word.is_empty()
+            && (prev.is_some_and(|it| it.is_ascii_lowercase() || it.is_ascii_digit())
+                || next.is_some_and(|it| it.is_ascii_lowercase()));
+        if starts_word {
+            res.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        res.push(word);
+    }
+    res
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => format!(
+            "{}{}",
+            first.to_ascii_uppercase(),
+            chars.as_str().to_ascii_lowercase()
+        ),
+        None => String::new(),
+    }
+}
+
+/// Reports the `kind` of name `name`, at `range`, which should be `renamed`.
+fn diagnostic(
+    kind: &str,
+    name: &str,
+    renamed: &str,
+    case: NamingCase,
+    range: TextRange,
+) -> Diagnostic {
+    Diagnostic::new(
+        "naming-convention",
+        Severity::Information,
+        format!("{kind} `{name}` should be {}, as `{renamed}`", case.label()),
+        range,
+    )
+}
+
+/// A fix renaming `uses` to `renamed`, along with `source_change`.
+fn rename_fix(
+    renamed: &str,
+    target: TextRange,
+    uses: Vec<(FileId, TextRange)>,
+    mut source_change: SourceChange,
+) -> Assist {
+    let mut builders: FxHashMap<FileId, TextEditBuilder> = FxHashMap::default();
+    for (file_id, range) in uses {
+        builders
+            .entry(file_id)
+            .or_insert_with(TextEdit::builder)
+            .replace(range, renamed.to_string());
+    }
+    for (file_id, builder) in builders {
+        source_change.insert_source_edit(file_id, builder.finish());
+    }
+    Assist {
+        id: AssistId("rename_to_convention", AssistKind::QuickFix),
+        label: format!("Rename to `{renamed}`"),
+        target,
+        source_change,
+    }
+}
+
+fn function_names(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext, case: NamingCase) {
+    let named = functions(ctx.root())
+        .filter(|it| matches!(it, FunctionLike::Decl(_) | FunctionLike::Tag(_)));
+    for func in named {
+        let (Some(name), Some(range)) = (func.name(), func.name_range()) else {
+            continue;
+        };
+        let Some(renamed) = case.rename(&name) else {
+            continue;
+        };
+        let mut uses = vec![(ctx.file_id(), range)];
+        uses.extend(calls_of(ctx, &name, range));
+        acc.push(
+            diagnostic("Function", &name, &renamed, case, range).with_fix(rename_fix(
+                &renamed,
+                range,
+                uses,
+                SourceChange::default(),
+            )),
+        );
+    }
+}
+
+/// The names of the calls, in any file of the same root, to the function
+/// `name` declared at `range` in the file of `ctx`.
+fn calls_of(ctx: &DiagnosticsContext, name: &str, range: TextRange) -> Vec<(FileId, TextRange)> {
+    let analysis = ctx.analysis();
+    let mut file_ids: Vec<FileId> = analysis
+        .files
+        .keys()
+        .copied()
+        .filter(|&it| analysis.same_root(it, ctx.file_id()))
+        .collect();
+    file_ids.sort_by_key(|it| it.0);
+    let mut res = Vec::new();
+    for file_id in file_ids {
+        analysis.unwind_if_cancelled();
+        let Some(parse) = analysis.parse(file_id) else {
+            continue;
+        };
+        for call in parse
+            .syntax_node()
+            .descendants()
+            .filter_map(ast::CallExpr::cast)
+        {
+            if !call
+                .callee_name()
+                .is_some_and(|it| it.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+            let Some((decl_file, func)) = resolve_call(analysis, file_id, &call) else {
+                continue;
+            };
+            if decl_file != ctx.file_id() || func.name_range() != Some(range) {
+                continue;
+            }
+            let callee = match call.callee() {
+                Some(ast::Expr::NameRef(it)) => Some(it.syntax().text_range()),
+                Some(ast::Expr::FieldExpr(it)) => it.field().map(|it| it.text_range()),
+                _ => None,
+            };
+            res.extend(callee.map(|it| (file_id, it)));
+        }
+    }
+    res
+}
+
+fn variable_names(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext, case: NamingCase) {
+    for func in functions(ctx.root()) {
+        let mut seen = FxHashSet::default();
+        for (name, range) in local_declarations(&func) {
+            let key = name.to_ascii_lowercase();
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            let Some(renamed) = case.rename(&name) else {
+                continue;
+            };
+            let uses = local_uses(&func, &key)
+                .into_iter()
+                .map(|it| (ctx.file_id(), it))
+                .collect();
+            acc.push(
+                diagnostic("Variable", &name, &renamed, case, range).with_fix(rename_fix(
+                    &renamed,
+                    range,
+                    uses,
+                    SourceChange::default(),
+                )),
+            );
+        }
+    }
+}
+
+/// The `var`s and `local.` assignments of `func`, in source order.
+fn local_declarations(func: &FunctionLike) -> Vec<(String, TextRange)> {
+    let mut res = Vec::new();
+    for node in func.own_nodes() {
+        if let Some(name) = ast::VarDecl::cast(node.clone()).and_then(|it| it.name()) {
+            res.push((name.text().to_string(), name.text_range()));
+        } else if let Some((name, range)) = local_field(&node) {
+            let is_assigned = node
+                .parent()
+                .and_then(ast::AssignExpr::cast)
+                .and_then(|it| it.lhs())
+                .is_some_and(|it| it.syntax() == &node);
+            if is_assigned {
+                res.push((name, range));
+            }
+        }
+    }
+    res
+}
+
+/// The name and range of the field of `local.x`.
+fn local_field(node: &SyntaxNode) -> Option<(String, TextRange)> {
+    let field = ast::FieldExpr::cast(node.clone())?;
+    let is_local = field
+        .receiver()
+        .is_some_and(|it| it.syntax().text().eq_ignore_ascii_case("local"));
+    if !is_local {
+        return None;
+    }
+    Some((field.field_name()?, field.field()?.text_range()))
+}
+
+/// Where the local variable `key` of `func` is declared and used, including
+/// in closures which don't declare their own.
+fn local_uses(func: &FunctionLike, key: &str) -> Vec<TextRange> {
+    let symbol = Symbol::new(key);
+    // The function a use refers to: the innermost declaring the name, or
+    // else the innermost one.
+    let owner = |node: &SyntaxNode| {
+        node.ancestors()
+            .filter_map(FunctionLike::cast)
+            .find(|it| it.declared_names().contains(&symbol))
+            .or_else(|| node.ancestors().find_map(FunctionLike::cast))
+    };
+    let mut res = Vec::new();
+    for node in func.syntax().descendants() {
+        let range = match node.kind() {
+            VAR_DECL => ast::VarDecl::cast(node.clone())
+                .and_then(|it| it.name())
+                .filter(|it| it.text().eq_ignore_ascii_case(key))
+                .map(|it| it.text_range()),
+            NAME_REF => ast::NameRef::cast(node.clone())
+                .filter(|it| it.text().eq_ignore_ascii_case(key))
+                .map(|it| it.syntax().text_range()),
+            FIELD_EXPR => local_field(&node)
+                .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, range)| range),
+            _ => None,
+        };
+        if range.is_some() && owner(&node).as_ref() == Some(func) {
+            res.extend(range);
+        }
+    }
+    res
+}
+
+fn component_name(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+    case: NamingCase,
+) -> Option<()> {
+    let component = ctx.root().descendants().find_map(Component::cast)?;
+    let path = ctx.analysis().paths.get(&ctx.file_id())?.to_string();
+    let stem = path.rsplit(['/', '\\']).next()?.strip_suffix(".cfc")?;
+    let renamed = case.rename(stem)?;
+    let range = match &component {
+        Component::Script(it) => it
+            .syntax()
+            .children_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+            .find(|it| it.kind() == KEYWORD)?
+            .text_range(),
+        Component::Tag(it) => it.name()?.text_range(),
+    };
+
+    let analysis = ctx.analysis();
+    let mut uses = Vec::new();
+    for reference in analysis.component_references().iter() {
+        if resolve_component(analysis, reference.file_id, reference.name.as_str())
+            != Some(ctx.file_id())
+        {
+            continue;
+        }
+        let last = reference.name.as_str().rsplit(['.', '/', '\\']).next()?;
+        if last.eq_ignore_ascii_case(stem) {
+            let end = reference.range.end();
+            uses.push((
+                reference.file_id,
+                TextRange::new(end - last.len() as TextSize, end),
+            ));
+        }
+    }
+    let mut source_change = SourceChange::default();
+    source_change.push_file_system_edit(FileSystemEdit::MoveFile {
+        src: ctx.file_id(),
+        dst: AnchoredPathBuf {
+            anchor: ctx.file_id(),
+            path: format!("{renamed}.cfc"),
+        },
+    });
+    acc.push(
+        diagnostic("Component", stem, &renamed, case, range).with_fix(rename_fix(
+            &renamed,
+            range,
+            uses,
+            source_change,
+        )),
+    );
+    Some(())
+}
+
+fn constant_names(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+    case: NamingCase,
+) -> Option<()> {
+    ctx.root().descendants().find_map(Component::cast)?;
+    // The assignments of every member of `variables`, and whether they set
+    // it to a literal in the body of the component.
+    let mut assignments: Vec<(String, TextRange, bool)> = Vec::new();
+    for assign in ctx.root().descendants().filter_map(ast::AssignExpr::cast) {
+        let Some((name, range)) = assign.lhs().and_then(|it| member(it.syntax())) else {
+            continue;
+        };
+        let in_body = assign
+            .syntax()
+            .ancestors()
+            .all(|it| FunctionLike::cast(it).is_none());
+        let is_literal = matches!(
+            assign.rhs(),
+            Some(ast::Expr::Literal(_) | ast::Expr::StringLit(_))
+        );
+        assignments.push((name, range, in_body && is_literal));
+    }
+    for (name, range, is_constant) in &assignments {
+        let key = name.to_ascii_lowercase();
+        let count = assignments
+            .iter()
+            .filter(|(it, _, _)| it.eq_ignore_ascii_case(&key))
+            .count();
+        if !is_constant || count > 1 {
+            continue;
+        }
+        let Some(renamed) = case.rename(name) else {
+            continue;
+        };
+        let uses = ctx
+            .root()
+            .descendants()
+            .filter_map(|it| member(&it))
+            .filter(|(it, _)| it.eq_ignore_ascii_case(&key))
+            .map(|(_, range)| (ctx.file_id(), range))
+            .collect();
+        acc.push(
+            diagnostic("Constant", name, &renamed, case, *range).with_fix(rename_fix(
+                &renamed,
+                *range,
+                uses,
+                SourceChange::default(),
+            )),
+        );
+    }
+    Some(())
+}
+
+/// The name and range of a member of `variables` named by `node`, either
+/// `variables.x` or an `x` which isn't local to the enclosing functions.
+fn member(node: &SyntaxNode) -> Option<(String, TextRange)> {
+    match node.kind() {
+        FIELD_EXPR => {
+            let field = ast::FieldExpr::cast(node.clone())?;
+            let is_variables = field
+                .receiver()
+                .is_some_and(|it| it.syntax().text().eq_ignore_ascii_case("variables"));
+            if !is_variables {
+                return None;
+            }
+            Some((field.field_name()?, field.field()?.text_range()))
+        }
+        NAME_REF => {
+            let name = ast::NameRef::cast(node.clone())?.text();
+            let key = Symbol::new(&name);
+            let is_local = node
+                .ancestors()
+                .filter_map(FunctionLike::cast)
+                .any(|it| it.declared_names().contains(&key));
+            let is_field = node.parent().is_some_and(|it| {
+                it.kind() == FIELD_EXPR && it.first_child().as_ref() != Some(node)
+            });
+            (!is_local && !is_field).then(|| (name, node.text_range()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::{AnchoredPathBuf, FileId};
+
+    use crate::{
+        diagnostics::{
+            diagnostics,
+            tests::{analysis_from_fixture, check_diagnostics_with_config, check_fix_with_config},
+            DiagnosticsConfig, NamingCase, NamingConventions,
+        },
+        FileSystemEdit,
+    };
+
+    fn naming() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            naming: Some(NamingConventions::default()),
+            ..DiagnosticsConfig::default()
+        }
+    }
+
+    #[test]
+    fn rename() {
+        let cases = [
+            (NamingCase::Camel, "get_user_by_id", Some("getUserById")),
+            (NamingCase::Camel, "GetURLParser", Some("getUrlParser")),
+            (NamingCase::Camel, "getURL", None),
+            (NamingCase::Pascal, "userService", Some("UserService")),
+            (NamingCase::Pascal, "user_service", Some("UserService")),
+            (NamingCase::UpperSnake, "maxRetries", Some("MAX_RETRIES")),
+            (NamingCase::UpperSnake, "MAX_RETRIES", None),
+            (NamingCase::Snake, "_userCount", Some("_user_count")),
+        ];
+        for (case, name, expected) in cases {
+            assert_eq!(case.rename(name).as_deref(), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn naming_conventions() {
+        check_diagnostics_with_config(
+            &naming(),
+            r#"
+//- /app/models/userService.cfc
+component {
+    variables.maxRetries = 3;
+    variables.cache = {};
+    function Get_User(id) {
+        var User_Row = queryExecute("SELECT 1", {id: id});
+        local.count = 1;
+        return User_Row.recordCount + local.count;
+    }
+    function init() {
+        variables.cache = {};
+        return this;
+    }
+}
+"#,
+            &[
+                (
+                    "Get_User",
+                    "Function `Get_User` should be camelCase, as `getUser`",
+                ),
+                (
+                    "User_Row",
+                    "Variable `User_Row` should be camelCase, as `userRow`",
+                ),
+                (
+                    "component",
+                    "Component `userService` should be PascalCase, as `UserService`",
+                ),
+                (
+                    "maxRetries",
+                    "Constant `maxRetries` should be UPPER_CASE, as `MAX_RETRIES`",
+                ),
+            ],
+        );
+        check_diagnostics_with_config(
+            &DiagnosticsConfig::default(),
+            r#"
+//- /app/models/userService.cfc
+component {
+    function Get_User() {}
+}
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn rename_function_and_calls() {
+        check_fix_with_config(
+            &naming(),
+            "Rename to `getUser`",
+            r#"
+//- /app/Service.cfc
+component {
+    function get_user(id) {
+        return id;
+    }
+    function list() {
+        return [get_user(1), this.GET_USER(2)];
+    }
+}
+"#,
+            r#"component {
+    function getUser(id) {
+        return id;
+    }
+    function list() {
+        return [getUser(1), this.getUser(2)];
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn rename_variable() {
+        check_fix_with_config(
+            &naming(),
+            "Rename to `userRow`",
+            r#"
+//- /app/Service.cfc
+component {
+    function f() {
+        var User_Row = 1;
+        local.User_Row += 1;
+        return () => User_Row + user_row;
+    }
+}
+"#,
+            r#"component {
+    function f() {
+        var userRow = 1;
+        local.userRow += 1;
+        return () => userRow + userRow;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn rename_constant() {
+        check_fix_with_config(
+            &naming(),
+            "Rename to `MAX_RETRIES`",
+            r#"
+//- /app/Service.cfc
+component {
+    maxRetries = 3;
+    function f(attempt) {
+        return attempt < variables.maxRetries && attempt < maxRetries;
+    }
+}
+"#,
+            r#"component {
+    MAX_RETRIES = 3;
+    function f(attempt) {
+        return attempt < variables.MAX_RETRIES && attempt < MAX_RETRIES;
+    }
+}"#,
+        );
+    }
+
+    #[test]
+    fn rename_component() {
+        let analysis = analysis_from_fixture(
+            r#"
+//- /app/models/userService.cfc
+component {}
+//- /app/handlers/Main.cfc
+component extends="models.userService" {
+    function f() {
+        return new models.UserService();
+    }
+}
+"#,
+        );
+        let fixes = &diagnostics(&analysis, &naming(), FileId(0))[0].fixes;
+        assert_eq!(fixes[0].label, "Rename to `UserService`");
+        assert_eq!(
+            fixes[0].source_change.file_system_edits,
+            [FileSystemEdit::MoveFile {
+                src: FileId(0),
+                dst: AnchoredPathBuf {
+                    anchor: FileId(0),
+                    path: "UserService.cfc".to_string(),
+                },
+            }]
+        );
+        let mut text = analysis.file_text(FileId(1)).unwrap().to_string();
+        fixes[0]
+            .source_change
+            .get_source_edit(FileId(1))
+            .unwrap()
+            .apply(&mut text);
+        assert_eq!(
+            text,
+            r#"component extends="models.UserService" {
+    function f() {
+        return new models.UserService();
+    }
+}"#
+        );
+    }
+}
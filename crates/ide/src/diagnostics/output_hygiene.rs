@@ -0,0 +1,263 @@
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    TextRange,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    TextEdit,
+};
+
+// Diagnostic: missing-output
+//
+// Reported, when output hygiene is enabled, on a `<cfcomponent>` or
+// `<cffunction>` without an `output` attribute, whose whitespace then ends
+// up in the page. Besides adding `output="false"` to the tag, a fix adds it
+// to every such tag of the file.
+
+// Diagnostic: stray-whitespace
+//
+// Reported, when output hygiene is enabled, on the header of a template:
+// the `<cfset>`s, `<cfparam>`s and the like it starts with, each writing a
+// line break before the page does. The fix wraps them in `<cfsilent>`.
+// Templates turning on `enableCFOutputOnly` are left alone.
+pub(crate) fn output_hygiene(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    if !ctx.config().output_hygiene {
+        return Some(());
+    }
+    missing_output(acc, ctx);
+    stray_whitespace(acc, ctx);
+    Some(())
+}
+
+fn missing_output(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) {
+    const OUTPUT: &str = " output=\"false\"";
+    let tags: Vec<(ast::Tag, TextRange)> = ctx
+        .root()
+        .descendants()
+        .filter_map(ast::Tag::cast)
+        .filter(|it| matches!(it.name_lower().as_str(), "cfcomponent" | "cffunction"))
+        .filter(|it| it.attribute("output").is_none())
+        .filter_map(|it| {
+            let range = it.name()?.text_range();
+            Some((it, range))
+        })
+        .collect();
+    let mut all = TextEdit::builder();
+    for (_, range) in &tags {
+        all.insert(range.end(), OUTPUT.to_string());
+    }
+    let all = all.finish();
+    for (tag, range) in &tags {
+        let mut diagnostic = Diagnostic::new(
+            "missing-output",
+            Severity::Information,
+            format!(
+                "`<{}>` has no `output` attribute, so its whitespace is written to the page",
+                tag.name_lower()
+            ),
+            *range,
+        )
+        .with_fix(ctx.fix(
+            "add_output_false",
+            "Add `output=\"false\"`",
+            *range,
+            TextEdit::insert(range.end(), OUTPUT.to_string()),
+        ));
+        if tags.len() > 1 {
+            diagnostic = diagnostic.with_fix(ctx.fix(
+                "add_output_false_everywhere",
+                "Add `output=\"false\"` to every component and function",
+                *range,
+                all.clone(),
+            ));
+        }
+        acc.push(diagnostic);
+    }
+}
+
+/// Tags writing nothing themselves, which make up the header of a template.
+const SILENT_TAGS: &[&str] = &[
+    "cfimport",
+    "cfparam",
+    "cfprocessingdirective",
+    "cfscript",
+    "cfset",
+    "cfsetting",
+];
+
+/// The fewest tags in a header worth reporting.
+const MIN_HEADER_TAGS: usize = 3;
+
+fn stray_whitespace(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let path = ctx.analysis().paths.get(&ctx.file_id())?.to_string();
+    if !path.to_ascii_lowercase().ends_with(".cfm") {
+        return None;
+    }
+    let mut header: Vec<ast::Tag> = Vec::new();
+    for element in ctx.root().children_with_tokens() {
+        match element {
+            NodeOrToken::Token(it) if it.kind() == CF_COMMENT => {}
+            NodeOrToken::Token(it) if it.kind() == TEXT && it.text().trim().is_empty() => {}
+            NodeOrToken::Node(node) => {
+                let Some(tag) = ast::Tag::cast(node) else {
+                    break;
+                };
+                let name = tag.name_lower();
+                if !SILENT_TAGS.contains(&name.as_str()) {
+                    break;
+                }
+                let output_only = tag
+                    .attribute("enablecfoutputonly")
+                    .and_then(|it| it.value())
+                    .is_some_and(|it| matches!(it.to_ascii_lowercase().as_str(), "true" | "yes"));
+                if name == "cfsetting" && output_only {
+                    return None;
+                }
+                header.push(tag);
+            }
+            NodeOrToken::Token(_) => break,
+        }
+    }
+    if header.len() < MIN_HEADER_TAGS {
+        return None;
+    }
+    let range = TextRange::new(
+        header.first()?.syntax().text_range().start(),
+        header.last()?.syntax().text_range().end(),
+    );
+    let mut edit = TextEdit::builder();
+    edit.insert(range.start(), "<cfsilent>\n".to_string());
+    edit.insert(range.end(), "\n</cfsilent>".to_string());
+    acc.push(
+        Diagnostic::new(
+            "stray-whitespace",
+            Severity::Information,
+            format!(
+                "The {} tags starting this template each write a line break to the page",
+                header.len()
+            ),
+            range,
+        )
+        .with_fix(ctx.fix(
+            "wrap_in_cfsilent",
+            "Wrap in `<cfsilent>`",
+            range,
+            edit.finish(),
+        )),
+    );
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{
+        tests::{check_diagnostics_with_config, check_fix_with_config},
+        DiagnosticsConfig,
+    };
+
+    fn hygiene() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            output_hygiene: true,
+            ..DiagnosticsConfig::default()
+        }
+    }
+
+    #[test]
+    fn missing_output() {
+        check_diagnostics_with_config(
+            &hygiene(),
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cffunction name="a" output="true"></cffunction>
+    <cffunction name="b"></cffunction>
+</cfcomponent>
+"#,
+            &[
+                (
+                    "cfcomponent",
+                    "`<cfcomponent>` has no `output` attribute, so its whitespace is written to \
+                     the page",
+                ),
+                (
+                    "cffunction",
+                    "`<cffunction>` has no `output` attribute, so its whitespace is written to \
+                     the page",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn add_output_false_everywhere() {
+        check_fix_with_config(
+            &hygiene(),
+            "Add `output=\"false\"` to every component and function",
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cffunction name="b"></cffunction>
+</cfcomponent>
+"#,
+            r#"<cfcomponent output="false">
+    <cffunction output="false" name="b"></cffunction>
+</cfcomponent>"#,
+        );
+    }
+
+    #[test]
+    fn stray_whitespace() {
+        check_diagnostics_with_config(
+            &hygiene(),
+            r#"
+//- /app/index.cfm
+<!--- Settings --->
+<cfparam name="page" default="1">
+<cfset title = "Home">
+
+<cfset items = []>
+<html><cfoutput>#title# #page# #arrayLen(items)#</cfoutput></html>
+"#,
+            &[(
+                "<cfparam name=\"page\" default=\"1\">\n<cfset title = \"Home\">\n\n<cfset items = []>",
+                "The 3 tags starting this template each write a line break to the page",
+            )],
+        );
+        check_diagnostics_with_config(
+            &hygiene(),
+            r#"
+//- /app/page.cfm
+<cfsetting enableCFOutputOnly="true">
+<cfparam name="page" default="1">
+<cfset title = "Home">
+<cfset items = []>
+<cfoutput>#title# #page# #arrayLen(items)#</cfoutput>
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn wrap_in_cfsilent() {
+        check_fix_with_config(
+            &hygiene(),
+            "Wrap in `<cfsilent>`",
+            r#"
+//- /app/index.cfm
+<cfset a = 1>
+<cfset b = 2>
+<cfset c = 3>
+<cfoutput>#a + b + c#</cfoutput>
+"#,
+            r#"<cfsilent>
+<cfset a = 1>
+<cfset b = 2>
+<cfset c = 3>
+</cfsilent>
+<cfoutput>#a + b + c#</cfoutput>"#,
+        );
+    }
+}
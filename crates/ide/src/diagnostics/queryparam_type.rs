@@ -0,0 +1,343 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    TextRange,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    sql::{is_query_execute, params_arg},
+    ty::{infer_expr, Ty},
+};
+
+// Diagnostic: queryparam-type-mismatch
+//
+// Reported on the value of a `<cfqueryparam>`, or of a `queryExecute()`
+// parameter, which can't be of its `cfsqltype`: a string variable bound as
+// `cf_sql_integer`, or a date bound as text, whose format then depends on
+// the server. Literal strings are fine when they convert.
+
+// Diagnostic: queryparam-list
+//
+// Reported on the value of a parameter with `list="true"` which is
+// obviously not a list, like a struct or a query. Arrays are fine.
+pub(crate) fn queryparam_type(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for node in ctx.root().descendants() {
+        let param = match node.kind() {
+            TAG => ast::Tag::cast(node)
+                .filter(|it| it.name_lower() == "cfqueryparam")
+                .and_then(|it| tag_param(&it)),
+            CALL_EXPR => {
+                let Some(call) = ast::CallExpr::cast(node).filter(is_query_execute) else {
+                    continue;
+                };
+                for param in call_params(&call) {
+                    check(acc, &param);
+                }
+                continue;
+            }
+            _ => None,
+        };
+        if let Some(param) = param {
+            check(acc, &param);
+        }
+    }
+    Some(())
+}
+
+/// A bound value with what is declared of it.
+struct Param {
+    value: Value,
+    range: TextRange,
+    /// The `cfsqltype` in lowercase, without its `cf_sql_` prefix.
+    sql_type: Option<String>,
+    list: bool,
+}
+
+enum Value {
+    /// A value written as is, e.g. `value="12"`.
+    Literal(String),
+    Expr(ast::Expr),
+    /// A string mixing text and interpolations.
+    Template(String),
+}
+
+impl Value {
+    fn ty(&self) -> Ty {
+        match self {
+            Value::Literal(_) | Value::Template(_) => Ty::String,
+            Value::Expr(it) => infer_expr(it),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Value::Literal(it) | Value::Template(it) => it.clone(),
+            Value::Expr(it) => it.syntax().text().to_string(),
+        }
+    }
+}
+
+fn tag_param(tag: &ast::Tag) -> Option<Param> {
+    let is_null = tag
+        .attribute("null")
+        .and_then(|it| it.value())
+        .is_some_and(|it| !is_false(&it));
+    if is_null {
+        return None;
+    }
+    let value = tag.attribute("value")?;
+    let element = value.value_element()?;
+    let range = element.text_range();
+    let value = match element {
+        SyntaxElement::Token(it) => Value::Literal(it.text().to_string()),
+        SyntaxElement::Node(node) => expr_value(ast::Expr::cast(node)?),
+    };
+    let sql_type = tag
+        .attribute("cfsqltype")
+        .or_else(|| tag.attribute("sqltype"))
+        .and_then(|it| it.value());
+    let list = tag
+        .attribute("list")
+        .and_then(|it| it.value())
+        .is_some_and(|it| !is_false(&it));
+    Some(Param {
+        value,
+        range,
+        sql_type: sql_type.map(|it| normalized_type(&it)),
+        list,
+    })
+}
+
+/// The parameters of `queryExecute()` given as `{value: ..., cfsqltype: ...}`
+/// structs, by name or by position.
+fn call_params(call: &ast::CallExpr) -> Vec<Param> {
+    let values: Vec<ast::Expr> = match params_arg(call).and_then(|it| it.expr()) {
+        Some(ast::Expr::StructLit(it)) => it.fields().filter_map(|it| it.value()).collect(),
+        Some(ast::Expr::ArrayLit(it)) => it.elements().collect(),
+        _ => return Vec::new(),
+    };
+    let mut res = Vec::new();
+    for value in values {
+        let ast::Expr::StructLit(param) = value else {
+            continue;
+        };
+        let field = |name: &str| {
+            param
+                .fields()
+                .find(|it| it.key().is_some_and(|it| it.eq_ignore_ascii_case(name)))
+                .and_then(|it| it.value())
+        };
+        let static_value = |expr: Option<ast::Expr>| match expr?.unwrapped() {
+            ast::Expr::StringLit(it) => it.value(),
+            ast::Expr::Literal(it) => Some(it.syntax().text().to_string()),
+            _ => None,
+        };
+        if static_value(field("null")).is_some_and(|it| !is_false(&it)) {
+            continue;
+        }
+        let Some(value) = field("value") else {
+            continue;
+        };
+        let sql_type = static_value(field("cfsqltype").or_else(|| field("sqltype")));
+        res.push(Param {
+            range: value.syntax().text_range(),
+            value: expr_value(value),
+            sql_type: sql_type.map(|it| normalized_type(&it)),
+            list: static_value(field("list")).is_some_and(|it| !is_false(&it)),
+        });
+    }
+    res
+}
+
+fn expr_value(expr: ast::Expr) -> Value {
+    let ast::Expr::StringLit(string) = &expr else {
+        return Value::Expr(expr.unwrapped());
+    };
+    if let Some(value) = string.value() {
+        return Value::Literal(value);
+    }
+    // `"#x#"` is the value of `x`.
+    let mut parts = string
+        .syntax()
+        .children_with_tokens()
+        .filter(|it| it.kind() != QUOTE);
+    match (parts.next(), parts.next()) {
+        (Some(SyntaxElement::Node(only)), None) => {
+            match ast::HashExpr::cast(only).and_then(|it| it.expr()) {
+                Some(inner) => Value::Expr(inner.unwrapped()),
+                None => Value::Template(string.syntax().text().to_string()),
+            }
+        }
+        _ => Value::Template(string.syntax().text().to_string()),
+    }
+}
+
+fn check(acc: &mut Vec<Diagnostic>, param: &Param) {
+    let ty = param.value.ty();
+    let text = param.value.text();
+    if param.list {
+        if is_complex(&ty) || matches!(ty, Ty::Boolean | Ty::Date) {
+            acc.push(Diagnostic::new(
+                "queryparam-list",
+                Severity::Warning,
+                format!("`{text}` is {}, not a list", describe(&ty)),
+                param.range,
+            ));
+        }
+        return;
+    }
+    let Some(sql_type) = param.sql_type.as_deref() else {
+        return;
+    };
+    let mismatch = match sql_type {
+        "bigint" | "decimal" | "double" | "float" | "integer" | "money" | "money4" | "numeric"
+        | "real" | "smallint" | "tinyint" => match (&param.value, &ty) {
+            (Value::Literal(it), _) => it.trim().parse::<f64>().is_err(),
+            (_, Ty::Any | Ty::Numeric | Ty::Boolean) => false,
+            _ => true,
+        },
+        "bit" => match (&param.value, &ty) {
+            (Value::Literal(it), _) => !is_boolean(it),
+            (_, Ty::Any | Ty::Numeric | Ty::Boolean) => false,
+            _ => true,
+        },
+        "date" | "time" | "timestamp" => is_complex(&ty) || matches!(ty, Ty::Array | Ty::Boolean),
+        "char" | "varchar" | "nchar" | "nvarchar" | "longvarchar" | "longnvarchar" | "clob"
+        | "nclob" => {
+            if ty == Ty::Date {
+                acc.push(Diagnostic::new(
+                    "queryparam-type-mismatch",
+                    Severity::Warning,
+                    format!(
+                        "`{text}` is a date, whose text as `cf_sql_{sql_type}` depends on the \
+                         server; format it or bind it as `cf_sql_timestamp`"
+                    ),
+                    param.range,
+                ));
+                return;
+            }
+            is_complex(&ty) || ty == Ty::Array
+        }
+        _ => false,
+    };
+    if mismatch {
+        acc.push(Diagnostic::new(
+            "queryparam-type-mismatch",
+            Severity::Warning,
+            format!(
+                "`{text}` is {}, but is bound as `cf_sql_{sql_type}`",
+                describe(&ty)
+            ),
+            param.range,
+        ));
+    }
+}
+
+/// Values no column type takes.
+fn is_complex(ty: &Ty) -> bool {
+    matches!(
+        ty,
+        Ty::Struct | Ty::Query | Ty::Function | Ty::Component(_) | Ty::Xml
+    )
+}
+
+fn describe(ty: &Ty) -> String {
+    match ty {
+        Ty::Array => "an array".to_string(),
+        Ty::Component(name) => format!("a `{name}`"),
+        Ty::Numeric => "a number".to_string(),
+        ty => format!("a {ty}"),
+    }
+}
+
+fn normalized_type(sql_type: &str) -> String {
+    let lower = sql_type.trim().to_ascii_lowercase();
+    lower.strip_prefix("cf_sql_").unwrap_or(&lower).to_string()
+}
+
+fn is_false(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "false" | "no" | "0"
+    )
+}
+
+fn is_boolean(value: &str) -> bool {
+    let lower = value.trim().to_ascii_lowercase();
+    matches!(lower.as_str(), "true" | "false" | "yes" | "no") || lower.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn tag_params() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfparam name="url.name" type="string" default="">
+<cfset filters = {active: true}>
+<cfquery name="q" datasource="app">
+    SELECT * FROM users
+    WHERE id = <cfqueryparam value="#url.name#" cfsqltype="cf_sql_integer">
+    AND age = <cfqueryparam value="12" cfsqltype="integer">
+    AND score = <cfqueryparam value="#url.name & 1#" cfsqltype="cf_sql_double">
+    AND level = <cfqueryparam value="high" cfsqltype="cf_sql_integer">
+    AND created = <cfqueryparam value="#now()#" cfsqltype="cf_sql_varchar">
+    AND day = <cfqueryparam value="#dateFormat(now())#" cfsqltype="cf_sql_varchar">
+    AND deleted = <cfqueryparam value="#url.name#" cfsqltype="cf_sql_bit" null="true">
+    AND role IN (<cfqueryparam value="#filters#" cfsqltype="cf_sql_varchar" list="true">)
+    AND team IN (<cfqueryparam value="#url.name#" cfsqltype="cf_sql_integer" list="true">)
+</cfquery>
+"##,
+            &[
+                (
+                    "\"#url.name#\"",
+                    "`url.name` is a string, but is bound as `cf_sql_integer`",
+                ),
+                (
+                    "\"#url.name & 1#\"",
+                    "`url.name & 1` is a string, but is bound as `cf_sql_double`",
+                ),
+                (
+                    "\"high\"",
+                    "`high` is a string, but is bound as `cf_sql_integer`",
+                ),
+                (
+                    "\"#now()#\"",
+                    "`now()` is a date, whose text as `cf_sql_varchar` depends on the server; \
+                     format it or bind it as `cf_sql_timestamp`",
+                ),
+                ("\"#filters#\"", "`filters` is a struct, not a list"),
+            ],
+        );
+    }
+
+    #[test]
+    fn query_execute_params() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    function find(string name, numeric id) {
+        return queryExecute(
+            "SELECT * FROM users WHERE id = :id AND name = :name AND since < :since",
+            {
+                id: {value: name, cfsqltype: "cf_sql_integer"},
+                name: {value: arguments.id, cfsqltype: "cf_sql_varchar"},
+                since: {value: now(), cfsqltype: "cf_sql_timestamp"}
+            }
+        );
+    }
+}
+"#,
+            &[(
+                "name",
+                "`name` is a string, but is bound as `cf_sql_integer`",
+            )],
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    TextRange,
+};
+
+use crate::{
+    component::{Component, Signature},
+    diagnostics::{unreachable_code::exits, Diagnostic, DiagnosticsContext, Severity},
+    resolve::static_text,
+    ty::{infer_expr, Ty},
+    var_scope::{functions, FunctionLike},
+};
+
+// Diagnostic: return-type-mismatch
+//
+// Reported on a `return` or `<cfreturn>` whose value can't be what the
+// function declares to return, e.g. a string literal from a function
+// returning `struct`, or no value from one returning anything but `void`.
+
+// Diagnostic: missing-return
+//
+// Reported on the name of a function declaring a return type other than
+// `any` or `void` which never returns, throws or aborts.
+pub(crate) fn return_type(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for func in functions(ctx.root()) {
+        let Some(signature) = Signature::of(&func) else {
+            continue;
+        };
+        let Some(declared) = signature.return_type.as_deref().map(declared_type) else {
+            continue;
+        };
+        if matches!(declared, Ty::Any | Ty::Void) || !has_body(&func) {
+            continue;
+        }
+        let declared_range = declared_range(&func);
+        let with_declaration = |diagnostic: Diagnostic| match declared_range {
+            Some(range) => diagnostic.with_related(ctx.frange(range), "Return type declared here"),
+            None => diagnostic,
+        };
+        let name = &signature.name;
+        let nodes = func.own_nodes();
+        let mut returns = false;
+        for node in &nodes {
+            let (range, value) = match node.kind() {
+                RETURN_STMT => {
+                    let Some(stmt) = ast::ReturnStmt::cast(node.clone()) else {
+                        continue;
+                    };
+                    (node.text_range(), stmt.expr())
+                }
+                TAG => {
+                    let Some(tag) =
+                        ast::Tag::cast(node.clone()).filter(|it| it.name_lower() == "cfreturn")
+                    else {
+                        continue;
+                    };
+                    let range = tag
+                        .open()
+                        .map_or(node.text_range(), |it| it.syntax().text_range());
+                    (range, tag.expr())
+                }
+                _ => continue,
+            };
+            returns = true;
+            let message = match value {
+                None => {
+                    format!("`{name}` returns nothing here, but is declared to return `{declared}`")
+                }
+                Some(expr) => match returned_type(&declared, &expr) {
+                    Some(actual) => format!(
+                        "`{name}` returns {actual} here, but is declared to return `{declared}`"
+                    ),
+                    None => continue,
+                },
+            };
+            acc.push(with_declaration(Diagnostic::new(
+                "return-type-mismatch",
+                Severity::Error,
+                message,
+                range,
+            )));
+        }
+        let exits = returns || nodes.iter().any(|it| exits(&NodeOrToken::Node(it.clone())));
+        if let (false, Some(range)) = (exits, func.name_range()) {
+            acc.push(with_declaration(Diagnostic::new(
+                "missing-return",
+                Severity::Error,
+                format!("`{name}` is declared to return `{declared}`, but never returns a value"),
+                range,
+            )));
+        }
+    }
+    Some(())
+}
+
+/// The type of a `returntype`, typed arrays like `string[]` included.
+fn declared_type(name: &str) -> Ty {
+    match name.trim().ends_with("[]") {
+        true => Ty::Array,
+        false => Ty::from_name(name),
+    }
+}
+
+/// Functions of interfaces, and abstract ones, only declare a signature.
+fn has_body(func: &FunctionLike) -> bool {
+    let in_interface = Component::enclosing(func.syntax()).is_some_and(|it| it.is_interface());
+    let has_block = match func {
+        FunctionLike::Decl(it) => it.body().is_some(),
+        _ => true,
+    };
+    !in_interface && has_block
+}
+
+/// The range of the written return type, for the related information.
+fn declared_range(func: &FunctionLike) -> Option<TextRange> {
+    match func {
+        FunctionLike::Decl(it) => Some(it.return_type()?.syntax().text_range()),
+        FunctionLike::Tag(it) => {
+            Some(static_text(it.attribute("returntype")?.value_element()?)?.text_range())
+        }
+        FunctionLike::Closure(_) | FunctionLike::Arrow(_) => None,
+    }
+}
+
+/// The type of `expr` as it reads in a message, if it provably isn't
+/// `declared`. Strings, which CFML converts when it can, only conflict with
+/// numbers and booleans when they are literals which don't convert.
+fn returned_type(declared: &Ty, expr: &ast::Expr) -> Option<String> {
+    let actual = infer_expr(expr);
+    let compatible = match (declared, &actual) {
+        (Ty::Any | Ty::Void | Ty::Binary | Ty::Xml, _) | (_, Ty::Any) => true,
+        (declared, actual) if declared == actual => true,
+        (Ty::String, Ty::Numeric | Ty::Boolean | Ty::Date) => true,
+        (Ty::Numeric, Ty::Boolean) | (Ty::Boolean, Ty::Numeric) => true,
+        (Ty::Numeric | Ty::Boolean | Ty::Date, Ty::String) => {
+            return literal_string(expr)
+                .filter(|it| !converts(declared, it))
+                .map(|_| format!("`{}`", expr.syntax().text()));
+        }
+        (Ty::Date, Ty::Numeric) => true,
+        (Ty::Struct | Ty::Component(_), Ty::Component(_)) => true,
+        _ => false,
+    };
+    (!compatible).then(|| match actual {
+        Ty::Array => "an array".to_string(),
+        Ty::Struct => "a struct".to_string(),
+        Ty::Query => "a query".to_string(),
+        Ty::Function => "a function".to_string(),
+        Ty::Component(name) => format!("a `{name}`"),
+        _ => format!("`{}`", expr.syntax().text()),
+    })
+}
+
+fn literal_string(expr: &ast::Expr) -> Option<String> {
+    match expr.clone().unwrapped() {
+        ast::Expr::StringLit(it) => it.value(),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the engine converts the string `value` to `ty`.
+fn converts(ty: &Ty, value: &str) -> bool {
+    let value = value.trim();
+    let is_number = value.parse::<f64>().is_ok();
+    match ty {
+        Ty::Numeric => is_number,
+        Ty::Boolean => {
+            is_number
+                || ["true", "false", "yes", "no"]
+                    .iter()
+                    .any(|it| value.eq_ignore_ascii_case(it))
+        }
+        // Dates take too many formats to tell.
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn mismatched_returns() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    struct function find(id) {
+        if (id) {
+            return "none";
+        }
+        if (!id) {
+            return;
+        }
+        return {id: id};
+    }
+    numeric function count(a) {
+        if (a) {
+            return "12";
+        }
+        return "many";
+    }
+    string[] function names() {
+        return ["a"];
+    }
+    Service function self() {
+        return this;
+    }
+    boolean function check() {
+        throw("Not implemented");
+    }
+    string function label() {
+        writeOutput("label");
+    }
+}
+"#,
+            &[
+                (
+                    "return \"none\";",
+                    "`find` returns `\"none\"` here, but is declared to return `struct`",
+                ),
+                (
+                    "return;",
+                    "`find` returns nothing here, but is declared to return `struct`",
+                ),
+                (
+                    "return \"many\";",
+                    "`count` returns `\"many\"` here, but is declared to return `numeric`",
+                ),
+                (
+                    "label",
+                    "`label` is declared to return `string`, but never returns a value",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn mismatched_cfreturn() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cffunction name="list" returntype="array">
+        <cfreturn {}>
+    </cffunction>
+    <cffunction name="get" returntype="query">
+        <cfset var q = queryNew("id")>
+        <cfreturn q>
+    </cffunction>
+</cfcomponent>
+"#,
+            &[(
+                "<cfreturn {}>",
+                "`list` returns a struct here, but is declared to return `array`",
+            )],
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken, SyntaxElement, TextRange,
+};
+
+use crate::{
+    diagnostics::{xss::tainted, Diagnostic, DiagnosticsContext, Severity},
+    resolve::static_text,
+    sql::is_safe_value,
+    tag_schema::BuiltinTag,
+    ty::{infer_expr, Ty},
+};
+
+// Diagnostic: code-injection
+//
+// Reported on values from `form`, `url`, `cgi` and `cookie` passed to
+// `evaluate()`, `precisionEvaluate()` or `iif()`, which run strings as code:
+// a crafted request runs whatever it sends.
+
+// Diagnostic: unrestricted-upload
+//
+// Reported on a `<cffile action="upload">`, `fileUpload()` or
+// `fileUploadAll()` which doesn't restrict the types of files with `accept`,
+// and on `strict="false"`, which trusts the extension of a file instead of
+// checking its content. An uploaded script may then be run by the server.
+
+// Diagnostic: command-injection
+//
+// Reported on values interpolated into the program or the arguments of a
+// `<cfexecute>`, unless they are provably safe, like literals. A value with
+// spaces or quotes then passes other arguments than it seems to, and one
+// from the request is an error. Arguments passed as an array are fine.
+
+// Diagnostic: dynamic-include
+//
+// Reported on a `<cfinclude>` or `include` whose template comes from `form`,
+// `url`, `cgi` or `cookie`, which lets a crafted request run any template of
+// the server, or read other files.
+pub(crate) fn security_audit(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for node in ctx.root().descendants() {
+        if let Some(call) = ast::CallExpr::cast(node.clone()) {
+            let Some(name) = call.callee_name() else {
+                continue;
+            };
+            match name.to_ascii_lowercase().as_str() {
+                "evaluate" | "precisionevaluate" | "iif" => code_injection(acc, &call, &name),
+                "fileupload" => upload_call(acc, &call, &name, 2),
+                "fileuploadall" => upload_call(acc, &call, &name, 1),
+                _ => {}
+            }
+        } else if let Some(tag) = BuiltinTag::cast(node) {
+            match tag.name.as_str() {
+                "cfexecute" => command_injection(acc, &tag),
+                "cffile" => upload_tag(acc, &tag),
+                "cfinclude" => dynamic_include(acc, &tag),
+                _ => {}
+            }
+        }
+    }
+    Some(())
+}
+
+fn code_injection(acc: &mut Vec<Diagnostic>, call: &ast::CallExpr, name: &str) {
+    let advice = if name.eq_ignore_ascii_case("iif") {
+        "use a ternary `a ? b : c` instead"
+    } else {
+        "access the variable with `[]` instead"
+    };
+    let args = call.arg_list().into_iter().flat_map(|it| it.args());
+    for expr in args.filter_map(|it| it.expr()) {
+        if let Some(source) = tainted(&expr) {
+            acc.push(Diagnostic::new(
+                "code-injection",
+                Severity::Error,
+                format!("`{source}` comes from the request and is run as code by `{name}()`, allowing code injection: {advice}"),
+                expr.syntax().text_range(),
+            ));
+        }
+    }
+}
+
+/// Checks a call to `fileUpload()` or `fileUploadAll()`, which take the
+/// accepted types as their argument at `accept`.
+fn upload_call(acc: &mut Vec<Diagnostic>, call: &ast::CallExpr, name: &str, accept: usize) {
+    let Some(args) = call.arg_list() else {
+        return;
+    };
+    let args: Vec<ast::Arg> = args.args().collect();
+    let is_named = args.iter().any(|it| it.name().is_some());
+    let has_accept = if is_named {
+        args.iter().any(|it| {
+            it.name()
+                .is_some_and(|it| matches!(it.to_ascii_lowercase().as_str(), "accept" | "mimetype"))
+        })
+    } else {
+        args.len() > accept
+    };
+    if has_accept {
+        return;
+    }
+    let Some(callee) = call.callee() else {
+        return;
+    };
+    acc.push(Diagnostic::new(
+        "unrestricted-upload",
+        Severity::Warning,
+        format!("`{name}()` accepts files of any type: pass the allowed ones as `accept`"),
+        callee.syntax().text_range(),
+    ));
+}
+
+fn upload_tag(acc: &mut Vec<Diagnostic>, tag: &BuiltinTag) {
+    let Some(action) = tag.attribute("action") else {
+        return;
+    };
+    let is_upload = action
+        .value()
+        .is_some_and(|it| matches!(it.to_ascii_lowercase().as_str(), "upload" | "uploadall"));
+    if !is_upload {
+        return;
+    }
+    if tag.attribute("accept").is_none() {
+        acc.push(Diagnostic::new(
+            "unrestricted-upload",
+            Severity::Warning,
+            "Uploads accept files of any type: list the allowed ones in `accept`",
+            action.syntax().text_range(),
+        ));
+    }
+    if let Some(strict) = tag.attribute("strict") {
+        let is_off = strict
+            .value()
+            .is_some_and(|it| matches!(it.to_ascii_lowercase().as_str(), "false" | "no"));
+        if is_off {
+            acc.push(Diagnostic::new(
+                "unrestricted-upload",
+                Severity::Warning,
+                "`strict=\"false\"` checks uploads by their extension only: remove it to check their content",
+                strict.syntax().text_range(),
+            ));
+        }
+    }
+}
+
+fn command_injection(acc: &mut Vec<Diagnostic>, tag: &BuiltinTag) {
+    for attribute in ["name", "arguments"] {
+        let Some(element) = tag.attribute(attribute).and_then(|it| it.value_element()) else {
+            continue;
+        };
+        for expr in dynamic_values(element) {
+            if is_safe_value(&expr) || infer_expr(&expr) == Ty::Array {
+                continue;
+            }
+            let text = expr.syntax().text();
+            let diagnostic = match tainted(&expr) {
+                Some(source) => Diagnostic::new(
+                    "command-injection",
+                    Severity::Error,
+                    format!("`{source}` comes from the request and is passed to `<cfexecute>`, allowing command injection: pass the arguments as an array"),
+                    expr.syntax().text_range(),
+                ),
+                None => Diagnostic::new(
+                    "command-injection",
+                    Severity::Warning,
+                    format!("`{text}` is interpolated into the command line of `<cfexecute>`, where it may pass other arguments: pass the arguments as an array"),
+                    expr.syntax().text_range(),
+                ),
+            };
+            acc.push(diagnostic);
+        }
+    }
+}
+
+fn dynamic_include(acc: &mut Vec<Diagnostic>, tag: &BuiltinTag) {
+    let element = match tag.attribute("template") {
+        Some(attribute) => attribute.value_element(),
+        None => tag
+            .name_token
+            .parent()
+            .children()
+            .find(|it| ast::Expr::can_cast(it.kind()))
+            .map(SyntaxElement::from),
+    };
+    let Some(element) = element else {
+        return;
+    };
+    if static_text(element.clone()).is_some() {
+        return;
+    }
+    let template = match &element {
+        NodeOrToken::Node(it) => ast::Expr::cast(it.clone()),
+        NodeOrToken::Token(_) => None,
+    };
+    let Some(source) = template.as_ref().and_then(tainted) else {
+        return;
+    };
+    let range: TextRange = element.text_range();
+    acc.push(Diagnostic::new(
+        "dynamic-include",
+        Severity::Error,
+        format!("`{source}` comes from the request and picks the included template, allowing any file to be run: map it to a fixed list of templates"),
+        range,
+    ));
+}
+
+/// The expressions making up the value `element` of an attribute: those
+/// interpolated in a string, or the value itself.
+fn dynamic_values(element: SyntaxElement) -> Vec<ast::Expr> {
+    let NodeOrToken::Node(node) = element else {
+        return Vec::new();
+    };
+    match ast::Expr::cast(node) {
+        Some(ast::Expr::StringLit(it)) => it
+            .syntax()
+            .children()
+            .filter_map(ast::HashExpr::cast)
+            .filter_map(|it| it.expr())
+            .collect(),
+        Some(it) => vec![it],
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{tests::check_diagnostics_with_config, DiagnosticsConfig};
+
+    fn security() -> DiagnosticsConfig {
+        let mut config = DiagnosticsConfig::default();
+        config.disabled.extend([
+            "deprecated-function".to_string(),
+            "undefined-variable".to_string(),
+        ]);
+        config
+    }
+
+    #[test]
+    fn code_injection() {
+        check_diagnostics_with_config(
+            &security(),
+            r##"
+//- /app/index.cfm
+<cfscript>
+    a = evaluate("form.#url.field#");
+    b = iif(len(cookie.pref), cookie.pref, de("none"));
+    c = evaluate("variables.total");
+</cfscript>
+"##,
+            &[
+                (
+                    r#""form.#url.field#""#,
+                    "`url.field` comes from the request and is run as code by `evaluate()`, allowing code injection: access the variable with `[]` instead",
+                ),
+                (
+                    "cookie.pref",
+                    "`cookie.pref` comes from the request and is run as code by `iif()`, allowing code injection: use a ternary `a ? b : c` instead",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn unrestricted_uploads() {
+        check_diagnostics_with_config(
+            &security(),
+            r##"
+//- /app/upload.cfm
+<cffile action="upload" fileField="photo" destination="/tmp">
+<cffile action="upload" fileField="photo" destination="/tmp" accept="image/png" strict="false">
+<cffile action="read" file="/tmp/a.txt" variable="text">
+<cfscript>
+    fileUpload("/tmp", "photo");
+    fileUpload("/tmp", "photo", "image/png");
+    fileUploadAll(destination = "/tmp", accept = "image/png");
+</cfscript>
+"##,
+            &[
+                (
+                    r#"action="upload""#,
+                    "Uploads accept files of any type: list the allowed ones in `accept`",
+                ),
+                (
+                    r#"strict="false""#,
+                    "`strict=\"false\"` checks uploads by their extension only: remove it to check their content",
+                ),
+                (
+                    "fileUpload",
+                    "`fileUpload()` accepts files of any type: pass the allowed ones as `accept`",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn command_injection() {
+        check_diagnostics_with_config(
+            &security(),
+            r##"
+//- /app/run.cfm
+<cfset dir = "/var/#session.user#">
+<cfexecute name="/bin/ls" arguments="-l #dir#" timeout="5"></cfexecute>
+<cfexecute name="/bin/ls" arguments="#url.flags#" timeout="5"></cfexecute>
+<cfexecute name="/bin/ls" arguments="#['-l', dir]#" timeout="5"></cfexecute>
+<cfexecute name="/bin/ls" arguments="-l #"/tmp"#" timeout="5"></cfexecute>
+"##,
+            &[
+                (
+                    "dir",
+                    "`dir` is interpolated into the command line of `<cfexecute>`, where it may pass other arguments: pass the arguments as an array",
+                ),
+                (
+                    "url.flags",
+                    "`url.flags` comes from the request and is passed to `<cfexecute>`, allowing command injection: pass the arguments as an array",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn dynamic_includes() {
+        check_diagnostics_with_config(
+            &security(),
+            r##"
+//- /app/index.cfm
+<cfinclude template="pages/#url.page#.cfm">
+<cfinclude template="#view#">
+<cfscript>
+    include form.template;
+</cfscript>
+"##,
+            &[
+                (
+                    r#""pages/#url.page#.cfm""#,
+                    "`url.page` comes from the request and picks the included template, allowing any file to be run: map it to a fixed list of templates",
+                ),
+                (
+                    "form.template",
+                    "`form.template` comes from the request and picks the included template, allowing any file to be run: map it to a fixed list of templates",
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use rustc_hash::FxHashMap;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+use virtual_fs::FileId;
+
+use crate::{
+    change_signature::old_params,
+    component::Component,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::{static_text, walk_extends},
+    var_scope::{functions, SCOPES},
+    FileRange,
+};
+
+// Diagnostic: shadowed-variable
+//
+// Reported on a `var` or `local.` declaration, or a loop variable, named
+// like an argument of its function, a member of the component's `variables`
+// scope or a built-in scope like `form`. An unscoped read of the name then
+// finds something else than it seems to.
+pub(crate) fn shadowed_variable(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let members = members(ctx);
+    for func in functions(ctx.root()) {
+        let params: FxHashMap<String, TextRange> = old_params(&func)
+            .into_iter()
+            .map(|it| (it.name.to_ascii_lowercase(), it.name_range))
+            .collect();
+        for (name, range) in declarations(&func.own_nodes()) {
+            let key = name.to_ascii_lowercase();
+            let diagnostic = if SCOPES.contains(&key.as_str()) {
+                Diagnostic::new(
+                    "shadowed-variable",
+                    Severity::Warning,
+                    format!("`{name}` shadows the `{key}` scope"),
+                    range,
+                )
+            } else if let Some(param) = params.get(&key) {
+                Diagnostic::new(
+                    "shadowed-variable",
+                    Severity::Warning,
+                    format!("`{name}` shadows the argument `{name}`"),
+                    range,
+                )
+                .with_related(ctx.frange(*param), "The argument is declared here")
+            } else if let Some(member) = members.get(&key) {
+                Diagnostic::new(
+                    "shadowed-variable",
+                    Severity::Warning,
+                    format!("`{name}` shadows `variables.{name}`"),
+                    range,
+                )
+                .with_related(*member, format!("`variables.{name}` is declared here"))
+            } else {
+                continue;
+            };
+            acc.push(diagnostic);
+        }
+    }
+    Some(())
+}
+
+/// The names a function declares among `nodes`, its own: `var`s, `local.`
+/// assignments and the variables of `<cfloop>`s.
+fn declarations(nodes: &[SyntaxNode]) -> Vec<(String, TextRange)> {
+    let mut res = Vec::new();
+    for node in nodes {
+        match node.kind() {
+            VAR_DECL => {
+                if let Some(name) = ast::VarDecl::cast(node.clone()).and_then(|it| it.name()) {
+                    res.push((name.text().to_string(), name.text_range()));
+                }
+            }
+            ASSIGN_EXPR => {
+                let Some(ast::Expr::FieldExpr(lhs)) =
+                    ast::AssignExpr::cast(node.clone()).and_then(|it| it.lhs())
+                else {
+                    continue;
+                };
+                let is_local = lhs
+                    .receiver()
+                    .is_some_and(|it| it.syntax().text().eq_ignore_ascii_case("local"));
+                if let (true, Some(name), Some(field)) = (is_local, lhs.field_name(), lhs.field()) {
+                    res.push((name, field.text_range()));
+                }
+            }
+            TAG => {
+                let Some(tag) =
+                    ast::Tag::cast(node.clone()).filter(|it| it.name_lower() == "cfloop")
+                else {
+                    continue;
+                };
+                for attribute in ["index", "item"] {
+                    let token = tag
+                        .attribute(attribute)
+                        .and_then(|it| static_text(it.value_element()?));
+                    if let Some(token) = token {
+                        res.push((token.text().to_string(), token.text_range()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+/// Where the members of the `variables` scope of the component and those
+/// it extends are declared: properties and `variables.` assignments.
+fn members(ctx: &DiagnosticsContext) -> FxHashMap<String, FileRange> {
+    let mut res = FxHashMap::default();
+    if ctx.root().descendants().find_map(Component::cast).is_none() {
+        return res;
+    }
+    let analysis = ctx.analysis();
+    let mut files: Vec<FileId> = Vec::new();
+    walk_extends(analysis, ctx.file_id(), |file_id, _| files.push(file_id));
+    for file_id in files {
+        let Some(parse) = analysis.parse(file_id) else {
+            continue;
+        };
+        let mut insert = |name: String, range| {
+            res.entry(name.to_ascii_lowercase())
+                .or_insert(FileRange { file_id, range });
+        };
+        let root = parse.syntax_node();
+        if let Some(component) = root.descendants().find_map(Component::cast) {
+            for property in component.properties() {
+                let token = property
+                    .find_attribute("name")
+                    .and_then(|it| static_text(it.value_element()?));
+                match (token, property.name()) {
+                    (Some(token), _) => insert(token.text().to_string(), token.text_range()),
+                    (None, Some(name)) => insert(name, property.syntax().text_range()),
+                    (None, None) => {}
+                }
+            }
+        }
+        for assign in root.descendants().filter_map(ast::AssignExpr::cast) {
+            let Some(ast::Expr::FieldExpr(lhs)) = assign.lhs() else {
+                continue;
+            };
+            let is_variables = lhs
+                .receiver()
+                .is_some_and(|it| it.syntax().text().eq_ignore_ascii_case("variables"));
+            if let (true, Some(name), Some(field)) = (is_variables, lhs.field_name(), lhs.field()) {
+                insert(name, field.text_range());
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn shadowed_variables() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component extends="Base" {
+    property name="dsn";
+    function init() {
+        variables.cache = {};
+        return this;
+    }
+    function f(id) {
+        var id = 1;
+        var dsn = "other";
+        local.form = {};
+        local.cache = id & dsn & local.form;
+        var logger = 1;
+        return local.cache & logger;
+    }
+}
+//- /app/Base.cfc
+component {
+    variables.logger = "";
+}
+"#,
+            &[
+                ("id", "`id` shadows the argument `id`"),
+                ("dsn", "`dsn` shadows `variables.dsn`"),
+                ("form", "`form` shadows the `form` scope"),
+                ("cache", "`cache` shadows `variables.cache`"),
+                ("logger", "`logger` shadows `variables.logger`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn shadowing_loop_variables() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cffunction name="sum" output="false">
+    <cfargument name="items">
+    <cfargument name="i">
+    <cfset var total = 0>
+    <cfloop array="#arguments.items#" index="i">
+        <cfset total += i>
+    </cfloop>
+    <cfscript>
+        for (var url in arguments.items) {
+            total += len(url);
+        }
+    </cfscript>
+    <cfreturn total>
+</cffunction>
+"##,
+            &[
+                ("i", "`i` shadows the argument `i`"),
+                ("url", "`url` shadows the `url` scope"),
+            ],
+        );
+    }
+}
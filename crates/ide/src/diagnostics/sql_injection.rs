@@ -0,0 +1,96 @@
+use syntax::ast::{self, AstNode};
+
+use crate::{
+    assists::assist_as_fix,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    sql::{is_safe_value, sql_query, SqlQuery},
+    FileRange,
+};
+
+// Diagnostic: sql-injection
+//
+// Reported on values interpolated into the SQL of a `<cfquery>` or a
+// `queryExecute()` instead of being passed as parameters, unless they are
+// provably safe: literals, numbers, or the results of functions like
+// `val()`. The fix passes the value with `<cfqueryparam>` or as a bound
+// parameter.
+pub(crate) fn sql_injection(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let text = ctx.analysis().file_text(ctx.file_id())?;
+    for hash in ctx.root().descendants().filter_map(ast::HashExpr::cast) {
+        let Some(query) = sql_query(&hash) else {
+            continue;
+        };
+        if hash.expr().is_none_or(|it| is_safe_value(&it)) {
+            continue;
+        }
+        let advice = match query {
+            SqlQuery::Tag(_) => "pass it with `<cfqueryparam>`",
+            SqlQuery::Call(_) => "pass it as a bound parameter",
+        };
+        let range = hash.syntax().text_range();
+        let mut diagnostic = Diagnostic::new(
+            "sql-injection",
+            Severity::Error,
+            format!(
+                "`{}` is written into SQL unescaped, allowing SQL injection: {advice}",
+                hash.syntax().text()
+            ),
+            range,
+        );
+        let frange = FileRange {
+            file_id: ctx.file_id(),
+            range,
+        };
+        if let Some(fix) = assist_as_fix("wrap_in_queryparam", frange, &text) {
+            diagnostic = diagnostic.with_fix(fix);
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unparameterized_values() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfparam name="url.page" type="numeric" default="1">
+<cfset sort = "name">
+<cfquery name="users">
+    SELECT * FROM users
+    WHERE id = #form.id# AND age > <cfqueryparam value="#form.age#">
+    ORDER BY #sort# OFFSET #val(url.offset)# LIMIT #url.page#
+</cfquery>
+<cfscript>
+    queryExecute("SELECT * FROM t WHERE name = '#url.name#'", {});
+</cfscript>
+"##,
+            &[
+                (
+                    "#form.id#",
+                    "`#form.id#` is written into SQL unescaped, allowing SQL injection: pass it with `<cfqueryparam>`",
+                ),
+                (
+                    "#url.name#",
+                    "`#url.name#` is written into SQL unescaped, allowing SQL injection: pass it as a bound parameter",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn wrap_in_queryparam() {
+        check_fix(
+            "Wrap `#form.name#` in `<cfqueryparam>`",
+            r##"
+//- /app/index.cfm
+<cfquery name="q">SELECT * FROM t WHERE name = '#form.name#'</cfquery>
+"##,
+            r##"<cfquery name="q">SELECT * FROM t WHERE name = <cfqueryparam value="#form.name#" cfsqltype="cf_sql_varchar"></cfquery>"##,
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Comments turning off diagnostics where a team accepts them:
+//!
+//! - `cf-lint-ignore-file` in the whole file,
+//! - `cf-lint-ignore-line` on the lines of the comment,
+//! - `cf-lint-ignore-next-line` on the line after it,
+//! - `cf-lint-ignore` in the tag or statement following it.
+//!
+//! Each may start with `@`, and be followed by the codes to ignore,
+//! separated by spaces or commas. Without codes, every diagnostic is
+//! ignored.
+use syntax::{SyntaxElement, SyntaxKind::*, SyntaxNode, SyntaxToken, TextRange};
+
+use crate::{diagnostics::Diagnostic, LineIndex};
+
+/// The directives, longest first for the prefixes to match.
+const DIRECTIVES: &[(&str, Directive)] = &[
+    ("cf-lint-ignore-next-line", Directive::NextLine),
+    ("cf-lint-ignore-line", Directive::Line),
+    ("cf-lint-ignore-file", Directive::File),
+    ("cf-lint-ignore", Directive::Block),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Directive {
+    File,
+    Line,
+    NextLine,
+    Block,
+}
+
+#[derive(Debug)]
+enum Scope {
+    File,
+    /// Lines, inclusive.
+    Lines(u32, u32),
+    Range(TextRange),
+}
+
+#[derive(Debug)]
+struct Suppression {
+    scope: Scope,
+    /// Lowercase codes, every code when empty.
+    codes: Vec<String>,
+}
+
+pub(crate) struct Suppressions {
+    line_index: LineIndex,
+    suppressions: Vec<Suppression>,
+}
+
+impl Suppressions {
+    pub(crate) fn of(root: &SyntaxNode) -> Suppressions {
+        let line_index = LineIndex::new(&root.text());
+        let line = |offset| line_index.line_col(offset).line;
+        let mut suppressions = Vec::new();
+        let comments = root
+            .descendants_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|it| matches!(it.kind(), COMMENT | CF_COMMENT));
+        for comment in comments {
+            let Some((directive, codes)) = parse(comment.text()) else {
+                continue;
+            };
+            let range = comment.text_range();
+            let scope = match directive {
+                Directive::File => Scope::File,
+                Directive::Line => Scope::Lines(line(range.start()), line(range.end())),
+                Directive::NextLine => {
+                    let next = line(range.end()) + 1;
+                    Scope::Lines(next, next)
+                }
+                Directive::Block => match next_sibling(&comment) {
+                    Some(it) => Scope::Range(it.text_range()),
+                    None => continue,
+                },
+            };
+            suppressions.push(Suppression { scope, codes });
+        }
+        Suppressions {
+            line_index,
+            suppressions,
+        }
+    }
+
+    pub(crate) fn suppresses(&self, diagnostic: &Diagnostic) -> bool {
+        let line = self.line_index.line_col(diagnostic.range.start()).line;
+        self.suppressions.iter().any(|it| {
+            let in_scope = match it.scope {
+                Scope::File => true,
+                Scope::Lines(start, end) => (start..=end).contains(&line),
+                Scope::Range(range) => range.contains_range(diagnostic.range),
+            };
+            in_scope
+                && (it.codes.is_empty()
+                    || it
+                        .codes
+                        .iter()
+                        .any(|it| it.eq_ignore_ascii_case(diagnostic.code.0)))
+        })
+    }
+}
+
+/// The directive of a comment and its codes.
+fn parse(comment: &str) -> Option<(Directive, Vec<String>)> {
+    let text = comment
+        .trim_start_matches("<!---")
+        .trim_end_matches("--->")
+        .trim_start_matches("//")
+        .trim_start_matches("/*")
+        .trim_end_matches("*/")
+        .trim_start_matches(['*', ' ', '\t'])
+        .trim();
+    let text = text.strip_prefix('@').unwrap_or(text);
+    let (directive, rest) = DIRECTIVES.iter().find_map(|(name, directive)| {
+        let prefix = text.get(..name.len())?;
+        let rest = &text[name.len()..];
+        let ends = rest.is_empty() || rest.starts_with(|it: char| it.is_whitespace() || it == ':');
+        (prefix.eq_ignore_ascii_case(name) && ends).then_some((*directive, rest))
+    })?;
+    let codes = rest
+        .split(|it: char| it.is_whitespace() || it == ',' || it == ':')
+        .filter(|it| !it.is_empty())
+        .map(|it| it.to_ascii_lowercase())
+        .collect();
+    Some((directive, codes))
+}
+
+/// The tag or statement following `comment`.
+fn next_sibling(comment: &SyntaxToken) -> Option<SyntaxElement> {
+    let mut next = comment.next_sibling_or_token();
+    while let Some(element) = next {
+        let is_blank = match &element {
+            SyntaxElement::Token(it) => {
+                it.kind().is_trivia() || (it.kind() == TEXT && it.text().trim().is_empty())
+            }
+            SyntaxElement::Node(_) => false,
+        };
+        if !is_blank {
+            return Some(element);
+        }
+        next = element.next_sibling_or_token();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn suppressed_diagnostics() {
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<!--- @cf-lint-ignore undefined-variable --->
+<cfoutput>
+    #a#
+    #b#
+</cfoutput>
+<cfscript>
+    // cf-lint-ignore-next-line
+    writeOutput(c);
+    writeOutput(d); // cf-lint-ignore-line xss, undefined-variable
+    writeOutput(e); /* cf-lint-ignore-line unused-variable */
+</cfscript>
+"#,
+            &[("e", "`e` is not defined")],
+        );
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<!--- cf-lint-ignore-file: undefined-variable --->
+<cfset x = y>
+"#,
+            &[],
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use rustc_hash::FxHashMap;
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange,
+};
+
+use crate::{
+    component::line_indent,
+    diagnostics::{unreachable_code::exits, Diagnostic, DiagnosticsContext, Severity},
+    resolve::static_text,
+    TextEdit,
+};
+
+// Diagnostic: duplicate-case
+//
+// Reported on a `<cfcase>` value, or a script `case` label, which an earlier
+// case of the same switch already handles. Values are compared ignoring
+// case, as the engines do. The value never matches, so it is tagged as
+// unnecessary.
+
+// Diagnostic: case-after-default
+//
+// Reported on a `<cfcase>` following the `<cfdefaultcase>` of its switch.
+
+// Diagnostic: empty-switch
+//
+// Reported on a `<cfswitch>` or `switch` without any case.
+
+// Diagnostic: switch-fallthrough
+//
+// Reported on a script `case` which runs into the next one, not ending with
+// `break`, `return` or the like, when enabled. Cases without statements,
+// sharing the statements of the next one, are fine.
+pub(crate) fn switch_cases(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for node in ctx.root().descendants() {
+        match node.kind() {
+            TAG => {
+                let Some(tag) = ast::Tag::cast(node).filter(|it| it.name_lower() == "cfswitch")
+                else {
+                    continue;
+                };
+                switch_tag(acc, ctx, &tag);
+            }
+            SWITCH_STMT => {
+                switch_stmt(acc, ctx, &node);
+            }
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+fn switch_tag(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext, tag: &ast::Tag) -> Option<()> {
+    let cases: Vec<ast::Tag> = tag
+        .child_tags()
+        .filter(|it| matches!(it.name_lower().as_str(), "cfcase" | "cfdefaultcase"))
+        .collect();
+    if cases.is_empty() {
+        acc.push(Diagnostic::new(
+            "empty-switch",
+            Severity::Warning,
+            "`<cfswitch>` has no cases",
+            tag.name()?.text_range(),
+        ));
+        return Some(());
+    }
+    let mut seen = Seen::default();
+    let mut default: Option<TextRange> = None;
+    for case in cases {
+        let Some(name) = case.name() else {
+            continue;
+        };
+        if case.name_lower() == "cfdefaultcase" {
+            default.get_or_insert(name.text_range());
+            continue;
+        }
+        if let Some(default) = default {
+            acc.push(
+                Diagnostic::new(
+                    "case-after-default",
+                    Severity::Warning,
+                    "`<cfcase>` follows `<cfdefaultcase>`, which should come last",
+                    name.text_range(),
+                )
+                .with_related(ctx.frange(default), "`<cfdefaultcase>` is here"),
+            );
+        }
+        let Some(value) = case.attribute("value") else {
+            continue;
+        };
+        let (Some(text), Some(range)) = (
+            value.value(),
+            value
+                .value_element()
+                .and_then(static_text)
+                .map(|it| it.text_range()),
+        ) else {
+            continue;
+        };
+        let delimiters = case
+            .attribute("delimiters")
+            .and_then(|it| it.value())
+            .unwrap_or_else(|| ",".to_string());
+        for value in text.split(|it| delimiters.contains(it)) {
+            seen.check(acc, ctx, value.to_lowercase(), value, range);
+        }
+    }
+    Some(())
+}
+
+fn switch_stmt(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+    node: &SyntaxNode,
+) -> Option<()> {
+    let clauses: Vec<SyntaxNode> = node
+        .children()
+        .filter(|it| matches!(it.kind(), CASE_CLAUSE | DEFAULT_CLAUSE))
+        .collect();
+    if clauses.is_empty() {
+        acc.push(Diagnostic::new(
+            "empty-switch",
+            Severity::Warning,
+            "`switch` has no cases",
+            keyword(node)?.text_range(),
+        ));
+        return Some(());
+    }
+    let mut seen = Seen::default();
+    for (idx, clause) in clauses.iter().enumerate() {
+        if clause.kind() == CASE_CLAUSE {
+            if let Some(label) = clause.children().find_map(ast::Expr::cast) {
+                if let Some(key) = label_key(&label) {
+                    let text = label.syntax().text().to_string();
+                    seen.check(acc, ctx, key, &text, label.syntax().text_range());
+                }
+            }
+        }
+        let is_last = idx + 1 == clauses.len();
+        if ctx.config().switch_fallthrough && !is_last {
+            fallthrough(acc, ctx, clause);
+        }
+    }
+    Some(())
+}
+
+/// Values of the cases seen so far, by the key they compare with.
+#[derive(Default)]
+struct Seen(FxHashMap<String, TextRange>);
+
+impl Seen {
+    fn check(
+        &mut self,
+        acc: &mut Vec<Diagnostic>,
+        ctx: &DiagnosticsContext,
+        key: String,
+        text: &str,
+        range: TextRange,
+    ) {
+        match self.0.get(&key) {
+            Some(first) => acc.push(
+                Diagnostic::new(
+                    "duplicate-case",
+                    Severity::Warning,
+                    format!("`{text}` is already handled by an earlier case"),
+                    range,
+                )
+                .with_unused(true)
+                .with_related(
+                    ctx.frange(*first),
+                    format!("`{text}` is first handled here"),
+                ),
+            ),
+            None => {
+                self.0.insert(key, range);
+            }
+        }
+    }
+}
+
+/// What a literal `case` label compares as: strings ignoring case, and
+/// numbers by value.
+fn label_key(label: &ast::Expr) -> Option<String> {
+    match label.clone().unwrapped() {
+        ast::Expr::StringLit(it) => Some(it.value()?.to_lowercase()),
+        ast::Expr::Literal(it) => {
+            let token = it.token()?;
+            match token.text().parse::<f64>() {
+                Ok(number) => Some(number.to_string()),
+                Err(_) => Some(token.text().to_ascii_lowercase()),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn fallthrough(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+    clause: &SyntaxNode,
+) -> Option<()> {
+    let statements: Vec<SyntaxNode> = clause
+        .children_with_tokens()
+        .skip_while(|it| it.kind() != COLON)
+        .filter_map(NodeOrToken::into_node)
+        .collect();
+    let last = statements.last()?;
+    let ends = statements.iter().any(|it| {
+        matches!(it.kind(), BREAK_STMT | CONTINUE_STMT) || exits(&NodeOrToken::Node(it.clone()))
+    });
+    if ends {
+        return Some(());
+    }
+    let range = keyword(clause)?.text_range();
+    let indent = line_indent(last);
+    acc.push(
+        Diagnostic::new(
+            "switch-fallthrough",
+            Severity::Warning,
+            "This case falls through to the next one",
+            range,
+        )
+        .with_fix(ctx.fix(
+            "add_break",
+            "Add `break`",
+            range,
+            TextEdit::insert(last.text_range().end(), format!("\n{indent}break;")),
+        )),
+    );
+    Some(())
+}
+
+/// `switch`, `case` or `default`.
+fn keyword(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.children_with_tokens()
+        .filter_map(NodeOrToken::into_token)
+        .find(|it| it.kind() == KEYWORD)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{
+        tests::{check_diagnostics, check_diagnostics_with_config, check_fix_with_config},
+        DiagnosticsConfig,
+    };
+
+    #[test]
+    fn switch_tags() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfparam name="url.x" default="a">
+<cfswitch expression="#url.x#">
+    <cfcase value="a,B">1</cfcase>
+    <cfdefaultcase>2</cfdefaultcase>
+    <cfcase value="c|b" delimiters="|">3</cfcase>
+</cfswitch>
+<cfswitch expression="#url.x#">
+</cfswitch>
+"##,
+            &[
+                (
+                    "cfcase",
+                    "`<cfcase>` follows `<cfdefaultcase>`, which should come last",
+                ),
+                ("c|b", "`b` is already handled by an earlier case"),
+                ("cfswitch", "`<cfswitch>` has no cases"),
+            ],
+        );
+    }
+
+    #[test]
+    fn switch_statements() {
+        let config = DiagnosticsConfig {
+            switch_fallthrough: true,
+            ..DiagnosticsConfig::default()
+        };
+        check_diagnostics_with_config(
+            &config,
+            r#"
+//- /app/Service.cfc
+component {
+    function f(x) {
+        var y = 0;
+        switch (x) {
+            case "a":
+            case 1:
+                y = 1;
+            case "A":
+                y = 2;
+                break;
+            case 1.0:
+                return y;
+            default:
+                y = 3;
+        }
+        switch (x) {
+        }
+        return y;
+    }
+}
+"#,
+            &[
+                ("case", "This case falls through to the next one"),
+                ("\"A\"", "`\"A\"` is already handled by an earlier case"),
+                ("1.0", "`1.0` is already handled by an earlier case"),
+                ("switch", "`switch` has no cases"),
+            ],
+        );
+    }
+
+    #[test]
+    fn add_break() {
+        let config = DiagnosticsConfig {
+            switch_fallthrough: true,
+            ..DiagnosticsConfig::default()
+        };
+        check_fix_with_config(
+            &config,
+            "Add `break`",
+            r#"
+//- /app/index.cfm
+<cfscript>
+    switch (url.x) {
+        case "a":
+            writeOutput(1);
+        default:
+            writeOutput(2);
+    }
+</cfscript>
+"#,
+            r#"<cfscript>
+    switch (url.x) {
+        case "a":
+            writeOutput(1);
+            break;
+        default:
+            writeOutput(2);
+    }
+</cfscript>"#,
+        );
+    }
+}
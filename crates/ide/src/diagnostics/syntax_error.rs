@@ -0,0 +1,37 @@
+use crate::diagnostics::{Diagnostic, DiagnosticsContext, Severity};
+
+// Diagnostic: syntax-error
+//
+// Reported on code the parser can't make sense of, e.g. a missing `;` or an
+// unterminated tag. The rest of the file is still analyzed, but diagnostics
+// near the error may be off until it is fixed.
+pub(crate) fn syntax_error(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let parse = ctx.analysis().parse(ctx.file_id())?;
+    acc.extend(parse.errors().iter().map(|error| {
+        Diagnostic::new(
+            "syntax-error",
+            Severity::Error,
+            error.message.clone(),
+            error.range,
+        )
+    }));
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn syntax_errors() {
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<cfscript>
+    x = ;
+</cfscript>
+"#,
+            &[(";", "expected expression")],
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::{
+    component::Component,
+    diagnostics::{Diagnostic, DiagnosticsContext},
+    resolve::walk_extends,
+    var_scope::{assignment, FunctionLike, SCOPES},
+    Symbol,
+};
+
+/// Variables the engine defines, besides the scopes.
+pub(super) const IMPLICIT_VARIABLES: &[&str] = &[
+    "cfcatch",
+    "cfdirectory",
+    "cferror",
+    "cffile",
+    "cfhttp",
+    "cfquery",
+    "cfthread",
+    "no",
+    // `rethrow;` in a `catch`.
+    "rethrow",
+    "super",
+    "thistag",
+    "yes",
+];
+
+/// Scopes in which an unscoped name is looked up, e.g. `local.x = 1` makes
+/// `x` readable.
+const LOOKUP_SCOPES: &[&str] = &["arguments", "local", "variables"];
+
+// Diagnostic: undefined-variable
+//
+// Reported on unscoped variables which nothing visible assigns to: not the
+// file, nor a component it extends, nor the enclosing functions with their
+// arguments and `var`s, nor tags like `<cfquery name>` or `<cfloop index>`.
+// Variables defined by an included template look undefined too, which is
+// why the severity is configurable.
+pub(crate) fn undefined_variable(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let root = ctx.root();
+    let defined = visible_names(ctx);
+    for name_ref in root.descendants().filter_map(ast::NameRef::cast) {
+        let name = name_ref.text();
+        let key = name.to_ascii_lowercase();
+        if SCOPES.contains(&key.as_str())
+            || IMPLICIT_VARIABLES.contains(&key.as_str())
+            || defined.contains(&key)
+            || is_callee(&name_ref)
+            || is_local(&name_ref, &key)
+            || in_query_loop(name_ref.syntax())
+        {
+            continue;
+        }
+        acc.push(Diagnostic::new(
+            "undefined-variable",
+            ctx.config().undefined_variable,
+            format!("`{name}` is not defined"),
+            name_ref.syntax().text_range(),
+        ));
+    }
+    Some(())
+}
+
+/// Names, in lowercase, which the file or the components it extends define
+/// outside of local scopes.
+pub(super) fn visible_names(ctx: &DiagnosticsContext) -> FxHashSet<String> {
+    let mut res = defined_names(ctx.root());
+    let analysis = ctx.analysis();
+    walk_extends(analysis, ctx.file_id(), |file_id, _| {
+        if file_id == ctx.file_id() {
+            return;
+        }
+        if let Some(parse) = analysis.parse(file_id) {
+            res.extend(defined_names(&parse.syntax_node()));
+        }
+    });
+    res
+}
+
+/// Names, in lowercase, which `root` defines outside of local scopes:
+/// assignments, functions, properties and the variables of `catch`.
+fn defined_names(root: &SyntaxNode) -> FxHashSet<String> {
+    let mut res = FxHashSet::default();
+    for node in root.descendants() {
+        if let Some(assignment) = assignment(&node) {
+            res.insert(assignment.name.to_ascii_lowercase());
+            continue;
+        }
+        let name = match node.kind() {
+            ASSIGN_EXPR => ast::AssignExpr::cast(node)
+                .and_then(|it| it.lhs())
+                .and_then(|it| assigned_root(&it)),
+            CATCH_CLAUSE => ast::CatchClause::cast(node).and_then(|it| it.name()?.text()),
+            // `var`s outside of functions, as in `for (var x in xs)` in a
+            // template.
+            VAR_DECL if !node.ancestors().any(|it| FunctionLike::cast(it).is_some()) => {
+                ast::VarDecl::cast(node).and_then(|it| Some(it.name()?.text().to_string()))
+            }
+            _ => FunctionLike::cast(node).and_then(|it| it.name()),
+        };
+        res.extend(name.map(|it| it.to_ascii_lowercase()));
+    }
+    if let Some(component) = root.descendants().find_map(Component::cast) {
+        res.extend(
+            component
+                .properties()
+                .iter()
+                .filter_map(|it| it.name())
+                .map(|it| it.to_ascii_lowercase()),
+        );
+    }
+    res
+}
+
+/// The variable a scoped or nested assignment defines: `x` in
+/// `variables.x = 1` and `x.y.z = 1`.
+fn assigned_root(lhs: &ast::Expr) -> Option<String> {
+    let mut expr = lhs.clone();
+    let mut field = None;
+    loop {
+        expr = match expr {
+            ast::Expr::FieldExpr(it) => {
+                field = it.field_name();
+                it.receiver()?
+            }
+            ast::Expr::IndexExpr(it) => {
+                field = None;
+                it.base()?
+            }
+            ast::Expr::NameRef(it) => {
+                let name = it.text();
+                if LOOKUP_SCOPES.contains(&name.to_ascii_lowercase().as_str()) {
+                    return field;
+                }
+                return Some(name);
+            }
+            _ => return None,
+        };
+    }
+}
+
+pub(super) fn is_callee(name_ref: &ast::NameRef) -> bool {
+    name_ref
+        .syntax()
+        .parent()
+        .and_then(ast::CallExpr::cast)
+        .and_then(|it| it.callee())
+        .is_some_and(|it| it.syntax() == name_ref.syntax())
+}
+
+/// Returns `true` if an enclosing function declares `key` as an argument or
+/// a `var`.
+fn is_local(name_ref: &ast::NameRef, key: &str) -> bool {
+    name_ref
+        .syntax()
+        .ancestors()
+        .filter_map(FunctionLike::cast)
+        .any(|it| it.declared_names().contains(&Symbol::new(key)))
+}
+
+/// Inside `<cfloop query>` and `<cfoutput query>`, the columns of the query
+/// are variables.
+pub(super) fn in_query_loop(node: &SyntaxNode) -> bool {
+    node.ancestors().filter_map(ast::Tag::cast).any(|it| {
+        matches!(it.name_lower().as_str(), "cfloop" | "cfoutput") && it.attribute("query").is_some()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn undefined_variables() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfparam name="title" default="">
+<cfquery name="users">SELECT * FROM users</cfquery>
+<cfloop array="#users#" index="user">
+    <cfset total.count = 1>
+    <cfoutput>#title# #user# #total# #missing#</cfoutput>
+</cfloop>
+<cfoutput query="users">#firstName#</cfoutput>
+<cffile action="upload" accept="image/png" result="upload">
+<cfset variables.saved = upload>
+<cfscript>
+    try { x = saved; } catch (any e) { writeOutput(e.message & cfcatch.detail); }
+    for (item in [1, 2]) { writeOutput(item & other); }
+</cfscript>
+"##,
+            &[
+                ("missing", "`missing` is not defined"),
+                ("other", "`other` is not defined"),
+            ],
+        );
+    }
+
+    #[test]
+    fn locals_and_inherited() {
+        check_diagnostics(
+            r#"
+//- /app/User.cfc
+component extends="Base" {
+    property name="email";
+    function f(a) {
+        var b = a;
+        local.c = b;
+        return [c, email, name, f, g, () => b, (d) => d + e];
+    }
+}
+//- /app/Base.cfc
+component {
+    variables.name = "";
+    function g() {}
+}
+"#,
+            &[("e", "`e` is not defined")],
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::{BuiltinTag, ATTRIBUTE_COLLECTION},
+    TextEdit,
+};
+
+// Diagnostic: unknown-attribute
+//
+// Reported on the attributes of a built-in tag, or script statement like
+// `lock`, which the tag doesn't have, e.g. a misspelled `datasorce`, or
+// which the configured engine doesn't support. Custom tags, and tags passing
+// their attributes on like `<cfmodule>`, accept anything.
+pub(crate) fn unknown_attribute(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let engine = ctx.config().engine;
+    for tag in ctx.root().descendants().filter_map(BuiltinTag::cast) {
+        let (name, schema) = (&tag.name, tag.schema);
+        for attribute in &tag.attributes {
+            let Some(token) = attribute.name() else {
+                continue;
+            };
+            let attribute_name = token.text().to_ascii_lowercase();
+            if attribute_name == ATTRIBUTE_COLLECTION {
+                continue;
+            }
+            let known = schema.attribute(&attribute_name);
+            let message = match (known, engine) {
+                (Some(it), Some(engine)) if !it.supported_by(Some(engine)) => {
+                    format!("`<{name}>` has no attribute `{}` in {engine}", token.text())
+                }
+                (Some(_), _) => continue,
+                (None, _) if schema.passthrough => continue,
+                (None, _) => format!("`<{name}>` has no attribute `{}`", token.text()),
+            };
+            let range = token.text_range();
+            let mut diagnostic =
+                Diagnostic::new("unknown-attribute", Severity::Error, message, range);
+            let suggestion = schema
+                .attributes
+                .iter()
+                .filter(|it| it.supported_by(engine))
+                .map(|it| (edit_distance(&attribute_name, it.name), it.name))
+                .filter(|(distance, _)| *distance <= 2)
+                .min();
+            if let (None, Some((_, suggestion))) = (known, suggestion) {
+                diagnostic = diagnostic.with_fix(ctx.fix(
+                    "rename_attribute",
+                    format!("Change to `{suggestion}`"),
+                    range,
+                    TextEdit::replace(range, suggestion.to_string()),
+                ));
+            }
+            acc.push(diagnostic);
+        }
+    }
+    Some(())
+}
+
+/// The number of characters to insert, delete or replace to turn `a` into
+/// `b`.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let next = (diagonal + usize::from(a != *b))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diagnostics::{
+            tests::{check_diagnostics, check_diagnostics_with_config, check_fix},
+            DiagnosticsConfig,
+        },
+        Engine, EngineKind,
+    };
+
+    #[test]
+    fn unknown_attributes() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfquery name="q" datasorce="app" lazy="true">
+    SELECT 1
+</cfquery>
+<cfmodule template="nav.cfm" active="home">
+<cf_menu anything="1">
+<cfdump var="#q#" attributeCollection="#q#" colour="red">
+<cfscript>
+    lock name="x" timeout=1 throwOnTimout=false {}
+    param q = 1;
+</cfscript>
+"##,
+            &[
+                ("cfdump", "`<cfdump>` looks left over from debugging"),
+                ("datasorce", "`<cfquery>` has no attribute `datasorce`"),
+                ("colour", "`<cfdump>` has no attribute `colour`"),
+                (
+                    "throwOnTimout",
+                    "`<cflock>` has no attribute `throwOnTimout`",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn attributes_of_other_engines() {
+        let config = DiagnosticsConfig {
+            engine: Some(Engine {
+                kind: EngineKind::Adobe,
+                version: 2021,
+            }),
+            ..DiagnosticsConfig::default()
+        };
+        check_diagnostics_with_config(
+            &config,
+            r#"
+//- /app/index.cfm
+<cfloop index="i" from="1" to="3" times="3"></cfloop>
+<cfquery name="q" datasource="app" cachename="q">SELECT 1</cfquery>
+"#,
+            &[(
+                "times",
+                "`<cfloop>` has no attribute `times` in Adobe ColdFusion 2021",
+            )],
+        );
+    }
+
+    #[test]
+    fn rename_attribute() {
+        check_fix(
+            "Change to `datasource`",
+            r#"
+//- /app/index.cfm
+<cfquery name="q" datasorce="app">SELECT 1</cfquery>
+"#,
+            r#"<cfquery name="q" datasource="app">SELECT 1</cfquery>"#,
+        );
+    }
+}
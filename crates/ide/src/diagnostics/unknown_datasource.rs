@@ -0,0 +1,209 @@
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken, SyntaxElement, TextRange,
+};
+
+use crate::{
+    diagnostics::{unknown_attribute::edit_distance, Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::BuiltinTag,
+    TextEdit,
+};
+
+// Diagnostic: unknown-datasource
+//
+// Reported on a `datasource` naming none of the configured datasources, in
+// the attributes of tags like `<cfquery>`, the options of `queryExecute()`,
+// and `this.datasource` in `Application.cfc`. This catches typos, and names
+// only defined on another environment, before deploying. The fix changes the
+// name to the closest configured one.
+//
+// The datasources are set in `diagnostics.datasources`, and read from the
+// `.cfconfig.json` at the root of the workspace folder of the file. Datasources
+// computed at runtime aren't checked.
+pub(crate) fn unknown_datasource(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let datasources = &ctx.config().datasources;
+    if datasources.is_empty() {
+        return Some(());
+    }
+    for node in ctx.root().descendants() {
+        let value = if let Some(tag) = BuiltinTag::cast(node.clone()) {
+            tag.attribute("datasource")
+                .and_then(|it| it.value_element())
+                .and_then(static_value)
+        } else if let Some(call) = ast::CallExpr::cast(node.clone()) {
+            query_execute_option(&call)
+        } else if let Some(assign) = ast::AssignExpr::cast(node) {
+            this_datasource(&assign)
+        } else {
+            None
+        };
+        let Some((name, range)) = value else {
+            continue;
+        };
+        if name.is_empty() || datasources.iter().any(|it| it.eq_ignore_ascii_case(&name)) {
+            continue;
+        }
+        let mut diagnostic = Diagnostic::new(
+            "unknown-datasource",
+            Severity::Warning,
+            format!("No datasource named `{name}` is configured"),
+            range,
+        );
+        let lower = name.to_ascii_lowercase();
+        let suggestion = datasources
+            .iter()
+            .map(|it| (edit_distance(&lower, &it.to_ascii_lowercase()), it))
+            .filter(|(distance, _)| *distance <= 2)
+            .min();
+        if let Some((_, suggestion)) = suggestion {
+            diagnostic = diagnostic.with_fix(ctx.fix(
+                "change_datasource",
+                format!("Change to `{suggestion}`"),
+                range,
+                TextEdit::replace(range, suggestion.clone()),
+            ));
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+/// The `datasource` in the options of `queryExecute(sql, params, options)`.
+fn query_execute_option(call: &ast::CallExpr) -> Option<(String, TextRange)> {
+    if !call.callee_name()?.eq_ignore_ascii_case("queryexecute") {
+        return None;
+    }
+    let args: Vec<ast::Arg> = call.arg_list()?.args().collect();
+    let options = match args.iter().find(|it| it.name().is_some()) {
+        Some(_) => args.iter().find(|it| {
+            it.name()
+                .is_some_and(|it| it.eq_ignore_ascii_case("options"))
+        })?,
+        None => args.get(2)?,
+    };
+    let ast::Expr::StructLit(options) = options.expr()? else {
+        return None;
+    };
+    let field = options.fields().find(|it| {
+        it.key()
+            .is_some_and(|it| it.eq_ignore_ascii_case("datasource"))
+    })?;
+    static_value(field.value()?.syntax().clone().into())
+}
+
+/// The value of `this.datasource = "name"`.
+fn this_datasource(assign: &ast::AssignExpr) -> Option<(String, TextRange)> {
+    let ast::Expr::FieldExpr(lhs) = assign.lhs()? else {
+        return None;
+    };
+    let is_this =
+        matches!(lhs.receiver()?, ast::Expr::NameRef(it) if it.text().eq_ignore_ascii_case("this"));
+    if !is_this || !lhs.field_name()?.eq_ignore_ascii_case("datasource") {
+        return None;
+    }
+    static_value(assign.rhs()?.syntax().clone().into())
+}
+
+/// The text of a literal value, with the range of the text, inside any
+/// quotes.
+fn static_value(element: SyntaxElement) -> Option<(String, TextRange)> {
+    match element {
+        NodeOrToken::Node(node) => {
+            let ast::Expr::StringLit(string) = ast::Expr::cast(node)? else {
+                return None;
+            };
+            if !string.is_static() {
+                return None;
+            }
+            let range = string.syntax().text_range();
+            // Inside the quotes.
+            let range = TextRange::new(range.start() + 1, range.end() - 1);
+            Some((string.value()?, range))
+        }
+        NodeOrToken::Token(token) => Some((token.text().to_string(), token.text_range())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{
+        tests::{check_diagnostics_with_config, check_fix_with_config},
+        DiagnosticsConfig,
+    };
+
+    fn datasources() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            datasources: vec!["main".to_string(), "Reporting".to_string()],
+            disabled: ["undefined-variable".to_string()].into_iter().collect(),
+            ..DiagnosticsConfig::default()
+        }
+    }
+
+    #[test]
+    fn unknown_datasources() {
+        check_diagnostics_with_config(
+            &datasources(),
+            r##"
+//- /app/index.cfm
+<cfquery name="a" datasource="reporting">select 1</cfquery>
+<cfquery name="b" datasource="legacy">select 1</cfquery>
+<cfquery name="c" datasource="#application.dsn#">select 1</cfquery>
+<cfscript>
+    d = queryExecute("select 1", {}, {datasource: "reportng"});
+    e = queryExecute(sql = "select 1", options = {datasource: "main"});
+    query name="f" datasource="staging" {
+        writeOutput("select 1");
+    }
+</cfscript>
+"##,
+            &[
+                ("legacy", "No datasource named `legacy` is configured"),
+                ("reportng", "No datasource named `reportng` is configured"),
+                ("staging", "No datasource named `staging` is configured"),
+            ],
+        );
+    }
+
+    #[test]
+    fn application_datasource() {
+        check_diagnostics_with_config(
+            &datasources(),
+            r#"
+//- /app/Application.cfc
+component {
+    this.name = "app";
+    this.datasource = "mian";
+}
+"#,
+            &[("mian", "No datasource named `mian` is configured")],
+        );
+    }
+
+    #[test]
+    fn unchecked_without_datasources() {
+        check_diagnostics_with_config(
+            &DiagnosticsConfig::default(),
+            r#"
+//- /app/index.cfm
+<cfquery name="a" datasource="legacy">select 1</cfquery>
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn change_datasource() {
+        check_fix_with_config(
+            &datasources(),
+            "Change to `Reporting`",
+            r#"
+//- /app/index.cfm
+<cfquery name="a" datasource="reportng">select 1</cfquery>
+"#,
+            r#"<cfquery name="a" datasource="Reporting">select 1</cfquery>"#,
+        );
+    }
+}
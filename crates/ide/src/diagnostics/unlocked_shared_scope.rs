@@ -0,0 +1,240 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::{
+    assists::utils::{assignment_target, reindent},
+    component::{indent_unit, line_indent},
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    tag_schema::BuiltinTag,
+    var_scope::FunctionLike,
+    TextEdit,
+};
+
+/// Methods of `Application.cfc` which the engine runs once per application
+/// or session, so that no other request writes to the scope meanwhile.
+const LIFECYCLE_METHODS: &[&str] = &[
+    "onapplicationend",
+    "onapplicationstart",
+    "onsessionend",
+    "onsessionstart",
+];
+
+// Diagnostic: unlocked-shared-scope
+//
+// Reported on writes to the `application` or `session` scopes outside a
+// `<cflock>` or `lock`, which concurrent requests may interleave, e.g. two
+// `application.hits++` counting once. The lifecycle methods of
+// `Application.cfc`, like `onApplicationStart()`, run alone and are fine.
+// The fix wraps the statement in an exclusive lock of the scope.
+//
+// Modern engines make single writes safe, so this is only reported when
+// `diagnostics.sharedScopeLocking.enable` is set.
+pub(crate) fn unlocked_shared_scope(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    if !ctx.config().shared_scope_locking {
+        return Some(());
+    }
+    let is_application_cfc = ctx
+        .analysis()
+        .paths
+        .get(&ctx.file_id())
+        .and_then(|it| it.to_string().rsplit(['/', '\\']).next().map(str::to_owned))
+        .is_some_and(|it| it.eq_ignore_ascii_case("Application.cfc"));
+    for node in ctx.root().descendants() {
+        let Some(target) = assignment_target(node.clone()) else {
+            continue;
+        };
+        let Some(scope) = shared_scope(&target) else {
+            continue;
+        };
+        if is_locked(&node) || (is_application_cfc && in_lifecycle_method(&node)) {
+            continue;
+        }
+        let range = target.syntax().text_range();
+        let mut diagnostic = Diagnostic::new(
+            "unlocked-shared-scope",
+            Severity::Warning,
+            format!(
+                "`{}` is written outside a lock, where concurrent requests may race",
+                target.syntax()
+            ),
+            range,
+        );
+        if let Some(statement) = statement(&node) {
+            let indent = line_indent(&statement);
+            let unit = indent_unit(&indent);
+            let body = reindent(&format!("{indent}{statement}"), &format!("{indent}{unit}"));
+            let wrapped = if statement.kind() == TAG {
+                format!("<cflock scope=\"{scope}\" type=\"exclusive\" timeout=\"10\">\n{body}\n{indent}</cflock>")
+            } else {
+                format!("lock scope=\"{scope}\" type=\"exclusive\" timeout=\"10\" {{\n{body}\n{indent}}}")
+            };
+            diagnostic = diagnostic.with_fix(ctx.fix(
+                "wrap_in_lock",
+                "Wrap in an exclusive lock",
+                range,
+                TextEdit::replace(statement.text_range(), wrapped),
+            ));
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+/// The scope written by `target`, if it is a member of `application` or
+/// `session`, in lowercase.
+fn shared_scope(target: &ast::Expr) -> Option<String> {
+    let mut base = match target {
+        ast::Expr::FieldExpr(it) => it.receiver()?,
+        ast::Expr::IndexExpr(it) => it.base()?,
+        _ => return None,
+    };
+    loop {
+        base = match base {
+            ast::Expr::FieldExpr(it) => it.receiver()?,
+            ast::Expr::IndexExpr(it) => it.base()?,
+            ast::Expr::NameRef(it) => {
+                let scope = it.text().to_ascii_lowercase();
+                return matches!(scope.as_str(), "application" | "session").then_some(scope);
+            }
+            _ => return None,
+        };
+    }
+}
+
+fn is_locked(node: &SyntaxNode) -> bool {
+    node.ancestors()
+        .filter_map(BuiltinTag::cast)
+        .any(|it| it.name == "cflock")
+}
+
+fn in_lifecycle_method(node: &SyntaxNode) -> bool {
+    node.ancestors()
+        .filter_map(FunctionLike::cast)
+        .find_map(|it| it.name())
+        .is_some_and(|it| LIFECYCLE_METHODS.contains(&it.to_ascii_lowercase().as_str()))
+}
+
+/// The script statement or `<cfset>` making up the write.
+fn statement(node: &SyntaxNode) -> Option<SyntaxNode> {
+    let parent = node.ancestors().skip(1).find(|it| !it.kind().is_expr())?;
+    match parent.kind() {
+        EXPR_STMT => Some(parent),
+        TAG_OPEN => {
+            let tag = parent.parent().and_then(ast::Tag::cast)?;
+            (tag.name_lower() == "cfset").then(|| tag.syntax().clone())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{
+        tests::{check_diagnostics_with_config, check_fix_with_config},
+        DiagnosticsConfig,
+    };
+
+    fn locking() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            shared_scope_locking: true,
+            ..DiagnosticsConfig::default()
+        }
+    }
+
+    #[test]
+    fn unlocked_writes() {
+        check_diagnostics_with_config(
+            &locking(),
+            r##"
+//- /app/index.cfm
+<cfset application.hits++>
+<cflock scope="session" type="exclusive" timeout="5">
+    <cfset session.cart = []>
+</cflock>
+<cfscript>
+    session["user"].name = "a";
+    lock scope="application" type="exclusive" timeout=5 {
+        application.cache = {};
+    }
+    x = application.cache;
+</cfscript>
+"##,
+            &[
+                (
+                    "application.hits",
+                    "`application.hits` is written outside a lock, where concurrent requests may race",
+                ),
+                (
+                    r#"session["user"].name"#,
+                    r#"`session["user"].name` is written outside a lock, where concurrent requests may race"#,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn lifecycle_methods() {
+        check_diagnostics_with_config(
+            &locking(),
+            r#"
+//- /app/Application.cfc
+component {
+    function onApplicationStart() {
+        application.cache = {};
+    }
+    function onRequestStart() {
+        application.hits = 1;
+    }
+}
+"#,
+            &[(
+                "application.hits",
+                "`application.hits` is written outside a lock, where concurrent requests may race",
+            )],
+        );
+    }
+
+    #[test]
+    fn wrap_in_lock() {
+        check_fix_with_config(
+            &locking(),
+            "Wrap in an exclusive lock",
+            r#"
+//- /app/Service.cfc
+component {
+    function f() {
+        application.cache = {};
+    }
+}
+"#,
+            r#"component {
+    function f() {
+        lock scope="application" type="exclusive" timeout="10" {
+            application.cache = {};
+        }
+    }
+}"#,
+        );
+        check_fix_with_config(
+            &locking(),
+            "Wrap in an exclusive lock",
+            r#"
+//- /app/index.cfm
+<cfif true>
+    <cfset session.cart = []>
+</cfif>
+"#,
+            r#"<cfif true>
+    <cflock scope="session" type="exclusive" timeout="10">
+        <cfset session.cart = []>
+    </cflock>
+</cfif>"#,
+        );
+    }
+}
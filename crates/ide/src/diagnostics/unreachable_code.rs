@@ -0,0 +1,273 @@
+use syntax::{
+    ast::{self, AstNode},
+    NodeOrToken, SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    var_scope::FunctionLike,
+};
+
+/// Tags ending the request or the function wherever they are.
+const EXITING_TAGS: &[&str] = &["cfabort", "cflocation", "cfrethrow", "cfreturn", "cfthrow"];
+
+/// Script statements and functions doing the same.
+const EXITING_STATEMENTS: &[&str] = &["abort", "location", "rethrow", "throw"];
+
+/// Tags starting another branch of their parent, which is reachable even
+/// when the previous one exits.
+const BRANCH_TAGS: &[&str] = &[
+    "cfcase",
+    "cfcatch",
+    "cfdefaultcase",
+    "cfelse",
+    "cfelseif",
+    "cffinally",
+];
+
+/// Tags running their body exactly once, which exit when it does.
+const TRANSPARENT_TAGS: &[&str] = &[
+    "cflock",
+    "cfoutput",
+    "cfscript",
+    "cfsilent",
+    "cftimer",
+    "cftransaction",
+];
+
+// Diagnostic: unreachable-code
+//
+// Reported on the statements following `return`, `throw`, `<cfabort>`,
+// `<cflocation>` and the like in the same block, and following an `if` or
+// `<cfif>` whose every branch exits, tagged as unnecessary for clients to
+// fade them out.
+pub(crate) fn unreachable_code(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    let mut res = Vec::new();
+    let containers = ctx.root().descendants().filter(|it| {
+        it.parent().is_none() || matches!(it.kind(), BLOCK | CASE_CLAUSE | DEFAULT_CLAUSE | TAG)
+    });
+    for container in containers {
+        for branch in branches(&container) {
+            let Some(exit) = branch.iter().position(exits) else {
+                continue;
+            };
+            // Functions are defined before the code runs.
+            let unreachable: Vec<&SyntaxElement> = branch[exit + 1..]
+                .iter()
+                .filter(|it| {
+                    it.as_node()
+                        .is_none_or(|it| FunctionLike::cast(it.clone()).is_none())
+                })
+                .collect();
+            let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) else {
+                continue;
+            };
+            let range = TextRange::new(trimmed(first).start(), trimmed(last).end());
+            let exit = &branch[exit];
+            let exit_range = match exit.as_node().and_then(|it| ast::Tag::cast(it.clone())) {
+                Some(tag) => tag
+                    .open()
+                    .map_or(exit.text_range(), |it| it.syntax().text_range()),
+                None => exit.text_range(),
+            };
+            res.push(
+                Diagnostic::new(
+                    "unreachable-code",
+                    Severity::Warning,
+                    "Unreachable code",
+                    range,
+                )
+                .with_unused(true)
+                .with_related(
+                    ctx.frange(exit_range),
+                    "Execution never continues past this",
+                ),
+            );
+        }
+    }
+    // Outer blocks are visited first.
+    res.sort_by_key(|it| it.range.start());
+    acc.extend(res);
+    Some(())
+}
+
+/// The range of `element`, without the blank lines around text.
+fn trimmed(element: &SyntaxElement) -> TextRange {
+    let range = element.text_range();
+    let Some(token) = element.as_token() else {
+        return range;
+    };
+    let text = token.text();
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+    TextRange::new(
+        range.start() + start as TextSize,
+        range.start() + end as TextSize,
+    )
+}
+
+/// The statements of `container`, grouped by branch: an `<cfif>` body is
+/// split at its `<cfelseif>` and `<cfelse>` tags. Blank text is left out.
+fn branches(container: &SyntaxNode) -> Vec<Vec<SyntaxElement>> {
+    let mut children: Vec<SyntaxElement> = container.children_with_tokens().collect();
+    if matches!(container.kind(), CASE_CLAUSE | DEFAULT_CLAUSE) {
+        let colon = children.iter().position(|it| it.kind() == COLON);
+        children.drain(..colon.map_or(children.len(), |it| it + 1));
+    }
+    let mut res = vec![Vec::new()];
+    for child in children {
+        match &child {
+            NodeOrToken::Node(node) => {
+                if matches!(node.kind(), TAG_OPEN | TAG_CLOSE) {
+                    continue;
+                }
+                if is_tag_in(node, BRANCH_TAGS) {
+                    res.push(Vec::new());
+                    continue;
+                }
+            }
+            NodeOrToken::Token(token) => {
+                if token.kind() != TEXT || token.text().trim().is_empty() {
+                    continue;
+                }
+            }
+        }
+        res.last_mut().unwrap().push(child);
+    }
+    res
+}
+
+/// Returns `true` if control never gets past `element`.
+pub(super) fn exits(element: &SyntaxElement) -> bool {
+    let Some(node) = element.as_node() else {
+        return false;
+    };
+    match node.kind() {
+        RETURN_STMT | THROW_STMT => true,
+        SCRIPT_TAG_STMT => node.first_token().is_some_and(|it| {
+            EXITING_STATEMENTS.contains(&it.text().to_ascii_lowercase().as_str())
+        }),
+        // `throw("x")`, `location("/")` or `rethrow`.
+        EXPR_STMT => {
+            let name = node
+                .children()
+                .find_map(ast::Expr::cast)
+                .and_then(|it| match it {
+                    ast::Expr::CallExpr(call) => match call.callee()? {
+                        ast::Expr::NameRef(it) => Some(it.text()),
+                        _ => None,
+                    },
+                    ast::Expr::NameRef(it) => Some(it.text()),
+                    _ => None,
+                });
+            name.is_some_and(|it| EXITING_STATEMENTS.contains(&it.to_ascii_lowercase().as_str()))
+        }
+        BLOCK => branches(node).iter().any(|it| it.iter().any(exits)),
+        IF_STMT => {
+            let Some(stmt) = ast::IfStmt::cast(node.clone()) else {
+                return false;
+            };
+            let branch_exits =
+                |it: Option<SyntaxNode>| it.is_some_and(|it| exits(&NodeOrToken::Node(it)));
+            branch_exits(stmt.then_branch())
+                && branch_exits(stmt.else_clause().and_then(|it| it.branch()))
+        }
+        TAG if is_tag_in(node, EXITING_TAGS) => true,
+        TAG if is_tag_in(node, &["cfif"]) => {
+            let has_else = ast::Tag::cast(node.clone())
+                .is_some_and(|it| it.child_tags().any(|it| it.name_lower() == "cfelse"));
+            has_else && branches(node).iter().all(|it| it.iter().any(exits))
+        }
+        TAG if is_tag_in(node, TRANSPARENT_TAGS) => {
+            branches(node).iter().any(|it| it.iter().any(exits))
+        }
+        _ => false,
+    }
+}
+
+fn is_tag_in(node: &SyntaxNode, names: &[&str]) -> bool {
+    ast::Tag::cast(node.clone()).is_some_and(|it| names.contains(&it.name_lower().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::check_diagnostics;
+
+    #[test]
+    fn unreachable_statements() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    function f(a) {
+        if (a) {
+            return 1;
+            a = 2;
+        } else {
+            throw("no");
+        }
+        a = 3;
+        return a;
+    }
+    function g(a) {
+        if (a) {
+            return 1;
+        }
+        switch (a) {
+            case 1:
+                return 2;
+            default:
+                a = 4;
+        }
+        return a;
+    }
+}
+"#,
+            &[
+                ("a = 2;", "Unreachable code"),
+                ("a = 3;\n        return a;", "Unreachable code"),
+            ],
+        );
+    }
+
+    #[test]
+    fn unreachable_tags() {
+        check_diagnostics(
+            r#"
+//- /app/index.cfm
+<cfif now() gt 1>
+    <cflocation url="/login">
+    <cfset x = 1>
+<cfelseif true>
+    <cfset x = 2>
+<cfelse>
+    <cfabort>
+</cfif>
+<cftry>
+    <cfthrow message="x">
+<cfcatch>
+    <cfset x = 3>
+</cfcatch>
+</cftry>
+<cfoutput>#x#</cfoutput>
+<cfscript>
+    abort;
+</cfscript>
+Done
+<cffunction name="f"></cffunction>
+"#,
+            &[
+                (
+                    "cfcatch",
+                    "The exception is neither rethrown nor logged, hiding the error",
+                ),
+                ("cfabort", "`<cfabort>` looks left over from debugging"),
+                ("abort", "`abort` looks left over from debugging"),
+                ("<cfset x = 1>", "Unreachable code"),
+                ("Done", "Unreachable code"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    resolve::resolve_component,
+    FileRange,
+};
+
+// Diagnostic: unresolved-component
+//
+// Reported on the name of a component, in `extends`, `implements`, `new` or
+// `createObject("component", ...)`, which no file of the workspace
+// resolves. The related information lists the paths tried and points at
+// components of the same name elsewhere. WireBox ids, which binders may map
+// to anything, and the components built into the engines aren't checked.
+pub(crate) fn unresolved_component(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext,
+) -> Option<()> {
+    let analysis = ctx.analysis();
+    let path = analysis.paths.get(&ctx.file_id())?.to_string();
+    let dir = &path[..path.rfind(['/', '\\']).unwrap_or(0)];
+    for reference in analysis.file_component_references(ctx.file_id())?.iter() {
+        let name = reference.name.as_str();
+        if reference.wirebox
+            || name.contains([':', '/', '\\'])
+            || is_builtin(name)
+            || resolve_component(analysis, ctx.file_id(), name).is_some()
+        {
+            continue;
+        }
+        let relative = format!("{}.cfc", name.replace('.', "/"));
+        let here = ctx.frange(reference.range);
+        let mut diagnostic = Diagnostic::new(
+            "unresolved-component",
+            Severity::Error,
+            format!("Component `{name}` can't be found"),
+            reference.range,
+        )
+        .with_related(here, format!("Tried `{dir}/{relative}`"))
+        .with_related(
+            here,
+            format!("Tried `{relative}` in every folder of the workspace"),
+        );
+
+        // Components sharing the last part of the name, likely the one meant.
+        let stem = format!("/{}.cfc", name.rsplit('.').next()?.to_ascii_lowercase());
+        let mut namesakes: Vec<(String, FileRange)> = analysis
+            .paths
+            .iter()
+            .map(|(&file_id, path)| (path.to_string(), file_id))
+            .filter(|(path, _)| {
+                path.replace('\\', "/")
+                    .to_ascii_lowercase()
+                    .ends_with(&stem)
+            })
+            .map(|(path, file_id)| {
+                let range = FileRange {
+                    file_id,
+                    range: Default::default(),
+                };
+                (path, range)
+            })
+            .collect();
+        namesakes.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, range) in namesakes {
+            diagnostic = diagnostic.with_related(range, format!("`{path}` has the same name"));
+        }
+        acc.push(diagnostic);
+    }
+    Some(())
+}
+
+/// The script components Adobe ColdFusion provides for tags, e.g.
+/// `new Query()`.
+fn is_builtin(name: &str) -> bool {
+    const BUILTIN: &[&str] = &[
+        "dbinfo",
+        "feed",
+        "ftp",
+        "http",
+        "imap",
+        "ldap",
+        "mail",
+        "pdf",
+        "pop",
+        "query",
+        "storedproc",
+    ];
+    BUILTIN.contains(&name.to_ascii_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::FileId;
+
+    use crate::{
+        diagnostics::{
+            diagnostics,
+            tests::{analysis_from_fixture, check_diagnostics},
+            DiagnosticsConfig,
+        },
+        FileRange,
+    };
+
+    #[test]
+    fn unresolved_components() {
+        check_diagnostics(
+            r#"
+//- /app/handlers/Main.cfc
+component extends="Base" implements="IHandler,models.IMissing" {
+    function index() {
+        var user = new models.Users();
+        var service = createObject("component", "models.UserService");
+        var q = new Query();
+        var id = getInstance("models.Missing");
+        return [user, service, q, id];
+    }
+}
+//- /app/handlers/Base.cfc
+component {}
+//- /app/models/IHandler.cfc
+interface {}
+//- /app/models/User.cfc
+component {}
+"#,
+            &[
+                (
+                    "models.IMissing",
+                    "Component `models.IMissing` can't be found",
+                ),
+                ("models.Users", "Component `models.Users` can't be found"),
+                (
+                    "models.UserService",
+                    "Component `models.UserService` can't be found",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn tried_paths() {
+        let analysis = analysis_from_fixture(
+            r#"
+//- /app/handlers/Main.cfc
+component {
+    function index() {
+        return new model.User();
+    }
+}
+//- /app/models/User.cfc
+component {}
+"#,
+        );
+        let diagnostics = diagnostics(&analysis, &DiagnosticsConfig::default(), FileId(0));
+        let related: Vec<(FileRange, &str)> = diagnostics[0]
+            .related
+            .iter()
+            .map(|it| (it.range, it.message.as_str()))
+            .collect();
+        let here = FileRange {
+            file_id: FileId(0),
+            range: diagnostics[0].range,
+        };
+        let there = FileRange {
+            file_id: FileId(1),
+            range: Default::default(),
+        };
+        assert_eq!(
+            related,
+            [
+                (here, "Tried `/app/handlers/model/User.cfc`"),
+                (
+                    here,
+                    "Tried `model/User.cfc` in every folder of the workspace"
+                ),
+                (there, "`/app/models/User.cfc` has the same name"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+};
+
+use crate::{
+    diagnostics::{
+        undefined_variable::{in_query_loop, is_callee, visible_names, IMPLICIT_VARIABLES},
+        Diagnostic, DiagnosticsContext, Severity,
+    },
+    var_scope::{FunctionLike, SCOPES},
+    Symbol, TextEdit,
+};
+
+// Diagnostic: unscoped-variable
+//
+// Reported, when strict scoping is enabled, on variables read without a
+// scope, e.g. `x` instead of `local.x`, `arguments.x` or `variables.x`,
+// with a fix adding the scope the name resolves to. Variables the engine
+// defines, columns of looped queries, `catch` variables and names which
+// don't resolve are left alone.
+pub(crate) fn unscoped_variable(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    if !ctx.config().strict_scoping {
+        return Some(());
+    }
+    let defined = visible_names(ctx);
+    for name_ref in ctx.root().descendants().filter_map(ast::NameRef::cast) {
+        let name = name_ref.text();
+        let key = name.to_ascii_lowercase();
+        if SCOPES.contains(&key.as_str())
+            || IMPLICIT_VARIABLES.contains(&key.as_str())
+            || is_callee(&name_ref)
+            || is_written(&name_ref)
+            || in_query_loop(name_ref.syntax())
+            || is_caught(&name_ref, &key)
+        {
+            continue;
+        }
+        let symbol = Symbol::new(&key);
+        let mut functions = name_ref.syntax().ancestors().filter_map(FunctionLike::cast);
+        let scope = match functions.next() {
+            Some(func) if func.param_names().contains(&symbol) => "arguments",
+            Some(func) if local_names(&func).contains(&symbol) => "local",
+            // The locals of outer functions, which closures can't name.
+            _ if functions.any(|it| it.declared_names().contains(&symbol)) => continue,
+            _ if defined.contains(&key) => "variables",
+            _ => continue,
+        };
+        let range = name_ref.syntax().text_range();
+        acc.push(
+            Diagnostic::new(
+                "unscoped-variable",
+                Severity::Information,
+                format!("`{name}` is read without a scope, as `{scope}.{name}`"),
+                range,
+            )
+            .with_fix(ctx.fix(
+                "add_scope",
+                format!("Prefix with `{scope}.`"),
+                range,
+                TextEdit::insert(range.start(), format!("{scope}.")),
+            )),
+        );
+    }
+    Some(())
+}
+
+/// Names of the local variables of `func`: its `var`s and the names assigned
+/// as `local.x`.
+fn local_names(func: &FunctionLike) -> FxHashSet<Symbol> {
+    let mut res = func.declared_names();
+    for assign in func
+        .own_nodes()
+        .into_iter()
+        .filter_map(ast::AssignExpr::cast)
+    {
+        let Some(ast::Expr::FieldExpr(field)) = assign.lhs() else {
+            continue;
+        };
+        let is_local = match field.receiver() {
+            Some(ast::Expr::NameRef(it)) => it.text().eq_ignore_ascii_case("local"),
+            _ => false,
+        };
+        if let (true, Some(name)) = (is_local, field.field_name()) {
+            res.insert(Symbol::new(&name));
+        }
+    }
+    res
+}
+
+/// Returns `true` if `name_ref` is, or starts, the target of an assignment.
+fn is_written(name_ref: &ast::NameRef) -> bool {
+    let mut node = name_ref.syntax().clone();
+    while let Some(parent) = node.parent() {
+        match parent.kind() {
+            FIELD_EXPR | INDEX_EXPR => node = parent,
+            ASSIGN_EXPR => {
+                return ast::AssignExpr::cast(parent)
+                    .and_then(|it| it.lhs())
+                    .is_some_and(|it| it.syntax() == &node)
+            }
+            // `for (x in xs)`.
+            FOR_IN_STMT => return parent.first_child().as_ref() == Some(&node),
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Returns `true` if `name_ref` reads the variable of an enclosing `catch`.
+fn is_caught(name_ref: &ast::NameRef, key: &str) -> bool {
+    name_ref
+        .syntax()
+        .ancestors()
+        .filter_map(ast::CatchClause::cast)
+        .filter_map(|it| it.name()?.text())
+        .any(|it| it.eq_ignore_ascii_case(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{
+        tests::{check_diagnostics_with_config, check_fix_with_config},
+        DiagnosticsConfig,
+    };
+
+    fn strict() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            strict_scoping: true,
+            ..DiagnosticsConfig::default()
+        }
+    }
+
+    #[test]
+    fn unscoped_reads() {
+        check_diagnostics_with_config(
+            &strict(),
+            r#"
+//- /app/Service.cfc
+component {
+    property name="dsn";
+    function f(id) {
+        var total = 0;
+        local.count = id;
+        try {
+            total = count + variables.dsn.len();
+        } catch (any e) {
+            writeLog(e.message);
+        }
+        return [total, dsn, (x) => x + total];
+    }
+}
+"#,
+            &[
+                ("id", "`id` is read without a scope, as `arguments.id`"),
+                ("count", "`count` is read without a scope, as `local.count`"),
+                ("total", "`total` is read without a scope, as `local.total`"),
+                ("dsn", "`dsn` is read without a scope, as `variables.dsn`"),
+                ("x", "`x` is read without a scope, as `arguments.x`"),
+            ],
+        );
+    }
+
+    #[test]
+    fn add_scope() {
+        check_fix_with_config(
+            &strict(),
+            "Prefix with `arguments.`",
+            r#"
+//- /app/Service.cfc
+component {
+    function f(id) {
+        return id;
+    }
+}
+"#,
+            r#"component {
+    function f(id) {
+        return arguments.id;
+    }
+}"#,
+        );
+    }
+}
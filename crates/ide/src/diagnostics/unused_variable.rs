@@ -0,0 +1,365 @@
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+use crate::{
+    assists::utils::removal_range,
+    change_signature::{change_signature, old_params},
+    component::Component,
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    var_scope::{functions, FunctionLike},
+    Assist, AssistId, AssistKind, FilePosition, ParamChange, TextEdit,
+};
+
+// Diagnostic: unused-variable
+//
+// Reported on `var` and `local.` declarations and on arguments which the
+// function never reads, tagged as unnecessary for clients to fade them out.
+// The fix removes the declaration, keeping an initializer with side effects,
+// or removes the argument from the function and its calls.
+pub(crate) fn unused_variable(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for func in functions(ctx.root()) {
+        let uses = Uses::of(&func);
+        if !uses.all_locals {
+            for local in locals(&func) {
+                if uses.reads.contains(&local.key) {
+                    continue;
+                }
+                let mut diagnostic = Diagnostic::new(
+                    "unused-variable",
+                    Severity::Warning,
+                    format!("Local variable `{}` is never read", local.name),
+                    local.name_range,
+                )
+                .with_unused(true);
+                // Removing the declaration would make later assignments
+                // unscoped.
+                if uses.writes.iter().filter(|it| **it == local.key).count() == local.writes {
+                    diagnostic = diagnostic.with_fix(ctx.fix(
+                        "remove_unused_variable",
+                        format!("Remove declaration of `{}`", local.name),
+                        local.name_range,
+                        remove_local(&local),
+                    ));
+                }
+                acc.push(diagnostic);
+            }
+        }
+        if uses.all_arguments || !has_body(&func) {
+            continue;
+        }
+        let params = old_params(&func);
+        for (idx, param) in params.iter().enumerate() {
+            let key = param.name.to_ascii_lowercase();
+            if key.starts_with('_') || uses.reads.contains(&key) {
+                continue;
+            }
+            let mut diagnostic = Diagnostic::new(
+                "unused-variable",
+                Severity::Hint,
+                format!("Argument `{}` is never used", param.name),
+                param.name_range,
+            )
+            .with_unused(true);
+            let remaining: Vec<ParamChange> = params
+                .iter()
+                .enumerate()
+                .filter(|(it, _)| *it != idx)
+                .map(|(old_index, it)| ParamChange {
+                    old_index: Some(old_index),
+                    name: it.name.clone(),
+                    ..ParamChange::default()
+                })
+                .collect();
+            let position = FilePosition {
+                file_id: ctx.file_id(),
+                offset: func.syntax().text_range().start(),
+            };
+            // Calls which cannot be traced would pass their arguments to the
+            // wrong parameters.
+            let change = change_signature(ctx.analysis(), position, &remaining)
+                .filter(|it| it.unresolved_calls.is_empty());
+            if let Some(change) = change {
+                diagnostic = diagnostic.with_fix(Assist {
+                    id: AssistId("remove_unused_variable", AssistKind::QuickFix),
+                    label: format!("Remove argument `{}`", param.name),
+                    target: param.name_range,
+                    source_change: change.source_change,
+                });
+            }
+            acc.push(diagnostic);
+        }
+    }
+    Some(())
+}
+
+/// How a function uses its variables, including in nested closures.
+struct Uses {
+    /// Names read, unscoped or through `local.` and `arguments.`, in
+    /// lowercase.
+    reads: FxHashSet<String>,
+    /// Names assigned to with `=`, once per assignment.
+    writes: Vec<String>,
+    /// `local` is used as a whole, e.g. returned.
+    all_locals: bool,
+    /// `arguments` is used as a whole, e.g. as an `argumentCollection`.
+    all_arguments: bool,
+}
+
+impl Uses {
+    fn of(func: &FunctionLike) -> Uses {
+        let mut uses = Uses {
+            reads: FxHashSet::default(),
+            writes: Vec::new(),
+            all_locals: false,
+            all_arguments: false,
+        };
+        for name_ref in func.syntax().descendants().filter_map(ast::NameRef::cast) {
+            let key = name_ref.text().to_ascii_lowercase();
+            let field = name_ref
+                .syntax()
+                .parent()
+                .and_then(ast::FieldExpr::cast)
+                .filter(|it| {
+                    it.receiver()
+                        .is_some_and(|it| it.syntax() == name_ref.syntax())
+                });
+            let (key, expr) = match (key.as_str(), field) {
+                ("local" | "arguments", Some(field)) => {
+                    let Some(name) = field.field_name() else {
+                        continue;
+                    };
+                    (name.to_ascii_lowercase(), field.syntax().clone())
+                }
+                ("local", None) => {
+                    uses.all_locals = true;
+                    continue;
+                }
+                ("arguments", None) => {
+                    uses.all_arguments = true;
+                    continue;
+                }
+                _ => (key, name_ref.syntax().clone()),
+            };
+            if is_assigned(&expr) {
+                uses.writes.push(key);
+            } else {
+                uses.reads.insert(key);
+            }
+        }
+        uses
+    }
+}
+
+/// Returns `true` if `expr` is the target of `=`.
+fn is_assigned(expr: &SyntaxNode) -> bool {
+    expr.parent()
+        .and_then(ast::AssignExpr::cast)
+        .filter(|it| it.op().is_some_and(|it| it.kind() == EQ))
+        .and_then(|it| it.lhs())
+        .is_some_and(|it| it.syntax() == expr)
+}
+
+/// A local variable declaration: `var x = ...` or `local.x = ...`.
+struct Local {
+    name: String,
+    key: String,
+    name_range: TextRange,
+    /// The `VAR_DECL` or `ASSIGN_EXPR`.
+    decl: SyntaxNode,
+    initializer: Option<ast::Expr>,
+    /// How many of the function's assignments are the declaration itself.
+    writes: usize,
+}
+
+/// The local variables `func` declares, each at its first declaration.
+fn locals(func: &FunctionLike) -> Vec<Local> {
+    let mut seen = FxHashSet::default();
+    let mut res = Vec::new();
+    for node in func.own_nodes() {
+        let local = if let Some(var) = ast::VarDecl::cast(node.clone()) {
+            // Loop variables are needed by the loop.
+            if matches!(
+                node.parent().map(|it| it.kind()),
+                Some(FOR_IN_STMT | FOR_STMT)
+            ) {
+                continue;
+            }
+            let Some(name) = var.name() else {
+                continue;
+            };
+            Local {
+                name: name.text().to_string(),
+                key: name.text().to_ascii_lowercase(),
+                name_range: name.text_range(),
+                decl: node,
+                initializer: var.initializer(),
+                writes: 0,
+            }
+        } else if let Some(assign) = ast::AssignExpr::cast(node.clone()) {
+            let Some(ast::Expr::FieldExpr(lhs)) = assign.lhs() else {
+                continue;
+            };
+            let is_local = lhs
+                .receiver()
+                .is_some_and(|it| it.syntax().text().eq_ignore_ascii_case("local"));
+            if !is_local || !is_assigned(lhs.syntax()) {
+                continue;
+            }
+            let (Some(name), Some(field)) = (lhs.field_name(), lhs.field()) else {
+                continue;
+            };
+            Local {
+                key: name.to_ascii_lowercase(),
+                name,
+                name_range: field.text_range(),
+                decl: node,
+                initializer: assign.rhs(),
+                writes: 1,
+            }
+        } else {
+            continue;
+        };
+        if seen.insert(local.key.clone()) {
+            res.push(local);
+        }
+    }
+    res
+}
+
+/// Returns `true` for functions with an implementation, whose arguments are
+/// expected to be used.
+fn has_body(func: &FunctionLike) -> bool {
+    let in_interface = Component::enclosing(func.syntax()).is_some_and(|it| it.is_interface());
+    match func {
+        FunctionLike::Decl(it) => it.body().is_some() && !in_interface,
+        FunctionLike::Tag(_) => !in_interface,
+        // Closures get their arguments from the caller, e.g. `arrayMap()`.
+        FunctionLike::Closure(_) | FunctionLike::Arrow(_) => false,
+    }
+}
+
+fn remove_local(local: &Local) -> TextEdit {
+    let has_effects = local.initializer.as_ref().is_some_and(|it| {
+        it.syntax()
+            .descendants()
+            .any(|it| matches!(it.kind(), CALL_EXPR | NEW_EXPR | ASSIGN_EXPR))
+    });
+    if let (true, Some(initializer)) = (has_effects, &local.initializer) {
+        // `var x = f();` → `f();`
+        let range = TextRange::new(
+            local.decl.text_range().start(),
+            initializer.syntax().text_range().start(),
+        );
+        return TextEdit::delete(range);
+    }
+    let stmt = match local.decl.parent() {
+        Some(parent) if parent.kind() == TAG_OPEN => parent.parent(),
+        Some(parent) if parent.kind() == EXPR_STMT => Some(parent),
+        _ => None,
+    };
+    TextEdit::delete(removal_range(stmt.as_ref().unwrap_or(&local.decl)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unused_variables() {
+        check_diagnostics(
+            r#"
+//- /app/Service.cfc
+component {
+    function f(a, b, _c) {
+        var x = 1;
+        var y = a;
+        local.z = 2;
+        local.z = 3;
+        x = 2;
+        return () => y;
+    }
+    function g() {
+        for (var i in [1]) {}
+        var all = 1;
+        return local;
+    }
+    function h(a) {
+        return arguments;
+    }
+}
+"#,
+            &[
+                ("x", "Local variable `x` is never read"),
+                ("z", "Local variable `z` is never read"),
+                ("b", "Argument `b` is never used"),
+            ],
+        );
+    }
+
+    #[test]
+    fn remove_unused_local() {
+        check_fix(
+            "Remove declaration of `x`",
+            r#"
+//- /app/Service.cfc
+component {
+    function f() {
+        var x = 1;
+        return 2;
+    }
+}
+"#,
+            r#"component {
+    function f() {
+        return 2;
+    }
+}"#,
+        );
+        check_fix(
+            "Remove declaration of `x`",
+            r#"
+//- /app/Service.cfc
+<cfcomponent>
+    <cffunction name="f">
+        <cfset local.x = save()>
+    </cffunction>
+</cfcomponent>
+"#,
+            r#"<cfcomponent>
+    <cffunction name="f">
+        <cfset save()>
+    </cffunction>
+</cfcomponent>"#,
+        );
+    }
+
+    #[test]
+    fn remove_unused_argument() {
+        check_fix(
+            "Remove argument `a`",
+            r#"
+//- /app/Service.cfc
+component {
+    function f(a, b) {
+        return b;
+    }
+    function g() {
+        return f(1, 2);
+    }
+}
+"#,
+            r#"component {
+    function f(b) {
+        return b;
+    }
+    function g() {
+        return f(2);
+    }
+}"#,
+        );
+    }
+}
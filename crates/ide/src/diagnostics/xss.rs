@@ -0,0 +1,253 @@
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::{
+    diagnostics::{Diagnostic, DiagnosticsContext, Severity},
+    ty::{infer_expr, variable_path, Ty},
+    TextEdit,
+};
+
+/// Scopes holding values the client controls.
+const USER_SCOPES: &[&str] = &["cgi", "cookie", "form", "url"];
+
+/// Functions encoding a value for output.
+const ENCODERS: &[&str] = &[
+    "encodeforcss",
+    "encodeforhtml",
+    "encodeforhtmlattribute",
+    "encodeforjavascript",
+    "encodeforurl",
+    "encodeforxml",
+    "encodeforxmlattribute",
+    "esapiencode",
+    "htmlcodeformat",
+    "htmleditformat",
+    "jsstringformat",
+    "urlencodedformat",
+    "xmlformat",
+];
+
+// Diagnostic: xss
+//
+// Reported on values from `form`, `url`, `cgi` and `cookie` written into the
+// page without encoding, which lets a crafted request inject markup or
+// scripts. The encoder to use depends on where the value lands: HTML text,
+// an attribute, a `<script>` or a `style`. The fix wraps the value in it.
+pub(crate) fn xss(acc: &mut Vec<Diagnostic>, ctx: &DiagnosticsContext) -> Option<()> {
+    for hash in ctx.root().descendants().filter_map(ast::HashExpr::cast) {
+        let Some(before) = output_before(ctx.root(), &hash) else {
+            continue;
+        };
+        let Some(expr) = hash.expr() else {
+            continue;
+        };
+        let Some(source) = tainted(&expr) else {
+            continue;
+        };
+        let encoder = encoder_for(&before);
+        let expr_range = expr.syntax().text_range();
+        let edit = TextEdit::replace(expr_range, format!("{encoder}({})", expr.syntax().text()));
+        acc.push(
+            Diagnostic::new(
+                "xss",
+                Severity::Error,
+                format!(
+                    "`{source}` comes from the request and is output unencoded, allowing XSS: use `{encoder}()`"
+                ),
+                expr_range,
+            )
+            .with_fix(ctx.fix(
+                "encode_output",
+                format!("Wrap in `{encoder}()`"),
+                expr_range,
+                edit,
+            )),
+        );
+    }
+    Some(())
+}
+
+/// The markup written before `hash`, if `hash` is output: in the body of a
+/// `<cfoutput>`, or in a string passed to `writeOutput()`.
+fn output_before(root: &SyntaxNode, hash: &ast::HashExpr) -> Option<String> {
+    let parent = hash.syntax().parent()?;
+    let start = hash.syntax().text_range().start();
+    let text_before = |node: &SyntaxNode, kind| -> String {
+        node.descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == kind && it.text_range().end() <= start)
+            .map(|it| it.text().to_string())
+            .collect()
+    };
+    match parent.kind() {
+        TAG => {
+            let in_output = parent
+                .ancestors()
+                .filter_map(ast::Tag::cast)
+                .any(|it| it.name_lower() == "cfoutput");
+            in_output.then(|| text_before(root, TEXT))
+        }
+        STRING => {
+            let arg = ast::Arg::cast(parent.parent()?)?;
+            let call = ast::CallExpr::cast(arg.syntax().parent()?.parent()?)?;
+            let is_output = call
+                .callee_name()
+                .is_some_and(|it| it.eq_ignore_ascii_case("writeOutput"));
+            is_output.then(|| text_before(&parent, STRING_TEXT))
+        }
+        _ => None,
+    }
+}
+
+/// The user-controlled variable `expr` outputs, if it isn't encoded.
+pub(crate) fn tainted(expr: &ast::Expr) -> Option<String> {
+    if matches!(infer_expr(expr), Ty::Numeric | Ty::Boolean) {
+        return None;
+    }
+    match expr.clone().unwrapped() {
+        expr @ (ast::Expr::NameRef(_) | ast::Expr::FieldExpr(_)) => {
+            let path = variable_path(&expr)?;
+            let (scope, _) = path.split_once('.')?;
+            USER_SCOPES.contains(&scope).then(|| expr.syntax().text())
+        }
+        ast::Expr::CallExpr(call) => {
+            let name = call.callee_name()?.to_ascii_lowercase();
+            if ENCODERS.contains(&name.as_str()) {
+                return None;
+            }
+            // `trim(url.x)` is as dangerous as `url.x`.
+            call.arg_list()?.args().find_map(|it| tainted(&it.expr()?))
+        }
+        // `"#url.page#.cfm"`.
+        ast::Expr::StringLit(it) => it
+            .syntax()
+            .children()
+            .filter_map(ast::HashExpr::cast)
+            .find_map(|it| tainted(&it.expr()?)),
+        ast::Expr::BinExpr(it) => it
+            .lhs()
+            .and_then(|it| tainted(&it))
+            .or_else(|| tainted(&it.rhs()?)),
+        ast::Expr::TernaryExpr(it) => it
+            .then_expr()
+            .and_then(|it| tainted(&it))
+            .or_else(|| tainted(&it.else_expr()?)),
+        _ => None,
+    }
+}
+
+/// The encoder for a value written after the markup `before`.
+fn encoder_for(before: &str) -> &'static str {
+    let lower = before.to_ascii_lowercase();
+    if lower.rfind("<script") > lower.rfind("</script") {
+        return "encodeForJavaScript";
+    }
+    let Some(tag_start) = lower.rfind('<').filter(|it| Some(*it) > lower.rfind('>')) else {
+        return "encodeForHTML";
+    };
+    // Inside a tag: find the attribute whose value this is.
+    let in_tag = &lower[tag_start..];
+    let mut quote = None;
+    let mut last_eq = None;
+    let mut value_eq = None;
+    for (idx, c) in in_tag.char_indices() {
+        match quote {
+            Some(it) if it == c => quote = None,
+            Some(_) => (),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                value_eq = last_eq;
+            }
+            None if c == '=' => last_eq = Some(idx),
+            None => (),
+        }
+    }
+    let eq = if quote.is_some() { value_eq } else { last_eq };
+    let Some(eq) = eq else {
+        return "encodeForHTMLAttribute";
+    };
+    let attr: String = in_tag[..eq]
+        .trim_end()
+        .chars()
+        .rev()
+        .take_while(|it| it.is_ascii_alphanumeric() || *it == '-')
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    if attr.starts_with("on") {
+        "encodeForJavaScript"
+    } else if attr == "style" {
+        "encodeForCSS"
+    } else if matches!(attr.as_str(), "href" | "src" | "action") && in_tag[eq..].contains('?') {
+        "encodeForURL"
+    } else {
+        "encodeForHTMLAttribute"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::tests::{check_diagnostics, check_fix};
+
+    #[test]
+    fn unencoded_output() {
+        check_diagnostics(
+            r##"
+//- /app/index.cfm
+<cfoutput>
+    <h1>#url.title#</h1>
+    <input value="#form.name#">
+    <a href="/search?q=#url.q#" onclick="track('#cgi.http_referer#')">#encodeForHTML(url.q)#</a>
+    <script>var user = "#trim(cookie.user)#";</script>
+    #val(url.page)#
+</cfoutput>
+<p>#url.ignored#</p>
+"##,
+            &[
+                (
+                    "url.title",
+                    "`url.title` comes from the request and is output unencoded, allowing XSS: use `encodeForHTML()`",
+                ),
+                (
+                    "form.name",
+                    "`form.name` comes from the request and is output unencoded, allowing XSS: use `encodeForHTMLAttribute()`",
+                ),
+                (
+                    "url.q",
+                    "`url.q` comes from the request and is output unencoded, allowing XSS: use `encodeForURL()`",
+                ),
+                (
+                    "cgi.http_referer",
+                    "`cgi.http_referer` comes from the request and is output unencoded, allowing XSS: use `encodeForJavaScript()`",
+                ),
+                (
+                    "trim(cookie.user)",
+                    "`cookie.user` comes from the request and is output unencoded, allowing XSS: use `encodeForJavaScript()`",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn wrap_in_encoder() {
+        check_fix(
+            "Wrap in `encodeForHTMLAttribute()`",
+            r##"
+//- /app/index.cfm
+<cfscript>writeOutput("<input value='#form.name#'>");</cfscript>
+"##,
+            r##"<cfscript>writeOutput("<input value='#encodeForHTMLAttribute(form.name)#'>");</cfscript>"##,
+        );
+    }
+
+    #[test]
+    fn test_encoder_for() {
+        assert_eq!(encoder_for("<div style=\"color: "), "encodeForCSS");
+        assert_eq!(encoder_for("<script>x</script><b>"), "encodeForHTML");
+    }
+}
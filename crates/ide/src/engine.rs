@@ -0,0 +1,25 @@
+//! The CFML engine the code runs on. Engines differ in the tags and
+//! functions they support, which changes between their versions too.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EngineKind {
+    Adobe,
+    Lucee,
+}
+
+/// An engine and its major version, e.g. Adobe ColdFusion 2021 or Lucee 6.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Engine {
+    pub kind: EngineKind,
+    pub version: u32,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            EngineKind::Adobe => write!(f, "Adobe ColdFusion {}", self.version),
+            EngineKind::Lucee => write!(f, "Lucee {}", self.version),
+        }
+    }
+}
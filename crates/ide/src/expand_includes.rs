@@ -0,0 +1,133 @@
+//! Flattens the chain of `<cfinclude>` of a template, to see which files run
+//! as part of it, and in what order, when looking for where a variable comes
+//! from.
+//!
+//! The includes of a file are taken in the order they are written, each one
+//! followed by the includes of the file it resolves to. A file included from
+//! several places shows up each time, but an include leading back to a file
+//! being expanded is left out, as the `recursive-include` diagnostic reports
+//! it.
+use virtual_fs::FileId;
+
+use crate::{
+    resolve::{includes, resolve_include},
+    Analysis, FileRange,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandedIncludes {
+    /// The template, then the files it includes in execution order.
+    pub files: Vec<FileId>,
+    pub unresolved: Vec<UnresolvedInclude>,
+}
+
+/// An include which no file of the workspace resolves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedInclude {
+    pub template: String,
+    /// The range of the template alone, without quotes.
+    pub range: FileRange,
+}
+
+pub(crate) fn expand_includes(analysis: &Analysis, file_id: FileId) -> Option<ExpandedIncludes> {
+    analysis.parse(file_id)?;
+    let mut res = ExpandedIncludes {
+        files: Vec::new(),
+        unresolved: Vec::new(),
+    };
+    expand(analysis, file_id, &mut Vec::new(), &mut res);
+    Some(res)
+}
+
+/// Adds `file_id` and what it includes to `acc`, `stack` being the files
+/// including it.
+fn expand(
+    analysis: &Analysis,
+    file_id: FileId,
+    stack: &mut Vec<FileId>,
+    acc: &mut ExpandedIncludes,
+) {
+    analysis.unwind_if_cancelled();
+    acc.files.push(file_id);
+    let Some(parse) = analysis.parse(file_id) else {
+        return;
+    };
+    stack.push(file_id);
+    for include in includes(&parse.syntax_node()) {
+        match resolve_include(analysis, file_id, &include.template) {
+            Some(target) if stack.contains(&target) => {}
+            Some(target) => expand(analysis, target, stack, acc),
+            None => acc.unresolved.push(UnresolvedInclude {
+                template: include.template,
+                range: FileRange {
+                    file_id,
+                    range: include.range,
+                },
+            }),
+        }
+    }
+    stack.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::tests::analysis_from_fixture;
+
+    #[test]
+    fn expands_in_execution_order() {
+        let analysis = analysis_from_fixture(
+            r#"
+//- /app/index.cfm
+<cfinclude template="inc/header.cfm">
+<cfset title = "Home">
+<cfinclude template="inc/footer.cfm">
+//- /app/inc/header.cfm
+<cfinclude template="vars.cfm">
+<cfinclude template="menu.cfm">
+//- /app/inc/vars.cfm
+<cfinclude template="../index.cfm">
+<cfset user = session.user>
+//- /app/inc/footer.cfm
+<cfinclude template="vars.cfm">
+<cfscript>include "/inc/scripts.cfm";</cfscript>
+"#,
+        );
+        let expanded = expand_includes(&analysis, FileId(0)).unwrap();
+        let paths: Vec<String> = expanded
+            .files
+            .iter()
+            .map(|it| analysis.paths[it].to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "/app/index.cfm",
+                "/app/inc/header.cfm",
+                "/app/inc/vars.cfm",
+                "/app/inc/footer.cfm",
+                "/app/inc/vars.cfm",
+            ]
+        );
+        let unresolved: Vec<(String, FileId, String)> = expanded
+            .unresolved
+            .into_iter()
+            .map(|it| {
+                let text = analysis.file_text(it.range.file_id).unwrap();
+                let range = text[std::ops::Range::from(it.range.range)].to_string();
+                (it.template, it.range.file_id, range)
+            })
+            .collect();
+        assert_eq!(
+            unresolved,
+            [
+                ("menu.cfm".to_string(), FileId(1), "menu.cfm".to_string()),
+                (
+                    "/inc/scripts.cfm".to_string(),
+                    FileId(3),
+                    "/inc/scripts.cfm".to_string()
+                ),
+            ]
+        );
+    }
+}
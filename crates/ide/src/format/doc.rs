@@ -0,0 +1,264 @@
+//! The layout of formatted code as a document, and the printer fitting it
+//! into the line width.
+//!
+//! A [`Doc::Group`] prints flat when the rest of its line fits, each of its
+//! [`Doc::Line`]s being a space; otherwise every one of them starts a new
+//! line. Indentation is only written once a line has content, so that blank
+//! lines stay empty, and the whitespace ending a line is dropped.
+use super::FormatConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Doc {
+    /// Text written as is. Its newlines, e.g. those of a string, are kept,
+    /// without indenting the lines they start.
+    Text(String),
+    /// A space, or a new line when its group breaks.
+    Line,
+    /// Nothing, or a new line when its group breaks.
+    SoftLine,
+    /// A new line, which breaks the groups around it.
+    HardLine,
+    /// Its contents indented one more level.
+    Indent(Vec<Doc>),
+    /// Its contents indented by some more columns.
+    Nest(u32, Vec<Doc>),
+    /// Its contents, flat if they fit on the line.
+    Group(Vec<Doc>),
+    /// Its contents only when its group breaks, e.g. the padding aligning
+    /// the lines it breaks into.
+    IfBreak(Vec<Doc>),
+    Concat(Vec<Doc>),
+}
+
+impl Doc {
+    pub(super) fn text(text: impl Into<String>) -> Doc {
+        Doc::Text(text.into())
+    }
+
+    /// Whether printing the document always starts a new line.
+    pub(super) fn has_hard_line(&self) -> bool {
+        match self {
+            Doc::HardLine => true,
+            Doc::Indent(docs)
+            | Doc::Nest(_, docs)
+            | Doc::Group(docs)
+            | Doc::IfBreak(docs)
+            | Doc::Concat(docs) => docs.iter().any(Doc::has_hard_line),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+pub(super) fn print(config: &FormatConfig, line_ending: &str, doc: &Doc) -> String {
+    let mut printer = Printer {
+        config,
+        line_ending,
+        out: String::new(),
+        column: 0,
+        line_start: true,
+    };
+    printer.print(doc);
+    printer.out
+}
+
+struct Printer<'a> {
+    config: &'a FormatConfig,
+    line_ending: &'a str,
+    out: String,
+    column: u32,
+    /// Whether nothing was written on the current line yet, its indentation
+    /// included.
+    line_start: bool,
+}
+
+impl Printer<'_> {
+    fn print(&mut self, doc: &Doc) {
+        let mut stack = vec![(0, Mode::Break, doc)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(text) => self.text(indent, text),
+                Doc::Line if mode == Mode::Flat => self.text(indent, " "),
+                Doc::SoftLine if mode == Mode::Flat => (),
+                Doc::Line | Doc::SoftLine | Doc::HardLine => self.newline(),
+                Doc::Indent(docs) => {
+                    let indent = indent + self.config.tab_size;
+                    stack.extend(docs.iter().rev().map(|it| (indent, mode, it)));
+                }
+                Doc::Nest(width, docs) => {
+                    let indent = indent + width;
+                    stack.extend(docs.iter().rev().map(|it| (indent, mode, it)));
+                }
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|it| (indent, mode, it))),
+                Doc::IfBreak(_) if mode == Mode::Flat => (),
+                Doc::IfBreak(docs) => stack.extend(docs.iter().rev().map(|it| (indent, mode, it))),
+                Doc::Group(docs) => {
+                    let column = if self.line_start { indent } else { self.column };
+                    let remaining = i64::from(self.config.max_line_width) - i64::from(column);
+                    let mode = if mode == Mode::Flat || fits(remaining, docs, &stack) {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.extend(docs.iter().rev().map(|it| (indent, mode, it)));
+                }
+            }
+        }
+    }
+
+    fn text(&mut self, indent: u32, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if self.line_start {
+            self.out.push_str(&indentation(self.config, indent));
+            self.column = indent;
+            self.line_start = false;
+        }
+        self.out.push_str(text);
+        match text.rfind('\n') {
+            Some(idx) => self.column = width(self.config, &text[idx + 1..]),
+            None => self.column += width(self.config, text),
+        }
+    }
+
+    fn newline(&mut self) {
+        let len = self.out.trim_end_matches([' ', '\t', '\r']).len();
+        self.out.truncate(len);
+        self.out.push_str(self.line_ending);
+        self.column = 0;
+        self.line_start = true;
+    }
+}
+
+/// Whether `docs` fit flat in the `remaining` width, along with what
+/// follows them on their line.
+fn fits(mut remaining: i64, docs: &[Doc], rest: &[(u32, Mode, &Doc)]) -> bool {
+    let mut stack: Vec<(Mode, &Doc)> = docs.iter().rev().map(|it| (Mode::Flat, it)).collect();
+    let mut rest = rest.iter().rev();
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(it) => it,
+            None => match rest.next() {
+                Some(&(_, mode, doc)) => (mode, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => match text.split_once('\n') {
+                Some((line, _)) => return remaining >= line.chars().count() as i64,
+                None => remaining -= text.chars().count() as i64,
+            },
+            Doc::Line if mode == Mode::Flat => remaining -= 1,
+            Doc::SoftLine if mode == Mode::Flat => (),
+            Doc::Line | Doc::SoftLine => return true,
+            // Only a broken group can hold a hard line.
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::IfBreak(_) if mode == Mode::Flat => (),
+            Doc::Indent(docs)
+            | Doc::Nest(_, docs)
+            | Doc::Group(docs)
+            | Doc::IfBreak(docs)
+            | Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|it| (mode, it))),
+        }
+        if remaining < 0 {
+            return false;
+        }
+    }
+}
+
+/// The width of `text` in columns, tabs going to the next tab stop.
+pub(super) fn width(config: &FormatConfig, text: &str) -> u32 {
+    let tab_size = config.tab_size.max(1);
+    text.chars().fold(0, |width, c| match c {
+        '\t' => (width / tab_size + 1) * tab_size,
+        '\r' => width,
+        _ => width + 1,
+    })
+}
+
+/// The indentation `width` columns wide, in the configured style.
+pub(super) fn indentation(config: &FormatConfig, width: u32) -> String {
+    let tab_size = config.tab_size.max(1);
+    if config.insert_spaces {
+        " ".repeat(width as usize)
+    } else {
+        "\t".repeat((width / tab_size) as usize) + &" ".repeat((width % tab_size) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(args: &[&str]) -> Doc {
+        let mut docs = vec![Doc::text("f(")];
+        let mut inner = vec![Doc::SoftLine];
+        for (idx, arg) in args.iter().enumerate() {
+            if idx > 0 {
+                inner.extend([Doc::text(","), Doc::Line]);
+            }
+            inner.push(Doc::text(*arg));
+        }
+        docs.extend([Doc::Indent(inner), Doc::SoftLine, Doc::text(");")]);
+        Doc::Group(docs)
+    }
+
+    #[test]
+    fn test_groups() {
+        let config = FormatConfig {
+            max_line_width: 12,
+            ..FormatConfig::default()
+        };
+        assert_eq!(print(&config, "\n", &call(&["a", "b"])), "f(a, b);");
+        assert_eq!(
+            print(&config, "\n", &call(&["aaa", "bbb", "ccc"])),
+            "f(\n    aaa,\n    bbb,\n    ccc\n);"
+        );
+        // The text after the group counts too.
+        let doc = Doc::Nest(2, vec![call(&["aaa", "b"]), Doc::text(" // x")]);
+        assert_eq!(
+            print(&config, "\n", &doc),
+            "  f(\n      aaa,\n      b\n  ); // x"
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let config = FormatConfig::default();
+        let doc = Doc::Indent(vec![
+            Doc::text("a  "),
+            Doc::HardLine,
+            Doc::HardLine,
+            Doc::text("\"b\nc\""),
+            Doc::Group(vec![Doc::text("x"), Doc::HardLine, Doc::text("y")]),
+        ]);
+        assert_eq!(
+            print(&config, "\r\n", &doc),
+            "    a\r\n\r\n    \"b\nc\"x\r\n    y"
+        );
+    }
+
+    #[test]
+    fn test_if_break() {
+        let config = FormatConfig {
+            max_line_width: 12,
+            ..FormatConfig::default()
+        };
+        let doc = |arg: &str| {
+            Doc::Group(vec![
+                Doc::text("f("),
+                Doc::IfBreak(vec![Doc::text("  ")]),
+                Doc::text(arg),
+                Doc::text(")"),
+            ])
+        };
+        assert_eq!(print(&config, "\n", &doc("a")), "f(a)");
+        assert_eq!(print(&config, "\n", &doc("aaaaaaaaaa")), "f(  aaaaaaaaaa)");
+    }
+}
@@ -0,0 +1,879 @@
+//! Formats a file: the edit giving it the configured layout.
+//!
+//! The tree is printed again as a [`Doc`], which lays out tag bodies, blocks
+//! and statements one level deeper than what contains them and wraps what
+//! doesn't fit in the line width. Markup keeps its own indentation relative
+//! to the tags around it, as the formatter knows nothing about HTML.
+//!
+//! Only whitespace, the case of tag names, keywords, scopes and built-in
+//! functions, the quotes of attributes and the trailing commas of literals
+//! change; the text of strings is never touched, so that formatting cannot
+//! change what a template outputs or what a script computes, but for the
+//! case and layout of the SQL of queries. The formatted text is parsed again
+//! to check this, and left alone if it differs.
+mod doc;
+mod script;
+mod sql;
+mod template;
+
+use rustc_hash::FxHashMap;
+use syntax::{
+    SyntaxElement, SyntaxKind, SyntaxKind::*, SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+use virtual_fs::FileId;
+
+use crate::{ty::Ty, var_scope::SCOPES, Analysis, TextEdit, TextEditBuilder};
+
+use self::doc::Doc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatConfig {
+    /// The width of an indentation level, and of a tab.
+    pub tab_size: u32,
+    /// Whether lines are indented with spaces rather than tabs.
+    pub insert_spaces: bool,
+    /// The width past which tags and lists are wrapped.
+    pub max_line_width: u32,
+    /// How many blank lines in a row are kept.
+    pub max_blank_lines: u32,
+    pub tag_case: Case,
+    pub keyword_case: Case,
+    /// The case of scopes such as `variables` or `form`.
+    pub scope_case: Case,
+    /// The case of built-in functions such as `arrayLen`.
+    pub function_case: Case,
+    /// The number of attributes past which a tag has one per line, however
+    /// short it is.
+    pub max_attributes_per_line: Option<u32>,
+    /// Whether the values of attributes one per line are aligned.
+    pub align_attributes: bool,
+    /// The tags whose attributes are always one per line, like `cfquery`.
+    pub multiline_tags: Vec<String>,
+    pub attribute_quote: Quote,
+    /// Where the opening brace of a block goes.
+    pub brace_style: BraceStyle,
+    /// The spaces around operators like `=` or `&&`. Those which are words,
+    /// like `eq`, always have them.
+    pub operator_spacing: Spacing,
+    /// The space between `if`, `for`, `while`, `switch` or `catch` and their
+    /// parenthesis.
+    pub keyword_spacing: Spacing,
+    /// The number of method calls past which a chain has one per line,
+    /// however short it is.
+    pub max_chained_calls_per_line: Option<u32>,
+    /// The comma after the last item of struct and array literals.
+    pub trailing_comma: TrailingComma,
+    /// Whether the SQL of `<cfquery>` and of `queryExecute()` strings
+    /// spanning lines has a clause per line.
+    pub sql_layout: bool,
+    /// The case of SQL keywords such as `SELECT`.
+    pub sql_keyword_case: Case,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            tab_size: 4,
+            insert_spaces: true,
+            max_line_width: 120,
+            max_blank_lines: 1,
+            tag_case: Case::Lower,
+            keyword_case: Case::Lower,
+            scope_case: Case::Lower,
+            function_case: Case::Preserve,
+            max_attributes_per_line: None,
+            align_attributes: false,
+            multiline_tags: Vec::new(),
+            attribute_quote: Quote::Preserve,
+            brace_style: BraceStyle::SameLine,
+            operator_spacing: Spacing::Preserve,
+            keyword_spacing: Spacing::Preserve,
+            max_chained_calls_per_line: None,
+            trailing_comma: TrailingComma::Preserve,
+            sql_layout: false,
+            sql_keyword_case: Case::Preserve,
+        }
+    }
+}
+
+/// The case names are written in. CFML ignores it, so changing it is safe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Preserve,
+    Lower,
+    Upper,
+}
+
+impl Case {
+    fn apply(self, text: &str) -> String {
+        match self {
+            Case::Preserve => text.to_string(),
+            Case::Lower => text.to_ascii_lowercase(),
+            Case::Upper => text.to_ascii_uppercase(),
+        }
+    }
+}
+
+/// The quotes around the values of attributes. Those containing a quote
+/// keep theirs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quote {
+    Preserve,
+    Double,
+    Single,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceStyle {
+    /// `if (x) {`, with `} else {`.
+    SameLine,
+    /// The brace on a line of its own, as is `else`.
+    NextLine,
+}
+
+/// Whether there are spaces somewhere, or as written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spacing {
+    Preserve,
+    Always,
+    Never,
+}
+
+impl Spacing {
+    fn apply(self, written: bool) -> bool {
+        match self {
+            Spacing::Preserve => written,
+            Spacing::Always => true,
+            Spacing::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingComma {
+    Preserve,
+    /// After the last item of the literals with one item per line, and
+    /// only those.
+    Multiline,
+    Never,
+}
+
+pub(crate) fn format(
+    analysis: &Analysis,
+    config: &FormatConfig,
+    file_id: FileId,
+) -> Option<TextEdit> {
+    let parse = analysis.parse(file_id)?;
+    let text = analysis.file_text(file_id)?;
+    let root = parse.syntax_node();
+    let formatter = Formatter {
+        config,
+        text: &text,
+        sql_tokens: sql::recased_tokens(&root, config.sql_keyword_case),
+    };
+    let doc = formatter.source_file(&root);
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut formatted = doc::print(config, line_ending, &doc);
+    // A file ends with a single newline, unless it is empty.
+    formatted.truncate(formatted.trim_end_matches([' ', '\t', '\r', '\n']).len());
+    if !formatted.is_empty() {
+        formatted.push_str(line_ending);
+    }
+
+    let reparsed = syntax::parse(&formatted);
+    if reparsed.errors().len() > parse.errors().len()
+        || shape(&reparsed.syntax_node()) != shape(&root)
+    {
+        // A bug of the formatter, better not to format at all then.
+        return Some(TextEdit::default());
+    }
+    Some(diff(&text, &formatted))
+}
+
+/// The kinds of the nodes of a tree and the text of its tokens which
+/// formatting keeps, i.e. without the whitespace of the layout and the case
+/// of names and of SQL.
+fn shape(root: &SyntaxNode) -> Vec<(SyntaxKind, String)> {
+    let mut res = Vec::new();
+    for element in root.descendants_with_tokens() {
+        if is_trailing_comma(&element) {
+            continue;
+        }
+        let text = match &element {
+            SyntaxElement::Node(_) => String::new(),
+            SyntaxElement::Token(token) => match token.kind() {
+                WHITESPACE => continue,
+                TEXT | COMMENT | CF_COMMENT => {
+                    let text: String = token.text().split_whitespace().collect();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    text.to_ascii_lowercase()
+                }
+                STRING_TEXT if sql::is_sql_string(&token.parent()) => {
+                    let text: String = token.text().split_whitespace().collect();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    text.to_ascii_lowercase()
+                }
+                KEYWORD | TAG_NAME | IDENT => token.text().to_ascii_lowercase(),
+                QUOTE => "\"".to_string(),
+                _ => token.text().to_string(),
+            },
+        };
+        res.push((element.kind(), text));
+    }
+    res
+}
+
+/// Whether `element` is the comma after the last item of a literal, which
+/// formatting may add or remove.
+fn is_trailing_comma(element: &SyntaxElement) -> bool {
+    let Some(token) = element.as_token().filter(|it| it.kind() == COMMA) else {
+        return false;
+    };
+    let in_literal = matches!(token.parent().kind(), ARRAY_LIT | STRUCT_LIT);
+    let mut next = token.next_sibling_or_token();
+    while let Some(it) = next.as_ref().filter(|it| it.kind().is_trivia()) {
+        next = it.next_sibling_or_token();
+    }
+    in_literal && next.is_some_and(|it| matches!(it.kind(), R_BRACK | R_BRACE))
+}
+
+/// The edit turning `before` into `after`, which differ in whitespace, case
+/// and quotes only, with an indel for each run of changed characters so that the
+/// markers of the client around the others stay in place.
+fn diff(before: &str, after: &str) -> TextEdit {
+    let mut diff = Diff::default();
+    let (mut i, mut j) = (0, 0);
+    loop {
+        let whitespace =
+            |text: &str| text.len() - text.trim_start_matches([' ', '\t', '\r', '\n']).len();
+        let (len_before, len_after) = (whitespace(&before[i..]), whitespace(&after[j..]));
+        diff.whitespace(i, &before[i..i + len_before], &after[j..j + len_after]);
+        (i, j) = (i + len_before, j + len_after);
+        match (before[i..].chars().next(), after[j..].chars().next()) {
+            (None, None) => break,
+            (Some(a), Some(b)) if a == b => (),
+            (Some(a), Some(b))
+                if a.eq_ignore_ascii_case(&b) || matches!((a, b), ('"', '\'') | ('\'', '"')) =>
+            {
+                diff.replace(i, i + a.len_utf8(), b.to_string())
+            }
+            _ => {
+                // Not expected, the rest is replaced then.
+                diff.replace(i, before.len(), after[j..].to_string());
+                break;
+            }
+        }
+        i += before[i..].chars().next().map_or(0, char::len_utf8);
+        j += after[j..].chars().next().map_or(0, char::len_utf8);
+    }
+    diff.finish()
+}
+
+#[derive(Default)]
+struct Diff {
+    builder: TextEditBuilder,
+    /// The last replacement, extended by the next one if they are adjacent.
+    pending: Option<(usize, usize, String)>,
+}
+
+impl Diff {
+    /// Replaces the whitespace `before` at `offset` with that `after`,
+    /// leaving what they start and end with alone.
+    fn whitespace(&mut self, offset: usize, before: &str, after: &str) {
+        let prefix = before
+            .bytes()
+            .zip(after.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (before, after) = (&before[prefix..], &after[prefix..]);
+        let suffix = before
+            .bytes()
+            .rev()
+            .zip(after.bytes().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (before, after) = (
+            &before[..before.len() - suffix],
+            &after[..after.len() - suffix],
+        );
+        if !before.is_empty() || !after.is_empty() {
+            let start = offset + prefix;
+            self.replace(start, start + before.len(), after.to_string());
+        }
+    }
+
+    fn replace(&mut self, start: usize, end: usize, text: String) {
+        match &mut self.pending {
+            Some((_, pending_end, pending_text)) if *pending_end == start => {
+                *pending_end = end;
+                pending_text.push_str(&text);
+            }
+            _ => {
+                self.flush();
+                self.pending = Some((start, end, text));
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some((start, end, text)) = self.pending.take() {
+            let range = TextRange::new(start as u32, end as u32);
+            self.builder.replace(range, text);
+        }
+    }
+
+    fn finish(mut self) -> TextEdit {
+        self.flush();
+        self.builder.finish()
+    }
+}
+
+struct Formatter<'a> {
+    config: &'a FormatConfig,
+    /// The text of the file.
+    text: &'a str,
+    /// The text of the tokens holding SQL with its keywords recased, by
+    /// their offset.
+    sql_tokens: FxHashMap<TextSize, String>,
+}
+
+impl Formatter<'_> {
+    fn source_file(&self, root: &SyntaxNode) -> Doc {
+        if root.children().any(|it| it.kind().is_stmt()) {
+            Doc::Concat(self.statements(root.children_with_tokens(), false))
+        } else {
+            let elements: Vec<_> = root.children_with_tokens().collect();
+            Doc::Concat(self.template_body(&elements, false, None))
+        }
+    }
+
+    /// The text of `token`, in the configured case if it is a name.
+    fn token_text(&self, token: &SyntaxToken) -> String {
+        let text = token.text();
+        if let Some(sql) = self.sql_tokens.get(&token.text_range().start()) {
+            return sql.clone();
+        }
+        match token.kind() {
+            KEYWORD => self.config.keyword_case.apply(text),
+            TAG_NAME if !text.to_ascii_lowercase().starts_with("cf_") => {
+                self.config.tag_case.apply(text)
+            }
+            IDENT if token.parent().kind() == NAME_REF => {
+                let name_ref = token.parent();
+                let is_callee = name_ref
+                    .parent()
+                    .is_some_and(|it| it.kind() == CALL_EXPR && it.first_child() == Some(name_ref));
+                if SCOPES.contains(&text.to_ascii_lowercase().as_str()) {
+                    self.config.scope_case.apply(text)
+                } else if is_callee && Ty::of_builtin(text).is_some() {
+                    self.config.function_case.apply(text)
+                } else {
+                    text.to_string()
+                }
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// The text of `node` with only the case of its names changed, for what
+    /// keeps its layout, like strings.
+    fn verbatim(&self, node: &SyntaxNode) -> String {
+        node.descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .map(|it| self.token_text(&it))
+            .collect()
+    }
+
+    /// A comment, whose lines keep their indentation relative to the line
+    /// it starts on.
+    fn comment(&self, token: &SyntaxToken) -> Doc {
+        let mut lines = token.text().split('\n');
+        let mut docs = vec![Doc::text(lines.next().unwrap_or_default())];
+        let indent = self.indent_at(token.text_range().start() as usize);
+        for line in lines {
+            let code = line.trim_start_matches([' ', '\t']);
+            let width = doc::width(self.config, &line[..line.len() - code.len()]);
+            docs.push(Doc::HardLine);
+            docs.push(Doc::Nest(
+                width.saturating_sub(indent),
+                vec![Doc::text(code)],
+            ));
+        }
+        Doc::Concat(docs)
+    }
+
+    /// The width of the indentation of the line containing `offset`.
+    fn indent_at(&self, offset: usize) -> u32 {
+        let line_start = self.text[..offset].rfind('\n').map_or(0, |it| it + 1);
+        let line = &self.text[line_start..];
+        let code = line.trim_start_matches([' ', '\t']);
+        doc::width(self.config, &line[..line.len() - code.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisHost;
+
+    fn check(config: &FormatConfig, before: &str, after: &str) {
+        let mut host = AnalysisHost::default();
+        host.set_file_text(FileId(0), Some(before.into()));
+        let edit = host.analysis().format(config, FileId(0)).unwrap();
+        let mut text = before.to_string();
+        edit.apply(&mut text);
+        assert_eq!(text, after);
+
+        // Formatting is stable.
+        host.set_file_text(FileId(0), Some(text.as_str().into()));
+        let edit = host.analysis().format(config, FileId(0)).unwrap();
+        assert!(edit.is_empty(), "{edit:?}");
+    }
+
+    /// `body` in `<cfscript>`.
+    fn script(body: &str) -> String {
+        let lines: String = body.lines().map(|it| format!("    {it}\n")).collect();
+        format!("<cfscript>\n{lines}</cfscript>\n")
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        check(
+            &FormatConfig::default(),
+            "<cfoutput>  \nhi   \n<cfscript>   \nx = \"a   \nb\";  \n</cfscript>\n</cfoutput>\n\n\n",
+            "<cfoutput>\n    hi\n    <cfscript>\n        x = \"a   \nb\";\n    </cfscript>\n</cfoutput>\n",
+        );
+        check(&FormatConfig::default(), "<cfset x = 1>", "<cfset x = 1>\n");
+        check(&FormatConfig::default(), "  \n\n", "");
+    }
+
+    #[test]
+    fn test_indentation() {
+        check(
+            &FormatConfig::default(),
+            "component {\n\tfunction f() {\n\t  \treturn 1; // one\t\n\t}\n}\n",
+            "component {\n    function f() {\n        return 1; // one\n    }\n}\n",
+        );
+        let tabs = FormatConfig {
+            tab_size: 2,
+            insert_spaces: false,
+            ..FormatConfig::default()
+        };
+        check(
+            &tabs,
+            "<cfif x>\n  <p>\n     y\n  </p>\n</cfif>\n",
+            "<cfif x>\n\t<p>\n\t\t y\n\t</p>\n</cfif>\n",
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let edit = diff("<CFSET x=1>\n\tY = 2;  \n", "<cfset x = 1>\n    Y = 2;\n");
+        let indels: Vec<_> = edit
+            .iter()
+            .map(|it| (it.delete, it.insert.as_str()))
+            .collect();
+        assert_eq!(
+            indels,
+            [
+                (TextRange::new(1, 6), "cfset"),
+                (TextRange::new(8, 8), " "),
+                (TextRange::new(9, 9), " "),
+                (TextRange::new(12, 13), "    "),
+                (TextRange::new(19, 21), ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        check(
+            &FormatConfig::default(),
+            r##"<cfif x>
+<div>
+  <p>#y#</p>
+</div>
+<CFELSEIF y>
+      <cfset   z  =  1  >
+<cfelse>
+  <pre>
+  keep   
+</pre>
+</cfif>
+"##,
+            r##"<cfif x>
+    <div>
+      <p>#y#</p>
+    </div>
+<cfelseif y>
+    <cfset z = 1>
+<cfelse>
+    <pre>
+  keep   
+</pre>
+</cfif>
+"##,
+        );
+        check(
+            &FormatConfig::default(),
+            "<cfoutput><b>#x#</b></cfoutput>\n\n\n\n<cfparam name=\"x\"  default=\"\" />\n",
+            "<cfoutput><b>#x#</b></cfoutput>\n\n<cfparam name=\"x\" default=\"\" />\n",
+        );
+    }
+
+    #[test]
+    fn test_script() {
+        check(
+            &FormatConfig::default(),
+            r##"component
+{
+    function f( a,b )
+    {
+      if (a) { x = 1; y = 2; }
+      else if (b) return;
+      else
+          z = 3;
+
+
+      switch (a) {
+      case 1: x = 1; break;
+      default:
+      x = 2;
+      }
+      try { f(); } catch (any e) { rethrow; } // done
+    }
+}
+"##,
+            r##"component {
+    function f( a, b ) {
+        if (a) {
+            x = 1;
+            y = 2;
+        } else if (b) return;
+        else
+            z = 3;
+
+        switch (a) {
+            case 1:
+                x = 1;
+                break;
+            default:
+                x = 2;
+        }
+        try {
+            f();
+        } catch (any e) {
+            rethrow;
+        } // done
+    }
+}
+"##,
+        );
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let config = FormatConfig {
+            max_line_width: 40,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            r##"<cfquery name="users" datasource="#dsn#" maxrows="10">
+    SELECT * FROM users
+</cfquery>
+<cfscript>
+    x = someFunction(argumentOne, argumentTwo);
+    ok = aaaaaaaaaaaa && bbbbbbbbbbbb && cccccc;
+    arrayEach(items, function(it) { f(it); });
+    s = {a: 1, b: 2};
+    t = [
+        1, 2
+    ];
+</cfscript>
+"##,
+            r##"<cfquery
+    name="users"
+    datasource="#dsn#"
+    maxrows="10">
+    SELECT * FROM users
+</cfquery>
+<cfscript>
+    x = someFunction(
+        argumentOne,
+        argumentTwo
+    );
+    ok = aaaaaaaaaaaa &&
+        bbbbbbbbbbbb &&
+        cccccc;
+    arrayEach(items, function(it) {
+        f(it);
+    });
+    s = {a: 1, b: 2};
+    t = [
+        1,
+        2
+    ];
+</cfscript>
+"##,
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        let text = r#"<cfquery name="users" datasource='main'>
+    SELECT 1
+</cfquery>
+<cfargument name="id" type="numeric" required>
+<cfparam name='x' default="it's">
+"#;
+        let config = FormatConfig {
+            max_attributes_per_line: Some(2),
+            align_attributes: true,
+            multiline_tags: vec!["CFQUERY".to_string()],
+            attribute_quote: Quote::Double,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            r#"<cfquery
+    name      ="users"
+    datasource="main">
+    SELECT 1
+</cfquery>
+<cfargument
+    name="id"
+    type="numeric"
+    required>
+<cfparam name="x" default="it's">
+"#,
+        );
+        let config = FormatConfig {
+            max_line_width: 30,
+            align_attributes: true,
+            attribute_quote: Quote::Single,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            r#"<cfquery
+    name      ='users'
+    datasource='main'>
+    SELECT 1
+</cfquery>
+<cfargument
+    name='id'
+    type='numeric'
+    required>
+<cfparam
+    name   ='x'
+    default="it's">
+"#,
+        );
+        // A value being typed.
+        let text = "<cfset x = 1>\n<cfinclude template=\"";
+        for config in [FormatConfig::default(), config] {
+            let mut host = AnalysisHost::default();
+            host.set_file_text(FileId(0), Some(text.into()));
+            host.analysis().format(&config, FileId(0)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_braces() {
+        let config = FormatConfig {
+            brace_style: BraceStyle::NextLine,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            "component {\n    function f() {\n        if (a) { b(); } else { c(); }\n        try { d(); } catch (any e) {} finally { e(); }\n    }\n}\n",
+            "component\n{\n    function f()\n    {\n        if (a)\n        {\n            b();\n        }\n        else\n        {\n            c();\n        }\n        try\n        {\n            d();\n        }\n        catch (any e)\n        {}\n        finally\n        {\n            e();\n        }\n    }\n}\n",
+        );
+        check(
+            &FormatConfig::default(),
+            &script("try {\n    d();\n}\ncatch (any e) {\n}\nfinally\n{\n    e();\n}"),
+            &script("try {\n    d();\n} catch (any e) {} finally {\n    e();\n}"),
+        );
+    }
+
+    #[test]
+    fn test_spacing() {
+        let text = &script("if(a==1) x=a - -1;\nwhile (x eq 2) y = a?b : c;\nz = x && y;");
+        let config = FormatConfig {
+            operator_spacing: Spacing::Always,
+            keyword_spacing: Spacing::Always,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            &script("if (a == 1) x = a - -1;\nwhile (x eq 2) y = a ? b : c;\nz = x && y;"),
+        );
+        let config = FormatConfig {
+            operator_spacing: Spacing::Never,
+            keyword_spacing: Spacing::Never,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            &script("if(a==1) x=a- -1;\nwhile(x eq 2) y=a?b:c;\nz=x&&y;"),
+        );
+    }
+
+    #[test]
+    fn test_method_chains() {
+        let text = &script(
+            "users = qb.from(\"users\").where(\"active\", 1).get();\nn = this.items.len().abs();",
+        );
+        check(&FormatConfig::default(), text, text);
+        let config = FormatConfig {
+            max_line_width: 40,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            &script("users = qb.from(\"users\")\n    .where(\"active\", 1)\n    .get();\nn = this.items.len().abs();"),
+        );
+        let config = FormatConfig {
+            max_chained_calls_per_line: Some(1),
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            &script("users = qb.from(\"users\")\n    .where(\"active\", 1)\n    .get();\nn = this.items\n    .len()\n    .abs();"),
+        );
+    }
+
+    #[test]
+    fn test_trailing_comma() {
+        let text = &script("a = [1, 2,];\nb = {\n    x: 1,\n    y: 2\n};\nf(1, 2);");
+        let config = FormatConfig {
+            trailing_comma: TrailingComma::Multiline,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            &script("a = [1, 2];\nb = {\n    x: 1,\n    y: 2,\n};\nf(1, 2);"),
+        );
+        let config = FormatConfig {
+            trailing_comma: TrailingComma::Never,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            &script("a = [1, 2];\nb = {\n    x: 1,\n    y: 2\n};\nf(1, 2);"),
+        );
+    }
+
+    #[test]
+    fn test_case() {
+        let text = "<CFSET VARIABLES.x = arrayLen(Y)>\n<cfscript>\n    IF (Form.a) myFunc();\n</cfscript>\n<CF_Custom>\n";
+        check(
+            &FormatConfig::default(),
+            text,
+            "<cfset variables.x = arrayLen(Y)>\n<cfscript>\n    if (form.a) myFunc();\n</cfscript>\n<CF_Custom>\n",
+        );
+        let config = FormatConfig {
+            tag_case: Case::Upper,
+            keyword_case: Case::Preserve,
+            scope_case: Case::Upper,
+            function_case: Case::Upper,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            "<CFSET VARIABLES.x = ARRAYLEN(Y)>\n<CFSCRIPT>\n    IF (FORM.a) myFunc();\n</CFSCRIPT>\n<CF_Custom>\n",
+        );
+    }
+
+    #[test]
+    fn test_sql() {
+        let text = r##"<cfquery name="q">
+  select u.id, u.name from users u left join roles r on r.id = u.role
+  where u.id = <cfqueryparam value="#id#"> and u.name like '%#name#%' -- name
+  order by u.name
+</cfquery>
+<cfscript>
+    q = queryExecute("select id
+        from users where active = 1", {});
+    n = queryExecute("select count(*) from users");
+</cfscript>
+"##;
+        let config = FormatConfig {
+            sql_keyword_case: Case::Upper,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            r##"<cfquery name="q">
+    SELECT u.id, u.name FROM users u LEFT JOIN roles r ON r.id = u.role
+    WHERE u.id = <cfqueryparam value="#id#"> AND u.name LIKE '%#name#%' -- name
+    ORDER BY u.name
+</cfquery>
+<cfscript>
+    q = queryExecute("SELECT id
+        FROM users WHERE active = 1", {});
+    n = queryExecute("SELECT count(*) FROM users");
+</cfscript>
+"##,
+        );
+        let config = FormatConfig {
+            sql_layout: true,
+            ..FormatConfig::default()
+        };
+        check(
+            &config,
+            text,
+            r##"<cfquery name="q">
+    select u.id, u.name
+    from users u
+    left join roles r on r.id = u.role
+    where u.id = <cfqueryparam value="#id#">
+        and u.name like '%#name#%' -- name
+    order by u.name
+</cfquery>
+<cfscript>
+    q = queryExecute("
+        select id
+        from users
+        where active = 1
+    ", {});
+    n = queryExecute("select count(*) from users");
+</cfscript>
+"##,
+        );
+        // Bodies with other tags keep their lines.
+        let text = "<cfquery name=\"q\">\n    select 1\n    <cfif x>where   x</cfif>\n</cfquery>\n";
+        check(&config, text, text);
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let text = "x = 1;\n\n\n\ny = 2;\n";
+        check(&FormatConfig::default(), text, "x = 1;\n\ny = 2;\n");
+        let config = FormatConfig {
+            max_blank_lines: 2,
+            ..FormatConfig::default()
+        };
+        check(&config, text, "x = 1;\n\n\ny = 2;\n");
+        let config = FormatConfig {
+            max_blank_lines: 0,
+            ..FormatConfig::default()
+        };
+        check(&config, text, "x = 1;\ny = 2;\n");
+    }
+}
@@ -0,0 +1,467 @@
+//! The layout of cfscript: statements one per line, the contents of braces
+//! one level deeper, and the lists and operators which wrap when they don't
+//! fit.
+//!
+//! Within a statement, tokens are separated by a single space where the file
+//! has whitespace between them, and by nothing otherwise, unless the spacing
+//! of operators and keywords is set.
+use syntax::{SyntaxElement, SyntaxKind::*, SyntaxNode};
+
+use super::{BraceStyle, Doc, Formatter, Spacing, TrailingComma};
+
+/// The keywords whose parenthesis follow the keyword spacing.
+const SPACED_KEYWORDS: &[&str] = &["catch", "for", "if", "switch", "while"];
+
+impl Formatter<'_> {
+    /// Lays out statements one per line, keeping the blank lines between
+    /// them and the comments on their lines. If `after_open`, they follow an
+    /// opening brace or tag, and start on a new line.
+    pub(super) fn statements(
+        &self,
+        elements: impl Iterator<Item = SyntaxElement>,
+        after_open: bool,
+    ) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        let mut newlines = 0;
+        let mut first = true;
+        let mut after_block_comment = false;
+        for element in elements {
+            if let SyntaxElement::Token(token) = &element {
+                if token.kind() == WHITESPACE {
+                    newlines += token.text().matches('\n').count();
+                    continue;
+                }
+            }
+            let is_comment = element.kind() == COMMENT;
+            if first && !after_open {
+                // The start of the file.
+            } else if newlines == 0 && (is_comment || after_block_comment) {
+                docs.push(Doc::text(" "));
+            } else {
+                docs.push(Doc::HardLine);
+                if !first {
+                    let blank_lines = newlines.saturating_sub(1);
+                    let blank_lines = blank_lines.min(self.config.max_blank_lines as usize);
+                    docs.extend((0..blank_lines).map(|_| Doc::HardLine));
+                }
+            }
+            after_block_comment = element
+                .as_token()
+                .is_some_and(|it| it.kind() == COMMENT && !it.text().starts_with("//"));
+            docs.push(self.element(&element));
+            newlines = 0;
+            first = false;
+        }
+        docs
+    }
+
+    pub(super) fn node(&self, node: &SyntaxNode) -> Doc {
+        match node.kind() {
+            BLOCK | SWITCH_STMT => self.braced(node),
+            CASE_CLAUSE | DEFAULT_CLAUSE => self.case_clause(node),
+            STRING if self.config.sql_layout => self
+                .sql_string(node)
+                .unwrap_or_else(|| Doc::text(self.verbatim(node))),
+            STRING | HASH_EXPR | ERROR => Doc::text(self.verbatim(node)),
+            ARG_LIST | PARAM_LIST | ARRAY_LIT | STRUCT_LIT => self.list(node),
+            BIN_EXPR => self.bin_expr(node),
+            CALL_EXPR | FIELD_EXPR | INDEX_EXPR => self
+                .method_chain(node)
+                .unwrap_or_else(|| Doc::Concat(self.elements(node.children_with_tokens()))),
+            _ => Doc::Concat(self.elements(node.children_with_tokens())),
+        }
+    }
+
+    fn element(&self, element: &SyntaxElement) -> Doc {
+        match element {
+            SyntaxElement::Token(token) if token.kind() == COMMENT => self.comment(token),
+            SyntaxElement::Token(token) => Doc::text(self.token_text(token)),
+            SyntaxElement::Node(node) => self.node(node),
+        }
+    }
+
+    /// Lays out the parts of a statement or expression.
+    fn elements(&self, elements: impl Iterator<Item = SyntaxElement>) -> Vec<Doc> {
+        let mut docs = Vec::new();
+        let mut prev: Option<SyntaxElement> = None;
+        let mut after_line_comment = false;
+        let (mut space, mut newline) = (false, false);
+        for element in elements {
+            let kind = element.kind();
+            if let SyntaxElement::Token(token) = &element {
+                if kind == WHITESPACE {
+                    space = true;
+                    newline |= token.text().contains('\n');
+                    continue;
+                }
+            }
+            let doc = self.element(&element);
+            if let Some(prev_element) = &prev {
+                let prev_kind = prev_element.kind();
+                if after_line_comment {
+                    docs.push(Doc::HardLine);
+                } else if kind == BLOCK || (prev_kind == BLOCK && kind == KEYWORD) {
+                    docs.push(self.before_brace());
+                } else if matches!(kind, ELSE_CLAUSE | CATCH_CLAUSE | FINALLY_CLAUSE) {
+                    docs.push(match ends_with_block(prev_element) {
+                        true => self.before_brace(),
+                        false => Doc::HardLine,
+                    });
+                } else if kind.is_stmt() && kind != VAR_DECL && newline {
+                    // The body of `if`, `for` or `while` on its own line.
+                    docs.push(Doc::Indent(vec![Doc::HardLine, doc]));
+                    prev = Some(element);
+                    (space, newline) = (false, false);
+                    continue;
+                } else if self.space_between(prev_element, &element, space) {
+                    docs.push(Doc::text(" "));
+                }
+            }
+            after_line_comment = kind == COMMENT && element.to_string().starts_with("//");
+            docs.push(doc);
+            prev = Some(element);
+            (space, newline) = (false, false);
+        }
+        docs
+    }
+
+    /// What separates an opening brace, or what follows a closing one like
+    /// `else`, from what comes before.
+    fn before_brace(&self) -> Doc {
+        match self.config.brace_style {
+            BraceStyle::SameLine => Doc::text(" "),
+            BraceStyle::NextLine => Doc::HardLine,
+        }
+    }
+
+    /// Whether there is a space between `prev` and `next`, `written` telling
+    /// whether the file has one.
+    fn space_between(&self, prev: &SyntaxElement, next: &SyntaxElement, written: bool) -> bool {
+        let is_keyword = prev.as_token().is_some_and(|it| {
+            it.kind() == KEYWORD
+                && SPACED_KEYWORDS.contains(&it.text().to_ascii_lowercase().as_str())
+        });
+        if is_keyword && matches!(next.kind(), PAREN_EXPR | L_PAREN) {
+            return self.config.keyword_spacing.apply(written);
+        }
+        if is_operator(prev) || is_operator(next) {
+            return self.operator_space(prev, next, written);
+        }
+        written
+    }
+
+    /// Whether there is a space between an operator and its operand.
+    /// Operators which are words, or would run into an operator of their
+    /// operand, keep the space.
+    fn operator_space(&self, prev: &SyntaxElement, next: &SyntaxElement, written: bool) -> bool {
+        let text = |element: &SyntaxElement| element.to_string();
+        let (prev, next) = (text(prev), text(next));
+        let is_word = |text: &str| text.chars().any(char::is_alphanumeric);
+        let is_operator_char = |c: Option<char>| c.is_some_and(|c| "+-*/%&|<>=!?:^".contains(c));
+        let needs_space = (is_operator_char(prev.chars().last())
+            && is_operator_char(next.chars().next()))
+            || (is_word(&prev) && is_word(&next) && written);
+        match self.config.operator_spacing {
+            Spacing::Never if needs_space => written,
+            spacing => spacing.apply(written),
+        }
+    }
+
+    /// A block, or a statement ending with braces like `switch`. The
+    /// statements in the braces go one level deeper.
+    fn braced(&self, node: &SyntaxNode) -> Doc {
+        let children: Vec<_> = node.children_with_tokens().collect();
+        let Some(open) = children.iter().position(|it| it.kind() == L_BRACE) else {
+            return Doc::Concat(self.elements(children.into_iter()));
+        };
+        let mut docs = self.elements(children[..open].iter().cloned());
+        if !docs.is_empty() {
+            docs.push(self.before_brace());
+        }
+        docs.push(Doc::text("{"));
+        let closed = open + 1 < children.len() && children.last().unwrap().kind() == R_BRACE;
+        let end = children.len() - usize::from(closed);
+        let statements = self.statements(children[open + 1..end].iter().cloned(), true);
+        if !statements.is_empty() {
+            docs.push(Doc::Indent(statements));
+            docs.push(Doc::HardLine);
+        }
+        if closed {
+            docs.push(Doc::text("}"));
+        }
+        Doc::Concat(docs)
+    }
+
+    /// A `case` or `default` of a `switch`, whose statements go one level
+    /// deeper.
+    fn case_clause(&self, node: &SyntaxNode) -> Doc {
+        let children: Vec<_> = node.children_with_tokens().collect();
+        let end = children
+            .iter()
+            .position(|it| it.kind() == COLON)
+            .map_or(children.len(), |it| it + 1);
+        let mut docs = self.elements(children[..end].iter().cloned());
+        let statements = self.statements(children[end..].iter().cloned(), true);
+        if !statements.is_empty() {
+            docs.push(Doc::Indent(statements));
+        }
+        Doc::Concat(docs)
+    }
+
+    /// Arguments, parameters or the items of a literal, on one line if they
+    /// fit and one per line otherwise. Literals written over several lines
+    /// stay so.
+    fn list(&self, node: &SyntaxNode) -> Doc {
+        let children: Vec<_> = node.children_with_tokens().collect();
+        let mut open = String::new();
+        let mut start = 0;
+        while let Some(SyntaxElement::Token(token)) = children.get(start) {
+            match token.kind() {
+                L_PAREN | L_BRACK | L_BRACE if start == 0 => (),
+                // `[:` of an ordered struct.
+                COLON if start == 1 => (),
+                _ => break,
+            }
+            open.push_str(token.text());
+            start += 1;
+        }
+        let closed = children.len() > start
+            && matches!(children.last().unwrap().kind(), R_PAREN | R_BRACK | R_BRACE);
+        let end = children.len() - usize::from(closed);
+        let close = if closed {
+            children.last().unwrap().to_string()
+        } else {
+            String::new()
+        };
+        let items = &children[start..end];
+
+        let padded = items.first().is_some_and(|it| it.kind() == WHITESPACE);
+        let expanded = matches!(node.kind(), STRUCT_LIT | ARRAY_LIT)
+            && items
+                .first()
+                .is_some_and(|it| it.kind() == WHITESPACE && it.to_string().contains('\n'));
+        let (line, padding) = match (expanded, padded) {
+            (true, _) => (Doc::HardLine, Doc::HardLine),
+            (false, true) => (Doc::Line, Doc::Line),
+            (false, false) => (Doc::Line, Doc::SoftLine),
+        };
+
+        let is_literal = matches!(node.kind(), STRUCT_LIT | ARRAY_LIT);
+        let last_item = items
+            .iter()
+            .rposition(|it| !it.kind().is_trivia() && it.kind() != COMMA);
+        let has_trailing_comma =
+            last_item.is_some_and(|last| items[last + 1..].iter().any(|it| it.kind() == COMMA));
+        let trailing_comma = || Doc::IfBreak(vec![Doc::text(",")]);
+
+        let mut docs = Vec::new();
+        let mut item_docs = Vec::new();
+        let mut has_comments = false;
+        let mut separator = Some(padding.clone());
+        let mut newline = false;
+        for (idx, element) in items.iter().enumerate() {
+            match element.kind() {
+                WHITESPACE => newline |= element.to_string().contains('\n'),
+                COMMA if is_literal && last_item.is_some_and(|last| idx > last) => {
+                    match self.config.trailing_comma {
+                        TrailingComma::Preserve => docs.push(Doc::text(",")),
+                        TrailingComma::Multiline => docs.push(trailing_comma()),
+                        TrailingComma::Never => (),
+                    }
+                }
+                COMMA => {
+                    docs.push(Doc::text(","));
+                    separator = Some(line.clone());
+                }
+                COMMENT => {
+                    has_comments = true;
+                    docs.push(if newline {
+                        Doc::HardLine
+                    } else {
+                        Doc::text(" ")
+                    });
+                    docs.push(self.element(element));
+                    separator = Some(if element.to_string().starts_with("//") {
+                        Doc::HardLine
+                    } else {
+                        Doc::text(" ")
+                    });
+                }
+                _ => {
+                    let doc = self.element(element);
+                    docs.extend(separator.take());
+                    docs.push(doc.clone());
+                    item_docs.push(doc);
+                    let add_comma = is_literal
+                        && !has_trailing_comma
+                        && last_item == Some(idx)
+                        && self.config.trailing_comma == TrailingComma::Multiline;
+                    if add_comma {
+                        docs.push(trailing_comma());
+                    }
+                }
+            }
+            if element.kind() != WHITESPACE {
+                newline = false;
+            }
+        }
+        if item_docs.is_empty() && !has_comments {
+            return Doc::text(open + &close);
+        }
+
+        // A closure, literal or SQL string spanning lines as the first or last
+        // argument starts on the line of the call.
+        let spanning = item_docs.iter().filter(|it| it.has_hard_line()).count();
+        let hugged = node.kind() == ARG_LIST
+            && !has_comments
+            && spanning == 1
+            && (item_docs[0].has_hard_line() || item_docs.last().is_some_and(Doc::has_hard_line));
+        if hugged {
+            let mut docs = vec![Doc::text(open)];
+            for (idx, doc) in item_docs.into_iter().enumerate() {
+                if idx > 0 {
+                    docs.push(Doc::text(", "));
+                }
+                docs.push(doc);
+            }
+            docs.push(Doc::text(close));
+            return Doc::Concat(docs);
+        }
+
+        let closing = match separator {
+            // After a line comment.
+            Some(Doc::HardLine) => Doc::HardLine,
+            _ => padding,
+        };
+        Doc::Group(vec![
+            Doc::text(open),
+            Doc::Indent(docs),
+            closing,
+            Doc::text(close),
+        ])
+    }
+
+    /// A chain of binary operators, which breaks after them when it doesn't
+    /// fit.
+    fn bin_expr(&self, node: &SyntaxNode) -> Doc {
+        let mut parts = Vec::new();
+        self.bin_parts(node, &mut parts);
+        let rest = parts.split_off(parts.len().min(1));
+        Doc::Group(vec![Doc::Concat(parts), Doc::Indent(rest)])
+    }
+
+    fn bin_parts(&self, node: &SyntaxNode, parts: &mut Vec<Doc>) {
+        let mut prev: Option<SyntaxElement> = None;
+        let mut space = false;
+        let mut after_line_comment = false;
+        for element in node.children_with_tokens() {
+            let kind = element.kind();
+            if kind == WHITESPACE {
+                space = true;
+                continue;
+            }
+            match (&element, &prev) {
+                // The left operand continues the chain.
+                (SyntaxElement::Node(node), None) if kind == BIN_EXPR => {
+                    self.bin_parts(node, parts)
+                }
+                (_, None) => parts.push(self.element(&element)),
+                (_, Some(prev)) => {
+                    let space = self.space_between(prev, &element, space);
+                    let after_operator = prev.as_token().is_some_and(|it| !it.kind().is_trivia());
+                    if after_line_comment {
+                        parts.push(Doc::HardLine);
+                    } else if after_operator && element.as_node().is_some() {
+                        parts.push(if space { Doc::Line } else { Doc::SoftLine });
+                    } else if space {
+                        parts.push(Doc::text(" "));
+                    }
+                    parts.push(self.element(&element));
+                }
+            }
+            space = false;
+            after_line_comment = kind == COMMENT && element.to_string().starts_with("//");
+            prev = Some(element);
+        }
+    }
+
+    /// A chain of method calls, like `qb.from("users").where(...).get()`,
+    /// which breaks before each call when it doesn't fit. A name keeps the
+    /// first call on its line.
+    fn method_chain(&self, node: &SyntaxNode) -> Option<Doc> {
+        // The calls, accesses and indexes from the innermost.
+        let mut spine = Vec::new();
+        let mut object = node.clone();
+        while matches!(object.kind(), CALL_EXPR | FIELD_EXPR | INDEX_EXPR) {
+            let first = object.first_child()?;
+            if object.first_child_or_token()?.as_node() != Some(&first)
+                || object
+                    .children_with_tokens()
+                    .any(|it| it.kind().is_trivia() && it.kind() != WHITESPACE)
+            {
+                return None;
+            }
+            spine.push(object);
+            object = first;
+        }
+        spine.reverse();
+        let is_method = |idx: usize| {
+            spine[idx].kind() == FIELD_EXPR
+                && spine.get(idx + 1).is_some_and(|it| it.kind() == CALL_EXPR)
+        };
+        let calls = (0..spine.len()).filter(|&idx| is_method(idx)).count();
+        if calls < 2 {
+            return None;
+        }
+
+        let mut head = vec![self.node(&object)];
+        let mut links: Vec<Vec<Doc>> = Vec::new();
+        for (idx, node) in spine.iter().enumerate() {
+            if is_method(idx) {
+                links.push(Vec::new());
+            }
+            let suffix = self.elements(node.children_with_tokens().skip(1));
+            match links.last_mut() {
+                Some(link) => link.extend(suffix),
+                None => head.extend(suffix),
+            }
+        }
+        if object.kind() == NAME_REF && head.len() == 1 {
+            head.extend(links.remove(0));
+        }
+        let forced = self
+            .config
+            .max_chained_calls_per_line
+            .is_some_and(|max| calls > max as usize);
+        let links = links
+            .into_iter()
+            .enumerate()
+            .flat_map(|(idx, link)| match idx {
+                // Breaks the group.
+                0 if forced => [Doc::HardLine, Doc::Concat(link)],
+                _ => [Doc::SoftLine, Doc::Concat(link)],
+            })
+            .collect();
+        head.push(Doc::Indent(links));
+        Some(Doc::Group(head))
+    }
+}
+
+/// Whether `element` ends with the closing brace of a block, like a `catch`
+/// followed by `finally`.
+fn ends_with_block(element: &SyntaxElement) -> bool {
+    let last = match element {
+        SyntaxElement::Node(node) => node.last_token(),
+        SyntaxElement::Token(_) => None,
+    };
+    last.is_some_and(|it| it.kind() == R_BRACE && it.parent().kind() == BLOCK)
+}
+
+/// Whether `element` is the operator of a binary expression, an assignment
+/// or a ternary.
+fn is_operator(element: &SyntaxElement) -> bool {
+    element.as_token().is_some_and(|it| {
+        !it.kind().is_trivia()
+            && matches!(it.parent().kind(), BIN_EXPR | ASSIGN_EXPR | TERNARY_EXPR)
+    })
+}
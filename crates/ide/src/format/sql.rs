@@ -0,0 +1,438 @@
+//! The SQL of `<cfquery>` bodies and of the strings passed to
+//! `queryExecute()`.
+//!
+//! Its keywords can be recased, and it can be laid out with a clause per
+//! line, continuation lines being one level deeper. Only the whitespace
+//! between SQL tokens changes: literals, quoted identifiers and comments are
+//! kept, as are the `#...#` interpolations and `<cfqueryparam>` tags.
+use rustc_hash::FxHashMap;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextSize,
+};
+
+use super::{Case, Doc, Formatter};
+
+/// Stands for an interpolation or a tag in SQL while it is tokenized.
+const PLACEHOLDER: char = '\u{E000}';
+
+const KEYWORDS: &[&str] = &[
+    "ALL",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CROSS",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "ELSE",
+    "END",
+    "EXCEPT",
+    "EXISTS",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "RIGHT",
+    "SELECT",
+    "SET",
+    "THEN",
+    "TOP",
+    "UNION",
+    "UPDATE",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+];
+
+/// The keywords starting a line, unless they are nested in parentheses.
+const CLAUSES: &[&str] = &[
+    "CROSS",
+    "DELETE",
+    "EXCEPT",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "HAVING",
+    "INNER",
+    "INSERT",
+    "INTERSECT",
+    "JOIN",
+    "LEFT",
+    "LIMIT",
+    "OFFSET",
+    "ORDER",
+    "RIGHT",
+    "SELECT",
+    "SET",
+    "UNION",
+    "UPDATE",
+    "VALUES",
+    "WHERE",
+];
+
+/// The keywords continuing a join, e.g. `LEFT OUTER JOIN`.
+const JOIN_MODIFIERS: &[&str] = &["CROSS", "FULL", "INNER", "LEFT", "OUTER", "RIGHT"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Whitespace,
+    Word,
+    /// A string literal or quoted identifier.
+    Quoted,
+    Comment,
+    Punct,
+}
+
+/// Splits SQL into tokens, keeping each literal and comment whole.
+fn tokenize(sql: &str) -> Vec<(Kind, &str)> {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$' | '@');
+    let mut tokens = Vec::new();
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_whitespace() {
+            let len = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            tokens.push((Kind::Whitespace, &rest[..len]));
+            len
+        } else if rest.starts_with("--") {
+            let len = rest.find(['\r', '\n']).unwrap_or(rest.len());
+            tokens.push((Kind::Comment, &rest[..len]));
+            len
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let len = comment.find("*/").map_or(rest.len(), |it| it + 4);
+            tokens.push((Kind::Comment, &rest[..len]));
+            len
+        } else if matches!(c, '\'' | '"' | '`' | '[') {
+            let end = if c == '[' { ']' } else { c };
+            let mut len = rest.len();
+            let mut chars = rest.char_indices().skip(1).peekable();
+            while let Some((idx, it)) = chars.next() {
+                if it != end {
+                    continue;
+                }
+                // A doubled quote is escaped.
+                if end != ']' && chars.next_if(|&(_, it)| it == end).is_some() {
+                    continue;
+                }
+                len = idx + 1;
+                break;
+            }
+            tokens.push((Kind::Quoted, &rest[..len]));
+            len
+        } else if is_word(c) {
+            let len = rest.find(|c: char| !is_word(c)).unwrap_or(rest.len());
+            tokens.push((Kind::Word, &rest[..len]));
+            len
+        } else {
+            tokens.push((Kind::Punct, &rest[..c.len_utf8()]));
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// `sql` with its keywords in `case`. Words after `.` or `:` are names.
+fn recase(sql: &str, case: Case) -> String {
+    let mut res = String::with_capacity(sql.len());
+    let mut prev = "";
+    for (kind, text) in tokenize(sql) {
+        let is_name = matches!(prev, "." | ":");
+        if kind == Kind::Word && !is_name && KEYWORDS.contains(&text.to_ascii_uppercase().as_str())
+        {
+            res.push_str(&case.apply(text));
+        } else {
+            res.push_str(text);
+        }
+        prev = text;
+    }
+    res
+}
+
+/// Splits `sql` into lines, each starting a clause or, if `true`, continuing
+/// one. `AND` and `OR` continue a clause, as do the lines `sql` already
+/// has.
+fn layout(sql: &str) -> Vec<(bool, String)> {
+    let mut lines: Vec<(bool, String)> = Vec::new();
+    let mut depth = 0u32;
+    let mut newline = false;
+    let mut space = false;
+    let mut prev = "";
+    let mut prev_word = String::new();
+    let mut in_between = false;
+    let mut after_line_comment = false;
+    let tokens = tokenize(sql);
+    for (idx, &(kind, text)) in tokens.iter().enumerate() {
+        if kind == Kind::Whitespace {
+            newline |= text.contains('\n');
+            space = true;
+            continue;
+        }
+        // Words after `.` or `:` are names.
+        let word = match kind {
+            Kind::Word if !matches!(prev, "." | ":") => text.to_ascii_uppercase(),
+            _ => String::new(),
+        };
+        // `LEFT(name, 1)` is a function.
+        let is_call = matches!(word.as_str(), "LEFT" | "RIGHT")
+            && tokens[idx + 1..]
+                .iter()
+                .find(|(kind, _)| *kind != Kind::Whitespace)
+                .is_some_and(|(_, text)| *text == "(");
+        let starts_clause = depth == 0
+            && CLAUSES.contains(&word.as_str())
+            && !is_call
+            && !(JOIN_MODIFIERS.contains(&prev_word.as_str())
+                && JOIN_MODIFIERS.contains(&word.as_str()))
+            && !(word == "JOIN" && JOIN_MODIFIERS.contains(&prev_word.as_str()))
+            && !(word == "FROM" && prev_word == "DELETE");
+        let is_logic = depth == 0 && (word == "OR" || word == "AND" && !in_between);
+        match lines.last_mut() {
+            None => lines.push((false, String::new())),
+            Some(_) if starts_clause => lines.push((false, String::new())),
+            Some(_) if is_logic || newline || after_line_comment => {
+                lines.push((true, String::new()))
+            }
+            Some((_, line)) if space => line.push(' '),
+            Some(_) => (),
+        }
+        lines.last_mut().unwrap().1.push_str(text);
+
+        match text {
+            "(" => depth += 1,
+            ")" => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        if word == "BETWEEN" {
+            in_between = true;
+        } else if word == "AND" {
+            in_between = false;
+        }
+        prev = text;
+        prev_word = word;
+        after_line_comment = kind == Kind::Comment && text.starts_with("--");
+        (newline, space) = (false, false);
+    }
+    lines
+}
+
+/// The text of the tokens holding SQL, with its keywords in `case`, by the
+/// offset of the token.
+pub(super) fn recased_tokens(root: &SyntaxNode, case: Case) -> FxHashMap<TextSize, String> {
+    let mut res = FxHashMap::default();
+    if case == Case::Preserve {
+        return res;
+    }
+    for node in root.descendants() {
+        let elements: Vec<_> = match node.kind() {
+            TAG if ast::Tag::cast(node.clone()).is_some_and(|it| it.name_lower() == "cfquery") => {
+                node.children_with_tokens()
+                    .skip(1)
+                    .filter(|it| it.kind() != TAG_CLOSE)
+                    .flat_map(|it| match it {
+                        SyntaxElement::Node(node) => node.descendants_with_tokens().collect(),
+                        token => vec![token],
+                    })
+                    .filter_map(SyntaxElement::into_token)
+                    .collect()
+            }
+            STRING if is_sql_string(&node) => node
+                .children_with_tokens()
+                .filter_map(SyntaxElement::into_token)
+                .collect(),
+            _ => continue,
+        };
+        let mut sql = String::new();
+        let mut texts = Vec::new();
+        for token in elements {
+            match token.kind() {
+                TEXT | STRING_TEXT if !token.text().contains(PLACEHOLDER) => {
+                    let start = sql.len();
+                    sql.push_str(token.text());
+                    texts.push((token.text_range().start(), start..sql.len()));
+                }
+                _ if sql.ends_with(PLACEHOLDER) => (),
+                _ => sql.push(PLACEHOLDER),
+            }
+        }
+        // Recasing keeps the length of the text, being ASCII only.
+        let sql = recase(&sql, case);
+        res.extend(
+            texts
+                .into_iter()
+                .map(|(offset, range)| (offset, sql[range].to_string())),
+        );
+    }
+    res
+}
+
+/// Whether `node` is the SQL string of a `queryExecute()` call.
+pub(super) fn is_sql_string(node: &SyntaxNode) -> bool {
+    node.kind() == STRING && crate::sql::sql_string_call(node).is_some()
+}
+
+impl Formatter<'_> {
+    /// The body of a `<cfquery>` laid out as SQL, one level deeper than the
+    /// tag, if it only holds SQL, interpolations and `<cfqueryparam>` tags.
+    pub(super) fn sql_body(&self, body: &[SyntaxElement]) -> Option<Vec<Doc>> {
+        let mut sql = String::new();
+        let mut items = Vec::new();
+        for element in body {
+            match element {
+                SyntaxElement::Token(token) if token.kind() == TEXT => {
+                    sql.push_str(&self.token_text(token))
+                }
+                SyntaxElement::Token(token) if token.kind() == CF_COMMENT => {
+                    items.push(self.comment(token))
+                }
+                SyntaxElement::Node(node) if is_query_param(node) || node.kind() == HASH_EXPR => {
+                    items.push(Doc::text(self.verbatim(node)))
+                }
+                _ => return None,
+            }
+            if element.kind() != TEXT {
+                sql.push(PLACEHOLDER);
+            }
+        }
+        let lines = self.sql_lines(&sql, items)?;
+        Some(vec![Doc::Indent(lines), Doc::HardLine])
+    }
+
+    /// A `queryExecute()` string spanning several lines with its SQL laid
+    /// out, one level deeper than the statement, between quotes on lines of
+    /// their own.
+    pub(super) fn sql_string(&self, node: &SyntaxNode) -> Option<Doc> {
+        if !is_sql_string(node) || !node.to_string().contains('\n') {
+            return None;
+        }
+        let children: Vec<_> = node.children_with_tokens().collect();
+        let [SyntaxElement::Token(open), body @ .., SyntaxElement::Token(close)] = &children[..]
+        else {
+            return None;
+        };
+        if open.kind() != QUOTE || close.kind() != QUOTE {
+            return None;
+        }
+        let mut sql = String::new();
+        let mut items = Vec::new();
+        for element in body {
+            match element {
+                // Quotes escaped by doubling them would be SQL literals.
+                SyntaxElement::Token(token)
+                    if token.kind() == STRING_TEXT && !token.text().contains(open.text()) =>
+                {
+                    sql.push_str(&self.token_text(token))
+                }
+                SyntaxElement::Node(node) if node.kind() == HASH_EXPR => {
+                    items.push(Doc::text(self.verbatim(node)));
+                    sql.push(PLACEHOLDER);
+                }
+                _ => return None,
+            }
+        }
+        let lines = self.sql_lines(&sql, items)?;
+        Some(Doc::Concat(vec![
+            Doc::text(open.text()),
+            Doc::Indent(lines),
+            Doc::HardLine,
+            Doc::text(close.text()),
+        ]))
+    }
+
+    /// The lines of `sql`, each after a new line, with `items` in place of
+    /// its placeholders.
+    fn sql_lines(&self, sql: &str, items: Vec<Doc>) -> Option<Vec<Doc>> {
+        if sql.chars().filter(|&it| it == PLACEHOLDER).count() != items.len() {
+            // The text has a placeholder of its own.
+            return None;
+        }
+        let lines = layout(sql);
+        if lines.is_empty() {
+            return None;
+        }
+        let mut items = items.into_iter();
+        let mut docs = Vec::new();
+        for (continues, line) in lines {
+            let mut line_docs = vec![Doc::HardLine];
+            for (idx, text) in line.split(PLACEHOLDER).enumerate() {
+                if idx > 0 {
+                    line_docs.extend(items.next());
+                }
+                line_docs.push(Doc::text(text));
+            }
+            docs.push(if continues {
+                Doc::Indent(line_docs)
+            } else {
+                Doc::Concat(line_docs)
+            });
+        }
+        Some(docs)
+    }
+}
+
+fn is_query_param(node: &SyntaxNode) -> bool {
+    ast::Tag::cast(node.clone()).is_some_and(|it| it.name_lower() == "cfqueryparam")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let lines = layout(
+            "select a, left(b, 1) from t.left\n  left outer join u on t.id = u.id where x between 1 and 2 and (y = 1 or z = 'a  b') -- c\n  order by a",
+        );
+        let lines: Vec<_> = lines
+            .iter()
+            .map(|(it, line)| (*it, line.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (false, "select a, left(b, 1)"),
+                (false, "from t.left"),
+                (false, "left outer join u on t.id = u.id"),
+                (false, "where x between 1 and 2"),
+                (true, "and (y = 1 or z = 'a  b') -- c"),
+                (false, "order by a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recase() {
+        assert_eq!(
+            recase(
+                "select t.from, \"select\" from t where x = :in -- and",
+                Case::Upper
+            ),
+            "SELECT t.from, \"select\" FROM t WHERE x = :in -- and"
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! The layout of templates: tags, their attributes and the lines of their
+//! bodies.
+//!
+//! A body spanning several lines is indented one level more than its tag,
+//! `<cfelse>` and `<cfelseif>` lining up with their `<cfif>`. Within a body,
+//! the lines keep their indentation relative to the least indented one, which
+//! is the indentation of the markup in them; the contents of `<pre>` and
+//! `<textarea>` are kept as they are.
+use syntax::{tags, SyntaxElement, SyntaxKind::*, SyntaxNode, SyntaxToken};
+
+use super::{doc, Doc, Formatter, Quote};
+
+/// HTML elements whose text is output with its whitespace, with how they
+/// end.
+const RAW_ELEMENTS: &[(&str, &str)] = &[("<pre", "</pre"), ("<textarea", "</textarea")];
+
+struct Line {
+    /// The width of the indentation of the line in the file, `None` for the
+    /// line of the tag whose body starts on it.
+    indent: Option<u32>,
+    docs: Vec<Doc>,
+    /// Whether the line has more than whitespace.
+    has_content: bool,
+    /// Whether the line starts with `<cfelse>` or `<cfelseif>`.
+    outdented: bool,
+}
+
+impl Line {
+    fn new(indent: Option<u32>) -> Line {
+        Line {
+            indent,
+            docs: Vec::new(),
+            has_content: false,
+            outdented: false,
+        }
+    }
+}
+
+impl Formatter<'_> {
+    /// Lays out template items, the body of a tag if `in_tag`, followed by
+    /// the `close` tag.
+    pub(super) fn template_body(
+        &self,
+        elements: &[SyntaxElement],
+        in_tag: bool,
+        close: Option<Doc>,
+    ) -> Vec<Doc> {
+        let mut lines = self.lines(elements, in_tag);
+        let first = lines
+            .iter()
+            .position(|it| it.has_content || it.indent.is_none());
+        let last = lines.iter().rposition(|it| it.has_content);
+        let close_inline = lines.len() == 1 || lines.last().is_some_and(|it| it.has_content);
+        // The least indentation of each branch, `<cfelse>` starting one.
+        let mut min_indents = vec![None::<u32>];
+        for line in &lines {
+            if line.outdented {
+                min_indents.push(None);
+            } else if let (Some(indent), true) = (line.indent, line.has_content) {
+                let min = min_indents.last_mut().unwrap();
+                *min = Some(min.map_or(indent, |it| it.min(indent)));
+            }
+        }
+        let mut min_indents = min_indents.into_iter().map(Option::unwrap_or_default);
+        let mut min_indent = min_indents.next().unwrap_or_default();
+        let base = if in_tag { self.config.tab_size } else { 0 };
+
+        let mut laid_out = Vec::new();
+        let mut blank_lines = 0;
+        let mut seen_content = false;
+        if let (Some(first), Some(last)) = (first, last.max(first)) {
+            for (idx, line) in lines.drain(first..=last).enumerate() {
+                if line.outdented {
+                    min_indent = min_indents.next().unwrap_or_default();
+                }
+                let Some(indent) = line.indent else {
+                    // The line of the tag.
+                    seen_content = line.has_content;
+                    laid_out.push((0, line.docs));
+                    continue;
+                };
+                if !line.has_content {
+                    blank_lines += 1;
+                    continue;
+                }
+                let mut docs = Vec::new();
+                if idx > 0 {
+                    docs.push(Doc::HardLine);
+                    if seen_content {
+                        let blank_lines = blank_lines.min(self.config.max_blank_lines);
+                        docs.extend((0..blank_lines).map(|_| Doc::HardLine));
+                    }
+                }
+                docs.extend(line.docs);
+                let width = if line.outdented {
+                    0
+                } else {
+                    base + indent - min_indent
+                };
+                laid_out.push((width, docs));
+                blank_lines = 0;
+                seen_content = true;
+            }
+        }
+        if let Some(close) = close {
+            match laid_out.last_mut() {
+                Some((_, docs)) if close_inline => docs.push(close),
+                _ => laid_out.push((0, vec![Doc::HardLine, close])),
+            }
+        }
+        laid_out
+            .into_iter()
+            .map(|(width, docs)| Doc::Nest(width, docs))
+            .collect()
+    }
+
+    /// Splits template items into lines.
+    fn lines(&self, elements: &[SyntaxElement], in_tag: bool) -> Vec<Line> {
+        let mut lines = vec![Line::new((!in_tag).then_some(0))];
+        // The end of the raw element the items are in.
+        let mut raw_end = None;
+        for element in elements {
+            match element {
+                SyntaxElement::Token(token) if token.kind() == CF_COMMENT => {
+                    let line = lines.last_mut().unwrap();
+                    line.docs.push(self.comment(token));
+                    line.has_content = true;
+                }
+                SyntaxElement::Token(token) => {
+                    self.split_text(&self.token_text(token), &mut lines, &mut raw_end)
+                }
+                SyntaxElement::Node(node) => {
+                    let line = lines.last_mut().unwrap();
+                    if raw_end.is_some() {
+                        line.docs.push(Doc::text(self.verbatim(node)));
+                    } else {
+                        line.outdented |= !line.has_content && is_else_tag(node);
+                        line.docs.push(self.template_item(node));
+                    }
+                    line.has_content = true;
+                }
+            }
+        }
+        lines
+    }
+
+    fn template_item(&self, node: &SyntaxNode) -> Doc {
+        match node.kind() {
+            TAG => self.tag(node),
+            TAG_OPEN => self.tag_open(node),
+            TAG_CLOSE => self.tag_close(node),
+            _ => Doc::text(self.verbatim(node)),
+        }
+    }
+
+    fn tag(&self, node: &SyntaxNode) -> Doc {
+        let mut children: Vec<_> = node.children_with_tokens().collect();
+        let close = match children.last() {
+            Some(SyntaxElement::Node(it)) if it.kind() == TAG_CLOSE && children.len() > 1 => {
+                let close = self.tag_close(it);
+                children.pop();
+                Some(close)
+            }
+            _ => None,
+        };
+        let Some(SyntaxElement::Node(open)) = children.first() else {
+            return Doc::text(self.verbatim(node));
+        };
+        let mut docs = vec![self.tag_open(open)];
+        let body = &children[1..];
+        let sql_body = (self.config.sql_layout && close.is_some() && tag_name(open) == "cfquery")
+            .then(|| self.sql_body(body))
+            .flatten();
+        if tag_name(open).eq_ignore_ascii_case("cfscript") {
+            docs.extend(self.script_body(body));
+            docs.extend(close);
+        } else if let Some(sql_body) = sql_body {
+            docs.extend(sql_body);
+            docs.extend(close);
+        } else {
+            docs.extend(self.template_body(body, true, close));
+        }
+        Doc::Concat(docs)
+    }
+
+    /// The statements of `<cfscript>`, one level deeper than the tag unless
+    /// they are on its line.
+    fn script_body(&self, body: &[SyntaxElement]) -> Vec<Doc> {
+        let statements = body.iter().filter(|it| it.kind() != WHITESPACE).count();
+        let multiline = body
+            .iter()
+            .any(|it| it.kind() == WHITESPACE && it.as_token().unwrap().text().contains('\n'));
+        if statements == 0 {
+            return Vec::new();
+        }
+        if multiline || statements > 1 {
+            let statements = self.statements(body.iter().cloned(), true);
+            return vec![Doc::Indent(statements), Doc::HardLine];
+        }
+        let mut docs = Vec::new();
+        for element in body {
+            match element {
+                SyntaxElement::Token(token) if token.kind() == WHITESPACE => {
+                    docs.push(Doc::text(" "));
+                }
+                SyntaxElement::Token(token) => docs.push(self.comment(token)),
+                SyntaxElement::Node(node) => docs.push(self.node(node)),
+            }
+        }
+        docs
+    }
+
+    fn tag_open(&self, node: &SyntaxNode) -> Doc {
+        let name = tag_name(node);
+        let has_expression = tags::has_expression(&name);
+        let attributes: Vec<_> = node
+            .children()
+            .filter(|it| it.kind() == ATTRIBUTE)
+            .collect();
+        let name_width = attributes
+            .iter()
+            .filter(|it| has_value(it))
+            .filter_map(|it| it.first_token())
+            .map(|it| doc::width(self.config, it.text()))
+            .max()
+            .unwrap_or(0);
+        let mut start = String::new();
+        let mut items = Vec::new();
+        let mut end = None;
+        let mut space = false;
+        for element in node.children_with_tokens() {
+            match &element {
+                SyntaxElement::Token(token) => match token.kind() {
+                    L_ANGLE | TAG_NAME => start.push_str(&self.token_text(token)),
+                    WHITESPACE => space = true,
+                    R_ANGLE => end = Some(">"),
+                    SLASH_R_ANGLE if space => end = Some(" />"),
+                    SLASH_R_ANGLE => end = Some("/>"),
+                    CF_COMMENT => items.push(self.comment(token)),
+                    _ => items.push(Doc::text(self.token_text(token))),
+                },
+                SyntaxElement::Node(node) if node.kind() == ATTRIBUTE => {
+                    items.push(self.attribute(node, name_width));
+                }
+                SyntaxElement::Node(node) => items.push(self.node(node)),
+            }
+            if element.kind() != WHITESPACE {
+                space = false;
+            }
+        }
+        let end = Doc::text(end.unwrap_or_default());
+        if has_expression {
+            let mut docs = vec![Doc::text(start)];
+            for item in items {
+                docs.push(Doc::text(" "));
+                docs.push(item);
+            }
+            docs.push(end);
+            return Doc::Concat(docs);
+        }
+        // A single attribute is on the line of the tag anyway.
+        let multiline = attributes.len() > 1
+            && self
+                .config
+                .multiline_tags
+                .iter()
+                .any(|it| it.eq_ignore_ascii_case(&name));
+        let too_many = self
+            .config
+            .max_attributes_per_line
+            .is_some_and(|max| attributes.len() > max as usize);
+        let attributes = items
+            .into_iter()
+            .enumerate()
+            .flat_map(|(idx, it)| match idx {
+                // Breaks the group.
+                0 if multiline || too_many => [Doc::HardLine, it],
+                _ => [Doc::Line, it],
+            })
+            .collect();
+        Doc::Group(vec![Doc::text(start), Doc::Indent(attributes), end])
+    }
+
+    /// An attribute of a tag, without spaces around its `=`, which is after
+    /// `name_width` columns if attributes are aligned and one per line.
+    fn attribute(&self, node: &SyntaxNode, name_width: u32) -> Doc {
+        let mut docs = Vec::new();
+        let mut text = String::new();
+        for element in node.children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token) if token.kind() == WHITESPACE => (),
+                SyntaxElement::Token(token)
+                    if token.kind() == EQ && self.config.align_attributes =>
+                {
+                    let padding = name_width.saturating_sub(doc::width(self.config, &text));
+                    docs.push(Doc::text(std::mem::take(&mut text)));
+                    docs.push(Doc::IfBreak(vec![Doc::text(" ".repeat(padding as usize))]));
+                    text.push('=');
+                }
+                SyntaxElement::Token(token) => text.push_str(&self.token_text(&token)),
+                SyntaxElement::Node(node) if node.kind() == STRING => {
+                    text.push_str(&self.attribute_value(&node))
+                }
+                SyntaxElement::Node(node) => text.push_str(&self.verbatim(&node)),
+            }
+        }
+        docs.push(Doc::text(text));
+        Doc::Concat(docs)
+    }
+
+    /// A quoted value, in the configured quotes unless it contains one.
+    fn attribute_value(&self, node: &SyntaxNode) -> String {
+        let text = self.verbatim(node);
+        let quote = match self.config.attribute_quote {
+            Quote::Preserve => return text,
+            Quote::Double => '"',
+            Quote::Single => '\'',
+        };
+        let quotes = node
+            .children_with_tokens()
+            .filter(|it| it.kind() == QUOTE)
+            .count();
+        if quotes != 2 {
+            return text;
+        }
+        let inner = &text[1..text.len() - 1];
+        if inner.contains(['"', '\'']) {
+            return text;
+        }
+        format!("{quote}{inner}{quote}")
+    }
+
+    fn tag_close(&self, node: &SyntaxNode) -> Doc {
+        let text: String = node
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() != WHITESPACE)
+            .map(|it| self.token_text(&it))
+            .collect();
+        Doc::text(text)
+    }
+
+    /// Adds the `text` of a token to the lines, starting new ones at its
+    /// newlines, unless they are in a raw element ending with `raw_end`.
+    fn split_text(
+        &self,
+        mut text: &str,
+        lines: &mut Vec<Line>,
+        raw_end: &mut Option<&'static str>,
+    ) {
+        while !text.is_empty() {
+            if let Some(end) = *raw_end {
+                let len = match find_ci(text, end) {
+                    Some(idx) => {
+                        *raw_end = None;
+                        idx + end.len()
+                    }
+                    None => text.len(),
+                };
+                self.push_text(lines.last_mut().unwrap(), &text[..len]);
+                text = &text[len..];
+                continue;
+            }
+            let line_len = text.find('\n').unwrap_or(text.len());
+            let raw = RAW_ELEMENTS.iter().find_map(|&(start, end)| {
+                let idx = find_ci(&text[..line_len], start)?;
+                let after = text[idx + start.len()..].chars().next();
+                after
+                    .is_some_and(|it| it == '>' || it.is_whitespace())
+                    .then_some((idx, end))
+            });
+            if let Some((idx, end)) = raw {
+                self.push_text(lines.last_mut().unwrap(), &text[..idx]);
+                *raw_end = Some(end);
+                text = &text[idx..];
+                continue;
+            }
+            self.push_text(lines.last_mut().unwrap(), &text[..line_len]);
+            if line_len < text.len() {
+                lines.push(Line::new(Some(0)));
+                text = &text[line_len + 1..];
+            } else {
+                text = "";
+            }
+        }
+    }
+
+    fn push_text(&self, line: &mut Line, mut text: &str) {
+        if let (Some(indent), true) = (line.indent, line.docs.is_empty()) {
+            let code = text.trim_start_matches([' ', '\t']);
+            let width = doc::width(self.config, &text[..text.len() - code.len()]);
+            line.indent = Some(indent + width);
+            text = code;
+        }
+        if !text.is_empty() {
+            line.has_content |= !text.trim().is_empty();
+            line.docs.push(Doc::text(text));
+        }
+    }
+}
+
+fn find_ci(text: &str, needle: &str) -> Option<usize> {
+    text.to_ascii_lowercase().find(needle)
+}
+
+fn tag_name(open: &SyntaxNode) -> String {
+    open.children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .find(|it: &SyntaxToken| it.kind() == TAG_NAME)
+        .map_or_else(String::new, |it| it.text().to_ascii_lowercase())
+}
+
+/// Whether an attribute has a value, rather than being a bare name.
+fn has_value(attribute: &SyntaxNode) -> bool {
+    attribute.children_with_tokens().any(|it| it.kind() == EQ)
+}
+
+fn is_else_tag(node: &SyntaxNode) -> bool {
+    node.kind() == TAG
+        && node
+            .first_child()
+            .is_some_and(|it| matches!(tag_name(&it).as_str(), "cfelse" | "cfelseif"))
+}
@@ -0,0 +1,139 @@
+//! Interned names. CFML identifiers and component paths are
+//! case-insensitive: a [`Symbol`] is a name folded to lowercase, which
+//! compares and hashes as an integer, and a [`Name`] keeps the case it was
+//! written in beside its symbol, to show it.
+//!
+//! The strings live as long as the process: the names of a workspace are
+//! few, and keep being used while it is open.
+use std::{
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+use rustc_hash::FxHashMap;
+
+/// A name in lowercase.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The symbol of `text`, in any case.
+    pub fn new(text: &str) -> Symbol {
+        if text.bytes().any(|it| it.is_ascii_uppercase()) {
+            Symbol(intern(&text.to_ascii_lowercase()))
+        } else {
+            Symbol(intern(text))
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        lookup(self.0)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// A name as written, equal to the names differing only in case.
+#[derive(Clone, Copy)]
+pub struct Name {
+    symbol: Symbol,
+    text: u32,
+}
+
+impl Name {
+    pub fn new(text: &str) -> Name {
+        Name {
+            symbol: Symbol::new(text),
+            text: intern(text),
+        }
+    }
+
+    pub fn symbol(self) -> Symbol {
+        self.symbol
+    }
+
+    /// The name in the case it was written in.
+    pub fn as_str(self) -> &'static str {
+        lookup(self.text)
+    }
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Name) -> bool {
+        self.symbol == other.symbol
+    }
+}
+
+impl Eq for Name {}
+
+impl std::hash::Hash for Name {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.symbol.hash(state);
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str().eq_ignore_ascii_case(other)
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Default)]
+struct Interner {
+    ids: FxHashMap<&'static str, u32>,
+    strings: Vec<&'static str>,
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+fn intern(text: &str) -> u32 {
+    let mut interner = interner().lock().unwrap();
+    if let Some(&id) = interner.ids.get(text) {
+        return id;
+    }
+    let text: &'static str = Box::leak(text.into());
+    let id = interner.strings.len() as u32;
+    interner.strings.push(text);
+    interner.ids.insert(text, id);
+    id
+}
+
+fn lookup(id: u32) -> &'static str {
+    interner().lock().unwrap().strings[id as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        let name = Name::new("getUser");
+        assert_eq!(name.as_str(), "getUser");
+        assert_eq!(name.symbol().as_str(), "getuser");
+        assert_eq!(name, Name::new("GETUSER"));
+        assert_eq!(name.symbol(), Symbol::new("getuser"));
+        assert_ne!(name, Name::new("getUsers"));
+        assert_eq!(name, "GetUser");
+        assert_eq!(format!("{name} {name:?}"), "getUser \"getUser\"");
+    }
+}
@@ -0,0 +1,436 @@
+//! IDE features for CFML, independent of LSP.
+//!
+//! [`AnalysisHost`] owns the current text and path of every file. The server
+//! feeds it changes and takes cheap [`Analysis`] snapshots to answer requests,
+//! which share the results of the queries derived from the files (see `db`). All
+//! positions are byte offsets; converting them to and from LSP positions is
+//! the job of the server, with the help of [`LineIndex`].
+mod assists;
+mod cancellation;
+mod change_signature;
+mod component;
+mod db;
+mod diagnostics;
+mod engine;
+mod expand_includes;
+mod format;
+mod intern;
+mod line_index;
+mod move_component;
+mod resolve;
+mod source_change;
+mod sql;
+mod symbols;
+mod tag_schema;
+mod text_edit;
+mod ty;
+mod var_scope;
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+use syntax::Parse;
+use virtual_fs::{FileId, VirtualFsPath};
+
+use crate::{
+    db::{FileData, Memos},
+    resolve::ComponentReference,
+};
+
+pub use crate::{
+    assists::{Assist, AssistConfig, AssistId, AssistKind},
+    cancellation::{CancellationToken, Cancelled},
+    change_signature::{ChangeSignature, ParamChange},
+    db::{FileIndex, IndexedReference},
+    diagnostics::{
+        Diagnostic, DiagnosticCategory, DiagnosticCode, DiagnosticsConfig, NamingCase,
+        NamingConventions, RelatedInformation, Severity, Thresholds,
+    },
+    engine::{Engine, EngineKind},
+    expand_includes::{ExpandedIncludes, UnresolvedInclude},
+    format::{BraceStyle, Case, FormatConfig, Quote, Spacing, TrailingComma},
+    intern::{Name, Symbol},
+    line_index::{LineCol, LineColUtf16, LineIndex},
+    source_change::{FileSystemEdit, SourceChange},
+    symbols::{ComponentName, ComponentSymbol, FileSymbols, FunctionSymbol},
+    text_edit::{Indel, TextEdit, TextEditBuilder},
+};
+pub use syntax::{TextRange, TextSize};
+
+/// The memory a part of the analysis holds, to debug its growth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub name: &'static str,
+    /// The number of items held, e.g. files.
+    pub count: usize,
+    /// An estimate of the bytes held, without the overhead of allocations.
+    pub bytes: usize,
+}
+
+/// An offset in a particular file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilePosition {
+    pub file_id: FileId,
+    pub offset: TextSize,
+}
+
+/// A range in a particular file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileRange {
+    pub file_id: FileId,
+    pub range: TextRange,
+}
+
+#[derive(Debug, Default)]
+pub struct AnalysisHost {
+    files: Arc<FxHashMap<FileId, Arc<FileData>>>,
+    paths: Arc<FxHashMap<FileId, VirtualFsPath>>,
+    /// The roots of the workspace, normalized like the paths.
+    roots: Arc<[String]>,
+    memos: Memos,
+}
+
+impl AnalysisHost {
+    /// Sets the text of a file, or removes it with `None`.
+    pub fn set_file_text(&mut self, file_id: FileId, text: Option<Arc<str>>) {
+        let files = Arc::make_mut(&mut self.files);
+        match text {
+            Some(text) => files.insert(file_id, Arc::new(FileData::new(text))),
+            None => files.remove(&file_id),
+        };
+        self.memos.component_references = Default::default();
+    }
+
+    /// Sets the path of a file, which other files use to refer to it, or
+    /// forgets it with `None`.
+    pub fn set_file_path(&mut self, file_id: FileId, path: Option<VirtualFsPath>) {
+        let paths = Arc::make_mut(&mut self.paths);
+        match path {
+            Some(path) => paths.insert(file_id, path),
+            None => paths.remove(&file_id),
+        };
+        self.memos.normalized_paths = Default::default();
+        self.memos.files_by_name = Default::default();
+        self.memos.file_roots = Default::default();
+    }
+
+    /// Sets the roots of the workspace. Each is a separate application: the
+    /// names in the files under one never resolve to the files under
+    /// another. The files under none resolve like those of the first.
+    pub fn set_roots(&mut self, roots: Vec<VirtualFsPath>) {
+        self.roots = roots
+            .iter()
+            .map(|it| {
+                resolve::normalized(&it.to_string())
+                    .trim_end_matches('/')
+                    .to_string()
+            })
+            .collect();
+        self.memos.file_roots = Default::default();
+    }
+
+    /// Sets what is indexed from the current text of a file, e.g. as kept
+    /// from a previous run, to skip computing it. `index` must come from the
+    /// same text.
+    pub fn set_file_index(&mut self, file_id: FileId, index: FileIndex) {
+        if let Some(data) = self.files.get(&file_id) {
+            data.set_index(file_id, index);
+        }
+    }
+
+    pub fn analysis(&self) -> Analysis {
+        Analysis {
+            files: Arc::clone(&self.files),
+            paths: Arc::clone(&self.paths),
+            roots: Arc::clone(&self.roots),
+            memos: self.memos.clone(),
+            cancellation: CancellationToken::default(),
+        }
+    }
+}
+
+/// A snapshot of the analysis state, used to answer queries.
+///
+/// Queries searching every file unwind with [`Cancelled`] once the token of
+/// the snapshot is cancelled.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    files: Arc<FxHashMap<FileId, Arc<FileData>>>,
+    paths: Arc<FxHashMap<FileId, VirtualFsPath>>,
+    roots: Arc<[String]>,
+    memos: Memos,
+    cancellation: CancellationToken,
+}
+
+impl Analysis {
+    /// The same snapshot, whose queries stop when `token` is cancelled.
+    pub fn with_cancellation(self, token: CancellationToken) -> Analysis {
+        Analysis {
+            cancellation: token,
+            ..self
+        }
+    }
+
+    /// Unwinds with [`Cancelled`] if the query was cancelled, to call between
+    /// files.
+    pub(crate) fn unwind_if_cancelled(&self) {
+        self.cancellation.unwind_if_cancelled();
+    }
+
+    /// Records that the query is done with `done` files out of `total`, for
+    /// the caller to show.
+    pub(crate) fn report_progress(&self, done: usize, total: usize) {
+        self.cancellation.report_progress(done, total);
+    }
+
+    pub fn file_text(&self, file_id: FileId) -> Option<Arc<str>> {
+        self.files.get(&file_id).map(|it| Arc::clone(&it.text))
+    }
+
+    pub fn parse(&self, file_id: FileId) -> Option<Parse> {
+        self.files.get(&file_id).map(|it| it.parse())
+    }
+
+    pub fn file_line_index(&self, file_id: FileId) -> Option<Arc<LineIndex>> {
+        self.files.get(&file_id).map(|it| it.line_index())
+    }
+
+    /// What is indexed from the text of `file_id`, to keep while the text
+    /// stays the same.
+    pub fn file_index(&self, file_id: FileId) -> Option<FileIndex> {
+        self.files.get(&file_id).map(|it| it.index(file_id))
+    }
+
+    /// The names of components in `file_id`.
+    pub(crate) fn file_component_references(
+        &self,
+        file_id: FileId,
+    ) -> Option<Arc<[ComponentReference]>> {
+        self.files
+            .get(&file_id)
+            .map(|it| it.component_references(file_id))
+    }
+
+    /// The names of components in every file: in `extends` and `implements`,
+    /// `new` expressions and `createObject()` calls, and the WireBox ids of
+    /// `getInstance()`, `.to()` and `inject` which are written as paths.
+    pub(crate) fn component_references(&self) -> Arc<[ComponentReference]> {
+        self.memos.component_references.get_or_init(|| {
+            let mut file_ids: Vec<FileId> = self.files.keys().copied().collect();
+            file_ids.sort_by_key(|it| it.0);
+            let mut res = Vec::new();
+            let total = file_ids.len();
+            for (done, file_id) in file_ids.into_iter().enumerate() {
+                self.unwind_if_cancelled();
+                self.report_progress(done, total);
+                res.extend(
+                    self.files[&file_id]
+                        .component_references(file_id)
+                        .iter()
+                        .cloned(),
+                );
+            }
+            res.into()
+        })
+    }
+
+    /// The paths of the files, with `/` separators and in lowercase.
+    pub(crate) fn normalized_paths(&self) -> Arc<FxHashMap<FileId, String>> {
+        self.memos.normalized_paths.get_or_init(|| {
+            let paths = self
+                .paths
+                .iter()
+                .map(|(&file_id, path)| (file_id, resolve::normalized(&path.to_string())))
+                .collect();
+            Arc::new(paths)
+        })
+    }
+
+    /// The files named `name`, in lowercase, wherever they are.
+    pub(crate) fn files_named(&self, name: Symbol) -> Vec<FileId> {
+        let files_by_name = self.memos.files_by_name.get_or_init(|| {
+            let mut res: FxHashMap<Symbol, Vec<FileId>> = FxHashMap::default();
+            for (&file_id, path) in self.normalized_paths().iter() {
+                let name = path.rsplit('/').next().unwrap_or(path);
+                res.entry(Symbol::new(name)).or_default().push(file_id);
+            }
+            for file_ids in res.values_mut() {
+                file_ids.sort_by_key(|it| it.0);
+            }
+            Arc::new(res)
+        });
+        files_by_name.get(&name).cloned().unwrap_or_default()
+    }
+
+    /// The index of the root `file_id` is under, the innermost if roots are
+    /// nested. The files outside of the roots, like untitled documents, go
+    /// with the first one. `None` without roots.
+    pub(crate) fn file_root(&self, file_id: FileId) -> Option<usize> {
+        let file_roots = self.memos.file_roots.get_or_init(|| {
+            if self.roots.is_empty() {
+                return Arc::default();
+            }
+            let res = self
+                .normalized_paths()
+                .iter()
+                .map(|(&file_id, path)| {
+                    let idx = self
+                        .roots
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, root)| {
+                            path.strip_prefix(root.as_str())
+                                .is_some_and(|rest| rest.starts_with('/'))
+                        })
+                        .max_by_key(|(_, root)| root.len())
+                        .map_or(0, |(idx, _)| idx);
+                    (file_id, idx)
+                })
+                .collect();
+            Arc::new(res)
+        });
+        file_roots.get(&file_id).copied()
+    }
+
+    /// Whether the names in `from` may refer to `to`, both being under the
+    /// same root.
+    pub(crate) fn same_root(&self, from: FileId, to: FileId) -> bool {
+        self.file_root(from) == self.file_root(to)
+    }
+
+    /// Computes the diagnostics of a file, with their fixes.
+    pub fn diagnostics(&self, config: &DiagnosticsConfig, file_id: FileId) -> Vec<Diagnostic> {
+        diagnostics::diagnostics(self, config, file_id)
+    }
+
+    /// Computes the diagnostics found by parsing a file alone, cheap enough
+    /// to keep live when the others only run on save.
+    pub fn syntax_diagnostics(
+        &self,
+        config: &DiagnosticsConfig,
+        file_id: FileId,
+    ) -> Vec<Diagnostic> {
+        diagnostics::syntax_diagnostics(self, config, file_id)
+    }
+
+    /// Computes the code actions available for `frange`.
+    pub fn assists(&self, config: &AssistConfig, frange: FileRange) -> Vec<Assist> {
+        let Some(data) = self.files.get(&frange.file_id) else {
+            return Vec::new();
+        };
+        assists::assists(config, frange, &data.text)
+    }
+
+    /// Returns `true` if "Move to folder…" applies to `frange`: it is on the
+    /// declaration of a component.
+    pub fn can_move_component(&self, frange: FileRange) -> bool {
+        move_component::is_on_component(self, frange)
+    }
+
+    /// Moves the component in `file_id` to the folder `new_dir`, updating
+    /// the references to it.
+    pub fn move_component(&self, file_id: FileId, new_dir: &VirtualFsPath) -> Option<SourceChange> {
+        move_component::move_component(self, file_id, new_dir)
+    }
+
+    /// The files `file_id` includes, directly or not, in the order they
+    /// run, and the includes which can't be resolved.
+    pub fn expand_includes(&self, file_id: FileId) -> Option<ExpandedIncludes> {
+        expand_includes::expand_includes(self, file_id)
+    }
+
+    /// The memory held by the texts and paths of the files, and by the
+    /// trees parsed from them.
+    pub fn memory_usage(&self) -> Vec<MemoryUsage> {
+        let texts = MemoryUsage {
+            name: "file texts",
+            count: self.files.len(),
+            bytes: self.files.values().map(|it| it.text.len()).sum(),
+        };
+        // A tree holds the text of its tokens, and nodes in proportion.
+        let parsed: Vec<&Arc<FileData>> = self.files.values().filter(|it| it.is_parsed()).collect();
+        let trees = MemoryUsage {
+            name: "parse trees",
+            count: parsed.len(),
+            bytes: parsed.iter().map(|it| it.text.len()).sum(),
+        };
+        let paths = MemoryUsage {
+            name: "file paths",
+            count: self.paths.len(),
+            bytes: self.paths.values().map(|it| it.to_string().len()).sum(),
+        };
+        vec![texts, trees, paths]
+    }
+
+    /// The edit formatting `file_id`.
+    pub fn format(&self, config: &FormatConfig, file_id: FileId) -> Option<TextEdit> {
+        format::format(self, config, file_id)
+    }
+
+    /// The component and the functions `file_id` declares.
+    pub fn file_symbols(&self, file_id: FileId) -> Option<FileSymbols> {
+        symbols::file_symbols(self, file_id)
+    }
+
+    /// Changes the parameters of the function at `position` to `params`,
+    /// updating its calls.
+    pub fn change_signature(
+        &self,
+        position: FilePosition,
+        params: &[ParamChange],
+    ) -> Option<ChangeSignature> {
+        change_signature::change_signature(self, position, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analysis_host() {
+        let mut host = AnalysisHost::default();
+        let file_id = FileId(0);
+        host.set_file_text(file_id, Some("<cfset x = 1>".into()));
+        let analysis = host.analysis();
+        host.set_file_text(file_id, None);
+        assert_eq!(
+            analysis.file_text(file_id).as_deref(),
+            Some("<cfset x = 1>")
+        );
+        assert!(analysis.parse(file_id).unwrap().errors().is_empty());
+        assert!(host.analysis().file_text(file_id).is_none());
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let mut host = AnalysisHost::default();
+        host.set_file_text(FileId(0), Some("<cfset x = 1>".into()));
+        host.set_file_text(FileId(1), Some("component {}".into()));
+        host.set_file_path(
+            FileId(0),
+            Some(VirtualFsPath::new_virtual_path("/index.cfm".to_string())),
+        );
+        let usage = host.analysis().memory_usage();
+        assert_eq!(
+            usage,
+            [
+                MemoryUsage {
+                    name: "file texts",
+                    count: 2,
+                    bytes: 25,
+                },
+                MemoryUsage {
+                    name: "parse trees",
+                    count: 0,
+                    bytes: 0,
+                },
+                MemoryUsage {
+                    name: "file paths",
+                    count: 1,
+                    bytes: 10,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Conversion between byte offsets and line/column positions.
+//!
+//! Columns are tracked in UTF-8 bytes; [`LineIndex::to_utf16`] and
+//! [`LineIndex::to_utf8`] translate them to and from the UTF-16 code units
+//! used by LSP clients. Only lines containing non-ASCII characters pay for
+//! the translation.
+//!
+//! Lines end at `\n`, and at a `\r` alone as LSP has it: the `\r\n` of the
+//! documents are turned into `\n` before they are analyzed. Positions from
+//! clients are trusted no further than the text: a column past the end of
+//! its line is the end of the line, and one inside a character is its
+//! start.
+use rustc_hash::FxHashMap;
+use syntax::{TextRange, TextSize};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Offset of the start of every line.
+    newlines: Vec<TextSize>,
+    /// Characters wider than one UTF-16 code unit, per line.
+    wide_chars: FxHashMap<u32, Vec<WideChar>>,
+    len: TextSize,
+}
+
+/// A position with the column in UTF-8 bytes, both zero-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// A position with the column in UTF-16 code units, both zero-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineColUtf16 {
+    pub line: u32,
+    pub col: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WideChar {
+    /// Start of the character, relative to the start of the line.
+    start: TextSize,
+    /// End of the character, relative to the start of the line.
+    end: TextSize,
+}
+
+impl WideChar {
+    fn len(&self) -> TextSize {
+        self.end - self.start
+    }
+
+    fn len_utf16(&self) -> TextSize {
+        if self.len() == 4 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut newlines = vec![0];
+        let mut wide_chars = FxHashMap::default();
+        let mut line_chars = Vec::new();
+        let mut line_start = 0;
+        for (offset, c) in text.char_indices() {
+            let offset = offset as TextSize;
+            let is_cr_alone = c == '\r' && text.as_bytes().get(offset as usize + 1) != Some(&b'\n');
+            if c == '\n' || is_cr_alone {
+                if !line_chars.is_empty() {
+                    wide_chars.insert(newlines.len() as u32 - 1, std::mem::take(&mut line_chars));
+                }
+                line_start = offset + 1;
+                newlines.push(line_start);
+                continue;
+            }
+            if !c.is_ascii() {
+                let start = offset - line_start;
+                line_chars.push(WideChar {
+                    start,
+                    end: start + c.len_utf8() as TextSize,
+                });
+            }
+        }
+        if !line_chars.is_empty() {
+            wide_chars.insert(newlines.len() as u32 - 1, line_chars);
+        }
+        LineIndex {
+            newlines,
+            wide_chars,
+            len: text.len() as TextSize,
+        }
+    }
+
+    pub fn len(&self) -> TextSize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn line_col(&self, offset: TextSize) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = self.newlines.partition_point(|&it| it <= offset) - 1;
+        LineCol {
+            line: line as u32,
+            col: offset - self.newlines[line],
+        }
+    }
+
+    /// Returns the offset of `line_col`, or `None` if the line does not exist.
+    /// Columns past the end of the line are clamped to its end, and columns
+    /// inside a character moved to its start.
+    pub fn offset(&self, line_col: LineCol) -> Option<TextSize> {
+        let line = self.line_range(line_col.line)?;
+        let mut col = line_col.col.min(line.len());
+        if let Some(chars) = self.wide_chars.get(&line_col.line) {
+            if let Some(c) = chars.iter().find(|c| c.start < col && col < c.end) {
+                col = c.start;
+            }
+        }
+        Some(line.start() + col)
+    }
+
+    pub fn to_utf16(&self, line_col: LineCol) -> LineColUtf16 {
+        let mut col = line_col.col;
+        if let Some(chars) = self.wide_chars.get(&line_col.line) {
+            for c in chars {
+                if c.end <= line_col.col {
+                    col -= c.len() - c.len_utf16();
+                } else if c.start < line_col.col {
+                    // Inside the character: at its start.
+                    col -= line_col.col - c.start;
+                }
+            }
+        }
+        LineColUtf16 {
+            line: line_col.line,
+            col,
+        }
+    }
+
+    /// The UTF-8 column of a UTF-16 one. A column between the two halves of
+    /// a surrogate pair is at the start of the character.
+    pub fn to_utf8(&self, line_col: LineColUtf16) -> LineCol {
+        let mut col = line_col.col;
+        if let Some(chars) = self.wide_chars.get(&line_col.line) {
+            // The UTF-8 bytes counted so far in excess of the UTF-16 units.
+            let mut excess = 0;
+            for c in chars {
+                let start_utf16 = c.start - excess;
+                if line_col.col <= start_utf16 {
+                    break;
+                }
+                if line_col.col < start_utf16 + c.len_utf16() {
+                    col = c.start;
+                    break;
+                }
+                excess += c.len() - c.len_utf16();
+                col = line_col.col + excess;
+            }
+        }
+        LineCol {
+            line: line_col.line,
+            col,
+        }
+    }
+
+    /// Returns the range of the given line, without its line terminator.
+    pub fn line_range(&self, line: u32) -> Option<TextRange> {
+        let start = *self.newlines.get(line as usize)?;
+        let end = self
+            .newlines
+            .get(line as usize + 1)
+            .map_or(self.len, |&it| it - 1);
+        Some(TextRange::new(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let index = LineIndex::new("ab\ncd\n\nef");
+        assert_eq!(index.line_col(0), LineCol { line: 0, col: 0 });
+        assert_eq!(index.line_col(2), LineCol { line: 0, col: 2 });
+        assert_eq!(index.line_col(3), LineCol { line: 1, col: 0 });
+        assert_eq!(index.line_col(6), LineCol { line: 2, col: 0 });
+        assert_eq!(index.line_col(9), LineCol { line: 3, col: 2 });
+        assert_eq!(index.offset(LineCol { line: 1, col: 1 }), Some(4));
+        assert_eq!(index.offset(LineCol { line: 4, col: 0 }), None);
+        assert_eq!(index.line_range(1), Some(TextRange::new(3, 5)));
+        assert_eq!(index.line_range(3), Some(TextRange::new(7, 9)));
+    }
+
+    #[test]
+    fn test_utf16() {
+        // `é` is two bytes and one UTF-16 unit, `😀` four bytes and two units.
+        let index = LineIndex::new("x\né😀a");
+        let line_col = index.line_col(8);
+        assert_eq!(line_col, LineCol { line: 1, col: 6 });
+        let wide = index.to_utf16(line_col);
+        assert_eq!(wide, LineColUtf16 { line: 1, col: 3 });
+        assert_eq!(index.to_utf8(wide), line_col);
+        assert_eq!(index.to_utf16(LineCol { line: 0, col: 1 }).col, 1);
+    }
+
+    #[test]
+    fn test_positions_inside_characters() {
+        // `é` spans bytes 0..2, `😀` bytes 2..6 and units 1..3.
+        let index = LineIndex::new("é😀a\nb");
+        assert_eq!(index.to_utf8(LineColUtf16 { line: 0, col: 2 }).col, 2);
+        assert_eq!(index.to_utf8(LineColUtf16 { line: 0, col: 3 }).col, 6);
+        assert_eq!(index.to_utf8(LineColUtf16 { line: 0, col: 9 }).col, 12);
+        assert_eq!(index.to_utf16(LineCol { line: 0, col: 4 }).col, 1);
+        assert_eq!(index.offset(LineCol { line: 0, col: 1 }), Some(0));
+        assert_eq!(index.offset(LineCol { line: 0, col: 4 }), Some(2));
+        assert_eq!(index.offset(LineCol { line: 0, col: 40 }), Some(7));
+        assert_eq!(index.offset(LineCol { line: 1, col: 40 }), Some(9));
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let index = LineIndex::new("a\rb\r\nc\nd");
+        assert_eq!(index.line_col(2), LineCol { line: 1, col: 0 });
+        assert_eq!(index.line_col(5), LineCol { line: 2, col: 0 });
+        assert_eq!(index.line_col(7), LineCol { line: 3, col: 0 });
+    }
+}
@@ -0,0 +1,250 @@
+//! Moves a component to another folder, updating the references to it.
+//!
+//! A reference names the component by its path from some root: `models.User`
+//! for `/app/models/User.cfc` means the root is `/app`. After the move, the
+//! reference names the new path from the same root. When the new folder is
+//! outside of that root, the root of the most qualified reference is tried.
+//! Siblings which the component names without dots get qualified names.
+use syntax::{ast::AstNode, TextRange};
+use virtual_fs::{AnchoredPathBuf, FileId, VirtualFsPath};
+
+use crate::{
+    component::Component,
+    resolve::{component_in, resolve_component, static_text},
+    Analysis, FileRange, FileSystemEdit, SourceChange, TextEdit, TextEditBuilder,
+};
+
+/// Returns `true` if `frange` is on the declaration of a component, before
+/// its body.
+pub(crate) fn is_on_component(analysis: &Analysis, frange: FileRange) -> bool {
+    let Some(parse) = analysis.parse(frange.file_id) else {
+        return false;
+    };
+    let Some(component) = parse.syntax_node().descendants().find_map(Component::cast) else {
+        return false;
+    };
+    let header_end = match &component {
+        Component::Script(it) => it.body().map(|it| it.syntax().text_range().start()),
+        Component::Tag(it) => it.open().map(|it| it.syntax().text_range().end()),
+    };
+    let start = component.syntax().text_range().start();
+    header_end.is_some_and(|end| TextRange::new(start, end).contains_range(frange.range))
+}
+
+pub(crate) fn move_component(
+    analysis: &Analysis,
+    file_id: FileId,
+    new_dir: &VirtualFsPath,
+) -> Option<SourceChange> {
+    let old_path = analysis.paths.get(&file_id)?.to_string().replace('\\', "/");
+    let (old_dir, file_name) = old_path.rsplit_once('/')?;
+    let new_dir = new_dir.to_string().replace('\\', "/");
+    let new_dir = new_dir.trim_end_matches('/');
+    if old_dir.eq_ignore_ascii_case(new_dir) {
+        return None;
+    }
+    let new_path = format!("{new_dir}/{file_name}");
+
+    let all_references = analysis.component_references();
+    // The references to the component, with the root each one is relative to.
+    let mut references: Vec<(FileId, TextRange, String)> = all_references
+        .iter()
+        .filter(|it| resolve_component(analysis, it.file_id, it.name.as_str()) == Some(file_id))
+        .filter_map(|it| Some((it.file_id, it.range, root_of(&old_path, it.name.as_str())?)))
+        .collect();
+    // The `name` of the component itself.
+    if let Some(token) = component_in(analysis, file_id)?
+        .find_attribute("name")
+        .and_then(|it| static_text(it.value_element()?))
+    {
+        if let Some(root) = root_of(&old_path, token.text()) {
+            references.push((file_id, token.text_range(), root));
+        }
+    }
+    let fallback_root = references
+        .iter()
+        .map(|(_, _, root)| root)
+        .min_by_key(|it| it.len())
+        .cloned();
+
+    let mut renames: Vec<(FileId, TextRange, String)> = Vec::new();
+    for (ref_file, range, root) in references {
+        let name =
+            name_from(&root, &new_path).or_else(|| name_from(fallback_root.as_deref()?, &new_path));
+        if let Some(name) = name {
+            renames.push((ref_file, range, name));
+        }
+    }
+    // Names without dots in the component itself were looked up next to
+    // it, which they no longer are.
+    for reference in all_references
+        .iter()
+        .filter(|it| it.file_id == file_id && !it.name.as_str().contains('.'))
+    {
+        let target = resolve_component(analysis, file_id, reference.name.as_str())
+            .filter(|it| *it != file_id)
+            .and_then(|it| analysis.paths.get(&it));
+        let name = target
+            .zip(fallback_root.as_deref())
+            .and_then(|(path, root)| name_from(root, &path.to_string().replace('\\', "/")));
+        if let Some(name) = name {
+            renames.push((file_id, reference.range, name));
+        }
+    }
+
+    let mut edits: Vec<(FileId, TextEditBuilder)> = Vec::new();
+    for (ref_file, range, name) in renames {
+        let idx = match edits.iter().position(|(it, _)| *it == ref_file) {
+            Some(idx) => idx,
+            None => {
+                edits.push((ref_file, TextEdit::builder()));
+                edits.len() - 1
+            }
+        };
+        edits[idx].1.replace(range, name);
+    }
+
+    let mut source_change = SourceChange::default();
+    for (file_id, builder) in edits {
+        source_change.insert_source_edit(file_id, builder.finish());
+    }
+    source_change.push_file_system_edit(FileSystemEdit::MoveFile {
+        src: file_id,
+        dst: AnchoredPathBuf {
+            anchor: file_id,
+            path: relative_path(old_dir, &new_path),
+        },
+    });
+    Some(source_change)
+}
+
+/// The folder `name` is relative to, for the component at `path`.
+fn root_of(path: &str, name: &str) -> Option<String> {
+    let suffix = format!("/{}.cfc", name.replace('.', "/"));
+    let root_len = path.len().checked_sub(suffix.len())?;
+    path[root_len..]
+        .eq_ignore_ascii_case(&suffix)
+        .then(|| path[..root_len].to_string())
+}
+
+/// The dotted name of the component at `path`, relative to `root`.
+fn name_from(root: &str, path: &str) -> Option<String> {
+    let prefix = path.get(..root.len() + 1)?;
+    if !prefix.eq_ignore_ascii_case(&format!("{root}/")) {
+        return None;
+    }
+    let rest = path[root.len() + 1..].strip_suffix(".cfc")?;
+    Some(rest.replace('/', "."))
+}
+
+/// `to`, relative to the folder `from`.
+fn relative_path(from: &str, to: &str) -> String {
+    let from: Vec<&str> = from.split('/').filter(|it| !it.is_empty()).collect();
+    let to: Vec<&str> = to.split('/').filter(|it| !it.is_empty()).collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut res = "../".repeat(from.len() - common);
+    res.push_str(&to[common..].join("/"));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::tests::analysis_from_fixture;
+
+    #[test]
+    fn move_updates_references() {
+        let analysis = analysis_from_fixture(
+            r#"
+//- /app/models/User.cfc
+component name="models.User" extends="Base" {}
+//- /app/models/Admin.cfc
+component extends="User" {}
+//- /app/handlers/Main.cfc
+component {
+    property name="users" inject="id:models.User";
+    function f() {
+        a = new models.User();
+        b = createObject("component", "models.User");
+        c = getInstance("models.User");
+        binder.map("user").to("models.User");
+        d = new lib.models.User();
+    }
+}
+//- /app/models/Base.cfc
+component {}
+"#,
+        );
+        let new_dir = VirtualFsPath::new_virtual_path("/app/domain/auth".to_string());
+        let change = move_component(&analysis, FileId(0), &new_dir).unwrap();
+        assert_eq!(
+            change.file_system_edits,
+            vec![FileSystemEdit::MoveFile {
+                src: FileId(0),
+                dst: AnchoredPathBuf {
+                    anchor: FileId(0),
+                    path: "../domain/auth/User.cfc".to_string(),
+                },
+            }]
+        );
+        let apply = |file_id: u32| {
+            let file_id = FileId(file_id);
+            let mut text = analysis.file_text(file_id).unwrap().to_string();
+            if let Some(edit) = change.get_source_edit(file_id) {
+                edit.apply(&mut text);
+            }
+            text
+        };
+        assert_eq!(
+            apply(0),
+            r#"component name="domain.auth.User" extends="models.Base" {}"#
+        );
+        assert_eq!(apply(1), r#"component extends="domain.auth.User" {}"#);
+        assert_eq!(
+            apply(2),
+            r#"component {
+    property name="users" inject="id:domain.auth.User";
+    function f() {
+        a = new domain.auth.User();
+        b = createObject("component", "domain.auth.User");
+        c = getInstance("domain.auth.User");
+        binder.map("user").to("domain.auth.User");
+        d = new lib.models.User();
+    }
+}"#
+        );
+    }
+
+    #[test]
+    fn on_component() {
+        let analysis = analysis_from_fixture(
+            "
+//- /app/User.cfc
+component accessors=\"true\" {
+    function f() {}
+}
+",
+        );
+        let on = |start: u32, end: u32| {
+            is_on_component(
+                &analysis,
+                FileRange {
+                    file_id: FileId(0),
+                    range: TextRange::new(start, end),
+                },
+            )
+        };
+        assert!(on(0, 9));
+        assert!(on(12, 20));
+        assert!(!on(34, 35));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("/app/models", "/app/User.cfc"), "../User.cfc");
+        assert_eq!(
+            relative_path("/app", "/app/models/User.cfc"),
+            "models/User.cfc"
+        );
+    }
+}
@@ -0,0 +1,548 @@
+//! Resolves the dotted names by which CFML refers to components, e.g.
+//! `models.User` in `new models.User()` or `implements="IUser"`, to files.
+//!
+//! Mappings and the web root are not known, so a name matches any `.cfc`
+//! file under the same workspace root whose path ends in it. A name without dots is looked up next to the
+//! file using it first, as the engine does.
+//!
+//! Calls resolve to the functions of the components they are traced to, and
+//! the templates of `<cfinclude>` to files: relative ones from the including
+//! file, and ones starting with `/` like the dotted names.
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+use virtual_fs::FileId;
+
+use crate::{
+    component::Component,
+    tag_schema::BuiltinTag,
+    ty::{infer_expr, Ty},
+    var_scope::{functions, FunctionLike},
+    Analysis, Name, Symbol,
+};
+
+/// A place where a component is named, e.g. `extends="Base"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ComponentReference {
+    pub(crate) file_id: FileId,
+    /// The range of the name alone, without quotes.
+    pub(crate) range: TextRange,
+    pub(crate) name: Name,
+    /// Whether the name is a WireBox id, which a binder may map elsewhere.
+    pub(crate) wirebox: bool,
+}
+
+/// The file of the component called `name` in `from`.
+pub(crate) fn resolve_component(analysis: &Analysis, from: FileId, name: &str) -> Option<FileId> {
+    let path = name.replace('.', "/").to_ascii_lowercase();
+    let suffix = format!("/{path}.cfc");
+    let dir = analysis.paths.get(&from).map(|it| {
+        let path = normalized(&it.to_string());
+        let end = path.rfind('/').unwrap_or(0);
+        path[..end].to_string()
+    });
+    let mut candidates = files_ending_in(analysis, from, &suffix);
+    // Next to `from` first, then the closest to a root.
+    candidates.sort_by_key(|(_, path)| {
+        let is_sibling = dir
+            .as_ref()
+            .is_some_and(|dir| path.len() == dir.len() + suffix.len() && path.starts_with(dir));
+        (!is_sibling, path.len(), path.clone())
+    });
+    candidates.first().map(|(file_id, _)| *file_id)
+}
+
+/// The component declared in a file.
+pub(crate) fn component_in(analysis: &Analysis, file_id: FileId) -> Option<Component> {
+    let root = analysis.parse(file_id)?.syntax_node();
+    root.descendants().find_map(Component::cast)
+}
+
+/// Applies `f` to the component of `file_id` and to every component it
+/// extends, most derived first. Stops at cycles.
+pub(crate) fn walk_extends(
+    analysis: &Analysis,
+    file_id: FileId,
+    mut f: impl FnMut(FileId, &Component),
+) {
+    let mut seen = Vec::new();
+    let mut next = Some(file_id);
+    while let Some(file_id) = next.take() {
+        if seen.contains(&file_id) {
+            break;
+        }
+        seen.push(file_id);
+        let Some(component) = component_in(analysis, file_id) else {
+            break;
+        };
+        f(file_id, &component);
+        next = component
+            .names_in("extends")
+            .first()
+            .and_then(|name| resolve_component(analysis, file_id, name));
+    }
+}
+
+/// The names of components in the file `file_id`, whose tree is `root`.
+pub(crate) fn file_component_references(
+    file_id: FileId,
+    root: &SyntaxNode,
+) -> Vec<ComponentReference> {
+    let mut res = Vec::new();
+    let mut push = |range, name: &str, wirebox| {
+        res.push(ComponentReference {
+            file_id,
+            range,
+            name: Name::new(name),
+            wirebox,
+        })
+    };
+    for node in root.descendants() {
+        if let Some(component) = Component::cast(node.clone()) {
+            for attribute in ["extends", "implements"] {
+                let value = component
+                    .find_attribute(attribute)
+                    .and_then(|it| static_text(it.value_element()?));
+                for (range, name) in value.iter().flat_map(list_items) {
+                    push(range, &name, false);
+                }
+            }
+        } else if let Some(new) = ast::NewExpr::cast(node.clone()) {
+            match new.path() {
+                Some(path) => push(path.syntax().text_range(), &path.syntax().text(), false),
+                None => {
+                    let string = new.syntax().children().find_map(ast::StringLit::cast);
+                    if let Some(token) =
+                        string.and_then(|it| static_text(it.syntax().clone().into()))
+                    {
+                        push(token.text_range(), token.text(), false);
+                    }
+                }
+            }
+        } else if let Some(call) = ast::CallExpr::cast(node.clone()) {
+            let token =
+                call_reference(&call).and_then(|it| static_text(it.syntax().clone().into()));
+            if let Some(token) = token {
+                let wirebox = !call
+                    .callee_name()
+                    .is_some_and(|it| it.eq_ignore_ascii_case("createobject"));
+                push(token.text_range(), token.text(), wirebox);
+            }
+        } else if let Some(attribute) = ast::Attribute::cast(node) {
+            let is_property = attribute.syntax().parent().is_some_and(|it| {
+                it.kind() == PROPERTY_DECL
+                    || ast::Tag::cast(it).is_some_and(|it| it.name_lower() == "cfproperty")
+            });
+            if !is_property || attribute.name_lower() != "inject" {
+                continue;
+            }
+            let Some(token) = attribute.value_element().and_then(static_text) else {
+                continue;
+            };
+            let text = token.text();
+            let (offset, name) = match text.split_once(':') {
+                Some((dsl, name))
+                    if ["id", "model"].contains(&dsl.to_ascii_lowercase().as_str()) =>
+                {
+                    (dsl.len() + 1, name)
+                }
+                Some(_) => continue,
+                None => (0, text),
+            };
+            // `inject="model"` takes the id from the property name.
+            if !name.contains('.') {
+                continue;
+            }
+            let start = token.text_range().start() + offset as TextSize;
+            push(TextRange::at(start, name.len() as TextSize), name, true);
+        }
+    }
+    res
+}
+
+/// A `<cfinclude>` or script `include` of a static template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Include {
+    /// The range of the template alone, without quotes.
+    pub(crate) range: TextRange,
+    pub(crate) template: String,
+}
+
+/// The includes in the tree `root`.
+pub(crate) fn includes(root: &SyntaxNode) -> Vec<Include> {
+    let mut res = Vec::new();
+    for tag in root.descendants().filter_map(BuiltinTag::cast) {
+        if tag.name != "cfinclude" {
+            continue;
+        }
+        let element = match tag.attribute("template") {
+            Some(attribute) => attribute.value_element(),
+            None => tag
+                .name_token
+                .parent()
+                .children()
+                .find_map(ast::StringLit::cast)
+                .map(|it| it.syntax().clone().into()),
+        };
+        if let Some(token) = element.and_then(static_text) {
+            res.push(Include {
+                range: token.text_range(),
+                template: token.text().to_string(),
+            });
+        }
+    }
+    res
+}
+
+/// The file `template` is in, when `from` includes it.
+pub(crate) fn resolve_include(analysis: &Analysis, from: FileId, template: &str) -> Option<FileId> {
+    let template = normalized(template.trim());
+    if template.starts_with('/') {
+        let mut candidates = files_ending_in(analysis, from, &template);
+        candidates.sort_by_key(|(_, path)| (path.len(), path.clone()));
+        return candidates.first().map(|(file_id, _)| *file_id);
+    }
+    let from_path = normalized(&analysis.paths.get(&from)?.to_string());
+    let mut segments: Vec<&str> = from_path.split('/').collect();
+    segments.pop();
+    for segment in template.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let path = segments.join("/");
+    files_ending_in(analysis, from, &path)
+        .into_iter()
+        .find(|(_, it)| *it == path)
+        .map(|(file_id, _)| file_id)
+}
+
+/// The files `from` may refer to whose normalized path ends in `suffix`,
+/// with their path. Only the files with the same name are looked at.
+fn files_ending_in(analysis: &Analysis, from: FileId, suffix: &str) -> Vec<(FileId, String)> {
+    let paths = analysis.normalized_paths();
+    let name = suffix.rsplit('/').next().unwrap_or(suffix);
+    analysis
+        .files_named(Symbol::new(name))
+        .into_iter()
+        .filter(|&file_id| analysis.same_root(from, file_id))
+        .filter_map(|file_id| {
+            let path = paths.get(&file_id)?;
+            path.ends_with(suffix).then(|| (file_id, path.clone()))
+        })
+        .collect()
+}
+
+/// The argument naming a component in `createObject("component", name)`,
+/// `getInstance(name)` or a WireBox binder's `.to(name)`.
+fn call_reference(call: &ast::CallExpr) -> Option<ast::Expr> {
+    let callee = call.callee_name()?.to_ascii_lowercase();
+    let args: Vec<ast::Arg> = call.arg_list()?.args().collect();
+    match callee.as_str() {
+        "createobject" => match args.as_slice() {
+            [only] => only.expr(),
+            [kind, name, ..] => {
+                let kind = match kind.expr() {
+                    Some(ast::Expr::StringLit(it)) => it.value(),
+                    _ => None,
+                };
+                kind.filter(|it| it.eq_ignore_ascii_case("component"))
+                    .and_then(|_| name.expr())
+            }
+            [] => None,
+        },
+        "getinstance" => {
+            let name = args
+                .iter()
+                .find(|it| it.name().is_some_and(|it| it.eq_ignore_ascii_case("name")))
+                .or_else(|| args.first().filter(|it| it.name().is_none()))?;
+            let expr = name.expr()?;
+            // Ids without dots are mapped names, not paths.
+            let is_path = matches!(&expr, ast::Expr::StringLit(it) if it.value().is_some_and(|it| it.contains('.')));
+            is_path.then_some(expr)
+        }
+        "to" if matches!(call.callee(), Some(ast::Expr::FieldExpr(_))) => match args.as_slice() {
+            [only] => only.expr(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The function `call` in `file_id` calls, with the file declaring it:
+/// `f()` and `this.f()` in the file or a component it extends, `super.f()`
+/// in the latter only, and `x.f()` when `x` is known to be an instance of a
+/// component, e.g. from `x = new Service()`.
+pub(crate) fn resolve_call(
+    analysis: &Analysis,
+    file_id: FileId,
+    call: &ast::CallExpr,
+) -> Option<(FileId, FunctionLike)> {
+    let name = call.callee_name()?;
+    let (target, skip) = match call.callee()? {
+        ast::Expr::NameRef(it) => {
+            // A closure in an argument or a local variable.
+            let key = Symbol::new(&name);
+            let is_local = it
+                .syntax()
+                .ancestors()
+                .filter_map(FunctionLike::cast)
+                .any(|it| it.declared_names().contains(&key));
+            if is_local {
+                return None;
+            }
+            (file_id, 0)
+        }
+        ast::Expr::FieldExpr(field) => {
+            let receiver = field.receiver()?;
+            let scope = match &receiver {
+                ast::Expr::NameRef(it) => it.text().to_ascii_lowercase(),
+                _ => String::new(),
+            };
+            match scope.as_str() {
+                "this" | "variables" => (file_id, 0),
+                "super" => (file_id, 1),
+                _ => match infer_expr(&receiver) {
+                    Ty::Component(name) => (resolve_component(analysis, file_id, &name)?, 0),
+                    _ => return None,
+                },
+            }
+        }
+        _ => return None,
+    };
+    let is_named = |it: &FunctionLike| it.name().is_some_and(|it| it.eq_ignore_ascii_case(&name));
+
+    let mut res = None;
+    let mut depth = 0;
+    walk_extends(analysis, target, |decl_file, component| {
+        if res.is_none() && depth >= skip {
+            res = component
+                .functions()
+                .into_iter()
+                .find(is_named)
+                .map(|it| (decl_file, it));
+        }
+        depth += 1;
+    });
+    if depth == 0 && skip == 0 && target == file_id {
+        // The functions of a template.
+        res = functions(&analysis.parse(file_id)?.syntax_node())
+            .filter(|it| matches!(it, FunctionLike::Decl(_) | FunctionLike::Tag(_)))
+            .find(is_named)
+            .map(|it| (file_id, it));
+    }
+    res
+}
+
+/// The token holding a static value: a bare word or the text of a string
+/// without interpolations.
+pub(crate) fn static_text(element: SyntaxElement) -> Option<SyntaxToken> {
+    match element {
+        SyntaxElement::Token(it) => Some(it),
+        SyntaxElement::Node(node) => {
+            let string = ast::StringLit::cast(node)?;
+            if !string.is_static() {
+                return None;
+            }
+            string
+                .syntax()
+                .children_with_tokens()
+                .filter_map(SyntaxElement::into_token)
+                .find(|it| it.kind() == STRING_TEXT)
+        }
+    }
+}
+
+/// The items of a comma-separated list, with their ranges.
+pub(crate) fn list_items(token: &SyntaxToken) -> Vec<(TextRange, String)> {
+    let start = token.text_range().start();
+    let mut res = Vec::new();
+    let mut offset = 0;
+    for item in token.text().split(',') {
+        let trimmed = item.trim();
+        if !trimmed.is_empty() {
+            let item_start = offset + (item.len() - item.trim_start().len());
+            let range = TextRange::at(start + item_start as TextSize, trimmed.len() as TextSize);
+            res.push((range, trimmed.to_string()));
+        }
+        offset += item.len() + 1;
+    }
+    res
+}
+
+pub(crate) fn normalized(path: &str) -> String {
+    path.replace('\\', "/").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::VirtualFsPath;
+
+    use super::*;
+    use crate::AnalysisHost;
+
+    fn analysis(paths: &[&str]) -> Analysis {
+        let mut host = AnalysisHost::default();
+        for (idx, path) in paths.iter().enumerate() {
+            let file_id = FileId(idx as u32);
+            host.set_file_text(file_id, Some("component {}".into()));
+            host.set_file_path(
+                file_id,
+                Some(VirtualFsPath::new_virtual_path(path.to_string())),
+            );
+        }
+        host.analysis()
+    }
+
+    #[test]
+    fn test_resolve_component() {
+        let analysis = analysis(&[
+            "/app/handlers/Main.cfc",
+            "/app/handlers/IHandler.cfc",
+            "/app/models/IHandler.cfc",
+            "/app/models/User.cfc",
+            "/app/lib/models/User.cfc",
+        ]);
+        let resolve = |name| resolve_component(&analysis, FileId(0), name);
+        assert_eq!(resolve("IHandler"), Some(FileId(1)));
+        assert_eq!(resolve("models.IHandler"), Some(FileId(2)));
+        assert_eq!(resolve("MODELS.user"), Some(FileId(3)));
+        assert_eq!(resolve("lib.models.User"), Some(FileId(4)));
+        assert_eq!(resolve("Missing"), None);
+    }
+
+    #[test]
+    fn test_resolve_component_per_root() {
+        let mut host = AnalysisHost::default();
+        for (idx, path) in [
+            "/shop/handlers/Main.cfc",
+            "/blog/models/User.cfc",
+            "/shop/lib/models/User.cfc",
+            "/scratch.cfm",
+        ]
+        .iter()
+        .enumerate()
+        {
+            host.set_file_path(
+                FileId(idx as u32),
+                Some(VirtualFsPath::new_virtual_path(path.to_string())),
+            );
+        }
+        let roots = ["/shop", "/blog"];
+        host.set_roots(
+            roots
+                .iter()
+                .map(|it| VirtualFsPath::new_virtual_path(it.to_string()))
+                .collect(),
+        );
+        let analysis = host.analysis();
+        assert_eq!(
+            resolve_component(&analysis, FileId(0), "models.User"),
+            Some(FileId(2))
+        );
+        assert_eq!(
+            resolve_component(&analysis, FileId(1), "lib.models.User"),
+            None
+        );
+        // Files outside of the roots go with the first one.
+        assert_eq!(
+            resolve_component(&analysis, FileId(3), "models.User"),
+            Some(FileId(2))
+        );
+
+        host.set_roots(Vec::new());
+        assert_eq!(
+            resolve_component(&host.analysis(), FileId(0), "models.User"),
+            Some(FileId(1))
+        );
+    }
+
+    #[test]
+    fn test_walk_extends_cycle() {
+        let mut host = AnalysisHost::default();
+        for (idx, (path, text)) in [
+            ("/app/A.cfc", "component extends=\"B\" {}"),
+            ("/app/B.cfc", "component extends=\"A\" {}"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let file_id = FileId(idx as u32);
+            host.set_file_text(file_id, Some(text.into()));
+            host.set_file_path(
+                file_id,
+                Some(VirtualFsPath::new_virtual_path(path.to_string())),
+            );
+        }
+        let mut walked = Vec::new();
+        walk_extends(&host.analysis(), FileId(0), |it, _| walked.push(it));
+        assert_eq!(walked, [FileId(0), FileId(1)]);
+    }
+
+    #[test]
+    fn test_resolve_include() {
+        let analysis = analysis(&[
+            "/app/pages/index.cfm",
+            "/app/pages/inc/header.cfm",
+            "/app/shared/footer.cfm",
+            "/lib/shared/footer.cfm",
+        ]);
+        let resolve = |template| resolve_include(&analysis, FileId(0), template);
+        assert_eq!(resolve("inc/Header.cfm"), Some(FileId(1)));
+        assert_eq!(resolve("./inc/header.cfm"), Some(FileId(1)));
+        assert_eq!(resolve("../shared/footer.cfm"), Some(FileId(2)));
+        assert_eq!(resolve("/shared/footer.cfm"), Some(FileId(2)));
+        assert_eq!(resolve("header.cfm"), None);
+    }
+
+    #[test]
+    fn test_component_references() {
+        let mut host = AnalysisHost::default();
+        host.set_file_text(
+            FileId(0),
+            Some(
+                r#"component extends="Base" implements="IA, b.IB" {
+    function f() {
+        a = new models.User();
+        b = createObject("component", "models.Role");
+        c = createObject("java", "java.io.File");
+        d = new "models.#x#"();
+        e = getInstance("models.Post");
+        f = getInstance("Settings");
+        binder.map("Mailer").to("services.Mailer");
+    }
+    property name="tags" inject="id:models.Tag";
+}"#
+                .into(),
+            ),
+        );
+        let analysis = host.analysis();
+        let text = analysis.file_text(FileId(0)).unwrap();
+        let refs: Vec<(&str, String)> = analysis
+            .component_references()
+            .iter()
+            .map(|it| (&text[std::ops::Range::from(it.range)], it.name.to_string()))
+            .collect();
+        let names: Vec<(&str, String)> = [
+            "Base",
+            "IA",
+            "b.IB",
+            "models.User",
+            "models.Role",
+            "models.Post",
+            "services.Mailer",
+            "models.Tag",
+        ]
+        .into_iter()
+        .map(|it| (it, it.to_string()))
+        .collect();
+        assert_eq!(refs, names);
+    }
+}
@@ -0,0 +1,44 @@
+//! Changes spanning one or more files, as produced by assists.
+use rustc_hash::FxHashMap;
+use virtual_fs::{AnchoredPathBuf, FileId};
+
+use crate::TextEdit;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SourceChange {
+    pub source_file_edits: FxHashMap<FileId, TextEdit>,
+    /// Applied after the text edits.
+    pub file_system_edits: Vec<FileSystemEdit>,
+    /// The inserted texts are LSP snippets, e.g. with a `$0` cursor.
+    pub is_snippet: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileSystemEdit {
+    /// Moves or renames a file. The destination is relative to the directory
+    /// of the file.
+    MoveFile { src: FileId, dst: AnchoredPathBuf },
+}
+
+impl SourceChange {
+    pub fn from_text_edit(file_id: FileId, edit: TextEdit) -> SourceChange {
+        let mut source_file_edits = FxHashMap::default();
+        source_file_edits.insert(file_id, edit);
+        SourceChange {
+            source_file_edits,
+            ..SourceChange::default()
+        }
+    }
+
+    pub fn insert_source_edit(&mut self, file_id: FileId, edit: TextEdit) {
+        self.source_file_edits.insert(file_id, edit);
+    }
+
+    pub fn push_file_system_edit(&mut self, edit: FileSystemEdit) {
+        self.file_system_edits.push(edit);
+    }
+
+    pub fn get_source_edit(&self, file_id: FileId) -> Option<&TextEdit> {
+        self.source_file_edits.get(&file_id)
+    }
+}
@@ -0,0 +1,247 @@
+//! Dynamic values in SQL: `#expr#` written straight into the body of a
+//! `<cfquery>` or into the SQL string passed to `queryExecute()`.
+//!
+//! Such values end up verbatim in the statement sent to the database, which
+//! both defeats query plan caching and opens the door to SQL injection. They
+//! should be passed as parameters instead.
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::ty::{infer_expr, variable_path, Ty};
+
+/// Functions whose result is safe to write into SQL.
+const SANITIZING_FUNCTIONS: &[&str] = &[
+    "createodbcdate",
+    "createodbcdatetime",
+    "createodbctime",
+    "encodeforsql",
+    "int",
+    "round",
+    "val",
+];
+
+/// Scopes holding values from the request, which are never safe.
+const USER_SCOPES: &[&str] = &["cgi", "cookie", "form", "url"];
+
+const MAX_DEPTH: u32 = 8;
+
+/// Where a dynamic SQL value was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SqlQuery {
+    /// The `<cfquery>` tag whose body contains the value.
+    Tag(ast::Tag),
+    /// The `queryExecute()` call whose SQL string contains the value.
+    Call(ast::CallExpr),
+}
+
+/// Returns the query `hash` is interpolated into, if it is part of SQL.
+pub(crate) fn sql_query(hash: &ast::HashExpr) -> Option<SqlQuery> {
+    let parent = hash.syntax().parent()?;
+    match parent.kind() {
+        TAG => parent
+            .ancestors()
+            .filter_map(ast::Tag::cast)
+            .find(|it| it.name_lower() == "cfquery")
+            .map(SqlQuery::Tag),
+        STRING => sql_string_call(&parent).map(SqlQuery::Call),
+        _ => None,
+    }
+}
+
+/// The `queryExecute()` call whose SQL is the string `string`.
+pub(crate) fn sql_string_call(string: &SyntaxNode) -> Option<ast::CallExpr> {
+    let arg = ast::Arg::cast(string.parent()?)?;
+    let call = ast::CallExpr::cast(arg.syntax().parent()?.parent()?)?;
+    let is_sql = sql_arg(&call).is_some_and(|it| it == arg);
+    is_sql.then_some(call)
+}
+
+/// Returns `true` for calls to `queryExecute()`.
+pub(crate) fn is_query_execute(call: &ast::CallExpr) -> bool {
+    matches!(call.callee(), Some(ast::Expr::NameRef(it)) if it.text().eq_ignore_ascii_case("queryExecute"))
+}
+
+/// The SQL argument of a `queryExecute()` call.
+pub(crate) fn sql_arg(call: &ast::CallExpr) -> Option<ast::Arg> {
+    if !is_query_execute(call) {
+        return None;
+    }
+    query_execute_arg(call, "sql", 0)
+}
+
+/// The parameters argument of a `queryExecute()` call.
+pub(crate) fn params_arg(call: &ast::CallExpr) -> Option<ast::Arg> {
+    query_execute_arg(call, "params", 1)
+}
+
+fn query_execute_arg(call: &ast::CallExpr, name: &str, position: usize) -> Option<ast::Arg> {
+    let args: Vec<_> = call.arg_list()?.args().collect();
+    if args.iter().any(|it| it.name().is_some()) {
+        return args
+            .into_iter()
+            .find(|it| it.name().is_some_and(|it| it.eq_ignore_ascii_case(name)));
+    }
+    args.into_iter().nth(position)
+}
+
+/// The `cfsqltype` to pass a value of type `ty` with.
+pub(crate) fn cf_sql_type(ty: &Ty) -> &'static str {
+    match ty {
+        Ty::Numeric => "cf_sql_numeric",
+        Ty::Boolean => "cf_sql_bit",
+        Ty::Date => "cf_sql_timestamp",
+        Ty::Binary => "cf_sql_blob",
+        _ => "cf_sql_varchar",
+    }
+}
+
+/// Returns `true` if `expr` is provably safe to write into SQL: a literal,
+/// a number, the result of a sanitizing function like `val()`, or a
+/// variable only ever assigned such values, e.g. `sort = asc ? "ASC" :
+/// "DESC"`.
+pub(crate) fn is_safe_value(expr: &ast::Expr) -> bool {
+    is_safe(expr, 0)
+}
+
+fn is_safe(expr: &ast::Expr, depth: u32) -> bool {
+    if depth > MAX_DEPTH {
+        return false;
+    }
+    if matches!(infer_expr(expr), Ty::Numeric | Ty::Boolean | Ty::Date) {
+        return true;
+    }
+    match expr.clone().unwrapped() {
+        ast::Expr::Literal(_) => true,
+        ast::Expr::StringLit(it) => it
+            .interpolations()
+            .all(|it| it.expr().is_some_and(|it| is_safe(&it, depth + 1))),
+        ast::Expr::CallExpr(it) => it
+            .callee_name()
+            .is_some_and(|it| SANITIZING_FUNCTIONS.contains(&it.to_ascii_lowercase().as_str())),
+        ast::Expr::TernaryExpr(it) => {
+            let then_safe = it.then_expr().is_some_and(|it| is_safe(&it, depth + 1));
+            then_safe && it.else_expr().is_some_and(|it| is_safe(&it, depth + 1))
+        }
+        ast::Expr::BinExpr(it) if it.op_text() == "&" => {
+            let lhs_safe = it.lhs().is_some_and(|it| is_safe(&it, depth + 1));
+            lhs_safe && it.rhs().is_some_and(|it| is_safe(&it, depth + 1))
+        }
+        expr @ (ast::Expr::NameRef(_) | ast::Expr::FieldExpr(_)) => {
+            let Some(path) = variable_path(&expr) else {
+                return false;
+            };
+            let scope = path.split('.').next().unwrap_or_default();
+            if USER_SCOPES.contains(&scope) || scope == "arguments" {
+                return false;
+            }
+            let values = assigned_values(expr.syntax(), &path);
+            !values.is_empty() && values.iter().all(|it| is_safe(it, depth + 1))
+        }
+        _ => false,
+    }
+}
+
+/// Every value the file assigns to the variable `path`.
+fn assigned_values(at: &SyntaxNode, path: &str) -> Vec<ast::Expr> {
+    let root = at.ancestors().last().unwrap_or_else(|| at.clone());
+    let name = path.strip_prefix("local.").unwrap_or(path);
+    let mut res = Vec::new();
+    for node in root.descendants() {
+        let value = if let Some(assign) = ast::AssignExpr::cast(node.clone()) {
+            let lhs = assign.lhs().and_then(|it| variable_path(&it));
+            if lhs.as_deref() != Some(path) {
+                continue;
+            }
+            assign.rhs()
+        } else if let Some(decl) = ast::VarDecl::cast(node) {
+            let declared = decl.name().map(|it| it.text().to_ascii_lowercase());
+            if declared.as_deref() != Some(name) {
+                continue;
+            }
+            decl.initializer()
+        } else {
+            continue;
+        };
+        match value {
+            Some(value) => res.push(value),
+            // Can't tell what was assigned.
+            None => return Vec::new(),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str, expected: &[&str]) {
+        let root = syntax::parse(text).syntax_node();
+        let actual: Vec<_> = root
+            .descendants()
+            .filter_map(ast::HashExpr::cast)
+            .filter(|it| sql_query(it).is_some())
+            .map(|it| it.syntax().text())
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_tag_query() {
+        check(
+            r##"<cfoutput>#a#</cfoutput>
+<cfquery name="q" datasource="#ds#">
+    select * from t where id = #b#
+    <cfif x>and name = '#c#'</cfif>
+    and x = <cfqueryparam value="#d#">
+</cfquery>"##,
+            &["#b#", "#c#"],
+        );
+    }
+
+    #[test]
+    fn test_query_execute() {
+        check(
+            r##"<cfscript>
+    queryExecute("select * from t where id = #a#", {}, {datasource: "#ds#"});
+    queryExecute(params = {}, sql = "select #b#");
+    foo("select #c#");
+</cfscript>"##,
+            &["#a#", "#b#"],
+        );
+    }
+
+    #[test]
+    fn test_is_safe_value() {
+        let root = syntax::parse(
+            r##"<cfscript>
+    sort = asc ? "name" : "name DESC";
+    table = "users_#year#";
+    queryExecute("select #val(form.id)#, #sort#, #42#, #form.name#, #table#, #arguments.x#");
+</cfscript>"##,
+        )
+        .syntax_node();
+        let actual: Vec<(String, bool)> = root
+            .descendants()
+            .filter_map(ast::HashExpr::cast)
+            .filter(|it| sql_query(it).is_some())
+            .map(|it| (it.syntax().text(), is_safe_value(&it.expr().unwrap())))
+            .collect();
+        let expected = [
+            ("#val(form.id)#", true),
+            ("#sort#", true),
+            ("#42#", true),
+            ("#form.name#", false),
+            ("#table#", false),
+            ("#arguments.x#", false),
+        ];
+        let expected: Vec<(String, bool)> = expected
+            .iter()
+            .map(|(text, safe)| (text.to_string(), *safe))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+}
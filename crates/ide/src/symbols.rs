@@ -0,0 +1,205 @@
+//! The components and functions a file declares, as the analysis sees them,
+//! with the components they extend and implement resolved to files.
+use syntax::{SyntaxNode, TextRange};
+use virtual_fs::FileId;
+
+use crate::{
+    component::{Component, Signature},
+    resolve::resolve_component,
+    var_scope::{functions, FunctionLike},
+    Analysis,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSymbols {
+    pub component: Option<ComponentSymbol>,
+    /// The functions declared outside of a component, e.g. in a template.
+    pub functions: Vec<FunctionSymbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentSymbol {
+    /// The name of the file, which is the name of the component.
+    pub name: String,
+    pub range: TextRange,
+    pub is_interface: bool,
+    pub extends: Option<ComponentName>,
+    pub implements: Vec<ComponentName>,
+    pub properties: Vec<String>,
+    pub functions: Vec<FunctionSymbol>,
+}
+
+/// A component named by another, with the file it resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentName {
+    pub name: String,
+    pub resolved: Option<FileId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSymbol {
+    pub name: String,
+    /// The range of the name.
+    pub range: TextRange,
+    pub access: Option<String>,
+    pub return_type: Option<String>,
+    pub params: Vec<String>,
+}
+
+pub(crate) fn file_symbols(analysis: &Analysis, file_id: FileId) -> Option<FileSymbols> {
+    let root = analysis.parse(file_id)?.syntax_node();
+    let Some(component) = root.descendants().find_map(Component::cast) else {
+        return Some(FileSymbols {
+            component: None,
+            functions: top_level_functions(&root),
+        });
+    };
+    let names_in = |attribute| {
+        component
+            .names_in(attribute)
+            .into_iter()
+            .map(|name| ComponentName {
+                resolved: resolve_component(analysis, file_id, &name),
+                name,
+            })
+            .collect::<Vec<_>>()
+    };
+    let name = analysis
+        .paths
+        .get(&file_id)
+        .map(|path| {
+            let path = path.to_string();
+            let file_name = path.rsplit(['/', '\\']).next().unwrap_or(&path);
+            let stem = file_name
+                .rsplit_once('.')
+                .map_or(file_name, |(stem, _)| stem);
+            stem.to_string()
+        })
+        .unwrap_or_default();
+    Some(FileSymbols {
+        component: Some(ComponentSymbol {
+            name,
+            range: component.syntax().text_range(),
+            is_interface: component.is_interface(),
+            extends: names_in("extends").into_iter().next(),
+            implements: names_in("implements"),
+            properties: component
+                .properties()
+                .iter()
+                .filter_map(|it| it.name())
+                .collect(),
+            functions: component
+                .functions()
+                .iter()
+                .filter_map(function_symbol)
+                .collect(),
+        }),
+        functions: Vec::new(),
+    })
+}
+
+/// The named functions not nested in another.
+fn top_level_functions(root: &SyntaxNode) -> Vec<FunctionSymbol> {
+    functions(root)
+        .filter(|it| {
+            it.syntax()
+                .ancestors()
+                .skip(1)
+                .all(|it| FunctionLike::cast(it).is_none())
+        })
+        .filter_map(|it| function_symbol(&it))
+        .collect()
+}
+
+fn function_symbol(func: &FunctionLike) -> Option<FunctionSymbol> {
+    let signature = Signature::of(func)?;
+    Some(FunctionSymbol {
+        name: signature.name,
+        range: func.name_range()?,
+        access: signature.access,
+        return_type: signature.return_type,
+        params: signature.params.into_iter().map(|it| it.name).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::VirtualFsPath;
+
+    use super::*;
+    use crate::AnalysisHost;
+
+    #[test]
+    fn test_file_symbols() {
+        let mut host = AnalysisHost::default();
+        let files = [
+            (
+                "/app/models/User.cfc",
+                r#"component extends="Base" implements="IUser, IMissing" accessors="true" {
+    property name="email";
+    public string function getName(required string id, boolean full = false) {
+        var f = function(x) { return x; };
+        return "";
+    }
+}"#,
+            ),
+            ("/app/models/Base.cfc", "component {}"),
+            ("/app/models/IUser.cfc", "interface {}"),
+            (
+                "/app/helpers.cfm",
+                "<cfscript>function helper(a) { function inner() {} }</cfscript>",
+            ),
+        ];
+        for (idx, (path, text)) in files.iter().enumerate() {
+            let file_id = FileId(idx as u32);
+            host.set_file_text(file_id, Some((*text).into()));
+            host.set_file_path(
+                file_id,
+                Some(VirtualFsPath::new_virtual_path(path.to_string())),
+            );
+        }
+        let analysis = host.analysis();
+
+        let symbols = analysis.file_symbols(FileId(0)).unwrap();
+        let component = symbols.component.unwrap();
+        assert_eq!(component.name, "User");
+        assert!(!component.is_interface);
+        assert_eq!(
+            component.extends,
+            Some(ComponentName {
+                name: "Base".to_string(),
+                resolved: Some(FileId(1)),
+            })
+        );
+        let implements: Vec<_> = component
+            .implements
+            .iter()
+            .map(|it| (it.name.as_str(), it.resolved))
+            .collect();
+        assert_eq!(implements, [("IUser", Some(FileId(2))), ("IMissing", None)]);
+        assert_eq!(component.properties, ["email"]);
+        let get_name = &component.functions[..];
+        assert_eq!(get_name.len(), 1);
+        assert_eq!(get_name[0].name, "getName");
+        assert_eq!(get_name[0].access.as_deref(), Some("public"));
+        assert_eq!(get_name[0].return_type.as_deref(), Some("string"));
+        assert_eq!(get_name[0].params, ["id", "full"]);
+
+        assert!(
+            analysis
+                .file_symbols(FileId(2))
+                .unwrap()
+                .component
+                .unwrap()
+                .is_interface
+        );
+        let helpers = analysis.file_symbols(FileId(3)).unwrap();
+        assert!(helpers.component.is_none());
+        let names: Vec<_> = helpers
+            .functions
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect();
+        assert_eq!(names, ["helper"]);
+    }
+}
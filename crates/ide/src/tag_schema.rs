@@ -0,0 +1,848 @@
+//! The attributes of the built-in tags: the engines supporting them, the
+//! values they take and the combinations each tag requires.
+//!
+//! Tags which aren't listed, like custom tags, aren't checked. Tags which
+//! keep the attributes they don't declare, as metadata or to pass them on,
+//! are marked as such.
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken,
+};
+
+use crate::{Engine, EngineKind};
+
+use EngineKind::*;
+
+pub(crate) struct TagSchema {
+    /// In lowercase, with the `cf` prefix.
+    pub(crate) name: &'static str,
+    pub(crate) attributes: &'static [AttributeSchema],
+    /// Accepts any attribute, e.g. `cfmodule` passes them to the module and
+    /// `cffunction` keeps them as metadata.
+    pub(crate) passthrough: bool,
+    /// The combinations of attributes the tag needs, one of which must be
+    /// given in full. Nothing is required when empty.
+    pub(crate) required: &'static [&'static [&'static str]],
+}
+
+impl TagSchema {
+    const fn requires(self, required: &'static [&'static [&'static str]]) -> TagSchema {
+        TagSchema { required, ..self }
+    }
+
+    pub(crate) fn attribute(&self, name: &str) -> Option<&'static AttributeSchema> {
+        self.attributes.iter().find(|it| it.name == name)
+    }
+}
+
+pub(crate) struct AttributeSchema {
+    /// In lowercase.
+    pub(crate) name: &'static str,
+    /// The only engine supporting the attribute, since the given version.
+    pub(crate) only: Option<(EngineKind, u32)>,
+    pub(crate) ty: AttributeType,
+}
+
+/// The values an attribute takes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum AttributeType {
+    Any,
+    /// `true`, `false`, `yes`, `no` or a number.
+    Boolean,
+    Numeric,
+    /// One of these lowercase values.
+    OneOf(&'static [&'static str]),
+    /// One of [`SQL_TYPES`], with or without the `cf_sql_` prefix.
+    SqlType,
+}
+
+/// The types of `cfsqltype`, without their `cf_sql_` prefix.
+pub(crate) const SQL_TYPES: &[&str] = &[
+    "array",
+    "bigint",
+    "binary",
+    "bit",
+    "blob",
+    "char",
+    "clob",
+    "date",
+    "decimal",
+    "distinct",
+    "double",
+    "float",
+    "idstamp",
+    "integer",
+    "longnvarchar",
+    "longvarbinary",
+    "longvarchar",
+    "money",
+    "money4",
+    "nchar",
+    "nclob",
+    "numeric",
+    "nvarchar",
+    "other",
+    "real",
+    "refcursor",
+    "smallint",
+    "sqlxml",
+    "struct",
+    "time",
+    "timestamp",
+    "tinyint",
+    "varbinary",
+    "varchar",
+];
+
+impl AttributeSchema {
+    const fn boolean(self) -> AttributeSchema {
+        AttributeSchema {
+            ty: AttributeType::Boolean,
+            ..self
+        }
+    }
+
+    const fn numeric(self) -> AttributeSchema {
+        AttributeSchema {
+            ty: AttributeType::Numeric,
+            ..self
+        }
+    }
+
+    const fn one_of(self, values: &'static [&'static str]) -> AttributeSchema {
+        AttributeSchema {
+            ty: AttributeType::OneOf(values),
+            ..self
+        }
+    }
+
+    const fn sql_type(self) -> AttributeSchema {
+        AttributeSchema {
+            ty: AttributeType::SqlType,
+            ..self
+        }
+    }
+
+    /// Returns `true` if `engine` supports the attribute, or isn't known.
+    pub(crate) fn supported_by(&self, engine: Option<Engine>) -> bool {
+        match (self.only, engine) {
+            (Some((kind, since)), Some(engine)) => engine.kind == kind && engine.version >= since,
+            _ => true,
+        }
+    }
+}
+
+const fn tag(name: &'static str, attributes: &'static [AttributeSchema]) -> TagSchema {
+    TagSchema {
+        name,
+        attributes,
+        passthrough: false,
+        required: &[],
+    }
+}
+
+const fn passthrough(name: &'static str, attributes: &'static [AttributeSchema]) -> TagSchema {
+    TagSchema {
+        name,
+        attributes,
+        passthrough: true,
+        required: &[],
+    }
+}
+
+const fn attr(name: &'static str) -> AttributeSchema {
+    AttributeSchema {
+        name,
+        only: None,
+        ty: AttributeType::Any,
+    }
+}
+
+const fn only(engine: EngineKind, since: u32, name: &'static str) -> AttributeSchema {
+    AttributeSchema {
+        name,
+        only: Some((engine, since)),
+        ty: AttributeType::Any,
+    }
+}
+
+/// Every tag accepts its attributes as a struct.
+pub(crate) const ATTRIBUTE_COLLECTION: &str = "attributecollection";
+
+const TAGS: &[TagSchema] = &[
+    tag("cfabort", &[attr("showerror")]),
+    passthrough(
+        "cfargument",
+        &[
+            attr("default"),
+            attr("displayname"),
+            attr("hint"),
+            attr("name"),
+            attr("required").boolean(),
+            attr("type"),
+        ],
+    )
+    .requires(&[&["name"]]),
+    tag("cfbreak", &[]),
+    tag("cfcase", &[attr("delimiters"), attr("value")]).requires(&[&["value"]]),
+    tag("cfcatch", &[attr("type")]),
+    passthrough(
+        "cfcomponent",
+        &[
+            attr("accessors").boolean(),
+            attr("alias"),
+            attr("bindingname"),
+            attr("displayname"),
+            attr("extends"),
+            attr("hint"),
+            attr("implements"),
+            attr("output").boolean(),
+            attr("persistent").boolean(),
+            attr("serializable").boolean(),
+        ],
+    ),
+    tag(
+        "cfcontent",
+        &[
+            attr("deletefile").boolean(),
+            attr("file"),
+            attr("reset").boolean(),
+            attr("type"),
+            attr("variable"),
+        ],
+    ),
+    tag("cfcontinue", &[]),
+    tag(
+        "cfcookie",
+        &[
+            attr("domain"),
+            attr("encodevalue").boolean(),
+            attr("expires"),
+            attr("httponly").boolean(),
+            attr("name"),
+            attr("path"),
+            attr("preservecase").boolean(),
+            attr("samesite").one_of(&["lax", "none", "strict"]),
+            attr("secure").boolean(),
+            attr("value"),
+        ],
+    )
+    .requires(&[&["name"]]),
+    tag("cfdefaultcase", &[]),
+    tag(
+        "cfdirectory",
+        &[
+            attr("action").one_of(&["copy", "create", "delete", "list", "rename"]),
+            attr("createpath").boolean(),
+            attr("destination"),
+            attr("directory"),
+            attr("filter"),
+            attr("listinfo").one_of(&["all", "name"]),
+            attr("mode"),
+            attr("name"),
+            attr("newdirectory"),
+            attr("recurse").boolean(),
+            attr("sort"),
+            attr("storeacl"),
+            attr("storelocation"),
+            attr("type").one_of(&["all", "dir", "file"]),
+        ],
+    )
+    .requires(&[&["directory"]]),
+    tag(
+        "cfdump",
+        &[
+            attr("abort").boolean(),
+            only(Lucee, 5, "eval"),
+            attr("expand").boolean(),
+            attr("format"),
+            attr("hide"),
+            attr("keys"),
+            attr("label"),
+            attr("metainfo").boolean(),
+            attr("output"),
+            attr("show"),
+            attr("showudfs").boolean(),
+            attr("top").numeric(),
+            attr("var"),
+        ],
+    )
+    .requires(&[&["var"]]),
+    tag("cfelse", &[]),
+    tag(
+        "cfexecute",
+        &[
+            attr("arguments"),
+            attr("errorfile"),
+            attr("errorvariable"),
+            attr("name"),
+            attr("outputfile"),
+            attr("timeout").numeric(),
+            attr("variable"),
+        ],
+    )
+    .requires(&[&["name"]]),
+    tag(
+        "cfexit",
+        &[attr("method").one_of(&["exittag", "exittemplate", "loop"])],
+    ),
+    tag(
+        "cffile",
+        &[
+            attr("accept"),
+            attr("action").one_of(&[
+                "append",
+                "copy",
+                "delete",
+                "info",
+                "move",
+                "read",
+                "readbinary",
+                "rename",
+                "touch",
+                "upload",
+                "uploadall",
+                "write",
+            ]),
+            attr("addnewline").boolean(),
+            attr("allowedextensions"),
+            attr("attributes"),
+            attr("charset"),
+            attr("destination"),
+            attr("file"),
+            attr("filefield"),
+            attr("fixnewline").boolean(),
+            attr("mode"),
+            attr("nameconflict").one_of(&["error", "makeunique", "overwrite", "skip"]),
+            attr("output"),
+            attr("result"),
+            attr("source"),
+            attr("strict").boolean(),
+            attr("variable"),
+        ],
+    )
+    .requires(&[&["action"]]),
+    tag("cffinally", &[]),
+    tag("cfflush", &[attr("interval").numeric()]),
+    passthrough(
+        "cffunction",
+        &[
+            attr("access").one_of(&["package", "private", "public", "remote"]),
+            attr("description"),
+            attr("displayname"),
+            attr("hint"),
+            attr("name"),
+            attr("output").boolean(),
+            attr("returnformat").one_of(&["json", "plain", "wddx"]),
+            attr("returntype"),
+            attr("roles"),
+            attr("securejson").boolean(),
+            attr("verifyclient").boolean(),
+        ],
+    )
+    .requires(&[&["name"]]),
+    tag(
+        "cfheader",
+        &[
+            attr("charset"),
+            attr("name"),
+            attr("statuscode").numeric(),
+            attr("statustext"),
+            attr("value"),
+        ],
+    )
+    .requires(&[&["name"], &["statuscode"]]),
+    tag(
+        "cfhttp",
+        &[
+            attr("authtype"),
+            attr("cachedwithin"),
+            attr("charset"),
+            attr("clientcert"),
+            attr("clientcertpassword"),
+            attr("columns"),
+            attr("compression"),
+            attr("delimiter"),
+            attr("domain"),
+            attr("encodeurl"),
+            attr("file"),
+            attr("firstrowasheaders").boolean(),
+            attr("getasbinary"),
+            attr("method").one_of(&[
+                "delete", "get", "head", "options", "patch", "post", "put", "trace",
+            ]),
+            attr("multipart").boolean(),
+            attr("multiparttype"),
+            attr("name"),
+            attr("password"),
+            attr("path"),
+            attr("port").numeric(),
+            attr("proxypassword"),
+            attr("proxyport").numeric(),
+            attr("proxyserver"),
+            attr("proxyuser"),
+            attr("redirect").boolean(),
+            attr("resolveurl").boolean(),
+            attr("result"),
+            attr("textqualifier"),
+            attr("throwonerror").boolean(),
+            attr("timeout").numeric(),
+            attr("url"),
+            attr("useragent"),
+            attr("username"),
+            attr("workstation"),
+        ],
+    )
+    .requires(&[&["url"]]),
+    tag(
+        "cfhttpparam",
+        &[
+            attr("encoded").boolean(),
+            attr("file"),
+            attr("mimetype"),
+            attr("name"),
+            attr("type").one_of(&[
+                "body",
+                "cgi",
+                "cookie",
+                "file",
+                "formfield",
+                "header",
+                "url",
+                "xml",
+            ]),
+            attr("value"),
+        ],
+    )
+    .requires(&[&["type"]]),
+    tag("cfimport", &[attr("path"), attr("prefix"), attr("taglib")])
+        .requires(&[&["path"], &["taglib"]]),
+    tag("cfinclude", &[attr("runonce").boolean(), attr("template")]).requires(&[&["template"]]),
+    passthrough(
+        "cfinterface",
+        &[attr("displayname"), attr("extends"), attr("hint")],
+    ),
+    // Undeclared attributes are arguments of the method.
+    passthrough(
+        "cfinvoke",
+        &[
+            attr("argumentcollection"),
+            attr("component"),
+            attr("method"),
+            attr("password"),
+            attr("returnvariable"),
+            attr("timeout"),
+            attr("username"),
+            attr("webservice"),
+        ],
+    )
+    .requires(&[&["method"]]),
+    tag(
+        "cfinvokeargument",
+        &[attr("name"), attr("omit").boolean(), attr("value")],
+    )
+    .requires(&[&["name"]]),
+    tag(
+        "cflocation",
+        &[
+            attr("addtoken").boolean(),
+            attr("encode").boolean(),
+            attr("statuscode").numeric(),
+            attr("url"),
+        ],
+    )
+    .requires(&[&["url"]]),
+    tag(
+        "cflock",
+        &[
+            attr("name"),
+            attr("scope").one_of(&["application", "request", "server", "session"]),
+            attr("throwontimeout").boolean(),
+            attr("timeout").numeric(),
+            attr("type").one_of(&["exclusive", "readonly"]),
+        ],
+    )
+    .requires(&[&["name", "timeout"], &["scope", "timeout"]]),
+    tag(
+        "cflog",
+        &[
+            attr("application"),
+            attr("file"),
+            attr("log"),
+            attr("text"),
+            attr("type"),
+        ],
+    )
+    .requires(&[&["text"]]),
+    tag(
+        "cfloop",
+        &[
+            attr("array"),
+            attr("characters").numeric(),
+            attr("charset"),
+            attr("collection"),
+            attr("condition"),
+            attr("delimiters"),
+            attr("endrow").numeric(),
+            attr("file"),
+            attr("from").numeric(),
+            attr("group"),
+            attr("groupcasesensitive").boolean(),
+            attr("index"),
+            attr("item"),
+            attr("list"),
+            attr("query"),
+            attr("startrow").numeric(),
+            attr("step").numeric(),
+            only(Lucee, 5, "times").numeric(),
+            attr("to").numeric(),
+        ],
+    )
+    .requires(&[
+        &["array", "index"],
+        &["array", "item"],
+        &["collection", "index"],
+        &["collection", "item"],
+        &["condition"],
+        &["file", "index"],
+        &["file", "item"],
+        &["from", "index", "to"],
+        &["group"],
+        &["list", "index"],
+        &["list", "item"],
+        &["query"],
+        &["times"],
+    ]),
+    tag(
+        "cfmail",
+        &[
+            attr("bcc"),
+            attr("cc"),
+            attr("charset"),
+            attr("debug").boolean(),
+            attr("failto"),
+            attr("from"),
+            attr("group"),
+            attr("groupcasesensitive").boolean(),
+            attr("maxrows").numeric(),
+            attr("mimeattach"),
+            attr("password"),
+            attr("port").numeric(),
+            attr("priority"),
+            attr("query"),
+            attr("remove").boolean(),
+            attr("replyto"),
+            attr("server"),
+            attr("spoolenable").boolean(),
+            attr("startrow").numeric(),
+            attr("subject"),
+            attr("timeout").numeric(),
+            attr("to"),
+            attr("type"),
+            attr("username"),
+            attr("usessl").boolean(),
+            attr("usetls").boolean(),
+            attr("wraptext").numeric(),
+        ],
+    )
+    .requires(&[&["from", "to"]]),
+    tag(
+        "cfmailparam",
+        &[
+            attr("content"),
+            attr("contentid"),
+            attr("disposition"),
+            attr("file"),
+            attr("name"),
+            attr("remove").boolean(),
+            attr("type"),
+            attr("value"),
+        ],
+    )
+    .requires(&[&["content"], &["file"], &["name"]]),
+    tag(
+        "cfmailpart",
+        &[attr("charset"), attr("type"), attr("wraptext").numeric()],
+    )
+    .requires(&[&["type"]]),
+    // Undeclared attributes are the attributes of the module.
+    passthrough("cfmodule", &[attr("name"), attr("template")])
+        .requires(&[&["name"], &["template"]]),
+    tag(
+        "cfoutput",
+        &[
+            attr("encodefor").one_of(&[
+                "css",
+                "dn",
+                "html",
+                "htmlattribute",
+                "javascript",
+                "ldap",
+                "url",
+                "xml",
+                "xmlattribute",
+                "xpath",
+            ]),
+            attr("group"),
+            attr("groupcasesensitive").boolean(),
+            attr("maxrows").numeric(),
+            attr("query"),
+            attr("startrow").numeric(),
+        ],
+    ),
+    tag(
+        "cfparam",
+        &[
+            attr("default"),
+            attr("max"),
+            attr("maxlength").numeric(),
+            attr("min"),
+            attr("name"),
+            attr("pattern"),
+            attr("type"),
+        ],
+    )
+    .requires(&[&["name"]]),
+    tag(
+        "cfprocessingdirective",
+        &[attr("pageencoding"), attr("suppresswhitespace").boolean()],
+    ),
+    tag(
+        "cfprocparam",
+        &[
+            attr("cfsqltype").sql_type(),
+            attr("dbvarname"),
+            attr("maxlength").numeric(),
+            attr("null").boolean(),
+            attr("scale").numeric(),
+            attr("type").one_of(&["in", "inout", "out"]),
+            attr("value"),
+            attr("variable"),
+        ],
+    ),
+    tag(
+        "cfprocresult",
+        &[
+            attr("maxrows").numeric(),
+            attr("name"),
+            attr("resultset").numeric(),
+        ],
+    )
+    .requires(&[&["name"]]),
+    passthrough(
+        "cfproperty",
+        &[
+            attr("default"),
+            attr("displayname"),
+            attr("getter").boolean(),
+            attr("hint"),
+            attr("name"),
+            attr("required").boolean(),
+            attr("setter").boolean(),
+            attr("type"),
+        ],
+    ),
+    tag(
+        "cfquery",
+        &[
+            attr("blockfactor").numeric(),
+            attr("cachedafter"),
+            attr("cachedwithin"),
+            only(Adobe, 9, "cachename"),
+            only(Adobe, 9, "cacheregion"),
+            only(Adobe, 11, "clientinfo"),
+            attr("columnkey"),
+            attr("datasource"),
+            attr("dbtype"),
+            attr("debug").boolean(),
+            only(Adobe, 11, "fetchclientinfo"),
+            only(Lucee, 5, "lazy").boolean(),
+            only(Lucee, 6, "listener"),
+            attr("maxrows").numeric(),
+            attr("name"),
+            only(Adobe, 9, "ormoptions"),
+            attr("password"),
+            only(Lucee, 5, "psq").boolean(),
+            attr("result"),
+            attr("returntype").one_of(&["array", "query", "struct"]),
+            only(Lucee, 5, "tags"),
+            attr("timeout").numeric(),
+            attr("username"),
+        ],
+    ),
+    tag(
+        "cfqueryparam",
+        &[
+            attr("cfsqltype").sql_type(),
+            attr("list").boolean(),
+            attr("maxlength").numeric(),
+            attr("null").boolean(),
+            attr("scale").numeric(),
+            attr("separator"),
+            only(Lucee, 5, "sqltype"),
+            attr("value"),
+        ],
+    )
+    .requires(&[&["null"], &["value"]]),
+    tag("cfrethrow", &[]),
+    tag(
+        "cfsavecontent",
+        &[
+            attr("append").boolean(),
+            only(Lucee, 5, "trim").boolean(),
+            attr("variable"),
+        ],
+    )
+    .requires(&[&["variable"]]),
+    tag("cfscript", &[]),
+    tag(
+        "cfsetting",
+        &[
+            attr("enablecfoutputonly").boolean(),
+            attr("requesttimeout").numeric(),
+            attr("showdebugoutput").boolean(),
+        ],
+    ),
+    tag("cfsilent", &[]),
+    tag(
+        "cfstoredproc",
+        &[
+            attr("blockfactor").numeric(),
+            attr("cachedafter"),
+            attr("cachedwithin"),
+            attr("datasource"),
+            attr("debug").boolean(),
+            attr("password"),
+            attr("procedure"),
+            attr("result"),
+            attr("returncode").boolean(),
+            attr("username"),
+        ],
+    )
+    .requires(&[&["procedure"]]),
+    tag("cfswitch", &[attr("expression")]).requires(&[&["expression"]]),
+    // Undeclared attributes end up in the `attributes` scope of the thread.
+    passthrough(
+        "cfthread",
+        &[
+            attr("action").one_of(&["join", "run", "sleep", "terminate"]),
+            attr("duration").numeric(),
+            attr("name"),
+            attr("priority").one_of(&["high", "low", "normal"]),
+            attr("timeout").numeric(),
+        ],
+    ),
+    tag(
+        "cfthrow",
+        &[
+            attr("detail"),
+            attr("errorcode"),
+            attr("extendedinfo"),
+            attr("message"),
+            attr("object"),
+            attr("type"),
+        ],
+    ),
+    tag("cftimer", &[attr("label"), attr("type")]),
+    tag(
+        "cftransaction",
+        &[
+            attr("action").one_of(&["begin", "commit", "rollback", "setsavepoint"]),
+            attr("datasource"),
+            attr("isolation").one_of(&[
+                "read_committed",
+                "read_uncommitted",
+                "repeatable_read",
+                "serializable",
+            ]),
+            attr("nested").boolean(),
+            attr("savepoint"),
+        ],
+    ),
+    tag("cftry", &[]),
+];
+
+/// The schema of a built-in tag, by lowercase name.
+pub(crate) fn tag_schema(name: &str) -> Option<&'static TagSchema> {
+    TAGS.iter().find(|it| it.name == name)
+}
+
+/// A built-in tag, or the script statement standing for it, like `lock`.
+pub(crate) struct BuiltinTag {
+    /// In lowercase, with the `cf` prefix.
+    pub(crate) name: String,
+    /// The tag name, or the keyword of the statement.
+    pub(crate) name_token: SyntaxToken,
+    pub(crate) schema: &'static TagSchema,
+    pub(crate) attributes: Vec<ast::Attribute>,
+    /// Written in the short form of script, which gives its main attribute
+    /// without naming it, like `include "a.cfm";` or `param x = 1;`.
+    pub(crate) shorthand: bool,
+}
+
+impl BuiltinTag {
+    pub(crate) fn cast(node: SyntaxNode) -> Option<BuiltinTag> {
+        let res = match node.kind() {
+            TAG => {
+                let tag = ast::Tag::cast(node)?;
+                let name = tag.name_lower();
+                BuiltinTag {
+                    schema: tag_schema(&name)?,
+                    name,
+                    name_token: tag.name()?,
+                    attributes: tag.attributes().collect(),
+                    shorthand: false,
+                }
+            }
+            SCRIPT_TAG_STMT => {
+                let stmt = ast::ScriptTagStmt::cast(node)?;
+                let name = format!("cf{}", stmt.name_lower());
+                let mut attributes: Vec<ast::Attribute> = stmt.attributes().collect();
+                let mut shorthand = stmt
+                    .syntax()
+                    .children()
+                    .any(|it| ast::Expr::can_cast(it.kind()));
+                // `param x = 1;` names the parameter with its first attribute.
+                if name == "cfparam" && !attributes.iter().any(|it| it.name_lower() == "name") {
+                    shorthand |= !attributes.is_empty();
+                    attributes.drain(..attributes.len().min(1));
+                }
+                BuiltinTag {
+                    schema: tag_schema(&name)?,
+                    name,
+                    name_token: stmt.syntax().first_token()?,
+                    attributes,
+                    shorthand,
+                }
+            }
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    pub(crate) fn attribute(&self, name: &str) -> Option<&ast::Attribute> {
+        self.attributes.iter().find(|it| it.name_lower() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consistent_schemas() {
+        let is_sorted = |names: Vec<&str>| names.windows(2).all(|it| it[0] < it[1]);
+        assert!(is_sorted(TAGS.iter().map(|it| it.name).collect()));
+        for tag in TAGS {
+            assert!(
+                is_sorted(tag.attributes.iter().map(|it| it.name).collect()),
+                "{}",
+                tag.name
+            );
+            for name in tag.required.iter().flat_map(|it| it.iter()) {
+                assert!(tag.attribute(name).is_some(), "{} {name}", tag.name);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Edits to the text of a single file.
+use syntax::{TextRange, TextSize};
+
+/// Deletes `delete` and inserts `insert` in its place.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Indel {
+    pub insert: String,
+    pub delete: TextRange,
+}
+
+impl Indel {
+    pub fn insert(offset: TextSize, text: String) -> Indel {
+        Indel::replace(TextRange::empty(offset), text)
+    }
+
+    pub fn delete(range: TextRange) -> Indel {
+        Indel::replace(range, String::new())
+    }
+
+    pub fn replace(range: TextRange, replace_with: String) -> Indel {
+        Indel {
+            delete: range,
+            insert: replace_with,
+        }
+    }
+}
+
+/// A set of non-overlapping [`Indel`]s, sorted by position. All ranges refer
+/// to the text before the edit.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    indels: Vec<Indel>,
+}
+
+impl TextEdit {
+    pub fn builder() -> TextEditBuilder {
+        TextEditBuilder::default()
+    }
+
+    pub fn insert(offset: TextSize, text: String) -> TextEdit {
+        let mut builder = TextEdit::builder();
+        builder.insert(offset, text);
+        builder.finish()
+    }
+
+    pub fn delete(range: TextRange) -> TextEdit {
+        let mut builder = TextEdit::builder();
+        builder.delete(range);
+        builder.finish()
+    }
+
+    pub fn replace(range: TextRange, replace_with: String) -> TextEdit {
+        let mut builder = TextEdit::builder();
+        builder.replace(range, replace_with);
+        builder.finish()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indels.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Indel> {
+        self.indels.iter()
+    }
+
+    /// Applies the edit to `text`.
+    pub fn apply(&self, text: &mut String) {
+        for indel in self.indels.iter().rev() {
+            text.replace_range(std::ops::Range::<usize>::from(indel.delete), &indel.insert);
+        }
+    }
+}
+
+impl IntoIterator for TextEdit {
+    type Item = Indel;
+    type IntoIter = std::vec::IntoIter<Indel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.indels.into_iter()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TextEditBuilder {
+    indels: Vec<Indel>,
+}
+
+impl TextEditBuilder {
+    pub fn is_empty(&self) -> bool {
+        self.indels.is_empty()
+    }
+
+    pub fn insert(&mut self, offset: TextSize, text: String) {
+        self.indels.push(Indel::insert(offset, text));
+    }
+
+    pub fn delete(&mut self, range: TextRange) {
+        self.indels.push(Indel::delete(range));
+    }
+
+    pub fn replace(&mut self, range: TextRange, replace_with: String) {
+        self.indels.push(Indel::replace(range, replace_with));
+    }
+
+    /// Sorts the indels and checks that they do not overlap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two indels overlap.
+    pub fn finish(self) -> TextEdit {
+        let mut indels = self.indels;
+        // A stable sort keeps insertions at the same offset in the order they
+        // were added.
+        indels.sort_by_key(|it| (it.delete.start(), it.delete.end()));
+        for pair in indels.windows(2) {
+            assert!(
+                pair[0].delete.end() <= pair[1].delete.start(),
+                "overlapping indels: {:?} and {:?}",
+                pair[0].delete,
+                pair[1].delete
+            );
+        }
+        TextEdit { indels }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut builder = TextEdit::builder();
+        builder.replace(TextRange::new(4, 5), "y".to_string());
+        builder.insert(0, "var ".to_string());
+        builder.delete(TextRange::new(5, 6));
+        let edit = builder.finish();
+        let mut text = "set x = 1".to_string();
+        edit.apply(&mut text);
+        assert_eq!(text, "var set y= 1");
+    }
+
+    #[test]
+    fn test_insertions_keep_order() {
+        let mut builder = TextEdit::builder();
+        builder.insert(1, "b".to_string());
+        builder.insert(1, "c".to_string());
+        let mut text = "ad".to_string();
+        builder.finish().apply(&mut text);
+        assert_eq!(text, "abcd");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_overlapping_indels() {
+        let mut builder = TextEdit::builder();
+        builder.delete(TextRange::new(0, 3));
+        builder.delete(TextRange::new(2, 4));
+        builder.finish();
+    }
+}
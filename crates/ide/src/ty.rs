@@ -0,0 +1,430 @@
+//! A best-effort guess at the types of CFML values.
+//!
+//! CFML is dynamically typed, so this only looks at what is obvious from the
+//! source: literals, operators, calls to well-known built-in functions and the
+//! declared types of arguments and `<cfparam>`s. Everything else is
+//! [`Ty::Any`].
+use std::fmt;
+
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxKind::*,
+    SyntaxNode,
+};
+
+use crate::var_scope::FunctionLike;
+
+/// How deep to follow variables to their initializers.
+const MAX_DEPTH: u32 = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Ty {
+    Any,
+    Array,
+    Binary,
+    Boolean,
+    Date,
+    Function,
+    Numeric,
+    Query,
+    String,
+    Struct,
+    Void,
+    Xml,
+    /// An instance of the named component.
+    Component(String),
+}
+
+/// Return types of built-in functions, by lowercase name.
+const BUILTIN_RETURN_TYPES: &[(&str, &str)] = &[
+    ("abs", "numeric"),
+    ("arrayisempty", "boolean"),
+    ("arraylen", "numeric"),
+    ("arraynew", "array"),
+    ("arraytolist", "string"),
+    ("asc", "numeric"),
+    ("ceiling", "numeric"),
+    ("chr", "string"),
+    ("createdate", "date"),
+    ("createdatetime", "date"),
+    ("createodbcdate", "date"),
+    ("createodbcdatetime", "date"),
+    ("createtime", "date"),
+    ("createuuid", "string"),
+    ("dateadd", "date"),
+    ("datediff", "numeric"),
+    ("dateformat", "string"),
+    ("day", "numeric"),
+    ("directoryexists", "boolean"),
+    ("encodeforhtml", "string"),
+    ("fileexists", "boolean"),
+    ("find", "numeric"),
+    ("findnocase", "numeric"),
+    ("fix", "numeric"),
+    ("gettickcount", "numeric"),
+    ("hash", "string"),
+    ("hour", "numeric"),
+    ("htmleditformat", "string"),
+    ("int", "numeric"),
+    ("isarray", "boolean"),
+    ("isboolean", "boolean"),
+    ("isdate", "boolean"),
+    ("isdefined", "boolean"),
+    ("isnull", "boolean"),
+    ("isnumeric", "boolean"),
+    ("issimplevalue", "boolean"),
+    ("isstruct", "boolean"),
+    ("isvalid", "boolean"),
+    ("lcase", "string"),
+    ("left", "string"),
+    ("len", "numeric"),
+    ("listappend", "string"),
+    ("listfirst", "string"),
+    ("listgetat", "string"),
+    ("listlast", "string"),
+    ("listlen", "numeric"),
+    ("listtoarray", "array"),
+    ("lsdateformat", "string"),
+    ("max", "numeric"),
+    ("mid", "string"),
+    ("min", "numeric"),
+    ("minute", "numeric"),
+    ("month", "numeric"),
+    ("now", "date"),
+    ("numberformat", "string"),
+    ("parsedatetime", "date"),
+    ("queryexecute", "query"),
+    ("querynew", "query"),
+    ("rand", "numeric"),
+    ("randrange", "numeric"),
+    ("repeatstring", "string"),
+    ("replace", "string"),
+    ("replacenocase", "string"),
+    ("rereplace", "string"),
+    ("reverse", "string"),
+    ("right", "string"),
+    ("round", "numeric"),
+    ("second", "numeric"),
+    ("serializejson", "string"),
+    ("sgn", "numeric"),
+    ("sqr", "numeric"),
+    ("structcopy", "struct"),
+    ("structcount", "numeric"),
+    ("structkeyarray", "array"),
+    ("structkeyexists", "boolean"),
+    ("structnew", "struct"),
+    ("timeformat", "string"),
+    ("tostring", "string"),
+    ("trim", "string"),
+    ("ucase", "string"),
+    ("val", "numeric"),
+    ("year", "numeric"),
+];
+
+impl Ty {
+    /// Parses a type as written in `returntype`, `type` or a script type
+    /// annotation.
+    pub(crate) fn from_name(name: &str) -> Ty {
+        let lower = name.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "" | "any" => Ty::Any,
+            "array" => Ty::Array,
+            "binary" => Ty::Binary,
+            "boolean" => Ty::Boolean,
+            "date" | "time" | "eurodate" | "usdate" => Ty::Date,
+            "function" | "closure" => Ty::Function,
+            "numeric" | "integer" | "float" | "range" => Ty::Numeric,
+            "query" => Ty::Query,
+            "string"
+            | "uuid"
+            | "guid"
+            | "email"
+            | "url"
+            | "variablename"
+            | "zipcode"
+            | "telephone"
+            | "creditcard"
+            | "ssn"
+            | "social_security_number"
+            | "regex"
+            | "regular_expression" => Ty::String,
+            "struct" => Ty::Struct,
+            "void" => Ty::Void,
+            "xml" => Ty::Xml,
+            _ => Ty::Component(name.trim().to_string()),
+        }
+    }
+
+    /// The return type of a built-in function, if it is known.
+    pub(crate) fn of_builtin(name: &str) -> Option<Ty> {
+        let lower = name.to_ascii_lowercase();
+        BUILTIN_RETURN_TYPES
+            .iter()
+            .find(|(it, _)| *it == lower)
+            .map(|(_, ty)| Ty::from_name(ty))
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Ty::Any => "any",
+            Ty::Array => "array",
+            Ty::Binary => "binary",
+            Ty::Boolean => "boolean",
+            Ty::Date => "date",
+            Ty::Function => "function",
+            Ty::Numeric => "numeric",
+            Ty::Query => "query",
+            Ty::String => "string",
+            Ty::Struct => "struct",
+            Ty::Void => "void",
+            Ty::Xml => "xml",
+            Ty::Component(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+/// Guesses the type of `expr`.
+pub(crate) fn infer_expr(expr: &ast::Expr) -> Ty {
+    infer(expr, 0)
+}
+
+fn infer(expr: &ast::Expr, depth: u32) -> Ty {
+    if depth > MAX_DEPTH {
+        return Ty::Any;
+    }
+    match expr.clone().unwrapped() {
+        ast::Expr::Literal(it) => match it.token() {
+            Some(token) if token.kind() == NUMBER => Ty::Numeric,
+            Some(token) if token.text().eq_ignore_ascii_case("null") => Ty::Any,
+            Some(_) => Ty::Boolean,
+            None => Ty::Any,
+        },
+        ast::Expr::StringLit(_) => Ty::String,
+        ast::Expr::ArrayLit(_) => Ty::Array,
+        ast::Expr::StructLit(_) => Ty::Struct,
+        ast::Expr::ClosureExpr(_) | ast::Expr::ArrowExpr(_) => Ty::Function,
+        ast::Expr::NewExpr(it) => it.component_name().map_or(Ty::Any, Ty::Component),
+        ast::Expr::CallExpr(it) => match it.callee() {
+            Some(ast::Expr::NameRef(name)) => Ty::of_builtin(&name.text()).unwrap_or(Ty::Any),
+            _ => Ty::Any,
+        },
+        ast::Expr::BinExpr(it) => match it.op_text().as_str() {
+            "&" => Ty::String,
+            "+" | "-" | "*" | "/" | "\\" | "%" | "^" | "mod" => Ty::Numeric,
+            _ => Ty::Boolean,
+        },
+        ast::Expr::PrefixExpr(it) => match it.op().map(|it| it.text().to_ascii_lowercase()) {
+            Some(op) if op == "!" || op == "not" => Ty::Boolean,
+            _ => Ty::Numeric,
+        },
+        ast::Expr::PostfixExpr(_) => Ty::Numeric,
+        ast::Expr::TernaryExpr(it) => {
+            let then_ty = it.then_expr().map_or(Ty::Any, |it| infer(&it, depth + 1));
+            let else_ty = it.else_expr().map_or(Ty::Any, |it| infer(&it, depth + 1));
+            if then_ty == else_ty {
+                then_ty
+            } else {
+                Ty::Any
+            }
+        }
+        ast::Expr::AssignExpr(it) => it.rhs().map_or(Ty::Any, |it| infer(&it, depth + 1)),
+        expr @ (ast::Expr::NameRef(_) | ast::Expr::FieldExpr(_)) => match variable_path(&expr) {
+            Some(path) => infer_variable(expr.syntax(), &path, depth + 1),
+            None => Ty::Any,
+        },
+        _ => Ty::Any,
+    }
+}
+
+/// The dotted path of a variable reference in lowercase, e.g. `arguments.id`.
+pub(crate) fn variable_path(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::NameRef(it) => Some(it.text().to_ascii_lowercase()),
+        ast::Expr::FieldExpr(it) => {
+            let receiver = variable_path(&it.receiver()?)?;
+            Some(format!(
+                "{receiver}.{}",
+                it.field_name()?.to_ascii_lowercase()
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Looks for the declaration of the variable `path` visible from `at`: an
+/// argument, a local variable or a `<cfparam>`.
+fn infer_variable(at: &SyntaxNode, path: &str, depth: u32) -> Ty {
+    let func = at.ancestors().find_map(FunctionLike::cast);
+    let (scope, name) = match path.split_once('.') {
+        Some((scope, name)) if !name.contains('.') => (Some(scope), name),
+        Some(_) => (None, path),
+        None => (None, path),
+    };
+
+    if let Some(func) = &func {
+        if matches!(scope, None | Some("arguments")) {
+            if let Some(ty) = argument_type(func, name, depth) {
+                return ty;
+            }
+        }
+    }
+
+    let nodes = match &func {
+        Some(func) => func.own_nodes(),
+        None => {
+            let root = at.ancestors().last().unwrap_or_else(|| at.clone());
+            root.descendants()
+                .filter(|it| {
+                    it.ancestors()
+                        .skip(1)
+                        .all(|it| FunctionLike::cast(it).is_none())
+                })
+                .collect()
+        }
+    };
+    let local = func.is_some() && matches!(scope, None | Some("local"));
+    for node in nodes {
+        if node.text_range().start() >= at.text_range().start() {
+            break;
+        }
+        let ty = match node.kind() {
+            VAR_DECL if local => {
+                let decl = ast::VarDecl::cast(node).unwrap();
+                if !decl
+                    .name()
+                    .is_some_and(|it| it.text().eq_ignore_ascii_case(name))
+                {
+                    continue;
+                }
+                decl.initializer().map(|it| infer(&it, depth))
+            }
+            ASSIGN_EXPR => {
+                let assign = ast::AssignExpr::cast(node).unwrap();
+                let lhs = assign.lhs().and_then(|it| variable_path(&it));
+                if lhs.as_deref() != Some(path) {
+                    continue;
+                }
+                assign.rhs().map(|it| infer(&it, depth))
+            }
+            TAG => {
+                let tag = ast::Tag::cast(node).unwrap();
+                if tag.name_lower() != "cfparam" {
+                    continue;
+                }
+                let declared = tag.attribute("name").and_then(|it| it.value());
+                if !declared.is_some_and(|it| it.eq_ignore_ascii_case(path)) {
+                    continue;
+                }
+                tag.attribute("type")
+                    .and_then(|it| it.value())
+                    .map(|it| Ty::from_name(&it))
+            }
+            _ => None,
+        };
+        match ty {
+            Some(Ty::Any) | None => (),
+            Some(ty) => return ty,
+        }
+    }
+    Ty::Any
+}
+
+fn argument_type(func: &FunctionLike, name: &str, depth: u32) -> Option<Ty> {
+    let params = match func {
+        FunctionLike::Decl(it) => it.param_list().map(|it| it.params().collect()),
+        FunctionLike::Closure(it) => it.param_list().map(|it| it.params().collect()),
+        FunctionLike::Arrow(it) => Some(it.params()),
+        FunctionLike::Tag(it) => {
+            let arg = it.child_tags().find(|it| {
+                it.name_lower() == "cfargument"
+                    && it
+                        .attribute("name")
+                        .and_then(|it| it.value())
+                        .is_some_and(|it| it.eq_ignore_ascii_case(name))
+            })?;
+            let ty = arg.attribute("type").and_then(|it| it.value());
+            return Some(ty.map_or(Ty::Any, |it| Ty::from_name(&it)));
+        }
+    };
+    let param = params?.into_iter().find(|it| {
+        it.name()
+            .and_then(|it| it.text())
+            .is_some_and(|it| it.eq_ignore_ascii_case(name))
+    })?;
+    let ty = match (param.ty(), param.default()) {
+        (Some(ty), _) => Ty::from_name(&ty.syntax().text()),
+        (None, Some(default)) => infer(&default, depth),
+        (None, None) => Ty::Any,
+    };
+    Some(ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Infers the type of the expression in the last `<cfset>` or `return`.
+    fn check(text: &str, expected: Ty) {
+        let root = syntax::parse(text).syntax_node();
+        let expr = root
+            .descendants()
+            .filter(|it| matches!(it.kind(), TAG_OPEN | RETURN_STMT))
+            .filter_map(|it| it.children().find_map(ast::Expr::cast))
+            .last()
+            .unwrap();
+        assert_eq!(infer_expr(&expr), expected);
+    }
+
+    #[test]
+    fn test_literals_and_operators() {
+        check("<cfset 1>", Ty::Numeric);
+        check("<cfset TRUE>", Ty::Boolean);
+        check("<cfset 'a' & b>", Ty::String);
+        check("<cfset a * (b + 1)>", Ty::Numeric);
+        check("<cfset not a>", Ty::Boolean);
+        check("<cfset a eq b>", Ty::Boolean);
+        check("<cfset x ? 1 : 2>", Ty::Numeric);
+        check("<cfset x ? 1 : 'a'>", Ty::Any);
+        check("<cfset now()>", Ty::Date);
+        check(
+            "<cfset new model.User()>",
+            Ty::Component("model.User".to_string()),
+        );
+        check("<cfset foo()>", Ty::Any);
+    }
+
+    #[test]
+    fn test_variables() {
+        check(
+            "component { function f(numeric id, name = 'x') { return id; } }",
+            Ty::Numeric,
+        );
+        check(
+            "component { function f(numeric id, name = 'x') { return arguments.name; } }",
+            Ty::String,
+        );
+        check(
+            "component { function f() { var d = now(); var e = d; return e; } }",
+            Ty::Date,
+        );
+        check(
+            r#"<cffunction name="f"><cfargument name="id" type="boolean"><cfset id></cffunction>"#,
+            Ty::Boolean,
+        );
+        check(
+            r#"<cfparam name="url.page" type="integer"><cfset url.page>"#,
+            Ty::Numeric,
+        );
+        check("<cfset x = 1><cfset local.x>", Ty::Any);
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Ty::from_name("UUID"), Ty::String);
+        assert_eq!(Ty::from_name(""), Ty::Any);
+        assert_eq!(Ty::from_name("models.User").to_string(), "models.User");
+    }
+}
@@ -0,0 +1,421 @@
+//! Finds variables which a function assigns to without declaring them local.
+//!
+//! In CFML an unscoped assignment inside a function writes to the component's
+//! `variables` scope unless the name was declared with `var` (or written as
+//! `local.name`). The value then leaks between calls, and between requests
+//! sharing a cached component, which is a classic source of race conditions.
+use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, AstNode},
+    SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{resolve::static_text, Symbol};
+
+/// Scopes which may be written to by name, e.g. `variables = {}` is not an
+/// unscoped variable.
+pub(crate) const SCOPES: &[&str] = &[
+    "application",
+    "arguments",
+    "attributes",
+    "caller",
+    "cgi",
+    "client",
+    "cookie",
+    "form",
+    "local",
+    "request",
+    "server",
+    "session",
+    "this",
+    "thread",
+    "url",
+    "variables",
+];
+
+/// Attributes of tags (and their script equivalents) which name a variable
+/// the tag assigns to.
+const ASSIGNING_ATTRIBUTES: &[(&str, &str)] = &[
+    ("directory", "name"),
+    ("file", "result"),
+    ("file", "variable"),
+    ("http", "result"),
+    ("invoke", "returnvariable"),
+    ("loop", "index"),
+    ("loop", "item"),
+    ("param", "name"),
+    ("query", "name"),
+    ("query", "result"),
+    ("savecontent", "variable"),
+    ("storedproc", "result"),
+];
+
+/// A function, closure or `<cffunction>`: anything with its own local scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FunctionLike {
+    Decl(ast::FunctionDecl),
+    Tag(ast::Tag),
+    Closure(ast::ClosureExpr),
+    Arrow(ast::ArrowExpr),
+}
+
+impl FunctionLike {
+    pub(crate) fn cast(node: SyntaxNode) -> Option<FunctionLike> {
+        let res = match node.kind() {
+            FUNCTION_DECL => FunctionLike::Decl(ast::FunctionDecl::cast(node)?),
+            TAG => {
+                let tag = ast::Tag::cast(node)?;
+                if tag.name_lower() != "cffunction" {
+                    return None;
+                }
+                FunctionLike::Tag(tag)
+            }
+            CLOSURE_EXPR => FunctionLike::Closure(ast::ClosureExpr::cast(node)?),
+            ARROW_EXPR => FunctionLike::Arrow(ast::ArrowExpr::cast(node)?),
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    pub(crate) fn syntax(&self) -> &SyntaxNode {
+        match self {
+            FunctionLike::Decl(it) => it.syntax(),
+            FunctionLike::Tag(it) => it.syntax(),
+            FunctionLike::Closure(it) => it.syntax(),
+            FunctionLike::Arrow(it) => it.syntax(),
+        }
+    }
+
+    /// The declared name, if the function has one.
+    pub(crate) fn name(&self) -> Option<String> {
+        match self {
+            FunctionLike::Decl(it) => it.name()?.text(),
+            FunctionLike::Tag(it) => it.attribute("name")?.value(),
+            FunctionLike::Closure(_) | FunctionLike::Arrow(_) => None,
+        }
+    }
+
+    /// The range of the declared name, without quotes.
+    pub(crate) fn name_range(&self) -> Option<TextRange> {
+        match self {
+            FunctionLike::Decl(it) => Some(it.name()?.syntax().text_range()),
+            FunctionLike::Tag(it) => {
+                Some(static_text(it.attribute("name")?.value_element()?)?.text_range())
+            }
+            FunctionLike::Closure(_) | FunctionLike::Arrow(_) => None,
+        }
+    }
+
+    /// Names of the parameters.
+    pub(crate) fn param_names(&self) -> Vec<Symbol> {
+        let params = match self {
+            FunctionLike::Decl(it) => it.param_list().map(|it| it.params().collect()),
+            FunctionLike::Closure(it) => it.param_list().map(|it| it.params().collect()),
+            FunctionLike::Arrow(it) => Some(it.params()),
+            FunctionLike::Tag(it) => {
+                return it
+                    .child_tags()
+                    .filter(|it| it.name_lower() == "cfargument")
+                    .filter_map(|it| it.attribute("name")?.value())
+                    .map(|it| Symbol::new(&it))
+                    .collect();
+            }
+        };
+        params
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|it| it.name()?.text())
+            .map(|it| Symbol::new(&it))
+            .collect()
+    }
+
+    /// Nodes belonging to this function, excluding the bodies of nested
+    /// functions and closures, in source order.
+    pub(crate) fn own_nodes(&self) -> Vec<SyntaxNode> {
+        fn go(node: &SyntaxNode, acc: &mut Vec<SyntaxNode>) {
+            for child in node.children() {
+                if FunctionLike::cast(child.clone()).is_some() {
+                    continue;
+                }
+                acc.push(child.clone());
+                go(&child, acc);
+            }
+        }
+        let mut acc = Vec::new();
+        go(self.syntax(), &mut acc);
+        acc
+    }
+
+    /// Names declared local to this function: parameters and `var`s.
+    pub(crate) fn declared_names(&self) -> FxHashSet<Symbol> {
+        let mut res: FxHashSet<Symbol> = self.param_names().into_iter().collect();
+        res.extend(
+            self.own_nodes()
+                .into_iter()
+                .filter_map(ast::VarDecl::cast)
+                .filter_map(|it| it.name())
+                .map(|it| Symbol::new(it.text())),
+        );
+        res
+    }
+}
+
+/// Returns every function-like node of the file, outermost first.
+pub(crate) fn functions(root: &SyntaxNode) -> impl Iterator<Item = FunctionLike> {
+    root.descendants().filter_map(FunctionLike::cast)
+}
+
+/// How to make an unscoped assignment local.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScopeFix {
+    /// Declare the variable with `var`, e.g. `var x = 1;`.
+    Var,
+    /// Prefix the variable with `local.`, where a declaration is not allowed.
+    Local,
+}
+
+impl ScopeFix {
+    pub(crate) fn insert_text(self) -> &'static str {
+        match self {
+            ScopeFix::Var => "var ",
+            ScopeFix::Local => "local.",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnscopedAssignment {
+    pub(crate) name: String,
+    /// The variable name as written.
+    pub(crate) name_range: TextRange,
+    /// The whole assignment, e.g. the statement or the tag.
+    pub(crate) target: TextRange,
+    pub(crate) fix: ScopeFix,
+}
+
+impl UnscopedAssignment {
+    /// Where the fix inserts its text.
+    pub(crate) fn fix_offset(&self) -> TextSize {
+        self.name_range.start()
+    }
+}
+
+/// Finds the variables `func` assigns to without scoping them. Only the first
+/// assignment of every variable is reported: fixing it makes the later ones
+/// local too.
+pub(crate) fn unscoped_assignments(func: &FunctionLike) -> Vec<UnscopedAssignment> {
+    let mut declared = func.declared_names();
+    // Closures can assign to the locals of the functions they are nested in.
+    for ancestor in func.syntax().ancestors().skip(1) {
+        if let Some(outer) = FunctionLike::cast(ancestor) {
+            declared.extend(outer.declared_names());
+        }
+    }
+
+    let mut res = Vec::new();
+    for node in func.own_nodes() {
+        let Some(assignment) = assignment(&node) else {
+            continue;
+        };
+        let key = Symbol::new(&assignment.name);
+        if SCOPES.contains(&key.as_str()) || !declared.insert(key) {
+            continue;
+        }
+        res.push(assignment);
+    }
+    res
+}
+
+/// The unscoped variable `node` assigns to, if it is an assignment.
+pub(crate) fn assignment(node: &SyntaxNode) -> Option<UnscopedAssignment> {
+    match node.kind() {
+        ASSIGN_EXPR => {
+            let assign = ast::AssignExpr::cast(node.clone())?;
+            let ast::Expr::NameRef(name) = assign.lhs()? else {
+                return None;
+            };
+            let fix = if assign.op()?.kind() == EQ && allows_var(node) {
+                ScopeFix::Var
+            } else {
+                ScopeFix::Local
+            };
+            Some(UnscopedAssignment {
+                name: name.text(),
+                name_range: name.syntax().text_range(),
+                target: node.text_range(),
+                fix,
+            })
+        }
+        FOR_IN_STMT => {
+            let name = node.children().find_map(ast::NameRef::cast)?;
+            // `for (x in xs)`: the loop variable comes right after `(`.
+            let first = node.children().find(|it| it.kind().is_expr())?;
+            if first != *name.syntax() {
+                return None;
+            }
+            Some(UnscopedAssignment {
+                name: name.text(),
+                name_range: name.syntax().text_range(),
+                target: node.text_range(),
+                fix: ScopeFix::Var,
+            })
+        }
+        TAG => {
+            let tag = ast::Tag::cast(node.clone())?;
+            let tag_name = tag.name_lower();
+            let name = tag_name.strip_prefix("cf")?;
+            tag.attributes()
+                .find_map(|attr| assigning_attribute(name, &attr, tag.syntax().text_range()))
+        }
+        SCRIPT_TAG_STMT => {
+            let stmt = ast::ScriptTagStmt::cast(node.clone())?;
+            let name = stmt.name_lower();
+            let target = stmt.syntax().text_range();
+            stmt.attributes()
+                .find_map(|attr| assigning_attribute(&name, &attr, target))
+        }
+        _ => None,
+    }
+}
+
+/// Returns `true` if `var` can be put in front of the assignment `node`.
+fn allows_var(node: &SyntaxNode) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    match parent.kind() {
+        EXPR_STMT => true,
+        TAG_OPEN => parent
+            .parent()
+            .and_then(ast::Tag::cast)
+            .is_some_and(|it| it.name_lower() == "cfset"),
+        // Only the initializer of `for (init; cond; step)`.
+        FOR_STMT => !parent
+            .children_with_tokens()
+            .take_while(|it| it.text_range().start() < node.text_range().start())
+            .any(|it| it.kind() == SEMICOLON),
+        _ => false,
+    }
+}
+
+fn assigning_attribute(
+    tag: &str,
+    attr: &ast::Attribute,
+    target: TextRange,
+) -> Option<UnscopedAssignment> {
+    let attr_name = attr.name_lower();
+    if !ASSIGNING_ATTRIBUTES.contains(&(tag, attr_name.as_str())) {
+        return None;
+    }
+    let value_range = match attr.value_element()? {
+        SyntaxElement::Token(it) if it.kind() == ATTR_VALUE => it.text_range(),
+        SyntaxElement::Node(it) if it.kind() == STRING => {
+            let text = it
+                .children_with_tokens()
+                .find(|it| it.kind() == STRING_TEXT)?;
+            text.text_range()
+        }
+        _ => return None,
+    };
+    let name = attr.value()?;
+    if !is_plain_identifier(&name) {
+        return None;
+    }
+    Some(UnscopedAssignment {
+        name,
+        name_range: value_range,
+        target,
+        fix: ScopeFix::Local,
+    })
+}
+
+fn is_plain_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|it| it.is_alphabetic() || it == '_')
+        && chars.all(|it| it.is_alphanumeric() || it == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str, expected: &[(&str, ScopeFix)]) {
+        let root = syntax::parse(text).syntax_node();
+        let actual: Vec<_> = functions(&root)
+            .flat_map(|it| unscoped_assignments(&it))
+            .map(|it| (it.name, it.fix))
+            .collect();
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(name, fix)| (name.to_string(), fix))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_script_function() {
+        check(
+            "component {
+                function f(a) {
+                    var b = 1;
+                    a = 2;
+                    c = 3;
+                    c = 4;
+                    local.d = 5;
+                    variables.e = 6;
+                    for (i = 1; i < 10; i++) {}
+                    for (k in a) {}
+                    x = y = 1;
+                    n += 1;
+                }
+            }",
+            &[
+                ("c", ScopeFix::Var),
+                ("i", ScopeFix::Var),
+                ("k", ScopeFix::Var),
+                ("x", ScopeFix::Var),
+                ("y", ScopeFix::Local),
+                ("n", ScopeFix::Local),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_tag_function() {
+        check(
+            r#"<cffunction name="f">
+                <cfargument name="a">
+                <cfset var b = 1>
+                <cfset a = 1>
+                <cfset c = 2>
+                <cfquery name="q" datasource="ds">select 1</cfquery>
+                <cfloop from="1" to="2" index="local.i"></cfloop>
+                <cfloop from="1" to="2" index="j"></cfloop>
+            </cffunction>
+            <cfset outside = 1>"#,
+            &[
+                ("c", ScopeFix::Var),
+                ("q", ScopeFix::Local),
+                ("j", ScopeFix::Local),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_closures() {
+        check(
+            "component {
+                function f() {
+                    var total = 0;
+                    each(function(x) { total = total + x; y = x; });
+                    z = 1;
+                }
+            }",
+            &[("z", ScopeFix::Var), ("y", ScopeFix::Var)],
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! A typed layer over the untyped syntax tree.
+//!
+//! Each wrapper is a zero-cost view of a [`SyntaxNode`] of a specific kind.
+//! Accessors return `Option` because the tree may be incomplete while the
+//! user is typing.
+mod nodes;
+
+pub use nodes::*;
+
+use crate::{SyntaxKind, SyntaxNode, SyntaxToken};
+
+pub trait AstNode {
+    fn can_cast(kind: SyntaxKind) -> bool
+    where
+        Self: Sized;
+
+    fn cast(syntax: SyntaxNode) -> Option<Self>
+    where
+        Self: Sized;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+mod support {
+    use super::{AstNode, SyntaxKind, SyntaxNode, SyntaxToken};
+
+    pub(super) fn child<N: AstNode>(parent: &SyntaxNode) -> Option<N> {
+        parent.children().find_map(N::cast)
+    }
+
+    pub(super) fn children<N: AstNode>(parent: &SyntaxNode) -> impl Iterator<Item = N> {
+        parent.children().filter_map(N::cast)
+    }
+
+    pub(super) fn token(parent: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+        parent
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == kind)
+    }
+
+    /// Finds a keyword token by its (case-insensitive) text.
+    pub(super) fn keyword(parent: &SyntaxNode, kw: &str) -> Option<SyntaxToken> {
+        parent
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == SyntaxKind::KEYWORD && it.text().eq_ignore_ascii_case(kw))
+    }
+}
+
+macro_rules! ast_node {
+    ($(#[$attr:meta])* $name:ident, $kind:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name {
+            syntax: SyntaxNode,
+        }
+
+        impl AstNode for $name {
+            fn can_cast(kind: SyntaxKind) -> bool {
+                kind == SyntaxKind::$kind
+            }
+
+            fn cast(syntax: SyntaxNode) -> Option<Self> {
+                if Self::can_cast(syntax.kind()) {
+                    Some(Self { syntax })
+                } else {
+                    None
+                }
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                &self.syntax
+            }
+        }
+    };
+}
+use ast_node;
+
+/// Unescapes the text of a CFML string: doubled quotes and `##`.
+pub fn unescape_string(text: &str, quote: char) -> String {
+    let doubled = format!("{quote}{quote}");
+    text.replace(&doubled, &quote.to_string())
+        .replace("##", "#")
+}
+
+fn ident_text(token: Option<SyntaxToken>) -> Option<String> {
+    token.map(|it| it.text().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn find<N: AstNode>(text: &str) -> N {
+        parse(text)
+            .syntax_node()
+            .descendants()
+            .find_map(N::cast)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tag_accessors() {
+        let tag: Tag = find("<CFFunction name=\"f\" access='public' output=#x#></cffunction>");
+        assert_eq!(tag.name_lower(), "cffunction");
+        assert_eq!(tag.attribute("NAME").unwrap().value().as_deref(), Some("f"));
+        assert_eq!(
+            tag.attribute("access").unwrap().value().as_deref(),
+            Some("public")
+        );
+        assert_eq!(tag.attribute("output").unwrap().value(), None);
+        assert!(tag.close().is_some());
+    }
+
+    #[test]
+    fn test_set_tag() {
+        let tag: Tag = find("<cfset var x = 1>");
+        let var_decl = tag.var_decl().unwrap();
+        assert_eq!(var_decl.name().unwrap().text(), "x");
+        assert_eq!(var_decl.initializer().unwrap().syntax().text(), "1");
+    }
+
+    #[test]
+    fn test_string_value() {
+        let string: StringLit = find("<cfset x = 'it''s ## done'>");
+        assert!(string.is_static());
+        assert_eq!(string.value().as_deref(), Some("it's # done"));
+        let string: StringLit = find("<cfset x = \"a#b#\">");
+        assert!(!string.is_static());
+        assert_eq!(string.value(), None);
+    }
+
+    #[test]
+    fn test_function_decl() {
+        let func: FunctionDecl =
+            find("component { private numeric function add(required numeric a, b = 2) { return a + b; } }");
+        assert_eq!(func.name().unwrap().text().as_deref(), Some("add"));
+        assert_eq!(func.access().as_deref(), Some("private"));
+        assert_eq!(func.return_type().unwrap().syntax().text(), "numeric");
+        let params: Vec<_> = func.param_list().unwrap().params().collect();
+        assert!(params[0].is_required());
+        assert_eq!(params[0].name().unwrap().text().as_deref(), Some("a"));
+        assert_eq!(params[1].default().unwrap().syntax().text(), "2");
+        assert!(func.body().is_some());
+    }
+
+    #[test]
+    fn test_exprs() {
+        let call: CallExpr = find("<cfset foo.bar(a = 1, 2)>");
+        let Some(Expr::FieldExpr(field)) = call.callee() else {
+            panic!("expected a field expression");
+        };
+        assert_eq!(field.field_name().as_deref(), Some("bar"));
+        let args: Vec<_> = call.arg_list().unwrap().args().collect();
+        assert_eq!(args[0].name().as_deref(), Some("a"));
+        assert_eq!(args[1].name(), None);
+
+        let assign: AssignExpr = find("<cfset local.x += 1>");
+        assert_eq!(assign.op().unwrap().text(), "+=");
+        assert_eq!(assign.lhs().unwrap().syntax().text(), "local.x");
+
+        let bin: BinExpr = find("<cfif a does NOT contain b></cfif>");
+        assert_eq!(bin.op_text(), "does not contain");
+    }
+}
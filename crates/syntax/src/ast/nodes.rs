@@ -0,0 +1,781 @@
+//! Typed wrappers for every node kind, with their accessors.
+use super::{ast_node, ident_text, support, unescape_string, AstNode};
+use crate::{SyntaxElement, SyntaxKind, SyntaxKind::*, SyntaxNode, SyntaxToken};
+
+ast_node!(SourceFile, SOURCE_FILE);
+ast_node!(
+    /// A tag element, from its open tag to its (optional) closing tag.
+    Tag,
+    TAG
+);
+ast_node!(TagOpen, TAG_OPEN);
+ast_node!(TagClose, TAG_CLOSE);
+ast_node!(
+    /// `name="value"` in a tag, or `name=value` on a script declaration.
+    Attribute,
+    ATTRIBUTE
+);
+ast_node!(HashExpr, HASH_EXPR);
+ast_node!(ComponentDecl, COMPONENT_DECL);
+ast_node!(FunctionDecl, FUNCTION_DECL);
+ast_node!(ParamList, PARAM_LIST);
+ast_node!(Param, PARAM);
+ast_node!(PropertyDecl, PROPERTY_DECL);
+ast_node!(ImportDecl, IMPORT_DECL);
+ast_node!(Name, NAME);
+ast_node!(Type, TYPE);
+ast_node!(Path, PATH);
+ast_node!(Block, BLOCK);
+ast_node!(VarDecl, VAR_DECL);
+ast_node!(ExprStmt, EXPR_STMT);
+ast_node!(IfStmt, IF_STMT);
+ast_node!(ElseClause, ELSE_CLAUSE);
+ast_node!(ForStmt, FOR_STMT);
+ast_node!(ForInStmt, FOR_IN_STMT);
+ast_node!(WhileStmt, WHILE_STMT);
+ast_node!(SwitchStmt, SWITCH_STMT);
+ast_node!(TryStmt, TRY_STMT);
+ast_node!(CatchClause, CATCH_CLAUSE);
+ast_node!(ReturnStmt, RETURN_STMT);
+ast_node!(ScriptTagStmt, SCRIPT_TAG_STMT);
+ast_node!(Literal, LITERAL);
+ast_node!(
+    /// A quoted string, possibly with `#...#` interpolations.
+    StringLit,
+    STRING
+);
+ast_node!(NameRef, NAME_REF);
+ast_node!(FieldExpr, FIELD_EXPR);
+ast_node!(IndexExpr, INDEX_EXPR);
+ast_node!(CallExpr, CALL_EXPR);
+ast_node!(ArgList, ARG_LIST);
+ast_node!(Arg, ARG);
+ast_node!(NewExpr, NEW_EXPR);
+ast_node!(BinExpr, BIN_EXPR);
+ast_node!(PrefixExpr, PREFIX_EXPR);
+ast_node!(PostfixExpr, POSTFIX_EXPR);
+ast_node!(TernaryExpr, TERNARY_EXPR);
+ast_node!(AssignExpr, ASSIGN_EXPR);
+ast_node!(ParenExpr, PAREN_EXPR);
+ast_node!(StructLit, STRUCT_LIT);
+ast_node!(StructField, STRUCT_FIELD);
+ast_node!(ArrayLit, ARRAY_LIT);
+ast_node!(ClosureExpr, CLOSURE_EXPR);
+ast_node!(ArrowExpr, ARROW_EXPR);
+
+macro_rules! expr_enum {
+    ($($variant:ident($kind:ident)),* $(,)?) => {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum Expr {
+            $($variant($variant),)*
+        }
+
+        impl AstNode for Expr {
+            fn can_cast(kind: SyntaxKind) -> bool {
+                matches!(kind, $($kind)|*)
+            }
+
+            fn cast(syntax: SyntaxNode) -> Option<Self> {
+                let res = match syntax.kind() {
+                    $($kind => Expr::$variant($variant { syntax }),)*
+                    _ => return None,
+                };
+                Some(res)
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                match self {
+                    $(Expr::$variant(it) => it.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+expr_enum! {
+    Literal(LITERAL),
+    StringLit(STRING),
+    NameRef(NAME_REF),
+    FieldExpr(FIELD_EXPR),
+    IndexExpr(INDEX_EXPR),
+    CallExpr(CALL_EXPR),
+    NewExpr(NEW_EXPR),
+    BinExpr(BIN_EXPR),
+    PrefixExpr(PREFIX_EXPR),
+    PostfixExpr(POSTFIX_EXPR),
+    TernaryExpr(TERNARY_EXPR),
+    AssignExpr(ASSIGN_EXPR),
+    ParenExpr(PAREN_EXPR),
+    StructLit(STRUCT_LIT),
+    ArrayLit(ARRAY_LIT),
+    ClosureExpr(CLOSURE_EXPR),
+    ArrowExpr(ARROW_EXPR),
+    HashExpr(HASH_EXPR),
+}
+
+impl Expr {
+    /// Strips parentheses and `#...#` around an expression.
+    pub fn unwrapped(self) -> Expr {
+        let mut expr = self;
+        loop {
+            let inner = match &expr {
+                Expr::ParenExpr(it) => it.expr(),
+                Expr::HashExpr(it) => it.expr(),
+                _ => None,
+            };
+            match inner {
+                Some(inner) => expr = inner,
+                None => return expr,
+            }
+        }
+    }
+}
+
+fn keyword_text(node: &SyntaxNode) -> Option<String> {
+    node.children_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .find(|it| it.kind() == KEYWORD)
+        .map(|it| it.text().to_ascii_lowercase())
+}
+
+impl SourceFile {
+    /// The component declared by a script `.cfc`.
+    pub fn component_decl(&self) -> Option<ComponentDecl> {
+        support::child(&self.syntax)
+    }
+
+    /// Top-level tags of a template file.
+    pub fn tags(&self) -> impl Iterator<Item = Tag> {
+        support::children(&self.syntax)
+    }
+}
+
+impl Tag {
+    pub fn open(&self) -> Option<TagOpen> {
+        support::child(&self.syntax)
+    }
+
+    pub fn close(&self) -> Option<TagClose> {
+        support::child(&self.syntax)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        self.open()
+            .and_then(|it| support::token(it.syntax(), TAG_NAME))
+    }
+
+    /// The tag name in lowercase, e.g. `cfset`.
+    pub fn name_lower(&self) -> String {
+        self.name()
+            .map(|it| it.text().to_ascii_lowercase())
+            .unwrap_or_default()
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        self.open()
+            .into_iter()
+            .flat_map(|it| support::children(it.syntax()).collect::<Vec<_>>())
+    }
+
+    /// Looks up an attribute by its (case-insensitive) name.
+    pub fn attribute(&self, name: &str) -> Option<Attribute> {
+        self.attributes().find(|it| {
+            it.name()
+                .is_some_and(|it| it.text().eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// The expression of `<cfset>`, `<cfif>` and similar tags.
+    pub fn expr(&self) -> Option<Expr> {
+        self.open().and_then(|it| support::child(it.syntax()))
+    }
+
+    /// The declaration of `<cfset var x = ...>`.
+    pub fn var_decl(&self) -> Option<VarDecl> {
+        self.open().and_then(|it| support::child(it.syntax()))
+    }
+
+    /// Tags nested directly inside this tag's body.
+    pub fn child_tags(&self) -> impl Iterator<Item = Tag> {
+        support::children(&self.syntax)
+    }
+
+    pub fn is_self_closing(&self) -> bool {
+        self.open()
+            .is_some_and(|it| support::token(it.syntax(), SLASH_R_ANGLE).is_some())
+    }
+}
+
+impl TagClose {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, TAG_NAME)
+    }
+}
+
+impl Attribute {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, ATTR_NAME)
+    }
+
+    pub fn name_lower(&self) -> String {
+        self.name()
+            .map(|it| it.text().to_ascii_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// The value of the attribute: a string, `#expr#`, a bare word or (in
+    /// script) any expression.
+    pub fn value_element(&self) -> Option<SyntaxElement> {
+        self.syntax
+            .children_with_tokens()
+            .skip_while(|it| it.kind() != EQ)
+            .skip(1)
+            .find(|it| !it.kind().is_trivia())
+    }
+
+    pub fn value_expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    /// The value as plain text, if it is static, e.g. `name="x"` or
+    /// `output=false`.
+    pub fn value(&self) -> Option<String> {
+        match self.value_element()? {
+            SyntaxElement::Token(token) => Some(token.text().to_string()),
+            SyntaxElement::Node(node) => match Expr::cast(node)? {
+                Expr::StringLit(it) => it.value(),
+                Expr::Literal(it) => Some(it.syntax().text()),
+                Expr::NameRef(it) => Some(it.syntax().text()),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl HashExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl StringLit {
+    pub fn quote_char(&self) -> char {
+        self.syntax
+            .first_token()
+            .and_then(|it| it.text().chars().next())
+            .unwrap_or('"')
+    }
+
+    /// Returns `true` if the string has no interpolations.
+    pub fn is_static(&self) -> bool {
+        support::child::<HashExpr>(&self.syntax).is_none()
+    }
+
+    /// The unescaped value of a static string.
+    pub fn value(&self) -> Option<String> {
+        if !self.is_static() {
+            return None;
+        }
+        let text = support::token(&self.syntax, STRING_TEXT)
+            .map(|it| it.text().to_string())
+            .unwrap_or_default();
+        Some(unescape_string(&text, self.quote_char()))
+    }
+
+    pub fn interpolations(&self) -> impl Iterator<Item = HashExpr> {
+        support::children(&self.syntax)
+    }
+}
+
+impl Literal {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.syntax.first_token()
+    }
+}
+
+impl ComponentDecl {
+    pub fn keyword(&self) -> Option<SyntaxToken> {
+        support::keyword(&self.syntax, "component")
+            .or_else(|| support::keyword(&self.syntax, "interface"))
+    }
+
+    pub fn is_interface(&self) -> bool {
+        support::keyword(&self.syntax, "interface").is_some()
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        support::children(&self.syntax)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<Attribute> {
+        self.attributes().find(|it| {
+            it.name()
+                .is_some_and(|it| it.text().eq_ignore_ascii_case(name))
+        })
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        support::child(&self.syntax)
+    }
+}
+
+impl FunctionDecl {
+    pub fn function_kw(&self) -> Option<SyntaxToken> {
+        support::keyword(&self.syntax, "function")
+    }
+
+    /// Modifiers such as `public` or `static`, in lowercase.
+    pub fn modifiers(&self) -> impl Iterator<Item = String> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|it| it.kind() == KEYWORD)
+            .map(|it| it.text().to_ascii_lowercase())
+            .take_while(|it| it != "function")
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// The access modifier, from the keyword or the `access` attribute.
+    pub fn access(&self) -> Option<String> {
+        const ACCESS: &[&str] = &["public", "private", "package", "remote"];
+        self.modifiers()
+            .find(|it| ACCESS.contains(&it.as_str()))
+            .or_else(|| {
+                self.attributes()
+                    .find(|it| it.name_lower() == "access")
+                    .and_then(|it| it.value())
+            })
+    }
+
+    pub fn return_type(&self) -> Option<Type> {
+        support::child(&self.syntax)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        support::child(&self.syntax)
+    }
+
+    pub fn param_list(&self) -> Option<ParamList> {
+        support::child(&self.syntax)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        support::children(&self.syntax)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        support::child(&self.syntax)
+    }
+}
+
+impl ParamList {
+    pub fn params(&self) -> impl Iterator<Item = Param> {
+        support::children(&self.syntax)
+    }
+}
+
+impl Param {
+    pub fn is_required(&self) -> bool {
+        support::keyword(&self.syntax, "required").is_some()
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        support::child(&self.syntax)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        support::child(&self.syntax)
+    }
+
+    pub fn default(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        support::children(&self.syntax)
+    }
+}
+
+impl PropertyDecl {
+    pub fn ty(&self) -> Option<Type> {
+        support::child(&self.syntax)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        support::children(&self.syntax)
+    }
+
+    /// The property name, from `property name;` or `property name="x";`.
+    pub fn name(&self) -> Option<String> {
+        support::child::<Name>(&self.syntax)
+            .and_then(|it| it.text())
+            .or_else(|| {
+                self.attributes()
+                    .find(|it| it.name_lower() == "name")
+                    .and_then(|it| it.value())
+            })
+    }
+}
+
+impl ImportDecl {
+    pub fn path(&self) -> Option<Path> {
+        support::child(&self.syntax)
+    }
+}
+
+impl Name {
+    pub fn ident(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, IDENT)
+    }
+
+    pub fn text(&self) -> Option<String> {
+        ident_text(self.ident())
+    }
+}
+
+impl NameRef {
+    pub fn ident(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, IDENT)
+    }
+
+    pub fn text(&self) -> String {
+        ident_text(self.ident()).unwrap_or_default()
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.syntax.children().filter(|it| it.kind().is_stmt())
+    }
+
+    pub fn l_brace(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, L_BRACE)
+    }
+
+    pub fn r_brace(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, R_BRACE)
+    }
+}
+
+impl VarDecl {
+    pub fn var_kw(&self) -> Option<SyntaxToken> {
+        support::keyword(&self.syntax, "var")
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        support::child::<Name>(&self.syntax).and_then(|it| it.ident())
+    }
+
+    pub fn initializer(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl ExprStmt {
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl IfStmt {
+    pub fn condition(&self) -> Option<ParenExpr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn then_branch(&self) -> Option<SyntaxNode> {
+        self.syntax.children().find(|it| it.kind().is_stmt())
+    }
+
+    pub fn else_clause(&self) -> Option<ElseClause> {
+        support::child(&self.syntax)
+    }
+}
+
+impl ElseClause {
+    pub fn branch(&self) -> Option<SyntaxNode> {
+        self.syntax.children().find(|it| it.kind().is_stmt())
+    }
+}
+
+impl ForInStmt {
+    pub fn var_decl(&self) -> Option<VarDecl> {
+        support::child(&self.syntax)
+    }
+
+    pub fn body(&self) -> Option<SyntaxNode> {
+        self.syntax
+            .children()
+            .find(|it| it.kind().is_stmt() && it.kind() != VAR_DECL)
+    }
+}
+
+impl ForStmt {
+    pub fn var_decl(&self) -> Option<VarDecl> {
+        support::child(&self.syntax)
+    }
+}
+
+impl CatchClause {
+    pub fn ty(&self) -> Option<Type> {
+        support::child(&self.syntax)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        support::child(&self.syntax)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        support::child(&self.syntax)
+    }
+}
+
+impl ReturnStmt {
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl ScriptTagStmt {
+    /// The statement name in lowercase, e.g. `lock`.
+    pub fn name_lower(&self) -> String {
+        keyword_text(&self.syntax).unwrap_or_default()
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = Attribute> {
+        support::children(&self.syntax)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        support::child(&self.syntax)
+    }
+}
+
+impl FieldExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn field(&self) -> Option<SyntaxToken> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|it| it.kind() == IDENT)
+            .last()
+    }
+
+    pub fn field_name(&self) -> Option<String> {
+        ident_text(self.field())
+    }
+
+    /// Returns `true` for `a?.b`.
+    pub fn is_safe(&self) -> bool {
+        support::token(&self.syntax, QUESTION_DOT).is_some()
+    }
+}
+
+impl IndexExpr {
+    pub fn base(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn index(&self) -> Option<Expr> {
+        support::children(&self.syntax).nth(1)
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn arg_list(&self) -> Option<ArgList> {
+        support::child(&self.syntax)
+    }
+
+    /// The name of the called function for `f()` and `x.f()`.
+    pub fn callee_name(&self) -> Option<String> {
+        match self.callee()? {
+            Expr::NameRef(it) => Some(it.text()),
+            Expr::FieldExpr(it) => it.field_name(),
+            _ => None,
+        }
+    }
+}
+
+impl ArgList {
+    pub fn args(&self) -> impl Iterator<Item = Arg> {
+        support::children(&self.syntax)
+    }
+}
+
+impl Arg {
+    /// The name of a named argument, `f(name = value)`.
+    pub fn name(&self) -> Option<String> {
+        support::token(&self.syntax, EQ).or_else(|| support::token(&self.syntax, COLON))?;
+        match self.syntax.first_child_or_token()? {
+            SyntaxElement::Token(it) if it.kind() == IDENT => Some(it.text().to_string()),
+            SyntaxElement::Node(it) => StringLit::cast(it)?.value(),
+            _ => None,
+        }
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        support::children(&self.syntax).last()
+    }
+}
+
+impl NewExpr {
+    pub fn path(&self) -> Option<Path> {
+        support::child(&self.syntax)
+    }
+
+    /// The instantiated component, from `new a.b()` or `new "a.b"()`.
+    pub fn component_name(&self) -> Option<String> {
+        self.path()
+            .map(|it| it.syntax().text())
+            .or_else(|| support::child::<StringLit>(&self.syntax)?.value())
+    }
+
+    pub fn arg_list(&self) -> Option<ArgList> {
+        support::child(&self.syntax)
+    }
+}
+
+impl BinExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        support::children(&self.syntax).nth(1)
+    }
+
+    /// The operator tokens between the operands.
+    pub fn op_tokens(&self) -> Vec<SyntaxToken> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|it| !it.kind().is_trivia())
+            .collect()
+    }
+
+    /// The operator in lowercase, with words separated by single spaces.
+    pub fn op_text(&self) -> String {
+        self.op_tokens()
+            .iter()
+            .map(|it| it.text().to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl PrefixExpr {
+    pub fn op(&self) -> Option<SyntaxToken> {
+        self.syntax.first_token()
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl PostfixExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl TernaryExpr {
+    pub fn condition(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn then_expr(&self) -> Option<Expr> {
+        support::children(&self.syntax).nth(1)
+    }
+
+    pub fn else_expr(&self) -> Option<Expr> {
+        support::children(&self.syntax).nth(2)
+    }
+}
+
+impl AssignExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        support::children(&self.syntax).nth(1)
+    }
+
+    pub fn op(&self) -> Option<SyntaxToken> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .find(|it| it.kind().is_assign_op())
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        support::child(&self.syntax)
+    }
+}
+
+impl StructLit {
+    pub fn fields(&self) -> impl Iterator<Item = StructField> {
+        support::children(&self.syntax)
+    }
+}
+
+impl StructField {
+    /// The key as written, without quotes for static strings.
+    pub fn key(&self) -> Option<String> {
+        match self.syntax.first_child_or_token()? {
+            SyntaxElement::Token(it) => Some(it.text().to_string()),
+            SyntaxElement::Node(it) => StringLit::cast(it)?.value(),
+        }
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        let key_is_node = self.syntax.first_child_or_token()?.as_node().is_some();
+        support::children(&self.syntax).nth(usize::from(key_is_node))
+    }
+}
+
+impl ArrayLit {
+    pub fn elements(&self) -> impl Iterator<Item = Expr> {
+        support::children(&self.syntax)
+    }
+}
+
+impl ClosureExpr {
+    pub fn param_list(&self) -> Option<ParamList> {
+        support::child(&self.syntax)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        support::child(&self.syntax)
+    }
+}
+
+impl ArrowExpr {
+    pub fn param_list(&self) -> Option<ParamList> {
+        support::child(&self.syntax)
+    }
+
+    /// Parameters, including the bare `x` of `x => ...`.
+    pub fn params(&self) -> Vec<Param> {
+        match self.param_list() {
+            Some(list) => list.params().collect(),
+            None => support::children(&self.syntax).collect(),
+        }
+    }
+
+    pub fn body(&self) -> Option<SyntaxNode> {
+        self.syntax
+            .children()
+            .find(|it| it.kind() == BLOCK || it.kind().is_expr())
+    }
+}
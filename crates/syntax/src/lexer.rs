@@ -0,0 +1,305 @@
+//! Script-mode tokenization plus the raw scanning helpers shared by the
+//! template parser.
+//!
+//! CFML has no single token stream: what a character means depends on
+//! whether we are in markup, inside a tag, inside a string or inside
+//! `<cfscript>`. The parser therefore drives lexing itself and only asks this
+//! module for one token (or one piece of trivia) at a time.
+use crate::SyntaxKind::{self, *};
+
+/// Returns the kind and length of the trivia at the start of `text`, if any.
+pub(crate) fn trivia(text: &str) -> Option<(SyntaxKind, usize)> {
+    let bytes = text.as_bytes();
+    let first = *bytes.first()?;
+    if first.is_ascii_whitespace() {
+        let len = bytes
+            .iter()
+            .position(|it| !it.is_ascii_whitespace())
+            .unwrap_or(bytes.len());
+        return Some((WHITESPACE, len));
+    }
+    if text.starts_with("<!---") {
+        return Some((CF_COMMENT, cf_comment_len(text)));
+    }
+    if text.starts_with("//") {
+        let len = text.find('\n').unwrap_or(text.len());
+        return Some((COMMENT, len));
+    }
+    if let Some(body) = text.strip_prefix("/*") {
+        let len = body.find("*/").map_or(text.len(), |it| it + 4);
+        return Some((COMMENT, len));
+    }
+    None
+}
+
+/// Length of a (possibly nested) `<!--- ... --->` comment at the start of `text`.
+pub(crate) fn cf_comment_len(text: &str) -> usize {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with("<!---") {
+            depth += 1;
+            i += 5;
+        } else if rest.starts_with("--->") {
+            depth -= 1;
+            i += 4;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+pub(crate) fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Length of the identifier at the start of `text` (zero if there is none).
+pub(crate) fn ident_len(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    match chars.next() {
+        Some((_, c)) if is_ident_start(c) => (),
+        _ => return 0,
+    }
+    chars
+        .find(|&(_, c)| !is_ident_continue(c))
+        .map_or(text.len(), |(idx, _)| idx)
+}
+
+/// Skips a string literal starting with a quote at `start`, honouring doubled
+/// quotes and `#...#` interpolations. Returns the offset after the closing
+/// quote, or the end of the text for an unterminated string.
+pub(crate) fn skip_string(text: &str, start: usize) -> usize {
+    let bytes = text.as_bytes();
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            c if c == quote => {
+                if bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            }
+            b'#' if bytes.get(i + 1) == Some(&b'#') => i += 2,
+            b'#' => i = skip_hash(text, i),
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Skips a `#...#` interpolation starting at `start`.
+pub(crate) fn skip_hash(text: &str, start: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => return i + 1,
+            b'"' | b'\'' => i = skip_string(text, i),
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Lexes one script token at the start of `text`, which must not start with
+/// trivia. `in_tag` makes `>` and `/>` terminate the enclosing tag instead of
+/// being comparison operators.
+pub(crate) fn script_token(text: &str, in_tag: bool) -> (SyntaxKind, usize) {
+    let c = match text.chars().next() {
+        Some(c) => c,
+        None => return (ERROR_TOKEN, 0),
+    };
+    if is_ident_start(c) {
+        return (IDENT, ident_len(text));
+    }
+    let bytes = text.as_bytes();
+    if c.is_ascii_digit() || (c == '.' && bytes.get(1).is_some_and(u8::is_ascii_digit)) {
+        return (NUMBER, number_len(text));
+    }
+    if c == '"' || c == '\'' {
+        return (QUOTE, skip_string(text, 0));
+    }
+    if in_tag {
+        if text.starts_with("/>") {
+            return (SLASH_R_ANGLE, 2);
+        }
+        if c == '>' {
+            return (R_ANGLE, 1);
+        }
+    }
+    const PUNCT: &[(&str, SyntaxKind)] = &[
+        ("===", EQ3),
+        ("!==", NEQ2),
+        ("==", EQ2),
+        ("!=", NEQ),
+        ("<>", NEQ),
+        ("<=", LTEQ),
+        (">=", GTEQ),
+        ("&&", AMP2),
+        ("||", PIPE2),
+        ("++", PLUS2),
+        ("--", MINUS2),
+        ("+=", PLUSEQ),
+        ("-=", MINUSEQ),
+        ("*=", STAREQ),
+        ("/=", SLASHEQ),
+        ("%=", PERCENTEQ),
+        ("&=", AMPEQ),
+        ("=>", FAT_ARROW),
+        ("?.", QUESTION_DOT),
+        ("?:", ELVIS),
+        ("(", L_PAREN),
+        (")", R_PAREN),
+        ("{", L_BRACE),
+        ("}", R_BRACE),
+        ("[", L_BRACK),
+        ("]", R_BRACK),
+        (",", COMMA),
+        (";", SEMICOLON),
+        (":", COLON),
+        (".", DOT),
+        ("?", QUESTION),
+        ("=", EQ),
+        ("<", LT),
+        (">", GT),
+        ("+", PLUS),
+        ("-", MINUS),
+        ("*", STAR),
+        ("/", SLASH),
+        ("\\", BACKSLASH),
+        ("%", PERCENT),
+        ("^", CARET),
+        ("&", AMP),
+        ("!", BANG),
+        ("#", HASH),
+    ];
+    for &(punct, kind) in PUNCT {
+        if text.starts_with(punct) {
+            return (kind, punct.len());
+        }
+    }
+    (ERROR_TOKEN, c.len_utf8())
+}
+
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .position(|it| !it.is_ascii_digit())
+            .map_or(bytes.len(), |it| from + it)
+    };
+    let mut i = digits(0);
+    if bytes.get(i) == Some(&b'.') && bytes.get(i + 1).is_some_and(u8::is_ascii_digit) {
+        i = digits(i + 1);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        if bytes.get(j).is_some_and(u8::is_ascii_digit) {
+            i = digits(j);
+        }
+    }
+    i
+}
+
+/// Returns `true` if `text` starts with `prefix`, ignoring ASCII case.
+pub(crate) fn starts_with_ci(text: &str, prefix: &str) -> bool {
+    text.len() >= prefix.len()
+        && text.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
+
+/// Finds `needle` in `text` ignoring ASCII case.
+pub(crate) fn find_ci(text: &str, needle: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let needle = needle.as_bytes();
+    if needle.len() > bytes.len() {
+        return None;
+    }
+    (0..=bytes.len() - needle.len())
+        .find(|&i| bytes[i..i + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(mut text: &str) -> Vec<(SyntaxKind, &str)> {
+        let mut res = Vec::new();
+        while !text.is_empty() {
+            let (kind, len) = trivia(text).unwrap_or_else(|| script_token(text, false));
+            res.push((kind, &text[..len]));
+            text = &text[len..];
+        }
+        res
+    }
+
+    #[test]
+    fn test_script_tokens() {
+        assert_eq!(
+            tokens("local.x += 1.5e3;"),
+            vec![
+                (IDENT, "local"),
+                (DOT, "."),
+                (IDENT, "x"),
+                (WHITESPACE, " "),
+                (PLUSEQ, "+="),
+                (WHITESPACE, " "),
+                (NUMBER, "1.5e3"),
+                (SEMICOLON, ";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strings_are_skipped_whole() {
+        assert_eq!(
+            tokens(r#""a ""b"" #fn("x")# c" x"#),
+            vec![
+                (QUOTE, r#""a ""b"" #fn("x")# c""#),
+                (WHITESPACE, " "),
+                (IDENT, "x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(
+            tokens("// a\n/* b */<!--- c <!--- d ---> --->x"),
+            vec![
+                (COMMENT, "// a"),
+                (WHITESPACE, "\n"),
+                (COMMENT, "/* b */"),
+                (CF_COMMENT, "<!--- c <!--- d ---> --->"),
+                (IDENT, "x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tag_terminators() {
+        assert_eq!(script_token(">", true), (R_ANGLE, 1));
+        assert_eq!(script_token("/>", true), (SLASH_R_ANGLE, 2));
+        assert_eq!(script_token(">=", false), (GTEQ, 2));
+    }
+
+    #[test]
+    fn test_find_ci() {
+        assert_eq!(find_ci("abc</CFSCRIPT>", "</cfscript"), Some(3));
+        assert!(starts_with_ci("CFSet", "cfset"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Syntax trees for CFML.
+//!
+//! The tree is lossless: every byte of the source, including whitespace and
+//! comments, is kept in a token, so the text of the root node is always the
+//! text that was parsed. Template markup, tags and cfscript share one tree;
+//! a `<cfscript>` block or a script component simply contains statement nodes.
+//!
+//! The typed layer in [`ast`] wraps untyped [`SyntaxNode`]s and offers
+//! accessors for the parts analyses care about.
+pub mod ast;
+mod lexer;
+mod parser;
+mod syntax_kind;
+mod syntax_node;
+pub mod tags;
+mod text_range;
+
+use std::sync::Arc;
+
+pub use crate::{
+    ast::AstNode,
+    syntax_kind::SyntaxKind,
+    syntax_node::{
+        GreenElement, GreenNode, GreenNodeBuilder, GreenToken, NodeOrToken, SyntaxElement,
+        SyntaxNode, SyntaxToken, TokenAtOffset, WalkEvent,
+    },
+    text_range::{TextRange, TextSize},
+};
+
+/// The result of parsing a file: a green tree plus the errors found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parse {
+    green: Arc<GreenNode>,
+    errors: Arc<[SyntaxError]>,
+}
+
+impl Parse {
+    pub fn syntax_node(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn tree(&self) -> ast::SourceFile {
+        ast::SourceFile::cast(self.syntax_node()).unwrap()
+    }
+
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    /// The tree as indented lines, one per node or token, e.g. `TAG@0..13`
+    /// or `IDENT@1..6 "cfset"`, to debug the parser.
+    pub fn debug_tree(&self) -> String {
+        let mut res = String::new();
+        let mut depth = 0;
+        for event in self.syntax_node().preorder_with_tokens() {
+            match event {
+                WalkEvent::Enter(element) => {
+                    let indent = "  ".repeat(depth);
+                    let line = match element {
+                        NodeOrToken::Node(it) => format!("{indent}{it:?}\n"),
+                        NodeOrToken::Token(it) => format!("{indent}{it:?}\n"),
+                    };
+                    res.push_str(&line);
+                    depth += 1;
+                }
+                WalkEvent::Leave(_) => depth -= 1,
+            }
+        }
+        res
+    }
+}
+
+/// Parses a `.cfm` or `.cfc` file. Parsing never fails; unexpected input is
+/// kept in `ERROR` nodes and reported in [`Parse::errors`].
+pub fn parse(text: &str) -> Parse {
+    let (green, errors) = parser::parse_text(text);
+    Parse {
+        green,
+        errors: errors.into(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SyntaxError {
+    pub message: String,
+    pub range: TextRange,
+}
+
+impl SyntaxError {
+    pub fn new(message: impl Into<String>, range: TextRange) -> SyntaxError {
+        SyntaxError {
+            message: message.into(),
+            range,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tree() {
+        let parse = parse("<cfset x = 1>");
+        assert!(parse.errors().is_empty());
+        assert_eq!(parse.tree().syntax().text(), "<cfset x = 1>");
+    }
+
+    #[test]
+    fn test_debug_tree() {
+        let tree = parse("<cfset x = 1>").debug_tree();
+        let mut lines = tree.lines();
+        assert_eq!(lines.next(), Some("SOURCE_FILE@0..13"));
+        assert!(lines.all(|it| it.starts_with("  ")));
+        assert!(tree.contains("\"x\"\n"));
+    }
+}
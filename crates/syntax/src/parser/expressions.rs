@@ -0,0 +1,579 @@
+//! Expressions, strings and `#...#` interpolation.
+use super::{script, Parser};
+use crate::{SyntaxKind, SyntaxKind::*, TextRange};
+
+/// Parses an expression. Returns `false` if nothing could be parsed.
+pub(crate) fn expr(p: &mut Parser<'_>) -> bool {
+    expr_bp(p, 0)
+}
+
+enum Op {
+    Assign,
+    Ternary,
+    Elvis,
+    /// A binary operator made of `n` tokens, e.g. `DOES NOT CONTAIN`.
+    Binary(usize),
+}
+
+/// Word operators, with their binding power.
+const WORD_OPS: &[(&str, u8)] = &[
+    ("imp", 3),
+    ("eqv", 4),
+    ("or", 5),
+    ("xor", 6),
+    ("and", 7),
+    ("eq", 9),
+    ("neq", 9),
+    ("is", 9),
+    ("gt", 9),
+    ("lt", 9),
+    ("gte", 9),
+    ("lte", 9),
+    ("ge", 9),
+    ("le", 9),
+    ("contains", 9),
+    ("mod", 15),
+];
+
+fn current_op(p: &Parser<'_>) -> Option<(Op, u8, u8)> {
+    let kind = p.current();
+    let res = match kind {
+        k if k.is_assign_op() => (Op::Assign, 1, 1),
+        QUESTION => (Op::Ternary, 2, 2),
+        ELVIS => (Op::Elvis, 2, 2),
+        PIPE2 => (Op::Binary(1), 5, 6),
+        AMP2 => (Op::Binary(1), 7, 8),
+        EQ2 | EQ3 | NEQ | NEQ2 | LT | GT | LTEQ | GTEQ => (Op::Binary(1), 9, 10),
+        AMP => (Op::Binary(1), 11, 12),
+        PLUS | MINUS => (Op::Binary(1), 13, 14),
+        STAR | SLASH | BACKSLASH | PERCENT => (Op::Binary(1), 15, 16),
+        CARET => (Op::Binary(1), 17, 18),
+        IDENT => {
+            let text = p.current_text();
+            if text.eq_ignore_ascii_case("is") && p.nth_at_kw(1, "not") {
+                return Some((Op::Binary(2), 9, 10));
+            }
+            if text.eq_ignore_ascii_case("does")
+                && p.nth_at_kw(1, "not")
+                && p.nth_at_kw(2, "contain")
+            {
+                return Some((Op::Binary(3), 9, 10));
+            }
+            if text.eq_ignore_ascii_case("not") && p.nth_at_kw(1, "contains") {
+                return Some((Op::Binary(2), 9, 10));
+            }
+            let &(_, bp) = WORD_OPS
+                .iter()
+                .find(|(it, _)| it.eq_ignore_ascii_case(text))?;
+            (Op::Binary(1), bp, bp + 1)
+        }
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn expr_bp(p: &mut Parser<'_>, min_bp: u8) -> bool {
+    let checkpoint = p.checkpoint();
+    if !unary(p) {
+        return false;
+    }
+    while let Some((op, lbp, rbp)) = current_op(p) {
+        if lbp < min_bp {
+            break;
+        }
+        match op {
+            Op::Assign => {
+                p.start_node_at(checkpoint, ASSIGN_EXPR);
+                p.bump();
+                if !expr_bp(p, rbp) {
+                    p.error("expected expression");
+                }
+            }
+            Op::Ternary => {
+                p.start_node_at(checkpoint, TERNARY_EXPR);
+                p.bump();
+                if !expr_bp(p, rbp) {
+                    p.error("expected expression");
+                }
+                p.expect(COLON, "`:`");
+                if !expr_bp(p, rbp) {
+                    p.error("expected expression");
+                }
+            }
+            Op::Elvis => {
+                p.start_node_at(checkpoint, BIN_EXPR);
+                p.bump();
+                if !expr_bp(p, rbp) {
+                    p.error("expected expression");
+                }
+            }
+            Op::Binary(n) => {
+                p.start_node_at(checkpoint, BIN_EXPR);
+                for _ in 0..n {
+                    if p.at(IDENT) {
+                        p.bump_kw();
+                    } else {
+                        p.bump();
+                    }
+                }
+                if !expr_bp(p, rbp) {
+                    p.error("expected expression");
+                }
+            }
+        }
+        p.finish_node();
+    }
+    true
+}
+
+fn unary(p: &mut Parser<'_>) -> bool {
+    let operand_bp = match p.current() {
+        BANG => 9,
+        IDENT if p.at_kw("not") => 9,
+        MINUS | PLUS => 17,
+        PLUS2 | MINUS2 => 19,
+        _ => return postfix(p),
+    };
+    p.start_node(PREFIX_EXPR);
+    if p.at(IDENT) {
+        p.bump_kw();
+    } else {
+        p.bump();
+    }
+    if !expr_bp(p, operand_bp) {
+        p.error("expected expression");
+    }
+    p.finish_node();
+    true
+}
+
+fn postfix(p: &mut Parser<'_>) -> bool {
+    let checkpoint = p.checkpoint();
+    if !primary(p) {
+        return false;
+    }
+    loop {
+        match p.current() {
+            DOT | QUESTION_DOT => {
+                p.start_node_at(checkpoint, FIELD_EXPR);
+                p.bump();
+                if matches!(p.nth(0), IDENT | NUMBER) {
+                    p.bump_remap(IDENT);
+                } else {
+                    p.error("expected field name");
+                }
+                p.finish_node();
+            }
+            L_BRACK => {
+                p.start_node_at(checkpoint, INDEX_EXPR);
+                p.bump();
+                if !expr(p) {
+                    p.error("expected expression");
+                }
+                p.expect(R_BRACK, "`]`");
+                p.finish_node();
+            }
+            L_PAREN => {
+                p.start_node_at(checkpoint, CALL_EXPR);
+                arg_list(p);
+                p.finish_node();
+            }
+            PLUS2 | MINUS2 => {
+                p.start_node_at(checkpoint, POSTFIX_EXPR);
+                p.bump();
+                p.finish_node();
+            }
+            _ => break,
+        }
+    }
+    true
+}
+
+fn primary(p: &mut Parser<'_>) -> bool {
+    match p.current() {
+        NUMBER => {
+            p.start_node(LITERAL);
+            p.bump();
+            p.finish_node();
+        }
+        QUOTE => {
+            p.flush_trivia();
+            string(p);
+        }
+        HASH => {
+            p.flush_trivia();
+            hash_expr(p);
+        }
+        IDENT => {
+            let text = p.current_text();
+            if ["true", "false", "null"]
+                .iter()
+                .any(|it| it.eq_ignore_ascii_case(text))
+            {
+                p.start_node(LITERAL);
+                p.bump_kw();
+                p.finish_node();
+            } else if p.at_kw("new") && matches!(p.nth(1), IDENT | QUOTE) {
+                new_expr(p);
+            } else if p.at_kw("function") && p.nth(1) == L_PAREN {
+                closure(p);
+            } else if p.nth(1) == FAT_ARROW {
+                p.start_node(ARROW_EXPR);
+                p.start_node(PARAM);
+                p.start_node(NAME);
+                p.bump();
+                p.finish_node();
+                p.finish_node();
+                arrow_body(p);
+                p.finish_node();
+            } else {
+                p.start_node(NAME_REF);
+                p.bump();
+                p.finish_node();
+            }
+        }
+        L_PAREN if at_arrow_params(p) => {
+            p.start_node(ARROW_EXPR);
+            script::param_list(p);
+            arrow_body(p);
+            p.finish_node();
+        }
+        L_PAREN => {
+            p.start_node(PAREN_EXPR);
+            p.bump();
+            if !expr(p) {
+                p.error("expected expression");
+            }
+            p.expect(R_PAREN, "`)`");
+            p.finish_node();
+        }
+        L_BRACK => array_or_ordered_struct(p),
+        L_BRACE => struct_lit(p, L_BRACE, R_BRACE),
+        _ => {
+            p.error("expected expression");
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns `true` if the parenthesis at the current position starts the
+/// parameters of an arrow function, i.e. is followed by `=>`.
+fn at_arrow_params(p: &Parser<'_>) -> bool {
+    let mut depth = 0;
+    let mut n = 0;
+    loop {
+        match p.nth(n) {
+            L_PAREN => depth += 1,
+            R_PAREN => {
+                depth -= 1;
+                if depth == 0 {
+                    return p.nth(n + 1) == FAT_ARROW;
+                }
+            }
+            EOF => return false,
+            _ => (),
+        }
+        n += 1;
+    }
+}
+
+fn arrow_body(p: &mut Parser<'_>) {
+    p.expect(FAT_ARROW, "`=>`");
+    if p.at(L_BRACE) {
+        script::block(p);
+    } else if !expr(p) {
+        p.error("expected expression");
+    }
+}
+
+fn closure(p: &mut Parser<'_>) {
+    p.start_node(CLOSURE_EXPR);
+    p.bump_kw();
+    script::param_list(p);
+    script::attributes(p);
+    if p.at(L_BRACE) {
+        script::block(p);
+    } else {
+        p.error("expected `{`");
+    }
+    p.finish_node();
+}
+
+fn new_expr(p: &mut Parser<'_>) {
+    p.start_node(NEW_EXPR);
+    p.bump_kw();
+    if p.at(QUOTE) {
+        p.flush_trivia();
+        string(p);
+    } else {
+        path(p);
+    }
+    if p.at(L_PAREN) {
+        arg_list(p);
+    }
+    p.finish_node();
+}
+
+/// Parses a dotted name such as `com.example.Service`.
+pub(crate) fn path(p: &mut Parser<'_>) {
+    p.start_node(PATH);
+    p.bump_remap(IDENT);
+    while p.at(DOT) && matches!(p.nth(1), IDENT | STAR) {
+        p.bump();
+        p.bump();
+    }
+    p.finish_node();
+}
+
+pub(crate) fn arg_list(p: &mut Parser<'_>) {
+    p.start_node(ARG_LIST);
+    p.bump();
+    while !matches!(p.current(), R_PAREN | EOF) {
+        p.start_node(ARG);
+        if matches!(p.current(), IDENT | QUOTE) && matches!(p.nth(1), EQ | COLON) {
+            p.bump();
+            p.bump();
+        }
+        if !expr(p) {
+            p.finish_node();
+            if !matches!(p.current(), COMMA | R_PAREN) {
+                p.err_and_bump("expected argument");
+            }
+        } else {
+            p.finish_node();
+        }
+        if !p.at(R_PAREN)
+            && !p.expect(COMMA, "`,`")
+            && !matches!(p.current(), IDENT | QUOTE | NUMBER)
+        {
+            break;
+        }
+    }
+    p.expect(R_PAREN, "`)`");
+    p.finish_node();
+}
+
+fn array_or_ordered_struct(p: &mut Parser<'_>) {
+    let ordered_struct = (p.nth(1) == COLON && p.nth(2) == R_BRACK)
+        || (matches!(p.nth(1), IDENT | QUOTE) && matches!(p.nth(2), COLON | EQ));
+    if ordered_struct {
+        struct_lit(p, L_BRACK, R_BRACK);
+        return;
+    }
+    p.start_node(ARRAY_LIT);
+    p.bump();
+    while !matches!(p.current(), R_BRACK | EOF) {
+        if !expr(p) && !p.at(COMMA) {
+            p.err_and_bump("expected expression");
+        }
+        if !p.at(R_BRACK) && !p.expect(COMMA, "`,`") {
+            break;
+        }
+    }
+    p.expect(R_BRACK, "`]`");
+    p.finish_node();
+}
+
+fn struct_lit(p: &mut Parser<'_>, open: SyntaxKind, close: SyntaxKind) {
+    p.start_node(STRUCT_LIT);
+    p.bump();
+    if open == L_BRACK && p.at(COLON) {
+        p.bump();
+    }
+    while !p.at(close) && !p.at(EOF) {
+        p.start_node(STRUCT_FIELD);
+        match p.current() {
+            IDENT | NUMBER => p.bump_remap(p.current()),
+            QUOTE => {
+                p.flush_trivia();
+                string(p);
+            }
+            _ => p.error("expected struct key"),
+        }
+        if !p.eat(COLON) && !p.eat(EQ) {
+            p.error("expected `:` or `=`");
+        }
+        if !expr(p) {
+            p.error("expected expression");
+        }
+        p.finish_node();
+        if !p.at(close) && !p.expect(COMMA, "`,`") {
+            if matches!(p.current(), IDENT | QUOTE | NUMBER) {
+                continue;
+            }
+            break;
+        }
+    }
+    if close == R_BRACE {
+        p.expect(R_BRACE, "`}`");
+    } else {
+        p.expect(R_BRACK, "`]`");
+    }
+    p.finish_node();
+}
+
+/// Parses a quoted string at the current raw position, with `#...#`
+/// interpolations. Doubled quotes and `##` are kept inside the text tokens.
+pub(crate) fn string(p: &mut Parser<'_>) {
+    let quote = p.rest().as_bytes()[0];
+    p.start_raw_node(STRING);
+    p.raw_token(QUOTE, 1);
+    loop {
+        let rest = &p.text.as_bytes()[p.pos..];
+        let mut i = 0;
+        let mut closed = false;
+        let mut interpolation = false;
+        while i < rest.len() {
+            match rest[i] {
+                c if c == quote => {
+                    if rest.get(i + 1) == Some(&quote) {
+                        i += 2;
+                    } else {
+                        closed = true;
+                        break;
+                    }
+                }
+                b'#' if rest.get(i + 1) == Some(&b'#') => i += 2,
+                b'#' => {
+                    interpolation = true;
+                    break;
+                }
+                _ => i += 1,
+            }
+        }
+        if i > 0 {
+            p.raw_token(STRING_TEXT, i);
+        }
+        if closed {
+            p.raw_token(QUOTE, 1);
+            break;
+        }
+        if interpolation {
+            let limit = p.limit;
+            p.limit = p.text.len();
+            hash_expr(p);
+            p.limit = limit;
+            continue;
+        }
+        p.error_at(TextRange::empty(p.pos as u32), "unterminated string");
+        break;
+    }
+    p.finish_node();
+}
+
+/// Parses `#expr#` at the current raw position.
+pub(crate) fn hash_expr(p: &mut Parser<'_>) {
+    p.start_raw_node(HASH_EXPR);
+    p.raw_token(HASH, 1);
+    let in_tag = p.in_tag;
+    p.in_tag = false;
+    if !expr(p) {
+        while !matches!(p.current(), HASH | EOF | QUOTE) {
+            p.err_and_bump("expected expression");
+        }
+    }
+    p.expect(HASH, "`#`");
+    p.in_tag = in_tag;
+    p.finish_node();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, SyntaxKind::*, SyntaxNode};
+
+    fn expr_kinds(text: &str) -> Vec<crate::SyntaxKind> {
+        let parse = parse(&format!("<cfset {text}>"));
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        let tag_open = parse
+            .syntax_node()
+            .descendants()
+            .find(|it| it.kind() == TAG_OPEN)
+            .unwrap();
+        let expr = tag_open.children().find(|it| it.kind().is_expr()).unwrap();
+        expr.descendants().map(|it| it.kind()).collect()
+    }
+
+    fn first_expr(text: &str) -> SyntaxNode {
+        let parse = parse(&format!("<cfset {text}>"));
+        parse
+            .syntax_node()
+            .descendants()
+            .find(|it| it.kind().is_expr())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        let expr = first_expr("x = a + b * c");
+        assert_eq!(expr.kind(), ASSIGN_EXPR);
+        let rhs = expr.last_child().unwrap();
+        assert_eq!(rhs.kind(), BIN_EXPR);
+        assert_eq!(rhs.last_child().unwrap().text(), "b * c");
+    }
+
+    #[test]
+    fn test_word_operators() {
+        let expr = first_expr("not a eq b and c does not contain d");
+        assert_eq!(expr.kind(), BIN_EXPR);
+        assert_eq!(expr.first_child().unwrap().text(), "not a eq b");
+        assert_eq!(expr.last_child().unwrap().text(), "c does not contain d");
+    }
+
+    #[test]
+    fn test_postfix_chain() {
+        assert_eq!(
+            expr_kinds("a.b[1].c(x=1, 2)"),
+            vec![
+                CALL_EXPR, FIELD_EXPR, INDEX_EXPR, FIELD_EXPR, NAME_REF, LITERAL, ARG_LIST, ARG,
+                LITERAL, ARG, LITERAL
+            ]
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            expr_kinds("{a: [1, \"#b#\"], 'c' = true}"),
+            vec![
+                STRUCT_LIT,
+                STRUCT_FIELD,
+                ARRAY_LIT,
+                LITERAL,
+                STRING,
+                HASH_EXPR,
+                NAME_REF,
+                STRUCT_FIELD,
+                STRING,
+                LITERAL
+            ]
+        );
+        assert_eq!(expr_kinds("[:]"), vec![STRUCT_LIT]);
+    }
+
+    #[test]
+    fn test_new_and_closures() {
+        assert_eq!(
+            expr_kinds("new com.foo.Bar(1)"),
+            vec![NEW_EXPR, PATH, ARG_LIST, ARG, LITERAL]
+        );
+        assert_eq!(expr_kinds("(a, b) => a + b")[..2], [ARROW_EXPR, PARAM_LIST]);
+        assert_eq!(
+            expr_kinds("function(x) { return x; }")[..2],
+            [CLOSURE_EXPR, PARAM_LIST]
+        );
+    }
+
+    #[test]
+    fn test_ternary_and_elvis() {
+        assert_eq!(first_expr("a ? b : c").kind(), TERNARY_EXPR);
+        assert_eq!(first_expr("a ?: c").kind(), BIN_EXPR);
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let expr = first_expr("\"a \"\"b\"\" ## #c#\"");
+        let kinds: Vec<_> = expr.children_with_tokens().map(|it| it.kind()).collect();
+        assert_eq!(kinds, vec![QUOTE, STRING_TEXT, HASH_EXPR, QUOTE]);
+    }
+}
@@ -0,0 +1,328 @@
+//! A hand-written, error-tolerant parser for CFML.
+//!
+//! The parser builds the green tree directly. Script tokens are lexed lazily
+//! from the current position so the parser can switch between template, tag
+//! and script modes at any point; trivia is only attached to the tree when the
+//! next significant token is consumed, which keeps leading and trailing trivia
+//! outside of nodes.
+mod expressions;
+mod script;
+mod template;
+
+use std::cell::Cell;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    lexer,
+    syntax_node::{Checkpoint, GreenNodeBuilder},
+    SyntaxError, SyntaxKind,
+    SyntaxKind::*,
+    TextRange,
+};
+
+pub(crate) use template::TagPairing;
+
+pub(crate) struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+    builder: GreenNodeBuilder,
+    errors: Vec<SyntaxError>,
+    pairings: FxHashMap<usize, TagPairing>,
+    /// End of the current script region, e.g. the start of `</cfscript>`.
+    limit: usize,
+    /// Inside a tag, where `>` ends the tag rather than being an operator.
+    in_tag: bool,
+    cache: Cell<Option<(usize, usize, bool, Token)>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: SyntaxKind,
+    start: usize,
+    len: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn new(text: &'t str) -> Parser<'t> {
+        Parser {
+            text,
+            pos: 0,
+            builder: GreenNodeBuilder::default(),
+            errors: Vec::new(),
+            pairings: FxHashMap::default(),
+            limit: text.len(),
+            in_tag: false,
+            cache: Cell::new(None),
+        }
+    }
+
+    fn finish(self) -> (std::sync::Arc<crate::GreenNode>, Vec<SyntaxError>) {
+        (self.builder.finish(), self.errors)
+    }
+
+    fn rest(&self) -> &'t str {
+        &self.text[self.pos..self.limit.max(self.pos)]
+    }
+
+    fn token_from(&self, pos: usize) -> Token {
+        let mut start = pos;
+        while start < self.limit {
+            match lexer::trivia(&self.text[start..self.limit]) {
+                Some((_, len)) => start += len,
+                None => break,
+            }
+        }
+        if start >= self.limit {
+            return Token {
+                kind: EOF,
+                start: start.min(self.limit.max(pos)),
+                len: 0,
+            };
+        }
+        let (kind, len) = lexer::script_token(&self.text[start..self.limit], self.in_tag);
+        Token { kind, start, len }
+    }
+
+    fn token(&self) -> Token {
+        if let Some((pos, limit, in_tag, token)) = self.cache.get() {
+            if pos == self.pos && limit == self.limit && in_tag == self.in_tag {
+                return token;
+            }
+        }
+        let token = self.token_from(self.pos);
+        self.cache
+            .set(Some((self.pos, self.limit, self.in_tag, token)));
+        token
+    }
+
+    fn nth_token(&self, n: usize) -> Token {
+        let mut token = self.token();
+        for _ in 0..n {
+            if token.kind == EOF {
+                break;
+            }
+            token = self.token_from(token.start + token.len);
+        }
+        token
+    }
+
+    pub(crate) fn current(&self) -> SyntaxKind {
+        self.token().kind
+    }
+
+    pub(crate) fn nth(&self, n: usize) -> SyntaxKind {
+        self.nth_token(n).kind
+    }
+
+    pub(crate) fn nth_text(&self, n: usize) -> &'t str {
+        let token = self.nth_token(n);
+        &self.text[token.start..token.start + token.len]
+    }
+
+    pub(crate) fn current_text(&self) -> &'t str {
+        self.nth_text(0)
+    }
+
+    pub(crate) fn at(&self, kind: SyntaxKind) -> bool {
+        self.current() == kind
+    }
+
+    /// Checks whether the `n`-th token is the (case-insensitive) keyword `kw`.
+    pub(crate) fn nth_at_kw(&self, n: usize, kw: &str) -> bool {
+        self.nth(n) == IDENT && self.nth_text(n).eq_ignore_ascii_case(kw)
+    }
+
+    pub(crate) fn at_kw(&self, kw: &str) -> bool {
+        self.nth_at_kw(0, kw)
+    }
+
+    pub(crate) fn current_range(&self) -> TextRange {
+        let token = self.token();
+        TextRange::at(token.start as u32, token.len as u32)
+    }
+
+    /// Attaches pending trivia to the current node.
+    pub(crate) fn flush_trivia(&mut self) {
+        while self.pos < self.limit {
+            match lexer::trivia(&self.text[self.pos..self.limit]) {
+                Some((kind, len)) => self.raw_token(kind, len),
+                None => break,
+            }
+        }
+    }
+
+    /// Emits the next `len` bytes of text as a token, without any lexing.
+    pub(crate) fn raw_token(&mut self, kind: SyntaxKind, len: usize) {
+        let end = (self.pos + len).min(self.text.len());
+        self.builder.token(kind, &self.text[self.pos..end]);
+        self.pos = end;
+    }
+
+    pub(crate) fn bump(&mut self) {
+        let kind = self.current();
+        self.bump_remap(kind);
+    }
+
+    pub(crate) fn bump_remap(&mut self, kind: SyntaxKind) {
+        let token = self.token();
+        if token.kind == EOF {
+            return;
+        }
+        if token.kind == QUOTE {
+            self.flush_trivia();
+            expressions::string(self);
+            return;
+        }
+        self.flush_trivia();
+        self.raw_token(kind, token.len);
+    }
+
+    pub(crate) fn bump_kw(&mut self) {
+        self.bump_remap(KEYWORD);
+    }
+
+    pub(crate) fn eat(&mut self, kind: SyntaxKind) -> bool {
+        if self.at(kind) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn eat_kw(&mut self, kw: &str) -> bool {
+        if self.at_kw(kw) {
+            self.bump_kw();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn expect(&mut self, kind: SyntaxKind, what: &str) -> bool {
+        if self.eat(kind) {
+            true
+        } else {
+            self.error(format!("expected {what}"));
+            false
+        }
+    }
+
+    pub(crate) fn error(&mut self, message: impl Into<String>) {
+        let range = self.current_range();
+        self.error_at(range, message);
+    }
+
+    pub(crate) fn error_at(&mut self, range: TextRange, message: impl Into<String>) {
+        let message = message.into();
+        // Avoid piling up identical errors at the same place during recovery.
+        if self
+            .errors
+            .last()
+            .is_some_and(|it| it.range == range && it.message == message)
+        {
+            return;
+        }
+        self.errors.push(SyntaxError::new(message, range));
+    }
+
+    /// Reports an error and wraps the current token into an `ERROR` node.
+    pub(crate) fn err_and_bump(&mut self, message: &str) {
+        self.error(message);
+        if self.at(EOF) {
+            return;
+        }
+        self.start_node(ERROR);
+        self.bump();
+        self.finish_node();
+    }
+
+    pub(crate) fn start_node(&mut self, kind: SyntaxKind) {
+        self.flush_trivia();
+        self.builder.start_node(kind);
+    }
+
+    /// Starts a node at a raw position, without attaching pending trivia.
+    pub(crate) fn start_raw_node(&mut self, kind: SyntaxKind) {
+        self.builder.start_node(kind);
+    }
+
+    pub(crate) fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint {
+        self.flush_trivia();
+        self.builder.checkpoint()
+    }
+
+    pub(crate) fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        self.builder.start_node_at(checkpoint, kind);
+    }
+}
+
+pub(crate) fn parse_text(text: &str) -> (std::sync::Arc<crate::GreenNode>, Vec<SyntaxError>) {
+    let mut p = Parser::new(text);
+    p.builder.start_node(SOURCE_FILE);
+    if is_script_file(text) {
+        script::source_file(&mut p);
+    } else {
+        p.pairings = template::pair_tags(text);
+        let end = text.len();
+        template::items(&mut p, end, false);
+    }
+    p.flush_trivia();
+    if p.pos < text.len() {
+        // Anything left over (e.g. after an unbalanced `}`) is kept verbatim.
+        p.start_raw_node(ERROR);
+        p.raw_token(ERROR_TOKEN, text.len() - p.pos);
+        p.finish_node();
+    }
+    p.builder.finish_node();
+    p.finish()
+}
+
+/// Returns `true` if the file is written in cfscript, i.e. its first
+/// significant word is `component`, `interface`, `import` or a modifier.
+pub(crate) fn is_script_file(text: &str) -> bool {
+    let mut pos = 0;
+    while let Some((_, len)) = lexer::trivia(&text[pos..]) {
+        pos += len;
+    }
+    let rest = &text[pos..];
+    let word = &rest[..lexer::ident_len(rest)];
+    ["component", "interface", "import", "abstract", "final"]
+        .iter()
+        .any(|it| it.eq_ignore_ascii_case(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_script_file() {
+        assert!(is_script_file("/** doc */\ncomponent {}"));
+        assert!(is_script_file("<!--- c --->Component extends=\"x\" {}"));
+        assert!(is_script_file("import foo.*; component {}"));
+        assert!(!is_script_file("<cfcomponent></cfcomponent>"));
+        assert!(!is_script_file("components are great"));
+    }
+
+    #[test]
+    fn test_parse_is_lossless() {
+        let texts = [
+            "<cfset x = 1>\n<cfoutput>#x# ## done</cfoutput>",
+            "component { function f() { return 1 + } }",
+            "<cfif a><cfloop></cfif></cfunknown>",
+            "}}}<cfscript> x = { a: [1, 2 }",
+            "<cfset x = \"unterminated",
+        ];
+        for text in texts {
+            let (green, _) = parse_text(text);
+            let node = crate::SyntaxNode::new_root(green);
+            assert_eq!(node.text(), text);
+        }
+    }
+}
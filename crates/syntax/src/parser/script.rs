@@ -0,0 +1,594 @@
+//! cfscript statements and declarations.
+use super::{expressions, Parser};
+use crate::{tags, SyntaxKind::*};
+
+const MODIFIERS: &[&str] = &[
+    "public", "private", "package", "remote", "static", "abstract", "final",
+];
+
+fn is_modifier(text: &str) -> bool {
+    MODIFIERS.iter().any(|it| it.eq_ignore_ascii_case(text))
+}
+
+/// Parses a script file, e.g. a `.cfc` starting with `component`.
+pub(crate) fn source_file(p: &mut Parser<'_>) {
+    statements(p, |_| false);
+}
+
+/// Parses statements until the end of the current region or until
+/// `terminator` returns `true`.
+pub(crate) fn statements(p: &mut Parser<'_>, terminator: impl Fn(&Parser<'_>) -> bool) {
+    while !p.at(EOF) && !terminator(p) {
+        if p.at(R_BRACE) {
+            p.err_and_bump("unmatched `}`");
+            continue;
+        }
+        statement(p);
+    }
+}
+
+fn statement(p: &mut Parser<'_>) {
+    let next = p.nth(1);
+    match p.current() {
+        L_BRACE => block(p),
+        SEMICOLON => {
+            p.start_node(EMPTY_STMT);
+            p.bump();
+            p.finish_node();
+        }
+        IDENT => {
+            let kw = p.current_text().to_ascii_lowercase();
+            match kw.as_str() {
+                "var" if next == IDENT => var_decl(p, true),
+                "if" if next == L_PAREN => if_stmt(p),
+                "for" if next == L_PAREN => for_stmt(p),
+                "while" if next == L_PAREN => while_stmt(p),
+                "do" if next == L_BRACE => do_while_stmt(p),
+                "switch" if next == L_PAREN => switch_stmt(p),
+                "try" if next == L_BRACE => try_stmt(p),
+                "return" => return_stmt(p),
+                "break" | "continue" if !continues_expr(next) => {
+                    p.start_node(if kw == "break" {
+                        BREAK_STMT
+                    } else {
+                        CONTINUE_STMT
+                    });
+                    p.bump_kw();
+                    stmt_end(p);
+                    p.finish_node();
+                }
+                "throw" if !continues_expr(next) => throw_stmt(p),
+                "import" if matches!(next, IDENT | QUOTE) => import_decl(p),
+                "property" if next == IDENT => property_decl(p),
+                _ if at_component_decl(p) => component_decl(p),
+                _ if at_function_decl(p) => function_decl(p),
+                _ if tags::is_script_tag_statement(&kw)
+                    && matches!(next, IDENT | QUOTE | L_BRACE | SEMICOLON | R_BRACE | EOF) =>
+                {
+                    script_tag_stmt(p)
+                }
+                _ => expr_stmt(p),
+            }
+        }
+        NUMBER | QUOTE | HASH | L_PAREN | L_BRACK | BANG | MINUS | PLUS | PLUS2 | MINUS2 => {
+            expr_stmt(p)
+        }
+        _ => p.err_and_bump("expected statement"),
+    }
+}
+
+/// Returns `true` if a keyword followed by `next` is really used as a
+/// variable, e.g. `throw = 1` or `break.x`.
+fn continues_expr(next: crate::SyntaxKind) -> bool {
+    next.is_assign_op() || matches!(next, DOT | L_PAREN | L_BRACK)
+}
+
+/// Consumes the end of a statement. Semicolons may be omitted at the end of
+/// a line or before a closing brace.
+fn stmt_end(p: &mut Parser<'_>) {
+    if p.eat(SEMICOLON) || matches!(p.current(), R_BRACE | EOF) {
+        return;
+    }
+    let start = p.current_range().start() as usize;
+    if !p.text[p.pos..start].contains('\n') {
+        p.error("expected `;`");
+    }
+}
+
+pub(crate) fn block(p: &mut Parser<'_>) {
+    let in_tag = p.in_tag;
+    p.in_tag = false;
+    p.start_node(BLOCK);
+    p.expect(L_BRACE, "`{`");
+    statements(p, |p| p.at(R_BRACE));
+    p.expect(R_BRACE, "`}`");
+    p.finish_node();
+    p.in_tag = in_tag;
+}
+
+fn expr_stmt(p: &mut Parser<'_>) {
+    p.start_node(EXPR_STMT);
+    expressions::expr(p);
+    stmt_end(p);
+    p.finish_node();
+}
+
+/// Parses `var name = value`, optionally followed by a semicolon.
+pub(crate) fn var_decl(p: &mut Parser<'_>, consume_semi: bool) {
+    p.start_node(VAR_DECL);
+    p.bump_kw();
+    name(p);
+    if p.eat(EQ) && !expressions::expr(p) {
+        p.error("expected expression");
+    }
+    if consume_semi {
+        stmt_end(p);
+    }
+    p.finish_node();
+}
+
+fn name(p: &mut Parser<'_>) {
+    p.start_node(NAME);
+    if p.at(IDENT) {
+        p.bump();
+    } else {
+        p.error("expected name");
+    }
+    p.finish_node();
+}
+
+fn type_ref(p: &mut Parser<'_>) {
+    p.start_node(TYPE);
+    p.bump();
+    while p.at(DOT) && p.nth(1) == IDENT {
+        p.bump();
+        p.bump();
+    }
+    if p.at(L_BRACK) && p.nth(1) == R_BRACK {
+        p.bump();
+        p.bump();
+    }
+    p.finish_node();
+}
+
+fn condition(p: &mut Parser<'_>) {
+    p.start_node(PAREN_EXPR);
+    p.expect(L_PAREN, "`(`");
+    if !expressions::expr(p) {
+        p.error("expected condition");
+    }
+    p.expect(R_PAREN, "`)`");
+    p.finish_node();
+}
+
+fn body(p: &mut Parser<'_>) {
+    if p.at(EOF) {
+        p.error("expected statement");
+    } else {
+        statement(p);
+    }
+}
+
+fn if_stmt(p: &mut Parser<'_>) {
+    p.start_node(IF_STMT);
+    p.bump_kw();
+    condition(p);
+    body(p);
+    if p.at_kw("else") {
+        p.start_node(ELSE_CLAUSE);
+        p.bump_kw();
+        body(p);
+        p.finish_node();
+    }
+    p.finish_node();
+}
+
+/// Checks whether the parenthesis after `for` contains `x in y`.
+fn at_for_in(p: &Parser<'_>) -> bool {
+    let mut depth = 0;
+    let mut n = 1;
+    loop {
+        match p.nth(n) {
+            L_PAREN | L_BRACK | L_BRACE => depth += 1,
+            R_PAREN | R_BRACK | R_BRACE => {
+                depth -= 1;
+                if depth == 0 {
+                    return false;
+                }
+            }
+            SEMICOLON | EOF => return false,
+            IDENT if depth == 1 && p.nth_at_kw(n, "in") => return true,
+            _ => (),
+        }
+        n += 1;
+    }
+}
+
+fn for_stmt(p: &mut Parser<'_>) {
+    if at_for_in(p) {
+        p.start_node(FOR_IN_STMT);
+        p.bump_kw();
+        p.bump();
+        if p.at_kw("var") {
+            var_decl(p, false);
+        } else {
+            expressions::expr(p);
+        }
+        if !p.eat_kw("in") {
+            p.error("expected `in`");
+        }
+        if !expressions::expr(p) {
+            p.error("expected expression");
+        }
+    } else {
+        p.start_node(FOR_STMT);
+        p.bump_kw();
+        p.bump();
+        if p.at_kw("var") && p.nth(1) == IDENT {
+            var_decl(p, false);
+        } else if !p.at(SEMICOLON) {
+            expressions::expr(p);
+        }
+        p.expect(SEMICOLON, "`;`");
+        if !p.at(SEMICOLON) {
+            expressions::expr(p);
+        }
+        p.expect(SEMICOLON, "`;`");
+        if !p.at(R_PAREN) {
+            expressions::expr(p);
+        }
+    }
+    p.expect(R_PAREN, "`)`");
+    body(p);
+    p.finish_node();
+}
+
+fn while_stmt(p: &mut Parser<'_>) {
+    p.start_node(WHILE_STMT);
+    p.bump_kw();
+    condition(p);
+    body(p);
+    p.finish_node();
+}
+
+fn do_while_stmt(p: &mut Parser<'_>) {
+    p.start_node(DO_WHILE_STMT);
+    p.bump_kw();
+    block(p);
+    if p.eat_kw("while") {
+        condition(p);
+    } else {
+        p.error("expected `while`");
+    }
+    stmt_end(p);
+    p.finish_node();
+}
+
+fn switch_stmt(p: &mut Parser<'_>) {
+    p.start_node(SWITCH_STMT);
+    p.bump_kw();
+    condition(p);
+    p.expect(L_BRACE, "`{`");
+    while !matches!(p.current(), R_BRACE | EOF) {
+        let kind = if p.at_kw("case") {
+            CASE_CLAUSE
+        } else if p.at_kw("default") {
+            DEFAULT_CLAUSE
+        } else {
+            p.err_and_bump("expected `case` or `default`");
+            continue;
+        };
+        p.start_node(kind);
+        p.bump_kw();
+        if kind == CASE_CLAUSE && !expressions::expr(p) {
+            p.error("expected expression");
+        }
+        p.expect(COLON, "`:`");
+        statements(p, |p| {
+            p.at(R_BRACE) || p.at_kw("case") || (p.at_kw("default") && p.nth(1) == COLON)
+        });
+        p.finish_node();
+    }
+    p.expect(R_BRACE, "`}`");
+    p.finish_node();
+}
+
+fn try_stmt(p: &mut Parser<'_>) {
+    p.start_node(TRY_STMT);
+    p.bump_kw();
+    block(p);
+    while p.at_kw("catch") {
+        p.start_node(CATCH_CLAUSE);
+        p.bump_kw();
+        p.expect(L_PAREN, "`(`");
+        if p.at(IDENT) && p.nth(1) != R_PAREN {
+            type_ref(p);
+        } else if p.at(QUOTE) {
+            p.bump();
+        }
+        name(p);
+        p.expect(R_PAREN, "`)`");
+        block(p);
+        p.finish_node();
+    }
+    if p.at_kw("finally") {
+        p.start_node(FINALLY_CLAUSE);
+        p.bump_kw();
+        block(p);
+        p.finish_node();
+    }
+    p.finish_node();
+}
+
+fn return_stmt(p: &mut Parser<'_>) {
+    p.start_node(RETURN_STMT);
+    p.bump_kw();
+    if !matches!(p.current(), SEMICOLON | R_BRACE | EOF) {
+        expressions::expr(p);
+    }
+    stmt_end(p);
+    p.finish_node();
+}
+
+fn throw_stmt(p: &mut Parser<'_>) {
+    p.start_node(THROW_STMT);
+    p.bump_kw();
+    if p.at(IDENT) && p.nth(1) == EQ {
+        attributes(p);
+    } else if !matches!(p.current(), SEMICOLON | R_BRACE | EOF) {
+        expressions::expr(p);
+    }
+    stmt_end(p);
+    p.finish_node();
+}
+
+fn import_decl(p: &mut Parser<'_>) {
+    p.start_node(IMPORT_DECL);
+    p.bump_kw();
+    if p.at(QUOTE) {
+        p.bump();
+    } else {
+        expressions::path(p);
+    }
+    stmt_end(p);
+    p.finish_node();
+}
+
+fn property_decl(p: &mut Parser<'_>) {
+    p.start_node(PROPERTY_DECL);
+    p.bump_kw();
+    if p.at(IDENT) && p.nth(1) != EQ {
+        if p.nth(1) == IDENT && p.nth(2) != EQ || p.nth(1) == DOT {
+            type_ref(p);
+        }
+        name(p);
+    }
+    attributes(p);
+    stmt_end(p);
+    p.finish_node();
+}
+
+fn at_component_decl(p: &Parser<'_>) -> bool {
+    let mut n = 0;
+    while p.nth_at_kw(n, "abstract") || p.nth_at_kw(n, "final") {
+        n += 1;
+    }
+    (p.nth_at_kw(n, "component") || p.nth_at_kw(n, "interface"))
+        && matches!(p.nth(n + 1), IDENT | L_BRACE)
+}
+
+fn component_decl(p: &mut Parser<'_>) {
+    p.start_node(COMPONENT_DECL);
+    while !p.at_kw("component") && !p.at_kw("interface") {
+        p.bump_kw();
+    }
+    p.bump_kw();
+    attributes(p);
+    if p.at(L_BRACE) {
+        block(p);
+    } else {
+        p.error("expected `{`");
+    }
+    p.finish_node();
+}
+
+fn at_function_decl(p: &Parser<'_>) -> bool {
+    let mut n = 0;
+    while p.nth(n) == IDENT && is_modifier(p.nth_text(n)) {
+        n += 1;
+    }
+    if !p.nth_at_kw(n, "function") {
+        // A return type, possibly dotted or an array.
+        if p.nth(n) != IDENT {
+            return false;
+        }
+        n += 1;
+        while p.nth(n) == DOT && p.nth(n + 1) == IDENT {
+            n += 2;
+        }
+        if p.nth(n) == L_BRACK && p.nth(n + 1) == R_BRACK {
+            n += 2;
+        }
+    }
+    p.nth_at_kw(n, "function") && p.nth(n + 1) == IDENT && p.nth(n + 2) == L_PAREN
+}
+
+fn function_decl(p: &mut Parser<'_>) {
+    p.start_node(FUNCTION_DECL);
+    while p.at(IDENT) && is_modifier(p.current_text()) && !p.at_kw("function") {
+        p.bump_kw();
+    }
+    if !p.at_kw("function") {
+        type_ref(p);
+    }
+    p.bump_kw();
+    name(p);
+    param_list(p);
+    attributes(p);
+    if p.at(L_BRACE) {
+        block(p);
+    } else {
+        stmt_end(p);
+    }
+    p.finish_node();
+}
+
+pub(crate) fn param_list(p: &mut Parser<'_>) {
+    p.start_node(PARAM_LIST);
+    p.expect(L_PAREN, "`(`");
+    while !matches!(p.current(), R_PAREN | L_BRACE | EOF) {
+        if p.at(IDENT) {
+            param(p);
+        } else {
+            p.err_and_bump("expected parameter");
+        }
+        if !p.at(R_PAREN) && !p.expect(COMMA, "`,`") && !p.at(IDENT) {
+            break;
+        }
+    }
+    p.expect(R_PAREN, "`)`");
+    p.finish_node();
+}
+
+fn param(p: &mut Parser<'_>) {
+    p.start_node(PARAM);
+    if p.at_kw("required") && p.nth(1) == IDENT {
+        p.bump_kw();
+    }
+    if matches!(p.nth(1), IDENT | DOT) || (p.nth(1) == L_BRACK && p.nth(2) == R_BRACK) {
+        type_ref(p);
+    }
+    name(p);
+    if p.eat(EQ) && !expressions::expr(p) {
+        p.error("expected expression");
+    }
+    attributes(p);
+    p.finish_node();
+}
+
+/// Parses `name=value` attributes of script declarations.
+pub(crate) fn attributes(p: &mut Parser<'_>) {
+    while p.at(IDENT) && p.nth(1) == EQ {
+        p.start_node(ATTRIBUTE);
+        p.bump_remap(ATTR_NAME);
+        p.bump();
+        if !expressions::expr(p) {
+            p.error("expected attribute value");
+        }
+        p.finish_node();
+    }
+}
+
+/// Parses tag-in-script statements such as `lock name="x" { ... }`.
+fn script_tag_stmt(p: &mut Parser<'_>) {
+    p.start_node(SCRIPT_TAG_STMT);
+    p.bump_kw();
+    while !matches!(p.current(), SEMICOLON | L_BRACE | R_BRACE | EOF) {
+        if p.at(IDENT) && p.nth(1) == EQ {
+            attributes(p);
+        } else if !expressions::expr(p) {
+            p.err_and_bump("expected attribute");
+        }
+    }
+    if p.at(L_BRACE) {
+        block(p);
+    } else {
+        stmt_end(p);
+    }
+    p.finish_node();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, SyntaxKind::*, SyntaxNode};
+
+    fn script(text: &str) -> SyntaxNode {
+        let parse = parse(text);
+        assert!(parse.errors().is_empty(), "{:?}", parse.errors());
+        parse.syntax_node()
+    }
+
+    fn kinds(node: &SyntaxNode) -> Vec<crate::SyntaxKind> {
+        node.descendants()
+            .map(|it| it.kind())
+            .filter(|it| it.is_stmt())
+            .collect()
+    }
+
+    #[test]
+    fn test_component() {
+        let node = script(
+            "component extends=\"base\" accessors=true {\n\
+             property name=\"x\";\n\
+             property string y;\n\
+             public string function f(required string a, b = 1) output=false {\n\
+                 var c = a & b\n\
+                 return c;\n\
+             }\n\
+             }",
+        );
+        assert_eq!(
+            kinds(&node),
+            vec![
+                COMPONENT_DECL,
+                BLOCK,
+                PROPERTY_DECL,
+                PROPERTY_DECL,
+                FUNCTION_DECL,
+                BLOCK,
+                VAR_DECL,
+                RETURN_STMT
+            ]
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let node = script(
+            "component { function f() {\n\
+             if (a) { x = 1; } else if (b) x = 2; else { x = 3; }\n\
+             for (var i = 1; i <= 10; i++) {}\n\
+             for (k in s) {}\n\
+             while (x) break;\n\
+             do { x--; } while (x > 0);\n\
+             switch (x) { case 1: y = 1; break; default: y = 2; }\n\
+             try { z(); } catch (any e) { rethrow; } finally { }\n\
+             } }",
+        );
+        let kinds = kinds(&node);
+        for kind in [
+            IF_STMT,
+            FOR_STMT,
+            FOR_IN_STMT,
+            WHILE_STMT,
+            BREAK_STMT,
+            DO_WHILE_STMT,
+            SWITCH_STMT,
+            TRY_STMT,
+        ] {
+            assert!(kinds.contains(&kind), "{kind:?} missing from {kinds:?}");
+        }
+    }
+
+    #[test]
+    fn test_script_tag_statements() {
+        let node = script("<cfscript>lock name=\"x\" type=\"exclusive\" { a = 1; } param name=\"b\" default=1;</cfscript>");
+        assert_eq!(
+            kinds(&node),
+            vec![SCRIPT_TAG_STMT, BLOCK, EXPR_STMT, SCRIPT_TAG_STMT]
+        );
+    }
+
+    #[test]
+    fn test_keywords_as_variables() {
+        let node = script("<cfscript>throw = 1; param.x = 2;</cfscript>");
+        assert_eq!(kinds(&node), vec![EXPR_STMT, EXPR_STMT]);
+    }
+
+    #[test]
+    fn test_missing_semicolon() {
+        let parse = parse("<cfscript>a = 1 b = 2</cfscript>");
+        assert_eq!(parse.errors().len(), 1);
+        assert_eq!(parse.errors()[0].message, "expected `;`");
+    }
+}
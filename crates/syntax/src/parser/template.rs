@@ -0,0 +1,431 @@
+//! Template mode: markup text, CFML tags and `#expr#` output.
+//!
+//! Whether a tag has a body can't be decided locally (`<cfhttp>` may or may
+//! not be closed, and a forgotten `</cfif>` should not swallow the rest of the
+//! file), so [`pair_tags`] first scans the whole template and matches opening
+//! and closing tags. The parser then only consults the result.
+use rustc_hash::FxHashMap;
+
+use super::{expressions, script, Parser};
+use crate::{lexer, tags, SyntaxKind::*, TextRange};
+
+/// Where the body of an opening tag ends, and where its closing tag starts, if
+/// it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TagPairing {
+    pub(crate) body_end: usize,
+    pub(crate) close: Option<usize>,
+}
+
+/// Length of the CFML tag name following `<` at the start of `rest`, if it
+/// starts a CFML tag such as `<cfset` or `<cf_custom`.
+fn open_tag_name_len(rest: &str) -> Option<usize> {
+    let name = rest.strip_prefix('<')?;
+    tag_name_len(name)
+}
+
+fn close_tag_name_len(rest: &str) -> Option<usize> {
+    let name = rest.strip_prefix("</")?;
+    tag_name_len(name)
+}
+
+fn tag_name_len(name: &str) -> Option<usize> {
+    if !lexer::starts_with_ci(name, "cf") {
+        return None;
+    }
+    match name[2..].chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => (),
+        _ => return None,
+    }
+    let len = name
+        .char_indices()
+        .find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '_'))
+        .map_or(name.len(), |(idx, _)| idx);
+    Some(len)
+}
+
+/// Scans an opening tag starting at `start` and returns the offset after it,
+/// and whether the tag was self-closing.
+fn scan_tag_end(text: &str, start: usize) -> (usize, bool) {
+    let bytes = text.as_bytes();
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => i = lexer::skip_string(text, i),
+            b'#' => i = lexer::skip_hash(text, i),
+            b'<' if text[i..].starts_with("<!---") => i += lexer::cf_comment_len(&text[i..]),
+            b'<' => return (i, false),
+            b'>' => return (i + 1, bytes[i - 1] == b'/'),
+            _ => i += 1,
+        }
+    }
+    (bytes.len(), false)
+}
+
+/// Matches opening and closing CFML tags of a template.
+///
+/// The result contains an entry for every opening tag that has a body. Tags
+/// which may optionally be closed (custom tags, `<cfhttp>`, ...) but were not
+/// are left out and treated as void; tags which must be closed but were not
+/// get a body extending to wherever their parent ends.
+pub(crate) fn pair_tags(text: &str) -> FxHashMap<usize, TagPairing> {
+    let mut res = FxHashMap::default();
+    let mut stack: Vec<(String, usize)> = Vec::new();
+    let close_unmatched = |res: &mut FxHashMap<usize, TagPairing>, name: &str, start, end| {
+        if tags::requires_body(name) {
+            res.insert(
+                start,
+                TagPairing {
+                    body_end: end,
+                    close: None,
+                },
+            );
+        }
+    };
+
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        if !rest.starts_with('<') {
+            i += rest.find('<').unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("<!---") {
+            i += lexer::cf_comment_len(rest);
+        } else if let Some(name_len) = open_tag_name_len(rest) {
+            let name = rest[1..1 + name_len].to_ascii_lowercase();
+            let (end, self_closing) = scan_tag_end(text, i);
+            if self_closing || tags::is_void_tag(&name) {
+                i = end;
+            } else if name == "cfscript" {
+                stack.push((name, i));
+                i = lexer::find_ci(&text[end..], "</cfscript").map_or(text.len(), |it| end + it);
+            } else {
+                stack.push((name, i));
+                i = end;
+            }
+        } else if let Some(name_len) = close_tag_name_len(rest) {
+            let name = rest[2..2 + name_len].to_ascii_lowercase();
+            if let Some(idx) = stack.iter().rposition(|(it, _)| *it == name) {
+                for (unclosed, start) in stack.drain(idx + 1..) {
+                    close_unmatched(&mut res, &unclosed, start, i);
+                }
+                let (_, start) = stack.pop().unwrap();
+                res.insert(
+                    start,
+                    TagPairing {
+                        body_end: i,
+                        close: Some(i),
+                    },
+                );
+            }
+            i += rest.find('>').map_or(rest.len(), |it| it + 1);
+        } else {
+            i += 1;
+        }
+    }
+    for (unclosed, start) in stack {
+        close_unmatched(&mut res, &unclosed, start, text.len());
+    }
+    res
+}
+
+/// Parses template items until `end`.
+pub(crate) fn items(p: &mut Parser<'_>, end: usize, output: bool) {
+    while p.pos < end.min(p.text.len()) {
+        item(p, end, output);
+    }
+}
+
+fn item(p: &mut Parser<'_>, end: usize, output: bool) {
+    let rest = &p.text[p.pos..end];
+    if rest.starts_with("<!---") {
+        p.raw_token(CF_COMMENT, lexer::cf_comment_len(rest));
+    } else if open_tag_name_len(rest).is_some() {
+        tag(p, output);
+    } else if close_tag_name_len(rest).is_some() {
+        tag_close(p);
+    } else if output && rest.starts_with('#') && !rest.starts_with("##") {
+        expressions::hash_expr(p);
+    } else {
+        text(p, end, output);
+    }
+}
+
+fn text(p: &mut Parser<'_>, end: usize, output: bool) {
+    let rest = &p.text[p.pos..end];
+    let mut i = 0;
+    while i < rest.len() {
+        let tail = &rest[i..];
+        if i > 0
+            && (tail.starts_with("<!---")
+                || open_tag_name_len(tail).is_some()
+                || close_tag_name_len(tail).is_some())
+        {
+            break;
+        }
+        if output && tail.starts_with('#') {
+            if tail.starts_with("##") {
+                i += 2;
+                continue;
+            }
+            if i > 0 {
+                break;
+            }
+        }
+        let step = tail.find(['<', '#']).unwrap_or(tail.len());
+        i += step.max(tail.chars().next().map_or(1, char::len_utf8));
+    }
+    p.raw_token(TEXT, i);
+}
+
+fn tag_trivia(p: &mut Parser<'_>) {
+    loop {
+        let rest = p.rest();
+        if rest.starts_with("<!---") {
+            p.raw_token(CF_COMMENT, lexer::cf_comment_len(rest));
+            continue;
+        }
+        let len = rest
+            .bytes()
+            .position(|it| !it.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            break;
+        }
+        p.raw_token(WHITESPACE, len);
+    }
+}
+
+fn tag(p: &mut Parser<'_>, output: bool) {
+    let start = p.pos;
+    let name_len = open_tag_name_len(p.rest()).unwrap_or(0);
+    let name = p.text[start + 1..start + 1 + name_len].to_ascii_lowercase();
+
+    p.start_raw_node(TAG);
+    p.start_raw_node(TAG_OPEN);
+    p.raw_token(L_ANGLE, 1);
+    p.raw_token(TAG_NAME, name_len);
+    let self_closing = if tags::has_expression(&name) {
+        tag_expression(p)
+    } else {
+        attributes(p)
+    };
+    p.finish_node();
+
+    if !self_closing {
+        if let Some(pairing) = p.pairings.get(&start).copied() {
+            if name == "cfscript" {
+                let limit = p.limit;
+                p.limit = pairing.body_end;
+                script::statements(p, |_| false);
+                p.flush_trivia();
+                p.limit = limit;
+            } else {
+                items(p, pairing.body_end, output || tags::enables_output(&name));
+            }
+            if pairing.close == Some(p.pos) {
+                tag_close(p);
+            }
+        }
+    }
+    p.finish_node();
+}
+
+/// Parses the expression part of `<cfset ...>`, `<cfif ...>` and friends.
+fn tag_expression(p: &mut Parser<'_>) -> bool {
+    let in_tag = p.in_tag;
+    p.in_tag = true;
+    if !matches!(p.current(), R_ANGLE | SLASH_R_ANGLE | EOF) {
+        if p.at_kw("var") {
+            script::var_decl(p, false);
+        } else {
+            expressions::expr(p);
+        }
+    }
+    while !matches!(p.current(), R_ANGLE | SLASH_R_ANGLE | EOF) {
+        p.err_and_bump("unexpected token in tag");
+    }
+    let self_closing = p.at(SLASH_R_ANGLE);
+    if !p.eat(R_ANGLE) && !p.eat(SLASH_R_ANGLE) {
+        p.error("unterminated tag");
+    }
+    p.in_tag = in_tag;
+    self_closing
+}
+
+fn is_attr_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.' | '@' | '$')
+}
+
+/// Parses tag attributes up to and including the closing `>`. Returns `true`
+/// if the tag was self-closing.
+fn attributes(p: &mut Parser<'_>) -> bool {
+    loop {
+        tag_trivia(p);
+        let rest = p.rest();
+        if rest.starts_with("/>") {
+            p.raw_token(SLASH_R_ANGLE, 2);
+            return true;
+        }
+        if rest.starts_with('>') {
+            p.raw_token(R_ANGLE, 1);
+            return false;
+        }
+        let c = match rest.chars().next() {
+            Some('<') | None => {
+                p.error_at(TextRange::empty(p.pos as u32), "unterminated tag");
+                return false;
+            }
+            Some(c) => c,
+        };
+        if is_attr_name_char(c) {
+            attribute(p);
+        } else {
+            let range = TextRange::at(p.pos as u32, c.len_utf8() as u32);
+            p.error_at(range, "unexpected character in tag");
+            p.start_raw_node(ERROR);
+            p.raw_token(ERROR_TOKEN, c.len_utf8());
+            p.finish_node();
+        }
+    }
+}
+
+fn attribute(p: &mut Parser<'_>) {
+    p.start_raw_node(ATTRIBUTE);
+    let rest = p.rest();
+    let name_len = rest
+        .char_indices()
+        .find(|&(_, c)| !is_attr_name_char(c))
+        .map_or(rest.len(), |(idx, _)| idx);
+    p.raw_token(ATTR_NAME, name_len);
+
+    let rest = p.rest();
+    let ws = rest
+        .bytes()
+        .position(|it| !it.is_ascii_whitespace())
+        .unwrap_or(rest.len());
+    if rest[ws..].starts_with('=') {
+        if ws > 0 {
+            p.raw_token(WHITESPACE, ws);
+        }
+        p.raw_token(EQ, 1);
+        tag_trivia(p);
+        attribute_value(p);
+    }
+    p.finish_node();
+}
+
+fn attribute_value(p: &mut Parser<'_>) {
+    let rest = p.rest();
+    match rest.chars().next() {
+        Some('"' | '\'') => expressions::string(p),
+        Some('#') => expressions::hash_expr(p),
+        _ => {
+            let len = rest
+                .char_indices()
+                .find(|&(idx, c)| c.is_whitespace() || c == '>' || rest[idx..].starts_with("/>"))
+                .map_or(rest.len(), |(idx, _)| idx);
+            if len == 0 {
+                p.error_at(TextRange::empty(p.pos as u32), "expected attribute value");
+            } else {
+                p.raw_token(ATTR_VALUE, len);
+            }
+        }
+    }
+}
+
+fn tag_close(p: &mut Parser<'_>) {
+    let name_len = close_tag_name_len(p.rest()).unwrap_or(0);
+    p.start_raw_node(TAG_CLOSE);
+    p.raw_token(L_ANGLE_SLASH, 2);
+    p.raw_token(TAG_NAME, name_len);
+    tag_trivia(p);
+    if p.rest().starts_with('>') {
+        p.raw_token(R_ANGLE, 1);
+    } else {
+        p.error_at(TextRange::empty(p.pos as u32), "expected `>`");
+    }
+    p.finish_node();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairings(text: &str) -> Vec<(usize, TagPairing)> {
+        let mut res: Vec<_> = pair_tags(text).into_iter().collect();
+        res.sort_by_key(|(it, _)| *it);
+        res
+    }
+
+    #[test]
+    fn test_pair_tags() {
+        let text = "<cfif a><cfset x = 1></cfif>";
+        assert_eq!(
+            pairings(text),
+            vec![(
+                0,
+                TagPairing {
+                    body_end: 21,
+                    close: Some(21)
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_pair_tags_optional_body() {
+        // `<cfhttp>` is not closed here, so it has no body.
+        let text = "<cfoutput><cfhttp url=\"x\"></cfoutput>";
+        assert_eq!(
+            pairings(text),
+            vec![(
+                0,
+                TagPairing {
+                    body_end: 26,
+                    close: Some(26)
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_pair_tags_unclosed() {
+        let text = "<cfoutput><cfif a></cfoutput>";
+        assert_eq!(
+            pairings(text),
+            vec![
+                (
+                    0,
+                    TagPairing {
+                        body_end: 18,
+                        close: Some(18)
+                    }
+                ),
+                (
+                    10,
+                    TagPairing {
+                        body_end: 18,
+                        close: None
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pair_tags_skips_strings_and_script() {
+        let text = "<cfset x = \"</cfif>\"><cfscript>if (a < b) {}</cfscript>";
+        assert_eq!(
+            pairings(text),
+            vec![(
+                21,
+                TagPairing {
+                    body_end: 44,
+                    close: Some(44)
+                }
+            )]
+        );
+    }
+}
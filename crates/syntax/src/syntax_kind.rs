@@ -0,0 +1,236 @@
+//! The kinds of tokens and nodes in a CFML syntax tree.
+
+/// Every token and node in the tree is tagged with a `SyntaxKind`.
+///
+/// CFML mixes tag markup and script, so the set of kinds covers both. Keywords
+/// share a single [`SyntaxKind::KEYWORD`] kind; clients compare the token text
+/// (case-insensitively) to tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(non_camel_case_types)]
+#[repr(u16)]
+pub enum SyntaxKind {
+    // Trivia
+    WHITESPACE,
+    /// `// ...` or `/* ... */`
+    COMMENT,
+    /// `<!--- ... --->`
+    CF_COMMENT,
+
+    // Template tokens
+    /// Markup or plain output text between tags.
+    TEXT,
+    /// `<` starting a tag.
+    L_ANGLE,
+    /// `</` starting a closing tag.
+    L_ANGLE_SLASH,
+    /// `>` ending a tag.
+    R_ANGLE,
+    /// `/>` ending a self-closing tag.
+    SLASH_R_ANGLE,
+    TAG_NAME,
+    ATTR_NAME,
+    /// An unquoted attribute value.
+    ATTR_VALUE,
+
+    // Script tokens
+    IDENT,
+    KEYWORD,
+    NUMBER,
+    /// `"` or `'` delimiting a string.
+    QUOTE,
+    STRING_TEXT,
+    HASH,
+    L_PAREN,
+    R_PAREN,
+    L_BRACE,
+    R_BRACE,
+    L_BRACK,
+    R_BRACK,
+    COMMA,
+    SEMICOLON,
+    COLON,
+    DOT,
+    QUESTION,
+    QUESTION_DOT,
+    ELVIS,
+    EQ,
+    EQ2,
+    EQ3,
+    NEQ,
+    NEQ2,
+    LT,
+    GT,
+    LTEQ,
+    GTEQ,
+    PLUS,
+    MINUS,
+    STAR,
+    SLASH,
+    BACKSLASH,
+    PERCENT,
+    CARET,
+    AMP,
+    AMP2,
+    PIPE2,
+    BANG,
+    PLUS2,
+    MINUS2,
+    PLUSEQ,
+    MINUSEQ,
+    STAREQ,
+    SLASHEQ,
+    PERCENTEQ,
+    AMPEQ,
+    FAT_ARROW,
+    /// A character the lexer does not understand.
+    ERROR_TOKEN,
+    /// End of input; never stored in a tree.
+    EOF,
+
+    // Nodes
+    SOURCE_FILE,
+    /// A complete tag element: open tag, body and closing tag.
+    TAG,
+    TAG_OPEN,
+    TAG_CLOSE,
+    ATTRIBUTE,
+    /// `#expr#` inside output text, strings or attribute values.
+    HASH_EXPR,
+
+    COMPONENT_DECL,
+    FUNCTION_DECL,
+    PARAM_LIST,
+    PARAM,
+    PROPERTY_DECL,
+    IMPORT_DECL,
+    NAME,
+    TYPE,
+    PATH,
+
+    BLOCK,
+    VAR_DECL,
+    EXPR_STMT,
+    IF_STMT,
+    ELSE_CLAUSE,
+    FOR_STMT,
+    FOR_IN_STMT,
+    WHILE_STMT,
+    DO_WHILE_STMT,
+    SWITCH_STMT,
+    CASE_CLAUSE,
+    DEFAULT_CLAUSE,
+    TRY_STMT,
+    CATCH_CLAUSE,
+    FINALLY_CLAUSE,
+    RETURN_STMT,
+    BREAK_STMT,
+    CONTINUE_STMT,
+    THROW_STMT,
+    /// Tag-in-script statements such as `lock name="x" { ... }` or `param x;`.
+    SCRIPT_TAG_STMT,
+    EMPTY_STMT,
+
+    LITERAL,
+    STRING,
+    NAME_REF,
+    FIELD_EXPR,
+    INDEX_EXPR,
+    CALL_EXPR,
+    ARG_LIST,
+    ARG,
+    NEW_EXPR,
+    BIN_EXPR,
+    PREFIX_EXPR,
+    POSTFIX_EXPR,
+    TERNARY_EXPR,
+    ASSIGN_EXPR,
+    PAREN_EXPR,
+    STRUCT_LIT,
+    STRUCT_FIELD,
+    ARRAY_LIT,
+    CLOSURE_EXPR,
+    ARROW_EXPR,
+    ERROR,
+}
+
+use SyntaxKind::*;
+
+impl SyntaxKind {
+    pub fn is_trivia(self) -> bool {
+        matches!(self, WHITESPACE | COMMENT | CF_COMMENT)
+    }
+
+    pub fn is_expr(self) -> bool {
+        matches!(
+            self,
+            LITERAL
+                | STRING
+                | NAME_REF
+                | FIELD_EXPR
+                | INDEX_EXPR
+                | CALL_EXPR
+                | NEW_EXPR
+                | BIN_EXPR
+                | PREFIX_EXPR
+                | POSTFIX_EXPR
+                | TERNARY_EXPR
+                | ASSIGN_EXPR
+                | PAREN_EXPR
+                | STRUCT_LIT
+                | ARRAY_LIT
+                | CLOSURE_EXPR
+                | ARROW_EXPR
+                | HASH_EXPR
+        )
+    }
+
+    pub fn is_stmt(self) -> bool {
+        matches!(
+            self,
+            COMPONENT_DECL
+                | FUNCTION_DECL
+                | PROPERTY_DECL
+                | IMPORT_DECL
+                | BLOCK
+                | VAR_DECL
+                | EXPR_STMT
+                | IF_STMT
+                | FOR_STMT
+                | FOR_IN_STMT
+                | WHILE_STMT
+                | DO_WHILE_STMT
+                | SWITCH_STMT
+                | TRY_STMT
+                | RETURN_STMT
+                | BREAK_STMT
+                | CONTINUE_STMT
+                | THROW_STMT
+                | SCRIPT_TAG_STMT
+                | EMPTY_STMT
+        )
+    }
+
+    /// Returns `true` for assignment operators such as `=` and `&=`.
+    pub fn is_assign_op(self) -> bool {
+        matches!(
+            self,
+            EQ | PLUSEQ | MINUSEQ | STAREQ | SLASHEQ | PERCENTEQ | AMPEQ
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_classes() {
+        assert!(WHITESPACE.is_trivia());
+        assert!(CF_COMMENT.is_trivia());
+        assert!(!IDENT.is_trivia());
+        assert!(CALL_EXPR.is_expr());
+        assert!(IF_STMT.is_stmt());
+        assert!(AMPEQ.is_assign_op());
+        assert!(!EQ2.is_assign_op());
+    }
+}
@@ -0,0 +1,814 @@
+//! A lossless syntax tree.
+//!
+//! The tree is split in two layers, in the spirit of rowan: an immutable
+//! "green" tree which only knows kinds, text and lengths, and a "red" layer of
+//! cheap handles ([`SyntaxNode`] / [`SyntaxToken`]) which add absolute offsets
+//! and parent pointers on top of it. Concatenating every token of a tree yields
+//! exactly the source text it was parsed from.
+use std::{fmt, hash, sync::Arc};
+
+use crate::{SyntaxKind, TextRange, TextSize};
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    text_len: TextSize,
+    children: Vec<GreenElement>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: Box<str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+        GreenNode {
+            kind,
+            text_len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text_len(&self) -> TextSize {
+        self.text_len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    fn write_text(&self, buf: &mut String) {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => node.write_text(buf),
+                GreenElement::Token(token) => buf.push_str(&token.text),
+            }
+        }
+    }
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> GreenToken {
+        GreenToken {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(it) => it.kind,
+            GreenElement::Token(it) => it.kind,
+        }
+    }
+
+    pub fn text_len(&self) -> TextSize {
+        match self {
+            GreenElement::Node(it) => it.text_len,
+            GreenElement::Token(it) => it.text.len() as TextSize,
+        }
+    }
+}
+
+/// A node of the tree with its absolute position and parent.
+#[derive(Clone)]
+pub struct SyntaxNode(Arc<NodeData>);
+
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    index: usize,
+    offset: TextSize,
+}
+
+/// A leaf of the tree with its absolute position and parent.
+#[derive(Clone)]
+pub struct SyntaxToken {
+    parent: SyntaxNode,
+    green: Arc<GreenToken>,
+    index: usize,
+    offset: TextSize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NodeOrToken<N, T> {
+    Node(N),
+    Token(T),
+}
+
+pub type SyntaxElement = NodeOrToken<SyntaxNode, SyntaxToken>;
+
+/// Result of looking up the token at an offset: an offset sitting exactly on
+/// the boundary of two tokens touches both of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenAtOffset<T> {
+    None,
+    Single(T),
+    Between(T, T),
+}
+
+impl<T> TokenAtOffset<T> {
+    pub fn left_biased(self) -> Option<T> {
+        match self {
+            TokenAtOffset::None => None,
+            TokenAtOffset::Single(it) => Some(it),
+            TokenAtOffset::Between(it, _) => Some(it),
+        }
+    }
+
+    pub fn right_biased(self) -> Option<T> {
+        match self {
+            TokenAtOffset::None => None,
+            TokenAtOffset::Single(it) => Some(it),
+            TokenAtOffset::Between(_, it) => Some(it),
+        }
+    }
+}
+
+impl<T> Iterator for TokenAtOffset<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match std::mem::replace(self, TokenAtOffset::None) {
+            TokenAtOffset::None => None,
+            TokenAtOffset::Single(it) => Some(it),
+            TokenAtOffset::Between(left, right) => {
+                *self = TokenAtOffset::Single(right);
+                Some(left)
+            }
+        }
+    }
+}
+
+/// Event produced by [`SyntaxNode::preorder`].
+#[derive(Debug, Clone)]
+pub enum WalkEvent<T> {
+    Enter(T),
+    Leave(T),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Arc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Arc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    fn new_child(
+        green: Arc<GreenNode>,
+        parent: SyntaxNode,
+        index: usize,
+        offset: TextSize,
+    ) -> Self {
+        SyntaxNode(Arc::new(NodeData {
+            green,
+            parent: Some(parent),
+            index,
+            offset,
+        }))
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        TextRange::at(self.0.offset, self.0.green.text_len)
+    }
+
+    /// Returns the source text covered by this node.
+    pub fn text(&self) -> String {
+        let mut buf = String::with_capacity(self.0.green.text_len as usize);
+        self.0.green.write_text(&mut buf);
+        buf
+    }
+
+    /// Index of this node among its parent's children (tokens included).
+    pub fn index(&self) -> usize {
+        self.0.index
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// Iterates over this node and all of its ancestors, innermost first.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    fn element_at(&self, index: usize, offset: TextSize) -> Option<SyntaxElement> {
+        let green = self.0.green.children.get(index)?;
+        Some(match green {
+            GreenElement::Node(node) => NodeOrToken::Node(SyntaxNode::new_child(
+                node.clone(),
+                self.clone(),
+                index,
+                offset,
+            )),
+            GreenElement::Token(token) => NodeOrToken::Token(SyntaxToken {
+                parent: self.clone(),
+                green: token.clone(),
+                index,
+                offset,
+            }),
+        })
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let node = self.clone();
+        let mut offset = self.0.offset;
+        (0..self.0.green.children.len()).filter_map(move |index| {
+            let element = node.element_at(index, offset)?;
+            offset += node.0.green.children[index].text_len();
+            Some(element)
+        })
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens()
+            .filter_map(NodeOrToken::into_node)
+    }
+
+    pub fn first_child_or_token(&self) -> Option<SyntaxElement> {
+        self.element_at(0, self.0.offset)
+    }
+
+    pub fn last_child_or_token(&self) -> Option<SyntaxElement> {
+        let children = &self.0.green.children;
+        let last = children.last()?;
+        self.element_at(
+            children.len() - 1,
+            self.text_range().end() - last.text_len(),
+        )
+    }
+
+    pub fn first_child(&self) -> Option<SyntaxNode> {
+        self.children().next()
+    }
+
+    pub fn last_child(&self) -> Option<SyntaxNode> {
+        self.children().last()
+    }
+
+    pub fn next_sibling_or_token(&self) -> Option<SyntaxElement> {
+        let parent = self.parent()?;
+        parent.element_at(self.0.index + 1, self.text_range().end())
+    }
+
+    pub fn prev_sibling_or_token(&self) -> Option<SyntaxElement> {
+        let parent = self.parent()?;
+        let index = self.0.index.checked_sub(1)?;
+        let len = parent.0.green.children[index].text_len();
+        parent.element_at(index, self.0.offset - len)
+    }
+
+    pub fn next_sibling(&self) -> Option<SyntaxNode> {
+        std::iter::successors(self.next_sibling_or_token(), |it| {
+            it.next_sibling_or_token()
+        })
+        .find_map(NodeOrToken::into_node)
+    }
+
+    pub fn prev_sibling(&self) -> Option<SyntaxNode> {
+        std::iter::successors(self.prev_sibling_or_token(), |it| {
+            it.prev_sibling_or_token()
+        })
+        .find_map(NodeOrToken::into_node)
+    }
+
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        self.descendants_with_tokens()
+            .find_map(NodeOrToken::into_token)
+    }
+
+    pub fn last_token(&self) -> Option<SyntaxToken> {
+        let mut child = self.last_child_or_token();
+        while let Some(element) = child {
+            if let Some(token) = element.last_token_in() {
+                return Some(token);
+            }
+            child = element.prev_sibling_or_token();
+        }
+        None
+    }
+
+    /// Walks the subtree rooted at this node, entering and leaving every
+    /// element in source order.
+    pub fn preorder_with_tokens(&self) -> impl Iterator<Item = WalkEvent<SyntaxElement>> {
+        let mut stack: Vec<(SyntaxElement, bool)> = vec![(NodeOrToken::Node(self.clone()), false)];
+        std::iter::from_fn(move || {
+            let (element, entered) = stack.pop()?;
+            if entered {
+                return Some(WalkEvent::Leave(element));
+            }
+            stack.push((element.clone(), true));
+            if let NodeOrToken::Node(node) = &element {
+                let children: Vec<_> = node.children_with_tokens().collect();
+                stack.extend(children.into_iter().rev().map(|it| (it, false)));
+            }
+            Some(WalkEvent::Enter(element))
+        })
+    }
+
+    /// Walks the nodes of the subtree rooted at this node.
+    pub fn preorder(&self) -> impl Iterator<Item = WalkEvent<SyntaxNode>> {
+        self.preorder_with_tokens().filter_map(|event| match event {
+            WalkEvent::Enter(NodeOrToken::Node(it)) => Some(WalkEvent::Enter(it)),
+            WalkEvent::Leave(NodeOrToken::Node(it)) => Some(WalkEvent::Leave(it)),
+            _ => None,
+        })
+    }
+
+    /// Iterates over this node and all nodes below it, in source order.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.preorder().filter_map(|event| match event {
+            WalkEvent::Enter(it) => Some(it),
+            WalkEvent::Leave(_) => None,
+        })
+    }
+
+    pub fn descendants_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        self.preorder_with_tokens().filter_map(|event| match event {
+            WalkEvent::Enter(it) => Some(it),
+            WalkEvent::Leave(_) => None,
+        })
+    }
+
+    /// Iterates over every token below this node.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.descendants_with_tokens()
+            .filter_map(NodeOrToken::into_token)
+    }
+
+    /// Finds the token(s) touching `offset`.
+    pub fn token_at_offset(&self, offset: TextSize) -> TokenAtOffset<SyntaxToken> {
+        fn collect(node: &SyntaxNode, offset: TextSize, acc: &mut Vec<SyntaxToken>) {
+            for child in node.children_with_tokens() {
+                let range = child.text_range();
+                if range.start() > offset {
+                    break;
+                }
+                if !range.contains_inclusive(offset) {
+                    continue;
+                }
+                match child {
+                    NodeOrToken::Node(it) => collect(&it, offset, acc),
+                    NodeOrToken::Token(it) if !range.is_empty() => acc.push(it),
+                    NodeOrToken::Token(_) => (),
+                }
+            }
+        }
+        if !self.text_range().contains_inclusive(offset) {
+            return TokenAtOffset::None;
+        }
+        let mut acc = Vec::new();
+        collect(self, offset, &mut acc);
+        let mut acc = acc.into_iter();
+        match (acc.next(), acc.last()) {
+            (None, _) => TokenAtOffset::None,
+            (Some(it), None) => TokenAtOffset::Single(it),
+            (Some(left), Some(right)) => TokenAtOffset::Between(left, right),
+        }
+    }
+
+    /// Returns the innermost element which fully covers `range`.
+    pub fn covering_element(&self, range: TextRange) -> SyntaxElement {
+        let mut res = NodeOrToken::Node(self.clone());
+        loop {
+            let node = match &res {
+                NodeOrToken::Node(it) => it.clone(),
+                NodeOrToken::Token(_) => return res,
+            };
+            let mut candidates = node
+                .children_with_tokens()
+                .filter(|child| child.text_range().contains_range(range));
+            // An empty range on a boundary touches two children, prefer the right one.
+            let next = if range.is_empty() {
+                candidates.last()
+            } else {
+                candidates.next()
+            };
+            match next {
+                Some(child) => res = child,
+                None => return res,
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl hash::Hash for SyntaxNode {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0.green).hash(state);
+        self.0.offset.hash(state);
+    }
+}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?}", self.kind(), self.text_range())
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        TextRange::at(self.offset, self.green.text.len() as TextSize)
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// Iterates over the ancestors of this token, innermost first.
+    pub fn parent_ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.parent.ancestors()
+    }
+
+    pub fn next_sibling_or_token(&self) -> Option<SyntaxElement> {
+        self.parent
+            .element_at(self.index + 1, self.text_range().end())
+    }
+
+    pub fn prev_sibling_or_token(&self) -> Option<SyntaxElement> {
+        let index = self.index.checked_sub(1)?;
+        let len = self.parent.0.green.children[index].text_len();
+        self.parent.element_at(index, self.offset - len)
+    }
+
+    /// Returns the next token in the whole tree, if any.
+    pub fn next_token(&self) -> Option<SyntaxToken> {
+        let mut current = NodeOrToken::Token(self.clone());
+        loop {
+            let mut sibling = current.next_sibling_or_token();
+            while sibling.is_none() {
+                current = NodeOrToken::Node(current.parent()?);
+                sibling = current.next_sibling_or_token();
+            }
+            let sibling = sibling?;
+            match sibling.first_token_in() {
+                Some(token) => return Some(token),
+                None => current = sibling,
+            }
+        }
+    }
+
+    /// Returns the previous token in the whole tree, if any.
+    pub fn prev_token(&self) -> Option<SyntaxToken> {
+        let mut current = NodeOrToken::Token(self.clone());
+        loop {
+            let mut sibling = current.prev_sibling_or_token();
+            while sibling.is_none() {
+                current = NodeOrToken::Node(current.parent()?);
+                sibling = current.prev_sibling_or_token();
+            }
+            let sibling = sibling?;
+            match sibling.last_token_in() {
+                Some(token) => return Some(token),
+                None => current = sibling,
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &SyntaxToken) -> bool {
+        self.parent == other.parent && self.index == other.index
+    }
+}
+
+impl Eq for SyntaxToken {}
+
+impl hash::Hash for SyntaxToken {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.parent.hash(state);
+        self.index.hash(state);
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}@{:?} {:?}",
+            self.kind(),
+            self.text_range(),
+            self.text()
+        )
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
+
+impl<N, T> NodeOrToken<N, T> {
+    pub fn into_node(self) -> Option<N> {
+        match self {
+            NodeOrToken::Node(it) => Some(it),
+            NodeOrToken::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<T> {
+        match self {
+            NodeOrToken::Node(_) => None,
+            NodeOrToken::Token(it) => Some(it),
+        }
+    }
+
+    pub fn as_node(&self) -> Option<&N> {
+        match self {
+            NodeOrToken::Node(it) => Some(it),
+            NodeOrToken::Token(_) => None,
+        }
+    }
+
+    pub fn as_token(&self) -> Option<&T> {
+        match self {
+            NodeOrToken::Node(_) => None,
+            NodeOrToken::Token(it) => Some(it),
+        }
+    }
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            NodeOrToken::Node(it) => it.kind(),
+            NodeOrToken::Token(it) => it.kind(),
+        }
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            NodeOrToken::Node(it) => it.text_range(),
+            NodeOrToken::Token(it) => it.text_range(),
+        }
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        match self {
+            NodeOrToken::Node(it) => it.parent(),
+            NodeOrToken::Token(it) => Some(it.parent()),
+        }
+    }
+
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        let first = match self {
+            NodeOrToken::Node(it) => Some(it.clone()),
+            NodeOrToken::Token(it) => Some(it.parent()),
+        };
+        std::iter::successors(first, SyntaxNode::parent)
+    }
+
+    pub fn next_sibling_or_token(&self) -> Option<SyntaxElement> {
+        match self {
+            NodeOrToken::Node(it) => it.next_sibling_or_token(),
+            NodeOrToken::Token(it) => it.next_sibling_or_token(),
+        }
+    }
+
+    pub fn prev_sibling_or_token(&self) -> Option<SyntaxElement> {
+        match self {
+            NodeOrToken::Node(it) => it.prev_sibling_or_token(),
+            NodeOrToken::Token(it) => it.prev_sibling_or_token(),
+        }
+    }
+
+    fn first_token_in(&self) -> Option<SyntaxToken> {
+        match self {
+            NodeOrToken::Node(it) => it.first_token(),
+            NodeOrToken::Token(it) => Some(it.clone()),
+        }
+    }
+
+    fn last_token_in(&self) -> Option<SyntaxToken> {
+        match self {
+            NodeOrToken::Node(it) => it.last_token(),
+            NodeOrToken::Token(it) => Some(it.clone()),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeOrToken::Node(it) => fmt::Display::fmt(it, f),
+            NodeOrToken::Token(it) => fmt::Display::fmt(it, f),
+        }
+    }
+}
+
+impl From<SyntaxNode> for SyntaxElement {
+    fn from(node: SyntaxNode) -> SyntaxElement {
+        NodeOrToken::Node(node)
+    }
+}
+
+impl From<SyntaxToken> for SyntaxElement {
+    fn from(token: SyntaxToken) -> SyntaxElement {
+        NodeOrToken::Token(token)
+    }
+}
+
+/// Builds a green tree bottom-up from a flat sequence of events.
+#[derive(Default, Debug)]
+pub struct GreenNodeBuilder {
+    parents: Vec<(SyntaxKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+/// A position in the builder where a node can later be started with
+/// [`GreenNodeBuilder::start_node_at`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+impl GreenNodeBuilder {
+    pub fn start_node(&mut self, kind: SyntaxKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    pub fn token(&mut self, kind: SyntaxKind, text: &str) {
+        self.children
+            .push(GreenElement::Token(Arc::new(GreenToken::new(kind, text))));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("unbalanced finish_node");
+        let children = self.children.split_off(first_child);
+        self.children
+            .push(GreenElement::Node(Arc::new(GreenNode::new(kind, children))));
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    /// Wraps every element added since `checkpoint` into a new node of `kind`.
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        let Checkpoint(first_child) = checkpoint;
+        assert!(
+            first_child <= self.children.len(),
+            "checkpoint no longer valid, was finish_node called early?"
+        );
+        if let Some(&(_, parent_first_child)) = self.parents.last() {
+            assert!(
+                first_child >= parent_first_child,
+                "checkpoint no longer valid, was an unmatched start_node called?"
+            );
+        }
+        self.parents.push((kind, first_child));
+    }
+
+    pub fn finish(mut self) -> Arc<GreenNode> {
+        assert!(self.parents.is_empty(), "unbalanced start_node");
+        assert_eq!(self.children.len(), 1, "expected a single root node");
+        match self.children.pop() {
+            Some(GreenElement::Node(node)) => node,
+            _ => panic!("expected a root node"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyntaxKind::*;
+
+    fn sample() -> SyntaxNode {
+        // (a + b)
+        let mut builder = GreenNodeBuilder::default();
+        builder.start_node(SOURCE_FILE);
+        let checkpoint = builder.checkpoint();
+        builder.start_node(NAME_REF);
+        builder.token(IDENT, "a");
+        builder.finish_node();
+        builder.token(WHITESPACE, " ");
+        builder.token(PLUS, "+");
+        builder.token(WHITESPACE, " ");
+        builder.start_node(NAME_REF);
+        builder.token(IDENT, "bc");
+        builder.finish_node();
+        builder.start_node_at(checkpoint, BIN_EXPR);
+        builder.finish_node();
+        builder.finish_node();
+        SyntaxNode::new_root(builder.finish())
+    }
+
+    #[test]
+    fn test_tree_text_and_ranges() {
+        let root = sample();
+        assert_eq!(root.text(), "a + bc");
+        let bin = root.first_child().unwrap();
+        assert_eq!(bin.kind(), BIN_EXPR);
+        assert_eq!(bin.text_range(), TextRange::new(0, 6));
+        let names: Vec<_> = bin.children().map(|it| it.text()).collect();
+        assert_eq!(names, vec!["a", "bc"]);
+        let rhs = bin.last_child().unwrap();
+        assert_eq!(rhs.text_range(), TextRange::new(4, 6));
+        assert_eq!(rhs.parent(), Some(bin.clone()));
+        assert_eq!(rhs.prev_sibling(), bin.first_child());
+    }
+
+    #[test]
+    fn test_token_navigation() {
+        let root = sample();
+        let first = root.first_token().unwrap();
+        assert_eq!(first.text(), "a");
+        let kinds: Vec<_> = std::iter::successors(Some(first), |it| it.next_token())
+            .map(|it| it.kind())
+            .collect();
+        assert_eq!(kinds, vec![IDENT, WHITESPACE, PLUS, WHITESPACE, IDENT]);
+        let last = root.last_token().unwrap();
+        assert_eq!(last.text(), "bc");
+        assert_eq!(last.prev_token().unwrap().kind(), WHITESPACE);
+    }
+
+    #[test]
+    fn test_token_at_offset() {
+        let root = sample();
+        match root.token_at_offset(1) {
+            TokenAtOffset::Between(left, right) => {
+                assert_eq!(left.text(), "a");
+                assert_eq!(right.kind(), WHITESPACE);
+            }
+            it => panic!("unexpected {it:?}"),
+        }
+        assert_eq!(root.token_at_offset(5).right_biased().unwrap().text(), "bc");
+        assert_eq!(root.token_at_offset(42), TokenAtOffset::None);
+    }
+
+    #[test]
+    fn test_covering_element() {
+        let root = sample();
+        let element = root.covering_element(TextRange::new(4, 6));
+        assert_eq!(element.kind(), IDENT);
+        let element = root.covering_element(TextRange::new(0, 3));
+        assert_eq!(element.kind(), BIN_EXPR);
+    }
+
+    #[test]
+    fn test_descendants() {
+        let root = sample();
+        let kinds: Vec<_> = root.descendants().map(|it| it.kind()).collect();
+        assert_eq!(kinds, vec![SOURCE_FILE, BIN_EXPR, NAME_REF, NAME_REF]);
+    }
+}
@@ -0,0 +1,161 @@
+//! Structural facts about CFML tags the parser needs to build a tree: which
+//! tags never have a body, which always do, and which take an expression
+//! instead of attributes.
+
+/// Tags which never have a body or closing tag.
+const VOID_TAGS: &[&str] = &[
+    "cfabort",
+    "cfargument",
+    "cfbreak",
+    "cfcontent",
+    "cfcontinue",
+    "cfcookie",
+    "cfdump",
+    "cfelse",
+    "cfelseif",
+    "cfexit",
+    "cfflush",
+    "cfheader",
+    "cfhttpparam",
+    "cfimport",
+    "cfinclude",
+    "cfinvokeargument",
+    "cflocation",
+    "cflog",
+    "cfmailparam",
+    "cfobject",
+    "cfparam",
+    "cfprocparam",
+    "cfprocresult",
+    "cfproperty",
+    "cfqueryparam",
+    "cfrethrow",
+    "cfreturn",
+    "cfset",
+    "cfsetting",
+    "cfthrow",
+    "cfzipparam",
+];
+
+/// Tags which must be closed.
+const BODY_TAGS: &[&str] = &[
+    "cfcase",
+    "cfcatch",
+    "cfcomponent",
+    "cfdefaultcase",
+    "cfdocument",
+    "cffinally",
+    "cfform",
+    "cffunction",
+    "cfif",
+    "cfinterface",
+    "cflock",
+    "cfloop",
+    "cfmail",
+    "cfoutput",
+    "cfquery",
+    "cfsavecontent",
+    "cfscript",
+    "cfsilent",
+    "cfstoredproc",
+    "cfswitch",
+    "cftimer",
+    "cftry",
+    "cfxml",
+];
+
+/// Tags whose contents are a CFML expression rather than attributes.
+const EXPRESSION_TAGS: &[&str] = &["cfelseif", "cfif", "cfreturn", "cfset"];
+
+/// Tags inside which `#expr#` in text is evaluated.
+const OUTPUT_TAGS: &[&str] = &["cfmail", "cfoutput", "cfquery"];
+
+/// Tag-like statements available in cfscript, e.g. `lock name="x" { ... }`.
+const SCRIPT_TAG_STATEMENTS: &[&str] = &[
+    "abort",
+    "content",
+    "cookie",
+    "directory",
+    "document",
+    "dump",
+    "execute",
+    "exit",
+    "file",
+    "flush",
+    "header",
+    "http",
+    "httpparam",
+    "image",
+    "include",
+    "invoke",
+    "invokeargument",
+    "location",
+    "lock",
+    "log",
+    "loop",
+    "mail",
+    "mailparam",
+    "module",
+    "param",
+    "pdf",
+    "procparam",
+    "procresult",
+    "query",
+    "queryparam",
+    "savecontent",
+    "setting",
+    "silent",
+    "storedproc",
+    "thread",
+    "timer",
+    "trace",
+    "transaction",
+    "wddx",
+    "zip",
+    "zipparam",
+];
+
+fn contains_ci(list: &[&str], name: &str) -> bool {
+    list.iter().any(|it| it.eq_ignore_ascii_case(name))
+}
+
+/// Returns `true` if the tag never has a body, e.g. `<cfset>`.
+pub fn is_void_tag(name: &str) -> bool {
+    contains_ci(VOID_TAGS, name)
+}
+
+/// Returns `true` if the tag must always be closed, e.g. `<cfif>`.
+pub fn requires_body(name: &str) -> bool {
+    contains_ci(BODY_TAGS, name)
+}
+
+/// Returns `true` if the tag contains an expression, e.g. `<cfif a EQ b>`.
+pub fn has_expression(name: &str) -> bool {
+    contains_ci(EXPRESSION_TAGS, name)
+}
+
+/// Returns `true` if `#expr#` in the text of the tag's body is evaluated.
+pub fn enables_output(name: &str) -> bool {
+    contains_ci(OUTPUT_TAGS, name)
+}
+
+/// Returns `true` if `name` introduces a tag-in-script statement.
+pub fn is_script_tag_statement(name: &str) -> bool {
+    contains_ci(SCRIPT_TAG_STATEMENTS, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_classes() {
+        assert!(is_void_tag("CFSET"));
+        assert!(!is_void_tag("cfif"));
+        assert!(requires_body("cfFunction"));
+        assert!(!requires_body("cfhttp"));
+        assert!(has_expression("cfif"));
+        assert!(enables_output("cfquery"));
+        assert!(is_script_tag_statement("lock"));
+    }
+}
@@ -0,0 +1,125 @@
+//! Byte offsets and ranges into source text.
+use std::{fmt, ops};
+
+/// A byte offset into a source file.
+pub type TextSize = u32;
+
+/// A half-open range of bytes, `start..end`.
+#[derive(Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextRange {
+    start: TextSize,
+    end: TextSize,
+}
+
+impl TextRange {
+    /// Creates a new range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `end < start`.
+    pub fn new(start: TextSize, end: TextSize) -> TextRange {
+        assert!(start <= end, "invalid range {start}..{end}");
+        TextRange { start, end }
+    }
+
+    /// Creates a range starting at `offset` spanning `len` bytes.
+    pub fn at(offset: TextSize, len: TextSize) -> TextRange {
+        TextRange::new(offset, offset + len)
+    }
+
+    /// Creates an empty range at `offset`.
+    pub fn empty(offset: TextSize) -> TextRange {
+        TextRange::new(offset, offset)
+    }
+
+    pub fn start(self) -> TextSize {
+        self.start
+    }
+
+    pub fn end(self) -> TextSize {
+        self.end
+    }
+
+    pub fn len(self) -> TextSize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns `true` if `offset` is inside the range, excluding its end.
+    pub fn contains(self, offset: TextSize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Returns `true` if `offset` is inside the range, including its end.
+    pub fn contains_inclusive(self, offset: TextSize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+
+    /// Returns `true` if `other` is completely covered by `self`.
+    pub fn contains_range(self, other: TextRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Returns the overlap of two ranges, if any.
+    pub fn intersect(self, other: TextRange) -> Option<TextRange> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start <= end).then(|| TextRange::new(start, end))
+    }
+
+    /// Returns the smallest range covering both `self` and `other`.
+    pub fn cover(self, other: TextRange) -> TextRange {
+        TextRange::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Returns the smallest range covering both `self` and `offset`.
+    pub fn cover_offset(self, offset: TextSize) -> TextRange {
+        self.cover(TextRange::empty(offset))
+    }
+}
+
+impl fmt::Debug for TextRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl From<TextRange> for ops::Range<usize> {
+    fn from(range: TextRange) -> ops::Range<usize> {
+        range.start as usize..range.end as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_range() {
+        let range = TextRange::new(2, 5);
+        assert_eq!(range.len(), 3);
+        assert!(range.contains(2));
+        assert!(!range.contains(5));
+        assert!(range.contains_inclusive(5));
+        assert!(TextRange::empty(3).is_empty());
+    }
+
+    #[test]
+    fn test_text_range_cover_and_intersect() {
+        let a = TextRange::new(2, 5);
+        let b = TextRange::new(4, 9);
+        assert_eq!(a.cover(b), TextRange::new(2, 9));
+        assert_eq!(a.intersect(b), Some(TextRange::new(4, 5)));
+        assert_eq!(a.intersect(TextRange::new(7, 9)), None);
+        assert!(TextRange::new(0, 10).contains_range(a));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_text_range_invalid() {
+        TextRange::new(5, 2);
+    }
+}
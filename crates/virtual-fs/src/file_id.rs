@@ -16,6 +16,10 @@ impl FileId {
     }
 }
 
+/// File ids are already unique integers, so they can be used as hash keys
+/// as they are.
+impl nohash_hasher::IsEnabled for FileId {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,20 +1,31 @@
 mod anchored_path;
+pub use anchored_path::{AnchoredPath, AnchoredPathBuf};
 mod file_id;
 pub use file_id::FileId;
 mod path_interner;
 use path_interner::PathInterner;
 
 mod virtualfs_path;
-use std::{fmt, mem};
+use std::{fmt, fs, mem};
+
+use nohash_hasher::IntSet;
 pub use virtualfs_path::{VirtualFsPath, VirtualFsPathRepr, VirtualPath};
 
-pub use paths::AbsPathBuf;
+pub use paths::{AbsPath, AbsPathBuf};
 
+/// The files known to the server, as ids, with the changes to them since
+/// last taken.
+///
+/// Files come in two layers: their contents on disk, and the overlays of the
+/// documents open in the editor, which win over the disk until removed. The
+/// contents themselves aren't kept, only sent with the changes.
 #[derive(Default)]
 pub struct VirtualFS {
     data: Vec<FileState>,
     changes: Vec<ChangedFile>,
     interner: PathInterner,
+    /// The files whose contents the editor holds.
+    overlays: IntSet<FileId>,
 }
 
 #[derive(Copy, PartialEq, PartialOrd, Clone)]
@@ -92,6 +103,45 @@ impl VirtualFS {
         true
     }
 
+    /// Sets the contents of a file as on disk, or `None` once deleted. This
+    /// is ignored while the file has an overlay.
+    pub fn set_disk_contents(&mut self, path: VirtualFsPath, contents: Option<Vec<u8>>) -> bool {
+        if self.has_overlay(&path) {
+            return false;
+        }
+        self.set_file_contents(path, contents)
+    }
+
+    /// Reads a file from disk, e.g. once notified that it changed.
+    pub fn load_from_disk(&mut self, path: VirtualFsPath) -> bool {
+        let contents = path.as_path().and_then(|it| fs::read(it).ok());
+        self.set_disk_contents(path, contents)
+    }
+
+    /// Sets the contents of a document open in the editor, unsaved edits
+    /// included, which shadow the disk until the overlay is removed.
+    pub fn set_overlay(&mut self, path: VirtualFsPath, contents: Vec<u8>) -> bool {
+        let file_id = self.alloc_file_id(path.clone());
+        self.overlays.insert(file_id);
+        self.set_file_contents(path, Some(contents))
+    }
+
+    /// Removes the overlay of a closed document, going back to its contents
+    /// on disk.
+    pub fn remove_overlay(&mut self, path: VirtualFsPath) -> bool {
+        let removed = self
+            .interner
+            .get(&path)
+            .is_some_and(|it| self.overlays.remove(&it));
+        removed && self.load_from_disk(path)
+    }
+
+    pub fn has_overlay(&self, path: &VirtualFsPath) -> bool {
+        self.interner
+            .get(path)
+            .is_some_and(|it| self.overlays.contains(&it))
+    }
+
     pub fn take_changes(&mut self) -> Vec<ChangedFile> {
         mem::take(&mut self.changes)
     }
@@ -117,3 +167,60 @@ impl fmt::Debug for VirtualFS {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(vfs: &mut VirtualFS) -> Vec<Option<Vec<u8>>> {
+        vfs.take_changes()
+            .into_iter()
+            .map(|it| match it.change {
+                Change::Create(it) | Change::Modify(it) => Some(it),
+                Change::Delete => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_overlays() {
+        let dir = std::env::temp_dir().join("virtual-fs-overlays");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("index.cfm");
+        fs::write(&file, "disk").unwrap();
+        let path = VirtualFsPath::from(AbsPathBuf::try_from(file.clone()).unwrap());
+        let mut vfs = VirtualFS::default();
+
+        assert!(vfs.load_from_disk(path.clone()));
+        assert!(vfs.set_overlay(path.clone(), b"edited".to_vec()));
+        assert!(vfs.has_overlay(&path));
+        assert_eq!(
+            contents(&mut vfs),
+            [Some(b"disk".to_vec()), Some(b"edited".to_vec())]
+        );
+
+        // The disk changes, e.g. on a `git pull`, under the open document.
+        fs::write(&file, "pulled").unwrap();
+        assert!(!vfs.load_from_disk(path.clone()));
+        assert!(contents(&mut vfs).is_empty());
+
+        assert!(vfs.remove_overlay(path.clone()));
+        assert!(!vfs.has_overlay(&path));
+        assert_eq!(contents(&mut vfs), [Some(b"pulled".to_vec())]);
+
+        fs::remove_file(&file).unwrap();
+        assert!(vfs.load_from_disk(path.clone()));
+        assert_eq!(contents(&mut vfs), [None]);
+        assert_eq!(vfs.file_id(&path), None);
+    }
+
+    #[test]
+    fn test_unsaved_overlay() {
+        let path = VirtualFsPath::new_virtual_path("/untitled-1".to_string());
+        let mut vfs = VirtualFS::default();
+        vfs.set_overlay(path.clone(), b"<cfset x = 1>".to_vec());
+        assert!(vfs.file_id(&path).is_some());
+        assert!(vfs.remove_overlay(path.clone()));
+        assert_eq!(vfs.file_id(&path), None);
+    }
+}
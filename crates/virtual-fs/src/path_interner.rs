@@ -1,9 +1,9 @@
 //! Maps paths to compact integer ids. We don't care about clearings paths which
 //! no longer exist -- the assumption is total size of paths we ever look at is
 //! not too big.
-use std::hash::BuildHasherDefault;
-use rustc_hash::FxHasher;
 use indexmap::IndexSet;
+use rustc_hash::FxHasher;
+use std::hash::BuildHasherDefault;
 
 use crate::{FileId, VirtualFsPath};
 
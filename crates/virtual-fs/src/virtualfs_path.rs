@@ -80,6 +80,7 @@ impl VirtualFsPath {
         }
     }
 
+    #[allow(dead_code)]
     pub(crate) fn encode(&self, buf: &mut Vec<u8>) {
         let tag = match &self.0 {
             VirtualFsPathRepr::PathBuf(_) => 0,
@@ -342,7 +343,7 @@ impl VirtualPath {
     /// # Returns
     /// - `None` if `self` ends with `"//"`.
     /// - `Some((name, None))` if `self`'s base contains no `.`, or only one `.` at
-    /// the start.
+    ///   the start.
     /// - `Some((name, Some(extension))` else.
     ///
     /// # Note